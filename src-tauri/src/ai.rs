@@ -3,17 +3,15 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use tauri::AppHandle;
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct EmbeddingRequest {
-    pub text: String,
-    pub model: String,
-}
-
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ChatRequest {
     pub messages: Vec<ChatMessage>,
+    // Empty or "default" uses the configured model
+    #[serde(default)]
     pub model: String,
-    pub provider: Provider,
+    // None uses the configured provider
+    #[serde(default)]
+    pub provider: Option<Provider>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -22,10 +20,119 @@ pub struct ChatMessage {
     pub content: String,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
 pub enum Provider {
     Ollama,
     OpenAI,
+    // In-process embedding model; embeddings only
+    Local,
+}
+
+impl Provider {
+    // Matches the `ai_provider` setting values
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Provider::Ollama => "ollama",
+            Provider::OpenAI => "openai",
+            Provider::Local => "local",
+        }
+    }
+
+    fn from_setting(value: &str) -> Option<Self> {
+        match value {
+            "ollama" => Some(Provider::Ollama),
+            "openai" => Some(Provider::OpenAI),
+            "local" => Some(Provider::Local),
+            _ => None,
+        }
+    }
+
+    fn default_chat_model(&self) -> &'static str {
+        match self {
+            Provider::Ollama | Provider::Local => "llama3.1:8b",
+            Provider::OpenAI => "gpt-4o-mini",
+        }
+    }
+}
+
+const DEFAULT_OLLAMA_URL: &str = "http://localhost:11434";
+
+// Provider configuration from the settings table, with the OpenAI key from the keychain.
+// OLLAMA_URL / OPENAI_API_KEY are still honoured when the corresponding setting is empty, so
+// existing env-based setups keep working.
+#[derive(Debug, Clone)]
+pub struct AiSettings {
+    pub provider: Provider,
+    // `embedding_provider` setting; otherwise the local model when this build has one,
+    // otherwise the chat provider
+    pub embedding_provider: Provider,
+    pub ollama_url: String,
+    pub openai_api_key: Option<String>,
+    pub default_model: Option<String>,
+    // `context_window` setting: the chat model's window in tokens, also sent to Ollama as num_ctx
+    pub context_window: Option<usize>,
+}
+
+impl AiSettings {
+    pub fn load(conn: &rusqlite::Connection) -> Self {
+        let setting = |key: &str| crate::database::read_setting(conn, key).filter(|v| !v.trim().is_empty());
+        let env = |key: &str| std::env::var(key).ok().filter(|v| !v.trim().is_empty());
+        // Local only serves embeddings, so it can't be the chat provider
+        let provider = match setting("ai_provider").as_deref().and_then(Provider::from_setting) {
+            Some(Provider::OpenAI) => Provider::OpenAI,
+            _ => Provider::Ollama,
+        };
+        let embedding_provider = setting("embedding_provider")
+            .as_deref()
+            .and_then(Provider::from_setting)
+            .unwrap_or(if crate::local_embeddings::available() { Provider::Local } else { provider.clone() });
+        AiSettings {
+            provider,
+            embedding_provider,
+            ollama_url: setting("ollama_url")
+                .or_else(|| env("OLLAMA_URL"))
+                .unwrap_or_else(|| DEFAULT_OLLAMA_URL.to_string())
+                .trim_end_matches('/')
+                .to_string(),
+            // The settings row only exists until it has been moved to the keychain
+            openai_api_key: setting("openai_api_key")
+                .or_else(|| crate::secrets::get("openai_api_key").ok().flatten())
+                .or_else(|| env("OPENAI_API_KEY")),
+            default_model: setting("default_model"),
+            context_window: setting("context_window").and_then(|v| v.trim().parse().ok()).filter(|&n: &usize| n > 0),
+        }
+    }
+
+    // Generation options for Ollama requests, raising num_ctx when a context window is configured
+    pub fn ollama_options(&self, temperature: f32, num_predict: Option<u32>) -> serde_json::Value {
+        let mut options = serde_json::json!({ "temperature": temperature });
+        if let Some(num_predict) = num_predict {
+            options["num_predict"] = num_predict.into();
+        }
+        if let Some(window) = self.context_window {
+            options["num_ctx"] = window.into();
+        }
+        options
+    }
+
+    pub fn from_app(app_handle: &AppHandle) -> Result<Self> {
+        let conn = crate::database::open_conn(app_handle)?;
+        Ok(Self::load(&conn))
+    }
+
+    // The requested model, else `default_model` when it applies to this provider, else the
+    // provider's own default
+    pub fn chat_model(&self, provider: &Provider, requested: &str) -> String {
+        let requested = requested.trim();
+        if !requested.is_empty() && requested != "default" {
+            return requested.to_string();
+        }
+        match &self.default_model {
+            Some(model) if *provider == self.provider => model.clone(),
+            _ => provider.default_chat_model().to_string(),
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -68,15 +175,23 @@ pub struct VocabularyTag {
 
 // AI-powered tag extraction
 pub async fn extract_tags_ai(
-    app_handle: &AppHandle,
+    settings: &AiSettings,
     request: TagExtractionRequest,
-    provider: Provider,
+    provider: Option<Provider>,
 ) -> Result<TagExtractionResult> {
     let start_time = std::time::Instant::now();
     
-    match provider {
-        Provider::Ollama => extract_tags_ollama(app_handle, request).await,
-        Provider::OpenAI => extract_tags_openai(app_handle, request).await,
+    match provider.unwrap_or_else(|| settings.provider.clone()) {
+        Provider::Ollama => extract_tags_ollama(settings, request).await,
+        Provider::OpenAI => extract_tags_openai(settings, request).await,
+        Provider::Local => Ok(TagExtractionResult {
+            suggestions: extract_tags_rules(&request.text, &request.vocabulary)
+                .into_iter()
+                .take(request.max_tags as usize)
+                .collect(),
+            processing_time_ms: 0,
+            model_used: "rules".to_string(),
+        }),
     }
     .map(|mut result| {
         result.processing_time_ms = start_time.elapsed().as_millis() as u64;
@@ -84,15 +199,49 @@ pub async fn extract_tags_ai(
     })
 }
 
+// extract_tags_ai through the AI cache: the same text, vocabulary and limits given to the same
+// model again are answered from the cache. Mock and rule-based results aren't cached.
+pub async fn extract_tags_cached(
+    app_handle: &AppHandle,
+    settings: &AiSettings,
+    request: TagExtractionRequest,
+    provider: Option<Provider>,
+) -> Result<TagExtractionResult> {
+    use crate::ai_cache::{TAGS, TAGS_VERSION};
+    let provider = provider.unwrap_or_else(|| settings.provider.clone());
+    if provider == Provider::Local {
+        return extract_tags_ai(settings, request, Some(provider)).await;
+    }
+    let model = format!("{}:{}", provider.as_str(), settings.chat_model(&provider, ""));
+    let hash = crate::ai_cache::input_hash(&[
+        &request.text,
+        &request.vocabulary.join("\n"),
+        &request.max_tags.to_string(),
+        &request.confidence_threshold.to_string(),
+    ]);
+    let cached = {
+        let conn = crate::database::open_conn(app_handle)?;
+        crate::ai_cache::get(&conn, TAGS, &hash, &model, TAGS_VERSION)?
+    };
+    if let Some(result) = cached.and_then(|bytes| serde_json::from_slice::<TagExtractionResult>(&bytes).ok()) {
+        return Ok(TagExtractionResult { processing_time_ms: 0, ..result });
+    }
+
+    let result = crate::ai_audit::with_context("tag suggestions", vec![], extract_tags_ai(settings, request, Some(provider))).await?;
+    if !result.model_used.ends_with("(mock)") {
+        let conn = crate::database::open_conn(app_handle)?;
+        crate::ai_cache::put(&conn, TAGS, &hash, &model, TAGS_VERSION, &serde_json::to_vec(&result)?)?;
+    }
+    Ok(result)
+}
+
 // Ollama-based tag extraction
 async fn extract_tags_ollama(
-    _app_handle: &AppHandle,
+    settings: &AiSettings,
     request: TagExtractionRequest,
 ) -> Result<TagExtractionResult> {
     let client = reqwest::Client::new();
-    
-    let ollama_url = std::env::var("OLLAMA_URL")
-        .unwrap_or_else(|_| "http://localhost:11434".to_string());
+    let model = settings.chat_model(&Provider::Ollama, "");
     
     // Create a detailed prompt for tag extraction
     let prompt = format!(
@@ -110,7 +259,7 @@ async fn extract_tags_ollama(
     );
     
     let request_body = serde_json::json!({
-        "model": "llama3.1:8b",
+        "model": model,
         "prompt": prompt,
         "stream": false,
         "format": "json",
@@ -120,52 +269,44 @@ async fn extract_tags_ollama(
         }
     });
     
-    let response = client
-        .post(format!("{}/api/generate", ollama_url))
+    let http_request = client
+        .post(format!("{}/api/generate", settings.ollama_url))
         .header("Content-Type", "application/json")
-        .json(&request_body)
-        .send()
-        .await;
+        .json(&request_body);
+    let response = crate::ai_http::send(&Provider::Ollama, http_request).await;
     
-    let suggestions = match response {
+    let parsed = match response {
         Ok(resp) if resp.status().is_success() => {
             match resp.json::<serde_json::Value>().await {
                 Ok(json) => {
                     let response_text = json["response"].as_str().unwrap_or("{}");
-                    parse_tag_extraction_response(response_text, &request.vocabulary, request.confidence_threshold)
-                        .unwrap_or_else(|_| generate_mock_tag_suggestions(&request.text, &request.vocabulary))
+                    parse_tag_extraction_response(response_text, &request.vocabulary, request.confidence_threshold).ok()
                 },
-                Err(_) => generate_mock_tag_suggestions(&request.text, &request.vocabulary),
+                Err(_) => None,
             }
         },
-        _ => generate_mock_tag_suggestions(&request.text, &request.vocabulary),
+        _ => None,
     };
     
-    Ok(TagExtractionResult {
-        suggestions: suggestions.into_iter().take(request.max_tags as usize).collect(),
-        processing_time_ms: 0, // Will be set by caller
-        model_used: "llama3.1:8b".to_string(),
-    })
+    Ok(tag_result(parsed, &request, model))
 }
 
 // OpenAI-based tag extraction
 async fn extract_tags_openai(
-    _app_handle: &AppHandle,
+    settings: &AiSettings,
     request: TagExtractionRequest,
 ) -> Result<TagExtractionResult> {
     let client = reqwest::Client::new();
+    let model = settings.chat_model(&Provider::OpenAI, "");
     
-    let api_key = std::env::var("OPENAI_API_KEY")
-        .unwrap_or_else(|_| "your-openai-api-key".to_string());
-    
-    if api_key == "your-openai-api-key" {
+    let Some(api_key) = settings.openai_api_key.as_deref() else {
         let suggestions = generate_mock_tag_suggestions(&request.text, &request.vocabulary);
         return Ok(TagExtractionResult {
             suggestions: suggestions.into_iter().take(request.max_tags as usize).collect(),
             processing_time_ms: 0, // Will be set by caller
-            model_used: "gpt-4o-mini (mock)".to_string(),
+            model_used: format!("{} (mock)", model),
         });
-    }
+    };
     
     let system_message = format!(
         "You are a tag extraction assistant. Analyze the provided text and suggest relevant tags from the given vocabulary. \
@@ -190,42 +331,49 @@ async fn extract_tags_openai(
     ];
     
     let request_body = serde_json::json!({
-        "model": "gpt-4o-mini",
+        "model": model,
         "messages": messages,
         "temperature": 0.2,
         "max_tokens": 500,
         "response_format": { "type": "json_object" }
     });
     
-    let response = client
+    let http_request = client
         .post("https://api.openai.com/v1/chat/completions")
         .header("Authorization", format!("Bearer {}", api_key))
         .header("Content-Type", "application/json")
-        .json(&request_body)
-        .send()
-        .await;
+        .json(&request_body);
+    let response = crate::ai_http::send(&Provider::OpenAI, http_request).await;
     
-    let suggestions = match response {
+    let parsed = match response {
         Ok(resp) if resp.status().is_success() => {
             match resp.json::<serde_json::Value>().await {
                 Ok(json) => {
                     let response_text = json["choices"][0]["message"]["content"]
                         .as_str()
                         .unwrap_or("{}");
-                    parse_tag_extraction_response(response_text, &request.vocabulary, request.confidence_threshold)
-                        .unwrap_or_else(|_| generate_mock_tag_suggestions(&request.text, &request.vocabulary))
+                    parse_tag_extraction_response(response_text, &request.vocabulary, request.confidence_threshold).ok()
                 },
-                Err(_) => generate_mock_tag_suggestions(&request.text, &request.vocabulary),
+                Err(_) => None,
             }
         },
-        _ => generate_mock_tag_suggestions(&request.text, &request.vocabulary),
+        _ => None,
     };
     
-    Ok(TagExtractionResult {
+    Ok(tag_result(parsed, &request, model))
+}
+
+// The model's suggestions, or mock ones (marked in model_used) when the call or parse failed
+fn tag_result(parsed: Option<Vec<TagSuggestion>>, request: &TagExtractionRequest, model: String) -> TagExtractionResult {
+    let (suggestions, model_used) = match parsed {
+        Some(suggestions) => (suggestions, model),
+        None => (generate_mock_tag_suggestions(&request.text, &request.vocabulary), format!("{} (mock)", model)),
+    };
+    TagExtractionResult {
         suggestions: suggestions.into_iter().take(request.max_tags as usize).collect(),
         processing_time_ms: 0, // Will be set by caller
-        model_used: "gpt-4o-mini".to_string(),
-    })
+        model_used,
+    }
 }
 
 // Parse JSON response from AI models for tag extraction
@@ -290,6 +438,24 @@ pub fn extract_tags_rules(text: &str, vocabulary: &[String]) -> Vec<TagSuggestio
             continue;
         }
         
+        // Check keyword cues for known tags (e.g. "happy" for emotions)
+        if let Some((_, keywords)) = tag_keyword_patterns().into_iter().find(|(t, _)| *t == tag_lower) {
+            let found: Vec<String> = keywords
+                .iter()
+                .filter(|k| text_lower.contains(*k))
+                .map(|k| k.to_string())
+                .collect();
+            if !found.is_empty() {
+                suggestions.push(TagSuggestion {
+                    tag: tag.clone(),
+                    confidence: (0.5 + 0.1 * found.len() as f32).min(0.9),
+                    reasoning: format!("Found {} related keywords for '{}'", found.len(), tag),
+                    text_spans: found,
+                });
+                continue;
+            }
+        }
+        
         // Check for semantic matches based on tag category
         let confidence = calculate_semantic_match(&text_lower, &tag_lower);
         if confidence > 0.5 {
@@ -308,13 +474,9 @@ pub fn extract_tags_rules(text: &str, vocabulary: &[String]) -> Vec<TagSuggestio
     suggestions
 }
 
-// Generate mock tag suggestions for testing
-fn generate_mock_tag_suggestions(text: &str, vocabulary: &[String]) -> Vec<TagSuggestion> {
-    let text_lower = text.to_lowercase();
-    let mut suggestions = Vec::new();
-    
-    // Define keyword patterns for different tag categories
-    let patterns = vec![
+// Keyword cues for the default vocabulary tags
+fn tag_keyword_patterns() -> Vec<(&'static str, Vec<&'static str>)> {
+    vec![
         ("personal", vec!["i feel", "my", "myself", "personal", "private"]),
         ("work", vec!["work", "job", "office", "meeting", "project", "colleague"]),
         ("travel", vec!["trip", "travel", "vacation", "flight", "hotel", "visit"]),
@@ -325,9 +487,15 @@ fn generate_mock_tag_suggestions(text: &str, vocabulary: &[String]) -> Vec<TagSu
         ("creativity", vec!["creative", "art", "write", "music", "design", "inspiration"]),
         ("learning", vec!["learn", "study", "read", "course", "education", "knowledge"]),
         ("emotions", vec!["happy", "sad", "angry", "excited", "worried", "grateful"]),
-    ];
+    ]
+}
+
+// Generate mock tag suggestions for testing
+fn generate_mock_tag_suggestions(text: &str, vocabulary: &[String]) -> Vec<TagSuggestion> {
+    let text_lower = text.to_lowercase();
+    let mut suggestions = Vec::new();
     
-    for (tag, keywords) in patterns {
+    for (tag, keywords) in tag_keyword_patterns() {
         if vocabulary.contains(&tag.to_string()) {
             let mut matches = 0;
             let mut found_keywords = Vec::new();
@@ -462,18 +630,7 @@ pub fn get_default_vocabulary() -> ControlledVocabulary {
     ControlledVocabulary { tags, aliases }
 }
 
-// Standard embedding generation
-pub async fn generate_embedding(request: EmbeddingRequest) -> Result<Vec<f32>> {
-    // Default to OpenAI for embeddings unless model suggests Ollama
-    if request.model.contains("ollama") || request.model.contains("llama") {
-        generate_embedding_ollama(&request.text, &request.model).await
-    } else {
-        generate_embedding_openai(&request.text, &request.model).await
-    }
-}
-
-// OpenAI embedding generation
-async fn generate_embedding_openai(text: &str, model: &str) -> Result<Vec<f32>> {
+async fn request_embedding_openai(text: &str, model: &str, api_key: &str) -> Result<Vec<f32>> {
     let client = reqwest::Client::new();
     
     // Use text-embedding-3-small as default model
@@ -483,26 +640,17 @@ async fn generate_embedding_openai(text: &str, model: &str) -> Result<Vec<f32>>
         model
     };
     
-    let api_key = std::env::var("OPENAI_API_KEY")
-        .unwrap_or_else(|_| "your-openai-api-key".to_string());
-    
-    if api_key == "your-openai-api-key" {
-        // Return mock embedding if no API key is set
-        return Ok(generate_mock_embedding(text, 1536));
-    }
-    
     let request_body = serde_json::json!({
         "input": text,
         "model": model
     });
     
-    let response = client
+    let request = client
         .post("https://api.openai.com/v1/embeddings")
         .header("Authorization", format!("Bearer {}", api_key))
         .header("Content-Type", "application/json")
-        .json(&request_body)
-        .send()
-        .await?;
+        .json(&request_body);
+    let response = crate::ai_http::send(&Provider::OpenAI, request).await?;
     
     if !response.status().is_success() {
         let error_text = response.text().await.unwrap_or_default();
@@ -521,8 +669,7 @@ async fn generate_embedding_openai(text: &str, model: &str) -> Result<Vec<f32>>
     Ok(embedding)
 }
 
-// Ollama embedding generation
-async fn generate_embedding_ollama(text: &str, model: &str) -> Result<Vec<f32>> {
+async fn request_embedding_ollama(text: &str, model: &str, ollama_url: &str) -> Result<Vec<f32>> {
     let client = reqwest::Client::new();
     
     // Use nomic-embed-text as default embedding model
@@ -532,75 +679,109 @@ async fn generate_embedding_ollama(text: &str, model: &str) -> Result<Vec<f32>>
         model
     };
     
-    let ollama_url = std::env::var("OLLAMA_URL")
-        .unwrap_or_else(|_| "http://localhost:11434".to_string());
-    
     let request_body = serde_json::json!({
         "model": model,
         "prompt": text
     });
     
-    let response = client
-        .post(format!("{}/api/embeddings", ollama_url))
+    let request = client
+        .post(format!("{}/api/embeddings", ollama_url.trim_end_matches('/')))
         .header("Content-Type", "application/json")
-        .json(&request_body)
-        .send()
-        .await;
-    
-    let response = match response {
-        Ok(resp) => resp,
-        Err(_) => {
-            // If Ollama is not available, return mock embedding
-            return Ok(generate_mock_embedding(text, 768));
-        }
-    };
+        .json(&request_body);
+    let response = crate::ai_http::send(&Provider::Ollama, request).await?;
     
     if !response.status().is_success() {
-        // Fallback to mock embedding if Ollama request fails
-        return Ok(generate_mock_embedding(text, 768));
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(anyhow::anyhow!("Ollama API error: {}", error_text));
     }
     
-    let response_json: serde_json::Value = response.json().await
-        .unwrap_or_else(|_| serde_json::json!({"embedding": []}));
+    let response_json: serde_json::Value = response.json().await?;
     
-    let embedding = response_json["embedding"]
+    let embedding: Vec<f32> = response_json["embedding"]
         .as_array()
-        .map(|arr| arr.iter()
-            .map(|v| v.as_f64().unwrap_or(0.0) as f32)
-            .collect())
-        .unwrap_or_else(|| generate_mock_embedding(text, 768));
+        .ok_or_else(|| anyhow::anyhow!("Invalid Ollama response format"))?
+        .iter()
+        .map(|v| v.as_f64().unwrap_or(0.0) as f32)
+        .collect();
+    
+    if embedding.is_empty() {
+        return Err(anyhow::anyhow!("Ollama returned an empty embedding for model {}", model));
+    }
     
     Ok(embedding)
 }
 
-// Generate deterministic mock embedding based on text content
-fn generate_mock_embedding(text: &str, dimension: usize) -> Vec<f32> {
-    use std::collections::hash_map::DefaultHasher;
-    use std::hash::{Hash, Hasher};
-    
-    let mut hasher = DefaultHasher::new();
-    text.hash(&mut hasher);
-    let hash = hasher.finish();
-    
-    let mut embedding = Vec::with_capacity(dimension);
-    let mut seed = hash;
-    
-    for _ in 0..dimension {
-        // Simple linear congruential generator for consistent mock embeddings
-        seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
-        let value = (seed as f32) / (u64::MAX as f32) * 2.0 - 1.0; // Range [-1, 1]
-        embedding.push(value * 0.1); // Scale down to reasonable range
-    }
-    
-    // Normalize vector to unit length
-    let magnitude: f32 = embedding.iter().map(|x| x * x).sum::<f32>().sqrt();
-    if magnitude > 0.0 {
-        for value in &mut embedding {
-            *value /= magnitude;
+// Embeddings that get persisted must come from the real provider: there is no mock fallback,
+// so a failure leaves the entry pending instead of storing a vector that means nothing.
+pub async fn generate_embedding_strict(settings: &AiSettings, text: &str, model: &str) -> Result<Vec<f32>> {
+    match settings.embedding_provider {
+        Provider::Local => crate::local_embeddings::embed(text, model).await,
+        Provider::Ollama => request_embedding_ollama(text, model, &settings.ollama_url).await,
+        Provider::OpenAI => {
+            let api_key = settings
+                .openai_api_key
+                .as_deref()
+                .ok_or_else(|| anyhow::anyhow!("No OpenAI API key is configured"))?;
+            request_embedding_openai(text, model, api_key).await
         }
     }
-    
-    embedding
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OllamaModel {
+    pub name: String,
+    // Bytes on disk
+    pub size: u64,
+    pub modified_at: Option<String>,
+    pub family: Option<String>,
+    pub parameter_size: Option<String>,
+    pub quantization_level: Option<String>,
+    // Embedding-only models can't chat, so the settings screen offers them for embeddings only
+    pub embedding: bool,
+}
+
+fn parse_ollama_tags(response: &serde_json::Value) -> Result<Vec<OllamaModel>> {
+    let models = response["models"]
+        .as_array()
+        .ok_or_else(|| anyhow::anyhow!("Invalid Ollama response format"))?;
+    let text = |v: &serde_json::Value| v.as_str().filter(|s| !s.is_empty()).map(str::to_string);
+    let mut models: Vec<OllamaModel> = models
+        .iter()
+        .filter_map(|m| {
+            let name = text(&m["name"]).or_else(|| text(&m["model"]))?;
+            let details = &m["details"];
+            let families: Vec<&str> = details["families"].as_array().into_iter().flatten().filter_map(|f| f.as_str()).collect();
+            let family = text(&details["family"]);
+            let embedding = name.contains("embed")
+                || family.iter().map(String::as_str).chain(families).any(|f| f.ends_with("bert"));
+            Some(OllamaModel {
+                size: m["size"].as_u64().unwrap_or(0),
+                modified_at: text(&m["modified_at"]),
+                family,
+                parameter_size: text(&details["parameter_size"]),
+                quantization_level: text(&details["quantization_level"]),
+                embedding,
+                name,
+            })
+        })
+        .collect();
+    models.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(models)
+}
+
+// Models installed on the configured Ollama server
+pub async fn list_ollama_models(settings: &AiSettings) -> Result<Vec<OllamaModel>> {
+    let client = reqwest::Client::builder().timeout(std::time::Duration::from_secs(5)).build()?;
+    let response = client
+        .get(format!("{}/api/tags", settings.ollama_url.trim_end_matches('/')))
+        .send()
+        .await
+        .map_err(|e| anyhow::anyhow!("Ollama is not available ({}). Make sure Ollama is running at {} or change the Ollama URL in Settings.", e, settings.ollama_url))?;
+    if !response.status().is_success() {
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(anyhow::anyhow!("Ollama API error: {}", error_text));
+    }
+    parse_ollama_tags(&response.json().await?)
 }
 
 // RAG-specific structures
@@ -608,13 +789,20 @@ fn generate_mock_embedding(text: &str, dimension: usize) -> Vec<f32> {
 pub struct RagRequest {
     pub question: String,
     pub conversation_id: Option<String>,
+    #[serde(default = "default_max_context_entries")]
     pub max_context_entries: u32,
     pub context_date_range: Option<(chrono::DateTime<chrono::Utc>, chrono::DateTime<chrono::Utc>)>,
     pub context_tags: Option<Vec<String>>,
     pub provider: Provider,
+    // Empty or "default" uses the configured model
+    #[serde(default)]
     pub model: String,
 }
 
+fn default_max_context_entries() -> u32 {
+    5
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct RagResponse {
     pub answer: String,
@@ -625,6 +813,9 @@ pub struct RagResponse {
     pub model_used: String,
     pub conversation_id: String,
     pub message_id: String,
+    // Filters the question was turned into before searching, when planning found any
+    #[serde(default)]
+    pub plan: Option<crate::query_plan::RetrievalPlan>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -648,15 +839,6 @@ pub struct ContextEntry {
     pub snippet: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct ConversationContext {
-    pub conversation_id: String,
-    pub messages: Vec<ConversationMessage>,
-    pub context_entries: Vec<ContextEntry>,
-    pub created_at: chrono::DateTime<chrono::Utc>,
-    pub updated_at: chrono::DateTime<chrono::Utc>,
-}
-
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ConversationMessage {
     pub message_id: String,
@@ -666,36 +848,43 @@ pub struct ConversationMessage {
     pub timestamp: chrono::DateTime<chrono::Utc>,
 }
 
+const RAG_SYSTEM_PROMPT: &str = "You are a helpful assistant that answers questions based on journal entries. Always cite specific entries when making claims, using the format [Entry N]. Be accurate and only make claims supported by the provided context.";
+
 // RAG pipeline implementation
 pub async fn process_rag_query(
     app_handle: &tauri::AppHandle,
     request: RagRequest,
 ) -> Result<RagResponse> {
     let start_time = std::time::Instant::now();
+    let settings = AiSettings::from_app(app_handle)?;
     
     // Step 1: Retrieve relevant context from journal entries
-    let context_entries = retrieve_relevant_context(
-        app_handle,
-        &request.question,
-        request.max_context_entries,
-        request.context_date_range,
-        request.context_tags.as_ref(),
-    ).await?;
+    let (context_entries, plan) = retrieve_planned_context(app_handle, &settings, &request).await?;
     
-    // Step 2: Generate answer using RAG
+    // Step 2: Generate answer using RAG, with earlier turns when continuing a conversation
+    let history = match &request.conversation_id {
+        Some(id) => crate::conversations::load_recent_turns(app_handle, id).await?,
+        None => vec![],
+    };
+    let model = settings.chat_model(&request.provider, &request.model);
+    let context_entries = fit_context(&settings, &request.provider, &model, &request.question, &history, context_entries);
+    let context_ids: Vec<String> = context_entries.iter().map(|e| e.entry_id.clone()).collect();
     let (answer, citations, confidence) = match request.provider {
-        Provider::Ollama => generate_rag_answer_ollama(
-            app_handle,
+        Provider::Local => generate_fallback_rag_response(&request.question, &context_entries),
+        Provider::Ollama => crate::ai_audit::with_context("chat answer", context_ids, generate_rag_answer_ollama(
+            &settings,
             &request.question,
             &context_entries,
+            &history,
             &request.model,
-        ).await?,
-        Provider::OpenAI => generate_rag_answer_openai(
-            app_handle,
+        )).await?,
+        Provider::OpenAI => crate::ai_audit::with_context("chat answer", context_ids, generate_rag_answer_openai(
+            &settings,
             &request.question,
             &context_entries,
+            &history,
             &request.model,
-        ).await?,
+        )).await?,
     };
     
     // Step 3: Create or update conversation
@@ -710,35 +899,216 @@ pub async fn process_rag_query(
         context_used: context_entries,
         confidence,
         processing_time_ms: processing_time,
-        model_used: request.model,
+        model_used: model,
         conversation_id,
         message_id,
+        plan,
     })
 }
 
+// Same pipeline as process_rag_query, but the answer is passed to `on_token` as it is
+// generated. Citations are verified once the answer is complete, so the returned answer
+// (with renumbered markers) should replace the streamed text.
+pub async fn process_rag_query_stream(
+    app_handle: &tauri::AppHandle,
+    request: RagRequest,
+    cancel: &crate::ai_stream::CancelToken,
+    mut on_token: impl FnMut(&str),
+) -> Result<RagResponse> {
+    let start_time = std::time::Instant::now();
+    let settings = AiSettings::from_app(app_handle)?;
+
+    let (context_entries, plan) = retrieve_planned_context(app_handle, &settings, &request).await?;
+
+    let history = match &request.conversation_id {
+        Some(id) => crate::conversations::load_recent_turns(app_handle, id).await?,
+        None => vec![],
+    };
+    let model = settings.chat_model(&request.provider, &request.model);
+    let context_entries = fit_context(&settings, &request.provider, &model, &request.question, &history, context_entries);
+    let no_openai_key = request.provider == Provider::OpenAI && settings.openai_api_key.is_none();
+    let (answer, citations, confidence) = if request.provider == Provider::Local || no_openai_key {
+        let fallback = generate_fallback_rag_response(&request.question, &context_entries);
+        on_token(&fallback.0);
+        fallback
+    } else {
+        let messages = vec![
+            ChatMessage { role: "system".to_string(), content: RAG_SYSTEM_PROMPT.to_string() },
+            ChatMessage {
+                role: "user".to_string(),
+                content: create_rag_prompt(&request.question, &build_context_string(&context_entries), &history),
+            },
+        ];
+        let context_ids = context_entries.iter().map(|e| e.entry_id.clone()).collect();
+        let answer = crate::ai_audit::with_context(
+            "chat answer",
+            context_ids,
+            crate::ai_stream::stream_chat(&settings, &request.provider, &model, &messages, 0.3, cancel, on_token),
+        )
+        .await?;
+        let (answer, citations) = verify_citations(&answer, &context_entries);
+        let confidence = calculate_answer_confidence(&answer, &context_entries);
+        (answer, citations, confidence)
+    };
+
+    Ok(RagResponse {
+        answer,
+        citations,
+        context_used: context_entries,
+        confidence,
+        processing_time_ms: start_time.elapsed().as_millis() as u64,
+        model_used: model,
+        conversation_id: request.conversation_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string()),
+        message_id: uuid::Uuid::new_v4().to_string(),
+        plan,
+    })
+}
+
+// The retrieved entries, packed into what the model's window leaves after the prompt,
+// conversation and answer
+fn fit_context(
+    settings: &AiSettings,
+    provider: &Provider,
+    model: &str,
+    question: &str,
+    history: &[ChatMessage],
+    context_entries: Vec<ContextEntry>,
+) -> Vec<ContextEntry> {
+    let window = crate::tokens::context_window(settings, provider, model);
+    let fixed = crate::tokens::estimate_tokens(RAG_SYSTEM_PROMPT)
+        + crate::tokens::estimate_tokens(&create_rag_prompt(question, "", history))
+        + crate::tokens::ANSWER_TOKENS;
+    crate::tokens::pack_context(context_entries, window.saturating_sub(fixed))
+}
+
+// Common words that would otherwise make every entry match a question
+pub(crate) const QUESTION_STOP_WORDS: &[&str] = &[
+    "a", "about", "after", "all", "am", "an", "and", "any", "are", "as", "at", "be", "been", "before",
+    "but", "by", "can", "could", "did", "do", "does", "doing", "during", "for", "from", "had", "has",
+    "have", "how", "i", "if", "in", "into", "is", "it", "its", "me", "my", "myself", "of", "on", "or",
+    "our", "so", "than", "that", "the", "their", "them", "then", "there", "these", "they", "this",
+    "those", "to", "was", "we", "were", "what", "when", "where", "which", "while", "who", "whom",
+    "why", "will", "with", "would", "you", "your", "ever", "last", "tell", "much", "many",
+];
+
+// FTS query for a natural-language question: its content words, any of which may match
+fn question_keywords(question: &str) -> String {
+    let mut words: Vec<String> = Vec::new();
+    for word in question.split(|c: char| !c.is_alphanumeric() && c != '\'') {
+        let word = word.trim_matches('\'').to_lowercase();
+        if word.chars().count() < 2 || QUESTION_STOP_WORDS.contains(&word.as_str()) || words.contains(&word) {
+            continue;
+        }
+        words.push(word);
+    }
+    if words.is_empty() {
+        return question.to_string();
+    }
+    words.join(" OR ")
+}
+
+// Context for a RAG request. Unless the caller chose filters, the question is first planned
+// into filters (query_plan.rs); when those match nothing, the question is searched unfiltered.
+async fn retrieve_planned_context(
+    app_handle: &tauri::AppHandle,
+    settings: &AiSettings,
+    request: &RagRequest,
+) -> Result<(Vec<ContextEntry>, Option<crate::query_plan::RetrievalPlan>)> {
+    let caller_filters = request.context_date_range.is_some() || request.context_tags.as_ref().is_some_and(|t| !t.is_empty());
+    if !caller_filters {
+        let plan = crate::query_plan::plan(app_handle, settings, &request.provider, &request.model, &request.question).await;
+        if let Some(plan) = plan {
+            tracing::debug!("plan method={} dates={:?}..{:?} tags={:?} people={:?} anchor={:?}",
+                plan.method, plan.date_from, plan.date_to, plan.tags, plan.people, plan.anchor);
+            let filters = crate::search::SearchFilters {
+                date_range: plan.date_range(),
+                tags: (!plan.tags.is_empty()).then(|| plan.tags.clone()),
+                source_types: None,
+                min_score: None,
+                entities: (!plan.people.is_empty()).then(|| plan.people.clone()),
+                languages: None,
+            };
+            let keywords = question_keywords(if plan.keywords.is_empty() { &request.question } else { &plan.keywords });
+            let mut entries = retrieve_with_filters(app_handle, &keywords, &request.question, &filters, request.max_context_entries).await?;
+            // A period with few matching words ("how was my first month there?") is still read
+            // from its own entries
+            if plan.date_range().is_some() && entries.len() < request.max_context_entries as usize {
+                let page = crate::database::filtered_entries_page(app_handle, &filters.to_entry_filter(), request.max_context_entries, 0).await?;
+                for (entry, fragments) in page.entries {
+                    if entries.len() >= request.max_context_entries as usize {
+                        break;
+                    }
+                    if entries.iter().any(|e| e.entry_id == entry.id) {
+                        continue;
+                    }
+                    entries.push(ContextEntry {
+                        entry_id: entry.id,
+                        title: entry.title,
+                        snippet: crate::highlight::plain_text(&fragments),
+                        body: entry.body,
+                        entry_date: entry.entry_date,
+                        tags: vec![],
+                        relevance_score: 0.0,
+                    });
+                }
+            }
+            crate::sensitive::prepare_context(&*crate::database::open_conn(app_handle)?, &mut entries)?;
+            if !entries.is_empty() {
+                return Ok((entries, Some(plan)));
+            }
+            tracing::debug!("planned filters matched nothing; searching without them");
+        }
+    }
+    let entries = retrieve_relevant_context(
+        app_handle,
+        &request.question,
+        request.max_context_entries,
+        request.context_date_range,
+        request.context_tags.as_ref(),
+    ).await?;
+    Ok((entries, None))
+}
+
 // Retrieve relevant context entries using hybrid search
-async fn retrieve_relevant_context(
+pub(crate) async fn retrieve_relevant_context(
     app_handle: &tauri::AppHandle,
     question: &str,
     max_entries: u32,
     date_range: Option<(chrono::DateTime<chrono::Utc>, chrono::DateTime<chrono::Utc>)>,
     tags: Option<&Vec<String>>,
 ) -> Result<Vec<ContextEntry>> {
-    use crate::search::{SearchFilters, hybrid_search};
-    
-    // Create search filters
-    let filters = SearchFilters {
+    // Create search filters. No score threshold: hybrid scores are rank-based (RRF), so
+    // they say nothing about absolute relevance
+    let filters = crate::search::SearchFilters {
         date_range,
         tags: tags.cloned(),
         source_types: None, // Include all source types
-        min_score: Some(0.3), // Minimum relevance threshold
+        min_score: None,
+        entities: None,
+        languages: None,
     };
-    
-    // Use hybrid search to find relevant entries
-    let search_results = hybrid_search(app_handle, question, &filters, max_entries).await?;
+    let mut entries = retrieve_with_filters(app_handle, &question_keywords(question), question, &filters, max_entries).await?;
+    crate::sensitive::prepare_context(&*crate::database::open_conn(app_handle)?, &mut entries)?;
+    Ok(entries)
+}
+
+async fn retrieve_with_filters(
+    app_handle: &tauri::AppHandle,
+    keywords: &str,
+    question: &str,
+    filters: &crate::search::SearchFilters,
+    max_entries: u32,
+) -> Result<Vec<ContextEntry>> {
+    // Use hybrid search to find relevant entries; with reranking on, a wider pool to pick from
+    let (rerank_mode, rerank_candidates) = crate::rerank::load_config(app_handle);
+    let fetch = match rerank_mode {
+        crate::rerank::RerankMode::Off => max_entries,
+        _ => max_entries.max(rerank_candidates),
+    };
+    let search_results = crate::search::hybrid_search_with(app_handle, keywords, question, filters, fetch).await?;
     
     // Convert search results to context entries
-    let context_entries: Vec<ContextEntry> = search_results
+    let mut context_entries: Vec<ContextEntry> = search_results
         .into_iter()
         .map(|result| {
             let snippet = if result.snippet.is_empty() {
@@ -760,51 +1130,44 @@ async fn retrieve_relevant_context(
             }
         })
         .collect();
-    
-    Ok(context_entries)
+
+    // Long entries contribute their best-matching chunks rather than their opening pages
+    crate::chunks::focus_context(app_handle, question, &mut context_entries).await;
+    Ok(crate::rerank::rerank(app_handle, rerank_mode, question, context_entries, max_entries as usize).await)
 }
 
 // Generate RAG answer using Ollama
 async fn generate_rag_answer_ollama(
-    _app_handle: &tauri::AppHandle,
+    settings: &AiSettings,
     question: &str,
     context_entries: &[ContextEntry],
+    history: &[ChatMessage],
     model: &str,
 ) -> Result<(String, Vec<Citation>, f32)> {
     // Build context string from entries
     let context = build_context_string(context_entries);
     
     // Create RAG prompt
-    let prompt = create_rag_prompt(question, &context);
+    let prompt = create_rag_prompt(question, &context, history);
     
     // Make actual Ollama API call
     let client = reqwest::Client::new();
     
-    let model = if model.is_empty() || model == "default" {
-        "llama3.1:8b"
-    } else {
-        model
-    };
-    
-    let ollama_url = std::env::var("OLLAMA_URL")
-        .unwrap_or_else(|_| "http://localhost:11434".to_string());
+    let model = settings.chat_model(&Provider::Ollama, model);
     
     let request_body = serde_json::json!({
         "model": model,
         "prompt": prompt,
         "stream": false,
-        "options": {
-            "temperature": 0.3, // Lower temperature for more focused answers
-            "num_predict": 1000
-        }
+        // Lower temperature for more focused answers
+        "options": settings.ollama_options(0.3, Some(crate::tokens::ANSWER_TOKENS as u32))
     });
     
-    let response = client
-        .post(format!("{}/api/generate", ollama_url))
+    let request = client
+        .post(format!("{}/api/generate", settings.ollama_url))
         .header("Content-Type", "application/json")
-        .json(&request_body)
-        .send()
-        .await;
+        .json(&request_body);
+    let response = crate::ai_http::send(&Provider::Ollama, request).await;
     
     let answer = match response {
         Ok(resp) if resp.status().is_success() => {
@@ -816,8 +1179,8 @@ async fn generate_rag_answer_ollama(
         _ => return Ok(generate_fallback_rag_response(question, context_entries)),
     };
     
-    // Extract citations from context entries that were used
-    let citations = extract_citations_from_answer(&answer, context_entries);
+    // Keep only citations of entries that were actually in the context
+    let (answer, citations) = verify_citations(&answer, context_entries);
     let confidence = calculate_answer_confidence(&answer, context_entries);
     
     Ok((answer, citations, confidence))
@@ -825,37 +1188,31 @@ async fn generate_rag_answer_ollama(
 
 // Generate RAG answer using OpenAI
 async fn generate_rag_answer_openai(
-    _app_handle: &tauri::AppHandle,
+    settings: &AiSettings,
     question: &str,
     context_entries: &[ContextEntry],
+    history: &[ChatMessage],
     model: &str,
 ) -> Result<(String, Vec<Citation>, f32)> {
     // Build context string from entries
     let context = build_context_string(context_entries);
     
     // Create RAG prompt
-    let prompt = create_rag_prompt(question, &context);
+    let prompt = create_rag_prompt(question, &context, history);
     
     // Make actual OpenAI API call
     let client = reqwest::Client::new();
     
-    let model = if model.is_empty() || model == "default" {
-        "gpt-4o-mini"
-    } else {
-        model
-    };
-    
-    let api_key = std::env::var("OPENAI_API_KEY")
-        .unwrap_or_else(|_| "your-openai-api-key".to_string());
+    let model = settings.chat_model(&Provider::OpenAI, model);
     
-    if api_key == "your-openai-api-key" {
+    let Some(api_key) = settings.openai_api_key.as_deref() else {
         return Ok(generate_fallback_rag_response(question, context_entries));
-    }
+    };
     
     let messages = vec![
         serde_json::json!({
             "role": "system",
-            "content": "You are a helpful assistant that answers questions based on journal entries. Always cite specific entries when making claims, using the format [Entry N]. Be accurate and only make claims supported by the provided context."
+            "content": RAG_SYSTEM_PROMPT
         }),
         serde_json::json!({
             "role": "user", 
@@ -870,13 +1227,12 @@ async fn generate_rag_answer_openai(
         "max_tokens": 1500
     });
     
-    let response = client
+    let request = client
         .post("https://api.openai.com/v1/chat/completions")
         .header("Authorization", format!("Bearer {}", api_key))
         .header("Content-Type", "application/json")
-        .json(&request_body)
-        .send()
-        .await;
+        .json(&request_body);
+    let response = crate::ai_http::send(&Provider::OpenAI, request).await;
     
     let answer = match response {
         Ok(resp) if resp.status().is_success() => {
@@ -891,15 +1247,15 @@ async fn generate_rag_answer_openai(
         _ => return Ok(generate_fallback_rag_response(question, context_entries)),
     };
     
-    // Extract citations from context entries that were used
-    let citations = extract_citations_from_answer(&answer, context_entries);
+    // Keep only citations of entries that were actually in the context
+    let (answer, citations) = verify_citations(&answer, context_entries);
     let confidence = calculate_answer_confidence(&answer, context_entries);
     
     Ok((answer, citations, confidence))
 }
 
 // Build context string from entries
-fn build_context_string(context_entries: &[ContextEntry]) -> String {
+pub(crate) fn build_context_string(context_entries: &[ContextEntry]) -> String {
     let mut context = String::new();
     
     for (i, entry) in context_entries.iter().enumerate() {
@@ -916,7 +1272,17 @@ fn build_context_string(context_entries: &[ContextEntry]) -> String {
 }
 
 // Create RAG prompt with context
-fn create_rag_prompt(question: &str, context: &str) -> String {
+fn create_rag_prompt(question: &str, context: &str, history: &[ChatMessage]) -> String {
+    // Earlier turns let follow-ups like "and after that?" resolve against the conversation
+    let mut conversation = String::new();
+    if !history.is_empty() {
+        conversation.push_str("Conversation so far:\n");
+        for turn in history {
+            let speaker = if turn.role == "assistant" { "Assistant" } else { "User" };
+            conversation.push_str(&format!("{}: {}\n", speaker, turn.content));
+        }
+        conversation.push('\n');
+    }
     format!(
         r#"You are a helpful assistant that answers questions about personal journal entries. 
 Use only the provided context to answer the question. If the context doesn't contain enough information to answer the question, say so clearly.
@@ -926,10 +1292,10 @@ When referencing information from the context, include citation numbers in squar
 Context:
 {}
 
-Question: {}
+{}Question: {}
 
 Answer:"#,
-        context, question
+        context, conversation, question
     )
 }
 
@@ -941,73 +1307,75 @@ fn generate_fallback_rag_response(question: &str, context_entries: &[ContextEntr
     (answer, citations, confidence)
 }
 
-// Extract citations from AI answer by looking for [Entry N] patterns
-fn extract_citations_from_answer(answer: &str, context_entries: &[ContextEntry]) -> Vec<Citation> {
-    let mut citations = Vec::new();
-    
-    // Look for [Entry N] patterns in the answer
-    let re = match regex::Regex::new(r"\[Entry (\d+)\]") {
-        Ok(regex) => regex,
-        Err(_) => {
-            // If regex fails, fallback to simple citation extraction
-            return extract_simple_citations(context_entries);
-        }
-    };
-    
-    for caps in re.captures_iter(answer) {
-        if let Some(num_str) = caps.get(1) {
-            if let Ok(entry_num) = num_str.as_str().parse::<usize>() {
-                if entry_num > 0 && entry_num <= context_entries.len() {
-                    let entry = &context_entries[entry_num - 1];
-                    citations.push(Citation {
-                        entry_id: entry.entry_id.clone(),
-                        entry_title: entry.title.clone(),
-                        entry_date: entry.entry_date,
-                        snippet: if entry.snippet.len() > 200 {
-                            format!("{}...", &entry.snippet[..200])
-                        } else {
-                            entry.snippet.clone()
-                        },
-                        relevance_score: entry.relevance_score,
-                        citation_number: entry_num as u32,
-                    });
+// Verifies the citations in a model answer against the context it was given. Markers like
+// `[Entry 3]`, `[3]` or `[Entry 1, 4]` that point at a real context entry are renumbered in
+// order of first use and rewritten as `[1]`, `[2]`...; markers for entries that were never
+// provided are removed, so every number in the answer matches a returned Citation.
+pub(crate) fn verify_citations(answer: &str, context_entries: &[ContextEntry]) -> (String, Vec<Citation>) {
+    static MARKER: std::sync::OnceLock<regex::Regex> = std::sync::OnceLock::new();
+    let marker = MARKER.get_or_init(|| {
+        regex::Regex::new(r"(?i)\s?\[(?:entry\s*)?\d+(?:\s*,\s*(?:entry\s*)?\d+)*\]").expect("valid citation pattern")
+    });
+    static NUMBER: std::sync::OnceLock<regex::Regex> = std::sync::OnceLock::new();
+    let number = NUMBER.get_or_init(|| regex::Regex::new(r"\d+").expect("valid number pattern"));
+
+    // context index -> new citation number, in order of first use
+    let mut order: Vec<usize> = Vec::new();
+    let text = marker.replace_all(answer, |caps: &regex::Captures| {
+        let matched = &caps[0];
+        let mut numbers: Vec<usize> = Vec::new();
+        for n in number.find_iter(matched) {
+            let Ok(n) = n.as_str().parse::<usize>() else { continue };
+            if n == 0 || n > context_entries.len() {
+                continue; // hallucinated: no such entry in the context
+            }
+            let index = n - 1;
+            let position = match order.iter().position(|&i| i == index) {
+                Some(p) => p,
+                None => {
+                    order.push(index);
+                    order.len() - 1
                 }
+            };
+            if !numbers.contains(&(position + 1)) {
+                numbers.push(position + 1);
             }
         }
-    }
-    
-    // If no explicit citations found, include top relevant entries
-    if citations.is_empty() {
-        citations = extract_simple_citations(context_entries);
-    }
-    
-    citations
-}
+        if numbers.is_empty() {
+            return String::new();
+        }
+        let lead = if matched.starts_with(char::is_whitespace) { " " } else { "" };
+        let list = numbers.iter().map(|n| format!("[{}]", n)).collect::<Vec<_>>().join("");
+        format!("{}{}", lead, list)
+    });
 
-// Simple citation extraction when regex is not available
-fn extract_simple_citations(context_entries: &[ContextEntry]) -> Vec<Citation> {
-    context_entries.iter()
+    let citations = order
+        .iter()
         .enumerate()
-        .take(3) // Include top 3 entries as citations
-        .filter(|(_, entry)| entry.relevance_score > 0.3)
-        .map(|(i, entry)| Citation {
-            entry_id: entry.entry_id.clone(),
-            entry_title: entry.title.clone(),
-            entry_date: entry.entry_date,
-            snippet: if entry.snippet.len() > 200 {
-                format!("{}...", &entry.snippet[..200])
-            } else {
-                entry.snippet.clone()
-            },
-            relevance_score: entry.relevance_score,
-            citation_number: (i + 1) as u32,
-        })
-        .collect()
+        .map(|(i, &index)| citation_for(&context_entries[index], (i + 1) as u32))
+        .collect();
+    (text.into_owned(), citations)
+}
+
+fn citation_for(entry: &ContextEntry, citation_number: u32) -> Citation {
+    let snippet = if entry.snippet.chars().count() > 200 {
+        format!("{}...", entry.snippet.chars().take(200).collect::<String>())
+    } else {
+        entry.snippet.clone()
+    };
+    Citation {
+        entry_id: entry.entry_id.clone(),
+        entry_title: entry.title.clone(),
+        entry_date: entry.entry_date,
+        snippet,
+        relevance_score: entry.relevance_score,
+        citation_number,
+    }
 }
 
 fn generate_mock_rag_response(question: &str, context_entries: &[ContextEntry]) -> (String, Vec<Citation>) {
     let question_lower = question.to_lowercase();
-    let mut answer = String::new();
+    let mut answer: String;
     let mut citations = Vec::new();
     
     // Analyze question type and generate appropriate response
@@ -1121,31 +1489,36 @@ fn calculate_answer_confidence(answer: &str, context_entries: &[ContextEntry]) -
 }
 
 // Standard chat completion
-pub async fn chat_completion(request: ChatRequest) -> Result<String> {
-    match request.provider {
-        Provider::OpenAI => chat_completion_openai(request.messages, &request.model).await,
-        Provider::Ollama => chat_completion_ollama(request.messages, &request.model).await,
+pub async fn chat_completion(settings: &AiSettings, request: ChatRequest) -> Result<String> {
+    let provider = request.provider.unwrap_or_else(|| settings.provider.clone());
+    let model = settings.chat_model(&provider, &request.model);
+    match provider {
+        Provider::OpenAI => chat_completion_openai(settings, request.messages, &model).await,
+        Provider::Ollama => chat_completion_ollama(settings, request.messages, &model).await,
+        Provider::Local => Err(anyhow::anyhow!("The local provider only computes embeddings; choose Ollama or OpenAI for chat")),
     }
 }
 
+// Streaming variant of chat_completion; returns the full text once the stream ends or is cancelled
+pub async fn chat_completion_stream(
+    settings: &AiSettings,
+    request: ChatRequest,
+    cancel: &crate::ai_stream::CancelToken,
+    on_token: impl FnMut(&str),
+) -> Result<String> {
+    let provider = request.provider.unwrap_or_else(|| settings.provider.clone());
+    let model = settings.chat_model(&provider, &request.model);
+    crate::ai_stream::stream_chat(settings, &provider, &model, &request.messages, 0.7, cancel, on_token).await
+}
+
 // OpenAI chat completion
-async fn chat_completion_openai(messages: Vec<ChatMessage>, model: &str) -> Result<String> {
+async fn chat_completion_openai(settings: &AiSettings, messages: Vec<ChatMessage>, model: &str) -> Result<String> {
     let client = reqwest::Client::new();
     
-    // Use gpt-4o-mini as default model
-    let model = if model.is_empty() || model == "default" {
-        "gpt-4o-mini"
-    } else {
-        model
+    let Some(api_key) = settings.openai_api_key.as_deref() else {
+        return Err(anyhow::anyhow!("Add an OpenAI API key in Settings to use OpenAI chat completion"));
     };
     
-    let api_key = std::env::var("OPENAI_API_KEY")
-        .unwrap_or_else(|_| "your-openai-api-key".to_string());
-    
-    if api_key == "your-openai-api-key" {
-        return Ok("Please set your OPENAI_API_KEY environment variable to use OpenAI chat completion.".to_string());
-    }
-    
     let request_body = serde_json::json!({
         "model": model,
         "messages": messages.iter().map(|msg| serde_json::json!({
@@ -1156,13 +1529,12 @@ async fn chat_completion_openai(messages: Vec<ChatMessage>, model: &str) -> Resu
         "max_tokens": 2000
     });
     
-    let response = client
+    let request = client
         .post("https://api.openai.com/v1/chat/completions")
         .header("Authorization", format!("Bearer {}", api_key))
         .header("Content-Type", "application/json")
-        .json(&request_body)
-        .send()
-        .await?;
+        .json(&request_body);
+    let response = crate::ai_http::send(&Provider::OpenAI, request).await?;
     
     if !response.status().is_success() {
         let error_text = response.text().await.unwrap_or_default();
@@ -1180,58 +1552,36 @@ async fn chat_completion_openai(messages: Vec<ChatMessage>, model: &str) -> Resu
 }
 
 // Ollama chat completion
-async fn chat_completion_ollama(messages: Vec<ChatMessage>, model: &str) -> Result<String> {
+async fn chat_completion_ollama(settings: &AiSettings, messages: Vec<ChatMessage>, model: &str) -> Result<String> {
     let client = reqwest::Client::new();
-    
-    // Use llama3.1:8b as default model
-    let model = if model.is_empty() || model == "default" {
-        "llama3.1:8b"
-    } else {
-        model
-    };
-    
-    let ollama_url = std::env::var("OLLAMA_URL")
-        .unwrap_or_else(|_| "http://localhost:11434".to_string());
+    let ollama_url = &settings.ollama_url;
     
     // Convert messages to a single prompt for Ollama
-    let mut prompt = String::new();
-    for message in &messages {
-        match message.role.as_str() {
-            "system" => prompt.push_str(&format!("System: {}\n", message.content)),
-            "user" => prompt.push_str(&format!("User: {}\n", message.content)),
-            "assistant" => prompt.push_str(&format!("Assistant: {}\n", message.content)),
-            _ => prompt.push_str(&format!("{}: {}\n", message.role, message.content)),
-        }
-    }
-    prompt.push_str("Assistant: ");
+    let prompt = crate::ai_stream::ollama_prompt(&messages);
     
     let request_body = serde_json::json!({
         "model": model,
         "prompt": prompt,
         "stream": false,
-        "options": {
-            "temperature": 0.7,
-            "num_predict": 500
-        }
+        "options": settings.ollama_options(0.7, Some(500))
     });
     
-    let response = client
+    let request = client
         .post(format!("{}/api/generate", ollama_url))
         .header("Content-Type", "application/json")
-        .json(&request_body)
-        .send()
-        .await;
+        .json(&request_body);
+    let response = crate::ai_http::send(&Provider::Ollama, request).await;
     
     let response = match response {
         Ok(resp) => resp,
         Err(e) => {
-            return Ok(format!("Ollama is not available ({}). Please make sure Ollama is running at {} or set OLLAMA_URL environment variable.", e, ollama_url));
+            return Err(anyhow::anyhow!("Ollama is not available ({}). Make sure Ollama is running at {} or change the Ollama URL in Settings.", e, ollama_url));
         }
     };
     
     if !response.status().is_success() {
         let error_text = response.text().await.unwrap_or_default();
-        return Ok(format!("Ollama error: {}. Please check that the model '{}' is available.", error_text, model));
+        return Err(anyhow::anyhow!("Ollama error: {}. Check that the model '{}' is available.", error_text, model));
     }
     
     let response_json: serde_json::Value = response.json().await
@@ -1248,6 +1598,96 @@ async fn chat_completion_ollama(messages: Vec<ChatMessage>, model: &str) -> Resu
 #[cfg(test)]
 mod tests {
     use super::*;
+
+    fn context(n: usize) -> Vec<ContextEntry> {
+        (1..=n)
+            .map(|i| ContextEntry {
+                entry_id: format!("e{}", i),
+                title: None,
+                body: String::new(),
+                entry_date: chrono::Utc::now(),
+                tags: vec![],
+                relevance_score: 0.5,
+                snippet: "é".repeat(300),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_verify_citations_renumbers_and_drops_unknown() {
+        let answer = "You started running in May [Entry 3]. You ran a 5k [Entry 7] with Sam [Entry 1, Entry 3]. Again [3].";
+        let (text, citations) = verify_citations(answer, &context(3));
+        assert_eq!(text, "You started running in May [1]. You ran a 5k with Sam [2][1]. Again [1].");
+        let ids: Vec<&str> = citations.iter().map(|c| c.entry_id.as_str()).collect();
+        assert_eq!(ids, ["e3", "e1"]);
+        assert_eq!(citations[1].citation_number, 2);
+        assert_eq!(citations[0].snippet.chars().count(), 203);
+
+        let (text, citations) = verify_citations("Nothing relevant [Entry 9].", &context(2));
+        assert_eq!(text, "Nothing relevant.");
+        assert!(citations.is_empty());
+    }
+
+    #[test]
+    fn test_question_keywords() {
+        assert_eq!(question_keywords("When did I start running again?"), "start OR running OR again");
+        assert_eq!(question_keywords("What about Sam's wedding, the wedding?"), "sam's OR wedding");
+        assert_eq!(question_keywords("what is it?"), "what is it?");
+    }
+
+    #[test]
+    fn test_parse_ollama_tags() {
+        let response = serde_json::json!({
+            "models": [
+                {
+                    "name": "nomic-embed-text:latest",
+                    "modified_at": "2024-05-01T10:00:00Z",
+                    "size": 274302450u64,
+                    "details": { "family": "nomic-bert", "families": ["nomic-bert"], "parameter_size": "137M", "quantization_level": "F16" }
+                },
+                {
+                    "name": "llama3.1:8b",
+                    "size": 4920753328u64,
+                    "details": { "family": "llama", "parameter_size": "8.0B", "quantization_level": "Q4_0" }
+                },
+                { "size": 1 }
+            ]
+        });
+        let models = parse_ollama_tags(&response).unwrap();
+        let names: Vec<&str> = models.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, ["llama3.1:8b", "nomic-embed-text:latest"]);
+        assert_eq!(models[0].size, 4920753328);
+        assert_eq!(models[0].parameter_size.as_deref(), Some("8.0B"));
+        assert!(!models[0].embedding && models[1].embedding);
+        assert!(parse_ollama_tags(&serde_json::json!({ "error": "nope" })).is_err());
+    }
+
+    #[test]
+    fn test_ai_settings_from_table() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            r#"
+            CREATE TABLE settings (key TEXT PRIMARY KEY, value TEXT NOT NULL);
+            INSERT INTO settings VALUES ('ai_provider', 'openai'), ('openai_api_key', 'sk-test'),
+                ('ollama_url', 'http://nas:11434/'), ('default_model', 'gpt-4.1');
+            "#,
+        )
+        .unwrap();
+        let settings = AiSettings::load(&conn);
+        assert_eq!(settings.provider, Provider::OpenAI);
+        assert_eq!(settings.openai_api_key.as_deref(), Some("sk-test"));
+        assert_eq!(settings.ollama_url, "http://nas:11434");
+
+        assert_eq!(settings.chat_model(&Provider::OpenAI, ""), "gpt-4.1");
+        assert_eq!(settings.chat_model(&Provider::OpenAI, "gpt-4o"), "gpt-4o");
+        // default_model belongs to the configured provider, not to a per-request override
+        assert_eq!(settings.chat_model(&Provider::Ollama, "default"), "llama3.1:8b");
+
+        conn.execute("INSERT INTO settings VALUES ('embedding_provider', 'local')", []).unwrap();
+        let settings = AiSettings::load(&conn);
+        assert_eq!(settings.embedding_provider, Provider::Local);
+        assert_eq!(settings.provider, Provider::OpenAI);
+    }
     
     #[test]
     fn test_rule_based_extraction() {
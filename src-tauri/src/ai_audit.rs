@@ -0,0 +1,139 @@
+use anyhow::Result;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use tauri::AppHandle;
+
+// Audit log of outbound AI requests. ai_http.rs records every request it sends (retries
+// included): where it went, how many bytes it carried and what came back. What the request
+// was for, and which entries' text it included, comes from the caller through `with_context`,
+// so callers don't have to thread it down to the HTTP layer. Request bodies and API keys are
+// never stored.
+
+static DB_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+// Where the journal databases live; set once at startup
+pub fn set_db_dir(dir: PathBuf) {
+    let _ = DB_DIR.set(dir);
+}
+
+#[derive(Debug, Clone)]
+struct AuditContext {
+    purpose: &'static str,
+    entry_ids: Vec<String>,
+}
+
+tokio::task_local! {
+    static CONTEXT: AuditContext;
+}
+
+// Runs `fut` with its AI requests recorded as `purpose`, sharing the text of `entry_ids`
+pub async fn with_context<F: Future>(purpose: &'static str, entry_ids: Vec<String>, fut: F) -> F::Output {
+    CONTEXT.scope(AuditContext { purpose, entry_ids }, fut).await
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AiAuditRecord {
+    pub id: i64,
+    pub created_at: String,
+    pub provider: String,
+    // scheme://host/path; never the query string
+    pub destination: String,
+    pub purpose: String,
+    pub bytes_sent: u64,
+    // HTTP status, or None when no response came back
+    pub status: Option<u16>,
+    pub entry_ids: Vec<String>,
+}
+
+fn destination(url: &reqwest::Url) -> String {
+    match url.port() {
+        Some(port) => format!("{}://{}:{}{}", url.scheme(), url.host_str().unwrap_or(""), port, url.path()),
+        None => format!("{}://{}{}", url.scheme(), url.host_str().unwrap_or(""), url.path()),
+    }
+}
+
+fn insert(conn: &Connection, provider: &str, destination: &str, bytes_sent: u64, status: Option<u16>) -> Result<()> {
+    let context = CONTEXT.try_with(|c| c.clone()).unwrap_or(AuditContext { purpose: "other", entry_ids: vec![] });
+    conn.prepare_cached(
+        r#"INSERT INTO ai_audit_log (created_at, provider, destination, purpose, bytes_sent, status, entry_ids)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)"#,
+    )?
+    .execute(params![
+        chrono::Utc::now().to_rfc3339(),
+        provider,
+        destination,
+        context.purpose,
+        bytes_sent as i64,
+        status,
+        serde_json::to_string(&context.entry_ids)?,
+    ])?;
+    Ok(())
+}
+
+// Called by ai_http for each request sent. Failing to record never fails the request, but is
+// logged, since a gap in an audit log should at least be visible somewhere.
+pub(crate) fn record(provider: &str, url: &reqwest::Url, bytes_sent: u64, status: Option<u16>) {
+    let Some(dir) = DB_DIR.get() else { return };
+    let result = crate::journals::active_path(dir)
+        .and_then(|path| crate::database::open_conn_at(&path))
+        .and_then(|conn| insert(&conn, provider, &destination(url), bytes_sent, status));
+    if let Err(e) = result {
+        tracing::warn!("could not record request to {}: {}", destination(url), e);
+    }
+}
+
+// Newest first
+pub async fn get_log(app_handle: &AppHandle, limit: u32, offset: u32) -> Result<Vec<AiAuditRecord>> {
+    let conn = crate::database::open_conn(app_handle)?;
+    list(&conn, limit, offset)
+}
+
+fn list(conn: &Connection, limit: u32, offset: u32) -> Result<Vec<AiAuditRecord>> {
+    let mut stmt = conn.prepare_cached(
+        r#"SELECT id, created_at, provider, destination, purpose, bytes_sent, status, entry_ids
+            FROM ai_audit_log ORDER BY id DESC LIMIT ?1 OFFSET ?2"#,
+    )?;
+    let rows = stmt.query_map(params![limit, offset], |r| {
+        Ok(AiAuditRecord {
+            id: r.get(0)?,
+            created_at: r.get(1)?,
+            provider: r.get(2)?,
+            destination: r.get(3)?,
+            purpose: r.get(4)?,
+            bytes_sent: r.get::<_, i64>(5)? as u64,
+            status: r.get(6)?,
+            entry_ids: serde_json::from_str(&r.get::<_, String>(7)?).unwrap_or_default(),
+        })
+    })?;
+    Ok(rows.collect::<rusqlite::Result<_>>()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_records_carry_caller_context() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::migrations::run_migrations(&mut conn).unwrap();
+        let url = reqwest::Url::parse("https://api.openai.com/v1/embeddings?key=secret").unwrap();
+
+        insert(&conn, "openai", &destination(&url), 10, None).unwrap();
+        with_context("embedding", vec!["e1".into()], async {
+            insert(&conn, "openai", &destination(&url), 120, Some(200)).unwrap();
+        })
+        .await;
+
+        let log = list(&conn, 10, 0).unwrap();
+        assert_eq!(log.len(), 2);
+        assert_eq!(log[0].purpose, "embedding");
+        assert_eq!(log[0].entry_ids, vec!["e1"]);
+        assert_eq!(log[0].status, Some(200));
+        assert_eq!(log[0].destination, "https://api.openai.com/v1/embeddings");
+        assert_eq!(log[1].purpose, "other");
+        assert!(log[1].entry_ids.is_empty());
+    }
+}
@@ -0,0 +1,71 @@
+use anyhow::Result;
+use rusqlite::{params, Connection, OptionalExtension};
+use sha2::{Digest, Sha256};
+
+// Results of deterministic AI calls (embeddings, tag extraction), keyed by the hash of their
+// input, the model and the prompt version, so re-running a backfill or re-importing the same
+// text doesn't pay for the same call twice. Bump a kind's version when its prompt or input
+// format changes, which leaves the old rows unused.
+
+pub const EMBEDDING: &str = "embedding";
+pub const EMBEDDING_VERSION: i64 = 1;
+pub const TAGS: &str = "tags";
+pub const TAGS_VERSION: i64 = 1;
+
+// Hash of the call's inputs; the parts are separated so ("ab", "c") and ("a", "bc") differ
+pub fn input_hash(parts: &[&str]) -> String {
+    let mut hasher = Sha256::new();
+    for part in parts {
+        hasher.update(part.as_bytes());
+        hasher.update([0u8]);
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+pub fn get(conn: &Connection, kind: &str, hash: &str, model: &str, version: i64) -> Result<Option<Vec<u8>>> {
+    Ok(conn
+        .prepare_cached("SELECT value FROM ai_cache WHERE kind = ?1 AND text_hash = ?2 AND model = ?3 AND prompt_version = ?4")?
+        .query_row(params![kind, hash, model, version], |r| r.get(0))
+        .optional()?)
+}
+
+pub fn put(conn: &Connection, kind: &str, hash: &str, model: &str, version: i64, value: &[u8]) -> Result<()> {
+    conn.prepare_cached(
+        r#"INSERT INTO ai_cache (kind, text_hash, model, prompt_version, value, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            ON CONFLICT(kind, text_hash, model, prompt_version) DO UPDATE SET value = excluded.value, created_at = excluded.created_at"#,
+    )?
+    .execute(params![kind, hash, model, version, value, chrono::Utc::now().to_rfc3339()])?;
+    Ok(())
+}
+
+// Removes cached results of one kind (all kinds when None), optionally for one model only
+pub fn clear(conn: &Connection, kind: Option<&str>, model: Option<&str>) -> Result<usize> {
+    Ok(conn.execute(
+        "DELETE FROM ai_cache WHERE (?1 IS NULL OR kind = ?1) AND (?2 IS NULL OR model = ?2)",
+        params![kind, model],
+    )?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_round_trip() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::migrations::run_migrations(&mut conn).unwrap();
+        let hash = input_hash(&["some text"]);
+        assert_ne!(hash, input_hash(&["some", " text"]));
+        assert!(get(&conn, EMBEDDING, &hash, "ollama:nomic", EMBEDDING_VERSION).unwrap().is_none());
+
+        put(&conn, EMBEDDING, &hash, "ollama:nomic", EMBEDDING_VERSION, &[1, 2, 3, 4]).unwrap();
+        put(&conn, TAGS, &hash, "ollama:llama", TAGS_VERSION, b"[]").unwrap();
+        assert_eq!(get(&conn, EMBEDDING, &hash, "ollama:nomic", EMBEDDING_VERSION).unwrap(), Some(vec![1, 2, 3, 4]));
+        // Another model or prompt version is a miss
+        assert!(get(&conn, EMBEDDING, &hash, "openai:small", EMBEDDING_VERSION).unwrap().is_none());
+        assert!(get(&conn, EMBEDDING, &hash, "ollama:nomic", EMBEDDING_VERSION + 1).unwrap().is_none());
+
+        assert_eq!(clear(&conn, Some(EMBEDDING), Some("ollama:nomic")).unwrap(), 1);
+        assert_eq!(clear(&conn, None, None).unwrap(), 1);
+    }
+}
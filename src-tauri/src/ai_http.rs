@@ -0,0 +1,214 @@
+use anyhow::Result;
+use reqwest::{RequestBuilder, Response, StatusCode};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::ai::Provider;
+
+// Shared HTTP layer for AI provider calls. Requests to one provider are spaced out (rate
+// limiting), retried with exponential backoff on 429/5xx responses and timeouts (honoring
+// Retry-After), and after repeated failures the provider's circuit opens: calls fail right
+// away for a while instead of each waiting out its own retries, so batch jobs stop quickly.
+// Every request sent is recorded in the AI audit log (ai_audit.rs).
+
+const MAX_ATTEMPTS: u32 = 4;
+const BASE_DELAY: Duration = Duration::from_millis(500);
+const MAX_DELAY: Duration = Duration::from_secs(30);
+// Failed calls in a row (after their retries) that open the circuit, and for how long
+const FAILURES_TO_OPEN: u32 = 5;
+const OPEN_FOR: Duration = Duration::from_secs(30);
+
+// Minimum time between two requests to the provider
+fn min_interval(provider: &Provider) -> Duration {
+    match provider {
+        // 300 requests a minute, under OpenAI's lowest paid-tier limits
+        Provider::OpenAI => Duration::from_millis(200),
+        // A local server; just enough spacing that a batch job leaves room for interactive calls
+        Provider::Ollama => Duration::from_millis(20),
+        Provider::Local => Duration::ZERO,
+    }
+}
+
+#[derive(Debug)]
+pub struct CircuitOpen {
+    pub provider: &'static str,
+    pub retry_in: Duration,
+}
+
+impl std::fmt::Display for CircuitOpen {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} keeps failing; pausing requests for {}s", self.provider, self.retry_in.as_secs().max(1))
+    }
+}
+
+impl std::error::Error for CircuitOpen {}
+
+// True when the call failed without being sent because the provider's circuit is open
+pub(crate) fn is_circuit_open(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| cause.downcast_ref::<CircuitOpen>().is_some())
+}
+
+#[derive(Debug, Default)]
+struct ProviderState {
+    // Earliest time the next request may start
+    next_slot: Option<Instant>,
+    failures: u32,
+    open_until: Option<Instant>,
+}
+
+impl ProviderState {
+    // How long to wait before sending, or Err with the time left while the circuit is open.
+    // Once it has been open OPEN_FOR, one more failure opens it again.
+    fn admit(&mut self, interval: Duration, now: Instant) -> std::result::Result<Duration, Duration> {
+        if let Some(until) = self.open_until {
+            if now < until {
+                return Err(until - now);
+            }
+        }
+        let slot = self.next_slot.map_or(now, |s| s.max(now));
+        self.next_slot = Some(slot + interval);
+        Ok(slot - now)
+    }
+
+    fn record(&mut self, ok: bool, now: Instant) {
+        if ok {
+            self.failures = 0;
+            self.open_until = None;
+        } else {
+            self.failures += 1;
+            if self.failures >= FAILURES_TO_OPEN {
+                self.open_until = Some(now + OPEN_FOR);
+            }
+        }
+    }
+
+    // A 429 asks everyone to hold off, not just the request that got it
+    fn hold_off(&mut self, until: Instant) {
+        self.next_slot = Some(self.next_slot.map_or(until, |s| s.max(until)));
+    }
+}
+
+static STATES: Mutex<Option<HashMap<&'static str, ProviderState>>> = Mutex::new(None);
+
+fn with_state<T>(provider: &Provider, f: impl FnOnce(&mut ProviderState) -> T) -> T {
+    let mut guard = STATES.lock().unwrap_or_else(|e| e.into_inner());
+    f(guard.get_or_insert_with(HashMap::new).entry(provider.as_str()).or_default())
+}
+
+fn is_transient(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || matches!(status.as_u16(), 500 | 502 | 503 | 504)
+}
+
+// Retry-After in seconds (the HTTP-date form is rare from these APIs and ignored)
+fn retry_after(response: &Response) -> Option<Duration> {
+    let value = response.headers().get(reqwest::header::RETRY_AFTER)?.to_str().ok()?;
+    let secs: f64 = value.trim().parse().ok()?;
+    (secs >= 0.0).then(|| Duration::from_secs_f64(secs).min(MAX_DELAY))
+}
+
+// Exponential backoff with jitter: attempt 1 waits 0.25-0.5s, 2 waits 0.5-1s, ... capped at
+// MAX_DELAY; `jitter` in 0..1 picks where in the range
+fn backoff(attempt: u32, jitter: f64) -> Duration {
+    let full = BASE_DELAY.saturating_mul(1 << (attempt.saturating_sub(1)).min(16)).min(MAX_DELAY);
+    full / 2 + full.mul_f64(jitter.clamp(0.0, 1.0) / 2.0)
+}
+
+fn jitter() -> f64 {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.subsec_nanos())
+        .unwrap_or(0);
+    (nanos % 1000) as f64 / 1000.0
+}
+
+// Sends `request` to `provider` with rate limiting, retries and circuit breaking. A response
+// that still has an error status after the retries is returned for the caller to report.
+pub(crate) async fn send(provider: &Provider, request: RequestBuilder) -> Result<Response> {
+    let interval = min_interval(provider);
+    let mut attempt = 0;
+    loop {
+        let wait = with_state(provider, |s| s.admit(interval, Instant::now()))
+            .map_err(|retry_in| CircuitOpen { provider: provider.as_str(), retry_in })?;
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+        let (client, this_try) = request
+            .try_clone()
+            .ok_or_else(|| anyhow::anyhow!("AI request body can't be resent"))?
+            .build_split();
+        let this_try = this_try?;
+        let url = this_try.url().clone();
+        let bytes_sent = this_try.body().and_then(|b| b.as_bytes()).map_or(0, |b| b.len() as u64);
+        attempt += 1;
+
+        let outcome = client.execute(this_try).await;
+        crate::ai_audit::record(provider.as_str(), &url, bytes_sent, outcome.as_ref().ok().map(|r| r.status().as_u16()));
+        let (delay, reason) = match outcome {
+            Ok(response) if is_transient(response.status()) => {
+                if attempt >= MAX_ATTEMPTS {
+                    with_state(provider, |s| s.record(false, Instant::now()));
+                    return Ok(response);
+                }
+                let delay = retry_after(&response).unwrap_or_else(|| backoff(attempt, jitter()));
+                if response.status() == StatusCode::TOO_MANY_REQUESTS {
+                    with_state(provider, |s| s.hold_off(Instant::now() + delay));
+                }
+                (delay, response.status().to_string())
+            }
+            Ok(response) => {
+                // Other errors (bad key, unknown model) are the request's fault, not the provider's
+                with_state(provider, |s| s.record(true, Instant::now()));
+                return Ok(response);
+            }
+            Err(e) if e.is_timeout() && attempt < MAX_ATTEMPTS => (backoff(attempt, jitter()), e.to_string()),
+            Err(e) => {
+                with_state(provider, |s| s.record(false, Instant::now()));
+                return Err(e.into());
+            }
+        };
+        tracing::warn!(
+            "{} {} (attempt {}/{}); retrying in {}ms",
+            provider.as_str(), reason, attempt, MAX_ATTEMPTS, delay.as_millis()
+        );
+        tokio::time::sleep(delay).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_grows_and_caps() {
+        assert_eq!(backoff(1, 0.0), Duration::from_millis(250));
+        assert_eq!(backoff(1, 1.0), Duration::from_millis(500));
+        assert_eq!(backoff(3, 1.0), Duration::from_secs(2));
+        assert_eq!(backoff(40, 1.0), MAX_DELAY);
+    }
+
+    #[test]
+    fn test_rate_limit_and_circuit() {
+        let now = Instant::now();
+        let interval = Duration::from_millis(200);
+        let mut state = ProviderState::default();
+        assert_eq!(state.admit(interval, now), Ok(Duration::ZERO));
+        assert_eq!(state.admit(interval, now), Ok(interval));
+        state.hold_off(now + Duration::from_secs(5));
+        assert_eq!(state.admit(interval, now), Ok(Duration::from_secs(5)));
+
+        let mut state = ProviderState::default();
+        for _ in 0..FAILURES_TO_OPEN {
+            assert!(state.admit(Duration::ZERO, now).is_ok());
+            state.record(false, now);
+        }
+        assert_eq!(state.admit(Duration::ZERO, now + Duration::from_secs(10)), Err(OPEN_FOR - Duration::from_secs(10)));
+        // Half-open: one trial goes through; a failure reopens, a success closes
+        let later = now + OPEN_FOR;
+        assert!(state.admit(Duration::ZERO, later).is_ok());
+        state.record(false, later);
+        assert!(state.admit(Duration::ZERO, later).is_err());
+        state.record(true, later);
+        assert!(state.admit(Duration::ZERO, later).is_ok());
+    }
+}
@@ -0,0 +1,292 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::Notify;
+
+use crate::ai::{AiSettings, ChatMessage, Provider};
+
+// Token streaming for chat and RAG answers.
+//
+// Both providers stream newline-delimited records: OpenAI as server-sent events
+// (`data: {...}` with the text in choices[0].delta.content, ending with `data: [DONE]`),
+// Ollama as one JSON object per line with the text in `response` and `done: true` at the
+// end. Each piece is handed to the caller as it arrives; the full text is returned at the
+// end. Streams are registered under a caller-chosen id so `cancel` can stop one mid-answer,
+// in which case whatever arrived so far is returned.
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AiToken {
+    pub stream_id: String,
+    pub token: String,
+}
+
+#[derive(Default)]
+pub struct CancelToken {
+    cancelled: AtomicBool,
+    notify: Notify,
+}
+
+impl CancelToken {
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+        // notify_one keeps a permit, so a cancel between reads is not lost
+        self.notify.notify_one();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+static STREAMS: Mutex<Option<HashMap<String, Arc<CancelToken>>>> = Mutex::new(None);
+
+// Removes the stream from the registry when the request finishes, however it finishes
+pub struct StreamRegistration {
+    id: String,
+    pub token: Arc<CancelToken>,
+}
+
+impl Drop for StreamRegistration {
+    fn drop(&mut self) {
+        let mut streams = STREAMS.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(map) = streams.as_mut() {
+            map.remove(&self.id);
+        }
+    }
+}
+
+pub fn register(stream_id: &str) -> StreamRegistration {
+    let token = Arc::new(CancelToken::default());
+    let mut streams = STREAMS.lock().unwrap_or_else(|e| e.into_inner());
+    streams.get_or_insert_with(HashMap::new).insert(stream_id.to_string(), token.clone());
+    StreamRegistration { id: stream_id.to_string(), token }
+}
+
+// False when no such stream is running (already finished, or never started)
+pub fn cancel(stream_id: &str) -> bool {
+    let streams = STREAMS.lock().unwrap_or_else(|e| e.into_inner());
+    match streams.as_ref().and_then(|map| map.get(stream_id)) {
+        Some(token) => {
+            token.cancel();
+            true
+        }
+        None => false,
+    }
+}
+
+// Splits a byte stream into lines; partial lines (and split UTF-8 sequences) wait for the next chunk
+#[derive(Default)]
+struct LineBuffer {
+    pending: Vec<u8>,
+}
+
+impl LineBuffer {
+    fn push(&mut self, chunk: &[u8]) -> Vec<String> {
+        self.pending.extend_from_slice(chunk);
+        let mut lines = Vec::new();
+        while let Some(pos) = self.pending.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.pending.drain(..=pos).collect();
+            let line = String::from_utf8_lossy(&line).trim().to_string();
+            if !line.is_empty() {
+                lines.push(line);
+            }
+        }
+        lines
+    }
+
+    fn finish(&mut self) -> Option<String> {
+        let line = String::from_utf8_lossy(&std::mem::take(&mut self.pending)).trim().to_string();
+        (!line.is_empty()).then_some(line)
+    }
+}
+
+enum Piece {
+    Text(String),
+    Done,
+    Skip,
+}
+
+fn parse_openai_line(line: &str) -> Result<Piece> {
+    let Some(data) = line.strip_prefix("data:").map(str::trim) else {
+        return Ok(Piece::Skip); // comments and event names
+    };
+    if data == "[DONE]" {
+        return Ok(Piece::Done);
+    }
+    let json: serde_json::Value = serde_json::from_str(data)?;
+    if let Some(message) = json["error"]["message"].as_str() {
+        return Err(anyhow::anyhow!("OpenAI API error: {}", message));
+    }
+    Ok(match json["choices"][0]["delta"]["content"].as_str() {
+        Some(text) if !text.is_empty() => Piece::Text(text.to_string()),
+        _ => Piece::Skip,
+    })
+}
+
+fn parse_ollama_line(line: &str) -> Result<Piece> {
+    let json: serde_json::Value = serde_json::from_str(line)?;
+    if let Some(message) = json["error"].as_str() {
+        return Err(anyhow::anyhow!("Ollama error: {}", message));
+    }
+    if let Some(text) = json["response"].as_str().filter(|t| !t.is_empty()) {
+        return Ok(Piece::Text(text.to_string()));
+    }
+    Ok(if json["done"].as_bool() == Some(true) { Piece::Done } else { Piece::Skip })
+}
+
+// Same flattening the non-streaming Ollama path uses, since /api/generate takes one prompt
+pub fn ollama_prompt(messages: &[ChatMessage]) -> String {
+    let mut prompt = String::new();
+    for message in messages {
+        match message.role.as_str() {
+            "system" => prompt.push_str(&format!("System: {}\n", message.content)),
+            "user" => prompt.push_str(&format!("User: {}\n", message.content)),
+            "assistant" => prompt.push_str(&format!("Assistant: {}\n", message.content)),
+            _ => prompt.push_str(&format!("{}: {}\n", message.role, message.content)),
+        }
+    }
+    prompt.push_str("Assistant: ");
+    prompt
+}
+
+// Streams a completion, calling `on_token` for each piece; returns the text received
+pub async fn stream_chat(
+    settings: &AiSettings,
+    provider: &Provider,
+    model: &str,
+    messages: &[ChatMessage],
+    temperature: f32,
+    cancel: &CancelToken,
+    mut on_token: impl FnMut(&str),
+) -> Result<String> {
+    let client = reqwest::Client::new();
+    let (request, parse): (reqwest::RequestBuilder, fn(&str) -> Result<Piece>) = match provider {
+        Provider::OpenAI => {
+            let api_key = settings
+                .openai_api_key
+                .as_deref()
+                .ok_or_else(|| anyhow::anyhow!("Add an OpenAI API key in Settings to use OpenAI chat completion"))?;
+            let body = serde_json::json!({
+                "model": model,
+                "messages": messages.iter().map(|m| serde_json::json!({ "role": m.role, "content": m.content })).collect::<Vec<_>>(),
+                "temperature": temperature,
+                "max_tokens": 2000,
+                "stream": true
+            });
+            let request = client
+                .post("https://api.openai.com/v1/chat/completions")
+                .header("Authorization", format!("Bearer {}", api_key))
+                .json(&body);
+            (request, parse_openai_line)
+        }
+        Provider::Ollama => {
+            let body = serde_json::json!({
+                "model": model,
+                "prompt": ollama_prompt(messages),
+                "stream": true,
+                "options": settings.ollama_options(temperature, None)
+            });
+            (client.post(format!("{}/api/generate", settings.ollama_url)).json(&body), parse_ollama_line)
+        }
+        Provider::Local => {
+            return Err(anyhow::anyhow!("The local provider only computes embeddings; choose Ollama or OpenAI for chat"))
+        }
+    };
+
+    let mut response = crate::ai_http::send(provider, request).await?;
+    if !response.status().is_success() {
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(anyhow::anyhow!("{} API error: {}", provider.as_str(), error_text));
+    }
+
+    let mut text = String::new();
+    let mut lines = LineBuffer::default();
+    let mut handle = |line: &str, text: &mut String| -> Result<bool> {
+        match parse(line)? {
+            Piece::Text(piece) => {
+                on_token(&piece);
+                text.push_str(&piece);
+                Ok(false)
+            }
+            Piece::Done => Ok(true),
+            Piece::Skip => Ok(false),
+        }
+    };
+
+    'read: loop {
+        if cancel.is_cancelled() {
+            break;
+        }
+        let chunk = tokio::select! {
+            chunk = response.chunk() => chunk?,
+            _ = cancel.notify.notified() => break,
+        };
+        let Some(chunk) = chunk else {
+            if let Some(line) = lines.finish() {
+                handle(&line, &mut text)?;
+            }
+            break;
+        };
+        for line in lines.push(&chunk) {
+            if handle(&line, &mut text)? {
+                break 'read;
+            }
+        }
+    }
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(lines: &[String], parse: fn(&str) -> Result<Piece>) -> (String, bool) {
+        let mut out = String::new();
+        for line in lines {
+            match parse(line).unwrap() {
+                Piece::Text(t) => out.push_str(&t),
+                Piece::Done => return (out, true),
+                Piece::Skip => {}
+            }
+        }
+        (out, false)
+    }
+
+    #[test]
+    fn test_openai_sse_split_across_chunks() {
+        let stream = "data: {\"choices\":[{\"delta\":{\"role\":\"assistant\"}}]}\n\n\
+            data: {\"choices\":[{\"delta\":{\"content\":\"Caf\u{e9}\"}}]}\n\n\
+            : keep-alive\n\
+            data: {\"choices\":[{\"delta\":{\"content\":\" time\"}}]}\n\n\
+            data: [DONE]\n\n";
+        // Split mid-way through the two-byte é
+        let bytes = stream.as_bytes();
+        let split = stream.find('\u{e9}').unwrap() + 1;
+        let mut buffer = LineBuffer::default();
+        let mut lines = buffer.push(&bytes[..split]);
+        lines.extend(buffer.push(&bytes[split..]));
+        assert_eq!(texts(&lines, parse_openai_line), ("Caf\u{e9} time".to_string(), true));
+    }
+
+    #[test]
+    fn test_ollama_ndjson() {
+        let mut buffer = LineBuffer::default();
+        let mut lines = buffer.push(b"{\"response\":\"Hel\",\"done\":false}\n{\"response\":\"lo\",\"do");
+        lines.extend(buffer.push(b"ne\":false}\n{\"response\":\"\",\"done\":true}"));
+        lines.extend(buffer.finish());
+        assert_eq!(texts(&lines, parse_ollama_line), ("Hello".to_string(), true));
+
+        assert!(parse_ollama_line("{\"error\":\"model 'x' not found\"}").is_err());
+    }
+
+    #[test]
+    fn test_cancel_registry() {
+        let registration = register("s1");
+        assert!(cancel("s1"));
+        assert!(registration.token.is_cancelled());
+        drop(registration);
+        assert!(!cancel("s1"));
+    }
+}
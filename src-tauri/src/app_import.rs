@@ -0,0 +1,188 @@
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
+use regex::Regex;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use tauri::AppHandle;
+
+use crate::import::{normalize_content, FileType, ParsedFile};
+
+// Shared saving for exports from other journaling apps and blogs (diaro.rs, daylio.rs,
+// livejournal.rs, blog.rs, facebook.rs, sms.rs, feed.rs, imap.rs, pdf_import.rs, photos.rs).
+//
+// Each parser turns its export into `AppEntry` values; saving them skips text the journal
+// already has, tags the new entries and, when the app recorded a mood, stores that as the
+// entry's sentiment instead of the score computed from the text.
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AppEntry {
+    // Where it came from, e.g. "DiaroBackup.xml#<uid>"; stored as the source path
+    pub source: String,
+    pub title: Option<String>,
+    pub body: String,
+    pub tags: Vec<String>,
+    pub created: DateTime<Utc>,
+    pub timezone: String,
+    pub sentiment: Option<f32>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AppImportReport {
+    pub imported: u32,
+    pub duplicates: u32,
+    // Records without any text to import
+    pub skipped: u32,
+    pub failed: u32,
+    pub errors: Option<Vec<String>>,
+}
+
+impl AppImportReport {
+    pub fn error(&mut self, message: String) {
+        self.failed += 1;
+        self.errors.get_or_insert_with(Vec::new).push(message);
+    }
+}
+
+// Lowercased, trimmed and deduplicated, like Keep labels
+pub fn normalize_tags<'a>(tags: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    let mut tags: Vec<String> = tags.into_iter().map(|t| t.trim().to_lowercase()).filter(|t| !t.is_empty()).collect();
+    tags.sort();
+    tags.dedup();
+    tags
+}
+
+// A local time without zone in the import's time zone (IANA name or "+02:00"), else as UTC
+pub fn local_to_utc(local: NaiveDateTime, timezone: &str) -> DateTime<Utc> {
+    if let Ok(tz) = timezone.parse::<chrono_tz::Tz>() {
+        if let Some(t) = tz.from_local_datetime(&local).earliest() {
+            return t.with_timezone(&Utc);
+        }
+    }
+    if let Ok(offset) = timezone.parse::<chrono::FixedOffset>() {
+        if let Some(t) = offset.from_local_datetime(&local).single() {
+            return t.with_timezone(&Utc);
+        }
+    }
+    Utc.from_utc_datetime(&local)
+}
+
+// Every file under `paths` (files or folders) with one of `extensions`, sorted
+pub fn files_with_extension(paths: &[String], extensions: &[&str]) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = paths
+        .iter()
+        .flat_map(|p| walkdir::WalkDir::new(p).into_iter().filter_map(|e| e.ok()))
+        .map(|e| e.into_path())
+        .filter(|p| p.is_file() && p.extension().and_then(|e| e.to_str()).is_some_and(|e| extensions.iter().any(|x| e.eq_ignore_ascii_case(x))))
+        .collect();
+    files.sort();
+    files.dedup();
+    files
+}
+
+// Plain text from a post's HTML: line and paragraph breaks become newlines, other tags are
+// dropped (keeping their text) and entities are decoded
+pub fn html_to_text(html: &str) -> String {
+    static BREAK: OnceLock<Regex> = OnceLock::new();
+    static BLOCK: OnceLock<Regex> = OnceLock::new();
+    static TAG: OnceLock<Regex> = OnceLock::new();
+    static ENTITY: OnceLock<Regex> = OnceLock::new();
+    let brk = BREAK.get_or_init(|| Regex::new(r"(?i)<br\s*/?>").expect("break pattern"));
+    let block = BLOCK.get_or_init(|| Regex::new(r"(?i)</(?:p|div|li|h[1-6]|blockquote|pre|tr)\s*>|<hr\s*/?>").expect("block pattern"));
+    let tag = TAG.get_or_init(|| Regex::new(r"(?s)<!--.*?-->|<[^>]*>").expect("tag pattern"));
+    let entity = ENTITY.get_or_init(|| Regex::new(r"&(#[0-9]+|#[xX][0-9a-fA-F]+|[a-zA-Z]+);").expect("entity pattern"));
+
+    let text = brk.replace_all(html, "\n");
+    let text = block.replace_all(&text, "\n\n");
+    let text = tag.replace_all(&text, "");
+    let text = entity.replace_all(&text, |caps: &regex::Captures| {
+        let name = &caps[1];
+        let decoded = match name {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some(' '),
+            _ if name.starts_with("#x") || name.starts_with("#X") => u32::from_str_radix(&name[2..], 16).ok().and_then(char::from_u32),
+            _ if name.starts_with('#') => name[1..].parse().ok().and_then(char::from_u32),
+            _ => None,
+        };
+        decoded.map(String::from).unwrap_or_else(|| caps[0].to_string())
+    });
+    // At most one blank line between paragraphs, no trailing spaces
+    let mut out = String::new();
+    let mut blank = 0;
+    for line in text.lines().map(str::trim_end) {
+        if line.trim().is_empty() {
+            blank += 1;
+            continue;
+        }
+        if !out.is_empty() {
+            out.push_str(if blank > 0 { "\n\n" } else { "\n" });
+        }
+        out.push_str(line);
+        blank = 0;
+    }
+    out
+}
+
+pub async fn save_all(app_handle: &AppHandle, entries: Vec<AppEntry>, file_type: FileType, report: &mut AppImportReport) -> anyhow::Result<()> {
+    for entry in entries {
+        save_one(app_handle, entry, file_type, report).await?;
+    }
+    Ok(())
+}
+
+// Saves one entry, counting it in `report`; the new entry's id, or None when it was skipped,
+// a duplicate or failed
+pub async fn save_one(app_handle: &AppHandle, entry: AppEntry, file_type: FileType, report: &mut AppImportReport) -> anyhow::Result<Option<String>> {
+    let content = normalize_content(&entry.body);
+    if content.is_empty() {
+        report.skipped += 1;
+        return Ok(None);
+    }
+    let text_hash = format!("{:x}", Sha256::digest(content.as_bytes()));
+    if crate::database::check_duplicate(app_handle, &text_hash).await?.is_some() {
+        report.duplicates += 1;
+        return Ok(None);
+    }
+    let parsed = ParsedFile {
+        path: entry.source.clone(),
+        size_bytes: content.len() as u64,
+        content,
+        title: entry.title,
+        file_type,
+        text_hash,
+    };
+    match crate::database::save_entry(app_handle, parsed, entry.created, entry.timezone).await {
+        Ok(id) => {
+            let conn = crate::database::open_conn(app_handle)?;
+            if !entry.tags.is_empty() {
+                crate::database::add_entry_tags(&conn, &id, &entry.tags)?;
+            }
+            if let Some(sentiment) = entry.sentiment {
+                conn.execute("UPDATE entries SET sentiment = ?2 WHERE id = ?1", rusqlite::params![id, sentiment])?;
+            }
+            report.imported += 1;
+            Ok(Some(id))
+        }
+        Err(e) => {
+            report.error(format!("{}: {}", entry.source, e));
+            Ok(None)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_html_to_text() {
+        let html = "<p>Went to the <b>lake</b> &amp; swam.<br/>Cold!</p>\n<p>Saw <a href=\"x\">Ann</a> &#8212; caf&eacute;</p><!-- hidden -->";
+        assert_eq!(html_to_text(html), "Went to the lake & swam.\nCold!\n\nSaw Ann \u{2014} caf&eacute;");
+        assert_eq!(html_to_text("plain\n\n\n\ntext  "), "plain\n\ntext");
+    }
+}
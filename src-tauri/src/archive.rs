@@ -0,0 +1,101 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use tauri::AppHandle;
+
+// Archived copies of imported files (opt-in, `archive_originals`).
+//
+// Each file is copied into `archive/` next to the journals, named by the SHA-256 of its bytes
+// ("3f/3fa4...e1.docx"), and the entry records that name in `archive_file`. The same file
+// imported twice, or into two journals, is stored once. Entries can then be recovered in their
+// original form after the folder in `source_path` is moved or deleted. Archived files are kept
+// when their entry is deleted.
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ArchivedOriginal {
+    pub source_path: String,
+    pub archive_path: String,
+}
+
+pub(crate) fn archive_dir(app_handle: &AppHandle) -> Result<PathBuf> {
+    Ok(crate::database::get_db_dir(app_handle)?.join("archive"))
+}
+
+// Copies `source` into the archive unless it is already there; returns its archive name
+pub fn store(dir: &Path, source: &Path) -> Result<String> {
+    let bytes = fs::read(source).with_context(|| format!("Could not read {}", source.display()))?;
+    let hash = format!("{:x}", Sha256::digest(&bytes));
+    let extension = source.extension().and_then(|e| e.to_str()).map(|e| format!(".{}", e.to_lowercase())).unwrap_or_default();
+    let name = format!("{}/{}{}", &hash[..2], hash, extension);
+    let path = dir.join(&name);
+    if !path.exists() {
+        fs::create_dir_all(dir.join(&hash[..2]))?;
+        // Written under a temporary name first so an interrupted copy never passes for the file
+        let partial = dir.join(format!("{}.partial", name));
+        fs::write(&partial, &bytes)?;
+        fs::rename(&partial, &path)?;
+    }
+    Ok(name)
+}
+
+// Archives an entry's source file and records it on the entry
+pub(crate) fn archive_entry(app_handle: &AppHandle, conn: &Connection, entry_id: &str, source: &Path) -> Result<()> {
+    let name = store(&archive_dir(app_handle)?, source)?;
+    conn.execute("UPDATE entries SET archive_file = ?2 WHERE id = ?1", params![entry_id, name])?;
+    Ok(())
+}
+
+pub(crate) fn original(app_handle: &AppHandle, conn: &Connection, entry_id: &str) -> Result<Option<ArchivedOriginal>> {
+    let row: Option<(String, Option<String>)> = conn
+        .query_row("SELECT source_path, archive_file FROM entries WHERE id = ?1", params![entry_id], |r| Ok((r.get(0)?, r.get(1)?)))
+        .optional()?;
+    let Some((source_path, Some(name))) = row else { return Ok(None) };
+    let path = archive_dir(app_handle)?.join(name);
+    Ok(path.is_file().then(|| ArchivedOriginal { source_path, archive_path: path.to_string_lossy().to_string() }))
+}
+
+// Copies an archived file to `dest`, checking it still matches the hash in its name
+pub fn restore(archive_path: &Path, dest: &Path) -> Result<()> {
+    let bytes = fs::read(archive_path).with_context(|| format!("Could not read {}", archive_path.display()))?;
+    let expected = archive_path.file_stem().and_then(|s| s.to_str()).unwrap_or_default();
+    if format!("{:x}", Sha256::digest(&bytes)) != expected {
+        anyhow::bail!("Archived copy {} is damaged", archive_path.display());
+    }
+    fs::write(dest, bytes).with_context(|| format!("Could not write {}", dest.display()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_store_and_restore() {
+        let dir = std::env::temp_dir().join(format!("jr-archive-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("Morning Pages.DOCX");
+        fs::write(&source, b"dear diary").unwrap();
+
+        let archive = dir.join("archive");
+        let name = store(&archive, &source).unwrap();
+        assert!(name.starts_with("93/") && name.ends_with(".docx"), "{}", name);
+        // Same bytes elsewhere land on the same archived copy
+        let copy = dir.join("copy.docx");
+        fs::copy(&source, &copy).unwrap();
+        assert_eq!(store(&archive, &copy).unwrap(), name);
+        assert_eq!(fs::read_dir(archive.join(&name[..2])).unwrap().count(), 1);
+
+        fs::remove_file(&source).unwrap();
+        let restored = dir.join("restored.docx");
+        restore(&archive.join(&name), &restored).unwrap();
+        assert_eq!(fs::read(&restored).unwrap(), b"dear diary");
+
+        fs::write(archive.join(&name), b"tampered").unwrap();
+        assert!(restore(&archive.join(&name), &restored).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,613 @@
+use std::collections::BTreeMap;
+
+use anyhow::{Context, Result};
+use base64::Engine;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::database::EntryFilter;
+
+// Full JSON backup of the journal (schema documented in the README).
+//
+// Holds what can't be recomputed: entries with their tags, settings (secrets stay in the OS
+// keychain and are never written), AI conversations, period summaries and search history.
+// Embeddings, chunks and extracted entities are derived and are rebuilt after an import.
+// Timestamps are written exactly as stored so a restore is lossless. With a search filter only
+// the matching entries are exported, without settings, conversations or history.
+
+pub const FORMAT: &str = "journal-reader-backup";
+pub const FORMAT_VERSION: u32 = 1;
+// Settings-only file, for setting up the app on another machine
+pub const SETTINGS_FORMAT: &str = "journal-reader-settings";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Backup {
+    pub format: String,
+    pub format_version: u32,
+    pub exported_at: String,
+    pub schema_version: i64,
+    pub entries: Vec<BackupEntry>,
+    #[serde(default)]
+    pub settings: BTreeMap<String, String>,
+    #[serde(default)]
+    pub conversations: Vec<BackupConversation>,
+    #[serde(default)]
+    pub summaries: Vec<BackupSummary>,
+    #[serde(default)]
+    pub search_history: Vec<BackupSearch>,
+    #[serde(default)]
+    pub templates: Vec<crate::templates::EntryTemplate>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SettingsFile {
+    pub format: String,
+    pub format_version: u32,
+    pub exported_at: String,
+    pub settings: BTreeMap<String, String>,
+    #[serde(default)]
+    pub templates: Vec<crate::templates::EntryTemplate>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupEntry {
+    pub id: String,
+    pub title: Option<String>,
+    pub body: String,
+    pub entry_date: String,
+    pub entry_timezone: String,
+    pub source_path: String,
+    pub source_type: String,
+    pub text_hash: String,
+    pub created_at: String,
+    pub updated_at: String,
+    pub sentiment: Option<f64>,
+    pub language: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    // Sensitive entries only: the sealed body, base64; `body` is then empty (see sensitive.rs)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body_sealed: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupConversation {
+    pub id: String,
+    pub title: String,
+    pub created_at: String,
+    pub updated_at: String,
+    pub messages: Vec<BackupMessage>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupMessage {
+    pub id: String,
+    pub role: String,
+    pub content: String,
+    // The citation list as JSON, when the message has one
+    pub citations: Option<serde_json::Value>,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupSummary {
+    pub period_start: String,
+    pub period_end: String,
+    pub granularity: String,
+    pub summary: String,
+    pub model: String,
+    pub entry_count: i64,
+    pub source_hash: String,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupSearch {
+    pub query: String,
+    pub use_count: i64,
+    pub last_used_at: String,
+}
+
+// How a restore treats what the journal already has. Entries are never overwritten in either
+// mode: an entry is imported only when no entry with its id or text_hash exists.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RestoreMode {
+    // The backup's settings replace the current ones, e.g. when setting up a new machine
+    #[default]
+    Full,
+    // Only what is missing is added; current settings are kept. For recovering deleted
+    // entries without disturbing anything written or changed since the backup
+    Merge,
+}
+
+// An entry whose id is in both the backup and the journal but whose text differs; the
+// journal's version is kept and the backup's is stored as a conflict to resolve
+#[derive(Debug, Clone, Serialize)]
+pub struct RestoreConflict {
+    pub entry_id: String,
+    pub title: Option<String>,
+    pub current_updated_at: String,
+    pub backup_updated_at: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BackupImportReport {
+    pub entries_imported: u32,
+    // Already present, by id or by content (conflicts included)
+    pub entries_skipped: u32,
+    pub conflicts: Vec<RestoreConflict>,
+    // Settings whose value the backup changed
+    pub settings_changed: Vec<String>,
+    pub conversations_imported: u32,
+    pub summaries_imported: u32,
+    pub templates_imported: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BackupExportReport {
+    pub path: String,
+    pub entries: u32,
+    pub conversations: u32,
+}
+
+// Secrets, the LAN sync device id and automation hooks stay with the device
+fn is_portable(key: &str) -> bool {
+    !crate::secrets::is_secret(key) && key != crate::lan_sync::DEVICE_ID_KEY && !crate::hooks::SETTINGS.contains(&key)
+}
+
+fn non_secret_settings(conn: &Connection) -> Result<BTreeMap<String, String>> {
+    let mut stmt = conn.prepare("SELECT key, value FROM settings ORDER BY key")?;
+    let settings = stmt
+        .query_map([], |r| Ok((r.get::<_, String>(0)?, r.get::<_, String>(1)?)))?
+        .collect::<rusqlite::Result<Vec<_>>>()?
+        .into_iter()
+        .filter(|(k, _)| is_portable(k))
+        .collect();
+    Ok(settings)
+}
+
+// Writes settings, skipping secrets and other device-local ones; returns the keys whose value changed. Without `overwrite`
+// only settings the journal doesn't have yet are written
+fn restore_settings(conn: &Connection, settings: &BTreeMap<String, String>, overwrite: bool) -> Result<Vec<String>> {
+    let mut changed = Vec::new();
+    for (key, value) in settings {
+        if !is_portable(key) {
+            continue;
+        }
+        let current: Option<String> = conn
+            .query_row("SELECT value FROM settings WHERE key = ?1", params![key], |r| r.get(0))
+            .optional()?;
+        if current.is_some() && !overwrite {
+            continue;
+        }
+        if current.as_ref() != Some(value) {
+            conn.execute("INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)", params![key, value])?;
+            changed.push(key.clone());
+        }
+    }
+    Ok(changed)
+}
+
+pub fn collect(conn: &Connection, filter: &EntryFilter) -> Result<Backup> {
+    let entries = collect_entries(conn, filter)?;
+    let mut backup = Backup {
+        format: FORMAT.to_string(),
+        format_version: FORMAT_VERSION,
+        exported_at: chrono::Utc::now().to_rfc3339(),
+        schema_version: crate::migrations::current_version(conn)?,
+        entries,
+        settings: BTreeMap::new(),
+        conversations: Vec::new(),
+        summaries: Vec::new(),
+        search_history: Vec::new(),
+        templates: Vec::new(),
+    };
+    if !filter.is_empty() {
+        return Ok(backup);
+    }
+
+    backup.settings = non_secret_settings(conn)?;
+    backup.templates = crate::templates::list(conn)?;
+
+    let mut messages: BTreeMap<String, Vec<BackupMessage>> = BTreeMap::new();
+    let mut stmt = conn.prepare(
+        "SELECT conversation_id, id, role, content, citations, created_at
+         FROM conversation_messages ORDER BY conversation_id, created_at, rowid",
+    )?;
+    for row in stmt.query_map([], |r| {
+        let citations: Option<String> = r.get(4)?;
+        Ok((
+            r.get::<_, String>(0)?,
+            BackupMessage {
+                id: r.get(1)?,
+                role: r.get(2)?,
+                content: r.get(3)?,
+                citations: citations.map(|c| serde_json::from_str(&c).unwrap_or(serde_json::Value::String(c))),
+                created_at: r.get(5)?,
+            },
+        ))
+    })? {
+        let (conversation, message) = row?;
+        messages.entry(conversation).or_default().push(message);
+    }
+    let mut stmt = conn.prepare("SELECT id, title, created_at, updated_at FROM conversations ORDER BY created_at, id")?;
+    backup.conversations = stmt
+        .query_map([], |r| {
+            let id: String = r.get(0)?;
+            Ok(BackupConversation {
+                messages: messages.remove(&id).unwrap_or_default(),
+                id,
+                title: r.get(1)?,
+                created_at: r.get(2)?,
+                updated_at: r.get(3)?,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    let mut stmt = conn.prepare(
+        "SELECT period_start, period_end, granularity, summary, model, entry_count, source_hash, created_at
+         FROM summaries ORDER BY period_start, granularity",
+    )?;
+    backup.summaries = stmt
+        .query_map([], |r| {
+            Ok(BackupSummary {
+                period_start: r.get(0)?,
+                period_end: r.get(1)?,
+                granularity: r.get(2)?,
+                summary: r.get(3)?,
+                model: r.get(4)?,
+                entry_count: r.get(5)?,
+                source_hash: r.get(6)?,
+                created_at: r.get(7)?,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    let mut stmt = conn.prepare("SELECT query, use_count, last_used_at FROM search_history ORDER BY last_used_at")?;
+    backup.search_history = stmt
+        .query_map([], |r| Ok(BackupSearch { query: r.get(0)?, use_count: r.get(1)?, last_used_at: r.get(2)? }))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    Ok(backup)
+}
+
+// Entries matching the filter with their tags, oldest first; also what LAN sync exchanges
+pub(crate) fn collect_entries(conn: &Connection, filter: &EntryFilter) -> Result<Vec<BackupEntry>> {
+    let mut tags: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    let mut stmt = conn.prepare("SELECT entry_id, tag FROM entry_tags ORDER BY entry_id, tag")?;
+    for row in stmt.query_map([], |r| Ok((r.get::<_, String>(0)?, r.get::<_, String>(1)?)))? {
+        let (id, tag) = row?;
+        tags.entry(id).or_default().push(tag);
+    }
+
+    let (conditions, values) = filter.to_sql(1)?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT e.id, e.title, e.body, e.entry_date, e.entry_timezone, e.source_path, e.source_type, e.text_hash,
+                e.created_at, e.updated_at, e.sentiment, e.language, e.body_sealed
+         FROM entries e WHERE 1 = 1{} ORDER BY e.entry_date, e.id",
+        conditions
+    ))?;
+    let entries = stmt
+        .query_map(rusqlite::params_from_iter(values), |r| {
+            Ok(BackupEntry {
+                id: r.get(0)?,
+                title: r.get(1)?,
+                body: r.get(2)?,
+                entry_date: r.get(3)?,
+                entry_timezone: r.get(4)?,
+                source_path: r.get(5)?,
+                source_type: r.get(6)?,
+                text_hash: r.get(7)?,
+                created_at: r.get(8)?,
+                updated_at: r.get(9)?,
+                sentiment: r.get(10)?,
+                language: r.get(11)?,
+                tags: Vec::new(),
+                body_sealed: r
+                    .get::<_, Option<Vec<u8>>>(12)?
+                    .map(|b| base64::engine::general_purpose::STANDARD.encode(b)),
+            })
+        })?
+        .map(|e| {
+            e.map(|mut e| {
+                e.tags = tags.remove(&e.id).unwrap_or_default();
+                e
+            })
+        })
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(entries)
+}
+
+// Merges a backup into the database in one transaction. Entries already present (same id or
+// same content) are skipped; settings from the backup replace local values.
+pub fn restore(conn: &mut Connection, backup: &Backup, mode: RestoreMode) -> Result<BackupImportReport> {
+    if backup.format != FORMAT {
+        anyhow::bail!("Not a Journal Reader backup");
+    }
+    if backup.format_version > FORMAT_VERSION {
+        anyhow::bail!("Backup format {} is newer than this version supports ({})", backup.format_version, FORMAT_VERSION);
+    }
+    let mut report = BackupImportReport::default();
+    let tx = conn.transaction()?;
+    for entry in &backup.entries {
+        let by_id: Option<(String, Option<String>, String)> = tx
+            .query_row("SELECT text_hash, title, updated_at FROM entries WHERE id = ?1", params![entry.id], |r| {
+                Ok((r.get(0)?, r.get(1)?, r.get(2)?))
+            })
+            .optional()?;
+        if let Some((text_hash, title, updated_at)) = by_id {
+            if text_hash != entry.text_hash {
+                // Kept for review rather than dropped (conflicts.rs)
+                crate::conflicts::record(&tx, entry, &format!("backup:{}", backup.exported_at))?;
+                report.conflicts.push(RestoreConflict {
+                    entry_id: entry.id.clone(),
+                    title,
+                    current_updated_at: updated_at,
+                    backup_updated_at: entry.updated_at.clone(),
+                });
+            }
+            report.entries_skipped += 1;
+            continue;
+        }
+        let same_text: Option<i64> = tx
+            .query_row("SELECT 1 FROM entries WHERE text_hash = ?1", params![entry.text_hash], |r| r.get(0))
+            .optional()?;
+        if same_text.is_some() {
+            report.entries_skipped += 1;
+            continue;
+        }
+        let body_sealed = match &entry.body_sealed {
+            Some(b) => Some(base64::engine::general_purpose::STANDARD.decode(b).context("Invalid sealed body in backup")?),
+            None => None,
+        };
+        tx.execute(
+            "INSERT INTO entries (id, title, body, entry_date, entry_timezone, source_path, source_type, text_hash,
+                                  created_at, updated_at, sentiment, language, sensitive, body_sealed)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+            params![
+                entry.id,
+                entry.title,
+                entry.body,
+                entry.entry_date,
+                entry.entry_timezone,
+                entry.source_path,
+                entry.source_type,
+                entry.text_hash,
+                entry.created_at,
+                entry.updated_at,
+                entry.sentiment,
+                // Backups from before language detection carry none
+                entry.language.clone().or_else(|| crate::import::detect_language(&entry.body)),
+                body_sealed.is_some(),
+                body_sealed,
+            ],
+        )?;
+        tx.execute(
+            "INSERT INTO entries_fts (title, body, entry_id) VALUES (?1, ?2, ?3)",
+            params![entry.title.clone().unwrap_or_default(), entry.body, entry.id],
+        )?;
+        crate::database::add_entry_tags(&tx, &entry.id, &entry.tags)?;
+        report.entries_imported += 1;
+    }
+
+    report.settings_changed = restore_settings(&tx, &backup.settings, mode == RestoreMode::Full)?;
+    report.templates_imported = crate::templates::restore(&tx, &backup.templates, false)?;
+
+    for conversation in &backup.conversations {
+        let inserted = tx.execute(
+            "INSERT OR IGNORE INTO conversations (id, title, created_at, updated_at) VALUES (?1, ?2, ?3, ?4)",
+            params![conversation.id, conversation.title, conversation.created_at, conversation.updated_at],
+        )?;
+        if inserted == 0 {
+            continue;
+        }
+        for message in &conversation.messages {
+            let citations = message.citations.as_ref().map(|c| match c {
+                serde_json::Value::String(s) => s.clone(),
+                other => other.to_string(),
+            });
+            tx.execute(
+                "INSERT OR IGNORE INTO conversation_messages (id, conversation_id, role, content, citations, created_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![message.id, conversation.id, message.role, message.content, citations, message.created_at],
+            )?;
+        }
+        report.conversations_imported += 1;
+    }
+
+    for s in &backup.summaries {
+        report.summaries_imported += tx.execute(
+            "INSERT OR IGNORE INTO summaries (period_start, period_end, granularity, summary, model, entry_count, source_hash, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![s.period_start, s.period_end, s.granularity, s.summary, s.model, s.entry_count, s.source_hash, s.created_at],
+        )? as u32;
+    }
+
+    for h in &backup.search_history {
+        tx.execute(
+            "INSERT INTO search_history (query, use_count, last_used_at) VALUES (?1, ?2, ?3)
+             ON CONFLICT(query) DO UPDATE SET use_count = MAX(use_count, excluded.use_count),
+                                              last_used_at = MAX(last_used_at, excluded.last_used_at)",
+            params![h.query, h.use_count, h.last_used_at],
+        )?;
+    }
+    tx.commit()?;
+    Ok(report)
+}
+
+pub async fn export_json(app_handle: &AppHandle, path: &str, filter: &EntryFilter) -> Result<BackupExportReport> {
+    let conn = crate::database::open_conn(app_handle)?;
+    let backup = collect(&conn, filter)?;
+    if backup.entries.is_empty() && !filter.is_empty() {
+        anyhow::bail!("No entries match the filter");
+    }
+    std::fs::write(path, serde_json::to_vec_pretty(&backup)?)?;
+    Ok(BackupExportReport {
+        path: path.to_string(),
+        entries: backup.entries.len() as u32,
+        conversations: backup.conversations.len() as u32,
+    })
+}
+
+pub async fn export_settings(app_handle: &AppHandle, path: &str) -> Result<u32> {
+    let conn = crate::database::open_conn(app_handle)?;
+    let file = SettingsFile {
+        format: SETTINGS_FORMAT.to_string(),
+        format_version: FORMAT_VERSION,
+        exported_at: chrono::Utc::now().to_rfc3339(),
+        settings: non_secret_settings(&conn)?,
+        templates: crate::templates::list(&conn)?,
+    };
+    std::fs::write(path, serde_json::to_vec_pretty(&file)?)?;
+    Ok(file.settings.len() as u32)
+}
+
+// Reads a settings file (or the settings of a full backup); returns the keys whose value changed
+pub async fn import_settings(app_handle: &AppHandle, path: &str) -> Result<Vec<String>> {
+    let text = std::fs::read_to_string(path)?;
+    let value: serde_json::Value = serde_json::from_str(&text).map_err(|e| anyhow::anyhow!("Invalid settings file: {}", e))?;
+    let format = value.get("format").and_then(|f| f.as_str()).unwrap_or_default();
+    if format != SETTINGS_FORMAT && format != FORMAT {
+        anyhow::bail!("Not a Journal Reader settings file");
+    }
+    let version = value.get("format_version").and_then(|v| v.as_u64()).unwrap_or(0);
+    if version > FORMAT_VERSION as u64 {
+        anyhow::bail!("Settings format {} is newer than this version supports ({})", version, FORMAT_VERSION);
+    }
+    let settings: BTreeMap<String, String> = match value.get("settings") {
+        Some(s) => serde_json::from_value(s.clone()).map_err(|e| anyhow::anyhow!("Invalid settings: {}", e))?,
+        None => BTreeMap::new(),
+    };
+    // Templates travel with the settings; ones with the same id are replaced
+    let templates: Vec<crate::templates::EntryTemplate> = match value.get("templates") {
+        Some(t) => serde_json::from_value(t.clone()).map_err(|e| anyhow::anyhow!("Invalid templates: {}", e))?,
+        None => Vec::new(),
+    };
+    let conn = crate::database::open_conn(app_handle)?;
+    crate::templates::restore(&conn, &templates, true)?;
+    restore_settings(&conn, &settings, true)
+}
+
+pub async fn import_json(app_handle: &AppHandle, path: &str, mode: RestoreMode) -> Result<BackupImportReport> {
+    let text = std::fs::read_to_string(path)?;
+    let backup: Backup = serde_json::from_str(&text).map_err(|e| anyhow::anyhow!("Invalid backup file: {}", e))?;
+    let mut conn = crate::database::open_conn(app_handle)?;
+    restore(&mut conn, &backup, mode)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seeded() -> Connection {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::migrations::run_migrations(&mut conn).unwrap();
+        conn.execute_batch(
+            r#"
+            INSERT INTO entries (id, title, body, entry_date, entry_timezone, source_path, source_type, text_hash, created_at, updated_at, sentiment)
+                VALUES ('a', 'Day one', 'Walked to the lake.', '2021-03-05T12:00:00+00:00', 'UTC', '/j/a.txt', 'txt', 'ha', '2021-03-05T13:00:00+00:00', '2021-03-05T13:00:00+00:00', 0.4);
+            INSERT INTO entries_fts (title, body, entry_id) VALUES ('Day one', 'Walked to the lake.', 'a');
+            INSERT INTO entry_tags (entry_id, tag) VALUES ('a', 'walks'), ('a', 'lake');
+            INSERT INTO settings (key, value) VALUES ('ai_provider', 'ollama'), ('openai_api_key', 'sk-secret');
+            INSERT INTO conversations (id, title, created_at, updated_at) VALUES ('c1', 'Lake?', '2021-04-01T00:00:00Z', '2021-04-01T00:00:01Z');
+            INSERT INTO conversation_messages (id, conversation_id, role, content, citations, created_at)
+                VALUES ('m1', 'c1', 'user', 'When was the lake walk?', NULL, '2021-04-01T00:00:00Z'),
+                       ('m2', 'c1', 'assistant', 'March 5.', '[{"entry_id":"a"}]', '2021-04-01T00:00:01Z');
+            INSERT INTO search_history (query, use_count, last_used_at) VALUES ('lake', 3, '2021-04-02T00:00:00Z');
+            "#,
+        )
+        .unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let backup = collect(&seeded(), &EntryFilter::default()).unwrap();
+        assert!(!backup.settings.contains_key("openai_api_key"));
+        assert_eq!(backup.entries[0].tags, ["lake", "walks"]);
+        assert_eq!(backup.conversations[0].messages[1].citations, Some(serde_json::json!([{ "entry_id": "a" }])));
+
+        let json = serde_json::to_string(&backup).unwrap();
+        let mut fresh = Connection::open_in_memory().unwrap();
+        crate::migrations::run_migrations(&mut fresh).unwrap();
+        let report = restore(&mut fresh, &serde_json::from_str(&json).unwrap(), RestoreMode::Full).unwrap();
+        assert_eq!((report.entries_imported, report.conversations_imported), (1, 1));
+        assert_eq!(report.settings_changed, ["ai_provider"]);
+        let again = collect(&fresh, &EntryFilter::default()).unwrap();
+        assert_eq!(serde_json::to_value(&again.entries).unwrap(), serde_json::to_value(&backup.entries).unwrap());
+        assert_eq!(serde_json::to_value(&again.conversations).unwrap(), serde_json::to_value(&backup.conversations).unwrap());
+        let hits: i64 = fresh.query_row("SELECT COUNT(*) FROM entries_fts WHERE entries_fts MATCH 'lake'", [], |r| r.get(0)).unwrap();
+        assert_eq!(hits, 1);
+
+        // Restoring twice adds nothing
+        let report = restore(&mut fresh, &backup, RestoreMode::Full).unwrap();
+        assert_eq!((report.entries_imported, report.entries_skipped, report.conversations_imported), (0, 1, 0));
+        assert!(report.settings_changed.is_empty());
+        let secret: Option<String> = fresh
+            .query_row("SELECT value FROM settings WHERE key = 'openai_api_key'", [], |r| r.get(0))
+            .optional()
+            .unwrap();
+        assert_eq!(secret, None);
+    }
+
+    #[test]
+    fn test_hooks_are_neither_exported_nor_restored() {
+        let conn = seeded();
+        conn.execute("INSERT INTO settings (key, value) VALUES ('hook_webhook_url', 'https://mine.example/hook')", []).unwrap();
+        assert!(!collect(&conn, &EntryFilter::default()).unwrap().settings.contains_key("hook_webhook_url"));
+
+        let mut backup = collect(&seeded(), &EntryFilter::default()).unwrap();
+        backup.settings.insert("hook_script".into(), "/tmp/evil.sh".into());
+        backup.settings.insert("hook_webhook_url".into(), "https://attacker.example/collect".into());
+        backup.settings.insert("hook_events".into(), "entry-created".into());
+        let mut fresh = Connection::open_in_memory().unwrap();
+        crate::migrations::run_migrations(&mut fresh).unwrap();
+        let report = restore(&mut fresh, &backup, RestoreMode::Full).unwrap();
+        assert_eq!(report.settings_changed, ["ai_provider"]);
+        let hooks: i64 = fresh.query_row("SELECT COUNT(*) FROM settings WHERE key LIKE 'hook_%'", [], |r| r.get(0)).unwrap();
+        assert_eq!(hooks, 0);
+    }
+
+    #[test]
+    fn test_merge_adds_missing_and_reports_conflicts() {
+        let conn = seeded();
+        let backup = collect(&conn, &EntryFilter::default()).unwrap();
+        let mut current = seeded();
+        current
+            .execute_batch(
+                r#"
+                UPDATE entries SET body = 'Walked around the lake twice.', text_hash = 'ha2', updated_at = '2021-06-01T00:00:00+00:00' WHERE id = 'a';
+                UPDATE settings SET value = 'openai' WHERE key = 'ai_provider';
+                "#,
+            )
+            .unwrap();
+        let mut deleted = backup.clone();
+        deleted.entries.push(BackupEntry { id: "b".into(), text_hash: "hb".into(), body: "Deleted by mistake.".into(), ..backup.entries[0].clone() });
+        deleted.settings.insert("birthday".into(), "1990-04-01".into());
+
+        let report = restore(&mut current, &deleted, RestoreMode::Merge).unwrap();
+        assert_eq!((report.entries_imported, report.entries_skipped), (1, 1));
+        assert_eq!(report.conflicts.len(), 1);
+        assert_eq!(report.conflicts[0].entry_id, "a");
+        assert_eq!(report.conflicts[0].current_updated_at, "2021-06-01T00:00:00+00:00");
+        // The edited entry and the changed setting are kept; only the missing setting is added
+        let body: String = current.query_row("SELECT body FROM entries WHERE id = 'a'", [], |r| r.get(0)).unwrap();
+        assert_eq!(body, "Walked around the lake twice.");
+        assert_eq!(report.settings_changed, ["birthday"]);
+        let provider: String = current.query_row("SELECT value FROM settings WHERE key = 'ai_provider'", [], |r| r.get(0)).unwrap();
+        assert_eq!(provider, "openai");
+    }
+
+    #[test]
+    fn test_filtered_export_has_only_matching_entries() {
+        let conn = seeded();
+        let travel = EntryFilter { tags: Some(vec!["travel".into()]), ..Default::default() };
+        assert!(collect(&conn, &travel).unwrap().entries.is_empty());
+        let lake = EntryFilter { tags: Some(vec!["lake".into()]), date_from: Some("2021-01-01".into()), ..Default::default() };
+        let backup = collect(&conn, &lake).unwrap();
+        assert_eq!(backup.entries.len(), 1);
+        assert!(backup.settings.is_empty() && backup.conversations.is_empty() && backup.search_history.is_empty());
+    }
+}
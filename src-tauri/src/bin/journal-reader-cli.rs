@@ -0,0 +1,3 @@
+fn main() {
+    std::process::exit(journal_reader_lib::cli::main())
+}
@@ -0,0 +1,228 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, FixedOffset, NaiveDateTime, Utc};
+use tauri::AppHandle;
+
+use crate::app_import::{html_to_text, local_to_utc, normalize_tags, AppEntry, AppImportReport};
+use crate::import::FileType;
+
+// WordPress and Blogger import, for journals kept as a (usually private) blog.
+//
+// WordPress exports WXR (Tools → Export): RSS with `wp:` elements, one `<item>` per post, page
+// or attachment. Only posts are imported, in any status but trash; the body is
+// `content:encoded` and categories and tags both become tags. `wp:post_date` is the blog's local
+// time and `wp:post_date_gmt` the same moment in UTC, so their difference gives the time zone
+// (drafts have no GMT date and use the import's).
+//
+// Blogger exports Atom (Settings → Back up content, or Takeout), with comments, settings and the
+// template mixed in; posts are the entries whose kind category (older exports) or `blogger:type`
+// (Takeout) says so. `published` carries its own offset. Labels become tags.
+
+pub enum Blog {
+    WordPress,
+    Blogger,
+}
+
+pub(crate) fn local(node: &roxmltree::Node, name: &str) -> bool {
+    node.is_element() && node.tag_name().name() == name
+}
+
+// Text of the first child called `name` whose namespace contains `ns` ("" for any)
+pub(crate) fn child_text(node: &roxmltree::Node, ns: &str, name: &str) -> String {
+    node.children()
+        .find(|c| local(c, name) && (ns.is_empty() || c.tag_name().namespace().is_some_and(|n| n.contains(ns))))
+        .map(|c| c.children().filter(|d| d.is_text()).filter_map(|d| d.text()).collect::<String>())
+        .map(|t| t.trim().to_string())
+        .unwrap_or_default()
+}
+
+fn wp_time(time: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(time.trim(), "%Y-%m-%d %H:%M:%S").ok()
+}
+
+// "+02:00" for the offset between a local and a UTC time, if it is a whole minute
+fn offset_name(local: NaiveDateTime, utc: NaiveDateTime) -> Option<String> {
+    let seconds = (local - utc).num_seconds();
+    let offset = FixedOffset::east_opt(i32::try_from(seconds).ok()?)?;
+    (seconds % 60 == 0).then(|| offset.to_string())
+}
+
+fn parse_wordpress(doc: &roxmltree::Document, source: &str, timezone: &str) -> Result<Vec<AppEntry>> {
+    let mut entries = Vec::new();
+    for item in doc.descendants().filter(|n| local(n, "item")) {
+        let post_type = child_text(&item, "wordpress.org/export", "post_type");
+        let status = child_text(&item, "wordpress.org/export", "status");
+        if post_type != "post" || status == "trash" || status == "auto-draft" {
+            continue;
+        }
+        let id = child_text(&item, "wordpress.org/export", "post_id");
+        let post_date = wp_time(&child_text(&item, "wordpress.org/export", "post_date"));
+        let post_date_gmt = wp_time(&child_text(&item, "wordpress.org/export", "post_date_gmt"));
+        let (created, zone) = match (post_date, post_date_gmt) {
+            (Some(local), Some(utc)) => (utc.and_utc(), offset_name(local, utc).unwrap_or_else(|| "UTC".to_string())),
+            (Some(local), None) => (local_to_utc(local, timezone), timezone.to_string()),
+            _ => {
+                let pub_date = child_text(&item, "", "pubDate");
+                let date = DateTime::parse_from_rfc2822(&pub_date).with_context(|| format!("Post {} has no valid date", id))?;
+                (date.with_timezone(&Utc), date.offset().to_string())
+            }
+        };
+        let categories: Vec<String> = item.children().filter(|c| local(c, "category")).filter_map(|c| c.text()).map(html_to_text).collect();
+        entries.push(AppEntry {
+            source: format!("{}#{}", source, id),
+            title: Some(html_to_text(&child_text(&item, "", "title"))).filter(|t| !t.is_empty()),
+            body: html_to_text(&child_text(&item, "purl.org/rss/1.0/modules/content", "encoded")),
+            tags: normalize_tags(categories.iter().map(String::as_str).filter(|c| !c.eq_ignore_ascii_case("uncategorized"))),
+            created,
+            timezone: zone,
+            sentiment: None,
+        });
+    }
+    Ok(entries)
+}
+
+fn is_blogger_post(entry: &roxmltree::Node) -> bool {
+    let blogger_type = child_text(entry, "blogger", "type");
+    if !blogger_type.is_empty() {
+        return blogger_type.eq_ignore_ascii_case("post");
+    }
+    let kind = entry
+        .children()
+        .filter(|c| local(c, "category") && c.attribute("scheme").is_some_and(|s| s.ends_with("/g/2005#kind")))
+        .find_map(|c| c.attribute("term"));
+    kind.is_none_or(|k| k.ends_with("#post"))
+}
+
+fn parse_blogger(doc: &roxmltree::Document, source: &str) -> Result<Vec<AppEntry>> {
+    let mut entries = Vec::new();
+    for entry in doc.root_element().children().filter(|n| local(n, "entry")) {
+        if !is_blogger_post(&entry) || child_text(&entry, "blogger", "status").eq_ignore_ascii_case("deleted") {
+            continue;
+        }
+        let id = child_text(&entry, "", "id");
+        let published = child_text(&entry, "", "published");
+        let date = DateTime::parse_from_rfc3339(&published).with_context(|| format!("Post {} has no valid published date", id))?;
+        // Labels; the kind category uses a scheme, labels have the atom/ns# scheme or none
+        let labels: Vec<&str> = entry
+            .children()
+            .filter(|c| local(c, "category") && c.attribute("scheme").is_none_or(|s| s.ends_with("/atom/ns#")))
+            .filter_map(|c| c.attribute("term"))
+            .collect();
+        entries.push(AppEntry {
+            source: format!("{}#{}", source, id.rsplit('-').next().unwrap_or(&id)),
+            title: Some(html_to_text(&child_text(&entry, "", "title"))).filter(|t| !t.is_empty()),
+            body: html_to_text(&child_text(&entry, "", "content")),
+            tags: normalize_tags(labels),
+            created: date.with_timezone(&Utc),
+            timezone: date.offset().to_string(),
+            sentiment: None,
+        });
+    }
+    Ok(entries)
+}
+
+// Which export this is, from the root element
+pub fn parse_export(xml: &str, source: &str, timezone: &str) -> Result<(Blog, Vec<AppEntry>)> {
+    let doc = roxmltree::Document::parse(xml).context("Not a readable blog export")?;
+    let root = doc.root_element();
+    if local(&root, "rss") {
+        Ok((Blog::WordPress, parse_wordpress(&doc, source, timezone)?))
+    } else if local(&root, "feed") {
+        Ok((Blog::Blogger, parse_blogger(&doc, source)?))
+    } else {
+        anyhow::bail!("Not a WordPress (WXR) or Blogger (Atom) export")
+    }
+}
+
+pub async fn import_exports(app_handle: &AppHandle, paths: &[String], timezone: &str) -> Result<AppImportReport> {
+    let mut report = AppImportReport::default();
+    for file in crate::app_import::files_with_extension(paths, &["xml", "atom"]) {
+        let source = file.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        let parsed = std::fs::read_to_string(&file).map_err(anyhow::Error::from).and_then(|xml| parse_export(&xml, &source, timezone));
+        match parsed {
+            Ok((blog, entries)) => {
+                let file_type = match blog {
+                    Blog::WordPress => FileType::WordPress,
+                    Blog::Blogger => FileType::Blogger,
+                };
+                crate::app_import::save_all(app_handle, entries, file_type, &mut report).await?
+            }
+            Err(e) => report.error(format!("{}: {}", file.display(), e)),
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_wordpress() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<rss version="2.0" xmlns:content="http://purl.org/rss/1.0/modules/content/" xmlns:excerpt="http://wordpress.org/export/1.2/excerpt/" xmlns:wp="http://wordpress.org/export/1.2/">
+<channel>
+  <title>My diary</title>
+  <item>
+    <title>First snow</title>
+    <pubDate>Sat, 14 Dec 2019 20:10:00 +0000</pubDate>
+    <content:encoded><![CDATA[<p>It snowed all night.</p><p>Built a snowman &amp; went sledding.</p>]]></content:encoded>
+    <excerpt:encoded><![CDATA[]]></excerpt:encoded>
+    <wp:post_id>42</wp:post_id>
+    <wp:post_date><![CDATA[2019-12-14 21:10:00]]></wp:post_date>
+    <wp:post_date_gmt><![CDATA[2019-12-14 20:10:00]]></wp:post_date_gmt>
+    <wp:status><![CDATA[private]]></wp:status>
+    <wp:post_type><![CDATA[post]]></wp:post_type>
+    <category domain="category" nicename="winter"><![CDATA[Winter]]></category>
+    <category domain="post_tag" nicename="kids"><![CDATA[kids]]></category>
+    <category domain="category" nicename="uncategorized"><![CDATA[Uncategorized]]></category>
+  </item>
+  <item>
+    <title>About</title>
+    <content:encoded><![CDATA[About me]]></content:encoded>
+    <wp:post_id>2</wp:post_id>
+    <wp:post_date>2019-01-01 00:00:00</wp:post_date>
+    <wp:post_type>page</wp:post_type>
+  </item>
+</channel>
+</rss>"#;
+        let (_, entries) = parse_export(xml, "diary.WordPress.xml", "UTC").unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].title.as_deref(), Some("First snow"));
+        assert_eq!(entries[0].body, "It snowed all night.\n\nBuilt a snowman & went sledding.");
+        assert_eq!(entries[0].tags, ["kids", "winter"]);
+        assert_eq!(entries[0].created.to_rfc3339(), "2019-12-14T20:10:00+00:00");
+        assert_eq!(entries[0].timezone, "+01:00");
+        assert_eq!(entries[0].source, "diary.WordPress.xml#42");
+    }
+
+    #[test]
+    fn test_parse_blogger() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom">
+  <entry>
+    <id>tag:blogger.com,1999:blog-1.settings.BLOG_NAME</id>
+    <published>2010-01-01T00:00:00.000-08:00</published>
+    <category scheme="http://schemas.google.com/g/2005#kind" term="http://schemas.google.com/blogger/2008/kind#settings"/>
+    <content type="text">My blog</content>
+  </entry>
+  <entry>
+    <id>tag:blogger.com,1999:blog-1.post-555</id>
+    <published>2010-05-01T10:00:00.000-07:00</published>
+    <category scheme="http://schemas.google.com/g/2005#kind" term="http://schemas.google.com/blogger/2008/kind#post"/>
+    <category scheme="http://www.blogger.com/atom/ns#" term="Garden"/>
+    <title type="text">Tomatoes</title>
+    <content type="html">Planted &lt;i&gt;six&lt;/i&gt; tomatoes.&lt;br /&gt;Watered them.</content>
+  </entry>
+</feed>"#;
+        let (_, entries) = parse_export(xml, "blog.xml", "UTC").unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].title.as_deref(), Some("Tomatoes"));
+        assert_eq!(entries[0].body, "Planted six tomatoes.\nWatered them.");
+        assert_eq!(entries[0].tags, ["garden"]);
+        assert_eq!(entries[0].created.to_rfc3339(), "2010-05-01T17:00:00+00:00");
+        assert_eq!(entries[0].timezone, "-07:00");
+        assert_eq!(entries[0].source, "blog.xml#555");
+
+        assert!(parse_export("<livejournal/>", "x.xml", "UTC").is_err());
+    }
+}
@@ -0,0 +1,316 @@
+use anyhow::Result;
+use rusqlite::{params, Connection};
+use tauri::AppHandle;
+
+use crate::ai::ContextEntry;
+use crate::quantize::Quantization;
+
+// Long entries split into overlapping chunks for retrieval.
+//
+// A 10k-word entry embedded as one vector matches everything a little and nothing well. Entries
+// are cut into chunks of about `chunk_size` tokens, ending at a sentence where one is close, and
+// each chunk repeats the last `chunk_overlap` tokens of the one before so a passage on a boundary
+// is whole in one of them. Chunks are stored as byte ranges of the body, tagged with the
+// text_hash they were cut from and an embedding key (`provider:model#size/overlap`), so changing
+// either the model or the chunk settings makes entries pending again.
+
+pub const DEFAULT_CHUNK_SIZE: usize = 384;
+pub const DEFAULT_CHUNK_OVERLAP: usize = 64;
+// Changing either setting re-chunks and re-embeds every entry
+pub const CHUNK_SETTINGS: &[&str] = &["chunk_size", "chunk_overlap"];
+// Chunks of one entry handed to the model as RAG context
+const CONTEXT_CHUNKS: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChunkConfig {
+    pub size: usize,
+    pub overlap: usize,
+}
+
+impl ChunkConfig {
+    pub fn load(conn: &Connection) -> Self {
+        let read = |key: &str, default: usize| {
+            crate::database::read_setting(conn, key).and_then(|v| v.trim().parse().ok()).unwrap_or(default)
+        };
+        let size = read("chunk_size", DEFAULT_CHUNK_SIZE).clamp(64, 8192);
+        let overlap = read("chunk_overlap", DEFAULT_CHUNK_OVERLAP).min(size / 2);
+        ChunkConfig { size, overlap }
+    }
+}
+
+// Byte range of the entry body
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Chunk {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Chunk {
+    pub fn text<'a>(&self, body: &'a str) -> &'a str {
+        body.get(self.start..self.end).unwrap_or("").trim()
+    }
+}
+
+// Chunks covering `text`; text that fits in one chunk is a single chunk, even when empty
+pub fn split(text: &str, config: ChunkConfig) -> Vec<Chunk> {
+    let pieces: Vec<(usize, usize)> = crate::tokens::pieces(text).collect();
+    if pieces.iter().map(|p| p.1).sum::<usize>() <= config.size {
+        return vec![Chunk { start: 0, end: text.len() }];
+    }
+    let piece_end = |i: usize| pieces.get(i + 1).map_or(text.len(), |p| p.0);
+    let ends_sentence = |i: usize| {
+        let piece = &text[pieces[i].0..piece_end(i)];
+        if piece.trim().is_empty() {
+            return piece.contains('\n');
+        }
+        piece.trim_end_matches(['"', '\'', ')', '”', '’']).ends_with(['.', '!', '?'])
+    };
+
+    let mut chunks = Vec::new();
+    let mut first = 0;
+    loop {
+        // Take up to `size` tokens, but always at least one piece
+        let mut end = first;
+        let mut used = 0;
+        while end < pieces.len() && (end == first || used + pieces[end].1 <= config.size) {
+            used += pieces[end].1;
+            end += 1;
+        }
+        if end < pieces.len() {
+            // Back up to a sentence end within the last 30% of the chunk, if there is one
+            let mut e = end;
+            while e > first + 1 && used * 10 >= config.size * 7 {
+                if ends_sentence(e - 1) {
+                    end = e;
+                    break;
+                }
+                used -= pieces[e - 1].1;
+                e -= 1;
+            }
+        }
+        chunks.push(Chunk { start: pieces[first].0, end: piece_end(end - 1) });
+        if end == pieces.len() {
+            break;
+        }
+
+        // The next chunk starts `overlap` tokens back, at a sentence start if one is in reach
+        let mut next = end;
+        let mut overlap = 0;
+        while next > first + 1 && overlap + pieces[next - 1].1 <= config.overlap {
+            next -= 1;
+            overlap += pieces[next].1;
+        }
+        if let Some(k) = (next..end).find(|&k| ends_sentence(k - 1)) {
+            next = k;
+        }
+        first = next;
+    }
+    chunks
+}
+
+// Replaces the stored chunks of an entry, in the storage format currently set
+pub(crate) fn store(
+    conn: &Connection,
+    entry_id: &str,
+    text_hash: &str,
+    embedding_key: &str,
+    chunks: &[(Chunk, Vec<f32>)],
+) -> Result<()> {
+    let format = Quantization::load(conn);
+    conn.execute("DELETE FROM entry_chunks WHERE entry_id = ?1", params![entry_id])?;
+    let mut insert = conn.prepare_cached(
+        r#"INSERT INTO entry_chunks (entry_id, chunk_index, start_offset, end_offset, text_hash, embedding, embedding_key, vector_format)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)"#,
+    )?;
+    for (i, (chunk, vector)) in chunks.iter().enumerate() {
+        insert.execute(params![
+            entry_id,
+            i as i64,
+            chunk.start as i64,
+            chunk.end as i64,
+            text_hash,
+            crate::quantize::encode(vector, format),
+            embedding_key,
+            format.as_str()
+        ])?;
+    }
+    Ok(())
+}
+
+// One row per current chunk, keyed by its entry, plus the whole-entry vector of entries embedded
+// before chunking existed (or with other chunk settings) so they stay searchable until re-embedded
+pub fn load_embeddings(conn: &Connection, model_key: &str, embedding_key: &str) -> Result<Vec<(String, Vec<f32>)>> {
+    let mut stmt = conn.prepare_cached(CURRENT_VECTORS)?;
+    let rows = stmt.query_map(params![model_key, embedding_key], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+    decode_rows(rows)
+}
+
+// The same rows for just these entries, for updating a loaded index
+pub fn load_entry_embeddings(conn: &Connection, model_key: &str, embedding_key: &str, ids: &[String]) -> Result<Vec<(String, Vec<f32>)>> {
+    let mut stmt = conn.prepare_cached(&format!("SELECT * FROM ({}) WHERE entry_id = ?3", CURRENT_VECTORS))?;
+    let mut out = Vec::new();
+    for id in ids {
+        let rows = stmt.query_map(params![model_key, embedding_key, id], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+        out.extend(decode_rows(rows)?);
+    }
+    Ok(out)
+}
+
+const CURRENT_VECTORS: &str = r#"SELECT c.entry_id AS entry_id, c.embedding, c.vector_format FROM entry_chunks c JOIN entries e ON e.id = c.entry_id
+        WHERE c.embedding_key = ?2 AND c.text_hash = e.text_hash AND c.embedding IS NOT NULL
+       UNION ALL
+       SELECT e.id, e.embedding, 'f32' FROM entries e
+        WHERE e.embedding IS NOT NULL AND e.embedding_model = ?1 AND e.embedding_text_hash = e.text_hash
+          AND NOT EXISTS (SELECT 1 FROM entry_chunks c
+                          WHERE c.entry_id = e.id AND c.embedding_key = ?2 AND c.text_hash = e.text_hash)"#;
+
+fn decode_rows(rows: impl Iterator<Item = rusqlite::Result<(String, Vec<u8>, String)>>) -> Result<Vec<(String, Vec<f32>)>> {
+    let mut out = Vec::new();
+    for r in rows {
+        let (id, bytes, format) = r?;
+        if let Some(v) = crate::quantize::decode(&bytes, &format) {
+            out.push((id, v));
+        }
+    }
+    Ok(out)
+}
+
+fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm = a.iter().map(|x| x * x).sum::<f32>().sqrt() * b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 { dot / norm } else { 0.0 }
+}
+
+// The chunks of an entry closest to `query`, merged in document order, and the single best one.
+// None when the entry is a single chunk or has no current chunks.
+fn relevant_excerpt(
+    conn: &Connection,
+    entry_id: &str,
+    body: &str,
+    embedding_key: &str,
+    query: &[f32],
+) -> Result<Option<(String, String)>> {
+    let mut stmt = conn.prepare_cached(
+        r#"SELECT c.start_offset, c.end_offset, c.embedding, c.vector_format FROM entry_chunks c JOIN entries e ON e.id = c.entry_id
+            WHERE c.entry_id = ?1 AND c.embedding_key = ?2 AND c.text_hash = e.text_hash AND c.embedding IS NOT NULL"#,
+    )?;
+    let rows = stmt.query_map(params![entry_id, embedding_key], |row| {
+        Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?, row.get::<_, Vec<u8>>(2)?, row.get::<_, String>(3)?))
+    })?;
+    let mut scored = Vec::new();
+    for r in rows {
+        let (start, end, bytes, format) = r?;
+        let chunk = Chunk { start: start as usize, end: end as usize };
+        if let Some(v) = crate::quantize::decode(&bytes, &format).filter(|_| !chunk.text(body).is_empty()) {
+            scored.push((chunk, cosine(query, &v)));
+        }
+    }
+    if scored.len() <= 1 {
+        return Ok(None);
+    }
+    scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+    scored.truncate(CONTEXT_CHUNKS);
+    let best = scored[0].0.text(body).to_string();
+
+    // Overlapping neighbours become one passage
+    let mut ranges: Vec<Chunk> = scored.into_iter().map(|(c, _)| c).collect();
+    ranges.sort_by_key(|c| c.start);
+    let mut merged: Vec<Chunk> = Vec::new();
+    for chunk in ranges {
+        match merged.last_mut() {
+            Some(last) if chunk.start <= last.end => last.end = last.end.max(chunk.end),
+            _ => merged.push(chunk),
+        }
+    }
+    let excerpt = merged.iter().map(|c| c.text(body)).collect::<Vec<_>>().join("\n...\n");
+    Ok(Some((excerpt, best)))
+}
+
+// Swaps the body of each long entry in RAG context for its chunks most relevant to `question`,
+// so the model sees the passages that matched instead of however much of the entry fits.
+// Citations still point at the entry. Leaves the context as it is if the query can't be embedded.
+pub(crate) async fn focus_context(app_handle: &AppHandle, question: &str, entries: &mut [ContextEntry]) {
+    let config = match crate::database::open_conn(app_handle) {
+        Ok(conn) => crate::embeddings::load_config(&conn),
+        Err(_) => return,
+    };
+    if !entries.iter().any(|e| crate::tokens::estimate_tokens(&e.body) > config.chunking.size) {
+        return;
+    }
+    let embed = config.embed(crate::embeddings::EmbedRole::Query, question);
+    let query = match crate::ai_audit::with_context("search query", vec![], embed).await {
+        Ok(query) => query,
+        Err(e) => {
+            tracing::warn!("query embedding failed, using whole entries: {}", e);
+            return;
+        }
+    };
+    let Ok(conn) = crate::database::open_conn(app_handle) else { return };
+    for entry in entries.iter_mut() {
+        match relevant_excerpt(&conn, &entry.entry_id, &entry.body, &config.embedding_key(), &query) {
+            Ok(Some((excerpt, best))) => {
+                entry.body = excerpt;
+                entry.snippet = best;
+            }
+            Ok(None) => {}
+            Err(e) => tracing::warn!("entry {}: {}", entry.entry_id, e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_overlaps_and_ends_at_sentences() {
+        let config = ChunkConfig { size: 64, overlap: 16 };
+        assert_eq!(split("Short entry.", config), vec![Chunk { start: 0, end: 12 }]);
+
+        let text = (1..=40).map(|i| format!("Sentence number {} is about the garden.", i)).collect::<Vec<_>>().join(" ");
+        let chunks = split(&text, config);
+        assert!(chunks.len() > 3);
+        assert_eq!(chunks[0].start, 0);
+        assert_eq!(chunks.last().unwrap().end, text.len());
+        for pair in chunks.windows(2) {
+            // Consecutive chunks overlap and every chunk ends on a sentence
+            assert!(pair[1].start < pair[0].end);
+            assert!(pair[0].text(&text).ends_with('.'));
+            assert!(crate::tokens::estimate_tokens(pair[0].text(&text)) <= config.size);
+        }
+        assert!(chunks[1].text(&text).starts_with("Sentence number"));
+    }
+
+    #[test]
+    fn test_relevant_excerpt_picks_matching_chunks() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::migrations::run_migrations(&mut conn).unwrap();
+        let body = "Garden notes. Work notes. Taxes due. More garden.";
+        conn.execute(
+            r#"INSERT INTO entries (id, body, entry_date, entry_timezone, source_path, source_type, text_hash, created_at, updated_at)
+                VALUES ('a', ?1, '2024-01-01T00:00:00+00:00', 'UTC', '', 'txt', 'h', '', '')"#,
+            params![body],
+        )
+        .unwrap();
+        let chunks = vec![
+            (Chunk { start: 0, end: 13 }, vec![1.0, 0.0]),
+            (Chunk { start: 13, end: 25 }, vec![0.2, 1.0]),
+            (Chunk { start: 25, end: 36 }, vec![0.0, 1.0]),
+            (Chunk { start: 36, end: body.len() }, vec![0.9, 0.1]),
+        ];
+        store(&conn, "a", "h", "k", &chunks).unwrap();
+        assert_eq!(load_embeddings(&conn, "m", "k").unwrap().len(), 4);
+
+        let (excerpt, best) = relevant_excerpt(&conn, "a", body, "k", &[1.0, 0.0]).unwrap().unwrap();
+        assert_eq!(best, "Garden notes.");
+        // The two leading chunks are adjacent and read as one passage; "Taxes due." is left out
+        assert_eq!(excerpt, "Garden notes. Work notes.\n...\nMore garden.");
+        // Stale chunks (the entry changed since) are ignored
+        conn.execute("UPDATE entries SET text_hash = 'h2' WHERE id = 'a'", []).unwrap();
+        assert!(relevant_excerpt(&conn, "a", body, "k", &[1.0, 0.0]).unwrap().is_none());
+        assert!(load_embeddings(&conn, "m", "k").unwrap().is_empty());
+    }
+}
@@ -0,0 +1,352 @@
+use anyhow::Result;
+use chrono::{DateTime, NaiveTime, Utc};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use rusqlite::Connection;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::database::{Entry, EntryFilter};
+
+// Headless companion to the desktop app (`journal-reader-cli`). It opens the same database
+// the app uses (the active journal unless --db or --journal says otherwise) and runs the
+// shared import, search and export code without starting Tauri. Embeddings and entity
+// extraction for imported entries are left to the app, which backfills them on its next run.
+
+// Must match `identifier` in tauri.conf.json: Tauri's app data dir is <data dir>/<identifier>
+const APP_IDENTIFIER: &str = "com.jasonb.journal-reader";
+const PASSPHRASE_ENV: &str = "JOURNAL_READER_PASSPHRASE";
+
+#[derive(Debug, Parser)]
+#[command(name = "journal-reader-cli", version, about = "Import, search and export a Journal Reader database")]
+struct Cli {
+    /// Database file to use instead of the app's active journal
+    #[arg(long, global = true)]
+    db: Option<PathBuf>,
+    /// Journal id from the app's journal list
+    #[arg(long, global = true, conflicts_with = "db")]
+    journal: Option<String>,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Import files or folders (searched recursively for supported files)
+    Import {
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+        /// Timezone recorded on the imported entries
+        #[arg(long, default_value = "UTC")]
+        timezone: String,
+        /// Show what would be imported without writing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Full-text search, best matches first
+    Search {
+        query: String,
+        #[arg(long, default_value_t = 20)]
+        limit: u32,
+        #[command(flatten)]
+        filter: FilterArgs,
+        /// Print results as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Export entries, oldest first
+    Export {
+        #[arg(long, value_enum, default_value_t = ExportFormat::Md)]
+        format: ExportFormat,
+        /// Output file (default: stdout)
+        #[arg(long, short)]
+        out: Option<PathBuf>,
+        #[command(flatten)]
+        filter: FilterArgs,
+    },
+}
+
+#[derive(Debug, Args)]
+struct FilterArgs {
+    /// First day to include (YYYY-MM-DD)
+    #[arg(long)]
+    from: Option<String>,
+    /// Last day to include (YYYY-MM-DD)
+    #[arg(long)]
+    to: Option<String>,
+    /// Only entries with this tag; repeat for several
+    #[arg(long = "tag")]
+    tags: Vec<String>,
+    /// Only entries in this language (ISO 639-3 code, e.g. spa); repeat for several
+    #[arg(long = "language")]
+    languages: Vec<String>,
+}
+
+impl FilterArgs {
+    fn to_filter(&self) -> EntryFilter {
+        EntryFilter {
+            date_from: self.from.clone(),
+            date_to: self.to.clone(),
+            tags: (!self.tags.is_empty()).then(|| self.tags.clone()),
+            languages: (!self.languages.is_empty()).then(|| self.languages.clone()),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum ExportFormat {
+    Md,
+    Json,
+    Csv,
+}
+
+fn default_db_path(journal: Option<&str>) -> Result<PathBuf> {
+    let dir = dirs::data_dir()
+        .ok_or_else(|| anyhow::anyhow!("No application data directory; pass --db"))?
+        .join(APP_IDENTIFIER)
+        .join("journal-reader");
+    let list = crate::journals::load(&dir)?;
+    let id = journal.unwrap_or(&list.active);
+    let found = list.journals.iter().find(|j| j.id == id).ok_or_else(|| anyhow::anyhow!("No journal with id {}", id))?;
+    Ok(PathBuf::from(&found.path))
+}
+
+// Only imports may create a new database
+fn open_db(path: &Path, create: bool) -> Result<crate::db_pool::PooledConnection> {
+    if !path.exists() {
+        if !create {
+            anyhow::bail!("No database at {}", path.display());
+        }
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+    }
+    let key = match crate::encryption::load_key_info(path)? {
+        Some(info) if crate::encryption::is_encrypted(path) => {
+            let passphrase = std::env::var(PASSPHRASE_ENV)
+                .map_err(|_| anyhow::anyhow!("Database is encrypted; set {} to its passphrase", PASSPHRASE_ENV))?;
+            Some(crate::encryption::derive_key(&passphrase, &info)?)
+        }
+        _ => None,
+    };
+    let mut conn = crate::db_pool::get(path, key.as_deref())?;
+    if key.is_some() && !crate::encryption::verify_readable(&conn) {
+        anyhow::bail!("Wrong passphrase");
+    }
+    crate::database::prepare_conn(&mut conn)?;
+    Ok(conn)
+}
+
+fn import_files(paths: &[PathBuf]) -> Vec<PathBuf> {
+    let supported = |p: &Path| p.extension().and_then(|e| e.to_str()).and_then(crate::import::FileType::from_extension).is_some();
+    let mut files = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            let mut found: Vec<PathBuf> = walkdir::WalkDir::new(path)
+                .into_iter()
+                .filter_map(|e| e.ok())
+                .map(|e| e.into_path())
+                .filter(|p| p.is_file() && supported(p))
+                .collect();
+            found.sort();
+            files.extend(found);
+        } else {
+            files.push(path.clone());
+        }
+    }
+    files
+}
+
+// The date in the file name (at noon, like the other importers), else the file's modification time
+fn entry_date(path: &Path) -> DateTime<Utc> {
+    let stem = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    if let Some(date) = crate::import::date_from_title(&stem) {
+        return DateTime::from_naive_utc_and_offset(date.and_time(NaiveTime::from_hms_opt(12, 0, 0).expect("valid time")), Utc);
+    }
+    std::fs::metadata(path).and_then(|m| m.modified()).map(DateTime::<Utc>::from).unwrap_or_else(|_| Utc::now())
+}
+
+async fn run_import(conn: &Connection, paths: &[PathBuf], timezone: &str, dry_run: bool) -> Result<bool> {
+    let (mut imported, mut duplicates, mut failed) = (0u32, 0u32, 0u32);
+    for path in import_files(paths) {
+        let path_str = path.to_string_lossy().to_string();
+        let date = entry_date(&path);
+        let parsed = match crate::import::parse_file(&path_str).await {
+            Ok(mut parsed) => {
+                parsed.content = crate::import::normalize_content(&parsed.content);
+                parsed
+            }
+            Err(e) => {
+                eprintln!("failed     {}: {}", path_str, e);
+                failed += 1;
+                continue;
+            }
+        };
+        if crate::database::find_duplicate(conn, &parsed.text_hash)?.is_some() {
+            println!("duplicate  {}", path_str);
+            duplicates += 1;
+            continue;
+        }
+        if dry_run {
+            println!("would add  {}  {}", date.format("%Y-%m-%d"), path_str);
+            imported += 1;
+            continue;
+        }
+        match crate::database::insert_entry(conn, parsed, date, timezone.to_string()) {
+            Ok(_) => {
+                println!("imported   {}  {}", date.format("%Y-%m-%d"), path_str);
+                imported += 1;
+            }
+            Err(e) => {
+                eprintln!("failed     {}: {}", path_str, e);
+                failed += 1;
+            }
+        }
+    }
+    let verb = if dry_run { "to import" } else { "imported" };
+    eprintln!("{} {}, {} duplicates, {} failed", imported, verb, duplicates, failed);
+    Ok(failed == 0)
+}
+
+fn run_search(conn: &Connection, query: &str, limit: u32, filter: &EntryFilter, json: bool) -> Result<()> {
+    let hits = crate::database::search_fts(conn, query, limit, filter)?;
+    if json {
+        let rows: Vec<serde_json::Value> = hits
+            .iter()
+            .map(|(e, fragments)| {
+                serde_json::json!({
+                    "id": e.id,
+                    "date": e.entry_date.format("%Y-%m-%d").to_string(),
+                    "title": e.title,
+                    "snippet": crate::highlight::plain_text(fragments),
+                    "source_path": e.source_path,
+                })
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&rows)?);
+        return Ok(());
+    }
+    for (entry, fragments) in &hits {
+        let snippet = crate::highlight::plain_text(fragments).split_whitespace().collect::<Vec<_>>().join(" ");
+        println!("{}  {}", entry.entry_date.format("%Y-%m-%d"), entry.title.as_deref().unwrap_or("(untitled)"));
+        println!("    {}", snippet);
+    }
+    eprintln!("{} results", hits.len());
+    Ok(())
+}
+
+fn markdown(entries: &[Entry], tags: &std::collections::HashMap<String, Vec<String>>) -> String {
+    let mut out = String::new();
+    for entry in entries {
+        let date = entry.entry_date.format("%Y-%m-%d").to_string();
+        // Titles taken from dated file names would only repeat the date
+        match entry.title.as_deref().map(str::trim).filter(|t| !t.is_empty() && *t != date) {
+            Some(title) => out.push_str(&format!("## {} — {}\n\n", date, title)),
+            None => out.push_str(&format!("## {}\n\n", date)),
+        }
+        if let Some(entry_tags) = tags.get(&entry.id).filter(|t| !t.is_empty()) {
+            let list: Vec<String> = entry_tags.iter().map(|t| format!("#{}", t)).collect();
+            out.push_str(&format!("Tags: {}\n\n", list.join(" ")));
+        }
+        out.push_str(entry.body.trim());
+        out.push_str("\n\n");
+    }
+    out
+}
+
+fn run_export(conn: &Connection, format: ExportFormat, out: Option<&Path>, filter: &EntryFilter) -> Result<()> {
+    let text = match format {
+        ExportFormat::Json => serde_json::to_string_pretty(&crate::backup::collect(conn, filter)?)?,
+        ExportFormat::Md => {
+            let entries = crate::database::filtered_entries(conn, filter)?;
+            let ids: Vec<String> = entries.iter().map(|e| e.id.clone()).collect();
+            markdown(&entries, &crate::database::tags_for_entries(conn, &ids)?)
+        }
+        ExportFormat::Csv => {
+            let rows: Vec<(Entry, String)> = crate::database::filtered_entries(conn, filter)?
+                .into_iter()
+                .map(|e| {
+                    let snippet = crate::csv_export::lead(&e.body);
+                    (e, snippet)
+                })
+                .collect();
+            crate::csv_export::render(conn, &rows)?
+        }
+    };
+    match out {
+        Some(path) => {
+            std::fs::write(path, text)?;
+            eprintln!("wrote {}", path.display());
+        }
+        None => std::io::stdout().write_all(text.as_bytes())?,
+    }
+    Ok(())
+}
+
+async fn execute(cli: Cli) -> Result<bool> {
+    let db_path = match &cli.db {
+        Some(path) => path.clone(),
+        None => default_db_path(cli.journal.as_deref())?,
+    };
+    let conn = open_db(&db_path, matches!(cli.command, Command::Import { .. }))?;
+    match &cli.command {
+        Command::Import { paths, timezone, dry_run } => run_import(&conn, paths, timezone, *dry_run).await,
+        Command::Search { query, limit, filter, json } => run_search(&conn, query, *limit, &filter.to_filter(), *json).map(|_| true),
+        Command::Export { format, out, filter } => run_export(&conn, *format, out.as_deref(), &filter.to_filter()).map(|_| true),
+    }
+}
+
+// Entry point for the `journal-reader-cli` binary; returns the process exit code
+pub fn main() -> i32 {
+    let cli = Cli::parse();
+    let runtime = match tokio::runtime::Runtime::new() {
+        Ok(rt) => rt,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            return 1;
+        }
+    };
+    match runtime.block_on(execute(cli)) {
+        Ok(true) => 0,
+        Ok(false) => 2,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            1
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cli_args_and_markdown() {
+        let cli = Cli::try_parse_from(["journal-reader-cli", "--db", "j.db", "export", "--from", "2021-01-01", "--tag", "work", "--tag", "travel"]).unwrap();
+        let Command::Export { format, out, filter } = cli.command else { panic!("expected export") };
+        assert_eq!((format, out), (ExportFormat::Md, None));
+        let filter = filter.to_filter();
+        assert_eq!(filter.date_from.as_deref(), Some("2021-01-01"));
+        assert_eq!(filter.tags, Some(vec!["work".to_string(), "travel".to_string()]));
+        assert!(Cli::try_parse_from(["journal-reader-cli", "--db", "a.db", "--journal", "work", "search", "x"]).is_err());
+
+        let entry = |id: &str, title: Option<&str>, body: &str| Entry {
+            id: id.to_string(),
+            title: title.map(str::to_string),
+            body: body.to_string(),
+            entry_date: "2021-03-05T12:00:00Z".parse().unwrap(),
+            entry_timezone: "UTC".to_string(),
+            source_path: String::new(),
+            source_type: "txt".to_string(),
+            text_hash: String::new(),
+            embedding: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            sentiment: None,
+            language: None,
+        };
+        let tags = [("a".to_string(), vec!["work".to_string()])].into_iter().collect();
+        let md = markdown(&[entry("a", Some("Trip"), " Packed.\n"), entry("b", Some("2021-03-05"), "Home.")], &tags);
+        assert_eq!(md, "## 2021-03-05 — Trip\n\nTags: #work\n\nPacked.\n\n## 2021-03-05\n\nHome.\n\n");
+    }
+}
@@ -0,0 +1,329 @@
+use anyhow::Result;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tauri::AppHandle;
+
+use crate::ai::{ChatMessage, ChatRequest};
+
+// Topic clusters over the stored entry embeddings.
+//
+// Spherical k-means: the vector index already holds unit-length vectors, so assignment is a
+// dot product and centroids are renormalized means. Seeding is k-means++ with a fixed seed, so
+// the same archive clusters the same way every time. Each cluster is then named by the chat
+// provider from its most central entries, falling back to its most frequent words.
+
+const MAX_ITERATIONS: usize = 50;
+const MAX_CLUSTERS: usize = 30;
+// Most central entries shown per cluster and used for labelling
+const REPRESENTATIVES: usize = 5;
+const LABEL_SNIPPET_CHARS: usize = 500;
+const SEED: u64 = 0x5eed;
+
+// Filler that says nothing about a topic, on top of the question stop words
+const LABEL_STOP_WORDS: &[&str] = &[
+    "just", "really", "got", "get", "went", "go", "going", "day", "today", "also", "some", "like", "one", "out",
+    "up", "very", "been", "more", "now", "still", "because", "not", "no", "know", "think", "feel", "felt",
+    "time", "back", "good", "well", "little", "lot", "things", "thing", "said", "make", "made", "want",
+];
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ClusterEntry {
+    pub id: String,
+    pub title: Option<String>,
+    pub entry_date: String,
+    // Cosine similarity to the cluster centroid
+    pub similarity: f32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EntryCluster {
+    pub id: u32,
+    pub label: String,
+    pub description: Option<String>,
+    pub size: u32,
+    pub date_from: Option<String>,
+    pub date_to: Option<String>,
+    pub representatives: Vec<ClusterEntry>,
+    pub entry_ids: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ClusteringResult {
+    pub clusters: Vec<EntryCluster>,
+    pub total_entries: u32,
+    pub model: String,
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+fn normalize(v: &mut [f32]) {
+    let norm = dot(v, v).sqrt();
+    if norm > 0.0 && norm.is_finite() {
+        v.iter_mut().for_each(|x| *x /= norm);
+    }
+}
+
+// sqrt(n / 2), the usual rule of thumb, within 2..=MAX_CLUSTERS
+fn default_k(n: usize) -> usize {
+    ((n as f64 / 2.0).sqrt().round() as usize).clamp(2, MAX_CLUSTERS)
+}
+
+// Cluster index for every vector; vectors must be unit length
+fn kmeans(vectors: &[&[f32]], k: usize) -> Vec<usize> {
+    let n = vectors.len();
+    let k = k.min(n);
+    if k <= 1 {
+        return vec![0; n];
+    }
+    let mut rng = StdRng::seed_from_u64(SEED);
+
+    // k-means++: later seeds favour points far (in cosine distance) from the chosen ones
+    let mut centroids: Vec<Vec<f32>> = vec![vectors[rng.gen_range(0..n)].to_vec()];
+    let mut distance: Vec<f32> = vectors.iter().map(|v| (1.0 - dot(v, &centroids[0])).max(0.0)).collect();
+    while centroids.len() < k {
+        let total: f32 = distance.iter().sum();
+        let next = if total <= 0.0 {
+            rng.gen_range(0..n)
+        } else {
+            let mut target = rng.gen_range(0.0..total);
+            distance.iter().position(|d| {
+                target -= d;
+                target <= 0.0
+            })
+            .unwrap_or(n - 1)
+        };
+        centroids.push(vectors[next].to_vec());
+        let latest = centroids.last().expect("pushed above");
+        for (d, v) in distance.iter_mut().zip(vectors) {
+            *d = d.min((1.0 - dot(v, latest)).max(0.0));
+        }
+    }
+
+    let mut assignment = vec![usize::MAX; n];
+    for _ in 0..MAX_ITERATIONS {
+        let mut changed = false;
+        for (i, v) in vectors.iter().enumerate() {
+            let best = centroids
+                .iter()
+                .enumerate()
+                .map(|(c, centroid)| (c, dot(v, centroid)))
+                .max_by(|a, b| a.1.total_cmp(&b.1))
+                .map(|(c, _)| c)
+                .unwrap_or(0);
+            if assignment[i] != best {
+                assignment[i] = best;
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+        let dim = vectors[0].len();
+        let mut sums = vec![vec![0.0f32; dim]; k];
+        for (v, &c) in vectors.iter().zip(&assignment) {
+            sums[c].iter_mut().zip(v.iter()).for_each(|(s, x)| *s += x);
+        }
+        for (centroid, mut sum) in centroids.iter_mut().zip(sums) {
+            // An emptied cluster keeps its old centroid
+            if sum.iter().any(|x| *x != 0.0) {
+                normalize(&mut sum);
+                *centroid = sum;
+            }
+        }
+    }
+    assignment
+}
+
+// Most frequent content words across the texts, for when the provider can't name a cluster
+fn keyword_label(texts: &[String]) -> String {
+    let mut counts: HashMap<String, u32> = HashMap::new();
+    for text in texts {
+        for word in text.split(|c: char| !c.is_alphanumeric() && c != '\'') {
+            let word = word.trim_matches('\'').to_lowercase();
+            if word.chars().count() < 3
+                || crate::ai::QUESTION_STOP_WORDS.contains(&word.as_str())
+                || LABEL_STOP_WORDS.contains(&word.as_str())
+            {
+                continue;
+            }
+            *counts.entry(word).or_insert(0) += 1;
+        }
+    }
+    let mut words: Vec<(String, u32)> = counts.into_iter().collect();
+    words.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    let label: Vec<String> = words.into_iter().take(3).map(|(w, _)| w).collect();
+    if label.is_empty() {
+        "Untitled".to_string()
+    } else {
+        label.join(", ")
+    }
+}
+
+// "Label: description" on the first line, as asked in the prompt
+fn parse_label(response: &str) -> Option<(String, Option<String>)> {
+    let line = response.lines().map(str::trim).find(|l| !l.is_empty())?;
+    let line = line.trim_start_matches(|c: char| c == '#' || c == '*' || c.is_whitespace()).trim_end_matches('*');
+    let (label, description) = match line.split_once(':') {
+        Some((label, description)) => (label, Some(description.trim().to_string()).filter(|d| !d.is_empty())),
+        None => (line, None),
+    };
+    let label = label.trim().trim_matches(['"', '*']).trim();
+    (!label.is_empty()).then(|| (label.to_string(), description))
+}
+
+async fn label_cluster(settings: &crate::ai::AiSettings, samples: &[String]) -> Option<(String, Option<String>)> {
+    let prompt = format!(
+        "These journal entries belong to one recurring theme. Reply with a single line: a 2-5 word name for the theme, a colon, then one sentence describing it.\n\n{}",
+        samples.iter().map(|s| format!("- {}", s)).collect::<Vec<_>>().join("\n")
+    );
+    let request = ChatRequest {
+        messages: vec![ChatMessage { role: "user".to_string(), content: prompt }],
+        model: String::new(),
+        provider: None,
+    };
+    match crate::ai::chat_completion(settings, request).await {
+        Ok(response) => parse_label(&response),
+        Err(e) => {
+            tracing::warn!("labelling failed: {}", e);
+            None
+        }
+    }
+}
+
+// Groups all embedded entries into `k` topics (chosen from the archive size when omitted).
+// With `label` off, clusters are named from their frequent words without calling the provider.
+pub async fn cluster_entries(app_handle: &AppHandle, k: Option<u32>, label: bool) -> Result<ClusteringResult> {
+    use crate::database::{get_db_file_path, get_entry_by_id, open_conn};
+
+    let (index, model, settings) = {
+        let conn = open_conn(app_handle)?;
+        let config = crate::embeddings::load_config(&conn);
+        let db_path = get_db_file_path(app_handle)?;
+        let index = crate::vector_index::get_or_load(&conn, &db_path, &config.model_key())?;
+        (index, config.model_key(), config.ai)
+    };
+    if index.len() < 2 {
+        return Err(anyhow::anyhow!("Clustering needs at least two embedded entries; run the embedding backfill first"));
+    }
+
+    let k = k.map(|k| (k as usize).clamp(1, MAX_CLUSTERS)).unwrap_or_else(|| default_k(index.len()));
+    let worker = index.clone();
+    let (assignment, similarities) = tokio::task::spawn_blocking(move || {
+        let vectors: Vec<&[f32]> = worker.rows().map(|(_, v)| v).collect();
+        let assignment = kmeans(&vectors, k);
+        // Similarity of each vector to its cluster's mean direction
+        let dim = vectors[0].len();
+        let mut centroids = vec![vec![0.0f32; dim]; k];
+        for (v, &c) in vectors.iter().zip(&assignment) {
+            centroids[c].iter_mut().zip(v.iter()).for_each(|(s, x)| *s += x);
+        }
+        centroids.iter_mut().for_each(|c| normalize(c));
+        let similarities: Vec<f32> = vectors.iter().zip(&assignment).map(|(v, &c)| dot(v, &centroids[c])).collect();
+        (assignment, similarities)
+    })
+    .await?;
+
+    let mut members: Vec<Vec<(String, f32)>> = vec![Vec::new(); k];
+    for (((id, _), &c), &similarity) in index.rows().zip(&assignment).zip(&similarities) {
+        members[c].push((id.to_string(), similarity));
+    }
+    members.retain(|m| !m.is_empty());
+    members.sort_by_key(|m| std::cmp::Reverse(m.len()));
+
+    let mut clusters = Vec::new();
+    for (cluster_id, mut cluster) in members.into_iter().enumerate() {
+        cluster.sort_by(|a, b| b.1.total_cmp(&a.1));
+        let mut representatives = Vec::new();
+        let mut samples = Vec::new();
+        for (id, similarity) in cluster.iter().take(REPRESENTATIVES) {
+            let Some(entry) = get_entry_by_id(app_handle, id).await? else { continue };
+            let text = crate::embeddings::embedding_text(entry.title.as_deref(), &entry.body);
+            samples.push(text.chars().take(LABEL_SNIPPET_CHARS).collect::<String>().replace('\n', " "));
+            representatives.push(ClusterEntry {
+                id: entry.id,
+                title: entry.title,
+                entry_date: entry.entry_date.to_rfc3339(),
+                similarity: *similarity,
+            });
+        }
+
+        let named = if label {
+            crate::ai_audit::with_context("topic labels", vec![], label_cluster(&settings, &samples)).await
+        } else {
+            None
+        };
+        let (label, description) = named.unwrap_or_else(|| (keyword_label(&samples), None));
+
+        let entry_ids: Vec<String> = cluster.into_iter().map(|(id, _)| id).collect();
+        let (date_from, date_to) = {
+            let conn = open_conn(app_handle)?;
+            let placeholders = vec!["?"; entry_ids.len()].join(", ");
+            conn.query_row(
+                &format!("SELECT min(entry_date), max(entry_date) FROM entries WHERE id IN ({})", placeholders),
+                rusqlite::params_from_iter(entry_ids.iter()),
+                |r| Ok((r.get::<_, Option<String>>(0)?, r.get::<_, Option<String>>(1)?)),
+            )?
+        };
+        clusters.push(EntryCluster {
+            id: cluster_id as u32,
+            label,
+            description,
+            size: entry_ids.len() as u32,
+            date_from,
+            date_to,
+            representatives,
+            entry_ids,
+        });
+    }
+
+    tracing::info!("entries={} k={} clusters={}", index.len(), k, clusters.len());
+    Ok(ClusteringResult { clusters, total_entries: index.len() as u32, model })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kmeans_separates_directions() {
+        // Two tight groups around the x and y axes
+        let raw: Vec<Vec<f32>> = vec![
+            vec![1.0, 0.1, 0.0],
+            vec![0.9, 0.0, 0.1],
+            vec![1.0, -0.1, 0.0],
+            vec![0.0, 1.0, 0.1],
+            vec![0.1, 0.9, 0.0],
+            vec![-0.1, 1.0, 0.0],
+        ];
+        let unit: Vec<Vec<f32>> = raw
+            .into_iter()
+            .map(|mut v| {
+                normalize(&mut v);
+                v
+            })
+            .collect();
+        let vectors: Vec<&[f32]> = unit.iter().map(Vec::as_slice).collect();
+        let assignment = kmeans(&vectors, 2);
+        assert_eq!(assignment[0], assignment[1]);
+        assert_eq!(assignment[1], assignment[2]);
+        assert_eq!(assignment[3], assignment[4]);
+        assert_eq!(assignment[4], assignment[5]);
+        assert_ne!(assignment[0], assignment[3]);
+        assert_eq!(kmeans(&vectors, 2), assignment);
+    }
+
+    #[test]
+    fn test_labels() {
+        assert_eq!(default_k(10), 2);
+        assert_eq!(default_k(5000), MAX_CLUSTERS);
+        assert_eq!(
+            parse_label("**Running and races**: Training for and running local races.\n"),
+            Some(("Running and races".to_string(), Some("Training for and running local races.".to_string())))
+        );
+        let texts = vec!["Long run by the river, knees sore after the run".to_string(), "Run club in the rain".to_string()];
+        assert_eq!(keyword_label(&texts), "run, club, knees");
+    }
+}
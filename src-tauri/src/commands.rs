@@ -1,4 +1,4 @@
-use crate::Result;
+use crate::{AppError, AppErrorKind, Result};
 use serde::{Deserialize, Serialize};
 use sha2::Digest as _;
 use base64::Engine;
@@ -32,6 +32,20 @@ pub struct ImportResult {
     pub errors: Option<Vec<String>>,
 }
 
+// Sent as `import://file-progress` after each file of import_files_with_dates
+#[derive(Debug, Serialize, Clone)]
+pub struct FileImportProgress {
+    pub path: String,
+    // "imported", "duplicate" or "failed"
+    pub outcome: String,
+    pub error: Option<String>,
+    pub error_code: Option<AppErrorKind>,
+    pub processed: u32,
+    pub total: u32,
+    pub imported: u32,
+    pub failed: u32,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct FileWithDate {
     pub path: String,
@@ -72,37 +86,466 @@ pub struct EntryPreview {
     pub preview: String,
     pub entry_date: String,
     pub tags: Vec<String>,
+    // Search hits only: preview fragments with match offsets
+    #[serde(default)]
+    pub snippets: Vec<crate::highlight::SnippetFragment>,
+    // The body is sealed; `preview` holds it only while sensitive entries are unlocked
+    #[serde(default)]
+    pub sensitive: bool,
+}
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchPage {
+    pub results: Vec<EntryPreview>,
+    pub total: u64,
+    pub offset: u32,
+    pub limit: u32,
+    // The query's date phrase ("last summer"), searched as a date filter instead of as words
+    #[serde(default)]
+    pub date_phrase: Option<crate::date_phrases::DatePhrase>,
 }
+
+// Searches started with the same `search_key` supersede each other: a new one interrupts the
+// previous one, which then fails with code CANCELLED. Times out after `search_timeout_secs`.
+// Unless `parse_dates` is false or a date filter is given, a date phrase in the query becomes
+// the date filter and the rest of the query is searched (see date_phrases.rs).
 #[tauri::command]
-pub async fn search_entries_simple(app_handle: tauri::AppHandle, query: String, limit: Option<u32>) -> Result<Vec<EntryPreview>> {
+pub async fn search_entries_simple(
+    app_handle: tauri::AppHandle,
+    query: String,
+    limit: Option<u32>,
+    offset: Option<u32>,
+    filters: Option<crate::database::EntryFilter>,
+    search_key: Option<String>,
+    parse_dates: Option<bool>,
+) -> Result<SearchPage> {
     use tokio::time::{timeout, Duration};
     let lim = limit.unwrap_or(50);
+    let offset = offset.unwrap_or(0);
     let trimmed = query.trim().to_string();
 
-    println!("[search] start query='{}' limit={}", trimmed, lim);
+    tracing::debug!("search start query='{}' limit={} offset={}", trimmed, lim, offset);
     let started = std::time::Instant::now();
 
-    let fut = crate::database::search_entries_fts_simple(&app_handle, &trimmed, lim);
-    let timed = timeout(Duration::from_secs(10), fut).await;
+    if offset == 0 {
+        crate::history::record_search(&app_handle, &trimmed);
+    }
+    let (timeout_secs, birthday) = match crate::database::open_conn(&app_handle) {
+        Ok(conn) => (
+            crate::database::read_setting(&conn, "search_timeout_secs").and_then(|v| v.trim().parse::<u64>().ok()),
+            crate::database::read_setting(&conn, "birthday").and_then(|v| crate::date_phrases::parse_birthday(&v)),
+        ),
+        Err(_) => (None, None),
+    };
+    let timeout_secs = timeout_secs.unwrap_or(10).clamp(1, 300);
+    let mut filters = filters.unwrap_or_default();
+    let date_phrase = if parse_dates.unwrap_or(true) && filters.date_from.is_none() && filters.date_to.is_none() {
+        crate::date_phrases::extract(&trimmed, chrono::Local::now().date_naive(), birthday)
+    } else {
+        None
+    };
+    let text_query = match &date_phrase {
+        Some(phrase) => {
+            tracing::debug!("date phrase '{}' -> {}..{}", phrase.phrase, phrase.from, phrase.to);
+            filters.date_from = Some(phrase.from.to_string());
+            filters.date_to = Some(phrase.to.to_string());
+            phrase.rest.clone()
+        }
+        None => trimmed.clone(),
+    };
+    if text_query.is_empty() && date_phrase.is_some() {
+        let page = crate::database::filtered_entries_page(&app_handle, &filters, lim, offset)
+            .await
+            .map_err(|e| AppError::new(AppErrorKind::Search, format!("Search error: {}", e)))?;
+        return Ok(search_page(page, offset, lim, date_phrase));
+    }
 
-    let results = match timed {
-        Ok(inner) => inner.map_err(|e| crate::AppError { message: format!("Search error: {}", e), code: Some("SEARCH_ERROR".into()) })?,
+    // Without a key the search can still be interrupted when it times out
+    let key = search_key.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let token = std::sync::Arc::new(crate::search_cancel::SearchToken::new(&key));
+    let fut = crate::database::search_entries_fts_page(&app_handle, &text_query, lim, offset, &filters, true, Some(token.clone()));
+    let timed = timeout(Duration::from_secs(timeout_secs), fut).await;
+
+    let page = match timed {
+        Ok(inner) => inner.map_err(|e| {
+            if crate::search_cancel::is_cancelled(&e) {
+                tracing::debug!("search cancelled query='{}'", trimmed);
+                AppError::new(AppErrorKind::Cancelled, e.to_string())
+            } else {
+                tracing::warn!("search failed: {}", e);
+                AppError::new(AppErrorKind::Search, format!("Search error: {}", e))
+            }
+        })?,
         Err(_) => {
-            println!("[search] timeout query='{}'", trimmed);
-            return Err(crate::AppError { message: "Search timed out".into(), code: Some("TIMEOUT".into()) });
+            tracing::warn!("search timed out after {}s", timeout_secs);
+            // Stop the query still running in its blocking thread
+            token.cancel();
+            return Err(AppError::new(AppErrorKind::Timeout, "Search timed out"));
         }
     };
 
     let elapsed = started.elapsed().as_millis();
-    println!("[search] done query='{}' ms={} results={}", trimmed, elapsed, results.len());
+    tracing::debug!("search done query='{}' ms={} results={} total={}", trimmed, elapsed, page.entries.len(), page.total);
+    Ok(search_page(page, offset, lim, date_phrase))
+}
 
-    Ok(results.into_iter().map(|(e, snip)| EntryPreview {
+fn search_page(
+    page: crate::database::EntryPage,
+    offset: u32,
+    limit: u32,
+    date_phrase: Option<crate::date_phrases::DatePhrase>,
+) -> SearchPage {
+    let results = page.entries.into_iter().map(|(e, fragments)| EntryPreview {
         id: e.id,
         title: e.title,
-        preview: if snip.is_empty() { create_preview(&e.body, 240) } else { snip },
+        preview: crate::highlight::plain_text(&fragments),
         entry_date: e.entry_date.to_rfc3339(),
         tags: vec![],
-    }).collect())
+        snippets: fragments,
+        sensitive: false,
+    }).collect();
+    SearchPage { results, total: page.total, offset, limit, date_phrase }
+}
+
+// Interrupts the search running under `search_key`, e.g. when the search box is cleared
+#[tauri::command]
+pub async fn cancel_search(search_key: String) -> Result<bool> {
+    Ok(crate::search_cancel::cancel(&search_key))
+}
+
+// Writes every entry matching the search (all filtered entries for an empty query) to a CSV file
+#[tauri::command]
+pub async fn export_search_results(
+    app_handle: tauri::AppHandle,
+    query: String,
+    filters: Option<crate::database::EntryFilter>,
+    dest: String,
+) -> Result<crate::csv_export::CsvExportReport> {
+    let export_error = |e| AppError::from_error(AppErrorKind::Export, e).with_path(&dest);
+    let target = crate::documents::ExportTarget::new(&app_handle, &dest).map_err(export_error)?;
+    let mut report = crate::csv_export::export_search_results(&app_handle, &query, &filters.unwrap_or_default(), target.path())
+        .await
+        .map_err(export_error)?;
+    target.finish(&app_handle).map_err(export_error)?;
+    report.path = dest.clone();
+    Ok(report)
+}
+
+// Full-text, semantic, or hybrid (reciprocal rank fusion of both) search
+#[tauri::command]
+pub async fn search_entries_advanced(app_handle: tauri::AppHandle, request: crate::search::SearchRequest) -> Result<Vec<crate::search::SearchResult>> {
+    let started = std::time::Instant::now();
+    if request.offset == 0 {
+        crate::history::record_search(&app_handle, &request.query);
+    }
+    let results = crate::search::search(&app_handle, &request).await
+        .map_err(|e| AppError::new(AppErrorKind::Search, format!("Search error: {}", e)))?;
+    tracing::debug!("advanced search type={:?} query='{}' ms={} results={}", request.search_type, request.query, started.elapsed().as_millis(), results.len());
+    Ok(results)
+}
+
+// Other entries about the same topic, by cosine similarity of stored embeddings
+#[tauri::command]
+pub async fn find_similar_entries(app_handle: tauri::AppHandle, entry_id: String, limit: Option<u32>) -> Result<Vec<crate::search::SearchResult>> {
+    let results = crate::search::find_similar(&app_handle, &entry_id, limit.unwrap_or(10)).await
+        .map_err(|e| AppError::from_error(AppErrorKind::NotEmbedded, e).with_entry(&entry_id))?;
+    Ok(results)
+}
+
+#[tauri::command]
+pub async fn get_search_history(app_handle: tauri::AppHandle, limit: Option<u32>) -> Result<Vec<crate::history::SearchHistoryItem>> {
+    Ok(crate::history::get_search_history(&app_handle, limit.unwrap_or(20)).await?)
+}
+
+#[tauri::command]
+pub async fn clear_search_history(app_handle: tauri::AppHandle) -> Result<()> {
+    crate::history::clear_search_history(&app_handle).await?;
+    Ok(())
+}
+
+// Autocomplete for the search box: matching past queries, then indexed words
+#[tauri::command]
+pub async fn get_search_suggestions(app_handle: tauri::AppHandle, prefix: String, limit: Option<u32>) -> Result<Vec<crate::history::SearchSuggestion>> {
+    Ok(crate::history::get_search_suggestions(&app_handle, &prefix, limit.unwrap_or(8)).await?)
+}
+
+// Provider, URL and API key come from settings unless the request names a provider
+#[tauri::command]
+pub async fn chat_completion(app_handle: tauri::AppHandle, request: crate::ai::ChatRequest) -> Result<String> {
+    let settings = crate::ai::AiSettings::from_app(&app_handle)?;
+    let provider = request.provider.clone().unwrap_or_else(|| settings.provider.clone());
+    crate::ai_audit::with_context("chat", vec![], crate::ai::chat_completion(&settings, request)).await
+        .map_err(|e| AppError::from_error(AppErrorKind::Ai, e).with_provider(&provider))
+}
+
+#[tauri::command]
+pub async fn extract_tags_ai(
+    app_handle: tauri::AppHandle,
+    mut request: crate::ai::TagExtractionRequest,
+    provider: Option<crate::ai::Provider>,
+) -> Result<crate::ai::TagExtractionResult> {
+    if request.vocabulary.is_empty() {
+        request.vocabulary = crate::ai::get_default_vocabulary().tags.into_iter().map(|t| t.name).collect();
+    }
+    let settings = crate::ai::AiSettings::from_app(&app_handle)?;
+    let used = provider.clone().unwrap_or_else(|| settings.provider.clone());
+    crate::ai::extract_tags_cached(&app_handle, &settings, request, provider).await
+        .map_err(|e| AppError::from_error(AppErrorKind::Ai, e).with_provider(&used))
+}
+
+// Emits `ai://token` with AiToken for each piece of the answer; cancel with cancel_ai_stream
+#[tauri::command]
+pub async fn chat_completion_stream(app_handle: tauri::AppHandle, request: crate::ai::ChatRequest, stream_id: String) -> Result<String> {
+    use tauri::Emitter;
+    let settings = crate::ai::AiSettings::from_app(&app_handle)?;
+    let provider = request.provider.clone().unwrap_or_else(|| settings.provider.clone());
+    let registration = crate::ai_stream::register(&stream_id);
+    let emitter = app_handle.clone();
+    let stream = crate::ai::chat_completion_stream(&settings, request, &registration.token, move |token| {
+        let _ = emitter.emit("ai://token", crate::ai_stream::AiToken { stream_id: stream_id.clone(), token: token.to_string() });
+    });
+    crate::ai_audit::with_context("chat", vec![], stream)
+        .await
+        .map_err(|e| AppError::from_error(AppErrorKind::Ai, e).with_provider(&provider))
+}
+
+// Question answering over the journal, streamed like chat_completion_stream
+#[tauri::command]
+pub async fn rag_query_stream(app_handle: tauri::AppHandle, request: crate::ai::RagRequest, stream_id: String) -> Result<crate::ai::RagResponse> {
+    use tauri::Emitter;
+    let registration = crate::ai_stream::register(&stream_id);
+    let emitter = app_handle.clone();
+    let question = request.question.clone();
+    let provider = request.provider.clone();
+    let response = crate::ai::process_rag_query_stream(&app_handle, request, &registration.token, move |token| {
+        let _ = emitter.emit("ai://token", crate::ai_stream::AiToken { stream_id: stream_id.clone(), token: token.to_string() });
+    })
+    .await
+    .map_err(|e| AppError::from_error(AppErrorKind::Ai, e).with_provider(&provider))?;
+    crate::conversations::save_exchange(&app_handle, &question, &response).await?;
+    Ok(response)
+}
+
+// Answers a question from journal entries and appends the exchange to the conversation,
+// starting a new one when conversation_id is None
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn ask_question(
+    app_handle: tauri::AppHandle,
+    question: String,
+    conversation_id: Option<String>,
+    max_context_entries: Option<u32>,
+    context_date_range: Option<(chrono::DateTime<chrono::Utc>, chrono::DateTime<chrono::Utc>)>,
+    context_tags: Option<Vec<String>>,
+    provider: Option<crate::ai::Provider>,
+    model: Option<String>,
+) -> Result<crate::ai::RagResponse> {
+    let provider = match provider {
+        Some(provider) => provider,
+        None => crate::ai::AiSettings::from_app(&app_handle)?.provider,
+    };
+    let request = crate::ai::RagRequest {
+        question,
+        conversation_id,
+        max_context_entries: max_context_entries.unwrap_or(5),
+        context_date_range,
+        context_tags,
+        provider,
+        model: model.unwrap_or_default(),
+    };
+    process_rag_query(app_handle, request).await
+}
+
+// Ask-my-journal: retrieves entries with hybrid FTS/vector search and answers from them.
+// Citation entry_ids can be opened with get_entry_by_id.
+#[tauri::command]
+pub async fn process_rag_query(app_handle: tauri::AppHandle, request: crate::ai::RagRequest) -> Result<crate::ai::RagResponse> {
+    let question = request.question.clone();
+    let provider = request.provider.clone();
+    let response = crate::ai::process_rag_query(&app_handle, request).await
+        .map_err(|e| AppError::from_error(AppErrorKind::Ai, e).with_provider(&provider))?;
+    crate::conversations::save_exchange(&app_handle, &question, &response).await?;
+    Ok(response)
+}
+
+#[tauri::command]
+pub async fn get_conversations_list(app_handle: tauri::AppHandle) -> Result<Vec<crate::conversations::ConversationSummary>> {
+    Ok(crate::conversations::list_conversations(&app_handle).await?)
+}
+
+#[tauri::command]
+pub async fn get_conversation_history(app_handle: tauri::AppHandle, conversation_id: String) -> Result<Vec<crate::ai::ConversationMessage>> {
+    crate::conversations::get_conversation_history(&app_handle, &conversation_id).await
+        .map_err(AppError::or(AppErrorKind::NotFound))
+}
+
+#[tauri::command]
+pub async fn delete_conversation(app_handle: tauri::AppHandle, conversation_id: String) -> Result<bool> {
+    Ok(crate::conversations::delete_conversation(&app_handle, &conversation_id).await?)
+}
+
+// Recap of the entries dated start..=end (YYYY-MM-DD); cached until those entries change
+#[tauri::command]
+pub async fn summarize_period(
+    app_handle: tauri::AppHandle,
+    start: String,
+    end: String,
+    granularity: String,
+    force: Option<bool>,
+) -> Result<crate::summaries::PeriodSummary> {
+    let granularity = crate::summaries::Granularity::parse(&granularity)
+        .map_err(AppError::or(AppErrorKind::InvalidInput))?;
+    crate::summaries::summarize_period(&app_handle, &start, &end, granularity, force.unwrap_or(false))
+        .await
+        .map_err(AppError::or(AppErrorKind::Ai))
+}
+
+// Writes a printable PDF book of the entries matching `filter` (the search filter) to `path`
+#[tauri::command]
+pub async fn export_pdf(
+    app_handle: tauri::AppHandle,
+    filter: Option<crate::database::EntryFilter>,
+    options: Option<crate::pdf_export::PdfOptions>,
+    path: String,
+) -> Result<crate::pdf_export::PdfExportReport> {
+    let export_error = |e| AppError::from_error(AppErrorKind::Export, e).with_path(&path);
+    let target = crate::documents::ExportTarget::new(&app_handle, &path).map_err(export_error)?;
+    let mut report = crate::pdf_export::export_pdf(&app_handle, &filter.unwrap_or_default(), &options.unwrap_or_default(), target.path())
+        .await
+        .map_err(export_error)?;
+    target.finish(&app_handle).map_err(export_error)?;
+    report.path = path.clone();
+    Ok(report)
+}
+
+#[tauri::command]
+pub async fn list_summaries(
+    app_handle: tauri::AppHandle,
+    granularity: Option<String>,
+    limit: Option<u32>,
+) -> Result<Vec<crate::summaries::PeriodSummary>> {
+    let granularity = granularity.as_deref().map(crate::summaries::Granularity::parse).transpose()?;
+    Ok(crate::summaries::list_summaries(&app_handle, granularity, limit.unwrap_or(20)).await?)
+}
+
+#[tauri::command]
+pub async fn generate_year_in_review(app_handle: tauri::AppHandle, year: i32) -> Result<crate::review::YearInReview> {
+    crate::review::year_in_review(&app_handle, year)
+        .await
+        .map_err(AppError::or(AppErrorKind::Ai))
+}
+
+// Average entry sentiment per day, week or month, plus the most positive and negative entries
+#[tauri::command]
+pub async fn get_sentiment_timeline(
+    app_handle: tauri::AppHandle,
+    granularity: String,
+    date_range: Option<(String, String)>,
+) -> Result<crate::sentiment::SentimentTimeline> {
+    let granularity = crate::summaries::Granularity::parse(&granularity)
+        .map_err(AppError::or(AppErrorKind::InvalidInput))?;
+    Ok(crate::sentiment::get_sentiment_timeline(&app_handle, granularity, date_range).await?)
+}
+
+// Finds people, places and events in new or edited entries (all entries with `force`)
+#[tauri::command]
+pub async fn extract_entities(app_handle: tauri::AppHandle, force: Option<bool>) -> Result<crate::entities::ExtractionReport> {
+    Ok(crate::entities::extract_pending(&app_handle, force.unwrap_or(false)).await?)
+}
+
+// Most mentioned people, places or events (all kinds when `kind` is omitted)
+#[tauri::command]
+pub async fn get_entities(
+    app_handle: tauri::AppHandle,
+    kind: Option<String>,
+    limit: Option<u32>,
+) -> Result<Vec<crate::entities::EntitySummary>> {
+    let kind = kind
+        .as_deref()
+        .map(crate::entities::EntityKind::parse)
+        .transpose()
+        .map_err(AppError::or(AppErrorKind::InvalidInput))?;
+    Ok(crate::entities::list_entities(&app_handle, kind, limit.unwrap_or(50)).await?)
+}
+
+// Reads new or edited entries for stated goals and later mentions of them
+#[tauri::command]
+pub async fn track_goals(app_handle: tauri::AppHandle, force: Option<bool>) -> Result<crate::goals::GoalReport> {
+    Ok(crate::goals::track_pending(&app_handle, force.unwrap_or(false)).await?)
+}
+
+// Goals with their timelines; `status` is "active", "dormant", "achieved" or "abandoned"
+#[tauri::command]
+pub async fn list_goals(app_handle: tauri::AppHandle, status: Option<String>) -> Result<Vec<crate::goals::Goal>> {
+    let conn = crate::database::open_conn(&app_handle)?;
+    Ok(crate::goals::list(&conn, status.as_deref())?)
+}
+
+// Reads new or edited entries for gratitude and best-moment sentences
+#[tauri::command]
+pub async fn extract_gratitude(app_handle: tauri::AppHandle, force: Option<bool>) -> Result<crate::gratitude::GratitudeReport> {
+    let mut conn = crate::database::open_conn(&app_handle)?;
+    Ok(crate::gratitude::extract_pending(&mut conn, force.unwrap_or(false))?)
+}
+
+// `kind` is "gratitude" or "highlight"; dates are inclusive YYYY-MM-DD days
+#[tauri::command]
+pub async fn list_gratitude(
+    app_handle: tauri::AppHandle,
+    kind: Option<String>,
+    date_from: Option<String>,
+    date_to: Option<String>,
+    limit: Option<u32>,
+    offset: Option<u32>,
+) -> Result<Vec<crate::gratitude::GratitudeItem>> {
+    let conn = crate::database::open_conn(&app_handle)?;
+    Ok(crate::gratitude::list(&conn, kind.as_deref(), date_from.as_deref(), date_to.as_deref(), limit.unwrap_or(100), offset.unwrap_or(0))?)
+}
+
+// Writes the items as a Markdown gratitude journal, oldest first, citing each entry
+#[tauri::command]
+pub async fn export_gratitude(
+    app_handle: tauri::AppHandle,
+    dest: String,
+    kind: Option<String>,
+    date_from: Option<String>,
+    date_to: Option<String>,
+) -> Result<crate::gratitude::GratitudeExportReport> {
+    let export_error = |e| AppError::from_error(AppErrorKind::Export, e).with_path(&dest);
+    let conn = crate::database::open_conn(&app_handle)?;
+    let target = crate::documents::ExportTarget::new(&app_handle, &dest).map_err(export_error)?;
+    let mut report = crate::gratitude::export(&conn, target.path(), kind.as_deref(), date_from.as_deref(), date_to.as_deref())
+        .map_err(export_error)?;
+    target.finish(&app_handle).map_err(export_error)?;
+    report.path = dest.clone();
+    Ok(report)
+}
+
+// Groups embedded entries into recurring topics; `label` (default true) names them with the chat provider
+#[tauri::command]
+pub async fn cluster_entries(
+    app_handle: tauri::AppHandle,
+    k: Option<u32>,
+    label: Option<bool>,
+) -> Result<crate::clustering::ClusteringResult> {
+    crate::clustering::cluster_entries(&app_handle, k, label.unwrap_or(true))
+        .await
+        .map_err(AppError::or(AppErrorKind::NotEmbedded))
+}
+
+#[tauri::command]
+pub async fn generate_writing_prompts(
+    app_handle: tauri::AppHandle,
+    count: Option<u32>,
+) -> Result<Vec<crate::prompts::WritingPrompt>> {
+    crate::prompts::generate_writing_prompts(&app_handle, count)
+        .await
+        .map_err(AppError::or(AppErrorKind::Ai))
+}
+
+// False when the stream already finished
+#[tauri::command]
+pub async fn cancel_ai_stream(stream_id: String) -> Result<bool> {
+    Ok(crate::ai_stream::cancel(&stream_id))
 }
 
 // Removed chat request in simplified app
@@ -117,27 +560,380 @@ pub async fn init_database(app_handle: tauri::AppHandle) -> Result<()> {
     crate::database::init_database(&app_handle).await?;
     // Backfill FTS on startup
     if let Err(e) = crate::database::ensure_fts_populated(&app_handle).await {
-        eprintln!("[fts] backfill error: {}", e);
+        tracing::warn!("search index backfill error: {}", e);
     }
+    // Entries may have been added outside the app (journal-reader-cli); both jobs skip done work
+    spawn_post_import(&app_handle);
     Ok(())
 }
 
 #[tauri::command]
 pub async fn get_settings(app_handle: tauri::AppHandle) -> Result<Vec<Setting>> {
-    let items = crate::database::get_settings(&app_handle).await.map_err(|e| crate::AppError { message: e.to_string(), code: Some("SETTINGS_READ".into()) })?;
+    let items = crate::database::get_settings(&app_handle).await.map_err(AppError::or(AppErrorKind::Settings))?;
     Ok(items.into_iter().map(|(key, value)| Setting { key, value }).collect())
 }
 
+// Settings that change which model embeds entries (the embedding provider can follow ai_provider)
+const EMBEDDING_SETTINGS: &[&str] = &["embedding_model", "embedding_provider", "ai_provider"];
+
 #[tauri::command]
 pub async fn update_setting(app_handle: tauri::AppHandle, key: String, value: String) -> Result<()> {
-    crate::database::update_setting(&app_handle, &key, &value).await.map_err(|e| crate::AppError { message: e.to_string(), code: Some("SETTINGS_WRITE".into()) })?;
+    // Secrets never go to the settings table
+    if crate::secrets::is_secret(&key) {
+        return set_secret(key, value).await;
+    }
+    crate::database::update_setting(&app_handle, &key, &value).await.map_err(AppError::or(AppErrorKind::Settings))?;
+    after_settings_changed(&app_handle, &[key]).await;
+    Ok(())
+}
+
+// Side effects of changed settings, shared by single updates and imports
+async fn after_settings_changed(app_handle: &tauri::AppHandle, keys: &[String]) {
+    let changed = |list: &[&str]| keys.iter().any(|k| list.contains(&k.as_str()));
+
+    if changed(crate::lan_sync::LISTENER_SETTINGS) {
+        crate::lan_sync::restart();
+    }
+
+    if changed(&["log_level"]) {
+        if let Ok(conn) = crate::database::open_conn(app_handle) {
+            crate::logging::apply_setting(&conn);
+        }
+    }
+
+    // Tokenizer changes only take effect after a reindex; run it in the background with progress events
+    if changed(crate::database::FTS_TOKENIZER_SETTINGS) && crate::database::fts_needs_rebuild(app_handle).await.unwrap_or(false) {
+        let handle = app_handle.clone();
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = rebuild_search_index(handle).await {
+                tracing::warn!("search reindex after setting change failed: {}", e.message);
+            }
+        });
+    }
+
+    // Stored vectors from the previous model (or chunking) no longer match queries; replace them
+    let embedding_setting = changed(EMBEDDING_SETTINGS) || changed(crate::chunks::CHUNK_SETTINGS);
+    if embedding_setting && auto_embed_enabled(app_handle) {
+        let pending = crate::embeddings::get_embedding_status(app_handle).await.map(|s| s.pending > 0 && !s.running);
+        if pending.unwrap_or(false) {
+            spawn_reembed(app_handle.clone(), false);
+        }
+    }
+}
+
+// Writes the non-secret settings to a JSON file for setting up another machine
+#[tauri::command]
+pub async fn export_settings(app_handle: tauri::AppHandle, path: String) -> Result<u32> {
+    let target = crate::documents::ExportTarget::new(&app_handle, &path).map_err(AppError::or(AppErrorKind::Settings))?;
+    let count = crate::backup::export_settings(&app_handle, target.path())
+        .await
+        .map_err(AppError::or(AppErrorKind::Settings))?;
+    target.finish(&app_handle).map_err(AppError::or(AppErrorKind::Settings))?;
+    Ok(count)
+}
+
+// Applies a settings file; API keys are never in it and stay as they are. Returns the changed keys.
+#[tauri::command]
+pub async fn import_settings(app_handle: tauri::AppHandle, path: String) -> Result<Vec<String>> {
+    let local = crate::documents::stage_one(&app_handle, &path).map_err(AppError::or(AppErrorKind::Settings))?;
+    let changed = crate::backup::import_settings(&app_handle, &local)
+        .await
+        .map_err(AppError::or(AppErrorKind::Settings))?;
+    after_settings_changed(&app_handle, &changed).await;
+    Ok(changed)
+}
+
+// Stores an API key or token in the OS keychain; an empty value removes it
+#[tauri::command]
+pub async fn set_secret(key: String, value: String) -> Result<()> {
+    crate::secrets::set(&key, &value).map_err(AppError::or(AppErrorKind::Keychain))
+}
+
+// Secrets are write-only from the UI; this only says whether one is stored
+#[tauri::command]
+pub async fn has_secret(key: String) -> Result<bool> {
+    crate::secrets::has(&key).map_err(AppError::or(AppErrorKind::Keychain))
+}
+
+#[tauri::command]
+pub async fn get_database_encryption_status(app_handle: tauri::AppHandle) -> Result<crate::database::EncryptionStatus> {
+    let status = crate::database::get_encryption_status(&app_handle).await.map_err(AppError::or(AppErrorKind::Encryption))?;
+    Ok(status)
+}
+
+#[tauri::command]
+pub async fn set_database_passphrase(app_handle: tauri::AppHandle, passphrase: String) -> Result<()> {
+    crate::database::set_database_passphrase(&app_handle, &passphrase).await.map_err(AppError::or(AppErrorKind::Encryption))?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn unlock_database(app_handle: tauri::AppHandle, passphrase: String) -> Result<bool> {
+    let ok = crate::database::unlock_database(&app_handle, &passphrase).await.map_err(AppError::or(AppErrorKind::Encryption))?;
+    if ok {
+        // Startup init was skipped while the database was locked
+        crate::database::init_database(&app_handle).await?;
+    }
+    Ok(ok)
+}
+
+#[tauri::command]
+pub async fn get_sensitive_status(app_handle: tauri::AppHandle) -> Result<crate::sensitive::SensitiveStatus> {
+    let conn = crate::database::open_conn(&app_handle)?;
+    crate::sensitive::status(&conn).map_err(AppError::or(AppErrorKind::Sensitive))
+}
+
+// Sets the passphrase for sensitive entries; changing it needs the current one
+#[tauri::command]
+pub async fn set_sensitive_passphrase(app_handle: tauri::AppHandle, current: Option<String>, passphrase: String) -> Result<()> {
+    let conn = crate::database::open_conn(&app_handle)?;
+    crate::sensitive::set_passphrase(&conn, current.as_deref(), &passphrase).map_err(AppError::or(AppErrorKind::Sensitive))
+}
+
+#[tauri::command]
+pub async fn unlock_sensitive(app_handle: tauri::AppHandle, passphrase: String) -> Result<()> {
+    let conn = crate::database::open_conn(&app_handle)?;
+    crate::sensitive::unlock(&conn, &passphrase).map_err(AppError::or(AppErrorKind::Sensitive))
+}
+
+#[tauri::command]
+pub async fn lock_sensitive() -> Result<()> {
+    crate::sensitive::lock();
     Ok(())
 }
 
+// Seals or restores an entry's body; both need sensitive entries unlocked
+#[tauri::command]
+pub async fn set_entry_sensitive(app_handle: tauri::AppHandle, id: String, sensitive: bool) -> Result<()> {
+    {
+        let conn = crate::database::open_conn(&app_handle)?;
+        if sensitive {
+            crate::sensitive::mark(&conn, &id).map_err(AppError::or(AppErrorKind::Sensitive))?;
+            return Ok(());
+        }
+        crate::sensitive::unmark(&conn, &id).map_err(AppError::or(AppErrorKind::Sensitive))?;
+    }
+    // The restored text is embedded and searched for people like an edit
+    if auto_embed_enabled(&app_handle) {
+        crate::edit_embeddings::schedule(&app_handle);
+    }
+    spawn_entity_extraction(&app_handle);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn list_journals(app_handle: tauri::AppHandle) -> Result<crate::journals::JournalList> {
+    let dir = crate::database::get_db_dir(&app_handle)?;
+    crate::journals::load(&dir).map_err(AppError::or(AppErrorKind::Database))
+}
+
+// Registers a journal with its own database; `path` defaults to the app data dir
+#[tauri::command]
+pub async fn create_journal(app_handle: tauri::AppHandle, name: String, path: Option<String>) -> Result<crate::journals::Journal> {
+    if cfg!(mobile) && path.as_deref().is_some_and(|p| !p.trim().is_empty()) {
+        return Err(AppError::new(AppErrorKind::InvalidInput, "On a phone journals are kept in the app's own storage"));
+    }
+    let dir = crate::database::get_db_dir(&app_handle)?;
+    crate::journals::create(&dir, &name, path.as_deref()).map_err(AppError::or(AppErrorKind::Database))
+}
+
+// Everything after this reads and writes the chosen journal; the UI reloads on `journal://switched`
+#[tauri::command]
+pub async fn switch_journal(app_handle: tauri::AppHandle, id: String) -> Result<crate::journals::Journal> {
+    use tauri::Emitter;
+    let journal = crate::database::switch_journal(&app_handle, &id)
+        .await
+        .map_err(AppError::or(AppErrorKind::Database))?;
+    if let Err(e) = crate::database::ensure_fts_populated(&app_handle).await {
+        tracing::warn!("search index backfill error: {}", e);
+    }
+    let _ = app_handle.emit("journal://switched", &journal);
+    Ok(journal)
+}
+
+// Entries from this month and day in earlier years; `date` (YYYY-MM-DD) defaults to today
+#[tauri::command]
+pub async fn get_on_this_day(app_handle: tauri::AppHandle, date: Option<String>) -> Result<crate::on_this_day::OnThisDay> {
+    let date = match date.as_deref().map(str::trim).filter(|d| !d.is_empty()) {
+        Some(d) => chrono::NaiveDate::parse_from_str(d, "%Y-%m-%d")
+            .map_err(|e| AppError::new(AppErrorKind::InvalidInput, format!("Invalid date: {}", e)))?,
+        None => chrono::Local::now().date_naive(),
+    };
+    crate::on_this_day::get(&app_handle, date)
+        .await
+        .map_err(AppError::or(AppErrorKind::Database))
+}
+
+#[tauri::command]
+pub async fn list_templates(app_handle: tauri::AppHandle) -> Result<Vec<crate::templates::EntryTemplate>> {
+    let conn = crate::database::open_conn(&app_handle)?;
+    Ok(crate::templates::list(&conn)?)
+}
+
+// Creates a template, or updates the one with `id`
+#[tauri::command]
+pub async fn save_template(
+    app_handle: tauri::AppHandle,
+    id: Option<String>,
+    name: String,
+    title: Option<String>,
+    body: String,
+) -> Result<crate::templates::EntryTemplate> {
+    let conn = crate::database::open_conn(&app_handle)?;
+    crate::templates::save(&conn, id.as_deref(), &name, title.as_deref(), &body)
+        .map_err(AppError::or(AppErrorKind::InvalidInput))
+}
+
+#[tauri::command]
+pub async fn delete_template(app_handle: tauri::AppHandle, id: String) -> Result<bool> {
+    let conn = crate::database::open_conn(&app_handle)?;
+    Ok(crate::templates::delete(&conn, &id)?)
+}
+
+// New entry for `date` (YYYY-MM-DD, default today) with the template's variables filled in
+#[tauri::command]
+pub async fn create_entry_from_template(
+    app_handle: tauri::AppHandle,
+    template_id: String,
+    date: Option<String>,
+    timezone: Option<String>,
+) -> Result<crate::templates::TemplateEntry> {
+    let date = match date.as_deref().map(str::trim).filter(|d| !d.is_empty()) {
+        Some(d) => chrono::NaiveDate::parse_from_str(d, "%Y-%m-%d")
+            .map_err(|e| AppError::new(AppErrorKind::InvalidInput, format!("Invalid date: {}", e)))?,
+        None => chrono::Local::now().date_naive(),
+    };
+    let created = crate::templates::create_entry(&app_handle, &template_id, date, timezone)
+        .await
+        .map_err(AppError::or(AppErrorKind::Database))?;
+    if created.created {
+        spawn_post_import(&app_handle);
+    }
+    Ok(created)
+}
+
+#[tauri::command]
+pub async fn update_entry(
+    app_handle: tauri::AppHandle,
+    id: String,
+    title: Option<String>,
+    body: String,
+    overwrite_source: Option<bool>,
+) -> Result<EntryPreview> {
+    let e = crate::database::update_entry(&app_handle, &id, title, &body, overwrite_source.unwrap_or(false))
+        .await
+        .map_err(|e| AppError::from_error(AppErrorKind::Database, e).with_entry(&id))?;
+    // The new text is re-embedded by the edit worker once the edits settle
+    if auto_embed_enabled(&app_handle) {
+        crate::edit_embeddings::schedule(&app_handle);
+    }
+    spawn_entity_extraction(&app_handle);
+    let sensitive = crate::sensitive::is_sensitive(&*crate::database::open_conn(&app_handle)?, &id)?;
+    Ok(EntryPreview {
+        id: e.id,
+        title: e.title,
+        preview: if sensitive { body } else { e.body },
+        entry_date: e.entry_date.to_rfc3339(),
+        tags: vec![],
+        snippets: vec![],
+        sensitive,
+    })
+}
+
+// Earlier versions of an entry, oldest first, ending with the current text
+#[tauri::command]
+pub async fn list_entry_versions(app_handle: tauri::AppHandle, id: String) -> Result<Vec<crate::versions::EntryVersion>> {
+    let conn = crate::database::open_conn(&app_handle)?;
+    crate::versions::list(&conn, &id).map_err(AppError::or(AppErrorKind::Database))
+}
+
+// Word-level changes between two versions of an entry (numbers from list_entry_versions)
+#[tauri::command]
+pub async fn diff_entry_versions(app_handle: tauri::AppHandle, id: String, v1: i64, v2: i64) -> Result<crate::versions::EntryDiff> {
+    let conn = crate::database::open_conn(&app_handle)?;
+    crate::versions::diff(&conn, &id, v1, v2).map_err(AppError::or(AppErrorKind::Database))
+}
+
+// Sends a `test` event to the configured webhook and/or script and reports each outcome
+#[tauri::command]
+pub async fn test_hooks(app_handle: tauri::AppHandle) -> Result<Vec<crate::hooks::HookResult>> {
+    crate::hooks::test(&app_handle)
+        .await
+        .map_err(AppError::or(AppErrorKind::Network))
+}
+
+// Re-reads local source files edited since import and updates their entries in place; `force`
+// re-reads every file regardless of its modification time
+#[tauri::command]
+pub async fn resync_from_sources(app_handle: tauri::AppHandle, force: Option<bool>) -> Result<crate::resync::ResyncReport> {
+    let report = crate::resync::resync_from_sources(&app_handle, force.unwrap_or(false))
+        .await
+        .map_err(AppError::or(AppErrorKind::Sync))?;
+    if report.updated > 0 {
+        spawn_post_import(&app_handle);
+    }
+    Ok(report)
+}
+
+// Pairs of entries with nearly the same text; `threshold` (0-1) is how much of the shorter one
+// must appear in the other
+#[tauri::command]
+pub async fn find_near_duplicates(app_handle: tauri::AppHandle, threshold: Option<f64>) -> Result<Vec<crate::near_duplicates::NearDuplicatePair>> {
+    let threshold = threshold.unwrap_or(crate::near_duplicates::DEFAULT_THRESHOLD).clamp(0.5, 1.0);
+    crate::near_duplicates::find_near_duplicates(&app_handle, threshold)
+        .await
+        .map_err(AppError::or(AppErrorKind::Database))
+}
+
+#[tauri::command]
+pub async fn merge_near_duplicates(
+    app_handle: tauri::AppHandle,
+    keep_id: String,
+    remove_id: String,
+    use_removed_text: bool,
+) -> Result<crate::near_duplicates::MergeReport> {
+    let report = crate::near_duplicates::merge_near_duplicates(&app_handle, &keep_id, &remove_id, use_removed_text)
+        .await
+        .map_err(AppError::or(AppErrorKind::Database))?;
+    if report.text_replaced {
+        spawn_post_import(&app_handle);
+    }
+    Ok(report)
+}
+
+#[tauri::command]
+pub async fn dismiss_near_duplicate(app_handle: tauri::AppHandle, entry_a: String, entry_b: String) -> Result<()> {
+    crate::near_duplicates::dismiss(&app_handle, &entry_a, &entry_b)
+        .await
+        .map_err(AppError::or(AppErrorKind::Database))
+}
+
+// Dates read from the selected entries' titles, file names and opening lines that differ from
+// their current dates; nothing is changed until apply_redates
+#[tauri::command]
+pub async fn propose_redates(app_handle: tauri::AppHandle, selection: crate::redate::RedateSelection) -> Result<Vec<crate::redate::RedateProposal>> {
+    let conn = crate::database::open_conn(&app_handle)?;
+    crate::redate::propose(&conn, &selection).map_err(AppError::or(AppErrorKind::Database))
+}
+
+#[tauri::command]
+pub async fn apply_redates(app_handle: tauri::AppHandle, changes: Vec<crate::redate::RedateChange>) -> Result<crate::redate::RedateReport> {
+    let conn = crate::database::open_conn(&app_handle)?;
+    crate::redate::apply(&conn, &changes).map_err(AppError::or(AppErrorKind::Database))
+}
+
+// Moves the active journal's database into another folder; the app keeps running on the copy
+#[tauri::command]
+pub async fn relocate_database(app_handle: tauri::AppHandle, folder: String) -> Result<crate::database::RelocationReport> {
+    crate::database::relocate_database(&app_handle, &folder)
+        .await
+        .map_err(AppError::or(AppErrorKind::Database))
+}
+
 #[tauri::command]
 pub async fn test_ai_connection(app_handle: tauri::AppHandle) -> Result<bool> {
     use std::time::Duration;
-    let settings = crate::database::get_settings(&app_handle).await.map_err(|e| crate::AppError { message: e.to_string(), code: Some("SETTINGS_READ".into()) })?;
+    let settings = crate::database::get_settings(&app_handle).await.map_err(AppError::or(AppErrorKind::Settings))?;
     let mut provider = "ollama".to_string();
     let mut ollama_url = "http://localhost:11434".to_string();
     for (k, v) in settings {
@@ -148,25 +944,27 @@ pub async fn test_ai_connection(app_handle: tauri::AppHandle) -> Result<bool> {
     if provider != "ollama" { return Ok(false); }
 
     let url = format!("{}/api/tags", ollama_url.trim_end_matches('/'));
-    let client = reqwest::Client::builder().timeout(Duration::from_secs(3)).build().map_err(|e| crate::AppError { message: e.to_string(), code: Some("HTTP".into()) })?;
+    let client = reqwest::Client::builder().timeout(Duration::from_secs(3)).build().map_err(AppError::or(AppErrorKind::Network))?;
     match client.get(url).send().await {
         Ok(resp) => Ok(resp.status().is_success() || resp.status().as_u16() == 404),
         Err(_) => Ok(false),
     }
 }
 
+#[tauri::command]
+pub async fn list_ollama_models(app_handle: tauri::AppHandle) -> Result<Vec<crate::ai::OllamaModel>> {
+    let settings = crate::ai::AiSettings::from_app(&app_handle).map_err(AppError::or(AppErrorKind::Settings))?;
+    crate::ai::list_ollama_models(&settings).await.map_err(|e| AppError::from_error(AppErrorKind::Ai, e).with_provider(&crate::ai::Provider::Ollama))
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GoogleOAuthStatus {
     pub connected: bool,
 }
 
 #[tauri::command]
-pub async fn get_google_oauth_status(app_handle: tauri::AppHandle) -> Result<GoogleOAuthStatus> {
-    let settings = crate::database::get_settings(&app_handle).await.map_err(|e| crate::AppError { message: e.to_string(), code: Some("SETTINGS_READ".into()) })?;
-    let mut has_token = false;
-    for (k, _) in settings {
-        if k == "google_access_token" { has_token = true; break; }
-    }
+pub async fn get_google_oauth_status() -> Result<GoogleOAuthStatus> {
+    let has_token = crate::secrets::has("google_access_token").map_err(AppError::or(AppErrorKind::Keychain))?;
     Ok(GoogleOAuthStatus { connected: has_token })
 }
 
@@ -175,18 +973,23 @@ pub struct GoogleOAuthInit {
     pub auth_url: String,
     pub state: String,
     pub code_verifier: String,
+    // Whether the loopback callback listener is up; if not, the code has to be pasted in
+    pub listening: bool,
+    // On iOS and Android the sign-in comes back as an app link; the result arrives on
+    // `google://oauth-complete`
+    pub app_redirect: bool,
 }
 
 #[tauri::command]
 pub async fn google_oauth_start(app_handle: tauri::AppHandle) -> Result<GoogleOAuthInit> {
     use rand::{distributions::Alphanumeric, Rng};
-    let settings = crate::database::get_settings(&app_handle).await.map_err(|e| crate::AppError { message: e.to_string(), code: Some("SETTINGS_READ".into()) })?;
+    let settings = crate::database::get_settings(&app_handle).await.map_err(AppError::or(AppErrorKind::Settings))?;
     let mut client_id = String::new();
     for (k, v) in settings {
         if k == "google_client_id" { client_id = v; }
     }
     if client_id.is_empty() {
-        return Err(crate::AppError { message: "Missing Google Client ID in settings".into(), code: Some("GOOGLE_CLIENT_ID".into()) });
+        return Err(AppError::new(AppErrorKind::Auth, "Missing Google Client ID in settings"));
     }
 
     // PKCE code_verifier and challenge
@@ -195,8 +998,9 @@ pub async fn google_oauth_start(app_handle: tauri::AppHandle) -> Result<GoogleOA
     let challenge = base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(sha);
     let state: String = rand::thread_rng().sample_iter(&Alphanumeric).take(24).map(char::from).collect();
 
-    // Loopback redirect
-    let redirect_uri = "http://127.0.0.1:8765/callback";
+    // Loopback redirect, listening before the browser opens (an app link on mobile)
+    let redirect_uri = crate::oauth_loopback::redirect_uri();
+    let listening = crate::oauth_loopback::begin(&state, &code_verifier).await;
     let scope = urlencoding::encode("https://www.googleapis.com/auth/drive.readonly");
     let auth_url = format!(
         "https://accounts.google.com/o/oauth2/v2/auth?response_type=code&client_id={}&redirect_uri={}&scope={}&access_type=offline&prompt=consent&code_challenge_method=S256&code_challenge={}&state={}",
@@ -207,51 +1011,90 @@ pub async fn google_oauth_start(app_handle: tauri::AppHandle) -> Result<GoogleOA
         state
     );
 
-    Ok(GoogleOAuthInit { auth_url, state, code_verifier })
+    Ok(GoogleOAuthInit { auth_url, state, code_verifier, listening, app_redirect: cfg!(mobile) })
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GoogleOAuthCompleteRequest {
     pub code: String,
     pub state: String,
-    pub code_verifier: String,
 }
 
+// Completes a sign-in from a pasted code; the state must match the pending sign-in
 #[tauri::command]
 pub async fn google_oauth_complete(app_handle: tauri::AppHandle, req: GoogleOAuthCompleteRequest) -> Result<bool> {
+    let code_verifier = crate::oauth_loopback::take_verifier(&req.state).await
+        .map_err(AppError::or(AppErrorKind::Auth))?;
+    google_exchange_code(&app_handle, &req.code, &code_verifier).await
+}
+
+// Waits for the browser to hit the loopback callback, then exchanges the code
+#[tauri::command]
+pub async fn google_oauth_wait(app_handle: tauri::AppHandle, state: String) -> Result<bool> {
+    let (code, code_verifier) = crate::oauth_loopback::wait(&state).await
+        .map_err(AppError::or(AppErrorKind::Auth))?;
+    google_exchange_code(&app_handle, &code, &code_verifier).await
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GoogleOAuthOutcome {
+    pub connected: bool,
+    pub error: Option<AppError>,
+}
+
+// Finishes a mobile sign-in from the `<app id>:/oauth2redirect?code=...` link the app was opened
+// with and reports it on `google://oauth-complete`; other links are ignored
+pub(crate) async fn google_oauth_app_link(app_handle: tauri::AppHandle, url: String) {
+    use tauri::Emitter;
+    let outcome = match crate::oauth_loopback::redirect_code(&url).await {
+        Ok(None) => return,
+        Ok(Some((code, code_verifier))) => google_exchange_code(&app_handle, &code, &code_verifier).await,
+        Err(e) => Err(AppError::from_error(AppErrorKind::Auth, e)),
+    };
+    let outcome = match outcome {
+        Ok(connected) => GoogleOAuthOutcome { connected, error: None },
+        Err(e) => {
+            tracing::warn!("Google sign-in failed: {}", e);
+            GoogleOAuthOutcome { connected: false, error: Some(e) }
+        }
+    };
+    let _ = app_handle.emit("google://oauth-complete", outcome);
+}
+
+async fn google_exchange_code(app_handle: &tauri::AppHandle, code: &str, code_verifier: &str) -> Result<bool> {
     // Exchange code for tokens
-    let settings = crate::database::get_settings(&app_handle).await.map_err(|e| crate::AppError { message: e.to_string(), code: Some("SETTINGS_READ".into()) })?;
+    let settings = crate::database::get_settings(app_handle).await.map_err(AppError::or(AppErrorKind::Settings))?;
     let mut client_id = String::new();
-    for (k, v) in settings.clone() {
+    for (k, v) in settings {
         if k == "google_client_id" { client_id = v; }
     }
     if client_id.is_empty() {
-        return Err(crate::AppError { message: "Missing Google Client ID in settings".into(), code: Some("GOOGLE_CLIENT_ID".into()) });
+        return Err(AppError::new(AppErrorKind::Auth, "Missing Google Client ID in settings"));
     }
-    let redirect_uri = "http://127.0.0.1:8765/callback";
+    let redirect_uri = crate::oauth_loopback::redirect_uri();
     let token_url = "https://oauth2.googleapis.com/token";
     let client = reqwest::Client::new();
     let params = [
         ("grant_type", "authorization_code"),
-        ("code", req.code.as_str()),
+        ("code", code),
         ("client_id", client_id.as_str()),
         ("redirect_uri", redirect_uri),
-        ("code_verifier", req.code_verifier.as_str()),
+        ("code_verifier", code_verifier),
     ];
-    let resp = client.post(token_url).form(&params).send().await.map_err(|e| crate::AppError { message: e.to_string(), code: Some("HTTP".into()) })?;
+    let resp = client.post(token_url).form(&params).send().await.map_err(AppError::or(AppErrorKind::Network))?;
     if !resp.status().is_success() {
-        return Err(crate::AppError { message: format!("Token exchange failed: {}", resp.status()), code: Some("TOKEN".into()) });
+        return Err(AppError::new(AppErrorKind::Auth, format!("Token exchange failed: {}", resp.status())));
     }
-    let json: serde_json::Value = resp.json().await.map_err(|e| crate::AppError { message: e.to_string(), code: Some("JSON".into()) })?;
+    let json: serde_json::Value = resp.json().await.map_err(AppError::or(AppErrorKind::Network))?;
     let access = json.get("access_token").and_then(|v| v.as_str()).unwrap_or("").to_string();
     let refresh = json.get("refresh_token").and_then(|v| v.as_str()).unwrap_or("").to_string();
     if access.is_empty() {
         return Ok(false);
     }
     // Store tokens
-    crate::database::update_setting(&app_handle, "google_access_token", &access).await.map_err(|e| crate::AppError { message: e.to_string(), code: Some("SETTINGS_WRITE".into()) })?;
+    crate::secrets::set("google_access_token", &access).map_err(AppError::or(AppErrorKind::Keychain))?;
     if !refresh.is_empty() {
-        let _ = crate::database::update_setting(&app_handle, "google_refresh_token", &refresh).await;
+        crate::secrets::set("google_refresh_token", &refresh).map_err(AppError::or(AppErrorKind::Keychain))?;
     }
     Ok(true)
 }
@@ -259,13 +1102,11 @@ pub async fn google_oauth_complete(app_handle: tauri::AppHandle, req: GoogleOAut
 async fn google_get_valid_access_token(app_handle: &tauri::AppHandle) -> std::result::Result<String, anyhow::Error> {
     let settings = crate::database::get_settings(app_handle).await?;
     let mut client_id = String::new();
-    let mut access = String::new();
-    let mut refresh = String::new();
     for (k, v) in settings {
         if k == "google_client_id" { client_id = v; }
-        else if k == "google_access_token" { access = v; }
-        else if k == "google_refresh_token" { refresh = v; }
     }
+    let access = crate::secrets::get("google_access_token")?.unwrap_or_default();
+    let refresh = crate::secrets::get("google_refresh_token")?.unwrap_or_default();
     if access.is_empty() && refresh.is_empty() { return Err(anyhow::anyhow!("No Google tokens")); }
     // Try a lightweight call to validate access token
     if !access.is_empty() {
@@ -289,7 +1130,9 @@ async fn google_get_valid_access_token(app_handle: &tauri::AppHandle) -> std::re
         let new_access = json.get("access_token").and_then(|v| v.as_str()).unwrap_or("").to_string();
         if new_access.is_empty() { return Err(anyhow::anyhow!("No access_token in refresh response")); }
         // Persist
-        let _ = crate::database::update_setting(app_handle, "google_access_token", &new_access).await;
+        if let Err(e) = crate::secrets::set("google_access_token", &new_access) {
+            tracing::warn!("could not store refreshed Google token: {}", e);
+        }
         return Ok(new_access);
     }
     Err(anyhow::anyhow!("No valid Google token"))
@@ -305,44 +1148,19 @@ pub struct ImportGDocByIdRequest {
 #[tauri::command]
 pub async fn google_import_doc_by_file_id(app_handle: tauri::AppHandle, req: ImportGDocByIdRequest) -> Result<String> {
     use chrono::{DateTime, Utc};
-    use crate::import::{ParsedFile, FileType, normalize_content};
-    use sha2::Sha256;
 
     let access = google_get_valid_access_token(&app_handle).await
-        .map_err(|e| crate::AppError { message: format!("Google token error: {}", e), code: Some("GOOGLE_TOKEN".into()) })?;
+        .map_err(|e| AppError::new(AppErrorKind::Auth, format!("Google token error: {}", e)))?;
 
-    // Try text export first
-    let base = format!("https://www.googleapis.com/drive/v3/files/{}", req.file_id);
-    let txt_url = format!("{}/export?mimeType=text/plain", base);
     let client = reqwest::Client::new();
-    let mut content = String::new();
-    let resp = client.get(&txt_url).bearer_auth(&access).send().await
-        .map_err(|e| crate::AppError { message: e.to_string(), code: Some("HTTP".into()) })?;
-    if resp.status().is_success() {
-        content = resp.text().await.unwrap_or_default();
-    } else {
-        // Fallback to docx export
-        let docx_url = format!("{}/export?mimeType=application/vnd.openxmlformats-officedocument.wordprocessingml.document", base);
-        let resp2 = client.get(&docx_url).bearer_auth(&access).send().await
-            .map_err(|e| crate::AppError { message: e.to_string(), code: Some("HTTP".into()) })?;
-        if resp2.status().is_success() {
-            let bytes = resp2.bytes().await.unwrap_or_default();
-            let tmp = std::env::temp_dir().join(format!("{}.docx", req.file_id));
-            let _ = std::fs::write(&tmp, &bytes);
-            if let Ok(text) = crate::import::parse_docx_file(tmp.to_string_lossy().as_ref()).await {
-                content = text;
-            }
-            let _ = std::fs::remove_file(&tmp);
-        }
-    }
+    let content = crate::gdrive::export_doc_text(&client, &access, &req.file_id).await
+        .map_err(AppError::or(AppErrorKind::Network))?;
     if content.trim().is_empty() {
-        return Err(crate::AppError { message: "Failed to export Google Doc content".into(), code: Some("GDRIVE_EXPORT".into()) });
+        return Err(AppError::new(AppErrorKind::Network, "Failed to export Google Doc content"));
     }
 
-    let content = normalize_content(&content);
-
     // Optionally fetch file name for title
-    let meta_url = format!("{}?fields=name", base);
+    let meta_url = format!("https://www.googleapis.com/drive/v3/files/{}?fields=name", req.file_id);
     let title = match client.get(&meta_url).bearer_auth(&access).send().await {
         Ok(resp) => match resp.json::<serde_json::Value>().await {
             Ok(j) => j.get("name").and_then(|v| v.as_str()).map(|s| s.to_string()),
@@ -350,36 +1168,55 @@ pub async fn google_import_doc_by_file_id(app_handle: tauri::AppHandle, req: Imp
         },
         Err(_) => None,
     };
-
-    // Build ParsedFile
-    let mut hasher = Sha256::new();
-    hasher.update(content.as_bytes());
-    let text_hash = format!("{:x}", hasher.finalize());
-    let parsed = ParsedFile {
-        path: format!("gdrive:{}", req.file_id),
-        content: content.clone(),
-        title,
-        file_type: FileType::Txt,
-        text_hash,
-        size_bytes: content.len() as u64,
-    };
+    let parsed = crate::gdrive::parsed_doc(&req.file_id, title, &content);
 
     // Parse date
     let entry_date = DateTime::parse_from_rfc3339(&req.entry_date)
-        .map_err(|e| crate::AppError { message: format!("Invalid date: {}", e), code: Some("DATE".into()) })?
+        .map_err(|e| AppError::new(AppErrorKind::InvalidInput, format!("Invalid date: {}", e)))?
         .with_timezone(&Utc);
 
     let id = crate::database::save_entry(&app_handle, parsed, entry_date, req.entry_timezone).await
-        .map_err(|e| crate::AppError { message: e.to_string(), code: Some("SAVE".into()) })?;
+        .map_err(AppError::or(AppErrorKind::Database))?;
+    crate::hooks::emit(&app_handle, "entry-imported", serde_json::json!({ "source": "gdrive", "imported": 1, "failed": 0 }));
     Ok(id)
 }
 
+// Imports every Google Doc in a Drive folder, dating each from its title or else its modified
+// time. Emits `gdrive://import-progress` with FolderImportProgress after each file.
+#[tauri::command]
+pub async fn google_import_folder(
+    app_handle: tauri::AppHandle,
+    folder_id: String,
+    timezone: Option<String>,
+) -> Result<crate::gdrive::FolderImportReport> {
+    use tauri::Emitter;
+    let access = google_get_valid_access_token(&app_handle).await
+        .map_err(|e| AppError::new(AppErrorKind::Auth, format!("Google token error: {}", e)))?;
+    let timezone = timezone.filter(|t| !t.trim().is_empty()).unwrap_or_else(|| "UTC".to_string());
+    let emitter = app_handle.clone();
+    let report = crate::gdrive::import_folder(&app_handle, &access, folder_id.trim(), &timezone, move |progress| {
+        let _ = emitter.emit("gdrive://import-progress", progress);
+    })
+    .await
+    .map_err(AppError::or(AppErrorKind::Network))?;
+    if report.imported > 0 {
+        spawn_post_import(&app_handle);
+        crate::hooks::emit(
+            &app_handle,
+            "entry-imported",
+            serde_json::json!({ "source": "gdrive", "imported": report.imported, "duplicates": report.duplicates, "failed": report.failed }),
+        );
+    }
+    Ok(report)
+}
+
 #[tauri::command]
-pub async fn scan_import_files(_app_handle: tauri::AppHandle, paths: Vec<String>) -> Result<Vec<FileImportItem>> {
+pub async fn scan_import_files(app_handle: tauri::AppHandle, paths: Vec<String>) -> Result<Vec<FileImportItem>> {
     use crate::import::{parse_file, FileType};
     use std::path::Path;
     use walkdir::WalkDir;
     
+    let paths = crate::documents::stage(&app_handle, &paths).map_err(AppError::or(AppErrorKind::Import))?;
     let mut files = Vec::new();
     
     for path_str in paths {
@@ -423,42 +1260,493 @@ pub async fn scan_import_files(_app_handle: tauri::AppHandle, paths: Vec<String>
     Ok(files)
 }
 
+// Emits `import://file-progress` with FileImportProgress after each file
 #[tauri::command]
 pub async fn import_files_with_dates(
     app_handle: tauri::AppHandle, 
     files: Vec<FileWithDate>
 ) -> Result<ImportResult> {
-    // use chrono::{DateTime, Utc};
+    use tauri::Emitter;
     let mut imported = 0u32;
     let mut failed = 0u32;
     let mut errors: Vec<String> = Vec::new();
 
-    for file in files {
-        match process_single_file(&app_handle, file).await {
-            Ok(_) => imported += 1,
-                Err(e) => {
-                    failed += 1;
-                errors.push(e.message);
+    // .gdoc link files import their full text through Drive when Google is connected
+    let has_gdocs = files.iter().any(|f| f.path.to_lowercase().ends_with(".gdoc"));
+    let google_access = if has_gdocs { google_get_valid_access_token(&app_handle).await.ok() } else { None };
+
+    let total = files.len() as u32;
+    for (i, file) in files.into_iter().enumerate() {
+        let path = file.path.clone();
+        let (outcome, error, error_code) = match process_single_file(&app_handle, file, google_access.as_deref()).await {
+            Ok(_) => {
+                imported += 1;
+                ("imported", None, None)
             }
-        }
+            Err(e) => {
+                failed += 1;
+                if e.kind != AppErrorKind::Duplicate {
+                    tracing::warn!("import of {} failed: {}", path, e.message);
+                }
+                errors.push(e.message.clone());
+                let outcome = if e.kind == AppErrorKind::Duplicate { "duplicate" } else { "failed" };
+                (outcome, Some(e.message), Some(e.kind))
+            }
+        };
+        let _ = app_handle.emit(
+            "import://file-progress",
+            FileImportProgress { path, outcome: outcome.into(), error, error_code, processed: i as u32 + 1, total, imported, failed },
+        );
+    }
+
+    if imported > 0 {
+        spawn_post_import(&app_handle);
+        crate::hooks::emit(&app_handle, "entry-imported", serde_json::json!({ "source": "files", "imported": imported, "failed": failed }));
     }
 
     Ok(ImportResult { imported, failed, errors: if errors.is_empty() { None } else { Some(errors) } })
 }
 
+// Imports Google Keep notes from a Takeout export (the Keep folder or individual note .json files)
+#[tauri::command]
+pub async fn import_keep_takeout(
+    app_handle: tauri::AppHandle,
+    paths: Vec<String>,
+    timezone: Option<String>,
+) -> Result<crate::keep::KeepImportReport> {
+    let timezone = timezone.filter(|t| !t.trim().is_empty()).unwrap_or_else(|| "UTC".to_string());
+    let paths = crate::documents::stage(&app_handle, &paths).map_err(AppError::or(AppErrorKind::Import))?;
+    let report = crate::keep::import_takeout(&app_handle, &paths, &timezone).await
+        .map_err(AppError::or(AppErrorKind::Import))?;
+    if report.imported > 0 {
+        spawn_post_import(&app_handle);
+        crate::hooks::emit(
+            &app_handle,
+            "entry-imported",
+            serde_json::json!({ "source": "keep", "imported": report.imported, "duplicates": report.duplicates, "failed": report.failed }),
+        );
+    }
+    Ok(report)
+}
+
+// Imports Diaro backups (the .zip or its DiaroBackup.xml); folders and tags become tags
+#[tauri::command]
+pub async fn import_diaro_export(
+    app_handle: tauri::AppHandle,
+    paths: Vec<String>,
+    timezone: Option<String>,
+) -> Result<crate::app_import::AppImportReport> {
+    let timezone = timezone.filter(|t| !t.trim().is_empty()).unwrap_or_else(|| "UTC".to_string());
+    let paths = crate::documents::stage(&app_handle, &paths).map_err(AppError::or(AppErrorKind::Import))?;
+    let report = crate::diaro::import_backup(&app_handle, &paths, &timezone).await
+        .map_err(AppError::or(AppErrorKind::Import))?;
+    if report.imported > 0 {
+        spawn_post_import(&app_handle);
+        crate::hooks::emit(
+            &app_handle,
+            "entry-imported",
+            serde_json::json!({ "source": "diaro", "imported": report.imported, "duplicates": report.duplicates, "failed": report.failed }),
+        );
+    }
+    Ok(report)
+}
+
+// Imports Daylio CSV exports; moods set the sentiment, activities become tags
+#[tauri::command]
+pub async fn import_daylio_csv(
+    app_handle: tauri::AppHandle,
+    paths: Vec<String>,
+    timezone: Option<String>,
+) -> Result<crate::app_import::AppImportReport> {
+    let timezone = timezone.filter(|t| !t.trim().is_empty()).unwrap_or_else(|| "UTC".to_string());
+    let paths = crate::documents::stage(&app_handle, &paths).map_err(AppError::or(AppErrorKind::Import))?;
+    let report = crate::daylio::import_csv(&app_handle, &paths, &timezone).await
+        .map_err(AppError::or(AppErrorKind::Import))?;
+    if report.imported > 0 {
+        spawn_post_import(&app_handle);
+        crate::hooks::emit(
+            &app_handle,
+            "entry-imported",
+            serde_json::json!({ "source": "daylio", "imported": report.imported, "duplicates": report.duplicates, "failed": report.failed }),
+        );
+    }
+    Ok(report)
+}
+
+// Turns a photo folder into dated stub entries, one per day with photos (see photos.rs)
+#[tauri::command]
+pub async fn import_photo_folder(
+    app_handle: tauri::AppHandle,
+    paths: Vec<String>,
+    timezone: Option<String>,
+    options: Option<crate::photos::PhotoOptions>,
+) -> Result<crate::app_import::AppImportReport> {
+    let timezone = timezone.filter(|t| !t.trim().is_empty()).unwrap_or_else(|| "UTC".to_string());
+    let paths = crate::documents::stage(&app_handle, &paths).map_err(AppError::or(AppErrorKind::Import))?;
+    let report = crate::photos::import_folders(&app_handle, &paths, &timezone, &options.unwrap_or_default()).await
+        .map_err(AppError::or(AppErrorKind::Import))?;
+    if report.imported > 0 {
+        spawn_post_import(&app_handle);
+        crate::hooks::emit(
+            &app_handle,
+            "entry-imported",
+            serde_json::json!({ "source": "photos", "imported": report.imported, "duplicates": report.duplicates, "failed": report.failed }),
+        );
+    }
+    Ok(report)
+}
+
+// Imports LiveJournal/Dreamwidth XML exports: any number of files, or a folder of monthly ones
+#[tauri::command]
+pub async fn import_livejournal_export(
+    app_handle: tauri::AppHandle,
+    paths: Vec<String>,
+    timezone: Option<String>,
+) -> Result<crate::app_import::AppImportReport> {
+    let timezone = timezone.filter(|t| !t.trim().is_empty()).unwrap_or_else(|| "UTC".to_string());
+    let paths = crate::documents::stage(&app_handle, &paths).map_err(AppError::or(AppErrorKind::Import))?;
+    let report = crate::livejournal::import_exports(&app_handle, &paths, &timezone).await
+        .map_err(AppError::or(AppErrorKind::Import))?;
+    if report.imported > 0 {
+        spawn_post_import(&app_handle);
+        crate::hooks::emit(
+            &app_handle,
+            "entry-imported",
+            serde_json::json!({ "source": "livejournal", "imported": report.imported, "duplicates": report.duplicates, "failed": report.failed }),
+        );
+    }
+    Ok(report)
+}
+
+// Imports WordPress (WXR) and Blogger (Atom) exports; the format is detected per file
+#[tauri::command]
+pub async fn import_blog_export(
+    app_handle: tauri::AppHandle,
+    paths: Vec<String>,
+    timezone: Option<String>,
+) -> Result<crate::app_import::AppImportReport> {
+    let timezone = timezone.filter(|t| !t.trim().is_empty()).unwrap_or_else(|| "UTC".to_string());
+    let paths = crate::documents::stage(&app_handle, &paths).map_err(AppError::or(AppErrorKind::Import))?;
+    let report = crate::blog::import_exports(&app_handle, &paths, &timezone).await
+        .map_err(AppError::or(AppErrorKind::Import))?;
+    if report.imported > 0 {
+        spawn_post_import(&app_handle);
+        crate::hooks::emit(
+            &app_handle,
+            "entry-imported",
+            serde_json::json!({ "source": "blog", "imported": report.imported, "duplicates": report.duplicates, "failed": report.failed }),
+        );
+    }
+    Ok(report)
+}
+
+// Imports posts and notes from a Facebook "Download Your Information" JSON export
+#[tauri::command]
+pub async fn import_facebook_export(
+    app_handle: tauri::AppHandle,
+    paths: Vec<String>,
+    timezone: Option<String>,
+) -> Result<crate::app_import::AppImportReport> {
+    let timezone = timezone.filter(|t| !t.trim().is_empty()).unwrap_or_else(|| "UTC".to_string());
+    let paths = crate::documents::stage(&app_handle, &paths).map_err(AppError::or(AppErrorKind::Import))?;
+    let report = crate::facebook::import_export(&app_handle, &paths, &timezone).await
+        .map_err(AppError::or(AppErrorKind::Import))?;
+    if report.imported > 0 {
+        spawn_post_import(&app_handle);
+        crate::hooks::emit(
+            &app_handle,
+            "entry-imported",
+            serde_json::json!({ "source": "facebook", "imported": report.imported, "duplicates": report.duplicates, "failed": report.failed }),
+        );
+    }
+    Ok(report)
+}
+
+// Conversations in an SMS Backup & Restore file, most messages first, for choosing one to import
+#[tauri::command]
+pub async fn list_sms_contacts(app_handle: tauri::AppHandle, path: String) -> Result<Vec<crate::sms::SmsContact>> {
+    crate::documents::stage_one(&app_handle, &path)
+        .and_then(|local| Ok(std::fs::read_to_string(local)?))
+        .and_then(|xml| crate::sms::list_contacts(&xml))
+        .map_err(|e| AppError::from_error(AppErrorKind::Import, e).with_path(&path))
+}
+
+// Imports SMS Backup & Restore files as one entry per conversation and day
+#[tauri::command]
+pub async fn import_sms_backup(
+    app_handle: tauri::AppHandle,
+    paths: Vec<String>,
+    timezone: Option<String>,
+    options: Option<crate::sms::SmsOptions>,
+) -> Result<crate::app_import::AppImportReport> {
+    let timezone = timezone.filter(|t| !t.trim().is_empty()).unwrap_or_else(|| "UTC".to_string());
+    let paths = crate::documents::stage(&app_handle, &paths).map_err(AppError::or(AppErrorKind::Import))?;
+    let report = crate::sms::import_backup(&app_handle, &paths, &timezone, &options.unwrap_or_default()).await
+        .map_err(AppError::or(AppErrorKind::Import))?;
+    if report.imported > 0 {
+        spawn_post_import(&app_handle);
+        crate::hooks::emit(
+            &app_handle,
+            "entry-imported",
+            serde_json::json!({ "source": "sms", "imported": report.imported, "duplicates": report.duplicates, "failed": report.failed }),
+        );
+    }
+    Ok(report)
+}
+
+// Imports a blog's RSS/Atom feed from its URL, following older pages where the feed links them
+#[tauri::command]
+pub async fn import_feed(
+    app_handle: tauri::AppHandle,
+    url: String,
+    timezone: Option<String>,
+) -> Result<crate::app_import::AppImportReport> {
+    let timezone = timezone.filter(|t| !t.trim().is_empty()).unwrap_or_else(|| "UTC".to_string());
+    let report = crate::feed::import_feed(&app_handle, &url, &timezone).await
+        .map_err(AppError::or(AppErrorKind::Import))?;
+    if report.imported > 0 {
+        spawn_post_import(&app_handle);
+        crate::hooks::emit(
+            &app_handle,
+            "entry-imported",
+            serde_json::json!({ "source": "feed", "imported": report.imported, "duplicates": report.duplicates, "failed": report.failed }),
+        );
+    }
+    Ok(report)
+}
+
+// Segments a PDF would be split into, with their pages, dates and the start of their text
+#[tauri::command]
+pub async fn preview_pdf_split(
+    app_handle: tauri::AppHandle,
+    path: String,
+    split: Option<crate::pdf_import::PdfSplit>,
+) -> Result<Vec<crate::pdf_import::SegmentPreview>> {
+    let local = crate::documents::stage_one(&app_handle, &path).map_err(|e| AppError::from_error(AppErrorKind::Import, e).with_path(&path))?;
+    crate::pdf_import::preview(&local, &split.unwrap_or_default())
+        .await
+        .map_err(|e| AppError::from_error(AppErrorKind::Import, e).with_path(&path))
+}
+
+// Imports a PDF's text as one entry, or several split by page range or date heading
+#[tauri::command]
+pub async fn import_pdf(
+    app_handle: tauri::AppHandle,
+    path: String,
+    split: Option<crate::pdf_import::PdfSplit>,
+    timezone: Option<String>,
+) -> Result<crate::app_import::AppImportReport> {
+    let timezone = timezone.filter(|t| !t.trim().is_empty()).unwrap_or_else(|| "UTC".to_string());
+    let local = crate::documents::stage_one(&app_handle, &path).map_err(|e| AppError::from_error(AppErrorKind::Import, e).with_path(&path))?;
+    let report = crate::pdf_import::import_pdf(&app_handle, &local, &split.unwrap_or_default(), &timezone).await
+        .map_err(|e| AppError::from_error(AppErrorKind::Import, e).with_path(&path))?;
+    if report.imported > 0 {
+        spawn_post_import(&app_handle);
+        crate::hooks::emit(
+            &app_handle,
+            "entry-imported",
+            serde_json::json!({ "source": "pdf", "imported": report.imported, "duplicates": report.duplicates, "failed": report.failed }),
+        );
+    }
+    Ok(report)
+}
+
+#[tauri::command]
+pub async fn get_imap_status(app_handle: tauri::AppHandle) -> Result<crate::imap::ImapStatus> {
+    let conn = crate::database::open_conn(&app_handle)?;
+    Ok(crate::imap::status(&conn)?)
+}
+
+// Pulls new messages from the journal mail folder now, outside the background schedule
+#[tauri::command]
+pub async fn fetch_imap_mail(app_handle: tauri::AppHandle) -> Result<crate::app_import::AppImportReport> {
+    crate::imap::fetch(&app_handle)
+        .await
+        .map_err(AppError::or(AppErrorKind::Sync))
+}
+
+// Writes a full JSON backup (entries, tags, settings without secrets, conversations, summaries),
+// or only the entries matching `filter` when one is given
+#[tauri::command]
+pub async fn export_journal_json(
+    app_handle: tauri::AppHandle,
+    path: String,
+    filter: Option<crate::database::EntryFilter>,
+) -> Result<crate::backup::BackupExportReport> {
+    let export_error = |e| AppError::from_error(AppErrorKind::Export, e).with_path(&path);
+    let filter = filter.unwrap_or_default();
+    let target = crate::documents::ExportTarget::new(&app_handle, &path).map_err(export_error)?;
+    let mut report = crate::backup::export_json(&app_handle, target.path(), &filter)
+        .await
+        .map_err(export_error)?;
+    target.finish(&app_handle).map_err(export_error)?;
+    report.path = path.clone();
+    crate::hooks::emit(
+        &app_handle,
+        "backup-completed",
+        serde_json::json!({ "path": report.path, "entries": report.entries, "conversations": report.conversations, "filtered": !filter.is_empty() }),
+    );
+    Ok(report)
+}
+
+// Merges a JSON backup into this journal; entries already present are skipped and those edited
+// since the backup are reported as conflicts. `mode` "merge" also keeps the current settings
+#[tauri::command]
+pub async fn import_journal_json(
+    app_handle: tauri::AppHandle,
+    path: String,
+    mode: Option<crate::backup::RestoreMode>,
+) -> Result<crate::backup::BackupImportReport> {
+    let local = crate::documents::stage_one(&app_handle, &path).map_err(|e| AppError::from_error(AppErrorKind::Import, e).with_path(&path))?;
+    let report = crate::backup::import_json(&app_handle, &local, mode.unwrap_or_default())
+        .await
+        .map_err(|e| AppError::from_error(AppErrorKind::Import, e).with_path(&path))?;
+    after_settings_changed(&app_handle, &report.settings_changed).await;
+    if report.entries_imported > 0 {
+        spawn_post_import(&app_handle);
+        crate::hooks::emit(
+            &app_handle,
+            "entry-imported",
+            serde_json::json!({ "source": "backup", "imported": report.entries_imported, "duplicates": report.entries_skipped, "failed": 0 }),
+        );
+    }
+    Ok(report)
+}
+
+// Uploads an encrypted snapshot to the configured S3 bucket and prunes old ones
+#[tauri::command]
+pub async fn run_s3_backup(app_handle: tauri::AppHandle) -> Result<crate::s3_backup::S3BackupReport> {
+    crate::s3_backup::run_backup(&app_handle)
+        .await
+        .map_err(AppError::or(AppErrorKind::Sync))
+}
+
+#[tauri::command]
+pub async fn list_s3_backups(app_handle: tauri::AppHandle) -> Result<Vec<crate::s3_backup::S3Snapshot>> {
+    crate::s3_backup::list(&app_handle)
+        .await
+        .map_err(AppError::or(AppErrorKind::Sync))
+}
+
+// Merges a snapshot from S3 into this journal, like import_journal_json
+#[tauri::command]
+pub async fn restore_s3_backup(
+    app_handle: tauri::AppHandle,
+    key: String,
+    mode: Option<crate::backup::RestoreMode>,
+) -> Result<crate::backup::BackupImportReport> {
+    let report = crate::s3_backup::restore(&app_handle, &key, mode.unwrap_or_default())
+        .await
+        .map_err(AppError::or(AppErrorKind::Sync))?;
+    after_settings_changed(&app_handle, &report.settings_changed).await;
+    if report.entries_imported > 0 {
+        spawn_post_import(&app_handle);
+    }
+    Ok(report)
+}
+
+#[tauri::command]
+pub async fn get_lan_sync_status(app_handle: tauri::AppHandle) -> Result<crate::lan_sync::LanSyncStatus> {
+    let conn = crate::database::open_conn(&app_handle)?;
+    Ok(crate::lan_sync::status(&conn)?)
+}
+
+// Creates and stores a new pairing code, returned once so it can be entered on the other devices
+#[tauri::command]
+pub async fn generate_lan_sync_code() -> Result<String> {
+    crate::lan_sync::generate_pairing_code().map_err(AppError::or(AppErrorKind::Sync))
+}
+
+#[tauri::command]
+pub async fn discover_lan_peers(app_handle: tauri::AppHandle) -> Result<Vec<crate::lan_sync::DiscoveredPeer>> {
+    crate::lan_sync::discover(&app_handle)
+        .await
+        .map_err(AppError::or(AppErrorKind::Sync))
+}
+
+// Two-way sync with a device on the local network; `address` is "host:port"
+#[tauri::command]
+pub async fn sync_with_lan_peer(app_handle: tauri::AppHandle, address: String) -> Result<crate::lan_sync::LanSyncReport> {
+    let report = crate::lan_sync::sync_with(&app_handle, address.trim())
+        .await
+        .map_err(AppError::or(AppErrorKind::Sync))?;
+    if report.received > 0 {
+        spawn_post_import(&app_handle);
+    }
+    Ok(report)
+}
+
+// Entries edited in two places (LAN sync, backup restore), with both versions
+#[tauri::command]
+pub async fn list_conflicts(app_handle: tauri::AppHandle) -> Result<Vec<crate::conflicts::EntryConflict>> {
+    let conn = crate::database::open_conn(&app_handle)?;
+    Ok(crate::conflicts::list(&conn)?)
+}
+
+#[tauri::command]
+pub async fn resolve_conflict(app_handle: tauri::AppHandle, id: i64, resolution: crate::conflicts::Resolution) -> Result<()> {
+    {
+        let conn = crate::database::open_conn(&app_handle)?;
+        crate::conflicts::resolve(&conn, id, resolution).map_err(AppError::or(AppErrorKind::Database))?;
+    }
+    if auto_embed_enabled(&app_handle) {
+        crate::edit_embeddings::schedule(&app_handle);
+    }
+    Ok(())
+}
+
+// Background work after new entries land: embeddings (when auto_embed is on) and entity extraction
+pub(crate) fn spawn_post_import(app_handle: &tauri::AppHandle) {
+    // Embed the new entries in the background so semantic search picks them up
+    if auto_embed_enabled(app_handle) {
+        let handle = app_handle.clone();
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = backfill_embeddings(handle).await {
+                tracing::warn!("embedding backfill after import skipped: {}", e.message);
+            }
+        });
+    }
+
+    spawn_entity_extraction(app_handle);
+}
+
+// Entity extraction and goal tracking are rule-based unless AI is enabled for them, so they
+// always follow an import or edit
+fn spawn_entity_extraction(app_handle: &tauri::AppHandle) {
+    let handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = crate::entities::extract_pending(&handle, false).await {
+            tracing::warn!("entity extraction after import failed: {}", e);
+        }
+        if let Err(e) = crate::goals::track_pending(&handle, false).await {
+            tracing::warn!("goal tracking after import failed: {}", e);
+        }
+        if let Err(e) = crate::database::open_conn(&handle).and_then(|mut conn| crate::gratitude::extract_pending(&mut conn, false)) {
+            tracing::warn!("gratitude extraction after import failed: {}", e);
+        }
+    });
+}
+
+fn auto_embed_enabled(app_handle: &tauri::AppHandle) -> bool {
+    crate::database::open_conn(app_handle)
+        .map(|conn| crate::database::setting_enabled(&conn, "auto_embed", true))
+        .unwrap_or(false)
+}
+
 async fn process_single_file(
     app_handle: &tauri::AppHandle,
     file_with_date: FileWithDate,
+    google_access: Option<&str>,
 ) -> Result<String> {
-    use crate::import::{parse_file, normalize_content};
+    use crate::import::{parse_file_with_drive, normalize_content};
     use crate::database::{save_entry, check_duplicate};
     use chrono::{DateTime, Utc};
     
     // Parse the file
-    let mut parsed_file = parse_file(&file_with_date.path).await
-        .map_err(|e| crate::AppError { 
-            message: format!("Failed to parse file: {}", e), 
-            code: Some("PARSE_ERROR".to_string()) 
+    let mut parsed_file = parse_file_with_drive(&file_with_date.path, google_access).await
+        .map_err(|e| {
+            let error = AppError::from_error(AppErrorKind::Import, e).with_path(&file_with_date.path);
+            AppError { message: format!("Failed to parse file: {}", error.message), ..error }
         })?;
     
     // Normalize content
@@ -466,18 +1754,12 @@ async fn process_single_file(
     
     // Check for duplicates
     if let Some(existing_id) = check_duplicate(app_handle, &parsed_file.text_hash).await? {
-        return Err(crate::AppError {
-            message: format!("Duplicate content found (existing entry: {})", existing_id),
-            code: Some("DUPLICATE".to_string()),
-        });
+        return Err(AppError::new(AppErrorKind::Duplicate, format!("Duplicate content found (existing entry: {})", existing_id)).with_entry(existing_id));
     }
     
     // Parse the entry date
     let entry_date = DateTime::parse_from_rfc3339(&file_with_date.entry_date)
-        .map_err(|e| crate::AppError {
-            message: format!("Invalid date format: {}", e),
-            code: Some("INVALID_DATE".to_string()),
-        })?
+        .map_err(|e| AppError::new(AppErrorKind::InvalidInput, format!("Invalid date format: {}", e)).with_path(&file_with_date.path))?
         .with_timezone(&Utc);
     
     // Save to database
@@ -501,6 +1783,13 @@ pub async fn get_available_years(app_handle: tauri::AppHandle) -> Result<Vec<i32
     Ok(years)
 }
 
+// Languages to offer in the search filter
+#[tauri::command]
+pub async fn get_languages(app_handle: tauri::AppHandle) -> Result<Vec<crate::database::LanguageCount>> {
+    let conn = crate::database::open_conn(&app_handle)?;
+    Ok(crate::database::language_counts(&conn)?)
+}
+
 #[tauri::command]
 pub async fn get_month_counts_for_year(app_handle: tauri::AppHandle, year: i32) -> Result<Vec<crate::database::MonthCount>> {
     let months = crate::database::get_month_counts_for_year(&app_handle, year).await?;
@@ -509,13 +1798,15 @@ pub async fn get_month_counts_for_year(app_handle: tauri::AppHandle, year: i32)
 
 #[tauri::command]
 pub async fn list_entries_for_month(app_handle: tauri::AppHandle, year: i32, month: u32) -> Result<Vec<EntryPreview>> {
-    let entries = crate::database::list_entries_by_month(&app_handle, year, month).await?;
-    let previews: Vec<EntryPreview> = entries.into_iter().map(|e| EntryPreview {
+    let conn = crate::database::open_conn(&app_handle)?;
+    let previews: Vec<EntryPreview> = crate::preview::for_month(&conn, year, month)?.into_iter().map(|e| EntryPreview {
+        sensitive: e.sensitive,
         id: e.id,
         title: e.title,
-        preview: create_preview(&e.body, 200),
-        entry_date: e.entry_date.to_rfc3339(),
+        preview: e.preview,
+        entry_date: e.entry_date,
         tags: vec![],
+        snippets: vec![],
     }).collect();
     Ok(previews)
 }
@@ -524,58 +1815,289 @@ pub async fn list_entries_for_month(app_handle: tauri::AppHandle, year: i32, mon
 
 // Removed day view for simplified UI
 
-fn create_preview(text: &str, max_len: usize) -> String {
-    let mut s = text.trim().replace('\n', " ");
-    if s.len() > max_len { s.truncate(max_len); s.push_str("..."); }
-    s
-}
-
-// Helper function to get month name
-fn get_month_name(month: u32) -> String {
-    match month {
-        1 => "January",
-        2 => "February", 
-        3 => "March",
-        4 => "April",
-        5 => "May",
-        6 => "June",
-        7 => "July",
-        8 => "August",
-        9 => "September",
-        10 => "October",
-        11 => "November",
-        12 => "December",
-        _ => "Unknown",
-    }.to_string()
-}
-
 #[tauri::command]
 pub async fn get_entry_by_id(app_handle: tauri::AppHandle, id: String) -> Result<Option<EntryPreview>> {
     if let Some(e) = crate::database::get_entry_by_id(&app_handle, &id).await? {
+        // A locked sensitive entry comes back with an empty body
+        let conn = crate::database::open_conn(&app_handle)?;
+        let sensitive = crate::sensitive::is_sensitive(&conn, &id)?;
+        let body = if sensitive && crate::sensitive::is_unlocked() { crate::sensitive::read_body(&conn, &id)? } else { e.body };
         Ok(Some(EntryPreview {
             id: e.id,
             title: e.title,
-            preview: e.body,
+            preview: body,
             entry_date: e.entry_date.to_rfc3339(),
             tags: vec![],
+            snippets: vec![],
+            sensitive,
         }))
     } else {
     Ok(None)
 }
 }
 
+// The archived copy of the file an entry was imported from, if it has one
+#[tauri::command]
+pub async fn get_entry_original(app_handle: tauri::AppHandle, id: String) -> Result<Option<crate::archive::ArchivedOriginal>> {
+    let conn = crate::database::open_conn(&app_handle)?;
+    Ok(crate::archive::original(&app_handle, &conn, &id)?)
+}
+
+// Saves an entry's archived original to `dest`
+#[tauri::command]
+pub async fn export_entry_original(app_handle: tauri::AppHandle, id: String, dest: String) -> Result<String> {
+    let conn = crate::database::open_conn(&app_handle)?;
+    let original = crate::archive::original(&app_handle, &conn, &id)?
+        .ok_or_else(|| AppError::new(AppErrorKind::NotFound, "This entry has no archived original").with_entry(&id))?;
+    let export_error = |e| AppError::from_error(AppErrorKind::Export, e).with_path(&dest);
+    let target = crate::documents::ExportTarget::new(&app_handle, &dest).map_err(export_error)?;
+    crate::archive::restore(std::path::Path::new(&original.archive_path), std::path::Path::new(target.path()))
+        .map_err(export_error)?;
+    target.finish(&app_handle).map_err(export_error)?;
+    Ok(dest)
+}
+
+// Photos attached by the photo import; captions of sensitive entries are left out
+#[tauri::command]
+pub async fn get_entry_photos(app_handle: tauri::AppHandle, id: String) -> Result<Vec<crate::photos::EntryPhoto>> {
+    let conn = crate::database::open_conn(&app_handle)?;
+    let mut photos = crate::photos::entry_photos(&conn, &id).map_err(AppError::or(AppErrorKind::Database))?;
+    if crate::sensitive::is_sensitive(&conn, &id)? {
+        photos.iter_mut().for_each(|p| p.caption = None);
+    }
+    Ok(photos)
+}
+
+// The bytes of an attached photo, for showing it in the app
+#[tauri::command]
+pub async fn read_entry_photo(app_handle: tauri::AppHandle, id: String, position: u32) -> Result<tauri::ipc::Response> {
+    let conn = crate::database::open_conn(&app_handle)?;
+    let photos = crate::photos::entry_photos(&conn, &id).map_err(AppError::or(AppErrorKind::Database))?;
+    let photo = photos
+        .into_iter()
+        .find(|p| p.position == position)
+        .ok_or_else(|| AppError::new(AppErrorKind::NotFound, "Photo not found").with_entry(&id))?;
+    let bytes = std::fs::read(&photo.path).map_err(|e| AppError::from_error(AppErrorKind::NotFound, e).with_path(&photo.path))?;
+    Ok(tauri::ipc::Response::new(bytes))
+}
+
+// Reads the entry aloud into a cached audio file (see tts.rs)
+#[tauri::command]
+pub async fn synthesize_entry_audio(app_handle: tauri::AppHandle, id: String) -> Result<crate::tts::EntryAudio> {
+    let entry = crate::database::get_entry_by_id(&app_handle, &id)
+        .await?
+        .ok_or_else(|| AppError::new(AppErrorKind::NotFound, "Entry not found").with_entry(&id))?;
+    let engine = {
+        let conn = crate::database::open_conn(&app_handle)?;
+        if crate::sensitive::is_sensitive(&conn, &id)? {
+            return Err(AppError::new(AppErrorKind::Sensitive, "Sensitive entries are not read aloud").with_entry(&id));
+        }
+        crate::tts::settings(&conn).0
+    };
+    crate::tts::synthesize(&app_handle, &entry).await.map_err(|e| match engine {
+        crate::tts::Engine::OpenAI => AppError::from_error(AppErrorKind::Ai, e).with_provider(&crate::ai::Provider::OpenAI).with_entry(&id),
+        crate::tts::Engine::System => AppError::from_error(AppErrorKind::Export, e).with_entry(&id),
+    })
+}
+
+// The bytes of the entry's synthesized audio, for playing it in the app
+#[tauri::command]
+pub async fn read_entry_audio(app_handle: tauri::AppHandle, id: String) -> Result<tauri::ipc::Response> {
+    let path = crate::tts::cached(&app_handle, &id)?
+        .ok_or_else(|| AppError::new(AppErrorKind::NotFound, "This entry has not been read aloud yet").with_entry(&id))?;
+    let bytes = std::fs::read(&path).map_err(|e| AppError::from_error(AppErrorKind::Internal, e).with_path(path.to_string_lossy()))?;
+    Ok(tauri::ipc::Response::new(bytes))
+}
+
+#[tauri::command]
+pub async fn export_entry_audio(app_handle: tauri::AppHandle, id: String, dest: String) -> Result<String> {
+    let audio = synthesize_entry_audio(app_handle.clone(), id).await?;
+    let export_error = |e| AppError::from_error(AppErrorKind::Export, e).with_path(&dest);
+    let target = crate::documents::ExportTarget::new(&app_handle, &dest).map_err(export_error)?;
+    std::fs::copy(&audio.path, target.path()).map_err(|e| export_error(e.into()))?;
+    target.finish(&app_handle).map_err(export_error)?;
+    Ok(dest)
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct DbDiagnostics {
     pub db_path: String,
     pub total_entries: u32,
     pub years: Vec<i32>,
+    #[serde(flatten)]
+    pub health: crate::database::DbHealth,
 }
 
 #[tauri::command]
 pub async fn get_db_diagnostics(app_handle: tauri::AppHandle) -> Result<DbDiagnostics> {
-    let info = crate::database::get_db_info(&app_handle).await.map_err(|e| crate::AppError { message: format!("DB info error: {}", e), code: Some("DB_INFO".into()) })?;
-    println!("[db] path={} total_entries={}", info.db_path, info.total_entries);
-    Ok(DbDiagnostics { db_path: info.db_path, total_entries: info.total_entries, years: info.years })
+    let info = crate::database::get_db_info(&app_handle).await.map_err(|e| AppError::new(AppErrorKind::Database, format!("DB info error: {}", e)))?;
+    let conn = crate::database::open_conn(&app_handle)?;
+    let health = crate::database::db_health(&conn, std::path::Path::new(&info.db_path))
+        .map_err(|e| AppError::new(AppErrorKind::Database, format!("DB info error: {}", e)))?;
+    tracing::info!(
+        "diagnostics path={} total_entries={} fts_rows={} missing_fts={} orphaned_fts={} schema={}/{}",
+        info.db_path, info.total_entries, health.fts_rows, health.entries_missing_fts, health.orphaned_fts_rows, health.schema_version, health.app_schema_version
+    );
+    Ok(DbDiagnostics { db_path: info.db_path, total_entries: info.total_entries, years: info.years, health })
+}
+
+#[tauri::command]
+pub async fn get_journal_statistics(app_handle: tauri::AppHandle) -> Result<crate::stats::JournalStatistics> {
+    let stats = crate::stats::get_journal_statistics(&app_handle).await.map_err(|e| AppError::new(AppErrorKind::Database, format!("Statistics error: {}", e)))?;
+    Ok(stats)
+}
+
+#[tauri::command]
+pub async fn get_year_summary_stats(app_handle: tauri::AppHandle, year: i32) -> Result<crate::stats::YearSummaryStats> {
+    crate::stats::get_year_summary_stats(&app_handle, year)
+        .await
+        .map_err(AppError::or(AppErrorKind::Database))
+}
+
+// Entries per month or year (`granularity`) for each tag; with no tags, the most used ones
+#[tauri::command]
+pub async fn get_tag_trends(app_handle: tauri::AppHandle, tags: Vec<String>, granularity: Option<String>) -> Result<crate::stats::TagTrends> {
+    crate::stats::get_tag_trends(&app_handle, &tags, granularity.as_deref().unwrap_or("month"))
+        .await
+        .map_err(AppError::or(AppErrorKind::Database))
+}
+
+// Entries and average length by weekday, hour and part of day, in each entry's timezone
+#[tauri::command]
+pub async fn get_writing_times(app_handle: tauri::AppHandle) -> Result<crate::stats::WritingTimes> {
+    crate::stats::get_writing_times(&app_handle)
+        .await
+        .map_err(AppError::or(AppErrorKind::Database))
+}
+
+// Current and longest daily writing streaks, plus entries and days written per month
+#[tauri::command]
+pub async fn get_writing_streaks(app_handle: tauri::AppHandle) -> Result<crate::stats::WritingStreaks> {
+    crate::stats::get_writing_streaks(&app_handle)
+        .await
+        .map_err(AppError::or(AppErrorKind::Database))
+}
+
+#[tauri::command]
+pub async fn run_db_maintenance(app_handle: tauri::AppHandle) -> Result<crate::database::MaintenanceReport> {
+    let report = crate::database::run_maintenance(&app_handle).await.map_err(|e| AppError::new(AppErrorKind::Database, format!("Maintenance error: {}", e)))?;
+    Ok(report)
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct IndexProgress {
+    pub processed: u64,
+    pub total: u64,
+}
+
+// Emits `fts://rebuild-progress` with IndexProgress after each batch
+#[tauri::command]
+pub async fn rebuild_search_index(app_handle: tauri::AppHandle) -> Result<u64> {
+    use tauri::Emitter;
+    let emitter = app_handle.clone();
+    let rows = crate::database::rebuild_fts_index(&app_handle, move |processed, total| {
+        let _ = emitter.emit("fts://rebuild-progress", IndexProgress { processed, total });
+    })
+    .await
+    .map_err(|e| AppError::new(AppErrorKind::SearchIndex, format!("Index rebuild error: {}", e)))?;
+    Ok(rows)
+}
+
+// Fixes an out-of-sync search index in place, faster than rebuild_search_index
+#[tauri::command]
+pub async fn repair_search_index(app_handle: tauri::AppHandle) -> Result<crate::database::FtsRepairReport> {
+    let conn = crate::database::open_conn(&app_handle)?;
+    crate::database::repair_fts(&conn).map_err(|e| AppError::new(AppErrorKind::SearchIndex, format!("Index repair error: {}", e)))
+}
+
+// Date formats offered for the `locale` setting
+#[tauri::command]
+pub async fn get_date_locales() -> Result<Vec<crate::locale::LocaleOption>> {
+    Ok(crate::locale::supported_locales())
+}
+
+// The last lines of the app's log files, newest last, for bug reports
+#[tauri::command]
+pub async fn get_recent_logs(app_handle: tauri::AppHandle, lines: Option<u32>) -> Result<crate::logging::RecentLogs> {
+    let dir = match crate::logging::log_dir() {
+        Some(dir) => dir,
+        None => crate::database::get_db_dir(&app_handle)?.join("logs"),
+    };
+    let count = lines.map_or(500, |n| n as usize).min(crate::logging::MAX_RECENT_LINES);
+    let lines = crate::logging::recent(&dir, count).map_err(|e| AppError::new(AppErrorKind::Internal, format!("Could not read logs: {}", e)))?;
+    Ok(crate::logging::RecentLogs { dir: dir.to_string_lossy().to_string(), lines })
+}
+
+#[tauri::command]
+pub async fn get_embedding_status(app_handle: tauri::AppHandle) -> Result<crate::embeddings::EmbeddingStatus> {
+    let status = crate::embeddings::get_embedding_status(&app_handle).await.map_err(AppError::or(AppErrorKind::Embeddings))?;
+    Ok(status)
+}
+
+fn emit_backfill_progress(app_handle: &tauri::AppHandle) -> impl Fn(crate::embeddings::EmbeddingProgress) + Send + 'static {
+    use tauri::Emitter;
+    let emitter = app_handle.clone();
+    move |progress| {
+        let _ = emitter.emit("embeddings://backfill-progress", progress);
+    }
+}
+
+// Emits `embeddings://backfill-progress` with EmbeddingProgress after each entry
+#[tauri::command]
+pub async fn backfill_embeddings(app_handle: tauri::AppHandle) -> Result<crate::embeddings::BackfillReport> {
+    let report = crate::embeddings::backfill_embeddings(&app_handle, emit_backfill_progress(&app_handle))
+        .await
+        .map_err(|e| AppError::new(AppErrorKind::Embeddings, format!("Embedding backfill error: {}", e)))?;
+    Ok(report)
+}
+
+// Starts re-embedding in the background after an embedding model change and returns the current
+// status. Progress comes as `embeddings://backfill-progress`; the end as `embeddings://reembed-done`
+// with the BackfillReport, or `embeddings://reembed-error` with a message.
+#[tauri::command]
+pub async fn reembed_all_entries(app_handle: tauri::AppHandle, force: Option<bool>) -> Result<crate::embeddings::EmbeddingStatus> {
+    let status = crate::embeddings::get_embedding_status(&app_handle)
+        .await
+        .map_err(AppError::or(AppErrorKind::Embeddings))?;
+    if status.running {
+        return Err(AppError::new(AppErrorKind::Embeddings, "An embedding backfill is already running"));
+    }
+    spawn_reembed(app_handle, force.unwrap_or(false));
+    Ok(status)
+}
+
+// Empties the cache of embeddings and tag suggestions (one kind, "embedding" or "tags", when
+// given); returns how many results were removed
+#[tauri::command]
+pub async fn clear_ai_cache(app_handle: tauri::AppHandle, kind: Option<String>) -> Result<usize> {
+    let conn = crate::database::open_conn(&app_handle)?;
+    Ok(crate::ai_cache::clear(&conn, kind.as_deref(), None)?)
+}
+
+// Outbound AI requests, newest first: destination, purpose, bytes sent and the entries whose
+// text was included
+#[tauri::command]
+pub async fn get_ai_audit_log(
+    app_handle: tauri::AppHandle,
+    limit: Option<u32>,
+    offset: Option<u32>,
+) -> Result<Vec<crate::ai_audit::AiAuditRecord>> {
+    Ok(crate::ai_audit::get_log(&app_handle, limit.unwrap_or(100).min(1000), offset.unwrap_or(0)).await?)
+}
+
+fn spawn_reembed(app_handle: tauri::AppHandle, force: bool) {
+    use tauri::Emitter;
+    tauri::async_runtime::spawn(async move {
+        match crate::embeddings::reembed_all(&app_handle, force, emit_backfill_progress(&app_handle)).await {
+            Ok(report) => {
+                let _ = app_handle.emit("embeddings://reembed-done", report);
+            }
+            Err(e) => {
+                tracing::warn!("re-embed failed: {}", e);
+                let _ = app_handle.emit("embeddings://reembed-error", e.to_string());
+            }
+        }
+    });
 }
 
 // Removed AI/tagging-related commands in simplified app
@@ -0,0 +1,241 @@
+use anyhow::{Context, Result};
+use base64::Engine;
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::backup::BackupEntry;
+
+// Entries changed in two places, from LAN sync and from backup restores.
+//
+// When a sync or restore meets an entry that was edited both here and elsewhere, one version
+// stays in the journal and the other is stored in `sync_conflicts` instead of being dropped:
+// with LAN sync's "newest" policy that is the edit that lost, with "manual" and with restores it
+// is the incoming version. Resolving keeps the current version, takes the other one, or saves a
+// merge of both bodies. The result gets a fresh `updated_at`, so the next sync carries it to the
+// other devices rather than bringing the losing edit back.
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConflictVersion {
+    pub title: Option<String>,
+    // Empty for a sealed body while sensitive entries are locked
+    pub body: String,
+    pub updated_at: String,
+    pub tags: Vec<String>,
+    pub sealed: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EntryConflict {
+    pub id: i64,
+    pub entry_id: String,
+    // "lan-sync:<device name>" or "backup:<exported_at>"
+    pub source: String,
+    pub detected_at: String,
+    // None when the entry has since been deleted here
+    pub current: Option<ConflictVersion>,
+    pub other: ConflictVersion,
+    // Starting point for a manual merge: the current body plus the other's paragraphs it lacks
+    pub merged_body: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "keep", rename_all = "snake_case")]
+pub enum Resolution {
+    Current,
+    Other,
+    Merged { title: Option<String>, body: String },
+}
+
+// Stores the version that was not kept; a later conflict from the same source replaces it
+pub fn record(conn: &Connection, other: &BackupEntry, source: &str) -> Result<()> {
+    conn.execute(
+        "INSERT INTO sync_conflicts (entry_id, source, remote, detected_at) VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT(entry_id, source) DO UPDATE SET remote = excluded.remote, detected_at = excluded.detected_at",
+        params![other.id, source, serde_json::to_string(other)?, Utc::now().to_rfc3339()],
+    )?;
+    Ok(())
+}
+
+pub fn count(conn: &Connection) -> Result<u32> {
+    let n: i64 = conn.query_row("SELECT COUNT(*) FROM sync_conflicts", [], |r| r.get(0))?;
+    Ok(n as u32)
+}
+
+fn version(entry: &BackupEntry) -> ConflictVersion {
+    let sealed = entry.body_sealed.as_deref().map(|b| {
+        let bytes = base64::engine::general_purpose::STANDARD.decode(b).unwrap_or_default();
+        crate::sensitive::current_key().and_then(|key| crate::sensitive::open(&key, &bytes)).unwrap_or_default()
+    });
+    ConflictVersion {
+        title: entry.title.clone(),
+        sealed: sealed.is_some(),
+        body: sealed.unwrap_or_else(|| entry.body.clone()),
+        updated_at: entry.updated_at.clone(),
+        tags: entry.tags.clone(),
+    }
+}
+
+fn current_entry(conn: &Connection, entry_id: &str) -> Result<Option<BackupEntry>> {
+    let filter = crate::database::EntryFilter::default();
+    let mut entries = crate::backup::collect_entries(conn, &filter)?;
+    Ok(entries.iter().position(|e| e.id == entry_id).map(|i| entries.swap_remove(i)))
+}
+
+fn stored(conn: &Connection, id: i64) -> Result<(String, BackupEntry)> {
+    let (entry_id, remote): (String, String) = conn
+        .query_row("SELECT entry_id, remote FROM sync_conflicts WHERE id = ?1", params![id], |r| Ok((r.get(0)?, r.get(1)?)))
+        .optional()?
+        .context("Conflict not found")?;
+    Ok((entry_id, serde_json::from_str(&remote).context("Unreadable stored version")?))
+}
+
+// The current body followed by the other body's paragraphs that it doesn't already contain
+pub fn merge_bodies(current: &str, other: &str) -> String {
+    let paragraphs = |s: &str| s.split("\n\n").map(str::trim).filter(|p| !p.is_empty()).map(str::to_string).collect::<Vec<_>>();
+    let mut merged = paragraphs(current);
+    for paragraph in paragraphs(other) {
+        if !merged.contains(&paragraph) {
+            merged.push(paragraph);
+        }
+    }
+    merged.join("\n\n")
+}
+
+pub fn list(conn: &Connection) -> Result<Vec<EntryConflict>> {
+    let mut stmt = conn.prepare("SELECT id, entry_id, source, remote, detected_at FROM sync_conflicts ORDER BY detected_at DESC, id")?;
+    let rows = stmt
+        .query_map([], |r| Ok((r.get::<_, i64>(0)?, r.get::<_, String>(1)?, r.get::<_, String>(2)?, r.get::<_, String>(3)?, r.get::<_, String>(4)?)))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    if rows.is_empty() {
+        return Ok(Vec::new());
+    }
+    let filter = crate::database::EntryFilter::default();
+    let mut entries: std::collections::HashMap<String, BackupEntry> =
+        crate::backup::collect_entries(conn, &filter)?.into_iter().map(|e| (e.id.clone(), e)).collect();
+    let mut conflicts = Vec::new();
+    for (id, entry_id, source, remote, detected_at) in rows {
+        let Ok(other) = serde_json::from_str::<BackupEntry>(&remote) else { continue };
+        let current = entries.remove(&entry_id).map(|e| version(&e));
+        let other = version(&other);
+        let merged_body = match &current {
+            Some(c) => merge_bodies(&c.body, &other.body),
+            None => other.body.clone(),
+        };
+        conflicts.push(EntryConflict { id, entry_id, source, detected_at, current, other, merged_body });
+    }
+    Ok(conflicts)
+}
+
+pub fn resolve(conn: &Connection, id: i64, resolution: Resolution) -> Result<()> {
+    let (entry_id, mut other) = stored(conn, id)?;
+    let current = current_entry(conn, &entry_id)?;
+    let now = Utc::now().to_rfc3339();
+    let tx = conn.unchecked_transaction()?;
+    match resolution {
+        Resolution::Current => {
+            tx.execute("UPDATE entries SET updated_at = ?2 WHERE id = ?1", params![entry_id, now])?;
+        }
+        Resolution::Other => {
+            if let Some(current) = &current {
+                other.tags = union(&current.tags, &other.tags);
+            }
+            other.updated_at = now;
+            let (applied, _) = crate::lan_sync::apply_entries(&tx, std::slice::from_ref(&other), false)?;
+            if applied == 0 {
+                anyhow::bail!("Another entry already has this text");
+            }
+        }
+        Resolution::Merged { title, body } => match &current {
+            Some(current) => {
+                crate::database::set_entry_text(&tx, &entry_id, title, &body)?;
+                crate::database::add_entry_tags(&tx, &entry_id, &union(&current.tags, &other.tags))?;
+            }
+            None => {
+                if other.body_sealed.is_some() {
+                    anyhow::bail!("This entry was deleted here and is sensitive; keep the other version instead");
+                }
+                other.title = title.map(|t| t.trim().to_string()).filter(|t| !t.is_empty());
+                other.text_hash = format!("{:x}", Sha256::digest(body.as_bytes()));
+                other.sentiment = Some(crate::sentiment::score(&body) as f64);
+                other.language = crate::import::detect_language(&body);
+                other.body = body;
+                other.updated_at = now;
+                let (applied, _) = crate::lan_sync::apply_entries(&tx, std::slice::from_ref(&other), false)?;
+                if applied == 0 {
+                    anyhow::bail!("Another entry already has this text");
+                }
+            }
+        },
+    }
+    tx.execute("DELETE FROM sync_conflicts WHERE id = ?1", params![id])?;
+    tx.commit()?;
+    crate::vector_index::invalidate_entries([&entry_id]);
+    Ok(())
+}
+
+fn union(a: &[String], b: &[String]) -> Vec<String> {
+    let tags: std::collections::BTreeSet<String> = a.iter().chain(b).cloned().collect();
+    tags.into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn backup_entry(id: &str, body: &str, updated_at: &str) -> BackupEntry {
+        BackupEntry {
+            id: id.to_string(),
+            title: Some("Lake".to_string()),
+            body: body.to_string(),
+            entry_date: "2024-03-01T08:00:00+00:00".to_string(),
+            entry_timezone: "UTC".to_string(),
+            source_path: "sync".to_string(),
+            source_type: "txt".to_string(),
+            text_hash: format!("{:x}", Sha256::digest(body.as_bytes())),
+            created_at: "2024-03-01T08:00:00+00:00".to_string(),
+            updated_at: updated_at.to_string(),
+            sentiment: None,
+            language: None,
+            tags: vec!["walks".to_string()],
+            body_sealed: None,
+        }
+    }
+
+    #[test]
+    fn test_merge_bodies_appends_missing_paragraphs() {
+        assert_eq!(merge_bodies("Walked.\n\nRained.", "Walked.\n\nSaw a heron."), "Walked.\n\nRained.\n\nSaw a heron.");
+        assert_eq!(merge_bodies("", "Only there."), "Only there.");
+    }
+
+    #[test]
+    fn test_resolve_each_way() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::migrations::run_migrations(&mut conn).unwrap();
+        crate::lan_sync::apply_entries(&conn, &[backup_entry("a", "Walked.", "2024-03-02T09:00:00+00:00")], false).unwrap();
+        let mut other = backup_entry("a", "Walked.\n\nSaw a heron.", "2024-03-03T09:00:00+00:00");
+        other.tags = vec!["birds".to_string()];
+        record(&conn, &other, "lan-sync:laptop").unwrap();
+
+        let listed = list(&conn).unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].current.as_ref().unwrap().body, "Walked.");
+        assert_eq!(listed[0].merged_body, "Walked.\n\nSaw a heron.");
+
+        resolve(&conn, listed[0].id, Resolution::Other).unwrap();
+        let body: String = conn.query_row("SELECT body FROM entries WHERE id = 'a'", [], |r| r.get(0)).unwrap();
+        assert_eq!(body, "Walked.\n\nSaw a heron.");
+        let tags: i64 = conn.query_row("SELECT COUNT(*) FROM entry_tags WHERE entry_id = 'a'", [], |r| r.get(0)).unwrap();
+        assert_eq!(tags, 2);
+        assert_eq!(count(&conn).unwrap(), 0);
+
+        record(&conn, &backup_entry("a", "Rained.", "2024-03-01T09:00:00+00:00"), "backup:2024-03-01").unwrap();
+        let id = list(&conn).unwrap()[0].id;
+        resolve(&conn, id, Resolution::Merged { title: None, body: "Walked.\n\nRained.".to_string() }).unwrap();
+        let (title, body): (Option<String>, String) =
+            conn.query_row("SELECT title, body FROM entries WHERE id = 'a'", [], |r| Ok((r.get(0)?, r.get(1)?))).unwrap();
+        assert_eq!((title, body.as_str()), (None, "Walked.\n\nRained."));
+        assert!(list(&conn).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,242 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::ai::{ChatMessage, Citation, ConversationMessage, RagResponse};
+
+// Stored question-answering conversations.
+//
+// A conversation is created by its first exchange and titled after the first question.
+// Each exchange appends the user's question and the assistant's answer (with citations as
+// JSON); the most recent turns are fed back into the prompt when the conversation continues.
+// Messages are ordered by insertion; conversation timestamps have fixed precision so they
+// sort as text.
+
+// Prior turns (user + assistant messages) included in a follow-up prompt
+pub const MAX_PROMPT_TURNS: usize = 6;
+const TITLE_CHARS: usize = 60;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConversationSummary {
+    pub conversation_id: String,
+    pub title: String,
+    pub last_message: String,
+    pub message_count: u32,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+fn title_for(question: &str) -> String {
+    let question = question.split_whitespace().collect::<Vec<_>>().join(" ");
+    if question.chars().count() <= TITLE_CHARS {
+        return question;
+    }
+    let cut: String = question.chars().take(TITLE_CHARS).collect();
+    format!("{}...", cut.trim_end())
+}
+
+fn insert_message(
+    conn: &Connection,
+    conversation_id: &str,
+    message_id: &str,
+    role: &str,
+    content: &str,
+    citations: Option<&[Citation]>,
+    at: &DateTime<Utc>,
+) -> Result<()> {
+    let citations = citations.map(serde_json::to_string).transpose()?;
+    conn.execute(
+        r#"INSERT INTO conversation_messages (id, conversation_id, role, content, citations, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6)"#,
+        params![message_id, conversation_id, role, content, citations, at.to_rfc3339_opts(chrono::SecondsFormat::Micros, true)],
+    )?;
+    Ok(())
+}
+
+// Stores one question and its answer, creating the conversation on first use
+pub fn record_exchange(conn: &mut Connection, question: &str, response: &RagResponse) -> Result<()> {
+    let tx = conn.transaction()?;
+    let asked_at = Utc::now();
+    let answered_at = Utc::now();
+    tx.execute(
+        r#"INSERT INTO conversations (id, title, created_at, updated_at) VALUES (?1, ?2, ?3, ?3)
+            ON CONFLICT(id) DO UPDATE SET updated_at = excluded.updated_at"#,
+        params![response.conversation_id, title_for(question), answered_at.to_rfc3339_opts(chrono::SecondsFormat::Micros, true)],
+    )?;
+    let question_id = uuid::Uuid::new_v4().to_string();
+    insert_message(&tx, &response.conversation_id, &question_id, "user", question, None, &asked_at)?;
+    insert_message(
+        &tx,
+        &response.conversation_id,
+        &response.message_id,
+        "assistant",
+        &response.answer,
+        Some(&response.citations),
+        &answered_at,
+    )?;
+    tx.commit()?;
+    Ok(())
+}
+
+pub fn list(conn: &Connection) -> Result<Vec<ConversationSummary>> {
+    let mut stmt = conn.prepare_cached(
+        r#"SELECT c.id, c.title, c.created_at, c.updated_at,
+                (SELECT count(*) FROM conversation_messages m WHERE m.conversation_id = c.id),
+                (SELECT m.content FROM conversation_messages m WHERE m.conversation_id = c.id
+                    ORDER BY m.rowid DESC LIMIT 1)
+            FROM conversations c
+            ORDER BY c.updated_at DESC"#,
+    )?;
+    let rows = stmt.query_map([], |r| {
+        Ok(ConversationSummary {
+            conversation_id: r.get(0)?,
+            title: r.get(1)?,
+            created_at: r.get(2)?,
+            updated_at: r.get(3)?,
+            message_count: r.get(4)?,
+            last_message: r.get::<_, Option<String>>(5)?.unwrap_or_default(),
+        })
+    })?;
+    Ok(rows.collect::<rusqlite::Result<_>>()?)
+}
+
+pub fn history(conn: &Connection, conversation_id: &str) -> Result<Vec<ConversationMessage>> {
+    let exists: Option<i64> = conn
+        .query_row("SELECT 1 FROM conversations WHERE id = ?1", params![conversation_id], |r| r.get(0))
+        .optional()?;
+    if exists.is_none() {
+        return Err(anyhow::anyhow!("Conversation not found: {}", conversation_id));
+    }
+    let mut stmt = conn.prepare_cached(
+        r#"SELECT id, role, content, citations, created_at FROM conversation_messages
+            WHERE conversation_id = ?1 ORDER BY rowid"#,
+    )?;
+    let rows = stmt.query_map(params![conversation_id], |r| {
+        Ok((
+            r.get::<_, String>(0)?,
+            r.get::<_, String>(1)?,
+            r.get::<_, String>(2)?,
+            r.get::<_, Option<String>>(3)?,
+            r.get::<_, String>(4)?,
+        ))
+    })?;
+    let mut messages = Vec::new();
+    for row in rows {
+        let (message_id, role, content, citations, created_at) = row?;
+        messages.push(ConversationMessage {
+            message_id,
+            role,
+            content,
+            citations: citations.as_deref().map(serde_json::from_str).transpose()?,
+            timestamp: DateTime::parse_from_rfc3339(&created_at)?.with_timezone(&Utc),
+        });
+    }
+    Ok(messages)
+}
+
+// The last `max` messages, oldest first, for the follow-up prompt; empty for a new conversation
+pub fn recent_turns(conn: &Connection, conversation_id: &str, max: usize) -> Result<Vec<ChatMessage>> {
+    let mut stmt = conn.prepare_cached(
+        r#"SELECT role, content FROM conversation_messages WHERE conversation_id = ?1
+            ORDER BY rowid DESC LIMIT ?2"#,
+    )?;
+    let mut turns: Vec<ChatMessage> = stmt
+        .query_map(params![conversation_id, max as i64], |r| Ok(ChatMessage { role: r.get(0)?, content: r.get(1)? }))?
+        .collect::<rusqlite::Result<_>>()?;
+    turns.reverse();
+    Ok(turns)
+}
+
+pub fn delete(conn: &Connection, conversation_id: &str) -> Result<bool> {
+    Ok(conn.execute("DELETE FROM conversations WHERE id = ?1", params![conversation_id])? > 0)
+}
+
+pub async fn list_conversations(app_handle: &AppHandle) -> Result<Vec<ConversationSummary>> {
+    let conn = crate::database::open_conn(app_handle)?;
+    list(&conn)
+}
+
+pub async fn get_conversation_history(app_handle: &AppHandle, conversation_id: &str) -> Result<Vec<ConversationMessage>> {
+    let conn = crate::database::open_conn(app_handle)?;
+    history(&conn, conversation_id)
+}
+
+pub async fn load_recent_turns(app_handle: &AppHandle, conversation_id: &str) -> Result<Vec<ChatMessage>> {
+    let conn = crate::database::open_conn(app_handle)?;
+    recent_turns(&conn, conversation_id, MAX_PROMPT_TURNS)
+}
+
+pub async fn save_exchange(app_handle: &AppHandle, question: &str, response: &RagResponse) -> Result<()> {
+    let mut conn = crate::database::open_conn(app_handle)?;
+    record_exchange(&mut conn, question, response)
+}
+
+pub async fn delete_conversation(app_handle: &AppHandle, conversation_id: &str) -> Result<bool> {
+    let conn = crate::database::open_conn(app_handle)?;
+    delete(&conn, conversation_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(conversation_id: &str, answer: &str) -> RagResponse {
+        RagResponse {
+            answer: answer.to_string(),
+            citations: vec![Citation {
+                entry_id: "e1".into(),
+                entry_title: None,
+                entry_date: Utc::now(),
+                snippet: "snippet".into(),
+                relevance_score: 0.9,
+                citation_number: 1,
+            }],
+            context_used: vec![],
+            confidence: 0.5,
+            processing_time_ms: 0,
+            model_used: "test".into(),
+            conversation_id: conversation_id.into(),
+            message_id: uuid::Uuid::new_v4().to_string(),
+            plan: None,
+        }
+    }
+
+    #[test]
+    fn test_conversation_round_trip() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("PRAGMA foreign_keys = ON;").unwrap();
+        crate::migrations::run_migrations(&mut conn).unwrap();
+
+        record_exchange(&mut conn, "When did I start running?", &response("c1", "In March [1].")).unwrap();
+        record_exchange(&mut conn, "And how far?", &response("c1", "5k [1].")).unwrap();
+
+        let list = list(&conn).unwrap();
+        assert_eq!(list.len(), 1);
+        assert_eq!(list[0].title, "When did I start running?");
+        assert_eq!(list[0].message_count, 4);
+        assert_eq!(list[0].last_message, "5k [1].");
+
+        let messages = history(&conn, "c1").unwrap();
+        let roles: Vec<&str> = messages.iter().map(|m| m.role.as_str()).collect();
+        assert_eq!(roles, ["user", "assistant", "user", "assistant"]);
+        assert_eq!(messages[1].citations.as_ref().unwrap()[0].entry_id, "e1");
+        assert!(messages[0].citations.is_none());
+
+        let turns = recent_turns(&conn, "c1", 3).unwrap();
+        assert_eq!(turns.iter().map(|t| t.content.as_str()).collect::<Vec<_>>(), ["In March [1].", "And how far?", "5k [1]."]);
+
+        assert!(delete(&conn, "c1").unwrap());
+        let remaining: i64 = conn.query_row("SELECT count(*) FROM conversation_messages", [], |r| r.get(0)).unwrap();
+        assert_eq!(remaining, 0);
+        assert!(history(&conn, "c1").is_err());
+    }
+
+    #[test]
+    fn test_title_is_truncated() {
+        let long = "word ".repeat(30);
+        let title = title_for(&long);
+        assert!(title.ends_with("...") && title.chars().count() <= TITLE_CHARS + 3);
+    }
+}
@@ -0,0 +1,109 @@
+use serde::Serialize;
+use tauri::AppHandle;
+
+use crate::database::EntryFilter;
+
+// CSV export of search results for spreadsheets: one row per matching entry, in the order the
+// search ranks them (date order when there is no query). UTF-8 with a BOM so Excel detects
+// the encoding, RFC 4180 quoting, and cells that a spreadsheet would run as a formula are
+// prefixed with an apostrophe.
+
+const HEADER: [&str; 6] = ["date", "title", "snippet", "tags", "source_path", "entry_id"];
+const SNIPPET_CHARS: usize = 240;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CsvExportReport {
+    pub path: String,
+    pub rows: u32,
+}
+
+fn escape(field: &str) -> String {
+    let field = if field.starts_with(['=', '+', '-', '@', '\t', '\r']) { format!("'{}", field) } else { field.to_string() };
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field
+    }
+}
+
+fn push_row(out: &mut String, fields: &[&str]) {
+    let row: Vec<String> = fields.iter().map(|f| escape(f)).collect();
+    out.push_str(&row.join(","));
+    out.push_str("\r\n");
+}
+
+// The start of an entry, on one line
+pub(crate) fn lead(body: &str) -> String {
+    let text = body.split_whitespace().collect::<Vec<_>>().join(" ");
+    match text.char_indices().nth(SNIPPET_CHARS) {
+        Some((cut, _)) => format!("{}…", text[..cut].trim_end()),
+        None => text,
+    }
+}
+
+pub async fn export_search_results(app_handle: &AppHandle, query: &str, filter: &EntryFilter, dest: &str) -> anyhow::Result<CsvExportReport> {
+    let query = query.trim();
+    // (entry, snippet) in result order
+    let rows: Vec<(crate::database::Entry, String)> = if query.is_empty() {
+        let conn = crate::database::open_conn(app_handle)?;
+        crate::database::filtered_entries(&conn, filter)?
+            .into_iter()
+            .map(|e| {
+                let snippet = lead(&e.body);
+                (e, snippet)
+            })
+            .collect()
+    } else {
+        crate::database::search_entries_fts_page(app_handle, query, u32::MAX, 0, filter, false, None)
+            .await?
+            .entries
+            .into_iter()
+            .map(|(e, fragments)| {
+                let snippet = crate::highlight::plain_text(&fragments).split_whitespace().collect::<Vec<_>>().join(" ");
+                (e, snippet)
+            })
+            .collect()
+    };
+
+    let conn = crate::database::open_conn(app_handle)?;
+    std::fs::write(dest, render(&conn, &rows)?)?;
+    Ok(CsvExportReport { path: dest.to_string(), rows: rows.len() as u32 })
+}
+
+// (entry, snippet) rows to CSV text, tags looked up on `conn`
+pub(crate) fn render(conn: &rusqlite::Connection, rows: &[(crate::database::Entry, String)]) -> anyhow::Result<String> {
+    let ids: Vec<String> = rows.iter().map(|(e, _)| e.id.clone()).collect();
+    let tags = crate::database::tags_for_entries(conn, &ids)?;
+
+    let mut out = String::from("\u{feff}");
+    push_row(&mut out, &HEADER);
+    for (entry, snippet) in rows {
+        let entry_tags = tags.get(&entry.id).map(|t| t.join("; ")).unwrap_or_default();
+        push_row(
+            &mut out,
+            &[
+                &entry.entry_date.format("%Y-%m-%d").to_string(),
+                entry.title.as_deref().unwrap_or(""),
+                snippet,
+                &entry_tags,
+                &entry.source_path,
+                &entry.id,
+            ],
+        );
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csv_escaping() {
+        let mut out = String::new();
+        push_row(&mut out, &["2021-03-05", "Said \"hi\", left", "line one\nline two", "=SUM(A1)", "plain"]);
+        assert_eq!(out, "2021-03-05,\"Said \"\"hi\"\", left\",\"line one\nline two\",'=SUM(A1),plain\r\n");
+        assert_eq!(lead("  a\n\nb  "), "a b");
+        assert!(lead(&"word ".repeat(100)).ends_with('…'));
+    }
+}
@@ -6,6 +6,8 @@ use chrono::{DateTime, Utc};
 use crate::import::ParsedFile;
 use std::path::{PathBuf};
 use rusqlite::{params, Connection, OptionalExtension};
+use sha2::{Digest, Sha256};
+use crate::db_pool::PooledConnection;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Entry {
@@ -26,56 +28,37 @@ pub struct Entry {
 
 pub async fn init_database(app_handle: &AppHandle) -> Result<()> {
     let _ = std::fs::create_dir_all(get_db_dir(app_handle)?);
-    let conn = open_conn(app_handle)?;
+    let mut conn = open_conn(app_handle)?;
+    prepare_conn(&mut conn)?;
+    // Not fatal: without a keychain the secrets stay put and the migration is retried next start
+    if let Err(e) = crate::secrets::migrate_settings(&conn) {
+        tracing::warn!("could not move secrets to the keychain: {}", e);
+    }
+    Ok(())
+}
+
+// WAL mode and schema migrations; shared with the CLI, which has no AppHandle
+pub(crate) fn prepare_conn(conn: &mut Connection) -> Result<()> {
+    // journal_mode can't change inside a transaction, so set pragmas before migrating
     conn.execute_batch(
         r#"
         PRAGMA journal_mode = WAL;
         PRAGMA foreign_keys = ON;
-
-        CREATE TABLE IF NOT EXISTS entries (
-            id TEXT PRIMARY KEY,
-            title TEXT,
-            body TEXT NOT NULL,
-            entry_date TEXT NOT NULL,
-            entry_timezone TEXT NOT NULL,
-            source_path TEXT NOT NULL,
-            source_type TEXT NOT NULL,
-            text_hash TEXT NOT NULL UNIQUE,
-            embedding BLOB,
-            created_at TEXT NOT NULL,
-            updated_at TEXT NOT NULL,
-            sentiment REAL,
-            language TEXT
-        );
-
-        CREATE INDEX IF NOT EXISTS idx_entries_entry_date ON entries(entry_date);
-        CREATE INDEX IF NOT EXISTS idx_entries_text_hash ON entries(text_hash);
-
-        -- Full-text search virtual table
-        CREATE VIRTUAL TABLE IF NOT EXISTS entries_fts
-        USING fts5(
-            title,
-            body,
-            entry_id UNINDEXED
-        );
-
-        -- Settings table (key/value)
-        CREATE TABLE IF NOT EXISTS settings (
-            key TEXT PRIMARY KEY,
-            value TEXT NOT NULL
-        );
         "#
     )?;
+    crate::migrations::run_migrations(conn)?;
     Ok(())
 }
 
 // Helper: app data dir
-fn get_db_dir(app_handle: &AppHandle) -> Result<PathBuf> {
+pub(crate) fn get_db_dir(app_handle: &AppHandle) -> Result<PathBuf> {
     match app_handle.path().app_data_dir() {
         Ok(mut dir) => {
             dir.push("journal-reader");
             Ok(dir)
         }
+        // Apps on iOS and Android can only write inside their own container
+        Err(e) if cfg!(mobile) => Err(anyhow::anyhow!("No app data directory: {}", e)),
         Err(_) => {
             // Fallback: current working directory
             Ok(std::env::current_dir()?)
@@ -83,16 +66,177 @@ fn get_db_dir(app_handle: &AppHandle) -> Result<PathBuf> {
     }
 }
 
-fn get_db_file_path(app_handle: &AppHandle) -> Result<PathBuf> {
-    let mut path = get_db_dir(app_handle)?;
-    path.push("journal.db");
-    Ok(path)
+// The active journal's database (see journals.rs)
+pub(crate) fn get_db_file_path(app_handle: &AppHandle) -> Result<PathBuf> {
+    crate::journals::active_path(&get_db_dir(app_handle)?)
 }
 
-fn open_conn(app_handle: &AppHandle) -> Result<Connection> {
+// Raw SQLCipher key for the current session, set once the user unlocks an encrypted database
+static DB_KEY: std::sync::Mutex<Option<String>> = std::sync::Mutex::new(None);
+
+fn current_db_key() -> Option<String> {
+    DB_KEY.lock().ok().and_then(|k| k.clone())
+}
+
+fn set_db_key(key: Option<String>) {
+    if let Ok(mut guard) = DB_KEY.lock() {
+        *guard = key;
+    }
+}
+
+pub(crate) fn open_conn(app_handle: &AppHandle) -> Result<PooledConnection> {
     let db_path = get_db_file_path(app_handle)?;
-    let conn = Connection::open(db_path)?;
-    Ok(conn)
+    open_conn_at(&db_path)
+}
+
+pub(crate) fn open_conn_at(db_path: &std::path::Path) -> Result<PooledConnection> {
+    let key = current_db_key();
+    if key.is_none() && crate::encryption::is_encrypted(db_path) {
+        return Err(anyhow::anyhow!("Database is encrypted and locked; unlock it with your passphrase"));
+    }
+    crate::db_pool::get(db_path, key.as_deref())
+}
+
+// Column order expected by entry_from_row
+pub(crate) const ENTRY_COLUMNS: &str = "id, title, body, entry_date, entry_timezone, source_path, source_type, text_hash, created_at, updated_at, sentiment, language";
+
+fn parse_ts(value: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|d| d.with_timezone(&Utc))
+        .unwrap_or_else(|_| Utc::now())
+}
+
+pub(crate) fn entry_from_row(row: &rusqlite::Row) -> rusqlite::Result<Entry> {
+    Ok(Entry {
+        id: row.get(0)?,
+        title: row.get(1)?,
+        body: row.get(2)?,
+        entry_date: parse_ts(&row.get::<_, String>(3)?),
+        entry_timezone: row.get(4)?,
+        source_path: row.get(5)?,
+        source_type: row.get(6)?,
+        text_hash: row.get(7)?,
+        embedding: None,
+        created_at: parse_ts(&row.get::<_, String>(8)?),
+        updated_at: parse_ts(&row.get::<_, String>(9)?),
+        sentiment: row.get(10).ok(),
+        language: row.get(11).ok(),
+    })
+}
+
+// Optional restrictions shared by the search queries, applied in SQL against alias `e`.
+// Dates are RFC3339 timestamps or plain YYYY-MM-DD days (date_to is then inclusive).
+// Entities are names of extracted people, places or events, matched case-insensitively.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct EntryFilter {
+    pub date_from: Option<String>,
+    pub date_to: Option<String>,
+    pub tags: Option<Vec<String>>,
+    pub source_types: Option<Vec<String>>,
+    pub entities: Option<Vec<String>>,
+    // ISO 639-3 codes as stored by import::detect_language
+    #[serde(default)]
+    pub languages: Option<Vec<String>>,
+}
+
+fn filter_bound(value: &str, upper: bool) -> Option<(String, &'static str)> {
+    let value = value.trim();
+    if let Ok(day) = chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        if upper {
+            // Everything before the start of the next day
+            let next = day.succ_opt()?;
+            return Some((next.and_hms_opt(0, 0, 0)?.and_utc().to_rfc3339(), "<"));
+        }
+        return Some((day.and_hms_opt(0, 0, 0)?.and_utc().to_rfc3339(), ">="));
+    }
+    let ts = DateTime::parse_from_rfc3339(value).ok()?.with_timezone(&Utc);
+    Some((ts.to_rfc3339(), if upper { "<=" } else { ">=" }))
+}
+
+impl EntryFilter {
+    pub fn is_empty(&self) -> bool {
+        let empty = |list: &Option<Vec<String>>| list.as_ref().is_none_or(|l| l.is_empty());
+        self.date_from.is_none()
+            && self.date_to.is_none()
+            && empty(&self.tags)
+            && empty(&self.source_types)
+            && empty(&self.entities)
+            && empty(&self.languages)
+    }
+
+    // " AND ..." conditions with numbered placeholders starting at `first_param`, plus their values
+    pub fn to_sql(&self, first_param: usize) -> Result<(String, Vec<rusqlite::types::Value>)> {
+        use rusqlite::types::Value;
+        let mut sql = String::new();
+        let mut values: Vec<Value> = Vec::new();
+
+        for (raw, upper) in [(&self.date_from, false), (&self.date_to, true)] {
+            if let Some(raw) = raw {
+                let (bound, op) = filter_bound(raw, upper)
+                    .ok_or_else(|| anyhow::anyhow!("Invalid date filter: {}", raw))?;
+                sql.push_str(&format!(" AND e.entry_date {} ?{}", op, first_param + values.len()));
+                values.push(Value::Text(bound));
+            }
+        }
+
+        // Any of the given values matches; entity names are compared in normalized form
+        type ListFilter<'a> = (&'static str, &'a Option<Vec<String>>, fn(&str) -> String);
+        let lists: [ListFilter; 4] = [
+            (" AND e.id IN (SELECT entry_id FROM entry_tags WHERE tag IN ({}))", &self.tags, str::to_string),
+            (" AND e.source_type IN ({})", &self.source_types, str::to_string),
+            (
+                " AND e.id IN (SELECT ee.entry_id FROM entry_entities ee JOIN entities en ON en.id = ee.entity_id \
+                 WHERE en.normalized IN ({}))",
+                &self.entities,
+                crate::entities::normalize,
+            ),
+            (" AND e.language IN ({})", &self.languages, str::to_string),
+        ];
+        for (template, items, convert) in lists {
+            let Some(items) = items.as_ref().filter(|i| !i.is_empty()) else { continue };
+            let placeholders: Vec<String> = (0..items.len())
+                .map(|i| format!("?{}", first_param + values.len() + i))
+                .collect();
+            sql.push_str(&template.replace("{}", &placeholders.join(", ")));
+            values.extend(items.iter().map(|i| Value::Text(convert(i))));
+        }
+
+        Ok((sql, values))
+    }
+}
+
+pub(crate) fn add_entry_tags(conn: &Connection, entry_id: &str, tags: &[String]) -> Result<()> {
+    let mut stmt = conn.prepare_cached("INSERT OR IGNORE INTO entry_tags (entry_id, tag) VALUES (?1, ?2)")?;
+    for tag in tags {
+        stmt.execute(params![entry_id, tag])?;
+    }
+    Ok(())
+}
+
+// Every entry matching the filter, oldest first; used by summaries and exports
+pub(crate) fn filtered_entries(conn: &Connection, filter: &EntryFilter) -> Result<Vec<Entry>> {
+    let (conditions, values) = filter.to_sql(1)?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM entries e WHERE 1 = 1{} ORDER BY e.entry_date ASC, e.id",
+        ENTRY_COLUMNS, conditions
+    ))?;
+    let rows = stmt.query_map(rusqlite::params_from_iter(values), entry_from_row)?;
+    Ok(rows.collect::<rusqlite::Result<_>>()?)
+}
+
+// Tags for each of the given entries (entries without tags are absent from the map)
+pub(crate) fn tags_for_entries(conn: &Connection, ids: &[String]) -> Result<std::collections::HashMap<String, Vec<String>>> {
+    let mut map: std::collections::HashMap<String, Vec<String>> = std::collections::HashMap::new();
+    let mut stmt = conn.prepare_cached("SELECT tag FROM entry_tags WHERE entry_id = ?1 ORDER BY tag")?;
+    for id in ids {
+        let tags = stmt
+            .query_map(params![id], |r| r.get::<_, String>(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        if !tags.is_empty() {
+            map.insert(id.clone(), tags);
+        }
+    }
+    Ok(map)
 }
 
 pub async fn save_entry(
@@ -100,10 +244,36 @@ pub async fn save_entry(
     parsed_file: ParsedFile,
     entry_date: DateTime<Utc>,
     entry_timezone: String,
+) -> Result<String> {
+    let conn = open_conn(app_handle)?;
+    let mut hook_data = serde_json::json!({
+        "title": parsed_file.title,
+        "entry_date": entry_date.to_rfc3339(),
+        "source_type": parsed_file.file_type.as_str(),
+        "source_path": parsed_file.path,
+    });
+    let source = std::path::PathBuf::from(&parsed_file.path);
+    let entry_id = insert_entry(&conn, parsed_file, entry_date, entry_timezone)?;
+    // A failed copy doesn't fail the import; the entry just has no archived original
+    if setting_enabled(&conn, "archive_originals", false) && source.is_file() {
+        if let Err(e) = crate::archive::archive_entry(app_handle, &conn, &entry_id, &source) {
+            tracing::warn!("could not archive {}: {}", source.display(), e);
+        }
+    }
+    hook_data["id"] = serde_json::json!(entry_id);
+    crate::hooks::emit(app_handle, "entry-created", hook_data);
+    Ok(entry_id)
+}
+
+pub(crate) fn insert_entry(
+    conn: &Connection,
+    parsed_file: ParsedFile,
+    entry_date: DateTime<Utc>,
+    entry_timezone: String,
 ) -> Result<String> {
     let entry_id = uuid::Uuid::new_v4().to_string();
-    
-    if let Some(existing_id) = check_duplicate(app_handle, &parsed_file.text_hash).await? {
+
+    if let Some(existing_id) = find_duplicate(conn, &parsed_file.text_hash)? {
         return Err(anyhow::anyhow!(
             "Duplicate content found (existing entry: {})", 
             existing_id
@@ -111,12 +281,18 @@ pub async fn save_entry(
     }
     
     let now = Utc::now().to_rfc3339();
-    let conn = open_conn(app_handle)?;
+    // Local files can be re-synced later; other sources have no file to watch
+    let (source_stat, source_hash) = match parsed_file.file_type {
+        crate::import::FileType::Txt | crate::import::FileType::Docx => {
+            (crate::import::source_stat(&parsed_file.path), crate::import::source_hash(&parsed_file.path))
+        }
+        _ => (None, None),
+    };
     conn.execute(
         r#"INSERT INTO entries (
             id, title, body, entry_date, entry_timezone, source_path, source_type, text_hash,
-            embedding, created_at, updated_at, sentiment, language
-        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, NULL, ?9, ?10, NULL, NULL)"#,
+            embedding, created_at, updated_at, sentiment, language, source_mtime, source_size, source_hash
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, NULL, ?9, ?10, ?11, ?12, ?13, ?14, ?15)"#,
         params![
             entry_id,
             parsed_file.title,
@@ -128,6 +304,11 @@ pub async fn save_entry(
             parsed_file.text_hash,
             now,
             now,
+            crate::sentiment::score(&parsed_file.content),
+            crate::import::detect_language(&parsed_file.content),
+            source_stat.map(|s| s.0),
+            source_stat.map(|s| s.1),
+            source_hash,
         ],
     )?;
 
@@ -141,13 +322,17 @@ pub async fn save_entry(
         ],
     )?;
 
-    eprintln!("[db] saved entry id={} path={} date={} tz={}", entry_id, parsed_file.path, entry_date, entry_timezone);
+    tracing::debug!("saved entry id={} path={} date={} tz={}", entry_id, parsed_file.path, entry_date, entry_timezone);
 
     Ok(entry_id)
 }
 
 pub async fn check_duplicate(app_handle: &AppHandle, text_hash: &str) -> Result<Option<String>> {
     let conn = open_conn(app_handle)?;
+    find_duplicate(&conn, text_hash)
+}
+
+pub(crate) fn find_duplicate(conn: &Connection, text_hash: &str) -> Result<Option<String>> {
     let id: Option<String> = conn
         .query_row(
             "SELECT id FROM entries WHERE text_hash = ?1 LIMIT 1",
@@ -160,156 +345,370 @@ pub async fn check_duplicate(app_handle: &AppHandle, text_hash: &str) -> Result<
 
 // Import jobs removed in simplified flow (we import synchronously)
 
-pub async fn list_entries_by_month(
-    app_handle: &AppHandle,
-    year: i32,
-    month: u32,
-) -> Result<Vec<Entry>> {
+pub async fn get_entry_by_id(app_handle: &AppHandle, entry_id: &str) -> Result<Option<Entry>> {
     let conn = open_conn(app_handle)?;
-    let start = format!("{:04}-{:02}-01T00:00:00Z", year, month);
-    // next month
-    let (ny, nm) = if month == 12 { (year + 1, 1) } else { (year, month + 1) };
-    let end = format!("{:04}-{:02}-01T00:00:00Z", ny, nm);
-
-    let mut stmt = conn.prepare(
+    let mut stmt = conn.prepare_cached(
         r#"SELECT id, title, body, entry_date, entry_timezone, source_path, source_type, text_hash,
                    created_at, updated_at, sentiment, language
-            FROM entries
-            WHERE entry_date >= ?1 AND entry_date < ?2
-            ORDER BY entry_date ASC"#,
+            FROM entries WHERE id = ?1"#,
     )?;
+    let row = stmt.query_row(params![entry_id], entry_from_row).optional()?;
+    Ok(row)
+}
 
-    let rows = stmt.query_map(params![start, end], |row| {
-        let entry_date_str: String = row.get(3)?;
-        let entry_date = DateTime::parse_from_rfc3339(&entry_date_str)
-            .map(|d| d.with_timezone(&Utc))
-            .unwrap_or_else(|_| Utc::now());
-        Ok(Entry {
-            id: row.get(0)?,
-            title: row.get(1)?,
-            body: row.get(2)?,
-            entry_date,
-            entry_timezone: row.get(4)?,
-            source_path: row.get(5)?,
-            source_type: row.get(6)?,
-            text_hash: row.get(7)?,
-            embedding: None,
-            created_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(8)?)
-                .map(|d| d.with_timezone(&Utc)).unwrap_or_else(|_| Utc::now()),
-            updated_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(9)?)
-                .map(|d| d.with_timezone(&Utc)).unwrap_or_else(|_| Utc::now()),
-            sentiment: row.get(10).ok(),
-            language: row.get(11).ok(),
-        })
-    })?;
+// Saves an edited title and body and refreshes the search index. Embeddings and chunks go
+// stale through the new text_hash; a changed body also sets `embedding_dirty` so the edit
+// worker (edit_embeddings.rs) re-embeds it without waiting for a full backfill
+// With the `write_back_sources` setting on, entries imported from a .txt file also rewrite
+// that file; `overwrite_source` skips the check for edits made on disk since the last sync
+pub async fn update_entry(app_handle: &AppHandle, entry_id: &str, title: Option<String>, body: &str, overwrite_source: bool) -> Result<Entry> {
+    let conn = open_conn(app_handle)?;
+    // A sensitive entry's text never goes back to a plaintext file
+    if !setting_enabled(&conn, "write_back_sources", false) || crate::sensitive::is_sensitive(&conn, entry_id)? {
+        return set_entry_text(&conn, entry_id, title, body);
+    }
+    let tx = conn.unchecked_transaction()?;
+    let entry = set_entry_text(&tx, entry_id, title, body)?;
+    crate::resync::write_back(&tx, &entry, overwrite_source)?;
+    tx.commit()?;
+    Ok(entry)
+}
+
+pub(crate) fn set_entry_text(conn: &Connection, entry_id: &str, title: Option<String>, body: &str) -> Result<Entry> {
+    let title = title.map(|t| t.trim().to_string()).filter(|t| !t.is_empty());
+    if crate::sensitive::is_sensitive(conn, entry_id)? {
+        crate::sensitive::update_sealed(conn, entry_id, title.as_deref(), body)?;
+        let entry = conn.query_row(&format!("SELECT {} FROM entries WHERE id = ?1", ENTRY_COLUMNS), params![entry_id], entry_from_row)?;
+        return Ok(entry);
+    }
+    crate::versions::record(conn, entry_id, title.as_deref(), body)?;
+    let text_hash = format!("{:x}", Sha256::digest(body.as_bytes()));
+    let changed = conn.execute(
+        r#"UPDATE entries SET title = ?2, body = ?3, text_hash = ?4, sentiment = ?5, language = ?6, updated_at = ?7,
+                embedding_dirty = CASE WHEN text_hash IS NOT ?4 THEN 1 ELSE embedding_dirty END, preview_hash = NULL
+            WHERE id = ?1"#,
+        params![
+            entry_id,
+            title,
+            body,
+            text_hash,
+            crate::sentiment::score(body),
+            crate::import::detect_language(body),
+            Utc::now().to_rfc3339()
+        ],
+    )?;
+    if changed == 0 {
+        anyhow::bail!("Entry not found");
+    }
+    conn.execute("DELETE FROM entries_fts WHERE entry_id = ?1", params![entry_id])?;
+    conn.execute(
+        "INSERT INTO entries_fts (title, body, entry_id) VALUES (?1, ?2, ?3)",
+        params![title.clone().unwrap_or_default(), body, entry_id],
+    )?;
+    let entry = conn.query_row(&format!("SELECT {} FROM entries WHERE id = ?1", ENTRY_COLUMNS), params![entry_id], entry_from_row)?;
+    Ok(entry)
+}
 
+// Newest first; used by the search pipeline when it needs to scan candidates
+pub async fn list_entries(
+    app_handle: &AppHandle,
+    limit: Option<u32>,
+    offset: Option<u32>,
+) -> Result<Vec<Entry>> {
+    let conn = open_conn(app_handle)?;
+    let mut stmt = conn.prepare_cached(&format!(
+        "SELECT {} FROM entries ORDER BY entry_date DESC LIMIT ?1 OFFSET ?2",
+        ENTRY_COLUMNS
+    ))?;
+    let limit = limit.map(|l| l as i64).unwrap_or(-1);
+    let rows = stmt.query_map(params![limit, offset.unwrap_or(0) as i64], entry_from_row)?;
     let mut entries = Vec::new();
     for r in rows { entries.push(r?); }
     Ok(entries)
 }
 
-pub async fn get_entry_by_id(app_handle: &AppHandle, entry_id: &str) -> Result<Option<Entry>> {
+// FTS results without snippets, in rank order
+pub async fn search_entries_fts(app_handle: &AppHandle, query: &str, limit: u32, filter: &EntryFilter) -> Result<Vec<Entry>> {
+    let results = search_entries_fts_simple(app_handle, query, limit, filter).await?;
+    Ok(results.into_iter().map(|(entry, _)| entry).collect())
+}
+
+// `{from}` and `{filters}` are filled in per query; the row queries bind ?1 = match argument,
+// ?2 = limit, ?3 = offset, then the EntryFilter parameters
+const FTS_FROM: &str = "FROM entries_fts f JOIN entries e ON e.id = f.entry_id WHERE entries_fts MATCH ?1";
+const LIKE_FROM: &str =
+    r#"FROM entries e WHERE (e.body LIKE ?1 ESCAPE '\' OR IFNULL(e.title, '') LIKE ?1 ESCAPE '\')"#;
+
+const FTS_MATCH_SQL: &str = r#"SELECT 
+    e.id, e.title, e.body, e.entry_date, e.entry_timezone, e.source_path, e.source_type, e.text_hash,
+    e.created_at, e.updated_at, e.sentiment, e.language,
+    highlight(entries_fts, 1, char(57344), char(57345)) AS marked
+{from}{filters}
+ORDER BY {rank} ASC
+LIMIT ?2 OFFSET ?3"#;
+
+const LIKE_MATCH_SQL: &str = r#"SELECT 
+    e.id, e.title, e.body, e.entry_date, e.entry_timezone, e.source_path, e.source_type, e.text_hash,
+    e.created_at, e.updated_at, e.sentiment, e.language,
+    e.body AS marked
+{from}{filters}
+ORDER BY e.entry_date DESC
+LIMIT ?2 OFFSET ?3"#;
+
+// A search hit with highlighted snippet fragments of its body
+pub type EntryHit = (Entry, Vec<crate::highlight::SnippetFragment>);
+
+// How a user query is run against the index
+enum MatchPlan {
+    Fts(String),
+    // Trigram indexes can't match fewer than 3 characters (common for CJK words); scan instead.
+    // Holds the LIKE pattern and the raw text to highlight.
+    Like(String, String),
+    Nothing,
+}
+
+impl MatchPlan {
+    fn for_query(conn: &Connection, query: &str) -> Self {
+        let trigram = trigram_enabled(conn);
+        if trigram && query.chars().count() < 3 {
+            return MatchPlan::Like(format!("%{}%", escape_like(query)), query.to_string());
+        }
+        // Sanitized user syntax; with trigram each quoted term is a plain substring match.
+        // Input with no indexable words (symbols, emoji) is scanned for literally instead.
+        match crate::fts_query::to_fts5(query, trigram) {
+            Some(expr) => MatchPlan::Fts(expr),
+            None => match crate::fts_query::like_text(query) {
+                Some(text) => MatchPlan::Like(format!("%{}%", escape_like(&text)), text),
+                None => MatchPlan::Nothing,
+            },
+        }
+    }
+
+    fn run(&self, conn: &Connection, limit: i64, offset: i64, filter: &EntryFilter) -> Result<Vec<EntryHit>> {
+        use crate::highlight::{fragments_for_terms, fragments_from_marked};
+        let rows = match self {
+            MatchPlan::Fts(expr) => query_entries_marked(conn, FTS_MATCH_SQL, FTS_FROM, expr, limit, offset, filter)?,
+            MatchPlan::Like(pattern, _) => query_entries_marked(conn, LIKE_MATCH_SQL, LIKE_FROM, pattern, limit, offset, filter)?,
+            MatchPlan::Nothing => return Ok(vec![]),
+        };
+        Ok(rows
+            .into_iter()
+            .map(|(entry, marked)| {
+                let fragments = match self {
+                    MatchPlan::Like(_, needle) => fragments_for_terms(&marked, std::slice::from_ref(needle)),
+                    _ => fragments_from_marked(&marked),
+                };
+                (entry, fragments)
+            })
+            .collect())
+    }
+
+    // Date of the earliest matching entry
+    fn first_date(&self, conn: &Connection) -> Result<Option<DateTime<Utc>>> {
+        let (from, arg) = match self {
+            MatchPlan::Fts(expr) => (FTS_FROM, expr),
+            MatchPlan::Like(pattern, _) => (LIKE_FROM, pattern),
+            MatchPlan::Nothing => return Ok(None),
+        };
+        let first: Option<String> = conn
+            .prepare_cached(&format!("SELECT MIN(e.entry_date) {}", from))?
+            .query_row([arg], |r| r.get(0))?;
+        Ok(first.map(|d| parse_ts(&d)))
+    }
+
+    fn count(&self, conn: &Connection, filter: &EntryFilter) -> Result<u64> {
+        let (from, arg) = match self {
+            MatchPlan::Fts(expr) => (FTS_FROM, expr),
+            MatchPlan::Like(pattern, _) => (LIKE_FROM, pattern),
+            MatchPlan::Nothing => return Ok(0),
+        };
+        // No limit/offset here, so the filter parameters follow ?1 directly
+        let (filter_sql, filter_values) = filter.to_sql(2)?;
+        let sql = format!("SELECT COUNT(*) {}{}", from, filter_sql);
+        let mut values: Vec<rusqlite::types::Value> = vec![arg.clone().into()];
+        values.extend(filter_values);
+        let n: i64 = conn
+            .prepare_cached(&sql)?
+            .query_row(rusqlite::params_from_iter(values), |r| r.get(0))?;
+        Ok(n as u64)
+    }
+}
+
+// Rows are the entry columns plus the body with matches wrapped in highlight markers
+pub(crate) const DEFAULT_TITLE_WEIGHT: f64 = 5.0;
+pub(crate) const DEFAULT_BODY_WEIGHT: f64 = 1.0;
+
+// bm25() with the title/body weights from settings; a title match counts `fts_title_weight`
+// times as much as one in the body, so an entry titled "Paris trip" outranks passing mentions
+fn rank_expr(conn: &Connection) -> String {
+    let weight = |key: &str, default: f64| {
+        read_setting(conn, key)
+            .and_then(|v| v.trim().parse::<f64>().ok())
+            .filter(|w| w.is_finite())
+            .map(|w| w.clamp(0.0, 100.0))
+            .unwrap_or(default)
+    };
+    format!(
+        "bm25(entries_fts, {:?}, {:?})",
+        weight("fts_title_weight", DEFAULT_TITLE_WEIGHT),
+        weight("fts_body_weight", DEFAULT_BODY_WEIGHT)
+    )
+}
+
+fn query_entries_marked(
+    conn: &Connection,
+    sql: &str,
+    from: &str,
+    arg: &str,
+    limit: i64,
+    offset: i64,
+    filter: &EntryFilter,
+) -> Result<Vec<(Entry, String)>> {
+    let (filter_sql, filter_values) = filter.to_sql(4)?;
+    let sql = sql.replace("{from}", from).replace("{filters}", &filter_sql).replace("{rank}", &rank_expr(conn));
+    let mut stmt = conn.prepare_cached(&sql)?;
+    tracing::debug!("fts execute query");
+    let mut values: Vec<rusqlite::types::Value> = vec![arg.to_string().into(), limit.into(), offset.into()];
+    values.extend(filter_values);
+    let rows = stmt.query_map(rusqlite::params_from_iter(values), |row| {
+        let entry = entry_from_row(row)?;
+        let marked: String = row.get(12)?;
+        Ok((entry, marked))
+    })?;
+
+    let mut results = Vec::new();
+    for r in rows { results.push(r?); }
+    tracing::debug!("fts rows={}", results.len());
+    Ok(results)
+}
+
+// When the journal first mentions `query` (user search syntax), e.g. "started new job"
+pub(crate) fn first_match_date(conn: &Connection, query: &str) -> Result<Option<DateTime<Utc>>> {
+    MatchPlan::for_query(conn, query).first_date(conn)
+}
+
+pub(crate) fn trigram_enabled(conn: &Connection) -> bool {
+    read_setting(conn, "fts_tokenizer").as_deref() == Some("trigram")
+}
+
+// One page of search hits plus the total number of matches
+pub struct EntryPage {
+    pub entries: Vec<EntryHit>,
+    pub total: u64,
+}
+
+// Accepts the search syntax described in fts_query.rs
+pub async fn search_entries_fts_simple(
+    app_handle: &AppHandle,
+    query: &str,
+    limit: u32,
+    filter: &EntryFilter,
+) -> Result<Vec<EntryHit>> {
+    Ok(search_entries_fts_page(app_handle, query, limit, 0, filter, false, None).await?.entries)
+}
+
+// Ranked full-text search on an open connection
+pub(crate) fn search_fts(conn: &Connection, query: &str, limit: u32, filter: &EntryFilter) -> Result<Vec<EntryHit>> {
+    if query.trim().is_empty() {
+        return Ok(vec![]);
+    }
+    MatchPlan::for_query(conn, query).run(conn, limit as i64, 0, filter)
+}
+
+// `with_total` adds a COUNT query; skip it when only the first hits are wanted. With a
+// `token`, the queries can be interrupted through it (see search_cancel.rs).
+pub async fn search_entries_fts_page(
+    app_handle: &AppHandle,
+    query: &str,
+    limit: u32,
+    offset: u32,
+    filter: &EntryFilter,
+    with_total: bool,
+    token: Option<std::sync::Arc<crate::search_cancel::SearchToken>>,
+) -> Result<EntryPage> {
+    if query.trim().is_empty() { return Ok(EntryPage { entries: vec![], total: 0 }); }
+    let db_path = get_db_file_path(app_handle)?;
+    let q = query.to_string();
+    let filter = filter.clone();
+    let page = tokio::task::spawn_blocking(move || -> Result<EntryPage> {
+        // rudimentary tracing
+        tracing::debug!("fts open db");
+        let conn = open_conn_at(&db_path)?;
+        if let Some(token) = &token {
+            token.attach(&conn)?;
+        }
+        tracing::debug!("fts prepare statement");
+        let plan = MatchPlan::for_query(&conn, &q);
+        let entries = plan.run(&conn, limit as i64, offset as i64, &filter)?;
+        let total = if with_total {
+            plan.count(&conn, &filter)?
+        } else {
+            entries.len() as u64
+        };
+        Ok(EntryPage { entries, total })
+    })
+    .await
+    .map_err(|e| anyhow::anyhow!(e.to_string()))??;
+
+    Ok(page)
+}
+
+// Entries matching only the filter, oldest first, for searches that were nothing but a date
+// phrase ("last summer"); their fragments are the start of each entry
+pub async fn filtered_entries_page(app_handle: &AppHandle, filter: &EntryFilter, limit: u32, offset: u32) -> Result<EntryPage> {
     let conn = open_conn(app_handle)?;
-    let mut stmt = conn.prepare(
-        r#"SELECT id, title, body, entry_date, entry_timezone, source_path, source_type, text_hash,
-                   created_at, updated_at, sentiment, language
-            FROM entries WHERE id = ?1"#,
+    let (conditions, values) = filter.to_sql(3)?;
+    let total: i64 = conn.query_row(
+        &format!("SELECT COUNT(*) FROM entries e WHERE 1 = 1{}", filter.to_sql(1)?.0),
+        rusqlite::params_from_iter(values.clone()),
+        |r| r.get(0),
     )?;
-    let row = stmt.query_row(params![entry_id], |row| {
-        let entry_date_str: String = row.get(3)?;
-        let entry_date = DateTime::parse_from_rfc3339(&entry_date_str)
-            .map(|d| d.with_timezone(&Utc))
-            .unwrap_or_else(|_| Utc::now());
-        Ok(Entry {
-            id: row.get(0)?,
-            title: row.get(1)?,
-            body: row.get(2)?,
-            entry_date,
-            entry_timezone: row.get(4)?,
-            source_path: row.get(5)?,
-            source_type: row.get(6)?,
-            text_hash: row.get(7)?,
-            embedding: None,
-            created_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(8)?)
-                .map(|d| d.with_timezone(&Utc)).unwrap_or_else(|_| Utc::now()),
-            updated_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(9)?)
-                .map(|d| d.with_timezone(&Utc)).unwrap_or_else(|_| Utc::now()),
-            sentiment: row.get(10).ok(),
-            language: row.get(11).ok(),
-        })
-    }).optional()?;
-    Ok(row)
+    let mut params: Vec<rusqlite::types::Value> = vec![(limit as i64).into(), (offset as i64).into()];
+    params.extend(values);
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM entries e WHERE 1 = 1{} ORDER BY e.entry_date ASC, e.id LIMIT ?1 OFFSET ?2",
+        ENTRY_COLUMNS, conditions
+    ))?;
+    let entries = stmt
+        .query_map(rusqlite::params_from_iter(params), entry_from_row)?
+        .map(|r| r.map(|e| {
+            let fragments = crate::highlight::fragments_for_terms(&e.body, &[]);
+            (e, fragments)
+        }))
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(EntryPage { entries, total: total as u64 })
 }
 
-// Simplified app: no FTS at this stage
-pub async fn search_entries_fts_simple(
+// Like search_entries_fts_simple, but each query word also matches indexed terms within a
+// small edit distance (see fuzzy.rs). Trigram indexes have no word vocabulary, so they
+// fall back to the exact search.
+pub async fn search_entries_fuzzy(
     app_handle: &AppHandle,
     query: &str,
     limit: u32,
-) -> Result<Vec<(Entry, String)>> {
+    filter: &EntryFilter,
+) -> Result<Vec<EntryHit>> {
     if query.trim().is_empty() { return Ok(vec![]); }
     let db_path = get_db_file_path(app_handle)?;
     let q = query.to_string();
     let lim = limit as i64;
-    let results = tokio::task::spawn_blocking(move || -> Result<Vec<(Entry, String)>> {
-        // rudimentary tracing
-        eprintln!("[fts] open db");
-        let conn = Connection::open(db_path)?;
-        eprintln!("[fts] prepare statement");
-        let mut stmt = conn.prepare(
-            r#"SELECT 
-                    e.id, e.title, e.body, e.entry_date, e.entry_timezone, e.source_path, e.source_type, e.text_hash,
-                    e.created_at, e.updated_at, e.sentiment, e.language,
-                    snippet(entries_fts, 1, '', '', '...', 10) AS snip
-                FROM entries_fts f
-                JOIN entries e ON e.id = f.entry_id
-                WHERE entries_fts MATCH ?1
-                ORDER BY bm25(entries_fts) ASC
-                LIMIT ?2"#,
-        )?;
-
-        eprintln!("[fts] execute query");
-        let rows = stmt.query_map(params![q, lim], |row| {
-            let entry_date_str: String = row.get(3)?;
-            let entry_date = DateTime::parse_from_rfc3339(&entry_date_str)
-                .map(|d| d.with_timezone(&Utc))
-                .unwrap_or_else(|_| Utc::now());
-            let created_at_str: String = row.get(8)?;
-            let updated_at_str: String = row.get(9)?;
-            let created_at = DateTime::parse_from_rfc3339(&created_at_str)
-                .map(|d| d.with_timezone(&Utc))
-                .unwrap_or_else(|_| Utc::now());
-            let updated_at = DateTime::parse_from_rfc3339(&updated_at_str)
-                .map(|d| d.with_timezone(&Utc))
-                .unwrap_or_else(|_| Utc::now());
-            let entry = Entry {
-                id: row.get(0)?,
-                title: row.get(1)?,
-                body: row.get(2)?,
-                entry_date,
-                entry_timezone: row.get(4)?,
-                source_path: row.get(5)?,
-                source_type: row.get(6)?,
-                text_hash: row.get(7)?,
-                embedding: None,
-                created_at,
-                updated_at,
-                sentiment: row.get(10).ok(),
-                language: row.get(11).ok(),
-            };
-            let snip: String = row.get(12)?;
-            Ok((entry, snip))
-        })?;
-
-        let mut results = Vec::new();
-        for r in rows { results.push(r?); }
-        eprintln!("[fts] rows={} ", results.len());
-        Ok(results)
+    let owned_filter = filter.clone();
+    let expanded = tokio::task::spawn_blocking(move || -> Result<Option<Vec<EntryHit>>> {
+        let conn = open_conn_at(&db_path)?;
+        if trigram_enabled(&conn) {
+            return Ok(None);
+        }
+        let Some(expr) = crate::fuzzy::expand_query(&conn, &q)? else { return Ok(Some(vec![])) };
+        tracing::debug!("fts fuzzy expr={}", expr);
+        Ok(Some(MatchPlan::Fts(expr).run(&conn, lim, 0, &owned_filter)?))
     })
     .await
     .map_err(|e| anyhow::anyhow!(e.to_string()))??;
 
-    Ok(results)
+    match expanded {
+        Some(results) => Ok(results),
+        None => search_entries_fts_simple(app_handle, query, limit, filter).await,
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -331,19 +730,218 @@ pub async fn get_db_info(app_handle: &AppHandle) -> Result<DbInfo> {
     })
 }
 
-pub async fn ensure_fts_populated(app_handle: &AppHandle) -> Result<()> {
-    let conn = open_conn(app_handle)?;
-    // Create FTS table if missing (idempotent)
-    conn.execute_batch(
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IndexStatus {
+    pub name: String,
+    pub table: String,
+    pub present: bool,
+}
+
+// The state of the search index, embeddings and schema, for telling why search finds nothing
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct DbHealth {
+    pub schema_version: i64,
+    // The version this build migrates to; a lower `schema_version` means a migration failed
+    pub app_schema_version: i64,
+    pub db_size: u64,
+    pub wal_size: u64,
+    pub fts_rows: u64,
+    // Entries search can't find, and index rows for entries that no longer exist
+    pub entries_missing_fts: u64,
+    pub orphaned_fts_rows: u64,
+    pub duplicate_fts_rows: u64,
+    pub fts_tokenizer_outdated: bool,
+    // Not yet embedded with the configured model (sensitive entries are never embedded)
+    pub entries_missing_embeddings: u64,
+    pub sensitive_entries: u64,
+    pub indexes: Vec<IndexStatus>,
+}
+
+// Indexes a fully migrated database has, from running the migrations on an empty one
+fn expected_indexes() -> Result<Vec<(String, String)>> {
+    let mut conn = Connection::open_in_memory()?;
+    crate::migrations::run_migrations(&mut conn)?;
+    index_names(&conn)
+}
+
+fn index_names(conn: &Connection) -> Result<Vec<(String, String)>> {
+    let mut stmt = conn.prepare(
+        "SELECT name, tbl_name FROM sqlite_master WHERE type = 'index' AND name NOT LIKE 'sqlite_autoindex_%' ORDER BY tbl_name, name",
+    )?;
+    let rows = stmt.query_map([], |r| Ok((r.get(0)?, r.get(1)?)))?;
+    Ok(rows.collect::<rusqlite::Result<_>>()?)
+}
+
+pub(crate) fn db_health(conn: &Connection, db_path: &std::path::Path) -> Result<DbHealth> {
+    let count = |sql: &str| -> Result<u64> { Ok(conn.query_row(sql, [], |r| r.get::<_, i64>(0))? as u64) };
+    let present: std::collections::HashSet<String> = index_names(conn)?.into_iter().map(|(name, _)| name).collect();
+    let config = crate::embeddings::load_config(conn);
+    let fts_rows = count("SELECT COUNT(*) FROM entries_fts")?;
+    Ok(DbHealth {
+        schema_version: crate::migrations::current_version(conn)?,
+        app_schema_version: crate::migrations::latest_version(),
+        db_size: file_size(db_path),
+        wal_size: file_size(&wal_path(db_path)),
+        fts_rows,
+        entries_missing_fts: count("SELECT COUNT(*) FROM entries WHERE id NOT IN (SELECT entry_id FROM entries_fts)")?,
+        orphaned_fts_rows: count("SELECT COUNT(*) FROM entries_fts WHERE entry_id NOT IN (SELECT id FROM entries)")?,
+        duplicate_fts_rows: fts_rows - count("SELECT COUNT(DISTINCT entry_id) FROM entries_fts")?,
+        fts_tokenizer_outdated: fts_tokenizer_outdated(conn)?,
+        entries_missing_embeddings: crate::embeddings::count_pending(conn, &config.model_key(), &config.embedding_key())?,
+        sensitive_entries: count("SELECT COUNT(*) FROM entries WHERE sensitive = 1")?,
+        indexes: expected_indexes()?
+            .into_iter()
+            .map(|(name, table)| IndexStatus { present: present.contains(&name), name, table })
+            .collect(),
+    })
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MaintenanceReport {
+    pub db_size_before: u64,
+    pub db_size_after: u64,
+    pub wal_size_before: u64,
+    pub wal_size_after: u64,
+    pub duration_ms: u64,
+    // Chunk vectors converted to the configured storage format
+    #[serde(default)]
+    pub vectors_compacted: u64,
+}
+
+fn file_size(path: &std::path::Path) -> u64 {
+    std::fs::metadata(path).map(|m| m.len()).unwrap_or(0)
+}
+
+fn wal_path(db_path: &std::path::Path) -> PathBuf {
+    let mut p = db_path.as_os_str().to_os_string();
+    p.push("-wal");
+    PathBuf::from(p)
+}
+
+// Reclaim space and refresh planner statistics. VACUUM rewrites the whole file, so this
+// can take a while on large journals; run it off the async runtime.
+pub async fn run_maintenance(app_handle: &AppHandle) -> Result<MaintenanceReport> {
+    let db_path = get_db_file_path(app_handle)?;
+    tokio::task::spawn_blocking(move || -> Result<MaintenanceReport> {
+        let started = std::time::Instant::now();
+        let wal = wal_path(&db_path);
+        let db_size_before = file_size(&db_path);
+        let wal_size_before = file_size(&wal);
+
+        let conn = open_conn_at(&db_path)?;
+        let vectors_compacted = crate::quantize::compact_chunks(&conn, crate::quantize::Quantization::load(&conn))?;
+        tracing::info!("maintenance: compacted {} chunk vectors", vectors_compacted);
+        tracing::info!("maintenance: fts optimize");
+        conn.execute("INSERT INTO entries_fts(entries_fts) VALUES('optimize')", [])?;
+        tracing::info!("maintenance: analyze");
+        conn.execute_batch("ANALYZE;")?;
+        tracing::info!("maintenance: vacuum");
+        conn.execute_batch("PRAGMA wal_checkpoint(TRUNCATE); VACUUM;")?;
+        // VACUUM goes through the WAL in WAL mode; fold it back and truncate
+        conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))?;
+        drop(conn);
+
+        let report = MaintenanceReport {
+            db_size_before,
+            db_size_after: file_size(&db_path),
+            wal_size_before,
+            wal_size_after: file_size(&wal),
+            duration_ms: started.elapsed().as_millis() as u64,
+            vectors_compacted,
+        };
+        tracing::info!(
+            "maintenance done db={}->{} wal={}->{} ms={}",
+            report.db_size_before, report.db_size_after, report.wal_size_before, report.wal_size_after, report.duration_ms
+        );
+        Ok(report)
+    })
+    .await
+    .map_err(|e| anyhow::anyhow!(e.to_string()))?
+}
+
+// Settings that change how entries_fts tokenizes text; changing any of them requires a reindex
+pub const FTS_TOKENIZER_SETTINGS: &[&str] = &["fts_tokenizer", "fts_stemming", "fts_remove_diacritics"];
+
+pub(crate) fn read_setting(conn: &Connection, key: &str) -> Option<String> {
+    conn.query_row("SELECT value FROM settings WHERE key = ?1", params![key], |r| r.get(0))
+        .optional()
+        .ok()
+        .flatten()
+}
+
+pub(crate) fn setting_enabled(conn: &Connection, key: &str, default: bool) -> bool {
+    match read_setting(conn, key) {
+        Some(v) => v == "true",
+        None => default,
+    }
+}
+
+// Build the FTS5 `tokenize` argument. Porter stemming wraps unicode61 so "running" matches "run";
+// remove_diacritics 2 folds accents (including those on composed characters) so "café" matches "cafe".
+// The trigram mode indexes every 3-character window instead of words, which makes substring search
+// work for Chinese/Japanese and other text without spaces; stemming/folding don't apply to it.
+pub fn fts_tokenizer_spec(mode: &str, stemming: bool, remove_diacritics: bool) -> String {
+    if mode == "trigram" {
+        return "trigram".to_string();
+    }
+    let base = format!("unicode61 remove_diacritics {}", if remove_diacritics { 2 } else { 0 });
+    if stemming { format!("porter {}", base) } else { base }
+}
+
+fn desired_fts_tokenizer(conn: &Connection) -> String {
+    fts_tokenizer_spec(
+        read_setting(conn, "fts_tokenizer").as_deref().unwrap_or("unicode61"),
+        setting_enabled(conn, "fts_stemming", true),
+        setting_enabled(conn, "fts_remove_diacritics", true),
+    )
+}
+
+pub(crate) fn escape_like(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+}
+
+// True when entries_fts exists but was built with a different tokenizer than the settings ask for
+fn fts_tokenizer_outdated(conn: &Connection) -> Result<bool> {
+    let sql: Option<String> = conn
+        .query_row(
+            "SELECT sql FROM sqlite_master WHERE type = 'table' AND name = 'entries_fts'",
+            [],
+            |r| r.get(0),
+        )
+        .optional()?;
+    Ok(match sql {
+        Some(sql) => !sql.contains(&format!("tokenize = '{}'", desired_fts_tokenizer(conn))),
+        None => false,
+    })
+}
+
+pub(crate) fn create_fts_table(conn: &Connection) -> rusqlite::Result<()> {
+    let tokenizer = desired_fts_tokenizer(conn);
+    conn.execute_batch(&format!(
         r#"
         CREATE VIRTUAL TABLE IF NOT EXISTS entries_fts
         USING fts5(
             title,
             body,
-            entry_id UNINDEXED
+            entry_id UNINDEXED,
+            tokenize = '{}'
         );
         "#,
-    )?;
+        tokenizer
+    ))
+}
+
+pub async fn ensure_fts_populated(app_handle: &AppHandle) -> Result<()> {
+    let conn = open_conn(app_handle)?;
+    if fts_tokenizer_outdated(&conn)? {
+        drop(conn);
+        tracing::info!("search tokenizer settings changed; rebuilding index");
+        rebuild_fts_index(app_handle, |_, _| {}).await?;
+        return Ok(());
+    }
+
+    // Create FTS table if missing (idempotent)
+    create_fts_table(&conn)?;
 
     // Backfill any missing rows into FTS from entries
     conn.execute(
@@ -359,6 +957,82 @@ pub async fn ensure_fts_populated(app_handle: &AppHandle) -> Result<()> {
     Ok(())
 }
 
+const FTS_REBUILD_BATCH: i64 = 500;
+
+// Drop and repopulate entries_fts from scratch. Runs in a single transaction so searches
+// keep using the old index until the new one is complete. `on_progress(processed, total)`
+// is called after every batch. Returns the number of rows indexed.
+pub async fn rebuild_fts_index<F>(app_handle: &AppHandle, on_progress: F) -> Result<u64>
+where
+    F: Fn(u64, u64) + Send + 'static,
+{
+    let db_path = get_db_file_path(app_handle)?;
+    tokio::task::spawn_blocking(move || -> Result<u64> {
+        let mut conn = open_conn_at(&db_path)?;
+        let tx = conn.transaction()?;
+        let total: i64 = tx.query_row("SELECT COUNT(*) FROM entries", [], |r| r.get(0))?;
+        tracing::info!("search index rebuild start total={}", total);
+
+        tx.execute_batch("DROP TABLE IF EXISTS entries_fts;")?;
+        create_fts_table(&tx)?;
+        on_progress(0, total as u64);
+
+        let mut processed: i64 = 0;
+        while processed < total {
+            let inserted = tx.execute(
+                r#"INSERT INTO entries_fts (title, body, entry_id)
+                    SELECT IFNULL(title, ''), body, id
+                    FROM entries
+                    ORDER BY rowid
+                    LIMIT ?1 OFFSET ?2"#,
+                params![FTS_REBUILD_BATCH, processed],
+            )?;
+            if inserted == 0 { break; }
+            processed += inserted as i64;
+            on_progress(processed as u64, total as u64);
+        }
+
+        tx.commit()?;
+        tracing::info!("search index rebuild done rows={}", processed);
+        Ok(processed as u64)
+    })
+    .await
+    .map_err(|e| anyhow::anyhow!(e.to_string()))?
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FtsRepairReport {
+    pub orphans_removed: u64,
+    pub duplicates_removed: u64,
+    pub entries_added: u64,
+}
+
+// Brings entries_fts back in line with entries without rebuilding it: drops rows for deleted
+// entries and extra rows for the same entry, and indexes entries that have no row
+pub(crate) fn repair_fts(conn: &Connection) -> Result<FtsRepairReport> {
+    let tx = conn.unchecked_transaction()?;
+    let orphans_removed = tx.execute("DELETE FROM entries_fts WHERE entry_id NOT IN (SELECT id FROM entries)", [])?;
+    let duplicates_removed = tx.execute(
+        "DELETE FROM entries_fts WHERE rowid NOT IN (SELECT MIN(rowid) FROM entries_fts GROUP BY entry_id)",
+        [],
+    )?;
+    // Sensitive entries have an empty body, so only their title is indexed
+    let entries_added = tx.execute(
+        r#"INSERT INTO entries_fts (title, body, entry_id)
+            SELECT IFNULL(title, ''), body, id FROM entries
+            WHERE id NOT IN (SELECT entry_id FROM entries_fts)"#,
+        [],
+    )?;
+    tx.commit()?;
+    let report = FtsRepairReport {
+        orphans_removed: orphans_removed as u64,
+        duplicates_removed: duplicates_removed as u64,
+        entries_added: entries_added as u64,
+    };
+    tracing::info!("search index repair orphans={} duplicates={} added={}", report.orphans_removed, report.duplicates_removed, report.entries_added);
+    Ok(report)
+}
+
 pub async fn get_settings(app_handle: &AppHandle) -> Result<Vec<(String, String)>> {
     let conn = open_conn(app_handle)?;
     let mut stmt = conn.prepare("SELECT key, value FROM settings")?;
@@ -378,6 +1052,51 @@ pub async fn get_settings(app_handle: &AppHandle) -> Result<Vec<(String, String)
         ("ollama_url".to_string(), "http://localhost:11434".to_string()),
         ("default_model".to_string(), "llama3.1:8b".to_string()),
         ("embedding_model".to_string(), "nomic-embed-text".to_string()),
+        ("auto_embed".to_string(), "true".to_string()),
+        ("fts_tokenizer".to_string(), "unicode61".to_string()),
+        ("fts_stemming".to_string(), "true".to_string()),
+        ("fts_remove_diacritics".to_string(), "true".to_string()),
+        ("auto_weekly_summary".to_string(), "false".to_string()),
+        ("entity_extraction_ai".to_string(), "false".to_string()),
+        ("goal_tracking_ai".to_string(), "false".to_string()),
+        ("chunk_size".to_string(), crate::chunks::DEFAULT_CHUNK_SIZE.to_string()),
+        ("chunk_overlap".to_string(), crate::chunks::DEFAULT_CHUNK_OVERLAP.to_string()),
+        ("rerank".to_string(), "off".to_string()),
+        ("chat_query_planning".to_string(), "true".to_string()),
+        ("embedding_quantization".to_string(), "none".to_string()),
+        ("ai_include_sensitive".to_string(), "false".to_string()),
+        ("s3_endpoint".to_string(), String::new()),
+        ("s3_region".to_string(), "us-east-1".to_string()),
+        ("s3_bucket".to_string(), String::new()),
+        ("s3_prefix".to_string(), "journal-reader/".to_string()),
+        ("s3_keep".to_string(), "7".to_string()),
+        ("s3_backup_interval_hours".to_string(), "0".to_string()),
+        ("lan_sync_enabled".to_string(), "false".to_string()),
+        ("lan_sync_port".to_string(), "47821".to_string()),
+        ("lan_sync_device_name".to_string(), String::new()),
+        ("lan_sync_conflicts".to_string(), "newest".to_string()),
+        ("imap_enabled".to_string(), "false".to_string()),
+        ("imap_port".to_string(), "993".to_string()),
+        ("imap_folder".to_string(), "Journal".to_string()),
+        ("imap_interval_minutes".to_string(), "15".to_string()),
+        ("hook_webhook_url".to_string(), String::new()),
+        ("hook_script".to_string(), String::new()),
+        ("hook_events".to_string(), crate::hooks::EVENTS.join(",")),
+        ("on_this_day_notification".to_string(), "false".to_string()),
+        ("on_this_day_time".to_string(), "08:00".to_string()),
+        ("weather_location".to_string(), String::new()),
+        ("write_back_sources".to_string(), "false".to_string()),
+        ("archive_originals".to_string(), "false".to_string()),
+        ("search_timeout_secs".to_string(), "10".to_string()),
+        ("log_level".to_string(), crate::logging::DEFAULT_LEVEL.to_string()),
+        ("locale".to_string(), crate::locale::DEFAULT_LOCALE.to_string()),
+        ("week_start".to_string(), crate::locale::DEFAULT_WEEK_START.to_string()),
+        ("tts_engine".to_string(), crate::tts::DEFAULT_ENGINE.to_string()),
+        ("tts_voice".to_string(), String::new()),
+        ("photo_caption_model".to_string(), String::new()),
+        ("fts_title_weight".to_string(), DEFAULT_TITLE_WEIGHT.to_string()),
+        ("fts_body_weight".to_string(), DEFAULT_BODY_WEIGHT.to_string()),
+        ("birthday".to_string(), String::new()),
     ];
     for (k, v) in defaults {
         if !have.contains(&k) {
@@ -397,6 +1116,212 @@ pub async fn update_setting(app_handle: &AppHandle, key: &str, value: &str) -> R
     Ok(())
 }
 
+// The tokenizer in effect is part of the table definition, so a changed setting means a rebuild
+pub async fn fts_needs_rebuild(app_handle: &AppHandle) -> Result<bool> {
+    let conn = open_conn(app_handle)?;
+    fts_tokenizer_outdated(&conn)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EncryptionStatus {
+    pub encrypted: bool,
+    pub unlocked: bool,
+}
+
+pub async fn get_encryption_status(app_handle: &AppHandle) -> Result<EncryptionStatus> {
+    let db_path = get_db_file_path(app_handle)?;
+    let encrypted = crate::encryption::is_encrypted(&db_path);
+    Ok(EncryptionStatus { encrypted, unlocked: !encrypted || current_db_key().is_some() })
+}
+
+// Encrypts a plaintext database on first use, or changes the passphrase of an unlocked one
+pub async fn set_database_passphrase(app_handle: &AppHandle, passphrase: &str) -> Result<()> {
+    if passphrase.chars().count() < 8 {
+        return Err(anyhow::anyhow!("Passphrase must be at least 8 characters"));
+    }
+    let db_path = get_db_file_path(app_handle)?;
+    let passphrase = passphrase.to_string();
+    let key = tokio::task::spawn_blocking(move || -> Result<String> {
+        if crate::encryption::is_encrypted(&db_path) {
+            let conn = open_conn_at(&db_path)?;
+            let key = crate::encryption::rekey_database(&conn, &db_path, &passphrase);
+            drop(conn);
+            key
+        } else {
+            // Pooled connections hold the plaintext file open; close them before the swap
+            crate::db_pool::clear();
+            crate::encryption::encrypt_plaintext_database(&db_path, &passphrase)
+        }
+    })
+    .await
+    .map_err(|e| anyhow::anyhow!(e.to_string()))??;
+    crate::db_pool::clear();
+    set_db_key(Some(key));
+    Ok(())
+}
+
+// Makes another journal active. Connections, the session key and cached vectors all belong
+// to the previous database, so they are dropped; an encrypted journal then needs unlocking.
+pub async fn switch_journal(app_handle: &AppHandle, id: &str) -> Result<crate::journals::Journal> {
+    if crate::embeddings::backfill_running() {
+        return Err(anyhow::anyhow!("Wait for the embedding backfill to finish before switching journals"));
+    }
+    let journal = crate::journals::set_active(&get_db_dir(app_handle)?, id)?;
+    crate::db_pool::clear();
+    set_db_key(None);
+    crate::vector_index::invalidate();
+    if !crate::encryption::is_encrypted(std::path::Path::new(&journal.path)) {
+        init_database(app_handle).await?;
+    }
+    Ok(journal)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RelocationReport {
+    pub old_path: String,
+    pub new_path: String,
+    pub bytes: u64,
+    // False when the copy is in use but the old file could not be deleted
+    pub old_removed: bool,
+}
+
+fn entry_count(conn: &Connection) -> Result<i64> {
+    Ok(conn.query_row("SELECT COUNT(*) FROM entries", [], |r| r.get(0))?)
+}
+
+fn open_keyed(path: &std::path::Path, key: Option<&str>) -> Result<Connection> {
+    let conn = Connection::open(path)?;
+    if let Some(key) = key {
+        crate::encryption::apply_key(&conn, key)?;
+    }
+    Ok(conn)
+}
+
+// A connection holding the database's write lock; other writers wait (or fail) until it is
+// dropped, while readers carry on. Held across a copy so nothing lands in the original after it.
+fn lock_for_copy(src: &std::path::Path, key: Option<&str>) -> Result<Connection> {
+    let conn = open_keyed(src, key)?;
+    conn.busy_timeout(std::time::Duration::from_secs(5))?;
+    // Fold the WAL into the main file first so there is less to copy; best effort
+    let _ = conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()));
+    conn.execute_batch("BEGIN EXCLUSIVE")?;
+    Ok(conn)
+}
+
+// Copies a database (its WAL and key info too) to `dst` while `locked` (from lock_for_copy)
+// keeps writers out, then checks the copy opens with the same key, passes integrity_check and
+// holds the same entries. A failed copy is removed.
+fn copy_verified(locked: &Connection, src: &std::path::Path, dst: &std::path::Path, key: Option<&str>) -> Result<u64> {
+    let expected = entry_count(locked)?;
+    let src_info = crate::encryption::key_info_path(src);
+    let dst_info = crate::encryption::key_info_path(dst);
+    let copied = (|| -> Result<u64> {
+        let mut bytes = std::fs::copy(src, dst)?;
+        // Committed pages not yet checkpointed; opening the copy replays them
+        if wal_path(src).exists() {
+            bytes += std::fs::copy(wal_path(src), wal_path(dst))?;
+        }
+        if src_info.exists() {
+            std::fs::copy(&src_info, &dst_info)?;
+        }
+        let conn = open_keyed(dst, key)?;
+        let integrity: String = conn.query_row("PRAGMA integrity_check", [], |r| r.get(0))?;
+        if integrity != "ok" {
+            anyhow::bail!("Copied database failed integrity check: {}", integrity);
+        }
+        let found = entry_count(&conn)?;
+        if found != expected {
+            anyhow::bail!("Copied database has {} entries, expected {}", found, expected);
+        }
+        Ok(bytes)
+    })();
+    if copied.is_err() {
+        remove_copy(dst);
+    }
+    copied
+}
+
+fn remove_copy(dst: &std::path::Path) {
+    for path in [dst.to_path_buf(), wal_path(dst), crate::encryption::key_info_path(dst)] {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+// Moves the active journal's database into `folder` (e.g. a synced or encrypted volume):
+// copy, verify, switch the registry to the copy, then delete the original. The original stays
+// write-locked from before the copy until the registry points at the copy.
+pub async fn relocate_database(app_handle: &AppHandle, folder: &str) -> Result<RelocationReport> {
+    if cfg!(mobile) {
+        return Err(anyhow::anyhow!("On a phone the database stays in the app's own storage"));
+    }
+    if crate::embeddings::backfill_running() {
+        return Err(anyhow::anyhow!("Wait for the embedding backfill to finish before moving the database"));
+    }
+    let folder = std::path::Path::new(folder.trim());
+    if !folder.is_dir() {
+        return Err(anyhow::anyhow!("{} is not a folder", folder.display()));
+    }
+    let src = get_db_file_path(app_handle)?;
+    let dst = folder.join(src.file_name().unwrap_or_else(|| std::ffi::OsStr::new("journal.db")));
+    if dst == src {
+        return Err(anyhow::anyhow!("The database is already in that folder"));
+    }
+    if dst.exists() {
+        return Err(anyhow::anyhow!("{} already exists; choose another folder", dst.display()));
+    }
+    let key = current_db_key();
+    if key.is_none() && crate::encryption::is_encrypted(&src) {
+        return Err(anyhow::anyhow!("Database is encrypted and locked; unlock it with your passphrase"));
+    }
+
+    // Pooled connections keep writing to the old file; close them for the copy
+    crate::db_pool::clear();
+    let db_dir = get_db_dir(app_handle)?;
+    let (copy_src, copy_dst) = (src.clone(), dst.clone());
+    let bytes = tokio::task::spawn_blocking(move || -> Result<u64> {
+        let locked = lock_for_copy(&copy_src, key.as_deref())?;
+        let bytes = copy_verified(&locked, &copy_src, &copy_dst, key.as_deref())?;
+        if let Err(e) = crate::journals::move_active(&db_dir, &copy_dst) {
+            remove_copy(&copy_dst);
+            return Err(e);
+        }
+        locked.execute_batch("ROLLBACK")?;
+        Ok(bytes)
+    })
+    .await
+    .map_err(|e| anyhow::anyhow!(e.to_string()))??;
+    crate::db_pool::clear();
+    crate::vector_index::invalidate();
+
+    let old_removed = std::fs::remove_file(&src).is_ok();
+    for extra in [wal_path(&src), PathBuf::from(format!("{}-shm", src.display())), crate::encryption::key_info_path(&src)] {
+        let _ = std::fs::remove_file(extra);
+    }
+    tracing::info!("moved {} -> {} ({} bytes)", src.display(), dst.display(), bytes);
+    Ok(RelocationReport {
+        old_path: src.to_string_lossy().to_string(),
+        new_path: dst.to_string_lossy().to_string(),
+        bytes,
+        old_removed,
+    })
+}
+
+pub async fn unlock_database(app_handle: &AppHandle, passphrase: &str) -> Result<bool> {
+    let db_path = get_db_file_path(app_handle)?;
+    let info = match crate::encryption::load_key_info(&db_path)? {
+        Some(info) if crate::encryption::is_encrypted(&db_path) => info,
+        _ => return Ok(true),
+    };
+    let key = crate::encryption::derive_key(passphrase, &info)?;
+    let conn = Connection::open(&db_path)?;
+    crate::encryption::apply_key(&conn, &key)?;
+    if !crate::encryption::verify_readable(&conn) {
+        return Ok(false);
+    }
+    set_db_key(Some(key));
+    Ok(true)
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct MonthCount {
     pub month: u32,
@@ -406,33 +1331,48 @@ pub struct MonthCount {
 pub async fn get_available_years(app_handle: &AppHandle) -> Result<Vec<i32>> {
     let conn = open_conn(app_handle)?;
     let mut stmt = conn.prepare(
-        r#"SELECT DISTINCT substr(entry_date, 1, 4) as year
+        r#"SELECT DISTINCT entry_year
             FROM entries
-            ORDER BY year DESC"#,
+            WHERE entry_year IS NOT NULL
+            ORDER BY entry_year DESC"#,
     )?;
-    let rows = stmt.query_map([], |row| {
-        let year_str: String = row.get(0)?;
-        let year = year_str.parse::<i32>().unwrap_or(0);
-        Ok(year)
-    })?;
+    let rows = stmt.query_map([], |row| row.get::<_, i32>(0))?;
     let mut years = Vec::new();
     for r in rows { years.push(r?); }
     Ok(years)
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LanguageCount {
+    pub code: String,
+    pub name: String,
+    pub entries: u32,
+}
+
+// Languages detected across the journal, most used first
+pub(crate) fn language_counts(conn: &Connection) -> Result<Vec<LanguageCount>> {
+    let mut stmt = conn.prepare(
+        "SELECT language, count(*) FROM entries WHERE language IS NOT NULL GROUP BY language ORDER BY count(*) DESC, language",
+    )?;
+    let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, u32>(1)?)))?;
+    let mut languages = Vec::new();
+    for r in rows {
+        let (code, entries) = r?;
+        languages.push(LanguageCount { name: crate::import::language_name(&code), code, entries });
+    }
+    Ok(languages)
+}
+
 pub async fn get_month_counts_for_year(app_handle: &AppHandle, year: i32) -> Result<Vec<MonthCount>> {
     let conn = open_conn(app_handle)?;
-    let start = format!("{:04}-01-01T00:00:00Z", year);
-    let end = format!("{:04}-12-31T23:59:59Z", year);
     let mut stmt = conn.prepare(
-        r#"SELECT cast(substr(entry_date, 6, 2) as INTEGER) as month,
-                   count(*) as cnt
+        r#"SELECT entry_month, count(*) as cnt
             FROM entries
-            WHERE entry_date BETWEEN ?1 AND ?2
-            GROUP BY month
-            ORDER BY month ASC"#,
+            WHERE entry_year = ?1
+            GROUP BY entry_month
+            ORDER BY entry_month ASC"#,
     )?;
-    let rows = stmt.query_map(params![start, end], |row| {
+    let rows = stmt.query_map(params![year], |row| {
         Ok(MonthCount { month: row.get::<_, i64>(0)? as u32, count: row.get::<_, i64>(1)? as u32 })
     })?;
     let mut counts = vec![MonthCount { month: 1, count: 0 }, MonthCount { month: 2, count: 0 }, MonthCount { month: 3, count: 0 }, MonthCount { month: 4, count: 0 }, MonthCount { month: 5, count: 0 }, MonthCount { month: 6, count: 0 }, MonthCount { month: 7, count: 0 }, MonthCount { month: 8, count: 0 }, MonthCount { month: 9, count: 0 }, MonthCount { month: 10, count: 0 }, MonthCount { month: 11, count: 0 }, MonthCount { month: 12, count: 0 }];
@@ -444,4 +1384,225 @@ pub async fn get_month_counts_for_year(app_handle: &AppHandle, year: i32) -> Res
         }
     }
     Ok(counts)
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_db_health() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::migrations::run_migrations(&mut conn).unwrap();
+        conn.execute_batch(
+            r#"INSERT INTO entries (id, body, entry_date, entry_timezone, source_path, source_type, text_hash, created_at, updated_at)
+               VALUES ('a', 'Walk.', '2021-03-05T12:00:00+00:00', 'UTC', '', 'txt', 'ha', '', ''),
+                      ('b', 'Rain.', '2021-03-06T12:00:00+00:00', 'UTC', '', 'txt', 'hb', '', '');
+               INSERT INTO entries_fts (title, body, entry_id) VALUES ('', 'Walk.', 'a'), ('', 'Walk.', 'a'), ('', 'Gone.', 'z');
+               DROP INDEX idx_entries_text_hash;"#,
+        )
+        .unwrap();
+        let health = db_health(&conn, std::path::Path::new("/nonexistent/journal.db")).unwrap();
+        assert_eq!(health.schema_version, health.app_schema_version);
+        assert_eq!((health.fts_rows, health.entries_missing_fts, health.orphaned_fts_rows, health.duplicate_fts_rows), (3, 1, 1, 1));
+        assert_eq!((health.entries_missing_embeddings, health.db_size), (2, 0));
+        let missing: Vec<&str> = health.indexes.iter().filter(|i| !i.present).map(|i| i.name.as_str()).collect();
+        assert_eq!(missing, ["idx_entries_text_hash"]);
+    }
+
+    #[test]
+    fn test_repair_fts() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::migrations::run_migrations(&mut conn).unwrap();
+        conn.execute_batch(
+            r#"INSERT INTO entries (id, title, body, entry_date, entry_timezone, source_path, source_type, text_hash, created_at, updated_at)
+               VALUES ('a', NULL, 'Walk by the lake.', '2021-03-05T12:00:00+00:00', 'UTC', '', 'txt', 'ha', '', ''),
+                      ('b', 'Storm', 'Rain all day.', '2021-03-06T12:00:00+00:00', 'UTC', '', 'txt', 'hb', '', '');
+               INSERT INTO entries_fts (title, body, entry_id) VALUES ('', 'Walk by the lake.', 'a'), ('', 'Walk by the lake.', 'a'), ('', 'Gone.', 'z');"#,
+        )
+        .unwrap();
+        let report = repair_fts(&conn).unwrap();
+        assert_eq!((report.orphans_removed, report.duplicates_removed, report.entries_added), (1, 1, 1));
+        let found: Vec<String> = conn
+            .prepare("SELECT entry_id FROM entries_fts WHERE entries_fts MATCH 'rain OR lake OR gone' ORDER BY entry_id")
+            .unwrap()
+            .query_map([], |r| r.get(0))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        assert_eq!(found, ["a", "b"]);
+        let again = repair_fts(&conn).unwrap();
+        assert_eq!((again.orphans_removed, again.duplicates_removed, again.entries_added), (0, 0, 0));
+    }
+
+    #[test]
+    fn test_copy_verified() {
+        let dir = std::env::temp_dir().join(format!("jr-relocate-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let src = dir.join("journal.db");
+        let conn = Connection::open(&src).unwrap();
+        conn.execute_batch("PRAGMA journal_mode = WAL; CREATE TABLE entries (id TEXT); INSERT INTO entries VALUES ('a'), ('b');").unwrap();
+
+        let dst = dir.join("moved.db");
+        let locked = lock_for_copy(&src, None).unwrap();
+        assert!(copy_verified(&locked, &src, &dst, None).unwrap() > 0);
+        // Writers can't slip anything into the original while it is locked
+        conn.busy_timeout(std::time::Duration::ZERO).unwrap();
+        assert!(conn.execute("INSERT INTO entries VALUES ('c')", []).is_err());
+        drop(locked);
+        let copy = Connection::open(&dst).unwrap();
+        assert_eq!(entry_count(&copy).unwrap(), 2);
+
+        // Not a database: the failed copy is cleaned up
+        let bogus = dir.join("bogus.db");
+        std::fs::write(&bogus, b"not sqlite").unwrap();
+        assert!(lock_for_copy(&bogus, None).and_then(|locked| copy_verified(&locked, &bogus, &dir.join("bad.db"), None)).is_err());
+        assert!(!dir.join("bad.db").exists());
+        drop((conn, copy));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_fts_tokenizer_spec() {
+        assert_eq!(fts_tokenizer_spec("unicode61", false, false), "unicode61 remove_diacritics 0");
+        assert_eq!(fts_tokenizer_spec("unicode61", true, true), "porter unicode61 remove_diacritics 2");
+        assert_eq!(fts_tokenizer_spec("trigram", true, true), "trigram");
+    }
+
+    #[test]
+    fn test_stemming_and_diacritics_match() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE settings (key TEXT PRIMARY KEY, value TEXT NOT NULL);").unwrap();
+        create_fts_table(&conn).unwrap();
+        conn.execute(
+            "INSERT INTO entries_fts (title, body, entry_id) VALUES ('', 'I went running to the café', '1')",
+            [],
+        )
+        .unwrap();
+        for q in ["run", "cafe"] {
+            let n: i64 = conn
+                .query_row("SELECT COUNT(*) FROM entries_fts WHERE entries_fts MATCH ?1", params![q], |r| r.get(0))
+                .unwrap();
+            assert_eq!(n, 1, "query {}", q);
+        }
+        assert!(!fts_tokenizer_outdated(&conn).unwrap());
+
+        conn.execute("INSERT INTO settings (key, value) VALUES ('fts_stemming', 'false')", []).unwrap();
+        assert!(fts_tokenizer_outdated(&conn).unwrap());
+    }
+
+    #[test]
+    fn test_trigram_substring_match() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE settings (key TEXT PRIMARY KEY, value TEXT NOT NULL);
+             INSERT INTO settings (key, value) VALUES ('fts_tokenizer', 'trigram');",
+        )
+        .unwrap();
+        create_fts_table(&conn).unwrap();
+        conn.execute(
+            "INSERT INTO entries_fts (title, body, entry_id) VALUES ('', '今天我们去了北京的公园散步', '1')",
+            [],
+        )
+        .unwrap();
+        let n: i64 = conn
+            .query_row("SELECT COUNT(*) FROM entries_fts WHERE entries_fts MATCH '\"北京的公园\"'", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(n, 1);
+    }
+
+    #[test]
+    fn test_entry_filter_sql() {
+        assert_eq!(EntryFilter::default().to_sql(3).unwrap().0, "");
+        let filter = EntryFilter {
+            date_from: Some("2016-01-01".into()),
+            date_to: Some("2016-12-31".into()),
+            tags: Some(vec!["dreams".into()]),
+            source_types: Some(vec!["txt".into(), "docx".into()]),
+            entities: None,
+            languages: Some(vec!["spa".into()]),
+        };
+        let (sql, values) = filter.to_sql(3).unwrap();
+        assert_eq!(
+            sql,
+            " AND e.entry_date >= ?3 AND e.entry_date < ?4 \
+             AND e.id IN (SELECT entry_id FROM entry_tags WHERE tag IN (?5)) AND e.source_type IN (?6, ?7) \
+             AND e.language IN (?8)"
+        );
+        assert_eq!(values[1], rusqlite::types::Value::Text("2017-01-01T00:00:00+00:00".into()));
+
+        let bad = EntryFilter { date_from: Some("last tuesday".into()), ..Default::default() };
+        assert!(bad.to_sql(3).is_err());
+    }
+
+    #[test]
+    fn test_filtered_fts_query() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::migrations::run_migrations(&mut conn).unwrap();
+        conn.execute_batch(
+            r#"
+            INSERT INTO entries (id, title, body, entry_date, entry_timezone, source_path, source_type, text_hash, created_at, updated_at)
+            VALUES ('a', NULL, 'a dream about flying', '2016-05-01T00:00:00+00:00', 'UTC', 'a', 'txt', 'h1', '', ''),
+                   ('b', NULL, 'another dream', '2017-05-01T00:00:00+00:00', 'UTC', 'b', 'txt', 'h2', '', '');
+            INSERT INTO entries_fts (title, body, entry_id) SELECT '', body, id FROM entries;
+            INSERT INTO entry_tags (entry_id, tag) VALUES ('a', 'dreams'), ('b', 'dreams');
+            "#,
+        )
+        .unwrap();
+        let filter = EntryFilter {
+            date_from: Some("2016-01-01".into()),
+            date_to: Some("2016-12-31".into()),
+            tags: Some(vec!["dreams".into()]),
+            source_types: None,
+            entities: None,
+            languages: None,
+        };
+        let plan = MatchPlan::Fts("\"dream\"".into());
+        let rows = plan.run(&conn, 10, 0, &filter).unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].0.id, "a");
+        assert_eq!(rows[0].1[0].highlights, vec![(2, 7)]);
+        assert_eq!(plan.count(&conn, &filter).unwrap(), 1);
+
+        // Paging walks the full result set while the count stays the same
+        let all = EntryFilter::default();
+        assert_eq!(plan.count(&conn, &all).unwrap(), 2);
+        let first = plan.run(&conn, 1, 0, &all).unwrap();
+        let second = plan.run(&conn, 1, 1, &all).unwrap();
+        assert_eq!(first.len(), 1);
+        assert_eq!(second.len(), 1);
+        assert_ne!(first[0].0.id, second[0].0.id);
+        assert!(plan.run(&conn, 1, 2, &all).unwrap().is_empty());
+
+        let like = MatchPlan::Like("%dream%".into(), "dream".into());
+        assert_eq!(like.count(&conn, &filter).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_title_matches_rank_first() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::migrations::run_migrations(&mut conn).unwrap();
+        conn.execute_batch(
+            r#"
+            INSERT INTO entries (id, title, body, entry_date, entry_timezone, source_path, source_type, text_hash, created_at, updated_at)
+            VALUES ('trip', 'Paris trip', 'Packed the night before and slept on the train.', '2016-05-01T00:00:00+00:00', 'UTC', 'a', 'txt', 'h1', '', ''),
+                   ('aside', 'Tuesday', 'Work, then dinner; Sam is off to Paris soon.', '2016-05-02T00:00:00+00:00', 'UTC', 'b', 'txt', 'h2', '', '');
+            INSERT INTO entries_fts (title, body, entry_id) SELECT title, body, id FROM entries;
+            "#,
+        )
+        .unwrap();
+        let plan = MatchPlan::Fts("\"paris\"".into());
+        let ids = |conn: &Connection| -> Vec<String> {
+            plan.run(conn, 10, 0, &EntryFilter::default()).unwrap().into_iter().map(|(e, _)| e.id).collect()
+        };
+        assert_eq!(ids(&conn), vec!["trip", "aside"]);
+
+        conn.execute("INSERT INTO settings (key, value) VALUES ('fts_title_weight', '0')", []).unwrap();
+        assert_eq!(ids(&conn), vec!["aside", "trip"]);
+    }
+
+    #[test]
+    fn test_escape_like() {
+        assert_eq!(escape_like("50%_off\\"), "50\\%\\_off\\\\");
+    }
+}
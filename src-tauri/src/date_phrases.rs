@@ -0,0 +1,290 @@
+use chrono::{Datelike, Duration, Months, NaiveDate, TimeZone, Utc};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+// Date phrases in search queries -> date ranges.
+//
+// "last summer", "winter 2019", "around my birthday", "march 2021", "the past 3 weeks" and
+// friends are matched by the rules below (seasons are northern-hemisphere meteorological ones;
+// winter 2019 runs from December 2019 into February 2020). Anything else that looks like a
+// single day ("last friday", "april 3 2018", "2 weeks ago") goes through interim, the
+// chrono-english parser. Only one phrase is taken per query; the rest of the query is what
+// gets searched for.
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DatePhrase {
+    // The words that were read as dates
+    pub phrase: String,
+    pub from: NaiveDate,
+    // Inclusive
+    pub to: NaiveDate,
+    // The query without the phrase
+    pub rest: String,
+}
+
+const MONTHS: &str = "january|february|march|april|may|june|july|august|september|october|november|december|jan|feb|mar|apr|jun|jul|aug|sept|sep|oct|nov|dec";
+const WEEKDAYS: &[&str] = &["monday", "tuesday", "wednesday", "thursday", "friday", "saturday", "sunday", "mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+// Words left dangling once a phrase is cut out ("dinner in" after "in winter 2019")
+const CONNECTORS: &[&str] = &["in", "on", "from", "during", "around", "at", "of", "since"];
+const BIRTHDAY_WINDOW_DAYS: i64 = 7;
+
+struct Rules {
+    birthday: Regex,
+    season: Regex,
+    month_year: Regex,
+    relative_month: Regex,
+    period: Regex,
+    last_n: Regex,
+    year_span: Regex,
+    year: Regex,
+    day: Regex,
+}
+
+fn rules() -> &'static Rules {
+    static RULES: OnceLock<Rules> = OnceLock::new();
+    RULES.get_or_init(|| {
+        let re = |pattern: String| Regex::new(&format!("(?i){}", pattern)).expect("date phrase pattern");
+        Rules {
+            birthday: re(r"\b(around|near|on)\s+my\s+birthday(?:\s+(?:in\s+)?(\d{4}))?\b".into()),
+            season: re(r"\b(?:(?:(last|this|previous)\s+(spring|summer|fall|autumn|winter))|(?:(spring|summer|fall|autumn|winter)\s+(?:of\s+)?(\d{4})))\b".into()),
+            month_year: re(format!(r"\b({})\.?\s+(\d{{4}})\b", MONTHS)),
+            relative_month: re(format!(r"\b(last|this)\s+({})\b", MONTHS)),
+            period: re(r"\b(last|this|past|previous)\s+(week|month|year)\b".into()),
+            last_n: re(r"\b(?:the\s+)?(?:last|past)\s+(\d{1,3})\s+(days?|weeks?|months?|years?)\b".into()),
+            year_span: re(r"\b(?:between|from)\s+(\d{4})\s+(?:and|to|-)\s+(\d{4})\b".into()),
+            year: re(r"\b(?:in|during)\s+(\d{4})\b".into()),
+            day: re(r"\b(yesterday|today)\b".into()),
+        }
+    })
+}
+
+fn month_number(name: &str) -> Option<u32> {
+    let prefix: String = name.to_lowercase().chars().take(3).collect();
+    ["jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec"]
+        .iter()
+        .position(|m| *m == prefix)
+        .map(|i| i as u32 + 1)
+}
+
+fn month_range(year: i32, month: u32) -> Option<(NaiveDate, NaiveDate)> {
+    let start = NaiveDate::from_ymd_opt(year, month, 1)?;
+    Some((start, start.checked_add_months(Months::new(1))?.pred_opt()?))
+}
+
+fn year_range(year: i32) -> Option<(NaiveDate, NaiveDate)> {
+    Some((NaiveDate::from_ymd_opt(year, 1, 1)?, NaiveDate::from_ymd_opt(year, 12, 31)?))
+}
+
+// The season starting in `year` (winter spills into the next year)
+fn season_range(season: &str, year: i32) -> Option<(NaiveDate, NaiveDate)> {
+    let first_month = match season.to_lowercase().as_str() {
+        "spring" => 3,
+        "summer" => 6,
+        "fall" | "autumn" => 9,
+        _ => 12,
+    };
+    let start = NaiveDate::from_ymd_opt(year, first_month, 1)?;
+    Some((start, start.checked_add_months(Months::new(3))?.pred_opt()?))
+}
+
+fn season(caps: &regex::Captures, today: NaiveDate) -> Option<(NaiveDate, NaiveDate)> {
+    if let (Some(name), Some(year)) = (caps.get(3), caps.get(4)) {
+        return season_range(name.as_str(), year.as_str().parse().ok()?);
+    }
+    let name = caps.get(2)?.as_str();
+    let qualifier = caps.get(1)?.as_str().to_lowercase();
+    // The instance that has started by today, then step back for "last"
+    let mut year = today.year();
+    while season_range(name, year)?.0 > today {
+        year -= 1;
+    }
+    if qualifier == "this" {
+        return season_range(name, year);
+    }
+    if season_range(name, year)?.1 >= today {
+        year -= 1;
+    }
+    season_range(name, year)
+}
+
+fn period(unit: &str, qualifier: &str, today: NaiveDate) -> Option<(NaiveDate, NaiveDate)> {
+    let monday = today - Duration::days(today.weekday().num_days_from_monday() as i64);
+    match (qualifier, unit) {
+        ("past", "week") => Some((today - Duration::days(6), today)),
+        ("past", "month") => Some((today.checked_sub_months(Months::new(1))?, today)),
+        ("past", "year") => Some((today.checked_sub_months(Months::new(12))?, today)),
+        ("this", "week") => Some((monday, today)),
+        ("this", "month") => Some((month_range(today.year(), today.month())?.0, today)),
+        ("this", "year") => Some((year_range(today.year())?.0, today)),
+        (_, "week") => Some((monday - Duration::days(7), monday - Duration::days(1))),
+        (_, "month") => {
+            let last = today.checked_sub_months(Months::new(1))?;
+            month_range(last.year(), last.month())
+        }
+        (_, _) => year_range(today.year() - 1),
+    }
+}
+
+fn last_n(n: u32, unit: &str, today: NaiveDate) -> Option<(NaiveDate, NaiveDate)> {
+    let from = match unit.trim_end_matches('s') {
+        "day" => today - Duration::days(n as i64),
+        "week" => today - Duration::weeks(n as i64),
+        "month" => today.checked_sub_months(Months::new(n))?,
+        _ => today.checked_sub_months(Months::new(n * 12))?,
+    };
+    Some((from, today))
+}
+
+fn birthday(caps: &regex::Captures, today: NaiveDate, birthday: Option<(u32, u32)>) -> Option<(NaiveDate, NaiveDate)> {
+    let (month, day) = birthday?;
+    // Feb 29 birthdays fall on the 28th in other years
+    let on = |year: i32| NaiveDate::from_ymd_opt(year, month, day).or_else(|| NaiveDate::from_ymd_opt(year, month, day - 1));
+    let date = match caps.get(2) {
+        Some(year) => on(year.as_str().parse().ok()?)?,
+        None => {
+            let this_year = on(today.year())?;
+            if this_year <= today { this_year } else { on(today.year() - 1)? }
+        }
+    };
+    if caps[1].eq_ignore_ascii_case("on") {
+        Some((date, date))
+    } else {
+        let window = Duration::days(BIRTHDAY_WINDOW_DAYS);
+        Some((date - window, date + window))
+    }
+}
+
+fn ranged(query: &str, today: NaiveDate, birthday_md: Option<(u32, u32)>) -> Option<(std::ops::Range<usize>, NaiveDate, NaiveDate)> {
+    let r = rules();
+    let found = |re: &Regex, f: &dyn Fn(&regex::Captures) -> Option<(NaiveDate, NaiveDate)>| {
+        let caps = re.captures(query)?;
+        let (from, to) = f(&caps)?;
+        Some((caps.get(0)?.range(), from, to))
+    };
+    found(&r.birthday, &|c| birthday(c, today, birthday_md))
+        .or_else(|| found(&r.season, &|c| season(c, today)))
+        .or_else(|| found(&r.month_year, &|c| month_range(c[2].parse().ok()?, month_number(&c[1])?)))
+        .or_else(|| {
+            found(&r.relative_month, &|c| {
+                let month = month_number(&c[2])?;
+                let this_year = c[1].eq_ignore_ascii_case("this") || month < today.month();
+                month_range(if this_year { today.year() } else { today.year() - 1 }, month)
+            })
+        })
+        .or_else(|| found(&r.last_n, &|c| last_n(c[1].parse().ok()?, &c[2].to_lowercase(), today)))
+        .or_else(|| found(&r.period, &|c| period(&c[2].to_lowercase(), &c[1].to_lowercase(), today)))
+        .or_else(|| {
+            found(&r.year_span, &|c| {
+                let (a, b): (i32, i32) = (c[1].parse().ok()?, c[2].parse().ok()?);
+                Some((year_range(a.min(b))?.0, year_range(a.max(b))?.1))
+            })
+        })
+        .or_else(|| found(&r.year, &|c| year_range(c[1].parse().ok()?)))
+        .or_else(|| {
+            found(&r.day, &|c| {
+                let day = if c[1].eq_ignore_ascii_case("yesterday") { today.pred_opt()? } else { today };
+                Some((day, day))
+            })
+        })
+}
+
+// Words that make a span worth handing to interim; a month name alone ("march") or a bare
+// number ("2019", which it would read as a time of day) is not enough
+fn looks_like_day(words: &[&str]) -> bool {
+    let lower: Vec<String> = words.iter().map(|w| w.to_lowercase()).collect();
+    let has_digit = lower.iter().any(|w| w.chars().any(|c| c.is_ascii_digit()));
+    let has_month = lower.iter().any(|w| MONTHS.split('|').any(|m| m == w.trim_end_matches(['.', ','])));
+    lower.iter().any(|w| WEEKDAYS.contains(&w.as_str()) || w == "ago")
+        || lower.iter().any(|w| w.chars().any(|c| c.is_ascii_digit()) && (w.contains('/') || w.contains('-')))
+        || (has_month && has_digit)
+}
+
+// Longest run of up to five words interim reads as a day on or before today
+fn single_day(query: &str, today: NaiveDate) -> Option<(std::ops::Range<usize>, NaiveDate, NaiveDate)> {
+    let words: Vec<(usize, &str)> = query.split_whitespace().map(|w| (w.as_ptr() as usize - query.as_ptr() as usize, w)).collect();
+    let now = Utc.from_utc_datetime(&today.and_hms_opt(12, 0, 0)?);
+    for len in (1..=words.len().min(5)).rev() {
+        for window in words.windows(len) {
+            let texts: Vec<&str> = window.iter().map(|(_, w)| *w).collect();
+            if !looks_like_day(&texts) {
+                continue;
+            }
+            let start = window[0].0;
+            let end = window[len - 1].0 + window[len - 1].1.len();
+            let Ok(parsed) = interim::parse_date_string(&query[start..end], now, interim::Dialect::Us) else { continue };
+            let day = parsed.date_naive();
+            if day <= today {
+                return Some((start..end, day, day));
+            }
+        }
+    }
+    None
+}
+
+// The query's date phrase, if any. `birthday` is (month, day).
+pub fn extract(query: &str, today: NaiveDate, birthday: Option<(u32, u32)>) -> Option<DatePhrase> {
+    let (range, from, to) = ranged(query, today, birthday).or_else(|| single_day(query, today))?;
+    let rest = format!("{} {}", &query[..range.start], &query[range.end..]);
+    let mut words: Vec<&str> = rest.split_whitespace().collect();
+    while words.last().is_some_and(|w| CONNECTORS.contains(&w.to_lowercase().as_str())) {
+        words.pop();
+    }
+    while words.first().is_some_and(|w| CONNECTORS.contains(&w.to_lowercase().as_str())) {
+        words.remove(0);
+    }
+    Some(DatePhrase { phrase: query[range].trim().to_string(), from, to, rest: words.join(" ") })
+}
+
+// The `birthday` setting: MM-DD or YYYY-MM-DD
+pub fn parse_birthday(value: &str) -> Option<(u32, u32)> {
+    let parts: Vec<&str> = value.trim().split('-').collect();
+    let (month, day) = match parts.as_slice() {
+        [_, m, d] | [m, d] => (m.parse::<u32>().ok()?, d.parse::<u32>().ok()?),
+        _ => return None,
+    };
+    // 2000 is a leap year, so Feb 29 is accepted
+    NaiveDate::from_ymd_opt(2000, month, day).map(|_| (month, day))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn d(y: i32, m: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, day).unwrap()
+    }
+
+    fn range(query: &str) -> Option<(NaiveDate, NaiveDate, String)> {
+        extract(query, d(2024, 10, 17), Some((3, 9))).map(|p| (p.from, p.to, p.rest))
+    }
+
+    #[test]
+    fn test_ranges() {
+        assert_eq!(range("beach last summer"), Some((d(2024, 6, 1), d(2024, 8, 31), "beach".into())));
+        assert_eq!(range("this summer"), Some((d(2024, 6, 1), d(2024, 8, 31), "".into())));
+        assert_eq!(range("last winter"), Some((d(2023, 12, 1), d(2024, 2, 29), "".into())));
+        assert_eq!(range("skiing in winter 2019"), Some((d(2019, 12, 1), d(2020, 2, 29), "skiing".into())));
+        assert_eq!(range("around my birthday"), Some((d(2024, 3, 2), d(2024, 3, 16), "".into())));
+        assert_eq!(range("cake on my birthday 2020"), Some((d(2020, 3, 9), d(2020, 3, 9), "cake".into())));
+        assert_eq!(range("March 2021 move"), Some((d(2021, 3, 1), d(2021, 3, 31), "move".into())));
+        assert_eq!(range("last december"), Some((d(2023, 12, 1), d(2023, 12, 31), "".into())));
+        assert_eq!(range("last month"), Some((d(2024, 9, 1), d(2024, 9, 30), "".into())));
+        assert_eq!(range("last week"), Some((d(2024, 10, 7), d(2024, 10, 13), "".into())));
+        assert_eq!(range("the past 3 weeks"), Some((d(2024, 9, 26), d(2024, 10, 17), "".into())));
+        assert_eq!(range("between 2018 and 2016"), Some((d(2016, 1, 1), d(2018, 12, 31), "".into())));
+        assert_eq!(range("work during 2019"), Some((d(2019, 1, 1), d(2019, 12, 31), "work".into())));
+        assert_eq!(range("dinner last friday"), Some((d(2024, 10, 11), d(2024, 10, 11), "dinner".into())));
+    }
+
+    #[test]
+    fn test_plain_queries_are_left_alone() {
+        assert_eq!(range("march for science"), None);
+        assert_eq!(range("summer camp"), None);
+        assert_eq!(range("2019 taxes"), None);
+        assert_eq!(range("may the force"), None);
+        assert_eq!(extract("around my birthday", d(2024, 10, 17), None), None);
+        assert_eq!(parse_birthday("1990-02-29"), Some((2, 29)));
+        assert_eq!(parse_birthday("13-01"), None);
+    }
+}
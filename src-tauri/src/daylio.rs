@@ -0,0 +1,123 @@
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use chrono::{NaiveDate, NaiveTime};
+use tauri::AppHandle;
+
+use crate::app_import::{local_to_utc, normalize_tags, AppEntry, AppImportReport};
+use crate::import::FileType;
+
+// Daylio import from its CSV export (More → Export entries → CSV).
+//
+// One row per check-in: `full_date,date,weekday,time,mood,activities,note_title,note`, with
+// activities joined by " | " and line breaks in notes written as `<br>`. The CSV has no time
+// zone, so times are read in the one chosen for the import. The five default moods map onto the
+// sentiment scale (rad 1.0 down to awful -1.0); every mood is also kept as a "mood:<name>" tag,
+// and activities become tags. A check-in without a note is imported as a short line naming the
+// mood, activities and time, so it still shows on the timeline (and, since duplicates are found
+// by text, isn't mistaken for another day's identical check-in).
+
+pub fn mood_sentiment(mood: &str) -> Option<f32> {
+    match mood.trim().to_lowercase().as_str() {
+        "rad" => Some(1.0),
+        "good" => Some(0.5),
+        "meh" => Some(0.0),
+        "bad" => Some(-0.5),
+        "awful" => Some(-1.0),
+        // Custom moods have no fixed place on the scale
+        _ => None,
+    }
+}
+
+fn parse_time(time: &str) -> Option<NaiveTime> {
+    let time = time.trim();
+    NaiveTime::parse_from_str(time, "%I:%M %p").or_else(|_| NaiveTime::parse_from_str(time, "%H:%M")).ok()
+}
+
+pub fn parse_csv(data: &str, source: &str, timezone: &str) -> Result<Vec<AppEntry>> {
+    let mut reader = csv::ReaderBuilder::new().flexible(true).from_reader(data.trim_start_matches('\u{feff}').as_bytes());
+    let headers = reader.headers().context("Not a readable Daylio export")?.clone();
+    let column = |name: &str| headers.iter().position(|h| h.trim() == name);
+    let (Some(date_col), Some(mood_col)) = (column("full_date"), column("mood")) else {
+        anyhow::bail!("Not a Daylio export (expected full_date and mood columns)");
+    };
+    let (time_col, activities_col, title_col, note_col) = (column("time"), column("activities"), column("note_title"), column("note"));
+
+    let mut entries = Vec::new();
+    for (i, record) in reader.records().enumerate() {
+        let record = record.with_context(|| format!("Row {} is unreadable", i + 2))?;
+        let get = |col: Option<usize>| col.and_then(|c| record.get(c)).unwrap_or("").trim();
+        let date = NaiveDate::parse_from_str(get(Some(date_col)), "%Y-%m-%d").with_context(|| format!("Row {} has no valid date", i + 2))?;
+        let time = parse_time(get(time_col)).unwrap_or(NaiveTime::MIN);
+        let mood = get(Some(mood_col));
+        let activities: Vec<&str> = get(activities_col).split('|').map(str::trim).filter(|a| !a.is_empty()).collect();
+        let note = get(note_col).replace("<br>", "\n").replace("<br/>", "\n");
+        let body = if note.trim().is_empty() {
+            let mut line = format!("Mood: {}", mood);
+            if !activities.is_empty() {
+                line.push_str(&format!(" · {}", activities.join(", ")));
+            }
+            format!("{}\nChecked in {} {}", line, date, time.format("%H:%M"))
+        } else {
+            note
+        };
+        let mood_tag = format!("mood:{}", mood);
+        let mut labels = activities.clone();
+        if !mood.is_empty() {
+            labels.push(&mood_tag);
+        }
+        entries.push(AppEntry {
+            source: format!("{}#{}T{}", source, date, time.format("%H:%M")),
+            title: Some(get(title_col).to_string()).filter(|t| !t.is_empty()),
+            body,
+            tags: normalize_tags(labels),
+            created: local_to_utc(date.and_time(time), timezone),
+            timezone: timezone.to_string(),
+            sentiment: mood_sentiment(mood),
+        });
+    }
+    Ok(entries)
+}
+
+pub async fn import_csv(app_handle: &AppHandle, paths: &[String], timezone: &str) -> Result<AppImportReport> {
+    let mut report = AppImportReport::default();
+    for path in paths {
+        let source = Path::new(path).file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_else(|| path.clone());
+        let parsed = std::fs::read_to_string(path).map_err(anyhow::Error::from).and_then(|data| parse_csv(&data, &source, timezone));
+        match parsed {
+            Ok(entries) => crate::app_import::save_all(app_handle, entries, FileType::Daylio, &mut report).await?,
+            Err(e) => report.error(format!("{}: {}", path, e)),
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_csv() {
+        let data = "\u{feff}full_date,date,weekday,time,mood,activities,note_title,note\n\
+2024-05-02,May 2,Thursday,9:15 pm,good,friends | running,Run club,\"Ran 5k.<br>Pizza after.\"\n\
+2024-05-01,May 1,Wednesday,08:00,meh,,,\n\
+2024-04-30,April 30,Tuesday,7:30 am,sleepy,work,,\n";
+        let entries = parse_csv(data, "daylio_export.csv", "Europe/London").unwrap();
+        assert_eq!(entries.len(), 3);
+
+        assert_eq!(entries[0].title.as_deref(), Some("Run club"));
+        assert_eq!(entries[0].body, "Ran 5k.\nPizza after.");
+        assert_eq!(entries[0].tags, ["friends", "mood:good", "running"]);
+        assert_eq!(entries[0].sentiment, Some(0.5));
+        // 21:15 BST
+        assert_eq!(entries[0].created.to_rfc3339(), "2024-05-02T20:15:00+00:00");
+        assert_eq!(entries[0].timezone, "Europe/London");
+
+        assert_eq!(entries[1].body, "Mood: meh\nChecked in 2024-05-01 08:00");
+        assert_eq!(entries[1].sentiment, Some(0.0));
+        assert_eq!(entries[2].body, "Mood: sleepy · work\nChecked in 2024-04-30 07:30");
+        assert_eq!(entries[2].sentiment, None);
+
+        assert!(parse_csv("title,body\na,b\n", "x.csv", "UTC").is_err());
+    }
+}
@@ -0,0 +1,155 @@
+use anyhow::Result;
+use rusqlite::Connection;
+use std::ops::{Deref, DerefMut};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+// Shared SQLite connection pool.
+//
+// Opening a connection per call throws away the page cache, prepared statements and
+// per-connection pragmas. Instead, connections are checked out of a small process-wide
+// pool and returned on drop. WAL mode lets the pooled readers run concurrently with a
+// single writer; busy_timeout absorbs short writer contention.
+//
+// The pool is bound to one (database path, encryption key) pair. Asking for a different
+// pair, or calling `clear()`, bumps the generation so connections still checked out are
+// closed when returned instead of going back into the pool.
+
+const MAX_IDLE: usize = 4;
+const STATEMENT_CACHE_CAPACITY: usize = 32;
+
+struct Pool {
+    path: PathBuf,
+    key: Option<String>,
+    generation: u64,
+    idle: Vec<Connection>,
+}
+
+static POOL: Mutex<Option<Pool>> = Mutex::new(None);
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+fn next_generation() -> u64 {
+    GENERATION.fetch_add(1, Ordering::SeqCst) + 1
+}
+
+fn open_configured(path: &Path, key: Option<&str>) -> Result<Connection> {
+    let conn = Connection::open(path)?;
+    if let Some(key) = key {
+        crate::encryption::apply_key(&conn, key)?;
+    }
+    // Startup init and commands may touch the database at the same time
+    conn.busy_timeout(Duration::from_secs(5))?;
+    conn.execute_batch(
+        r#"
+        PRAGMA foreign_keys = ON;
+        PRAGMA synchronous = NORMAL;
+        "#,
+    )?;
+    conn.set_prepared_statement_cache_capacity(STATEMENT_CACHE_CAPACITY);
+    Ok(conn)
+}
+
+pub struct PooledConnection {
+    conn: Option<Connection>,
+    generation: u64,
+}
+
+impl Deref for PooledConnection {
+    type Target = Connection;
+    fn deref(&self) -> &Connection {
+        self.conn.as_ref().expect("pooled connection already returned")
+    }
+}
+
+impl DerefMut for PooledConnection {
+    fn deref_mut(&mut self) -> &mut Connection {
+        self.conn.as_mut().expect("pooled connection already returned")
+    }
+}
+
+impl Drop for PooledConnection {
+    fn drop(&mut self) {
+        let Some(conn) = self.conn.take() else { return };
+        // Never pool a connection left mid-transaction by an early return
+        if !conn.is_autocommit() {
+            return;
+        }
+        if let Ok(mut guard) = POOL.lock() {
+            if let Some(pool) = guard.as_mut() {
+                if pool.generation == self.generation && pool.idle.len() < MAX_IDLE {
+                    pool.idle.push(conn);
+                }
+            }
+        }
+    }
+}
+
+pub fn get(path: &Path, key: Option<&str>) -> Result<PooledConnection> {
+    let (reused, generation) = {
+        let mut guard = POOL.lock().unwrap_or_else(|e| e.into_inner());
+        let matches = guard
+            .as_ref()
+            .map(|p| p.path == path && p.key.as_deref() == key)
+            .unwrap_or(false);
+        if !matches {
+            *guard = Some(Pool {
+                path: path.to_path_buf(),
+                key: key.map(|k| k.to_string()),
+                generation: next_generation(),
+                idle: Vec::new(),
+            });
+        }
+        let pool = guard.as_mut().expect("pool initialized above");
+        (pool.idle.pop(), pool.generation)
+    };
+
+    let conn = match reused {
+        Some(conn) => conn,
+        None => open_configured(path, key)?,
+    };
+    Ok(PooledConnection { conn: Some(conn), generation })
+}
+
+// Close idle connections and retire checked-out ones. Required before replacing or moving
+// the database file, and after anything that changes how connections must be opened.
+pub fn clear() {
+    let mut guard = POOL.lock().unwrap_or_else(|e| e.into_inner());
+    *guard = None;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_connections_are_reused() {
+        let dir = std::env::temp_dir().join(format!("jr-pool-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("journal.db");
+
+        {
+            let conn = get(&path, None).unwrap();
+            conn.execute_batch("CREATE TEMP TABLE marker (x INTEGER);").unwrap();
+        }
+        // Temp tables are per-connection, so seeing it proves we got the same connection back
+        let conn = get(&path, None).unwrap();
+        let n: i64 = conn
+            .query_row("SELECT COUNT(*) FROM sqlite_temp_master WHERE name = 'marker'", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(n, 1);
+        drop(conn);
+
+        clear();
+        let conn = get(&path, None).unwrap();
+        let n: i64 = conn
+            .query_row("SELECT COUNT(*) FROM sqlite_temp_master WHERE name = 'marker'", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(n, 0);
+        drop(conn);
+        clear();
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,142 @@
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use chrono::DateTime;
+use tauri::AppHandle;
+
+use crate::app_import::{normalize_tags, AppEntry, AppImportReport};
+use crate::import::FileType;
+
+// Diaro import from its backup (Settings → Backup in Diaro): a DiaroBackup.xml, or the .zip
+// that holds it next to the photos.
+//
+// The XML is a dump of Diaro's tables: `<data><table name="diaro_entries"><r>...</r></table>`,
+// one `<r>` per row with a child element per column. Entries carry their date in epoch
+// milliseconds plus the device's `tz_offset`, and point at folders and tags by uid
+// (`tags` is a comma-separated list). Folder and tag titles become tags. Photos are not imported.
+
+fn rows<'a>(doc: &'a roxmltree::Document, table: &str) -> Vec<roxmltree::Node<'a, 'a>> {
+    doc.descendants()
+        .filter(|n| n.has_tag_name("table") && n.attribute("name") == Some(table))
+        .flat_map(|t| t.children().filter(|r| r.has_tag_name("r")))
+        .collect()
+}
+
+fn field(row: &roxmltree::Node, name: &str) -> String {
+    row.children()
+        .find(|c| c.has_tag_name(name))
+        .and_then(|c| c.text())
+        .map(|t| t.trim().to_string())
+        .unwrap_or_default()
+}
+
+// uid -> title for the folders or tags table
+fn titles(doc: &roxmltree::Document, table: &str) -> HashMap<String, String> {
+    rows(doc, table).iter().map(|r| (field(r, "uid"), field(r, "title"))).filter(|(uid, _)| !uid.is_empty()).collect()
+}
+
+pub fn parse_backup(xml: &str, source: &str, default_timezone: &str) -> Result<Vec<AppEntry>> {
+    let doc = roxmltree::Document::parse(xml).context("Not a readable Diaro backup")?;
+    if !doc.root_element().has_tag_name("data") {
+        anyhow::bail!("Not a Diaro backup (expected a <data> root element)");
+    }
+    let folders = titles(&doc, "diaro_folders");
+    let tags = titles(&doc, "diaro_tags");
+    let mut entries = Vec::new();
+    for row in rows(&doc, "diaro_entries") {
+        let uid = field(&row, "uid");
+        let millis: i64 = field(&row, "date").parse().with_context(|| format!("Entry {} has no valid date", uid))?;
+        let created = DateTime::from_timestamp_millis(millis).with_context(|| format!("Entry {} has an invalid date", uid))?;
+        let offset = field(&row, "tz_offset");
+        let timezone = if offset.parse::<chrono::FixedOffset>().is_ok() { offset } else { default_timezone.to_string() };
+        let mut labels: Vec<&str> = field(&row, "tags").split(',').filter_map(|uid| tags.get(uid.trim())).map(String::as_str).collect();
+        let folder = folders.get(&field(&row, "folder_uid")).cloned().unwrap_or_default();
+        labels.push(&folder);
+        let title = Some(field(&row, "title")).filter(|t| !t.is_empty());
+        let text = field(&row, "text");
+        entries.push(AppEntry {
+            source: format!("{}#{}", source, uid),
+            // A title-only entry is still worth keeping
+            body: if text.is_empty() { title.clone().unwrap_or_default() } else { text },
+            title,
+            tags: normalize_tags(labels),
+            created,
+            timezone,
+            sentiment: None,
+        });
+    }
+    Ok(entries)
+}
+
+// The backup XML from a .xml file, or from the first .xml inside a .zip
+fn read_backup(path: &Path) -> Result<String> {
+    let is_zip = path.extension().and_then(|e| e.to_str()).is_some_and(|e| e.eq_ignore_ascii_case("zip"));
+    if !is_zip {
+        return Ok(std::fs::read_to_string(path)?);
+    }
+    let mut archive = zip::ZipArchive::new(std::fs::File::open(path)?)?;
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i)?;
+        if file.name().to_lowercase().ends_with(".xml") {
+            let mut xml = String::new();
+            file.read_to_string(&mut xml)?;
+            return Ok(xml);
+        }
+    }
+    anyhow::bail!("No Diaro backup XML in {}", path.display())
+}
+
+pub async fn import_backup(app_handle: &AppHandle, paths: &[String], timezone: &str) -> Result<AppImportReport> {
+    let mut report = AppImportReport::default();
+    for path in paths {
+        let source = Path::new(path).file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_else(|| path.clone());
+        let entries = match read_backup(Path::new(path)).and_then(|xml| parse_backup(&xml, &source, timezone)) {
+            Ok(entries) => entries,
+            Err(e) => {
+                report.error(format!("{}: {}", path, e));
+                continue;
+            }
+        };
+        crate::app_import::save_all(app_handle, entries, FileType::Diaro, &mut report).await?;
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_backup() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<data version="2">
+  <table name="diaro_folders"><r><uid>f1</uid><title>Travel</title><color>#F0B913</color></r></table>
+  <table name="diaro_tags">
+    <r><uid>t1</uid><title>Family</title></r>
+    <r><uid>t2</uid><title>beach</title></r>
+  </table>
+  <table name="diaro_entries">
+    <r>
+      <uid>e1</uid><date>1688212800000</date><tz_offset>+02:00</tz_offset>
+      <title>Lisbon</title><text>Swam in the sea &amp; ate sardines.</text>
+      <folder_uid>f1</folder_uid><tags>,t1,t2,</tags>
+    </r>
+    <r><uid>e2</uid><date>1688299200000</date><tz_offset></tz_offset><title></title><text>Quiet day.</text><folder_uid></folder_uid><tags></tags></r>
+  </table>
+</data>"#;
+        let entries = parse_backup(xml, "DiaroBackup.xml", "Europe/Berlin").unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].title.as_deref(), Some("Lisbon"));
+        assert_eq!(entries[0].body, "Swam in the sea & ate sardines.");
+        assert_eq!(entries[0].tags, ["beach", "family", "travel"]);
+        assert_eq!(entries[0].created.to_rfc3339(), "2023-07-01T12:00:00+00:00");
+        assert_eq!(entries[0].timezone, "+02:00");
+        assert_eq!(entries[0].source, "DiaroBackup.xml#e1");
+        assert_eq!(entries[1].timezone, "Europe/Berlin");
+        assert!(entries[1].tags.is_empty());
+
+        assert!(parse_backup("<entries/>", "x.xml", "UTC").is_err());
+    }
+}
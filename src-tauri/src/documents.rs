@@ -0,0 +1,181 @@
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+use tauri::AppHandle;
+use tauri_plugin_fs::{FilePath, FsExt, OpenOptions};
+
+// Files chosen in the system document picker.
+//
+// On the desktop the picker returns paths and they are used as they are. On Android it returns
+// content:// URIs and on iOS file:// URLs that are only readable while the app holds on to them;
+// neither can be walked, listed or opened again later by path. `stage` copies each picked
+// document through the fs plugin into `inbox/` next to the journals ("inbox/<hash>/<name>"),
+// so importers, folder scans and `source_path` keep working with ordinary paths; picking the
+// same document again reuses its copy. Exports to a picked destination are written to `outbox/`
+// first and copied through the plugin by `ExportTarget::finish`.
+
+fn file_path(path: &str) -> FilePath {
+    match FilePath::from_str(path) {
+        Ok(p) => p,
+        Err(never) => match never {},
+    }
+}
+
+fn inbox_dir(app_handle: &AppHandle) -> Result<PathBuf> {
+    Ok(crate::database::get_db_dir(app_handle)?.join("inbox"))
+}
+
+// Local paths for `paths`, copying picked documents into the inbox
+pub fn stage(app_handle: &AppHandle, paths: &[String]) -> Result<Vec<String>> {
+    paths.iter().map(|p| stage_one(app_handle, p)).collect()
+}
+
+pub fn stage_one(app_handle: &AppHandle, path: &str) -> Result<String> {
+    let FilePath::Url(url) = file_path(path) else {
+        return Ok(path.to_string());
+    };
+    if url.path().starts_with("/tree/") {
+        anyhow::bail!("Folders can't be read on this device; select the files in it instead");
+    }
+    let bytes = app_handle.fs().read(FilePath::Url(url)).with_context(|| format!("Could not read {}", path))?;
+    let staged = stage_bytes(&inbox_dir(app_handle)?, path, &bytes)?;
+    Ok(staged.to_string_lossy().to_string())
+}
+
+fn stage_bytes(dir: &Path, uri: &str, bytes: &[u8]) -> Result<PathBuf> {
+    let hash = format!("{:x}", Sha256::digest(bytes));
+    let folder = dir.join(&hash[..16]);
+    let path = folder.join(document_name(uri, bytes));
+    if !path.exists() {
+        fs::create_dir_all(&folder)?;
+        fs::write(&path, bytes)?;
+    }
+    Ok(path)
+}
+
+// The file name a picked document had, from the end of its URI, with an extension read from its
+// bytes when the URI has none (Android's download provider only gives ids like "msf:1000")
+fn document_name(uri: &str, bytes: &[u8]) -> String {
+    let last = uri.split(['?', '#']).next().unwrap_or_default().rsplit('/').next().unwrap_or_default();
+    let decoded = urlencoding::decode(last).map(|d| d.into_owned()).unwrap_or_else(|_| last.to_string());
+    let name: String = decoded
+        .rsplit(['/', ':', '\\'])
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .filter(|c| !c.is_control())
+        .collect();
+    let name = match name.trim() {
+        "" | "." | ".." => "document".to_string(),
+        n => n.to_string(),
+    };
+    let has_extension = Path::new(&name)
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| (1..=5).contains(&e.len()) && e.chars().all(|c| c.is_ascii_alphanumeric()));
+    if has_extension {
+        name
+    } else {
+        format!("{}.{}", name, sniff_extension(bytes))
+    }
+}
+
+fn sniff_extension(bytes: &[u8]) -> &'static str {
+    let head = &bytes[..bytes.len().min(4096)];
+    let text = String::from_utf8_lossy(head);
+    let trimmed = text.trim_start_matches('\u{feff}').trim_start();
+    if head.starts_with(b"PK\x03\x04") {
+        if head.windows(5).any(|w| w == b"word/") {
+            "docx"
+        } else {
+            "zip"
+        }
+    } else if head.starts_with(b"%PDF") {
+        "pdf"
+    } else if head.starts_with(&[0xD0, 0xCF, 0x11, 0xE0]) {
+        "doc"
+    } else if trimmed.starts_with('<') {
+        "xml"
+    } else if trimmed.starts_with('{') || trimmed.starts_with('[') {
+        "json"
+    } else {
+        "txt"
+    }
+}
+
+// Where an export is written: `dest` itself when it's a path, otherwise a file in `outbox/` that
+// `finish` copies to the picked document
+pub struct ExportTarget {
+    dest: String,
+    local: PathBuf,
+    staged: bool,
+}
+
+impl ExportTarget {
+    pub fn new(app_handle: &AppHandle, dest: &str) -> Result<Self> {
+        if let FilePath::Path(path) = file_path(dest) {
+            return Ok(Self { dest: dest.to_string(), local: path, staged: false });
+        }
+        let dir = crate::database::get_db_dir(app_handle)?.join("outbox");
+        fs::create_dir_all(&dir)?;
+        let name = document_name(dest, b"");
+        let local = dir.join(format!("{}-{}", uuid::Uuid::new_v4(), name));
+        Ok(Self { dest: dest.to_string(), local, staged: true })
+    }
+
+    pub fn path(&self) -> &str {
+        self.local.to_str().unwrap_or(&self.dest)
+    }
+
+    pub fn finish(&self, app_handle: &AppHandle) -> Result<()> {
+        if !self.staged {
+            return Ok(());
+        }
+        let bytes = fs::read(&self.local)?;
+        let mut options = OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        let dest = file_path(&self.dest);
+        let mut file = app_handle.fs().open(dest, options).with_context(|| format!("Could not write {}", self.dest))?;
+        file.write_all(&bytes)?;
+        Ok(())
+    }
+}
+
+impl Drop for ExportTarget {
+    fn drop(&mut self) {
+        if self.staged {
+            let _ = fs::remove_file(&self.local);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_document_names_and_staging() {
+        assert_eq!(
+            document_name("content://com.android.externalstorage.documents/document/primary%3ADocuments%2F2021%20trip.txt", b""),
+            "2021 trip.txt"
+        );
+        assert_eq!(document_name("content://com.android.providers.downloads.documents/document/msf%3A1000", b"%PDF-1.7"), "1000.pdf");
+        assert_eq!(document_name("file:///private/var/mobile/tmp/Journal-Inbox/notes.docx", b""), "notes.docx");
+        assert_eq!(document_name("content://x/document/", b"PK\x03\x04....word/document.xml"), "document.docx");
+        assert_eq!(document_name("content://x/document/backup", b"\xEF\xBB\xBF <?xml version"), "backup.xml");
+
+        let dir = std::env::temp_dir().join(format!("jr-inbox-{}", uuid::Uuid::new_v4()));
+        let first = stage_bytes(&dir, "content://x/document/a.txt", b"hello").unwrap();
+        let again = stage_bytes(&dir, "content://x/document/a.txt", b"hello").unwrap();
+        let other = stage_bytes(&dir, "content://x/document/a.txt", b"changed").unwrap();
+        assert_eq!(first, again);
+        assert_ne!(first, other);
+        assert_eq!(fs::read_to_string(&first).unwrap(), "hello");
+        assert_eq!(first.file_name().unwrap(), "a.txt");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
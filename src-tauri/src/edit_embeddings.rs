@@ -0,0 +1,48 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tauri::AppHandle;
+use tokio::sync::Notify;
+
+// Re-embedding of edited entries.
+//
+// Saving an edit refreshes the FTS row right away and sets `embedding_dirty` on the entry
+// (database::set_entry_text). Instead of starting a full backfill on every save, edits are
+// debounced: the worker waits until no edit has come in for EDIT_DELAY, then embeds only the
+// dirty entries. Storing a vector for an entry's current text clears the flag, whichever path
+// stored it, so edits still dirty when the app quits are picked up by the startup backfill.
+
+const EDIT_DELAY: Duration = Duration::from_secs(5);
+// How long to wait when a backfill holds the embedding lock
+const BUSY_RETRY: Duration = Duration::from_secs(30);
+
+static EDITED: Notify = Notify::const_new();
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+// Called after an entry's body changed; starts the worker on first use
+pub fn schedule(app_handle: &AppHandle) {
+    if !STARTED.swap(true, Ordering::SeqCst) {
+        let handle = app_handle.clone();
+        tauri::async_runtime::spawn(async move { run(handle).await });
+    }
+    // notify_one keeps a permit, so an edit that lands before the worker waits is not lost
+    EDITED.notify_one();
+}
+
+async fn run(app_handle: AppHandle) {
+    loop {
+        EDITED.notified().await;
+        // Keep waiting while edits keep coming, e.g. autosave while typing
+        while tokio::time::timeout(EDIT_DELAY, EDITED.notified()).await.is_ok() {}
+        loop {
+            match crate::embeddings::embed_dirty(&app_handle).await {
+                Ok(Some(_)) => break,
+                // A backfill may have passed the edited entries already; try again after it
+                Ok(None) => tokio::time::sleep(BUSY_RETRY).await,
+                Err(e) => {
+                    tracing::warn!("edit worker failed: {}", e);
+                    break;
+                }
+            }
+        }
+    }
+}
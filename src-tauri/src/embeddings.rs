@@ -0,0 +1,606 @@
+use anyhow::Result;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::AppHandle;
+
+use crate::ai::{AiSettings, Provider};
+use crate::chunks::{Chunk, ChunkConfig};
+
+// Stored entry embeddings.
+//
+// Each entry is embedded chunk by chunk (see chunks.rs); the chunk vectors go to `entry_chunks`
+// and their normalized mean to `entries.embedding` as little-endian f32 bytes, tagged with the
+// model that produced it (`provider:model`) and the text_hash of the text it was computed from.
+// An entry is pending when it has no vector, when either tag no longer matches, or when it has
+// no chunks cut with the current settings; the backfill walks pending entries in rowid order and
+// commits each entry as it goes, so an interrupted run simply resumes with whatever is still pending.
+
+const BACKFILL_BATCH: i64 = 32;
+// Give up on a run when the provider is clearly down rather than failing every entry
+const MAX_CONSECUTIVE_FAILURES: u32 = 5;
+// Guard against model input limits when a large chunk_size is configured
+const MAX_EMBED_CHARS: usize = 8000;
+
+// Sensitive entries have no plaintext body to embed (see sensitive.rs)
+const PENDING_CONDITION: &str = r#"sensitive = 0 AND (embedding IS NULL OR embedding_model IS NOT ?1 OR embedding_text_hash IS NOT text_hash
+    OR NOT EXISTS (SELECT 1 FROM entry_chunks c
+                   WHERE c.entry_id = entries.id AND c.embedding_key = ?2 AND c.text_hash = entries.text_hash))"#;
+
+static BACKFILL_RUNNING: AtomicBool = AtomicBool::new(false);
+
+// What a text is embedded as; some models were trained with a different prefix for each
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EmbedRole {
+    Query,
+    Passage,
+}
+
+// E5 models (multilingual-e5-*, also as pulled into Ollama) expect "query: " / "passage: ";
+// without them queries match poorly, and across languages hardly at all
+fn uses_e5_prefixes(model: &str) -> bool {
+    let model = model.to_lowercase();
+    model.contains("e5-") || model.contains("-e5") || model.contains("/e5")
+}
+
+// Models that place text in different languages in one space, so a German query finds English entries
+pub fn is_multilingual(model: &str) -> bool {
+    let model = model.to_lowercase();
+    ["multilingual", "bge-m3", "text-embedding-3", "labse", "granite-embedding"].iter().any(|m| model.contains(m))
+}
+
+#[derive(Debug, Clone)]
+pub struct EmbeddingConfig {
+    pub ai: AiSettings,
+    pub model: String,
+    pub chunking: ChunkConfig,
+}
+
+impl EmbeddingConfig {
+    // Vectors embedded with prefixes are tagged so those made before (without) are redone
+    pub fn model_key(&self) -> String {
+        let prefixes = if uses_e5_prefixes(&self.model) { "+e5" } else { "" };
+        format!("{}:{}{}", self.ai.embedding_provider.as_str(), self.model, prefixes)
+    }
+
+    pub async fn embed(&self, role: EmbedRole, text: &str) -> Result<Vec<f32>> {
+        let text = match (uses_e5_prefixes(&self.model), role) {
+            (true, EmbedRole::Query) => format!("query: {}", text),
+            (true, EmbedRole::Passage) => format!("passage: {}", text),
+            (false, _) => text.to_string(),
+        };
+        crate::ai::generate_embedding_strict(&self.ai, &text, &self.model).await
+    }
+
+    // Tags chunk vectors with the chunk settings too, since those change what was embedded
+    pub fn embedding_key(&self) -> String {
+        format!("{}#{}/{}", self.model_key(), self.chunking.size, self.chunking.overlap)
+    }
+}
+
+pub fn load_config(conn: &Connection) -> EmbeddingConfig {
+    let ai = AiSettings::load(conn);
+    // A server model name saved earlier means nothing to the local backend
+    let model = crate::database::read_setting(conn, "embedding_model")
+        .filter(|m| !m.trim().is_empty())
+        .filter(|m| ai.embedding_provider != Provider::Local || crate::local_embeddings::MODELS.contains(&m.as_str()))
+        .unwrap_or_else(|| match ai.embedding_provider {
+            Provider::Ollama => "nomic-embed-text".to_string(),
+            Provider::OpenAI => "text-embedding-3-small".to_string(),
+            Provider::Local => crate::local_embeddings::DEFAULT_MODEL.to_string(),
+        });
+    EmbeddingConfig { ai, model, chunking: ChunkConfig::load(conn) }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmbeddingProgress {
+    pub processed: u64,
+    pub total: u64,
+    pub failed: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackfillReport {
+    pub embedded: u64,
+    pub failed: u64,
+    pub remaining: u64,
+    pub model: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EmbeddingStatus {
+    pub total_entries: u64,
+    pub embedded: u64,
+    pub pending: u64,
+    // Pending entries that do have a vector, but from another model or of another size
+    pub stale: u64,
+    // Entries edited since they were embedded, waiting for the edit worker
+    pub edited: u64,
+    pub model: String,
+    pub multilingual: bool,
+    pub running: bool,
+}
+
+pub fn encode_embedding(vector: &[f32]) -> Vec<u8> {
+    vector.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+pub fn decode_embedding(bytes: &[u8]) -> Option<Vec<f32>> {
+    if !bytes.len().is_multiple_of(4) {
+        return None;
+    }
+    Some(
+        bytes
+            .chunks_exact(4)
+            .map(|c| f32::from_le_bytes([c[0], c[1], c[2], c[3]]))
+            .collect(),
+    )
+}
+
+// Title and body together, cut at a char boundary if very long
+pub fn embedding_text(title: Option<&str>, body: &str) -> String {
+    let mut text = match title {
+        Some(t) if !t.trim().is_empty() => format!("{}\n\n{}", t.trim(), body),
+        _ => body.to_string(),
+    };
+    if let Some((idx, _)) = text.char_indices().nth(MAX_EMBED_CHARS) {
+        text.truncate(idx);
+    }
+    text
+}
+
+pub fn count_pending(conn: &Connection, model_key: &str, embedding_key: &str) -> Result<u64> {
+    let n: i64 = conn.query_row(
+        &format!("SELECT COUNT(*) FROM entries WHERE {}", PENDING_CONDITION),
+        params![model_key, embedding_key],
+        |r| r.get(0),
+    )?;
+    Ok(n as u64)
+}
+
+// All current vectors for the configured model, for scoring queries against
+pub fn load_embeddings(conn: &Connection, model_key: &str) -> Result<Vec<(String, Vec<f32>)>> {
+    let mut stmt = conn.prepare_cached(
+        r#"SELECT id, embedding FROM entries
+            WHERE embedding IS NOT NULL AND embedding_model = ?1 AND embedding_text_hash = text_hash"#,
+    )?;
+    let rows = stmt.query_map(params![model_key], |row| Ok((row.get(0)?, row.get(1)?)))?;
+    decode_rows(rows)
+}
+
+// The current vectors of just these entries, for updating a loaded index
+pub fn load_entry_embeddings(conn: &Connection, model_key: &str, ids: &[String]) -> Result<Vec<(String, Vec<f32>)>> {
+    let mut stmt = conn.prepare_cached(
+        r#"SELECT id, embedding FROM entries
+            WHERE id = ?2 AND embedding IS NOT NULL AND embedding_model = ?1 AND embedding_text_hash = text_hash"#,
+    )?;
+    let mut out = Vec::new();
+    for id in ids {
+        out.extend(decode_rows(stmt.query_map(params![model_key, id], |row| Ok((row.get(0)?, row.get(1)?)))?)?);
+    }
+    Ok(out)
+}
+
+fn decode_rows(rows: impl Iterator<Item = rusqlite::Result<(String, Vec<u8>)>>) -> Result<Vec<(String, Vec<f32>)>> {
+    let mut out = Vec::new();
+    for r in rows {
+        let (id, bytes) = r?;
+        if let Some(v) = decode_embedding(&bytes) {
+            out.push((id, v));
+        }
+    }
+    Ok(out)
+}
+
+// Mean of the unit-length chunk vectors, the entry's vector for similarity and clustering
+fn mean_vector(vectors: &[&[f32]]) -> Vec<f32> {
+    let dim = vectors.first().map_or(0, |v| v.len());
+    let mut mean = vec![0.0f32; dim];
+    for v in vectors.iter().filter(|v| v.len() == dim) {
+        let norm = v.iter().map(|x| x * x).sum::<f32>().sqrt();
+        if norm > 0.0 {
+            for (m, x) in mean.iter_mut().zip(v.iter()) {
+                *m += x / norm;
+            }
+        }
+    }
+    mean
+}
+
+fn store_embedding(
+    conn: &Connection,
+    entry_id: &str,
+    text_hash: &str,
+    model_key: &str,
+    embedding_key: &str,
+    chunks: &[(Chunk, Vec<f32>)],
+) -> Result<()> {
+    let vector = mean_vector(&chunks.iter().map(|(_, v)| v.as_slice()).collect::<Vec<_>>());
+    let tx = conn.unchecked_transaction()?;
+    // The text_hash guard drops the vectors if the entry changed while we were embedding it
+    let changed = tx.execute(
+        r#"UPDATE entries
+            SET embedding = ?1, embedding_model = ?2, embedding_dim = ?3, embedding_text_hash = ?4, embedding_dirty = 0
+            WHERE id = ?5 AND text_hash = ?4"#,
+        params![encode_embedding(&vector), model_key, vector.len() as i64, text_hash, entry_id],
+    )?;
+    if changed > 0 {
+        crate::chunks::store(&tx, entry_id, text_hash, embedding_key, chunks)?;
+    }
+    tx.commit()?;
+    if changed > 0 {
+        crate::vector_index::invalidate_entries([entry_id]);
+    }
+    Ok(())
+}
+
+// Vectors for every chunk of an entry; fails if any chunk does. Chunks embedded before with
+// the same model (say, of a re-imported entry) come from the AI cache.
+async fn embed_chunks(app_handle: &AppHandle, config: &EmbeddingConfig, title: Option<&str>, body: &str) -> Result<Vec<(Chunk, Vec<f32>)>> {
+    use crate::ai_cache::{EMBEDDING, EMBEDDING_VERSION};
+    let model_key = config.model_key();
+    let mut out = Vec::new();
+    for chunk in crate::chunks::split(body, config.chunking) {
+        let text = embedding_text(title, chunk.text(body));
+        let hash = crate::ai_cache::input_hash(&[&text]);
+        let cached = {
+            let conn = crate::database::open_conn(app_handle)?;
+            crate::ai_cache::get(&conn, EMBEDDING, &hash, &model_key, EMBEDDING_VERSION)?
+        };
+        let vector = match cached.as_deref().and_then(decode_embedding).filter(|v| !v.is_empty()) {
+            Some(vector) => vector,
+            None => {
+                let vector = config.embed(EmbedRole::Passage, &text).await?;
+                if vector.is_empty() {
+                    return Err(anyhow::anyhow!("empty embedding"));
+                }
+                let conn = crate::database::open_conn(app_handle)?;
+                crate::ai_cache::put(&conn, EMBEDDING, &hash, &model_key, EMBEDDING_VERSION, &encode_embedding(&vector))?;
+                vector
+            }
+        };
+        out.push((chunk, vector));
+    }
+    Ok(out)
+}
+
+pub async fn get_embedding_status(app_handle: &AppHandle) -> Result<EmbeddingStatus> {
+    let conn = crate::database::open_conn(app_handle)?;
+    let config = load_config(&conn);
+    let model_key = config.model_key();
+    let total: i64 = conn.query_row("SELECT COUNT(*) FROM entries", [], |r| r.get(0))?;
+    let pending = count_pending(&conn, &model_key, &config.embedding_key())?;
+    let stale: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM entries WHERE embedding IS NOT NULL AND embedding_model IS NOT ?1",
+        params![model_key],
+        |r| r.get(0),
+    )?;
+    let edited: i64 = conn.query_row("SELECT COUNT(*) FROM entries WHERE embedding_dirty = 1", [], |r| r.get(0))?;
+    Ok(EmbeddingStatus {
+        total_entries: total as u64,
+        embedded: (total as u64).saturating_sub(pending),
+        pending,
+        stale: stale as u64,
+        edited: edited as u64,
+        model: model_key,
+        multilingual: is_multilingual(&config.model),
+        running: BACKFILL_RUNNING.load(Ordering::SeqCst),
+    })
+}
+
+pub fn backfill_running() -> bool {
+    BACKFILL_RUNNING.load(Ordering::SeqCst)
+}
+
+struct RunningGuard;
+
+impl Drop for RunningGuard {
+    fn drop(&mut self) {
+        BACKFILL_RUNNING.store(false, Ordering::SeqCst);
+    }
+}
+
+// Embed every pending entry with the configured model. Only one backfill runs at a time;
+// `on_progress` is called after each entry.
+pub async fn backfill_embeddings<F>(app_handle: &AppHandle, on_progress: F) -> Result<BackfillReport>
+where
+    F: Fn(EmbeddingProgress) + Send + 'static,
+{
+    if BACKFILL_RUNNING.swap(true, Ordering::SeqCst) {
+        return Err(anyhow::anyhow!("An embedding backfill is already running"));
+    }
+    let _guard = RunningGuard;
+
+    let (config, total) = {
+        let conn = crate::database::open_conn(app_handle)?;
+        let config = load_config(&conn);
+        let total = count_pending(&conn, &config.model_key(), &config.embedding_key())?;
+        (config, total)
+    };
+    let model_key = config.model_key();
+    let embedding_key = config.embedding_key();
+    tracing::info!("backfill start model={} pending={}", model_key, total);
+
+    let mut progress = EmbeddingProgress { processed: 0, total, failed: 0 };
+    let mut embedded = 0u64;
+    let mut consecutive_failures = 0u32;
+    let mut last_rowid: i64 = 0;
+    on_progress(progress.clone());
+
+    loop {
+        let batch: Vec<(i64, String, Option<String>, String, String)> = {
+            let conn = crate::database::open_conn(app_handle)?;
+            let mut stmt = conn.prepare_cached(&format!(
+                r#"SELECT rowid, id, title, body, text_hash FROM entries
+                    WHERE rowid > ?3 AND {}
+                    ORDER BY rowid LIMIT ?4"#,
+                PENDING_CONDITION
+            ))?;
+            let rows = stmt.query_map(params![model_key, embedding_key, last_rowid, BACKFILL_BATCH], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?))
+            })?;
+            rows.collect::<rusqlite::Result<_>>()?
+        };
+        if batch.is_empty() {
+            break;
+        }
+
+        for (rowid, id, title, body, text_hash) in batch {
+            last_rowid = rowid;
+            let embedded_chunks = crate::ai_audit::with_context(
+                "embedding",
+                vec![id.clone()],
+                embed_chunks(app_handle, &config, title.as_deref(), &body),
+            )
+            .await;
+            match embedded_chunks {
+                Ok(chunks) => {
+                    let conn = crate::database::open_conn(app_handle)?;
+                    store_embedding(&conn, &id, &text_hash, &model_key, &embedding_key, &chunks)?;
+                    embedded += 1;
+                    consecutive_failures = 0;
+                }
+                // The provider's circuit is open (ai_http.rs): every remaining entry would fail too
+                Err(e) if crate::ai_http::is_circuit_open(&e) => {
+                    return Err(anyhow::anyhow!("{}; {} entries embedded before stopping", e, embedded));
+                }
+                Err(e) => {
+                    tracing::warn!("entry {} failed: {}", id, e);
+                    progress.failed += 1;
+                    consecutive_failures += 1;
+                }
+            }
+            progress.processed += 1;
+            on_progress(progress.clone());
+
+            if consecutive_failures >= MAX_CONSECUTIVE_FAILURES {
+                return Err(anyhow::anyhow!(
+                    "Embedding provider failed {} times in a row; {} entries embedded before stopping",
+                    consecutive_failures,
+                    embedded
+                ));
+            }
+        }
+    }
+
+    let remaining = {
+        let conn = crate::database::open_conn(app_handle)?;
+        count_pending(&conn, &model_key, &embedding_key)?
+    };
+    tracing::info!(
+        "backfill done embedded={} failed={} remaining={}",
+        embedded, progress.failed, remaining
+    );
+    Ok(BackfillReport { embedded, failed: progress.failed, remaining, model: model_key })
+}
+
+// Embeds the entries marked `embedding_dirty` by an edit, oldest edit first. Returns None
+// without doing anything while a backfill runs, since that holds the same lock; entries that
+// fail stay dirty for the next run or backfill.
+pub async fn embed_dirty(app_handle: &AppHandle) -> Result<Option<u64>> {
+    if BACKFILL_RUNNING.swap(true, Ordering::SeqCst) {
+        return Ok(None);
+    }
+    let _guard = RunningGuard;
+
+    let (config, dirty) = {
+        let conn = crate::database::open_conn(app_handle)?;
+        let mut stmt = conn.prepare_cached(
+            "SELECT id, title, body, text_hash FROM entries WHERE embedding_dirty = 1 ORDER BY updated_at",
+        )?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)))?;
+        let dirty: Vec<(String, Option<String>, String, String)> = rows.collect::<rusqlite::Result<_>>()?;
+        (load_config(&conn), dirty)
+    };
+    let (model_key, embedding_key) = (config.model_key(), config.embedding_key());
+    let mut embedded = 0u64;
+    for (id, title, body, text_hash) in dirty {
+        let chunks = crate::ai_audit::with_context(
+            "embedding",
+            vec![id.clone()],
+            embed_chunks(app_handle, &config, title.as_deref(), &body),
+        )
+        .await;
+        match chunks {
+            Ok(chunks) => {
+                let conn = crate::database::open_conn(app_handle)?;
+                store_embedding(&conn, &id, &text_hash, &model_key, &embedding_key, &chunks)?;
+                embedded += 1;
+            }
+            Err(e) => {
+                tracing::warn!("edited entry {} failed: {}", id, e);
+                // One failure usually means the provider is down; leave the rest for later
+                break;
+            }
+        }
+    }
+    tracing::info!("re-embedded {} edited entries", embedded);
+    Ok(Some(embedded))
+}
+
+// Marks vectors of `model_key` whose size differs from what the model produces now as pending
+// (a model re-pulled under the same name can change dimension); with `all`, every vector.
+// Returns how many were invalidated.
+pub(crate) fn invalidate_mismatched(conn: &Connection, model_key: &str, dim: usize, all: bool) -> Result<usize> {
+    let changed = if all {
+        conn.execute("UPDATE entries SET embedding_model = NULL WHERE embedding IS NOT NULL", [])?
+    } else {
+        conn.execute(
+            "UPDATE entries SET embedding_model = NULL WHERE embedding IS NOT NULL AND embedding_model = ?1 AND embedding_dim IS NOT ?2",
+            params![model_key, dim as i64],
+        )?
+    };
+    if changed > 0 {
+        crate::vector_index::invalidate();
+    }
+    Ok(changed)
+}
+
+// Re-embeds everything the configured model doesn't cover: entries embedded with another model
+// or dimension, plus (with `force`) all entries. Probes the model first so a provider that is
+// down fails before anything is invalidated.
+pub async fn reembed_all<F>(app_handle: &AppHandle, force: bool, on_progress: F) -> Result<BackfillReport>
+where
+    F: Fn(EmbeddingProgress) + Send + 'static,
+{
+    if BACKFILL_RUNNING.load(Ordering::SeqCst) {
+        return Err(anyhow::anyhow!("An embedding backfill is already running"));
+    }
+    let config = {
+        let conn = crate::database::open_conn(app_handle)?;
+        load_config(&conn)
+    };
+    let probe = crate::ai_audit::with_context("embedding", vec![], config.embed(EmbedRole::Passage, "dimension probe")).await?;
+    let model_key = config.model_key();
+    {
+        let conn = crate::database::open_conn(app_handle)?;
+        let invalidated = invalidate_mismatched(&conn, &model_key, probe.len(), force)?;
+        // Forcing means not trusting earlier vectors from this model, cached ones included
+        if force {
+            crate::ai_cache::clear(&conn, Some(crate::ai_cache::EMBEDDING), Some(&model_key))?;
+        }
+        tracing::info!("re-embed model={} dim={} invalidated={}", model_key, probe.len(), invalidated);
+    }
+    backfill_embeddings(app_handle, on_progress).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn one_chunk(vector: &[f32]) -> Vec<(Chunk, Vec<f32>)> {
+        vec![(Chunk { start: 0, end: 3 }, vector.to_vec())]
+    }
+
+    #[test]
+    fn test_embedding_round_trip() {
+        let v = vec![0.25f32, -1.5, 3.0e-7, 0.0];
+        let bytes = encode_embedding(&v);
+        assert_eq!(bytes.len(), 16);
+        assert_eq!(decode_embedding(&bytes).unwrap(), v);
+        assert!(decode_embedding(&bytes[..3]).is_none());
+    }
+
+    #[test]
+    fn test_pending_tracks_model_and_text() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::migrations::run_migrations(&mut conn).unwrap();
+        conn.execute_batch(
+            r#"
+            INSERT INTO entries (id, title, body, entry_date, entry_timezone, source_path, source_type,
+                                 text_hash, created_at, updated_at)
+            VALUES ('a', NULL, 'one', '2020-01-01T00:00:00+00:00', 'UTC', 'a.txt', 'txt', 'h1', '', ''),
+                   ('b', NULL, 'two', '2020-01-02T00:00:00+00:00', 'UTC', 'b.txt', 'txt', 'h2', '', '');
+            "#,
+        )
+        .unwrap();
+        assert_eq!(count_pending(&conn, "ollama:m", "ollama:m#k").unwrap(), 2);
+
+        store_embedding(&conn, "a", "h1", "ollama:m", "ollama:m#k", &one_chunk(&[1.0, 0.0])).unwrap();
+        assert_eq!(count_pending(&conn, "ollama:m", "ollama:m#k").unwrap(), 1);
+        assert_eq!(load_embeddings(&conn, "ollama:m").unwrap().len(), 1);
+        // New chunk settings re-chunk the entry
+        assert_eq!(count_pending(&conn, "ollama:m", "ollama:m#k2").unwrap(), 2);
+
+        // A different model, or edited text, makes the stored vector stale
+        assert_eq!(count_pending(&conn, "openai:x", "openai:x#k").unwrap(), 2);
+        conn.execute("UPDATE entries SET text_hash = 'h1b' WHERE id = 'a'", []).unwrap();
+        assert_eq!(count_pending(&conn, "ollama:m", "ollama:m#k").unwrap(), 2);
+        assert!(load_embeddings(&conn, "ollama:m").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_edit_marks_entry_dirty_until_embedded() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::migrations::run_migrations(&mut conn).unwrap();
+        conn.execute_batch(
+            r#"
+            INSERT INTO entries (id, title, body, entry_date, entry_timezone, source_path, source_type,
+                                 text_hash, created_at, updated_at)
+            VALUES ('a', NULL, 'one', '2020-01-01T00:00:00+00:00', 'UTC', 'a.txt', 'txt', 'h1', '', '');
+            "#,
+        )
+        .unwrap();
+        let dirty = |conn: &Connection| -> i64 {
+            conn.query_row("SELECT embedding_dirty FROM entries WHERE id = 'a'", [], |r| r.get(0)).unwrap()
+        };
+        assert_eq!(dirty(&conn), 0);
+
+        // The seeded hash isn't the body's, so the first save counts as a text change
+        let entry = crate::database::set_entry_text(&conn, "a", None, "one").unwrap();
+        assert_eq!(dirty(&conn), 1);
+        store_embedding(&conn, "a", &entry.text_hash, "ollama:m", "ollama:m#k", &one_chunk(&[1.0, 0.0])).unwrap();
+        assert_eq!(dirty(&conn), 0);
+        // A title-only edit keeps the vector
+        crate::database::set_entry_text(&conn, "a", Some("New title".into()), "one").unwrap();
+        assert_eq!(dirty(&conn), 0);
+
+        // A vector for text that has since changed doesn't clear the flag
+        let edited = crate::database::set_entry_text(&conn, "a", None, "one, edited").unwrap();
+        assert_eq!(dirty(&conn), 1);
+        store_embedding(&conn, "a", &entry.text_hash, "ollama:m", "ollama:m#k", &one_chunk(&[1.0, 0.0])).unwrap();
+        assert_eq!(dirty(&conn), 1);
+        store_embedding(&conn, "a", &edited.text_hash, "ollama:m", "ollama:m#k", &one_chunk(&[1.0, 0.0])).unwrap();
+        assert_eq!(dirty(&conn), 0);
+    }
+
+    #[test]
+    fn test_model_prefixes_and_languages() {
+        assert!(uses_e5_prefixes("multilingual-e5-small"));
+        assert!(uses_e5_prefixes("jeffh/intfloat-multilingual-e5-large:f16"));
+        assert!(!uses_e5_prefixes("nomic-embed-text"));
+        assert!(is_multilingual("multilingual-e5-small"));
+        assert!(is_multilingual("bge-m3:latest"));
+        assert!(!is_multilingual("bge-small-en-v1.5"));
+    }
+
+    #[test]
+    fn test_invalidate_mismatched_dimension() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::migrations::run_migrations(&mut conn).unwrap();
+        conn.execute_batch(
+            r#"
+            INSERT INTO entries (id, title, body, entry_date, entry_timezone, source_path, source_type,
+                                 text_hash, created_at, updated_at)
+            VALUES ('a', NULL, 'one', '2020-01-01T00:00:00+00:00', 'UTC', 'a.txt', 'txt', 'h1', '', ''),
+                   ('b', NULL, 'two', '2020-01-02T00:00:00+00:00', 'UTC', 'b.txt', 'txt', 'h2', '', '');
+            "#,
+        )
+        .unwrap();
+        store_embedding(&conn, "a", "h1", "ollama:m", "ollama:m#k", &one_chunk(&[1.0, 0.0])).unwrap();
+        store_embedding(&conn, "b", "h2", "ollama:m", "ollama:m#k", &one_chunk(&[1.0, 0.0, 0.0])).unwrap();
+
+        assert_eq!(invalidate_mismatched(&conn, "ollama:m", 3, false).unwrap(), 1);
+        assert_eq!(count_pending(&conn, "ollama:m", "ollama:m#k").unwrap(), 1);
+        assert_eq!(invalidate_mismatched(&conn, "ollama:m", 3, true).unwrap(), 2);
+        assert_eq!(count_pending(&conn, "ollama:m", "ollama:m#k").unwrap(), 2);
+    }
+
+    #[test]
+    fn test_embedding_text_truncates_on_char_boundary() {
+        let body = "é".repeat(MAX_EMBED_CHARS + 10);
+        let text = embedding_text(Some("  "), &body);
+        assert_eq!(text.chars().count(), MAX_EMBED_CHARS);
+        assert_eq!(embedding_text(Some("Title"), "body"), "Title\n\nbody");
+    }
+}
@@ -0,0 +1,235 @@
+use anyhow::{Context, Result};
+use base64::Engine;
+use rand::RngCore;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+// Opt-in database encryption at rest (SQLCipher).
+//
+// The user's passphrase never touches disk. We derive a raw 256-bit key with
+// PBKDF2-HMAC-SHA256 and hand it to SQLCipher as `x'..'`, so SQLCipher skips its own KDF.
+// The salt and iteration count live in a small sidecar file next to the database
+// (`journal.db.keyinfo.json`); it is not secret but is required to re-derive the key.
+
+const KDF_ITERATIONS: u32 = 256_000;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct KeyInfo {
+    pub kdf: String,
+    pub iterations: u32,
+    pub salt: String, // base64
+}
+
+impl KeyInfo {
+    pub fn generate() -> Self {
+        let mut salt = [0u8; SALT_LEN];
+        rand::thread_rng().fill_bytes(&mut salt);
+        Self {
+            kdf: "pbkdf2-sha256".to_string(),
+            iterations: KDF_ITERATIONS,
+            salt: base64::engine::general_purpose::STANDARD.encode(salt),
+        }
+    }
+}
+
+pub fn key_info_path(db_path: &Path) -> PathBuf {
+    let mut name = db_path.file_name().map(|n| n.to_os_string()).unwrap_or_default();
+    name.push(".keyinfo.json");
+    db_path.with_file_name(name)
+}
+
+pub fn load_key_info(db_path: &Path) -> Result<Option<KeyInfo>> {
+    let path = key_info_path(db_path);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let text = std::fs::read_to_string(&path).context("Failed to read key info")?;
+    let info: KeyInfo = serde_json::from_str(&text).context("Failed to parse key info")?;
+    Ok(Some(info))
+}
+
+fn save_key_info(db_path: &Path, info: &KeyInfo) -> Result<()> {
+    let path = key_info_path(db_path);
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, serde_json::to_string_pretty(info)?)?;
+    std::fs::rename(&tmp, &path)?;
+    Ok(())
+}
+
+// Derive the hex-encoded raw key for SQLCipher
+pub fn derive_key(passphrase: &str, info: &KeyInfo) -> Result<String> {
+    let key = derive_raw_key(passphrase, info)?;
+    Ok(key.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+// Also used for the per-entry key of sensitive entries (sensitive.rs)
+pub fn derive_raw_key(passphrase: &str, info: &KeyInfo) -> Result<[u8; 32]> {
+    if info.kdf != "pbkdf2-sha256" {
+        return Err(anyhow::anyhow!("Unsupported key derivation: {}", info.kdf));
+    }
+    let salt = base64::engine::general_purpose::STANDARD
+        .decode(&info.salt)
+        .context("Invalid salt in key info")?;
+    let mut key = [0u8; 32];
+    pbkdf2::pbkdf2_hmac::<sha2::Sha256>(passphrase.as_bytes(), &salt, info.iterations, &mut key);
+    Ok(key)
+}
+
+// AES-256-GCM with a random nonce, returned as nonce followed by ciphertext. Used for sealed
+// entries (sensitive.rs) and remote backup snapshots (s3_backup.rs), not for the database itself
+pub fn seal(key: &[u8; 32], plaintext: &[u8]) -> Result<Vec<u8>> {
+    use aes_gcm::aead::{Aead, KeyInit};
+    let mut nonce = [0u8; NONCE_LEN];
+    rand::thread_rng().fill_bytes(&mut nonce);
+    let ciphertext = aes_gcm::Aes256Gcm::new(key.into())
+        .encrypt(aes_gcm::Nonce::from_slice(&nonce), plaintext)
+        .map_err(|_| anyhow::anyhow!("Encryption failed"))?;
+    let mut out = nonce.to_vec();
+    out.extend(ciphertext);
+    Ok(out)
+}
+
+pub fn open(key: &[u8; 32], sealed: &[u8]) -> Result<Vec<u8>> {
+    use aes_gcm::aead::{Aead, KeyInit};
+    if sealed.len() < NONCE_LEN {
+        anyhow::bail!("Encrypted data is truncated");
+    }
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+    aes_gcm::Aes256Gcm::new(key.into())
+        .decrypt(aes_gcm::Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| anyhow::anyhow!("Decryption failed (wrong key or corrupted data)"))
+}
+
+// Must run before any other statement on the connection
+pub fn apply_key(conn: &Connection, hex_key: &str) -> Result<()> {
+    conn.execute_batch(&format!("PRAGMA key = \"x'{}'\";", hex_key))?;
+    Ok(())
+}
+
+// SQLCipher only reports a wrong key on first read, so probe the schema
+pub fn verify_readable(conn: &Connection) -> bool {
+    conn.query_row("SELECT count(*) FROM sqlite_master", [], |r| r.get::<_, i64>(0))
+        .is_ok()
+}
+
+pub fn is_plaintext(db_path: &Path) -> bool {
+    if !db_path.exists() {
+        return false;
+    }
+    match Connection::open(db_path) {
+        Ok(conn) => verify_readable(&conn),
+        Err(_) => false,
+    }
+}
+
+// Encryption is in effect only when key info exists and the file isn't readable without it.
+// A leftover key info file next to a plaintext database (e.g. a crash mid-encryption) is ignored.
+pub fn is_encrypted(db_path: &Path) -> bool {
+    key_info_path(db_path).exists() && db_path.exists() && !is_plaintext(db_path)
+}
+
+// Encrypt an existing plaintext database in place: export into a new encrypted file,
+// verify it opens with the key, then swap it over the original. Returns the hex key.
+pub fn encrypt_plaintext_database(db_path: &Path, passphrase: &str) -> Result<String> {
+    let info = KeyInfo::generate();
+    let hex_key = derive_key(passphrase, &info)?;
+
+    let tmp_path = db_path.with_extension("db.encrypting");
+    let _ = std::fs::remove_file(&tmp_path);
+
+    {
+        let conn = Connection::open(db_path)?;
+        // Fold the WAL into the main file so the export sees everything
+        conn.execute_batch("PRAGMA wal_checkpoint(TRUNCATE);")?;
+        conn.execute(
+            &format!("ATTACH DATABASE ?1 AS encrypted KEY \"x'{}'\"", hex_key),
+            [tmp_path.to_string_lossy().to_string()],
+        )?;
+        conn.query_row("SELECT sqlcipher_export('encrypted')", [], |_| Ok(()))?;
+        conn.execute_batch("DETACH DATABASE encrypted;")?;
+    }
+
+    {
+        let check = Connection::open(&tmp_path)?;
+        apply_key(&check, &hex_key)?;
+        if !verify_readable(&check) {
+            let _ = std::fs::remove_file(&tmp_path);
+            return Err(anyhow::anyhow!("Encrypted copy failed verification"));
+        }
+    }
+
+    // Key info first: if we crash before the swap, is_encrypted() still sees a plaintext file
+    save_key_info(db_path, &info)?;
+    std::fs::rename(&tmp_path, db_path).context("Failed to replace database with encrypted copy")?;
+    for suffix in ["-wal", "-shm"] {
+        let mut side = db_path.as_os_str().to_os_string();
+        side.push(suffix);
+        let _ = std::fs::remove_file(PathBuf::from(side));
+    }
+
+    tracing::info!("encrypted database at {}", db_path.display());
+    Ok(hex_key)
+}
+
+// Change the passphrase of an already-encrypted, unlocked database. The salt is kept so the
+// key info file never has to change in step with the rekey.
+pub fn rekey_database(conn: &Connection, db_path: &Path, new_passphrase: &str) -> Result<String> {
+    let info = load_key_info(db_path)?.context("Database is not encrypted")?;
+    let hex_key = derive_key(new_passphrase, &info)?;
+    conn.execute_batch(&format!("PRAGMA rekey = \"x'{}'\";", hex_key))?;
+    Ok(hex_key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_derive_key_is_deterministic_per_salt() {
+        let info = KeyInfo::generate();
+        let a = derive_key("correct horse", &info).unwrap();
+        let b = derive_key("correct horse", &info).unwrap();
+        assert_eq!(a, b);
+        assert_eq!(a.len(), 64);
+
+        let other = KeyInfo::generate();
+        assert_ne!(a, derive_key("correct horse", &other).unwrap());
+    }
+
+    #[test]
+    fn test_encrypt_plaintext_database() {
+        let dir = std::env::temp_dir().join(format!("jr-enc-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let db_path = dir.join("journal.db");
+        {
+            let conn = Connection::open(&db_path).unwrap();
+            conn.execute_batch("CREATE TABLE t (v TEXT); INSERT INTO t VALUES ('secret');").unwrap();
+        }
+        assert!(is_plaintext(&db_path));
+
+        let key = encrypt_plaintext_database(&db_path, "correct horse").unwrap();
+        assert!(is_encrypted(&db_path));
+
+        let conn = Connection::open(&db_path).unwrap();
+        apply_key(&conn, &key).unwrap();
+        let v: String = conn.query_row("SELECT v FROM t", [], |r| r.get(0)).unwrap();
+        assert_eq!(v, "secret");
+
+        let info = load_key_info(&db_path).unwrap().unwrap();
+        let wrong = derive_key("wrong horse", &info).unwrap();
+        let conn = Connection::open(&db_path).unwrap();
+        apply_key(&conn, &wrong).unwrap();
+        assert!(!verify_readable(&conn));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_key_info_path() {
+        let p = key_info_path(Path::new("/tmp/x/journal.db"));
+        assert_eq!(p, PathBuf::from("/tmp/x/journal.db.keyinfo.json"));
+    }
+}
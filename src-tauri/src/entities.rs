@@ -0,0 +1,387 @@
+use anyhow::Result;
+use chrono::Utc;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tauri::AppHandle;
+
+use crate::ai::{AiSettings, ChatMessage, ChatRequest};
+
+// People, places and events mentioned in entries.
+//
+// The default pass is rule-based: runs of capitalized words that don't open a sentence are
+// candidates, classified by the word in front of them ("in Paris", "went to Lisbon" are places)
+// and by event words ("Jazz Festival"), everything else being a person. With
+// `entity_extraction_ai` on, the chat provider is asked instead and the rules remain the
+// fallback. Results are stored per entry, normalized by kind and lowercase name, together with
+// the text hash they were extracted from so edited entries are picked up again.
+
+const BATCH_SIZE: i64 = 100;
+
+// Capitalized words that are not names
+const NOT_NAMES: &[&str] = &[
+    "I", "I'm", "I've", "I'd", "I'll", "The", "A", "An", "And", "But", "So", "Then", "This", "That", "It", "We",
+    "He", "She", "They", "You", "My", "Our", "Today", "Tomorrow", "Yesterday", "Monday", "Tuesday", "Wednesday",
+    "Thursday", "Friday", "Saturday", "Sunday", "January", "February", "March", "April", "May", "June", "July",
+    "August", "September", "October", "November", "December", "God", "OK", "Ok", "Mom", "Dad",
+];
+
+const EVENT_WORDS: &[&str] = &[
+    "Festival", "Conference", "Wedding", "Birthday", "Party", "Christmas", "Thanksgiving", "Easter", "Halloween",
+    "Marathon", "Concert", "Graduation", "Reunion", "Summit", "Fair", "Carnival", "Olympics", "Hanukkah", "Diwali",
+];
+
+// Words that put the following name in a place: "in Paris", "visited Rome"
+const PLACE_CONTEXT: &[&str] = &["in", "at", "from", "visited", "near", "around", "across", "toward", "towards", "via", "into"];
+// "to" only counts after movement: "went to Lisbon" but not "talked to Anna"
+const MOVEMENT: &[&str] = &[
+    "went", "go", "going", "goes", "drove", "flew", "moved", "travelled", "traveled", "trip", "back", "walked",
+    "came", "come", "arrived", "headed", "returned", "drive", "fly", "move",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EntityKind {
+    Person,
+    Place,
+    Event,
+}
+
+impl EntityKind {
+    pub fn parse(value: &str) -> Result<Self> {
+        match value {
+            "person" | "people" => Ok(EntityKind::Person),
+            "place" | "places" => Ok(EntityKind::Place),
+            "event" | "events" => Ok(EntityKind::Event),
+            other => Err(anyhow::anyhow!("Unknown entity kind '{}' (use person, place or event)", other)),
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            EntityKind::Person => "person",
+            EntityKind::Place => "place",
+            EntityKind::Event => "event",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExtractedEntity {
+    pub kind: EntityKind,
+    pub name: String,
+    pub mentions: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EntitySummary {
+    pub id: i64,
+    pub kind: EntityKind,
+    pub name: String,
+    pub entry_count: u32,
+    pub first_seen: Option<String>,
+    pub last_seen: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExtractionReport {
+    pub processed: u32,
+    pub entities_found: u32,
+    // "rules" or "ai"
+    pub method: String,
+}
+
+pub fn normalize(name: &str) -> String {
+    name.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+fn last_word(text: &str) -> Option<String> {
+    text.split(|c: char| !c.is_alphanumeric() && c != '\'')
+        .rfind(|w| !w.is_empty())
+        .map(|w| w.to_lowercase())
+}
+
+// Rule-based pass: people, places and events in order of first mention
+pub fn extract_rules(text: &str) -> Vec<ExtractedEntity> {
+    static NAME: std::sync::OnceLock<regex::Regex> = std::sync::OnceLock::new();
+    let name_pattern = NAME.get_or_init(|| {
+        regex::Regex::new(r"\b[A-Z][a-z]+(?: [A-Z][a-z]+){0,2}(?:'s)?\b").expect("valid name pattern")
+    });
+
+    // name -> (first position, place votes, other votes, mentions, event)
+    let mut found: HashMap<String, (usize, u32, u32, u32, bool)> = HashMap::new();
+    for m in name_pattern.find_iter(text) {
+        let before = text[..m.start()].trim_end();
+        let mut candidate = m.as_str().trim_end_matches("'s");
+        let mut context = last_word(before);
+        let mut context_before = last_word(before.trim_end_matches(|c: char| c.is_alphanumeric() || c == '\''));
+        if before.is_empty() || before.ends_with(['.', '!', '?', '"', ':', '\n']) {
+            // "Later Ben" at a sentence start: only what follows the first word can be a name,
+            // and the first word is its context ("In Paris")
+            match candidate.split_once(' ') {
+                Some((first, rest)) => {
+                    context_before = None;
+                    context = Some(first.to_lowercase());
+                    candidate = rest;
+                }
+                None => continue,
+            }
+        }
+        let first = candidate.split(' ').next().unwrap_or(candidate);
+        if NOT_NAMES.contains(&first) {
+            continue;
+        }
+        let is_place = match context.as_deref() {
+            Some("to") => context_before.as_deref().is_some_and(|w| MOVEMENT.contains(&w)),
+            Some(word) => PLACE_CONTEXT.contains(&word),
+            None => false,
+        };
+        let is_event = candidate.split(' ').any(|w| EVENT_WORDS.contains(&w));
+        let entry = found.entry(candidate.to_string()).or_insert((m.start(), 0, 0, 0, false));
+        if is_place {
+            entry.1 += 1;
+        } else {
+            entry.2 += 1;
+        }
+        entry.3 += 1;
+        entry.4 |= is_event;
+    }
+
+    let mut entities: Vec<(usize, ExtractedEntity)> = found
+        .into_iter()
+        .map(|(name, (position, place, other, mentions, event))| {
+            let kind = if event {
+                EntityKind::Event
+            } else if place > other {
+                EntityKind::Place
+            } else {
+                EntityKind::Person
+            };
+            (position, ExtractedEntity { kind, name, mentions })
+        })
+        .collect();
+    entities.sort_by_key(|(position, _)| *position);
+    entities.into_iter().map(|(_, e)| e).collect()
+}
+
+fn count_mentions(text: &str, name: &str) -> u32 {
+    (text.to_lowercase().matches(&name.to_lowercase()).count() as u32).max(1)
+}
+
+// Reads {"people": [...], "places": [...], "events": [...]}, tolerating text around the JSON
+fn parse_ai_response(response: &str, text: &str) -> Result<Vec<ExtractedEntity>> {
+    let start = response.find('{').ok_or_else(|| anyhow::anyhow!("No JSON object in response"))?;
+    let end = response.rfind('}').ok_or_else(|| anyhow::anyhow!("No JSON object in response"))?;
+    let json: serde_json::Value = serde_json::from_str(&response[start..=end])?;
+    let mut entities = Vec::new();
+    for (key, kind) in [("people", EntityKind::Person), ("places", EntityKind::Place), ("events", EntityKind::Event)] {
+        for name in json[key].as_array().into_iter().flatten().filter_map(|v| v.as_str()) {
+            let name = name.split_whitespace().collect::<Vec<_>>().join(" ");
+            if name.is_empty() || entities.iter().any(|e: &ExtractedEntity| e.kind == kind && normalize(&e.name) == normalize(&name)) {
+                continue;
+            }
+            entities.push(ExtractedEntity { kind, mentions: count_mentions(text, &name), name });
+        }
+    }
+    Ok(entities)
+}
+
+async fn extract_ai(settings: &AiSettings, text: &str) -> Result<Vec<ExtractedEntity>> {
+    let prompt = format!(
+        "List the people, places and named events mentioned in this journal entry. Reply with only JSON of the form {{\"people\": [], \"places\": [], \"events\": []}} using names as written; leave out the writer.\n\n{}",
+        text
+    );
+    let request = ChatRequest {
+        messages: vec![ChatMessage { role: "user".to_string(), content: prompt }],
+        model: String::new(),
+        provider: None,
+    };
+    let response = crate::ai::chat_completion(settings, request).await?;
+    parse_ai_response(&response, text)
+}
+
+// Replaces the entities stored for one entry
+pub(crate) fn store_entities(
+    conn: &mut Connection,
+    entry_id: &str,
+    text_hash: &str,
+    method: &str,
+    entities: &[ExtractedEntity],
+) -> Result<()> {
+    let tx = conn.transaction()?;
+    tx.execute("DELETE FROM entry_entities WHERE entry_id = ?1", params![entry_id])?;
+    {
+        let mut upsert = tx.prepare_cached(
+            "INSERT INTO entities (kind, name, normalized) VALUES (?1, ?2, ?3) ON CONFLICT(kind, normalized) DO NOTHING",
+        )?;
+        let mut lookup = tx.prepare_cached("SELECT id FROM entities WHERE kind = ?1 AND normalized = ?2")?;
+        let mut link = tx.prepare_cached(
+            r#"INSERT INTO entry_entities (entry_id, entity_id, mentions) VALUES (?1, ?2, ?3)
+                ON CONFLICT(entry_id, entity_id) DO UPDATE SET mentions = mentions + excluded.mentions"#,
+        )?;
+        for entity in entities {
+            let normalized = normalize(&entity.name);
+            upsert.execute(params![entity.kind.as_str(), entity.name, normalized])?;
+            let id: i64 = lookup.query_row(params![entity.kind.as_str(), normalized], |r| r.get(0))?;
+            link.execute(params![entry_id, id, entity.mentions])?;
+        }
+    }
+    tx.execute(
+        r#"INSERT INTO entity_extractions (entry_id, text_hash, method, extracted_at) VALUES (?1, ?2, ?3, ?4)
+            ON CONFLICT(entry_id) DO UPDATE SET text_hash = excluded.text_hash, method = excluded.method,
+                extracted_at = excluded.extracted_at"#,
+        params![entry_id, text_hash, method, Utc::now().to_rfc3339()],
+    )?;
+    tx.commit()?;
+    Ok(())
+}
+
+// Entries never extracted, or edited since
+fn pending(conn: &Connection, limit: i64) -> Result<Vec<(String, String, String)>> {
+    let mut stmt = conn.prepare_cached(
+        r#"SELECT e.id, COALESCE(e.title || char(10), '') || e.body, e.text_hash FROM entries e
+            LEFT JOIN entity_extractions x ON x.entry_id = e.id
+            WHERE x.entry_id IS NULL OR x.text_hash != e.text_hash
+            LIMIT ?1"#,
+    )?;
+    let rows = stmt.query_map(params![limit], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)))?;
+    Ok(rows.collect::<rusqlite::Result<_>>()?)
+}
+
+pub(crate) fn list(conn: &Connection, kind: Option<EntityKind>, limit: u32) -> Result<Vec<EntitySummary>> {
+    let mut stmt = conn.prepare_cached(
+        r#"SELECT en.id, en.kind, en.name, count(*), min(e.entry_date), max(e.entry_date)
+            FROM entities en
+            JOIN entry_entities ee ON ee.entity_id = en.id
+            JOIN entries e ON e.id = ee.entry_id
+            WHERE ?1 IS NULL OR en.kind = ?1
+            GROUP BY en.id
+            ORDER BY count(*) DESC, en.name
+            LIMIT ?2"#,
+    )?;
+    let rows = stmt.query_map(params![kind.map(|k| k.as_str()), limit as i64], |r| {
+        Ok((r.get::<_, i64>(0)?, r.get::<_, String>(1)?, r.get(2)?, r.get(3)?, r.get(4)?, r.get(5)?))
+    })?;
+    let mut out = Vec::new();
+    for row in rows {
+        let (id, kind, name, entry_count, first_seen, last_seen) = row?;
+        out.push(EntitySummary { id, kind: EntityKind::parse(&kind)?, name, entry_count, first_seen, last_seen });
+    }
+    Ok(out)
+}
+
+// Extracts entities for every new or edited entry; `force` redoes all of them
+pub async fn extract_pending(app_handle: &AppHandle, force: bool) -> Result<ExtractionReport> {
+    let settings = {
+        let conn = crate::database::open_conn(app_handle)?;
+        if force {
+            conn.execute("DELETE FROM entity_extractions", [])?;
+        }
+        let use_ai = crate::database::setting_enabled(&conn, "entity_extraction_ai", false);
+        use_ai.then(|| AiSettings::load(&conn))
+    };
+    let method = if settings.is_some() { "ai" } else { "rules" };
+
+    let mut report = ExtractionReport { processed: 0, entities_found: 0, method: method.to_string() };
+    loop {
+        let batch = {
+            let conn = crate::database::open_conn(app_handle)?;
+            pending(&conn, BATCH_SIZE)?
+        };
+        if batch.is_empty() {
+            break;
+        }
+        for (entry_id, text, text_hash) in batch {
+            let (entities, used) = match &settings {
+                Some(settings) => match crate::ai_audit::with_context("entity extraction", vec![entry_id.clone()], extract_ai(settings, &text)).await {
+                    Ok(entities) => (entities, "ai"),
+                    Err(e) => {
+                        tracing::warn!("AI extraction failed for {}, using rules: {}", entry_id, e);
+                        (extract_rules(&text), "rules")
+                    }
+                },
+                None => (extract_rules(&text), "rules"),
+            };
+            let mut conn = crate::database::open_conn(app_handle)?;
+            store_entities(&mut conn, &entry_id, &text_hash, used, &entities)?;
+            report.processed += 1;
+            report.entities_found += entities.len() as u32;
+        }
+    }
+
+    let conn = crate::database::open_conn(app_handle)?;
+    conn.execute("DELETE FROM entities WHERE id NOT IN (SELECT entity_id FROM entry_entities)", [])?;
+    tracing::info!("processed={} found={} method={}", report.processed, report.entities_found, method);
+    Ok(report)
+}
+
+pub async fn list_entities(app_handle: &AppHandle, kind: Option<EntityKind>, limit: u32) -> Result<Vec<EntitySummary>> {
+    let conn = crate::database::open_conn(app_handle)?;
+    list(&conn, kind, limit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(entities: &[ExtractedEntity], kind: EntityKind) -> Vec<&str> {
+        entities.iter().filter(|e| e.kind == kind).map(|e| e.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_extract_rules() {
+        let text = "Went hiking with Anna Smith today. Later Ben's dog ran off.\n\
+            In Paris we talked to Anna Smith about the Jazz Festival. Then we went to Lisbon.\n\
+            Monday I met Ben again.";
+        let entities = extract_rules(text);
+        assert_eq!(names(&entities, EntityKind::Person), ["Anna Smith", "Ben"]);
+        assert_eq!(names(&entities, EntityKind::Place), ["Paris", "Lisbon"]);
+        assert_eq!(names(&entities, EntityKind::Event), ["Jazz Festival"]);
+        assert_eq!(entities.iter().find(|e| e.name == "Anna Smith").unwrap().mentions, 2);
+    }
+
+    #[test]
+    fn test_parse_ai_response() {
+        let response = "Sure:\n{\"people\": [\"Anna\", \"anna\"], \"places\": [\"Paris\"], \"events\": []}";
+        let entities = parse_ai_response(response, "Anna and I flew to Paris. Anna loved it.").unwrap();
+        assert_eq!(entities.len(), 2);
+        assert_eq!((entities[0].kind, entities[0].mentions), (EntityKind::Person, 2));
+        assert_eq!(entities[1].kind, EntityKind::Place);
+    }
+
+    #[test]
+    fn test_store_and_filter_by_entity() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::migrations::run_migrations(&mut conn).unwrap();
+        conn.execute_batch(
+            r#"
+            INSERT INTO entries (id, title, body, entry_date, entry_timezone, source_path, source_type, text_hash, created_at, updated_at)
+            VALUES ('a', NULL, 'We flew to Paris with Anna.', '2016-05-01T00:00:00+00:00', 'UTC', 'a', 'txt', 'h1', '', ''),
+                   ('b', NULL, 'Quiet day at home.', '2016-05-02T00:00:00+00:00', 'UTC', 'b', 'txt', 'h2', '', '');
+            "#,
+        )
+        .unwrap();
+        for (id, text, hash) in pending(&conn, 10).unwrap() {
+            store_entities(&mut conn, &id, &hash, "rules", &extract_rules(&text)).unwrap();
+        }
+        assert!(pending(&conn, 10).unwrap().is_empty());
+
+        let places = list(&conn, Some(EntityKind::Place), 10).unwrap();
+        assert_eq!(places.len(), 1);
+        assert_eq!((places[0].name.as_str(), places[0].entry_count), ("Paris", 1));
+
+        let filter = crate::database::EntryFilter { entities: Some(vec!["paris".into()]), ..Default::default() };
+        let (sql, values) = filter.to_sql(1).unwrap();
+        let mut stmt = conn.prepare(&format!("SELECT e.id FROM entries e WHERE 1 = 1{}", sql)).unwrap();
+        let ids: Vec<String> = stmt
+            .query_map(rusqlite::params_from_iter(values), |r| r.get(0))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        assert_eq!(ids, ["a"]);
+
+        // Editing the entry makes it pending again
+        conn.execute("UPDATE entries SET text_hash = 'h1b' WHERE id = 'a'", []).unwrap();
+        assert_eq!(pending(&conn, 10).unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,168 @@
+use serde::{Deserialize, Serialize};
+
+// Errors returned by commands.
+//
+// The frontend gets `{ message, code, path?, entry_id?, provider? }`: `code` is the kind
+// below in SCREAMING_SNAKE_CASE, for choosing what to tell the user ("pandoc is missing",
+// "unlock sensitive entries first") instead of a generic failure. A few kinds are read
+// from the error itself whatever the command passes, since the UI handles them the same
+// everywhere: locked sensitive entries, a source file changed on disk, a cancelled search
+// and a missing external tool.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum AppErrorKind {
+    // Anything without a more specific kind
+    Internal,
+    InvalidInput,
+    NotFound,
+    Duplicate,
+    Cancelled,
+    Timeout,
+    Database,
+    // The database passphrase is wrong or encryption failed
+    Encryption,
+    Settings,
+    Keychain,
+    Search,
+    SearchIndex,
+    Embeddings,
+    // The entry has no embedding yet, so similarity can't be computed
+    NotEmbedded,
+    Ai,
+    Network,
+    // Google sign-in is missing, expired or was refused
+    Auth,
+    Import,
+    Export,
+    // A program the app runs (pandoc) isn't installed
+    MissingTool,
+    Sync,
+    SourceConflict,
+    Sensitive,
+    SensitiveLocked,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ErrorContext {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entry_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppError {
+    pub message: String,
+    #[serde(rename = "code")]
+    pub kind: AppErrorKind,
+    #[serde(flatten)]
+    pub context: ErrorContext,
+}
+
+// A program the app needs to read a file isn't installed
+#[derive(Debug)]
+pub struct MissingTool {
+    pub tool: &'static str,
+    pub hint: &'static str,
+}
+
+impl std::fmt::Display for MissingTool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} is not installed; {}", self.tool, self.hint)
+    }
+}
+
+impl std::error::Error for MissingTool {}
+
+// The kind an error always has, whichever command it comes from
+fn inherent_kind(error: &anyhow::Error) -> Option<AppErrorKind> {
+    if crate::search_cancel::is_cancelled(error) {
+        return Some(AppErrorKind::Cancelled);
+    }
+    error.chain().find_map(|cause| {
+        if cause.is::<crate::sensitive::Locked>() {
+            Some(AppErrorKind::SensitiveLocked)
+        } else if cause.is::<crate::resync::SourceConflict>() {
+            Some(AppErrorKind::SourceConflict)
+        } else if cause.is::<MissingTool>() {
+            Some(AppErrorKind::MissingTool)
+        } else {
+            None
+        }
+    })
+}
+
+impl AppError {
+    pub fn new(kind: AppErrorKind, message: impl Into<String>) -> Self {
+        Self { message: message.into(), kind, context: ErrorContext::default() }
+    }
+
+    // `kind` unless the error has one of its own
+    pub fn from_error(kind: AppErrorKind, error: impl Into<anyhow::Error>) -> Self {
+        let error = error.into();
+        let kind = inherent_kind(&error).unwrap_or(kind);
+        let mut app_error = Self::new(kind, error.to_string());
+        if let Some(conflict) = error.chain().find_map(|c| c.downcast_ref::<crate::resync::SourceConflict>()) {
+            app_error.context.path = Some(conflict.0.clone());
+        }
+        app_error
+    }
+
+    // For `map_err`: `.map_err(AppError::or(AppErrorKind::Import))`
+    pub fn or<E: Into<anyhow::Error>>(kind: AppErrorKind) -> impl FnOnce(E) -> Self {
+        move |error| Self::from_error(kind, error)
+    }
+
+    pub fn with_path(mut self, path: impl Into<String>) -> Self {
+        self.context.path = Some(path.into());
+        self
+    }
+
+    pub fn with_entry(mut self, entry_id: impl Into<String>) -> Self {
+        self.context.entry_id = Some(entry_id.into());
+        self
+    }
+
+    pub fn with_provider(mut self, provider: &crate::ai::Provider) -> Self {
+        self.context.provider = Some(provider.as_str().to_string());
+        self
+    }
+}
+
+impl std::fmt::Display for AppError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl From<anyhow::Error> for AppError {
+    fn from(error: anyhow::Error) -> Self {
+        Self::from_error(AppErrorKind::Internal, error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kinds_and_serialization() {
+        let error = AppError::from_error(AppErrorKind::Database, anyhow::anyhow!("disk full")).with_entry("e1");
+        assert_eq!(error.kind, AppErrorKind::Database);
+        assert_eq!(
+            serde_json::to_value(&error).unwrap(),
+            serde_json::json!({ "message": "disk full", "code": "DATABASE", "entry_id": "e1" })
+        );
+
+        // Kinds carried by the error win over the command's
+        let locked: AppError = anyhow::Error::new(crate::sensitive::Locked).context("saving").into();
+        assert_eq!(locked.kind, AppErrorKind::SensitiveLocked);
+        let conflict = AppError::from_error(AppErrorKind::Database, crate::resync::SourceConflict("/j/a.txt".into()));
+        assert_eq!((conflict.kind, conflict.context.path.as_deref()), (AppErrorKind::SourceConflict, Some("/j/a.txt")));
+        let missing = AppError::from_error(AppErrorKind::Import, MissingTool { tool: "pandoc", hint: "install it" });
+        assert_eq!(serde_json::to_value(missing.kind).unwrap(), "MISSING_TOOL");
+    }
+}
@@ -0,0 +1,136 @@
+use anyhow::{Context, Result};
+use chrono::DateTime;
+use serde_json::Value;
+use tauri::AppHandle;
+
+use crate::app_import::{AppEntry, AppImportReport};
+use crate::import::FileType;
+
+// Facebook import from "Download Your Information" (JSON format).
+//
+// Posts are in `your_posts_*.json` (an array of `{timestamp, data: [{post}], title}`) and notes
+// in `notes.json` (`notes_v2`, or `notes` in older exports, with `title`, `text` and
+// `created_timestamp`). The whole export folder can be selected; only files named like posts or
+// notes are read, and posts without text of their own (photos, shares, check-ins) are skipped.
+// Timestamps are UTC; entries take the import's time zone and the `facebook` tag.
+//
+// Facebook writes strings as UTF-8 bytes escaped one by one (`Ã©` for "é"), so text
+// is re-decoded before use.
+
+const TAG: &str = "facebook";
+
+// Undo Facebook's escaping; strings that aren't mis-encoded UTF-8 are returned as they are
+fn fix_encoding(s: &str) -> String {
+    if s.chars().all(|c| (c as u32) < 256) {
+        let bytes: Vec<u8> = s.chars().map(|c| c as u8).collect();
+        if let Ok(fixed) = String::from_utf8(bytes) {
+            return fixed;
+        }
+    }
+    s.to_string()
+}
+
+fn text(value: &Value, key: &str) -> String {
+    value.get(key).and_then(Value::as_str).map(|s| fix_encoding(s).trim().to_string()).unwrap_or_default()
+}
+
+fn entry(source: String, title: Option<String>, body: String, timestamp: i64, timezone: &str) -> Result<AppEntry> {
+    Ok(AppEntry {
+        source,
+        title: title.filter(|t| !t.is_empty()),
+        body,
+        tags: vec![TAG.to_string()],
+        created: DateTime::from_timestamp(timestamp, 0).context("Invalid timestamp")?,
+        timezone: timezone.to_string(),
+        sentiment: None,
+    })
+}
+
+fn parse_posts(posts: &[Value], source: &str, timezone: &str) -> Result<Vec<AppEntry>> {
+    let mut entries = Vec::new();
+    for post in posts {
+        let Some(timestamp) = post.get("timestamp").and_then(Value::as_i64) else { continue };
+        let body = post
+            .get("data")
+            .and_then(Value::as_array)
+            .map(|data| data.iter().map(|d| text(d, "post")).filter(|p| !p.is_empty()).collect::<Vec<_>>().join("\n\n"))
+            .unwrap_or_default();
+        // The title is Facebook's caption ("... updated her status."), not the writer's
+        entries.push(entry(format!("{}#{}", source, timestamp), None, body, timestamp, timezone)?);
+    }
+    Ok(entries)
+}
+
+fn parse_notes(notes: &[Value], source: &str, timezone: &str) -> Result<Vec<AppEntry>> {
+    let mut entries = Vec::new();
+    for note in notes {
+        let Some(timestamp) = note.get("created_timestamp").and_then(Value::as_i64) else { continue };
+        let body = crate::app_import::html_to_text(&text(note, "text"));
+        entries.push(entry(format!("{}#{}", source, timestamp), Some(text(note, "title")), body, timestamp, timezone)?);
+    }
+    Ok(entries)
+}
+
+// Entries from one export file; Ok(None) when it holds neither posts nor notes
+pub fn parse_file(json: &str, source: &str, timezone: &str) -> Result<Option<Vec<AppEntry>>> {
+    let value: Value = serde_json::from_str(json).context("Not a readable Facebook export file")?;
+    if let Some(posts) = value.as_array().or_else(|| value.get("status_updates_v2").and_then(Value::as_array)) {
+        if posts.iter().any(|p| p.get("timestamp").is_some()) || posts.is_empty() {
+            return parse_posts(posts, source, timezone).map(Some);
+        }
+    }
+    if let Some(notes) = value.get("notes_v2").or_else(|| value.get("notes")).and_then(Value::as_array) {
+        return parse_notes(notes, source, timezone).map(Some);
+    }
+    Ok(None)
+}
+
+fn export_files(paths: &[String]) -> Vec<std::path::PathBuf> {
+    crate::app_import::files_with_extension(paths, &["json"])
+        .into_iter()
+        .filter(|p| p.file_name().and_then(|n| n.to_str()).is_some_and(|n| n.contains("post") || n.contains("note")))
+        .collect()
+}
+
+pub async fn import_export(app_handle: &AppHandle, paths: &[String], timezone: &str) -> Result<AppImportReport> {
+    let mut report = AppImportReport::default();
+    for file in export_files(paths) {
+        let source = file.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        let parsed = std::fs::read_to_string(&file).map_err(anyhow::Error::from).and_then(|json| parse_file(&json, &source, timezone));
+        match parsed {
+            Ok(Some(entries)) => crate::app_import::save_all(app_handle, entries, FileType::Facebook, &mut report).await?,
+            Ok(None) => {}
+            Err(e) => report.error(format!("{}: {}", file.display(), e)),
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_posts_and_notes() {
+        let posts = r#"[
+  {"timestamp": 1577836800, "data": [{"post": "Happy new year! CafÃ© at midnight"}], "title": "Jane updated her status."},
+  {"timestamp": 1577923200, "attachments": [{"data": [{"media": {"uri": "photo.jpg"}}]}], "title": "Jane added a new photo."}
+]"#;
+        let entries = parse_file(posts, "your_posts_1.json", "Europe/Paris").unwrap().unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].body, "Happy new year! Café at midnight");
+        assert_eq!(entries[0].title, None);
+        assert_eq!(entries[0].tags, ["facebook"]);
+        assert_eq!(entries[0].created.to_rfc3339(), "2020-01-01T00:00:00+00:00");
+        assert_eq!(entries[0].timezone, "Europe/Paris");
+        // Photo-only posts have no text and are skipped when saving
+        assert_eq!(entries[1].body, "");
+
+        let notes = r#"{"notes_v2": [{"title": "Twenty-five things", "text": "<p>One.</p><p>Two.</p>", "created_timestamp": 1233446400}]}"#;
+        let entries = parse_file(notes, "notes.json", "UTC").unwrap().unwrap();
+        assert_eq!(entries[0].title.as_deref(), Some("Twenty-five things"));
+        assert_eq!(entries[0].body, "One.\n\nTwo.");
+
+        assert!(parse_file(r#"{"comments_v2": []}"#, "comments.json", "UTC").unwrap().is_none());
+    }
+}
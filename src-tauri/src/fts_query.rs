@@ -0,0 +1,404 @@
+// User search syntax -> FTS5 MATCH expression.
+//
+// Supported: bare words (implicit AND), "quoted phrases", AND / OR / NOT (uppercase),
+// `-word` as NOT, parentheses, `title:` / `body:` column filters, `word*` prefixes and
+// NEAR(a b, N). Every term is re-emitted as a quoted FTS5 string, so stray quotes, dashes,
+// colons or other punctuation in user input can never produce a syntax error; malformed
+// operators and unbalanced parentheses are dropped rather than rejected.
+
+const COLUMNS: &[&str] = &["title", "body"];
+const DEFAULT_NEAR_DISTANCE: u32 = 10;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Term {
+        text: String,
+        column: Option<String>,
+        prefix: bool,
+    },
+    Near {
+        terms: Vec<String>,
+        distance: u32,
+    },
+    And,
+    Or,
+    Not,
+    LParen,
+    RParen,
+}
+
+fn is_boundary(c: char) -> bool {
+    c.is_whitespace() || c == '(' || c == ')' || c == '"'
+}
+
+fn read_quoted(chars: &[char], i: &mut usize) -> String {
+    // Caller positioned us on the opening quote; an unterminated quote runs to the end
+    *i += 1;
+    let start = *i;
+    while *i < chars.len() && chars[*i] != '"' {
+        *i += 1;
+    }
+    let text: String = chars[start..*i].iter().collect();
+    if *i < chars.len() {
+        *i += 1;
+    }
+    text
+}
+
+fn read_word(chars: &[char], i: &mut usize) -> String {
+    let start = *i;
+    while *i < chars.len() && !is_boundary(chars[*i]) {
+        *i += 1;
+    }
+    chars[start..*i].iter().collect()
+}
+
+// NEAR(a "b c" d, 5): phrases and words up to the closing paren, optional distance after a comma
+fn read_near(chars: &[char], i: &mut usize) -> Token {
+    *i += 1; // '('
+    let mut terms = Vec::new();
+    let mut distance = DEFAULT_NEAR_DISTANCE;
+    let mut after_comma = false;
+    while *i < chars.len() && chars[*i] != ')' {
+        let c = chars[*i];
+        if c == '"' {
+            terms.push(read_quoted(chars, i));
+        } else if c == ',' {
+            after_comma = true;
+            *i += 1;
+        } else if c.is_whitespace() || c == '(' {
+            *i += 1;
+        } else {
+            let start = *i;
+            while *i < chars.len() && !is_boundary(chars[*i]) && chars[*i] != ',' {
+                *i += 1;
+            }
+            let word: String = chars[start..*i].iter().collect();
+            if !after_comma {
+                terms.push(word);
+            } else if let Ok(n) = word.parse::<u32>() {
+                distance = n;
+            }
+        }
+    }
+    if *i < chars.len() {
+        *i += 1;
+    }
+    Token::Near { terms, distance }
+}
+
+fn tokenize(input: &str) -> Vec<Token> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c == '(' {
+            tokens.push(Token::LParen);
+            i += 1;
+        } else if c == ')' {
+            tokens.push(Token::RParen);
+            i += 1;
+        } else if c == '"' {
+            let text = read_quoted(&chars, &mut i);
+            tokens.push(Token::Term { text, column: None, prefix: false });
+        } else if c == '-' && matches!(chars.get(i + 1), Some(&n) if n == '"' || !is_boundary(n) && n != '-') {
+            // -word / -"phrase" means NOT; a dash inside a word (well-being) is left alone
+            tokens.push(Token::Not);
+            i += 1;
+        } else {
+            let word = read_word(&chars, &mut i);
+            match word.as_str() {
+                "AND" => tokens.push(Token::And),
+                "OR" => tokens.push(Token::Or),
+                "NOT" => tokens.push(Token::Not),
+                "NEAR" if i < chars.len() && chars[i] == '(' => tokens.push(read_near(&chars, &mut i)),
+                _ => {
+                    let (column, rest) = match word.split_once(':') {
+                        Some((col, rest)) if COLUMNS.contains(&col.to_lowercase().as_str()) => {
+                            (Some(col.to_lowercase()), rest.to_string())
+                        }
+                        _ => (None, word.clone()),
+                    };
+                    // title:"some phrase"
+                    let (text, prefix) = if column.is_some() && rest.is_empty() && i < chars.len() && chars[i] == '"' {
+                        (read_quoted(&chars, &mut i), false)
+                    } else {
+                        let prefix = rest.ends_with('*');
+                        (rest.trim_end_matches('*').replace('*', ""), prefix)
+                    };
+                    tokens.push(Token::Term { text, column, prefix });
+                }
+            }
+        }
+    }
+    tokens
+}
+
+fn has_content(text: &str) -> bool {
+    text.chars().any(|c| c.is_alphanumeric())
+}
+
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('"', "\"\""))
+}
+
+struct Parser<'a> {
+    tokens: &'a [Token],
+    pos: usize,
+    trigram: bool,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    // or := and (OR and)*
+    fn parse_or(&mut self) -> Option<String> {
+        let mut parts = Vec::new();
+        loop {
+            if let Some(p) = self.parse_and() {
+                parts.push(p);
+            }
+            match self.peek() {
+                Some(Token::Or) => self.pos += 1,
+                _ => break,
+            }
+        }
+        match parts.len() {
+            0 => None,
+            1 => parts.pop(),
+            _ => Some(format!("({})", parts.join(" OR "))),
+        }
+    }
+
+    // and := [NOT] primary ([AND] [NOT] primary)*
+    // FTS5's NOT is binary, so negated operands are collected and appended to the positive
+    // ones; a group that is only negations can't be expressed and is dropped.
+    fn parse_and(&mut self) -> Option<String> {
+        let mut positive = Vec::new();
+        let mut negative = Vec::new();
+        let mut negate = false;
+        while let Some(tok) = self.peek() {
+            match tok {
+                Token::Or | Token::RParen => break,
+                Token::And => self.pos += 1,
+                Token::Not => {
+                    negate = true;
+                    self.pos += 1;
+                }
+                _ => {
+                    if let Some(p) = self.parse_primary() {
+                        if negate { negative.push(p) } else { positive.push(p) }
+                    }
+                    negate = false;
+                }
+            }
+        }
+        if positive.is_empty() {
+            return None;
+        }
+        let mut expr = if positive.len() == 1 {
+            positive.pop().unwrap()
+        } else {
+            format!("({})", positive.join(" AND "))
+        };
+        if negative.is_empty() {
+            return Some(expr);
+        }
+        for n in negative {
+            expr = format!("{} NOT {}", expr, n);
+        }
+        Some(format!("({})", expr))
+    }
+
+    fn parse_primary(&mut self) -> Option<String> {
+        let tok = self.tokens.get(self.pos)?.clone();
+        self.pos += 1;
+        match tok {
+            Token::LParen => {
+                // Compound results come back parenthesized already
+                let inner = self.parse_or();
+                if let Some(Token::RParen) = self.peek() {
+                    self.pos += 1;
+                }
+                inner
+            }
+            Token::Term { text, column, prefix } => {
+                if !has_content(&text) {
+                    return None;
+                }
+                let mut term = quote(text.trim());
+                // Trigram matching is already substring-based and has no prefix queries
+                if prefix && !self.trigram {
+                    term.push_str(" *");
+                }
+                Some(match column {
+                    Some(col) => format!("{} : {}", col, term),
+                    None => term,
+                })
+            }
+            Token::Near { terms, distance } => {
+                let phrases: Vec<String> = terms.iter().filter(|t| has_content(t)).map(|t| quote(t.trim())).collect();
+                match phrases.len() {
+                    0 => None,
+                    1 => phrases.into_iter().next(),
+                    _ => Some(format!("NEAR({}, {})", phrases.join(" "), distance)),
+                }
+            }
+            // Stray operators/parens where an operand was expected
+            _ => None,
+        }
+    }
+}
+
+// Translate user input into a safe FTS5 expression; None when nothing searchable remains
+pub fn to_fts5(input: &str, trigram: bool) -> Option<String> {
+    let tokens = tokenize(input);
+    let mut parser = Parser { tokens: &tokens, pos: 0, trigram };
+    let mut parts = Vec::new();
+    while parser.pos < tokens.len() {
+        if let Some(e) = parser.parse_or() {
+            parts.push(e);
+        }
+        // Skip an unmatched ')' and keep going
+        if parser.pos < tokens.len() {
+            parser.pos += 1;
+        }
+    }
+    match parts.len() {
+        0 => None,
+        1 => parts.pop(),
+        _ => Some(parts.join(" AND ")),
+    }
+}
+
+// The plain words and phrases of a query, without operators or negated terms. Used where
+// the query text is matched outside FTS5 (snippets, scoring).
+pub fn positive_terms(input: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut negate = false;
+    for tok in tokenize(input) {
+        match tok {
+            Token::Not => negate = true,
+            Token::Term { text, .. } => {
+                if !negate && has_content(&text) {
+                    out.push(text.trim().to_string());
+                }
+                negate = false;
+            }
+            Token::Near { terms, .. } => {
+                if !negate {
+                    out.extend(terms.into_iter().filter(|t| has_content(t)));
+                }
+                negate = false;
+            }
+            _ => {}
+        }
+    }
+    out
+}
+
+// Plain text to scan for when a query leaves nothing for FTS5 (only symbols or emoji, which
+// the tokenizer drops). None when the query negates something or is only operators.
+pub fn like_text(input: &str) -> Option<String> {
+    let mut words = Vec::new();
+    for tok in tokenize(input) {
+        match tok {
+            Token::Not => return None,
+            Token::Term { text, .. } => words.push(text.trim().to_string()),
+            Token::Near { terms, .. } => words.extend(terms.iter().map(|t| t.trim().to_string())),
+            _ => {}
+        }
+    }
+    let text = words.into_iter().filter(|w| !w.is_empty()).collect::<Vec<_>>().join(" ");
+    if text.is_empty() { None } else { Some(text) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::Connection;
+
+    fn fts(q: &str) -> Option<String> {
+        to_fts5(q, false)
+    }
+
+    #[test]
+    fn test_basic_translation() {
+        assert_eq!(fts("hello world").as_deref(), Some("(\"hello\" AND \"world\")"));
+        assert_eq!(fts("\"new year\"").as_deref(), Some("\"new year\""));
+        assert_eq!(fts("cat OR dog").as_deref(), Some("(\"cat\" OR \"dog\")"));
+        assert_eq!(fts("walk* title:paris").as_deref(), Some("(\"walk\" * AND title : \"paris\")"));
+        assert_eq!(fts("title:\"trip home\"").as_deref(), Some("title : \"trip home\""));
+        assert_eq!(fts("NEAR(rain umbrella, 5)").as_deref(), Some("NEAR(\"rain\" \"umbrella\", 5)"));
+        assert_eq!(fts("coffee -tea").as_deref(), Some("(\"coffee\" NOT \"tea\")"));
+        assert_eq!(fts("-tea coffee").as_deref(), Some("(\"coffee\" NOT \"tea\")"));
+        assert_eq!(fts("well-being").as_deref(), Some("\"well-being\""));
+        assert_eq!(fts("(a OR b) c").as_deref(), Some("((\"a\" OR \"b\") AND \"c\")"));
+    }
+
+    #[test]
+    fn test_hostile_input_is_neutralized() {
+        assert_eq!(fts(""), None);
+        assert_eq!(fts("-"), None);
+        assert_eq!(fts("\""), None);
+        assert_eq!(fts("AND OR NOT"), None);
+        assert_eq!(fts("-tea"), None);
+        assert_eq!(fts("say \"hi").as_deref(), Some("(\"say\" AND \"hi\")"));
+        assert_eq!(fts("a) OR (b").as_deref(), Some("\"a\" AND \"b\""));
+        assert_eq!(fts("weird:col x").as_deref(), Some("(\"weird:col\" AND \"x\")"));
+        assert_eq!(to_fts5("walk*", true).as_deref(), Some("\"walk\""));
+    }
+
+    #[test]
+    fn test_every_translation_is_valid_fts5() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            r#"
+            CREATE VIRTUAL TABLE t USING fts5(title, body);
+            INSERT INTO t VALUES ('Paris trip', 'we walked in the rain with an umbrella');
+            "#,
+        )
+        .unwrap();
+        let inputs = [
+            "\"", "-", "--", "a -", "NOT", "AND a", "a OR", "((a", "a))", "title:", "title:*", "*", "a*b*",
+            "NEAR(", "NEAR()", "NEAR(a", "x:y:z", "\"a\"\"b\"", "-\"rain\" walked", "c++ c#", "^foo", "{a b}",
+            "rain NOT umbrella OR paris", "title:paris NEAR(rain umbrella, 3) walk*", "can't", "3-day", "don't\"",
+        ];
+        for input in inputs {
+            if let Some(expr) = fts(input) {
+                conn.query_row("SELECT count(*) FROM t WHERE t MATCH ?1", [&expr], |r| r.get::<_, i64>(0))
+                    .unwrap_or_else(|e| panic!("{:?} -> {:?}: {}", input, expr, e));
+            }
+        }
+        let n: i64 = conn
+            .query_row(
+                "SELECT count(*) FROM t WHERE t MATCH ?1",
+                [fts("title:paris NEAR(rain umbrella, 3) walk*").unwrap()],
+                |r| r.get(0),
+            )
+            .unwrap();
+        assert_eq!(n, 1);
+    }
+
+    #[test]
+    fn test_like_fallback_text() {
+        assert_eq!(fts("🎉 ..."), None);
+        assert_eq!(like_text("🎉 ...").as_deref(), Some("🎉 ..."));
+        assert_eq!(like_text("\"♥\""), Some("♥".to_string()));
+        assert_eq!(like_text("AND OR"), None);
+        assert_eq!(like_text("-🎉"), None);
+    }
+
+    #[test]
+    fn test_positive_terms() {
+        assert_eq!(
+            positive_terms("title:paris \"rainy day\" -sad OR walk*"),
+            vec!["paris", "rainy day", "walk"]
+        );
+    }
+}
@@ -0,0 +1,156 @@
+use anyhow::Result;
+use rusqlite::{params, Connection};
+
+// Typo-tolerant search.
+//
+// Each query word is expanded to the indexed terms within a small Levenshtein distance,
+// read from an fts5vocab view of entries_fts, and the alternatives are OR'ed together:
+// `recieve letter` becomes `("recieve" OR "receive") AND ("letter" OR "letters" ...)`.
+// Vocabulary terms are whatever the tokenizer stored, so with stemming enabled typos are
+// matched against stems.
+
+// Best alternatives kept per query word, nearest first, then most common
+const MAX_CANDIDATES: usize = 8;
+
+// Short words tolerate nothing (too many false friends), longer words one or two edits
+pub fn max_distance(word_len: usize) -> usize {
+    match word_len {
+        0..=3 => 0,
+        4..=6 => 1,
+        _ => 2,
+    }
+}
+
+// Levenshtein distance over chars, or None as soon as it must exceed `max`
+pub fn bounded_levenshtein(a: &str, b: &str, max: usize) -> Option<usize> {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    if a.len().abs_diff(b.len()) > max {
+        return None;
+    }
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut cur = vec![0; b.len() + 1];
+    for (i, ca) in a.iter().enumerate() {
+        cur[0] = i + 1;
+        let mut row_min = cur[0];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = if ca == cb { 0 } else { 1 };
+            cur[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(cur[j] + 1);
+            row_min = row_min.min(cur[j + 1]);
+        }
+        if row_min > max {
+            return None;
+        }
+        std::mem::swap(&mut prev, &mut cur);
+    }
+    let d = prev[b.len()];
+    (d <= max).then_some(d)
+}
+
+// Per-connection view of the index vocabulary; lives in `temp` so it never touches the schema
+pub(crate) fn ensure_vocab(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "CREATE VIRTUAL TABLE IF NOT EXISTS temp.entries_fts_vocab USING fts5vocab(main, entries_fts, row);",
+    )?;
+    Ok(())
+}
+
+fn candidates(conn: &Connection, word: &str) -> Result<Vec<String>> {
+    let len = word.chars().count();
+    let max = max_distance(len);
+    if max == 0 {
+        return Ok(vec![]);
+    }
+    let mut stmt = conn.prepare_cached(
+        "SELECT term, doc FROM temp.entries_fts_vocab WHERE length(term) BETWEEN ?1 AND ?2",
+    )?;
+    let rows = stmt.query_map(params![(len - max) as i64, (len + max) as i64], |r| {
+        Ok((r.get::<_, String>(0)?, r.get::<_, i64>(1)?))
+    })?;
+    let mut scored = Vec::new();
+    for row in rows {
+        let (term, docs) = row?;
+        if term == word {
+            continue;
+        }
+        if let Some(d) = bounded_levenshtein(word, &term, max) {
+            scored.push((d, -docs, term));
+        }
+    }
+    scored.sort();
+    Ok(scored.into_iter().take(MAX_CANDIDATES).map(|(_, _, t)| t).collect())
+}
+
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('"', "\"\""))
+}
+
+// FTS5 expression matching every query word or one of its near-misses; None when the
+// query has no searchable words. Operators in the query are ignored here.
+pub fn expand_query(conn: &Connection, query: &str) -> Result<Option<String>> {
+    ensure_vocab(conn)?;
+    let words: Vec<String> = crate::fts_query::positive_terms(query)
+        .iter()
+        .flat_map(|t| t.split(|c: char| !c.is_alphanumeric()).map(|w| w.to_lowercase()).collect::<Vec<_>>())
+        .filter(|w| !w.is_empty())
+        .collect();
+    if words.is_empty() {
+        return Ok(None);
+    }
+
+    let mut groups = Vec::new();
+    for word in words {
+        let mut alternatives = vec![quote(&word)];
+        alternatives.extend(candidates(conn, &word)?.iter().map(|t| quote(t)));
+        groups.push(if alternatives.len() == 1 {
+            alternatives.pop().unwrap()
+        } else {
+            format!("({})", alternatives.join(" OR "))
+        });
+    }
+    Ok(Some(groups.join(" AND ")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bounded_levenshtein() {
+        assert_eq!(bounded_levenshtein("receive", "recieve", 2), Some(2));
+        assert_eq!(bounded_levenshtein("kitten", "sitting", 3), Some(3));
+        assert_eq!(bounded_levenshtein("kitten", "sitting", 2), None);
+        assert_eq!(bounded_levenshtein("café", "cafe", 1), Some(1));
+        assert_eq!(bounded_levenshtein("a", "abcd", 2), None);
+    }
+
+    #[test]
+    fn test_expand_query_finds_misspellings() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            r#"
+            CREATE VIRTUAL TABLE entries_fts USING fts5(title, body, entry_id UNINDEXED);
+            INSERT INTO entries_fts VALUES ('', 'Went to the dentist about my tooth', '1');
+            INSERT INTO entries_fts VALUES ('', 'An appointment with the doctor', '2');
+            "#,
+        )
+        .unwrap();
+
+        let expr = expand_query(&conn, "dentsit apointment").unwrap().unwrap();
+        assert!(expr.contains("\"dentist\""), "{}", expr);
+        assert!(expr.contains("\"appointment\""), "{}", expr);
+
+        let n: i64 = conn
+            .query_row("SELECT count(*) FROM entries_fts WHERE entries_fts MATCH ?1", [&expr], |r| r.get(0))
+            .unwrap();
+        assert_eq!(n, 0); // both words must match; they are in different entries
+
+        let expr = expand_query(&conn, "docter").unwrap().unwrap();
+        let id: String = conn
+            .query_row("SELECT entry_id FROM entries_fts WHERE entries_fts MATCH ?1", [&expr], |r| r.get(0))
+            .unwrap();
+        assert_eq!(id, "2");
+
+        assert!(expand_query(&conn, "  \"\" - ").unwrap().is_none());
+    }
+}
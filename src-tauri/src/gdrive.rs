@@ -0,0 +1,223 @@
+use chrono::{DateTime, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::AppHandle;
+
+use crate::import::{normalize_content, FileType, ParsedFile};
+
+// Google Drive access for importing Docs: folder listing, export to text, and a folder import
+// that mirrors the local bulk import (dedupe by content hash, one result per file).
+
+const FILES_URL: &str = "https://www.googleapis.com/drive/v3/files";
+const DOC_MIME: &str = "application/vnd.google-apps.document";
+const DOCX_MIME: &str = "application/vnd.openxmlformats-officedocument.wordprocessingml.document";
+const PAGE_SIZE: &str = "100";
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DriveDoc {
+    pub id: String,
+    pub name: String,
+    pub modified_time: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FileList {
+    #[serde(default)]
+    files: Vec<DriveDoc>,
+    next_page_token: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FolderFileResult {
+    pub file_id: String,
+    pub name: String,
+    // "imported", "duplicate" or "failed"
+    pub status: String,
+    pub entry_id: Option<String>,
+    pub entry_date: Option<String>,
+    // "title" when the date came from the document name, else "modified"
+    pub date_source: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FolderImportReport {
+    pub imported: u32,
+    pub duplicates: u32,
+    pub failed: u32,
+    pub files: Vec<FolderFileResult>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FolderImportProgress {
+    pub processed: u64,
+    pub total: u64,
+}
+
+// All Google Docs directly inside a folder, following nextPageToken until the listing ends
+pub async fn list_folder_docs(client: &reqwest::Client, access: &str, folder_id: &str) -> anyhow::Result<Vec<DriveDoc>> {
+    let query = format!(
+        "'{}' in parents and mimeType='{}' and trashed=false",
+        folder_id.replace('\\', "\\\\").replace('\'', "\\'"),
+        DOC_MIME
+    );
+    let mut docs = Vec::new();
+    let mut page_token: Option<String> = None;
+    loop {
+        let mut params = vec![
+            ("q", query.as_str()),
+            ("fields", "nextPageToken,files(id,name,modifiedTime)"),
+            ("pageSize", PAGE_SIZE),
+            ("supportsAllDrives", "true"),
+            ("includeItemsFromAllDrives", "true"),
+        ];
+        if let Some(token) = page_token.as_deref() {
+            params.push(("pageToken", token));
+        }
+        let resp = client.get(FILES_URL).bearer_auth(access).query(&params).send().await?;
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let body = resp.text().await.unwrap_or_default();
+            return Err(anyhow::anyhow!("Drive listing failed ({}): {}", status, body.trim()));
+        }
+        let page: FileList = resp.json().await?;
+        docs.extend(page.files);
+        match page.next_page_token {
+            Some(token) if !token.is_empty() => page_token = Some(token),
+            _ => break,
+        }
+    }
+    Ok(docs)
+}
+
+// Exports a Doc as plain text, falling back to a docx export. Empty when neither export worked;
+// Err only for transport failures.
+pub async fn export_doc_text(client: &reqwest::Client, access: &str, file_id: &str) -> anyhow::Result<String> {
+    let base = format!("{}/{}", FILES_URL, file_id);
+    let resp = client.get(format!("{}/export", base)).bearer_auth(access).query(&[("mimeType", "text/plain")]).send().await?;
+    if resp.status().is_success() {
+        return Ok(resp.text().await.unwrap_or_default());
+    }
+    let resp = client.get(format!("{}/export", base)).bearer_auth(access).query(&[("mimeType", DOCX_MIME)]).send().await?;
+    if !resp.status().is_success() {
+        return Ok(String::new());
+    }
+    let bytes = resp.bytes().await.unwrap_or_default();
+    let tmp = std::env::temp_dir().join(format!("{}.docx", file_id));
+    let _ = std::fs::write(&tmp, &bytes);
+    let text = crate::import::parse_docx_file(tmp.to_string_lossy().as_ref()).await.unwrap_or_default();
+    let _ = std::fs::remove_file(&tmp);
+    Ok(text)
+}
+
+pub fn parsed_doc(file_id: &str, title: Option<String>, content: &str) -> ParsedFile {
+    let content = normalize_content(content);
+    let text_hash = format!("{:x}", Sha256::digest(content.as_bytes()));
+    ParsedFile {
+        path: format!("gdrive:{}", file_id),
+        size_bytes: content.len() as u64,
+        content,
+        title,
+        file_type: FileType::Txt,
+        text_hash,
+    }
+}
+
+// The date a Doc was written: from its name when it contains one (at noon, so the calendar day
+// survives timezone display), else its last modification time
+fn doc_date(doc: &DriveDoc) -> Option<(DateTime<Utc>, &'static str)> {
+    if let Some(date) = crate::import::date_from_title(&doc.name) {
+        let noon = date.and_time(NaiveTime::from_hms_opt(12, 0, 0)?);
+        return Some((DateTime::from_naive_utc_and_offset(noon, Utc), "title"));
+    }
+    doc.modified_time.map(|t| (t, "modified"))
+}
+
+async fn import_doc(
+    app_handle: &AppHandle,
+    client: &reqwest::Client,
+    access: &str,
+    doc: &DriveDoc,
+    timezone: &str,
+) -> FolderFileResult {
+    let mut result = FolderFileResult {
+        file_id: doc.id.clone(),
+        name: doc.name.clone(),
+        status: "failed".to_string(),
+        entry_id: None,
+        entry_date: None,
+        date_source: None,
+        error: None,
+    };
+    let outcome: anyhow::Result<()> = async {
+        let content = export_doc_text(client, access, &doc.id).await?;
+        if content.trim().is_empty() {
+            anyhow::bail!("Failed to export Google Doc content");
+        }
+        let parsed = parsed_doc(&doc.id, Some(doc.name.clone()), &content);
+        if let Some(existing) = crate::database::check_duplicate(app_handle, &parsed.text_hash).await? {
+            result.status = "duplicate".to_string();
+            result.entry_id = Some(existing);
+            return Ok(());
+        }
+        let (date, source) = doc_date(doc).ok_or_else(|| anyhow::anyhow!("No date in the title and no modified time"))?;
+        result.entry_date = Some(date.to_rfc3339());
+        result.date_source = Some(source.to_string());
+        let id = crate::database::save_entry(app_handle, parsed, date, timezone.to_string()).await?;
+        result.status = "imported".to_string();
+        result.entry_id = Some(id);
+        Ok(())
+    }
+    .await;
+    if let Err(e) = outcome {
+        result.error = Some(e.to_string());
+    }
+    result
+}
+
+pub async fn import_folder(
+    app_handle: &AppHandle,
+    access: &str,
+    folder_id: &str,
+    timezone: &str,
+    on_progress: impl Fn(FolderImportProgress),
+) -> anyhow::Result<FolderImportReport> {
+    let client = reqwest::Client::new();
+    let docs = list_folder_docs(&client, access, folder_id).await?;
+    let total = docs.len() as u64;
+    let mut report = FolderImportReport { imported: 0, duplicates: 0, failed: 0, files: Vec::with_capacity(docs.len()) };
+    for (i, doc) in docs.iter().enumerate() {
+        let result = import_doc(app_handle, &client, access, doc, timezone).await;
+        match result.status.as_str() {
+            "imported" => report.imported += 1,
+            "duplicate" => report.duplicates += 1,
+            _ => report.failed += 1,
+        }
+        report.files.push(result);
+        on_progress(FolderImportProgress { processed: i as u64 + 1, total });
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_doc_date_prefers_title() {
+        let modified = "2023-06-01T08:30:00Z".parse::<DateTime<Utc>>().unwrap();
+        let doc = |name: &str| DriveDoc { id: "x".into(), name: name.into(), modified_time: Some(modified) };
+        let (date, source) = doc_date(&doc("Journal 2021-03-05")).unwrap();
+        assert_eq!((date.to_rfc3339().as_str(), source), ("2021-03-05T12:00:00+00:00", "title"));
+        assert_eq!(doc_date(&doc("Untitled document")), Some((modified, "modified")));
+
+        let page: FileList = serde_json::from_str(
+            r#"{"nextPageToken":"abc","files":[{"id":"1","name":"A","modifiedTime":"2023-06-01T08:30:00.000Z"}]}"#,
+        )
+        .unwrap();
+        assert_eq!(page.next_page_token.as_deref(), Some("abc"));
+        assert_eq!(page.files[0].modified_time, Some(modified));
+    }
+}
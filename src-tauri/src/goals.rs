@@ -0,0 +1,505 @@
+use std::sync::OnceLock;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::ai::{AiSettings, ChatMessage, ChatRequest};
+
+// Goals stated in entries ("I want to run a marathon") and what later entries say about them.
+//
+// Entries are read in date order. Each may state new goals and mention goals stated in earlier
+// entries, as progress, achieved or abandoned; each mention is stored with the sentence it came
+// from, so a goal's timeline is its mentions in date order. A goal's status follows its latest
+// mention: achieved, abandoned, or active, and dormant once nothing has mentioned it for
+// DORMANT_DAYS before the newest entry. Mentions after a goal is achieved are not looked for.
+//
+// The default pass is rule-based: phrases like "I want to", "my goal is to", "I've decided to"
+// state goals, and a later sentence sharing two of the goal's key words (or a long one, like
+// "marathon") mentions it, with words like "finished" or "gave up" deciding how. With
+// `goal_tracking_ai` on, the chat provider reads each entry together with the goals still open
+// at its date, and the rules remain the fallback. Edited entries are read again, like entity
+// extraction; sensitive entries are never read.
+
+const BATCH_SIZE: i64 = 100;
+const DORMANT_DAYS: i64 = 180;
+// Open goals listed in the AI prompt, most recent first
+const MAX_PROMPT_GOALS: i64 = 30;
+
+const STOP_WORDS: &[&str] = &[
+    "the", "and", "for", "with", "more", "less", "this", "that", "year", "some", "every", "again", "really", "start",
+    "try", "get", "my", "our", "from", "into", "about", "finally", "least", "day", "week", "month", "each", "able",
+    "how", "its", "out", "all", "one", "two", "few", "lot",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MentionKind {
+    Stated,
+    Progress,
+    Achieved,
+    Abandoned,
+}
+
+impl MentionKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            MentionKind::Stated => "stated",
+            MentionKind::Progress => "progress",
+            MentionKind::Achieved => "achieved",
+            MentionKind::Abandoned => "abandoned",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "stated" => Some(MentionKind::Stated),
+            "progress" => Some(MentionKind::Progress),
+            "achieved" | "done" | "completed" => Some(MentionKind::Achieved),
+            "abandoned" | "dropped" => Some(MentionKind::Abandoned),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpenGoal {
+    pub id: i64,
+    pub description: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GoalUpdate {
+    pub goal_id: i64,
+    pub kind: MentionKind,
+    pub excerpt: String,
+}
+
+// What one entry says: goals it states (description, sentence) and mentions of open goals
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GoalExtraction {
+    pub stated: Vec<(String, String)>,
+    pub updates: Vec<GoalUpdate>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GoalEvent {
+    pub entry_id: String,
+    pub entry_date: String,
+    pub kind: MentionKind,
+    pub excerpt: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Goal {
+    pub id: i64,
+    pub description: String,
+    // "active", "dormant", "achieved" or "abandoned"
+    pub status: String,
+    pub stated_at: String,
+    pub source_entry_id: String,
+    pub last_mentioned_at: String,
+    pub timeline: Vec<GoalEvent>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GoalReport {
+    pub processed: u32,
+    pub goals_found: u32,
+    pub updates: u32,
+    // "rules" or "ai"
+    pub method: String,
+}
+
+pub fn normalize(description: &str) -> String {
+    description.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+fn sentences(text: &str) -> impl Iterator<Item = &str> {
+    text.split(['.', '!', '?', '\n']).map(str::trim).filter(|s| !s.is_empty())
+}
+
+fn excerpt(sentence: &str) -> String {
+    sentence.chars().take(200).collect()
+}
+
+// Lowercase words of three letters or more that say what the goal is about
+fn keywords(description: &str) -> Vec<String> {
+    description
+        .split(|c: char| !c.is_alphanumeric())
+        .map(str::to_lowercase)
+        .filter(|w| w.chars().count() >= 3 && !STOP_WORDS.contains(&w.as_str()))
+        .collect()
+}
+
+// "running" matches "run", "marathons" matches "marathon": the same first five letters, or the
+// whole of a shorter key word
+fn same_word(word: &str, key: &str) -> bool {
+    let stem = |w: &str| w.chars().take(5).collect::<String>();
+    if key.chars().count() < 5 {
+        word.starts_with(key)
+    } else {
+        stem(word) == stem(key)
+    }
+}
+
+fn mentions(sentence: &str, description: &str) -> bool {
+    let keys = keywords(description);
+    if keys.is_empty() {
+        return false;
+    }
+    let words: Vec<String> = sentence.split(|c: char| !c.is_alphanumeric()).map(str::to_lowercase).filter(|w| !w.is_empty()).collect();
+    let found: Vec<&String> = keys.iter().filter(|k| words.iter().any(|w| same_word(w, k))).collect();
+    // Two key words, or a long one on its own ("marathon")
+    found.len() >= keys.len().min(2) || found.iter().any(|k| k.chars().count() >= 6)
+}
+
+fn cue(sentence: &str) -> MentionKind {
+    static CUES: OnceLock<[Regex; 2]> = OnceLock::new();
+    let [achieved, abandoned] = CUES.get_or_init(|| {
+        [
+            Regex::new(r"(?i)\b(finished|completed|achieved|accomplished|did it|made it|crossed the finish|reached|succeeded)\b").expect("valid cue pattern"),
+            Regex::new(r"(?i)\b(gave up|give up|giving up|quit|quitting|stopped|no longer|not going to|abandon(ed)?|dropped)\b").expect("valid cue pattern"),
+        ]
+    });
+    if abandoned.is_match(sentence) {
+        MentionKind::Abandoned
+    } else if achieved.is_match(sentence) {
+        MentionKind::Achieved
+    } else {
+        MentionKind::Progress
+    }
+}
+
+// Rule-based pass over one entry
+pub fn extract_rules(text: &str, open: &[OpenGoal]) -> GoalExtraction {
+    static STATED: OnceLock<Regex> = OnceLock::new();
+    let stated_pattern = STATED.get_or_init(|| {
+        Regex::new(
+            r"(?i)\b(?:i (?:really |also |still )?want to|i'd (?:really |also )?like to|i would (?:really |also )?like to|i plan to|i'm planning to|i am planning to|my goal is to|one of my goals is to|i hope to|i've decided to|i have decided to|i resolve to|my resolution is to|i'm determined to|i am determined to|this year i will|i'm going to try to)\s+(.+)",
+        )
+        .expect("valid goal pattern")
+    });
+    let mut extraction = GoalExtraction::default();
+    for sentence in sentences(text) {
+        if let Some(c) = stated_pattern.captures(sentence) {
+            // The goal itself, without the reasons or plans that follow it
+            let goal = c[1].split([',', ';', ':']).next().unwrap_or_default();
+            let goal = [" because ", " but ", " so that ", " so ", " and then "].iter().fold(goal, |g, cut| g.split(cut).next().unwrap_or(g));
+            let goal = goal.trim();
+            if goal.split_whitespace().count() >= 2 && !extraction.stated.iter().any(|(d, _)| normalize(d) == normalize(goal)) {
+                extraction.stated.push((goal.to_string(), excerpt(sentence)));
+            }
+            continue;
+        }
+        for goal in open {
+            if mentions(sentence, &goal.description) && !extraction.updates.iter().any(|u| u.goal_id == goal.id) {
+                extraction.updates.push(GoalUpdate { goal_id: goal.id, kind: cue(sentence), excerpt: excerpt(sentence) });
+            }
+        }
+    }
+    extraction
+}
+
+// Reads {"new_goals": [{"goal", "quote"}], "updates": [{"id", "status", "quote"}]}, tolerating
+// text around the JSON; updates to goals that weren't offered are dropped
+fn parse_ai_response(response: &str, open: &[OpenGoal]) -> Result<GoalExtraction> {
+    let start = response.find('{').ok_or_else(|| anyhow::anyhow!("No JSON object in response"))?;
+    let end = response.rfind('}').ok_or_else(|| anyhow::anyhow!("No JSON object in response"))?;
+    let json: serde_json::Value = serde_json::from_str(&response[start..=end])?;
+    let mut extraction = GoalExtraction::default();
+    for goal in json["new_goals"].as_array().into_iter().flatten() {
+        let description = normalize(goal["goal"].as_str().unwrap_or_default());
+        if description.is_empty() || extraction.stated.iter().any(|(d, _)| *d == description) {
+            continue;
+        }
+        extraction.stated.push((description, excerpt(goal["quote"].as_str().unwrap_or_default())));
+    }
+    for update in json["updates"].as_array().into_iter().flatten() {
+        let (Some(id), Some(kind)) = (update["id"].as_i64(), update["status"].as_str().and_then(MentionKind::parse)) else { continue };
+        if kind == MentionKind::Stated || !open.iter().any(|g| g.id == id) || extraction.updates.iter().any(|u| u.goal_id == id) {
+            continue;
+        }
+        extraction.updates.push(GoalUpdate { goal_id: id, kind, excerpt: excerpt(update["quote"].as_str().unwrap_or_default()) });
+    }
+    Ok(extraction)
+}
+
+async fn extract_ai(settings: &AiSettings, text: &str, open: &[OpenGoal]) -> Result<GoalExtraction> {
+    let goals: Vec<String> = open.iter().map(|g| format!("{}: {}", g.id, g.description)).collect();
+    let prompt = format!(
+        "Read this journal entry. List goals the writer states for themselves (things they want to achieve, not passing wishes), and which of their earlier goals below the entry mentions, with status \"progress\", \"achieved\" or \"abandoned\". Reply with only JSON of the form {{\"new_goals\": [{{\"goal\": \"run a marathon\", \"quote\": \"sentence from the entry\"}}], \"updates\": [{{\"id\": 1, \"status\": \"progress\", \"quote\": \"sentence from the entry\"}}]}}.\n\nEarlier goals:\n{}\n\nEntry:\n{}",
+        if goals.is_empty() { "(none)".to_string() } else { goals.join("\n") },
+        text
+    );
+    let request = ChatRequest {
+        messages: vec![ChatMessage { role: "user".to_string(), content: prompt }],
+        model: String::new(),
+        provider: None,
+    };
+    let response = crate::ai::chat_completion(settings, request).await?;
+    parse_ai_response(&response, open)
+}
+
+// Goals stated before `before` (an entry date) that weren't achieved by then
+pub(crate) fn open_goals(conn: &Connection, before: &str, limit: i64) -> Result<Vec<OpenGoal>> {
+    let mut stmt = conn.prepare_cached(
+        r#"SELECT g.id, g.description FROM goals g
+            WHERE EXISTS (
+                SELECT 1 FROM goal_mentions m JOIN entries e ON e.id = m.entry_id
+                WHERE m.goal_id = g.id AND m.kind = 'stated' AND e.entry_date < ?1
+            )
+            AND IFNULL((
+                SELECT m.kind FROM goal_mentions m JOIN entries e ON e.id = m.entry_id
+                WHERE m.goal_id = g.id AND e.entry_date < ?1 ORDER BY e.entry_date DESC LIMIT 1
+            ), '') != 'achieved'
+            ORDER BY g.id DESC
+            LIMIT ?2"#,
+    )?;
+    let rows = stmt.query_map(params![before, limit], |r| Ok(OpenGoal { id: r.get(0)?, description: r.get(1)? }))?;
+    Ok(rows.collect::<rusqlite::Result<_>>()?)
+}
+
+// Replaces what is stored for one entry
+pub(crate) fn store(conn: &mut Connection, entry_id: &str, text_hash: &str, method: &str, extraction: &GoalExtraction) -> Result<()> {
+    let tx = conn.transaction()?;
+    tx.execute("DELETE FROM goal_mentions WHERE entry_id = ?1", params![entry_id])?;
+    {
+        let mut upsert = tx.prepare_cached(
+            "INSERT INTO goals (description, normalized, created_at) VALUES (?1, ?2, ?3) ON CONFLICT(normalized) DO NOTHING",
+        )?;
+        let mut lookup = tx.prepare_cached("SELECT id FROM goals WHERE normalized = ?1")?;
+        let mut mention = tx.prepare_cached(
+            "INSERT OR IGNORE INTO goal_mentions (goal_id, entry_id, kind, excerpt) VALUES (?1, ?2, ?3, ?4)",
+        )?;
+        for (description, excerpt) in &extraction.stated {
+            let normalized = normalize(description);
+            upsert.execute(params![description, normalized, Utc::now().to_rfc3339()])?;
+            let id: i64 = lookup.query_row(params![normalized], |r| r.get(0))?;
+            mention.execute(params![id, entry_id, MentionKind::Stated.as_str(), excerpt])?;
+        }
+        for update in &extraction.updates {
+            mention.execute(params![update.goal_id, entry_id, update.kind.as_str(), update.excerpt])?;
+        }
+    }
+    tx.execute(
+        r#"INSERT INTO goal_extractions (entry_id, text_hash, method, extracted_at) VALUES (?1, ?2, ?3, ?4)
+            ON CONFLICT(entry_id) DO UPDATE SET text_hash = excluded.text_hash, method = excluded.method,
+                extracted_at = excluded.extracted_at"#,
+        params![entry_id, text_hash, method, Utc::now().to_rfc3339()],
+    )?;
+    tx.commit()?;
+    Ok(())
+}
+
+// Entries never read, or edited since, oldest first
+fn pending(conn: &Connection, limit: i64) -> Result<Vec<(String, String, String, String)>> {
+    let mut stmt = conn.prepare_cached(
+        r#"SELECT e.id, COALESCE(e.title || char(10), '') || e.body, e.text_hash, e.entry_date FROM entries e
+            LEFT JOIN goal_extractions x ON x.entry_id = e.id
+            WHERE e.sensitive = 0 AND (x.entry_id IS NULL OR x.text_hash != e.text_hash)
+            ORDER BY e.entry_date
+            LIMIT ?1"#,
+    )?;
+    let rows = stmt.query_map(params![limit], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?)))?;
+    Ok(rows.collect::<rusqlite::Result<_>>()?)
+}
+
+// Goals no entry states any more
+fn remove_unstated(conn: &Connection) -> Result<()> {
+    conn.execute("DELETE FROM goals WHERE id NOT IN (SELECT goal_id FROM goal_mentions WHERE kind = 'stated')", [])?;
+    Ok(())
+}
+
+fn status(timeline: &[GoalEvent], newest_entry: Option<DateTime<Utc>>) -> &'static str {
+    match timeline.iter().rev().find(|e| e.kind != MentionKind::Stated).map(|e| e.kind) {
+        Some(MentionKind::Achieved) => return "achieved",
+        Some(MentionKind::Abandoned) => return "abandoned",
+        _ => {}
+    }
+    let last = timeline.last().and_then(|e| DateTime::parse_from_rfc3339(&e.entry_date).ok());
+    match (last, newest_entry) {
+        (Some(last), Some(newest)) if (newest - last.with_timezone(&Utc)).num_days() > DORMANT_DAYS => "dormant",
+        _ => "active",
+    }
+}
+
+// Most recently mentioned first; `status` keeps only goals in that state
+pub(crate) fn list(conn: &Connection, status_filter: Option<&str>) -> Result<Vec<Goal>> {
+    let newest: Option<String> = conn.query_row("SELECT MAX(entry_date) FROM entries", [], |r| r.get(0))?;
+    let newest = newest.and_then(|d| DateTime::parse_from_rfc3339(&d).ok()).map(|d| d.with_timezone(&Utc));
+    let mut stmt = conn.prepare_cached(
+        r#"SELECT g.id, g.description, m.entry_id, e.entry_date, m.kind, m.excerpt
+            FROM goals g
+            JOIN goal_mentions m ON m.goal_id = g.id
+            JOIN entries e ON e.id = m.entry_id
+            ORDER BY g.id, e.entry_date"#,
+    )?;
+    let rows = stmt.query_map([], |r| {
+        Ok((r.get::<_, i64>(0)?, r.get::<_, String>(1)?, r.get::<_, String>(2)?, r.get::<_, String>(3)?, r.get::<_, String>(4)?, r.get::<_, String>(5)?))
+    })?;
+    let mut grouped: Vec<(i64, String, Vec<GoalEvent>)> = Vec::new();
+    for row in rows {
+        let (id, description, entry_id, entry_date, kind, excerpt) = row?;
+        let Some(kind) = MentionKind::parse(&kind) else { continue };
+        if grouped.last().is_none_or(|(last, _, _)| *last != id) {
+            grouped.push((id, description, Vec::new()));
+        }
+        if let Some((_, _, timeline)) = grouped.last_mut() {
+            timeline.push(GoalEvent { entry_id, entry_date, kind, excerpt });
+        }
+    }
+    let mut goals: Vec<Goal> = grouped
+        .into_iter()
+        .filter_map(|(id, description, timeline)| {
+            let stated = timeline.iter().find(|e| e.kind == MentionKind::Stated)?;
+            Some(Goal {
+                id,
+                description,
+                status: status(&timeline, newest).to_string(),
+                stated_at: stated.entry_date.clone(),
+                source_entry_id: stated.entry_id.clone(),
+                last_mentioned_at: timeline.last().map(|e| e.entry_date.clone()).unwrap_or_default(),
+                timeline,
+            })
+        })
+        .filter(|g| status_filter.is_none_or(|s| g.status == s))
+        .collect();
+    goals.sort_by(|a, b| b.last_mentioned_at.cmp(&a.last_mentioned_at));
+    Ok(goals)
+}
+
+// Reads every new or edited entry for goals; `force` reads them all again
+pub async fn track_pending(app_handle: &AppHandle, force: bool) -> Result<GoalReport> {
+    let settings = {
+        let conn = crate::database::open_conn(app_handle)?;
+        if force {
+            conn.execute("DELETE FROM goal_extractions", [])?;
+        }
+        let use_ai = crate::database::setting_enabled(&conn, "goal_tracking_ai", false);
+        use_ai.then(|| AiSettings::load(&conn))
+    };
+    let method = if settings.is_some() { "ai" } else { "rules" };
+
+    let mut report = GoalReport { processed: 0, goals_found: 0, updates: 0, method: method.to_string() };
+    loop {
+        let batch = {
+            let conn = crate::database::open_conn(app_handle)?;
+            pending(&conn, BATCH_SIZE)?
+        };
+        if batch.is_empty() {
+            break;
+        }
+        for (entry_id, text, text_hash, entry_date) in batch {
+            let open = {
+                let conn = crate::database::open_conn(app_handle)?;
+                open_goals(&conn, &entry_date, if settings.is_some() { MAX_PROMPT_GOALS } else { i64::MAX })?
+            };
+            let (extraction, used) = match &settings {
+                Some(settings) => match crate::ai_audit::with_context("goal tracking", vec![entry_id.clone()], extract_ai(settings, &text, &open)).await {
+                    Ok(extraction) => (extraction, "ai"),
+                    Err(e) => {
+                        tracing::warn!("AI extraction failed for {}, using rules: {}", entry_id, e);
+                        (extract_rules(&text, &open), "rules")
+                    }
+                },
+                None => (extract_rules(&text, &open), "rules"),
+            };
+            let mut conn = crate::database::open_conn(app_handle)?;
+            store(&mut conn, &entry_id, &text_hash, used, &extraction)?;
+            report.processed += 1;
+            report.goals_found += extraction.stated.len() as u32;
+            report.updates += extraction.updates.len() as u32;
+        }
+    }
+
+    remove_unstated(&*crate::database::open_conn(app_handle)?)?;
+    tracing::info!("processed={} stated={} updates={} method={}", report.processed, report.goals_found, report.updates, method);
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn insert(conn: &Connection, id: &str, date: &str, body: &str) {
+        conn.execute(
+            r#"INSERT INTO entries (id, body, entry_date, entry_timezone, source_path, source_type, text_hash, created_at, updated_at)
+               VALUES (?1, ?2, ?3, 'UTC', '', 'txt', ?1, ?3, ?3)"#,
+            params![id, body, date],
+        )
+        .unwrap();
+    }
+
+    // The rule pass over entries in date order, as track_pending does
+    fn track(conn: &mut Connection, entries: &[(&str, &str, &str)]) {
+        for (id, date, body) in entries {
+            insert(conn, id, date, body);
+            let open = open_goals(conn, date, i64::MAX).unwrap();
+            let extraction = extract_rules(body, &open);
+            store(conn, id, id, "rules", &extraction).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_extract_rules() {
+        let text = "Long day. I really want to run a marathon, because my brother did. I'd like to sleep.";
+        let extraction = extract_rules(text, &[]);
+        assert_eq!(extraction.stated.iter().map(|(d, _)| d.as_str()).collect::<Vec<_>>(), ["run a marathon"]);
+        assert_eq!(extraction.stated[0].1, "I really want to run a marathon, because my brother did");
+
+        let open = [OpenGoal { id: 1, description: "run a marathon".into() }, OpenGoal { id: 2, description: "learn Spanish".into() }];
+        let extraction = extract_rules("Went running, 10k today. Marathon training is going well.\nGave up on learning Spanish.", &open);
+        assert_eq!(extraction.updates.len(), 2);
+        assert_eq!((extraction.updates[0].goal_id, extraction.updates[0].kind), (1, MentionKind::Progress));
+        assert_eq!(extraction.updates[0].excerpt, "Marathon training is going well");
+        assert_eq!((extraction.updates[1].goal_id, extraction.updates[1].kind), (2, MentionKind::Abandoned));
+    }
+
+    #[test]
+    fn test_goal_timeline() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::migrations::run_migrations(&mut conn).unwrap();
+        track(
+            &mut conn,
+            &[
+                ("a", "2023-01-01T12:00:00+00:00", "New year. My goal is to run a marathon. I also want to learn Spanish."),
+                ("b", "2023-03-01T12:00:00+00:00", "Ran 15k, the longest run yet for the marathon."),
+                ("c", "2023-10-08T12:00:00+00:00", "I finished the marathon in 4:12!"),
+                ("d", "2023-11-01T12:00:00+00:00", "Marathon run photos arrived."),
+                ("e", "2024-09-01T12:00:00+00:00", "Quiet week."),
+            ],
+        );
+        let goals = list(&conn, None).unwrap();
+        assert_eq!(goals.len(), 2);
+        let marathon = goals.iter().find(|g| g.description == "run a marathon").unwrap();
+        assert_eq!(marathon.status, "achieved");
+        assert_eq!(marathon.source_entry_id, "a");
+        // Nothing is looked for after the goal was achieved
+        assert_eq!(marathon.timeline.iter().map(|e| (e.entry_id.as_str(), e.kind)).collect::<Vec<_>>(), [
+            ("a", MentionKind::Stated),
+            ("b", MentionKind::Progress),
+            ("c", MentionKind::Achieved),
+        ]);
+        let spanish = goals.iter().find(|g| g.description == "learn Spanish").unwrap();
+        assert_eq!(spanish.status, "dormant");
+        assert_eq!(list(&conn, Some("dormant")).unwrap().len(), 1);
+
+        // Rewriting the entry that stated a goal removes the goal
+        store(&mut conn, "a", "a2", "rules", &extract_rules("New year. My goal is to run a marathon.", &[])).unwrap();
+        remove_unstated(&conn).unwrap();
+        assert_eq!(list(&conn, None).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_parse_ai_response() {
+        let open = [OpenGoal { id: 3, description: "write a novel".into() }];
+        let response = r#"Sure: {"new_goals": [{"goal": "Visit  Japan", "quote": "I want to see Japan"}], "updates": [{"id": 3, "status": "achieved", "quote": "The draft is done"}, {"id": 9, "status": "progress"}]}"#;
+        let extraction = parse_ai_response(response, &open).unwrap();
+        assert_eq!(extraction.stated, [("visit japan".to_string(), "I want to see Japan".to_string())]);
+        assert_eq!(extraction.updates, [GoalUpdate { goal_id: 3, kind: MentionKind::Achieved, excerpt: "The draft is done".into() }]);
+    }
+}
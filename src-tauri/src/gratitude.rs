@@ -0,0 +1,284 @@
+use std::sync::OnceLock;
+
+use anyhow::Result;
+use chrono::Utc;
+use regex::Regex;
+use rusqlite::{params, Connection};
+use serde::Serialize;
+
+// A gratitude journal drawn from entries: sentences that give thanks ("I'm grateful for...",
+// "thankful that...") and ones that name the day's best moment ("the best part of the day
+// was...", "...made my day"). Each is kept with the entry it came from, so browsing and the
+// Markdown export cite their source. A list under a heading like "Three things I'm thankful
+// for:" gives one item per line. Edited entries are read again, like entity extraction;
+// sensitive entries are never read.
+
+const BATCH_SIZE: i64 = 500;
+const MAX_ITEM_CHARS: usize = 400;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GratitudeItem {
+    pub id: i64,
+    pub entry_id: String,
+    pub entry_date: String,
+    pub entry_title: Option<String>,
+    // "gratitude" or "highlight"
+    pub kind: String,
+    pub text: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GratitudeReport {
+    pub processed: u32,
+    pub items: u32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GratitudeExportReport {
+    pub path: String,
+    pub items: u32,
+}
+
+fn gratitude_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r"(?i)\b(?:grateful|thankful|gratitude|blessed (?:to|with)|lucky to have|thank (?:god|goodness) for|i (?:really |truly )?appreciated?)\b")
+            .expect("valid regex")
+    })
+}
+
+// "not grateful", "wasn't thankful at all", "never appreciated"
+fn negated_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(?i)(?:\bnot|\bnever|n't|\bhardly)\s+(?:\w+\s+)?(?:grateful|thankful|appreciat)").expect("valid regex"))
+}
+
+fn highlight_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(
+            r"(?i)\b(?:(?:best|favou?rite|happiest|nicest) (?:part|moment|thing|bit)s? (?:of|about) (?:the|my|this|today|tonight|yesterday)|highlights? of (?:the|my|this|today)|(?:today|tonight)'s highlight|made my (?:day|week|morning|evening)|high point of)\b",
+        )
+        .expect("valid regex")
+    })
+}
+
+// "- ", "* ", "• ", "1. ", "2) "
+fn list_item_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"^\s*(?:[-*•]|\d{1,2}[.)])\s+(.+)$").expect("valid regex"))
+}
+
+fn kind_of(text: &str) -> Option<&'static str> {
+    if gratitude_re().is_match(text) && !negated_re().is_match(text) {
+        Some("gratitude")
+    } else if highlight_re().is_match(text) {
+        Some("highlight")
+    } else {
+        None
+    }
+}
+
+fn clip(text: &str) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    match text.char_indices().nth(MAX_ITEM_CHARS) {
+        Some((cut, _)) => format!("{}…", text[..cut].trim_end()),
+        None => text,
+    }
+}
+
+// (kind, text) in the order they appear, without repeats
+pub fn extract(text: &str) -> Vec<(&'static str, String)> {
+    static SENTENCE: OnceLock<Regex> = OnceLock::new();
+    let sentence = SENTENCE.get_or_init(|| Regex::new(r"[^.!?]+[.!?]*").expect("valid regex"));
+    let mut items: Vec<(&'static str, String)> = Vec::new();
+    let mut push = |kind: &'static str, text: &str| {
+        let text = clip(text);
+        if !text.is_empty() && !items.iter().any(|(_, t)| *t == text) {
+            items.push((kind, text));
+        }
+    };
+    // The kind of the list heading the following lines sit under
+    let mut list: Option<&'static str> = None;
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if let (Some(kind), Some(item)) = (list, list_item_re().captures(line)) {
+            push(kind, &item[1]);
+            continue;
+        }
+        list = None;
+        if trimmed.ends_with(':') {
+            if let Some(kind) = kind_of(trimmed) {
+                list = Some(kind);
+                continue;
+            }
+        }
+        for m in sentence.find_iter(trimmed) {
+            let s = m.as_str().trim();
+            let s = list_item_re().captures(s).and_then(|c| c.get(1)).map_or(s, |m| m.as_str());
+            if let Some(kind) = kind_of(s) {
+                push(kind, s);
+            }
+        }
+    }
+    items
+}
+
+// Replaces what is stored for one entry
+pub(crate) fn store(conn: &mut Connection, entry_id: &str, text_hash: &str, items: &[(&str, String)]) -> Result<()> {
+    let tx = conn.transaction()?;
+    tx.execute("DELETE FROM gratitude_items WHERE entry_id = ?1", params![entry_id])?;
+    {
+        let mut insert = tx.prepare_cached("INSERT INTO gratitude_items (entry_id, kind, text, position) VALUES (?1, ?2, ?3, ?4)")?;
+        for (position, (kind, text)) in items.iter().enumerate() {
+            insert.execute(params![entry_id, kind, text, position as i64])?;
+        }
+    }
+    tx.execute(
+        r#"INSERT INTO gratitude_extractions (entry_id, text_hash, extracted_at) VALUES (?1, ?2, ?3)
+            ON CONFLICT(entry_id) DO UPDATE SET text_hash = excluded.text_hash, extracted_at = excluded.extracted_at"#,
+        params![entry_id, text_hash, Utc::now().to_rfc3339()],
+    )?;
+    tx.commit()?;
+    Ok(())
+}
+
+// Entries never read, or edited since
+fn pending(conn: &Connection, limit: i64) -> Result<Vec<(String, String, String)>> {
+    let mut stmt = conn.prepare_cached(
+        r#"SELECT e.id, e.body, e.text_hash FROM entries e
+            LEFT JOIN gratitude_extractions x ON x.entry_id = e.id
+            WHERE e.sensitive = 0 AND (x.entry_id IS NULL OR x.text_hash != e.text_hash)
+            LIMIT ?1"#,
+    )?;
+    let rows = stmt.query_map(params![limit], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)))?;
+    Ok(rows.collect::<rusqlite::Result<_>>()?)
+}
+
+// Reads every new or edited entry; `force` reads them all again
+pub fn extract_pending(conn: &mut Connection, force: bool) -> Result<GratitudeReport> {
+    if force {
+        conn.execute("DELETE FROM gratitude_extractions", [])?;
+    }
+    let mut report = GratitudeReport { processed: 0, items: 0 };
+    loop {
+        let batch = pending(conn, BATCH_SIZE)?;
+        if batch.is_empty() {
+            break;
+        }
+        for (entry_id, body, text_hash) in batch {
+            let items = extract(&body);
+            store(conn, &entry_id, &text_hash, &items)?;
+            report.processed += 1;
+            report.items += items.len() as u32;
+        }
+    }
+    if report.processed > 0 {
+        tracing::info!("processed={} items={}", report.processed, report.items);
+    }
+    Ok(report)
+}
+
+// Newest entries first; `kind` keeps one kind, `date_from`/`date_to` are inclusive YYYY-MM-DD days
+pub fn list(conn: &Connection, kind: Option<&str>, date_from: Option<&str>, date_to: Option<&str>, limit: u32, offset: u32) -> Result<Vec<GratitudeItem>> {
+    let mut stmt = conn.prepare_cached(
+        r#"SELECT g.id, g.entry_id, e.entry_date, e.title, g.kind, g.text
+            FROM gratitude_items g
+            JOIN entries e ON e.id = g.entry_id
+            WHERE (?1 IS NULL OR g.kind = ?1)
+              AND (?2 IS NULL OR substr(e.entry_date, 1, 10) >= ?2)
+              AND (?3 IS NULL OR substr(e.entry_date, 1, 10) <= ?3)
+            ORDER BY e.entry_date DESC, g.entry_id, g.position
+            LIMIT ?4 OFFSET ?5"#,
+    )?;
+    let rows = stmt.query_map(params![kind, date_from, date_to, limit, offset], |r| {
+        Ok(GratitudeItem { id: r.get(0)?, entry_id: r.get(1)?, entry_date: r.get(2)?, entry_title: r.get(3)?, kind: r.get(4)?, text: r.get(5)? })
+    })?;
+    Ok(rows.collect::<rusqlite::Result<_>>()?)
+}
+
+// Oldest first, one section per entry citing its date, title and id
+pub fn markdown(items: &[GratitudeItem]) -> String {
+    let mut out = String::from("# Gratitude journal\n");
+    for (i, item) in items.iter().enumerate() {
+        if i == 0 || items[i - 1].entry_id != item.entry_id {
+            let date = item.entry_date.get(..10).unwrap_or(&item.entry_date);
+            match item.entry_title.as_deref().map(str::trim).filter(|t| !t.is_empty() && *t != date) {
+                Some(title) => out.push_str(&format!("\n## {} — {}\n\n", date, title)),
+                None => out.push_str(&format!("\n## {}\n\n", date)),
+            }
+        }
+        match item.kind.as_str() {
+            "highlight" => out.push_str(&format!("- **Highlight:** {}\n", item.text)),
+            _ => out.push_str(&format!("- {}\n", item.text)),
+        }
+        if items.get(i + 1).is_none_or(|next| next.entry_id != item.entry_id) {
+            out.push_str(&format!("\n_Entry {}_\n", item.entry_id));
+        }
+    }
+    out
+}
+
+pub fn export(conn: &Connection, dest: &str, kind: Option<&str>, date_from: Option<&str>, date_to: Option<&str>) -> Result<GratitudeExportReport> {
+    let mut items = list(conn, kind, date_from, date_to, u32::MAX, 0)?;
+    // Back to date order, keeping each entry's items in the order written
+    items.sort_by(|a, b| a.entry_date.cmp(&b.entry_date).then(a.entry_id.cmp(&b.entry_id)).then(a.id.cmp(&b.id)));
+    std::fs::write(dest, markdown(&items))?;
+    Ok(GratitudeExportReport { path: dest.to_string(), items: items.len() as u32 })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract() {
+        let text = "Long day at work. I'm so grateful for Sam's help with the move! I wasn't thankful for the rain.\n\
+                    The best part of the day was the walk home. Dinner was fine.\n\
+                    Three things I'm thankful for:\n- coffee\n2) an early night\n\nDinner with Jo made my day.";
+        assert_eq!(
+            extract(text),
+            [
+                ("gratitude", "I'm so grateful for Sam's help with the move!".to_string()),
+                ("highlight", "The best part of the day was the walk home.".to_string()),
+                ("gratitude", "coffee".to_string()),
+                ("gratitude", "an early night".to_string()),
+                ("highlight", "Dinner with Jo made my day.".to_string()),
+            ]
+        );
+        assert!(extract("An ungrateful cat. Nothing to report.").is_empty());
+    }
+
+    #[test]
+    fn test_store_list_and_markdown() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::migrations::run_migrations(&mut conn).unwrap();
+        conn.execute_batch(
+            r#"INSERT INTO entries (id, title, body, entry_date, entry_timezone, source_path, source_type, text_hash, created_at, updated_at)
+               VALUES ('a', 'Move', 'Thankful for friends.', '2021-03-05T20:00:00+00:00', 'UTC', '', 'txt', 'ha', '', ''),
+                      ('b', NULL, 'Highlight of the day: pancakes.', '2021-03-06T20:00:00+00:00', 'UTC', '', 'txt', 'hb', '', ''),
+                      ('c', NULL, 'Grateful for quiet.', '2021-03-07T20:00:00+00:00', 'UTC', '', 'txt', 'hc', '', '');
+               UPDATE entries SET sensitive = 1 WHERE id = 'c';"#,
+        )
+        .unwrap();
+        let report = extract_pending(&mut conn, false).unwrap();
+        assert_eq!((report.processed, report.items), (2, 2));
+        assert_eq!(extract_pending(&mut conn, false).unwrap().processed, 0);
+
+        let items = list(&conn, None, None, None, 10, 0).unwrap();
+        assert_eq!(items.iter().map(|i| (i.entry_id.as_str(), i.kind.as_str())).collect::<Vec<_>>(), [("b", "highlight"), ("a", "gratitude")]);
+        assert_eq!(list(&conn, Some("gratitude"), Some("2021-03-06"), None, 10, 0).unwrap().len(), 0);
+
+        let mut ordered = items;
+        ordered.reverse();
+        assert_eq!(
+            markdown(&ordered),
+            "# Gratitude journal\n\n## 2021-03-05 — Move\n\n- Thankful for friends.\n\n_Entry a_\n\n## 2021-03-06\n\n- **Highlight:** Highlight of the day: pancakes.\n\n_Entry b_\n"
+        );
+
+        // An edit is read again
+        conn.execute("UPDATE entries SET body = 'Rain.', text_hash = 'ha2' WHERE id = 'a'", []).unwrap();
+        assert_eq!(extract_pending(&mut conn, false).unwrap().items, 0);
+        assert_eq!(list(&conn, None, None, None, 10, 0).unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,214 @@
+use serde::{Deserialize, Serialize};
+
+// Match highlighting for search results.
+//
+// Matches are located either by FTS5's highlight(), which wraps each hit in the private-use
+// markers below (so stemmed and folded matches are found exactly as the index saw them), or
+// by scanning for query terms / regex matches in Rust. Either way the result is a handful of
+// fragments around the hits with char offsets of each match, which the UI can bold without
+// re-implementing the matching.
+
+pub const OPEN_MARK: char = '\u{E000}';
+pub const CLOSE_MARK: char = '\u{E001}';
+
+const ELLIPSIS: &str = "...";
+pub const DEFAULT_FRAGMENTS: usize = 3;
+pub const DEFAULT_WINDOW: usize = 160;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SnippetFragment {
+    pub text: String,
+    // [start, end) char offsets into `text`
+    pub highlights: Vec<(usize, usize)>,
+}
+
+// Strip FTS5 highlight markers, returning the plain chars and the marked spans
+pub fn parse_marked(marked: &str) -> (Vec<char>, Vec<(usize, usize)>) {
+    let mut chars = Vec::with_capacity(marked.len());
+    let mut spans = Vec::new();
+    let mut open: Option<usize> = None;
+    for c in marked.chars() {
+        match c {
+            OPEN_MARK => open = Some(chars.len()),
+            CLOSE_MARK => {
+                if let Some(start) = open.take() {
+                    if chars.len() > start {
+                        spans.push((start, chars.len()));
+                    }
+                }
+            }
+            _ => chars.push(c),
+        }
+    }
+    (chars, spans)
+}
+
+fn merge_spans(mut spans: Vec<(usize, usize)>) -> Vec<(usize, usize)> {
+    spans.sort();
+    let mut merged: Vec<(usize, usize)> = Vec::new();
+    for (s, e) in spans {
+        match merged.last_mut() {
+            Some(last) if s <= last.1 => last.1 = last.1.max(e),
+            _ => merged.push((s, e)),
+        }
+    }
+    merged
+}
+
+// Case-insensitive occurrences of any of `terms` in `chars`
+pub fn term_spans(chars: &[char], terms: &[String]) -> Vec<(usize, usize)> {
+    let fold = |c: char| c.to_lowercase().next().unwrap_or(c);
+    let lower: Vec<char> = chars.iter().map(|&c| fold(c)).collect();
+    let mut spans = Vec::new();
+    for term in terms {
+        let needle: Vec<char> = term.trim().chars().map(fold).collect();
+        if needle.is_empty() || needle.len() > lower.len() {
+            continue;
+        }
+        let mut i = 0;
+        while i + needle.len() <= lower.len() {
+            if lower[i..i + needle.len()] == needle[..] {
+                spans.push((i, i + needle.len()));
+                i += needle.len();
+            } else {
+                i += 1;
+            }
+        }
+    }
+    merge_spans(spans)
+}
+
+// Regex matches in `text`, converted from byte to char offsets
+pub fn regex_spans(text: &str, re: &regex::Regex) -> Vec<(usize, usize)> {
+    let char_starts: Vec<usize> = text.char_indices().map(|(b, _)| b).collect();
+    let to_char = |byte: usize| char_starts.partition_point(|&b| b < byte);
+    let spans = re
+        .find_iter(text)
+        .filter(|m| m.start() < m.end())
+        .map(|m| (to_char(m.start()), to_char(m.end())))
+        .collect();
+    merge_spans(spans)
+}
+
+// Up to `max_fragments` windows of about `window` chars around the spans, in text order.
+// With no spans the start of the text is returned as a single fragment.
+pub fn build_fragments(chars: &[char], spans: &[(usize, usize)], max_fragments: usize, window: usize) -> Vec<SnippetFragment> {
+    let make = |start: usize, end: usize, inside: &[(usize, usize)]| {
+        let lead = if start > 0 { ELLIPSIS } else { "" };
+        let trail = if end < chars.len() { ELLIPSIS } else { "" };
+        let shift = lead.chars().count();
+        let body: String = chars[start..end].iter().collect();
+        SnippetFragment {
+            text: format!("{}{}{}", lead, body, trail),
+            highlights: inside
+                .iter()
+                .map(|&(s, e)| (s.max(start) - start + shift, e.min(end) - start + shift))
+                .collect(),
+        }
+    };
+
+    if spans.is_empty() {
+        let end = window.min(chars.len());
+        return vec![make(0, end, &[])];
+    }
+
+    let mut fragments = Vec::new();
+    let mut covered_until = 0usize;
+    let mut i = 0;
+    while i < spans.len() && fragments.len() < max_fragments {
+        let (s, e) = spans[i];
+        if s < covered_until {
+            i += 1;
+            continue;
+        }
+        let width = window.max(e - s);
+        let lead = (width - (e - s)) / 2;
+        let mut start = s.saturating_sub(lead).max(covered_until);
+        let end = (start + width).min(chars.len());
+        start = end.saturating_sub(width).max(covered_until);
+
+        let inside: Vec<(usize, usize)> = spans[i..]
+            .iter()
+            .take_while(|&&(ss, _)| ss < end)
+            .copied()
+            .collect();
+        i += inside.len();
+        fragments.push(make(start, end, &inside));
+        covered_until = end;
+    }
+    fragments
+}
+
+pub fn fragments_from_marked(marked: &str) -> Vec<SnippetFragment> {
+    let (chars, spans) = parse_marked(marked);
+    build_fragments(&chars, &spans, DEFAULT_FRAGMENTS, DEFAULT_WINDOW)
+}
+
+pub fn fragments_for_terms(text: &str, terms: &[String]) -> Vec<SnippetFragment> {
+    let chars: Vec<char> = text.chars().collect();
+    let spans = term_spans(&chars, terms);
+    build_fragments(&chars, &spans, DEFAULT_FRAGMENTS, DEFAULT_WINDOW)
+}
+
+pub fn fragments_for_regex(text: &str, re: &regex::Regex) -> Vec<SnippetFragment> {
+    let chars: Vec<char> = text.chars().collect();
+    let spans = regex_spans(text, re);
+    build_fragments(&chars, &spans, DEFAULT_FRAGMENTS, DEFAULT_WINDOW)
+}
+
+// The fragments as one plain preview string
+pub fn plain_text(fragments: &[SnippetFragment]) -> String {
+    fragments.iter().map(|f| f.text.as_str()).collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn highlighted(f: &SnippetFragment) -> Vec<String> {
+        let chars: Vec<char> = f.text.chars().collect();
+        f.highlights.iter().map(|&(s, e)| chars[s..e].iter().collect()).collect()
+    }
+
+    #[test]
+    fn test_parse_marked() {
+        let marked = format!("a {}café{} and {}tea{}", OPEN_MARK, CLOSE_MARK, OPEN_MARK, CLOSE_MARK);
+        let (chars, spans) = parse_marked(&marked);
+        assert_eq!(chars.iter().collect::<String>(), "a café and tea");
+        assert_eq!(spans, vec![(2, 6), (11, 14)]);
+    }
+
+    #[test]
+    fn test_multiple_fragments_with_offsets() {
+        let text = format!("{} dream {} dream {}", "x".repeat(300), "y".repeat(300), "z".repeat(300));
+        let fragments = fragments_for_terms(&text, &["DREAM".to_string()]);
+        assert_eq!(fragments.len(), 2);
+        for f in &fragments {
+            assert!(f.text.starts_with(ELLIPSIS) && f.text.ends_with(ELLIPSIS));
+            assert_eq!(highlighted(f), vec!["dream"]);
+        }
+    }
+
+    #[test]
+    fn test_nearby_matches_share_a_fragment() {
+        let fragments = fragments_for_terms("Rain, then more rain.", &["rain".to_string()]);
+        assert_eq!(fragments.len(), 1);
+        assert_eq!(highlighted(&fragments[0]), vec!["Rain", "rain"]);
+        assert_eq!(fragments[0].text, "Rain, then more rain.");
+    }
+
+    #[test]
+    fn test_regex_spans_use_char_offsets() {
+        let re = regex::Regex::new(r"\d{3}-\d{4}").unwrap();
+        let fragments = fragments_for_regex("Zoë's number: 555-1234", &re);
+        assert_eq!(highlighted(&fragments[0]), vec!["555-1234"]);
+    }
+
+    #[test]
+    fn test_no_matches_returns_start() {
+        let fragments = fragments_for_terms("hello world", &["absent".to_string()]);
+        assert_eq!(fragments.len(), 1);
+        assert!(fragments[0].highlights.is_empty());
+        assert_eq!(plain_text(&fragments), "hello world");
+    }
+}
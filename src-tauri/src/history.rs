@@ -0,0 +1,195 @@
+use anyhow::Result;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+// Recent searches and search-box suggestions.
+//
+// Each distinct query is one row, bumped on reuse; the table is trimmed to the most recent
+// MAX_HISTORY queries. Suggestions combine matching past queries (most recent first) with
+// indexed words that start with the typed prefix (most common first). Recording can be
+// turned off with the `search_history_enabled` setting.
+
+const MAX_HISTORY: i64 = 200;
+// Vocabulary rows examined per prefix before ranking by frequency
+const VOCAB_SCAN_LIMIT: i64 = 500;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchHistoryItem {
+    pub query: String,
+    pub use_count: u32,
+    pub last_used_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct SearchSuggestion {
+    pub text: String,
+    pub source: String, // "history" or "term"
+}
+
+pub fn record_query(conn: &Connection, query: &str) -> Result<()> {
+    let query = query.trim();
+    if query.is_empty() || !crate::database::setting_enabled(conn, "search_history_enabled", true) {
+        return Ok(());
+    }
+    conn.execute(
+        r#"INSERT INTO search_history (query, use_count, last_used_at) VALUES (?1, 1, ?2)
+            ON CONFLICT(query) DO UPDATE SET use_count = use_count + 1, last_used_at = excluded.last_used_at"#,
+        params![query, chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Micros, true)],
+    )?;
+    conn.execute(
+        r#"DELETE FROM search_history WHERE query NOT IN (
+            SELECT query FROM search_history ORDER BY last_used_at DESC LIMIT ?1
+        )"#,
+        params![MAX_HISTORY],
+    )?;
+    Ok(())
+}
+
+// Best-effort: a failure to record history must never fail the search itself
+pub fn record_search(app_handle: &AppHandle, query: &str) {
+    let result = crate::database::open_conn(app_handle).and_then(|conn| record_query(&conn, query));
+    if let Err(e) = result {
+        tracing::warn!("failed to record search history: {}", e);
+    }
+}
+
+pub fn list_history(conn: &Connection, limit: u32) -> Result<Vec<SearchHistoryItem>> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT query, use_count, last_used_at FROM search_history ORDER BY last_used_at DESC LIMIT ?1",
+    )?;
+    let rows = stmt.query_map(params![limit as i64], |r| {
+        Ok(SearchHistoryItem { query: r.get(0)?, use_count: r.get(1)?, last_used_at: r.get(2)? })
+    })?;
+    Ok(rows.collect::<rusqlite::Result<_>>()?)
+}
+
+fn history_matches(conn: &Connection, prefix: &str, limit: u32) -> Result<Vec<String>> {
+    let pattern = format!("{}%", crate::database::escape_like(prefix));
+    let mut stmt = conn.prepare_cached(
+        r#"SELECT query FROM search_history WHERE query LIKE ?1 ESCAPE '\'
+            ORDER BY last_used_at DESC LIMIT ?2"#,
+    )?;
+    let rows = stmt.query_map(params![pattern, limit as i64], |r| r.get(0))?;
+    Ok(rows.collect::<rusqlite::Result<_>>()?)
+}
+
+fn term_matches(conn: &Connection, prefix: &str, limit: u32) -> Result<Vec<String>> {
+    // Trigram indexes store 3-character windows, not words
+    if crate::database::trigram_enabled(conn) {
+        return Ok(vec![]);
+    }
+    // With stemming on these are stems ("memori"), which still work as search terms
+    crate::fuzzy::ensure_vocab(conn)?;
+    let prefix = prefix.to_lowercase();
+    // fts5vocab serves term range constraints from the index, so this doesn't scan everything
+    let mut stmt = conn.prepare_cached(
+        r#"SELECT term, doc FROM temp.entries_fts_vocab
+            WHERE term >= ?1 AND term < ?1 || char(1114111)
+            LIMIT ?2"#,
+    )?;
+    let mut terms: Vec<(String, i64)> = stmt
+        .query_map(params![prefix, VOCAB_SCAN_LIMIT], |r| Ok((r.get(0)?, r.get(1)?)))?
+        .collect::<rusqlite::Result<_>>()?;
+    terms.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    Ok(terms.into_iter().take(limit as usize).map(|(t, _)| t).collect())
+}
+
+// Past queries first, then indexed words, without duplicates
+pub fn suggest(conn: &Connection, prefix: &str, limit: u32) -> Result<Vec<SearchSuggestion>> {
+    let prefix = prefix.trim_start();
+    if prefix.is_empty() {
+        return Ok(list_history(conn, limit)?
+            .into_iter()
+            .map(|h| SearchSuggestion { text: h.query, source: "history".into() })
+            .collect());
+    }
+
+    let mut out: Vec<SearchSuggestion> = history_matches(conn, prefix, limit)?
+        .into_iter()
+        .map(|text| SearchSuggestion { text, source: "history".into() })
+        .collect();
+
+    // Complete the last word being typed, keeping what came before it
+    let (head, last_word) = match prefix.rfind(char::is_whitespace) {
+        Some(i) => (&prefix[..=i], &prefix[i + 1..]),
+        None => ("", prefix),
+    };
+    if !last_word.is_empty() && out.len() < limit as usize {
+        for term in term_matches(conn, last_word, limit)? {
+            let text = format!("{}{}", head, term);
+            if out.len() >= limit as usize {
+                break;
+            }
+            if !out.iter().any(|s| s.text.eq_ignore_ascii_case(&text)) {
+                out.push(SearchSuggestion { text, source: "term".into() });
+            }
+        }
+    }
+    Ok(out)
+}
+
+pub async fn get_search_history(app_handle: &AppHandle, limit: u32) -> Result<Vec<SearchHistoryItem>> {
+    let conn = crate::database::open_conn(app_handle)?;
+    list_history(&conn, limit)
+}
+
+pub async fn clear_search_history(app_handle: &AppHandle) -> Result<()> {
+    let conn = crate::database::open_conn(app_handle)?;
+    conn.execute("DELETE FROM search_history", [])?;
+    Ok(())
+}
+
+pub async fn get_search_suggestions(app_handle: &AppHandle, prefix: &str, limit: u32) -> Result<Vec<SearchSuggestion>> {
+    let conn = crate::database::open_conn(app_handle)?;
+    suggest(&conn, prefix, limit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup() -> Connection {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::migrations::run_migrations(&mut conn).unwrap();
+        crate::database::create_fts_table(&conn).unwrap();
+        conn.execute_batch(
+            r#"
+            INSERT INTO entries_fts (title, body, entry_id) VALUES
+                ('', 'dreams and dreaming, a dream journal', '1'),
+                ('', 'another dream about the sea', '2');
+            "#,
+        )
+        .unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_history_is_deduplicated_and_recent_first() {
+        let conn = setup();
+        record_query(&conn, "travel").unwrap();
+        record_query(&conn, "dream journal").unwrap();
+        record_query(&conn, " travel ").unwrap();
+        let history = list_history(&conn, 10).unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].query, "travel");
+        assert_eq!(history[0].use_count, 2);
+
+        conn.execute("INSERT INTO settings (key, value) VALUES ('search_history_enabled', 'false')", []).unwrap();
+        record_query(&conn, "secret").unwrap();
+        assert_eq!(list_history(&conn, 10).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_suggestions_mix_history_and_terms() {
+        let conn = setup();
+        record_query(&conn, "dream journal").unwrap();
+        let suggestions = suggest(&conn, "dre", 5).unwrap();
+        assert_eq!(suggestions[0], SearchSuggestion { text: "dream journal".into(), source: "history".into() });
+        assert!(suggestions.iter().any(|s| s.source == "term" && s.text.starts_with("dream")));
+
+        // Only the last word is completed
+        let suggestions = suggest(&conn, "the se", 5).unwrap();
+        assert_eq!(suggestions, vec![SearchSuggestion { text: "the sea".into(), source: "term".into() }]);
+    }
+}
@@ -0,0 +1,183 @@
+use anyhow::Result;
+use rusqlite::Connection;
+use serde::Serialize;
+use std::time::Duration;
+use tauri::AppHandle;
+use tokio::io::AsyncWriteExt;
+
+// Event hooks for personal automation (Home Assistant, n8n, shell scripts).
+//
+// Each event is a JSON object `{event, sent_at, data}`. It is POSTed to `hook_webhook_url`
+// and/or handed to `hook_script`, which runs with the event name as its only argument (and in
+// JOURNAL_READER_EVENT) and the JSON on stdin. `hook_events` is a comma-separated list of the
+// events to send. Deliveries run in the background: a failing hook is logged and never fails
+// the action that triggered it.
+
+// entry-created: each entry saved by an import (entries restored from a JSON backup are only
+//   counted in that import's entry-imported)
+// entry-imported: once per import run that added entries (files, Google Drive, Keep, JSON backup)
+// backup-completed: a JSON backup was written
+pub const EVENTS: &[&str] = &["entry-created", "entry-imported", "backup-completed"];
+
+// Set on this device only: settings files, JSON backups and S3 snapshots neither carry nor
+// restore them, so importing someone else's file can't install a program to run or a URL that
+// receives entries
+pub const SETTINGS: &[&str] = &["hook_script", "hook_webhook_url", "hook_events"];
+
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+const SCRIPT_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Default)]
+struct HookConfig {
+    webhook_url: Option<String>,
+    script: Option<String>,
+    events: Vec<String>,
+}
+
+impl HookConfig {
+    fn load(conn: &Connection) -> Self {
+        let non_empty = |key: &str| crate::database::read_setting(conn, key).map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+        let events = match crate::database::read_setting(conn, "hook_events") {
+            Some(list) => list.split(',').map(|e| e.trim().to_lowercase()).filter(|e| !e.is_empty()).collect(),
+            None => EVENTS.iter().map(|e| e.to_string()).collect(),
+        };
+        HookConfig { webhook_url: non_empty("hook_webhook_url"), script: non_empty("hook_script"), events }
+    }
+
+    fn configured(&self) -> bool {
+        self.webhook_url.is_some() || self.script.is_some()
+    }
+
+    fn wants(&self, event: &str) -> bool {
+        self.configured() && self.events.iter().any(|e| e == event)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HookResult {
+    pub target: String,
+    pub ok: bool,
+    pub error: Option<String>,
+}
+
+fn payload(event: &str, data: serde_json::Value) -> serde_json::Value {
+    serde_json::json!({ "event": event, "sent_at": chrono::Utc::now().to_rfc3339(), "data": data })
+}
+
+async fn post_webhook(url: &str, body: &serde_json::Value) -> Result<()> {
+    let client = reqwest::Client::builder().timeout(WEBHOOK_TIMEOUT).build()?;
+    let resp = client.post(url).json(body).send().await?;
+    if !resp.status().is_success() {
+        anyhow::bail!("webhook returned {}", resp.status());
+    }
+    Ok(())
+}
+
+async fn run_script(path: &str, event: &str, body: &serde_json::Value) -> Result<()> {
+    let mut child = tokio::process::Command::new(path)
+        .arg(event)
+        .env("JOURNAL_READER_EVENT", event)
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // A script that ignores stdin may exit before reading it
+        let _ = stdin.write_all(body.to_string().as_bytes()).await;
+    }
+    let output = tokio::time::timeout(SCRIPT_TIMEOUT, child.wait_with_output())
+        .await
+        .map_err(|_| anyhow::anyhow!("script timed out after {}s", SCRIPT_TIMEOUT.as_secs()))??;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("script exited with {}: {}", output.status, stderr.trim());
+    }
+    Ok(())
+}
+
+async fn deliver(config: &HookConfig, event: &str, body: &serde_json::Value) -> Vec<HookResult> {
+    let mut results = Vec::new();
+    if let Some(url) = &config.webhook_url {
+        let outcome = post_webhook(url, body).await;
+        results.push(HookResult { target: url.clone(), ok: outcome.is_ok(), error: outcome.err().map(|e| e.to_string()) });
+    }
+    if let Some(script) = &config.script {
+        let outcome = run_script(script, event, body).await;
+        results.push(HookResult { target: script.clone(), ok: outcome.is_ok(), error: outcome.err().map(|e| e.to_string()) });
+    }
+    results
+}
+
+// Sends `event` in the background when a hook is configured for it
+pub fn emit(app_handle: &AppHandle, event: &str, data: serde_json::Value) {
+    let config = match crate::database::open_conn(app_handle) {
+        Ok(conn) => HookConfig::load(&conn),
+        Err(_) => return,
+    };
+    if !config.wants(event) {
+        return;
+    }
+    let event = event.to_string();
+    tauri::async_runtime::spawn(async move {
+        for result in deliver(&config, &event, &payload(&event, data)).await {
+            if let Some(error) = result.error {
+                tracing::warn!("{} -> {} failed: {}", event, result.target, error);
+            }
+        }
+    });
+}
+
+// Sends a `test` event to every configured hook and waits for the outcome
+pub async fn test(app_handle: &AppHandle) -> Result<Vec<HookResult>> {
+    let conn = crate::database::open_conn(app_handle)?;
+    let config = HookConfig::load(&conn);
+    drop(conn);
+    if !config.configured() {
+        anyhow::bail!("No webhook URL or script configured");
+    }
+    let data = serde_json::json!({ "message": "Test event from Journal Reader" });
+    Ok(deliver(&config, "test", &payload("test", data)).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hook_config() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE settings (key TEXT PRIMARY KEY, value TEXT NOT NULL);").unwrap();
+        let config = HookConfig::load(&conn);
+        assert!(!config.configured() && !config.wants("entry-created"));
+
+        conn.execute_batch(
+            "INSERT INTO settings VALUES ('hook_webhook_url', ' http://localhost:8123/api/webhook/journal '), ('hook_events', 'Entry-Created, backup-completed,');",
+        )
+        .unwrap();
+        let config = HookConfig::load(&conn);
+        assert_eq!(config.webhook_url.as_deref(), Some("http://localhost:8123/api/webhook/journal"));
+        assert!(config.wants("entry-created") && config.wants("backup-completed"));
+        assert!(!config.wants("entry-imported"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_script_hook_gets_event_and_payload() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = std::env::temp_dir().join(format!("jr-hooks-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let script = dir.join("hook.sh");
+        let out = dir.join("out.txt");
+        std::fs::write(&script, format!("#!/bin/sh\necho \"$1\" > '{}'\ncat >> '{}'\n", out.display(), out.display())).unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let config = HookConfig { script: Some(script.to_string_lossy().to_string()), ..Default::default() };
+        let results = deliver(&config, "entry-created", &payload("entry-created", serde_json::json!({ "id": "e1" }))).await;
+        assert!(results[0].ok, "{:?}", results[0].error);
+        let written = std::fs::read_to_string(&out).unwrap();
+        assert!(written.starts_with("entry-created\n{"));
+        assert!(written.contains("\"id\":\"e1\""));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,372 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tauri::AppHandle;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+
+use crate::app_import::{AppEntry, AppImportReport};
+use crate::import::FileType;
+
+// Email-to-journal: messages in a dedicated IMAP folder (or Gmail label), e.g. "Journal",
+// become entries.
+//
+// The server, user name and password live in the OS keychain (`imap_host`, `imap_username`,
+// `imap_password`); `imap_port` (993, TLS only) and `imap_folder` are settings. Each fetch asks
+// for messages with a UID above the last one imported (`imap_last_uid`, reset when the folder or
+// its UIDVALIDITY changes), so mail read elsewhere is still picked up and nothing is imported twice.
+// Imported messages are marked \Seen. With `imap_enabled` on, a background check fetches every
+// `imap_interval_minutes`. The subject becomes the title and the Date header the entry date (its
+// offset the time zone); the plain-text body is used (HTML converted when there is none), cut at
+// a "-- " signature line. Entries are tagged `email`.
+//
+// The client speaks just enough IMAP4rev1 for this: LOGIN, SELECT, UID SEARCH, UID FETCH,
+// UID STORE and LOGOUT.
+
+const LAST_UID_KEY: &str = "imap_last_uid";
+const UID_VALIDITY_KEY: &str = "imap_uid_validity";
+const LAST_FETCH_KEY: &str = "imap_last_fetch_at";
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+const TIMEOUT: Duration = Duration::from_secs(60);
+
+static RUNNING: AtomicBool = AtomicBool::new(false);
+
+fn last_error() -> &'static Mutex<Option<String>> {
+    static LAST_ERROR: OnceLock<Mutex<Option<String>>> = OnceLock::new();
+    LAST_ERROR.get_or_init(|| Mutex::new(None))
+}
+
+#[derive(Debug, Clone)]
+pub struct ImapConfig {
+    pub host: String,
+    pub port: u16,
+    pub username: String,
+    pub password: String,
+    pub folder: String,
+}
+
+impl ImapConfig {
+    pub fn load(conn: &rusqlite::Connection) -> Result<Self> {
+        let secret = |key: &str| -> Result<String> {
+            crate::secrets::get(key)?.map(|v| v.trim().to_string()).filter(|v| !v.is_empty()).with_context(|| format!("No {} stored in the keychain", key))
+        };
+        let setting = |key: &str| crate::database::read_setting(conn, key).map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+        Ok(Self {
+            host: secret("imap_host")?,
+            port: setting("imap_port").and_then(|p| p.parse().ok()).unwrap_or(993),
+            username: secret("imap_username")?,
+            // Passwords may legitimately start or end with spaces
+            password: crate::secrets::get("imap_password")?.filter(|p| !p.is_empty()).context("No imap_password stored in the keychain")?,
+            folder: setting("imap_folder").unwrap_or_else(|| "Journal".to_string()),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImapStatus {
+    pub configured: bool,
+    pub enabled: bool,
+    pub last_fetch_at: Option<String>,
+    pub last_error: Option<String>,
+}
+
+pub fn status(conn: &rusqlite::Connection) -> Result<ImapStatus> {
+    let configured = crate::secrets::has("imap_host")? && crate::secrets::has("imap_username")? && crate::secrets::has("imap_password")?;
+    Ok(ImapStatus {
+        configured,
+        enabled: crate::database::setting_enabled(conn, "imap_enabled", false),
+        last_fetch_at: crate::database::read_setting(conn, LAST_FETCH_KEY),
+        last_error: last_error().lock().unwrap().clone(),
+    })
+}
+
+// An IMAP quoted string
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+// Untagged response lines and the literals ({n} blocks) they carried, for one command
+#[derive(Debug, Default)]
+struct Reply {
+    lines: Vec<String>,
+    literals: Vec<Vec<u8>>,
+}
+
+struct Session<S> {
+    stream: BufReader<S>,
+    tag: u32,
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> Session<S> {
+    async fn open(stream: S) -> Result<Self> {
+        let mut session = Self { stream: BufReader::new(stream), tag: 0 };
+        let greeting = session.read_line().await?;
+        if !greeting.starts_with("* OK") {
+            anyhow::bail!("Unexpected IMAP greeting: {}", greeting.trim());
+        }
+        Ok(session)
+    }
+
+    async fn read_line(&mut self) -> Result<String> {
+        let mut line = Vec::new();
+        let n = tokio::time::timeout(TIMEOUT, self.stream.read_until(b'\n', &mut line)).await.context("IMAP server timed out")??;
+        if n == 0 {
+            anyhow::bail!("IMAP server closed the connection");
+        }
+        Ok(String::from_utf8_lossy(&line).into_owned())
+    }
+
+    async fn command(&mut self, command: &str) -> Result<Reply> {
+        self.tag += 1;
+        let tag = format!("a{}", self.tag);
+        self.stream.get_mut().write_all(format!("{} {}\r\n", tag, command).as_bytes()).await?;
+        self.stream.get_mut().flush().await?;
+        let mut reply = Reply::default();
+        loop {
+            let line = self.read_line().await?;
+            // A literal: "{123}\r\n" then exactly that many bytes, then the rest of the response
+            if let Some(size) = line.trim_end().strip_suffix('}').and_then(|l| l.rsplit_once('{')).and_then(|(_, n)| n.parse::<usize>().ok()) {
+                let mut literal = vec![0; size];
+                tokio::time::timeout(TIMEOUT, self.stream.read_exact(&mut literal)).await.context("IMAP server timed out")??;
+                reply.literals.push(literal);
+                reply.lines.push(line);
+                continue;
+            }
+            if let Some(status) = line.strip_prefix(&format!("{} ", tag)) {
+                if !status.starts_with("OK") {
+                    // Don't echo the command back: for LOGIN it holds the password
+                    let verb = command.split_whitespace().take(if command.starts_with("UID") { 2 } else { 1 }).collect::<Vec<_>>().join(" ");
+                    anyhow::bail!("IMAP {} failed: {}", verb, status.trim());
+                }
+                return Ok(reply);
+            }
+            reply.lines.push(line);
+        }
+    }
+
+    async fn login(&mut self, username: &str, password: &str) -> Result<()> {
+        self.command(&format!("LOGIN {} {}", quote(username), quote(password))).await.map(|_| ())
+    }
+
+    // UIDVALIDITY of the selected folder
+    async fn select(&mut self, folder: &str) -> Result<u64> {
+        let reply = self.command(&format!("SELECT {}", quote(folder))).await?;
+        reply
+            .lines
+            .iter()
+            .find_map(|l| l.split("[UIDVALIDITY ").nth(1).and_then(|rest| rest.split(']').next()).and_then(|n| n.trim().parse().ok()))
+            .context("IMAP server sent no UIDVALIDITY")
+    }
+
+    async fn uids_after(&mut self, last_uid: u64) -> Result<Vec<u64>> {
+        let reply = self.command(&format!("UID SEARCH UID {}:*", last_uid + 1)).await?;
+        let mut uids: Vec<u64> = reply
+            .lines
+            .iter()
+            .filter_map(|l| l.strip_prefix("* SEARCH"))
+            .flat_map(|l| l.split_whitespace().filter_map(|n| n.parse().ok()).collect::<Vec<u64>>())
+            // "n:*" always matches the highest UID, even when it is below n
+            .filter(|uid| *uid > last_uid)
+            .collect();
+        uids.sort_unstable();
+        Ok(uids)
+    }
+
+    async fn fetch(&mut self, uid: u64) -> Result<Vec<u8>> {
+        let mut reply = self.command(&format!("UID FETCH {} BODY.PEEK[]", uid)).await?;
+        reply.literals.pop().with_context(|| format!("IMAP server returned no body for message {}", uid))
+    }
+
+    async fn mark_seen(&mut self, uid: u64) -> Result<()> {
+        self.command(&format!("UID STORE {} +FLAGS (\\Seen)", uid)).await.map(|_| ())
+    }
+
+    async fn logout(&mut self) {
+        let _ = self.command("LOGOUT").await;
+    }
+}
+
+// The text before a "-- " signature separator
+fn strip_signature(body: &str) -> &str {
+    body.split_once("\n-- \n").or_else(|| body.split_once("\n-- \r\n")).map(|(text, _)| text).unwrap_or(body)
+}
+
+pub fn parse_message(raw: &[u8], folder: &str, uid: u64) -> Option<AppEntry> {
+    let message = mail_parser::MessageParser::default().parse(raw)?;
+    let date = message.date().and_then(|d| DateTime::parse_from_rfc3339(&d.to_rfc3339()).ok());
+    let body = message.body_text(0).map(|b| strip_signature(&b.replace("\r\n", "\n")).trim().to_string()).unwrap_or_default();
+    Some(AppEntry {
+        source: match message.message_id() {
+            Some(id) => format!("imap:{}", id),
+            None => format!("imap:{}#{}", folder, uid),
+        },
+        title: message.subject().map(|s| s.trim().to_string()).filter(|s| !s.is_empty()),
+        body,
+        tags: vec!["email".to_string()],
+        created: date.map(|d| d.with_timezone(&Utc)).unwrap_or_else(Utc::now),
+        timezone: date.map(|d| d.offset().to_string()).filter(|o| o != "+00:00").unwrap_or_else(|| "UTC".to_string()),
+        sentiment: None,
+    })
+}
+
+async fn connect(config: &ImapConfig) -> Result<Session<tokio_native_tls::TlsStream<tokio::net::TcpStream>>> {
+    let tcp = tokio::time::timeout(TIMEOUT, tokio::net::TcpStream::connect((config.host.as_str(), config.port)))
+        .await
+        .context("Connecting to the IMAP server timed out")??;
+    let connector = tokio_native_tls::TlsConnector::from(tokio_native_tls::native_tls::TlsConnector::new()?);
+    let tls = connector.connect(&config.host, tcp).await.context("TLS handshake with the IMAP server failed")?;
+    Session::open(tls).await
+}
+
+fn save_setting(app_handle: &AppHandle, key: &str, value: &str) -> Result<()> {
+    crate::database::open_conn(app_handle)?
+        .execute("INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)", rusqlite::params![key, value])?;
+    Ok(())
+}
+
+async fn fetch_new(app_handle: &AppHandle) -> Result<AppImportReport> {
+    let (config, last_uid, uid_validity) = {
+        let conn = crate::database::open_conn(app_handle)?;
+        let last_uid = crate::database::read_setting(&conn, LAST_UID_KEY).and_then(|v| v.parse::<u64>().ok());
+        (ImapConfig::load(&conn)?, last_uid.unwrap_or(0), crate::database::read_setting(&conn, UID_VALIDITY_KEY))
+    };
+    let mut session = connect(&config).await?;
+    session.login(&config.username, &config.password).await?;
+    // UIDs only count within one folder and UIDVALIDITY; a different folder, or the same one
+    // recreated, starts over
+    let validity = format!("{}:{}", config.folder, session.select(&config.folder).await?);
+    let mut last_uid = if uid_validity.as_deref() == Some(validity.as_str()) { last_uid } else { 0 };
+    save_setting(app_handle, UID_VALIDITY_KEY, &validity)?;
+
+    let mut report = AppImportReport::default();
+    for uid in session.uids_after(last_uid).await? {
+        let raw = session.fetch(uid).await?;
+        match parse_message(&raw, &config.folder, uid) {
+            Some(entry) => crate::app_import::save_all(app_handle, vec![entry], FileType::Email, &mut report).await?,
+            None => report.error(format!("Message {} could not be parsed", uid)),
+        }
+        session.mark_seen(uid).await?;
+        last_uid = uid;
+        save_setting(app_handle, LAST_UID_KEY, &last_uid.to_string())?;
+    }
+    session.logout().await;
+    save_setting(app_handle, LAST_FETCH_KEY, &Utc::now().to_rfc3339())?;
+    Ok(report)
+}
+
+// One fetch at a time; the scheduler and the "Check now" button can overlap
+pub async fn fetch(app_handle: &AppHandle) -> Result<AppImportReport> {
+    if RUNNING.swap(true, Ordering::SeqCst) {
+        anyhow::bail!("Already checking for mail");
+    }
+    let result = fetch_new(app_handle).await;
+    RUNNING.store(false, Ordering::SeqCst);
+    *last_error().lock().unwrap() = result.as_ref().err().map(|e| e.to_string());
+    if let Ok(report) = &result {
+        if report.imported > 0 {
+            crate::commands::spawn_post_import(app_handle);
+            crate::hooks::emit(
+                app_handle,
+                "entry-imported",
+                serde_json::json!({ "source": "email", "imported": report.imported, "duplicates": report.duplicates, "failed": report.failed }),
+            );
+        }
+    }
+    result
+}
+
+fn fetch_due(conn: &rusqlite::Connection, now: DateTime<Utc>) -> bool {
+    if !crate::database::setting_enabled(conn, "imap_enabled", false) {
+        return false;
+    }
+    let minutes: i64 = crate::database::read_setting(conn, "imap_interval_minutes").and_then(|v| v.trim().parse().ok()).unwrap_or(15).max(1);
+    match crate::database::read_setting(conn, LAST_FETCH_KEY).and_then(|v| DateTime::parse_from_rfc3339(&v).ok()) {
+        Some(last) => now - last.with_timezone(&Utc) >= chrono::Duration::minutes(minutes),
+        None => true,
+    }
+}
+
+pub fn start_scheduler(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(CHECK_INTERVAL).await;
+            let due = crate::database::open_conn(&app_handle).map(|conn| fetch_due(&conn, Utc::now())).unwrap_or(false);
+            if !due {
+                continue;
+            }
+            match fetch(&app_handle).await {
+                Ok(report) if report.imported > 0 => tracing::info!("imported {} messages", report.imported),
+                Ok(_) => {}
+                Err(e) => {
+                    tracing::warn!("fetch failed: {}", e);
+                    // Don't retry a failing server every minute
+                    let _ = save_setting(&app_handle, LAST_FETCH_KEY, &Utc::now().to_rfc3339());
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MESSAGE: &str = "From: Me <me@example.com>\r\nTo: Me <me@example.com>\r\nSubject: Train thoughts\r\nDate: Mon, 6 May 2024 08:12:00 +0200\r\nMessage-ID: <abc@example.com>\r\nContent-Type: text/plain; charset=utf-8\r\n\r\nThe fields were flooded.\r\n\r\n-- \r\nSent from my phone\r\n";
+
+    #[test]
+    fn test_parse_message() {
+        let entry = parse_message(MESSAGE.as_bytes(), "Journal", 7).unwrap();
+        assert_eq!(entry.title.as_deref(), Some("Train thoughts"));
+        assert_eq!(entry.body, "The fields were flooded.");
+        assert_eq!(entry.created.to_rfc3339(), "2024-05-06T06:12:00+00:00");
+        assert_eq!(entry.timezone, "+02:00");
+        assert_eq!(entry.source, "imap:abc@example.com");
+        assert_eq!(entry.tags, ["email"]);
+    }
+
+    #[tokio::test]
+    async fn test_session_against_scripted_server() {
+        let (client, server) = tokio::io::duplex(64 * 1024);
+        let script = tokio::spawn(async move {
+            let mut server = BufReader::new(server);
+            server.get_mut().write_all(b"* OK IMAP4rev1 ready\r\n").await.unwrap();
+            let mut commands = Vec::new();
+            let mut line = String::new();
+            while server.read_line(&mut line).await.unwrap() > 0 {
+                let (tag, command) = line.trim_end().split_once(' ').unwrap();
+                let response = match command.split_whitespace().next().unwrap() {
+                    "LOGIN" => format!("{} OK logged in\r\n", tag),
+                    "SELECT" => format!("* 2 EXISTS\r\n* OK [UIDVALIDITY 42] UIDs valid\r\n{} OK [READ-WRITE] done\r\n", tag),
+                    "UID" if command.starts_with("UID SEARCH") => format!("* SEARCH 3 9\r\n{} OK done\r\n", tag),
+                    "UID" if command.starts_with("UID FETCH") => {
+                        format!("* 2 FETCH (UID 9 BODY[] {{{}}}\r\n{})\r\n{} OK done\r\n", MESSAGE.len(), MESSAGE, tag)
+                    }
+                    "LOGOUT" => format!("* BYE\r\n{} OK bye\r\n", tag),
+                    _ => format!("{} NO unsupported\r\n", tag),
+                };
+                server.get_mut().write_all(response.as_bytes()).await.unwrap();
+                commands.push(command.to_string());
+                line.clear();
+            }
+            commands
+        });
+
+        let mut session = Session::open(client).await.unwrap();
+        session.login("me@example.com", "pa\"ss").await.unwrap();
+        assert_eq!(session.select("Journal").await.unwrap(), 42);
+        assert_eq!(session.uids_after(3).await.unwrap(), [9]);
+        let raw = session.fetch(9).await.unwrap();
+        assert_eq!(raw, MESSAGE.as_bytes());
+        let err = session.mark_seen(9).await.unwrap_err();
+        assert_eq!(err.to_string(), "IMAP UID STORE failed: NO unsupported");
+        session.logout().await;
+        drop(session);
+
+        let commands = script.await.unwrap();
+        assert_eq!(commands[0], r#"LOGIN "me@example.com" "pa\"ss""#);
+        assert_eq!(commands[2], "UID SEARCH UID 4:*");
+    }
+}
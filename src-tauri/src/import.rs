@@ -2,7 +2,7 @@ use anyhow::{Result, Context};
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::Path;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use sha2::{Sha256, Digest};
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -35,11 +35,28 @@ pub struct ParsedFile {
     pub size_bytes: u64,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub enum FileType {
     Txt,
     Docx,
     GDoc,
+    // Google Keep note (Takeout JSON); never chosen by extension
+    Keep,
+    // Written in the app from an entry template
+    Template,
+    // Entries from other apps' and blogs' exports (app_import.rs)
+    Diaro,
+    Daylio,
+    LiveJournal,
+    WordPress,
+    Blogger,
+    Facebook,
+    Sms,
+    Feed,
+    Email,
+    Pdf,
+    // A day of photos (photos.rs)
+    Photos,
 }
 
 impl FileType {
@@ -57,11 +74,30 @@ impl FileType {
             FileType::Txt => "txt",
             FileType::Docx => "docx",
             FileType::GDoc => "gdoc",
+            FileType::Keep => "keep",
+            FileType::Template => "template",
+            FileType::Diaro => "diaro",
+            FileType::Daylio => "daylio",
+            FileType::LiveJournal => "livejournal",
+            FileType::WordPress => "wordpress",
+            FileType::Blogger => "blogger",
+            FileType::Facebook => "facebook",
+            FileType::Sms => "sms",
+            FileType::Feed => "feed",
+            FileType::Email => "email",
+            FileType::Pdf => "pdf",
+            FileType::Photos => "photos",
         }
     }
 }
 
 pub async fn parse_file(file_path: &str) -> Result<ParsedFile> {
+    parse_file_with_drive(file_path, None).await
+}
+
+// Like parse_file, but .gdoc link files are exported through the Drive API when a Google
+// access token is given
+pub async fn parse_file_with_drive(file_path: &str, google_access: Option<&str>) -> Result<ParsedFile> {
     let path = Path::new(file_path);
     
     let extension = path
@@ -76,9 +112,9 @@ pub async fn parse_file(file_path: &str) -> Result<ParsedFile> {
         .context("Failed to read file metadata")?;
     
     let content = match file_type {
-        FileType::Txt => parse_txt_file(file_path).await?,
+        FileType::Txt | FileType::Keep | FileType::Template | FileType::Diaro | FileType::Daylio | FileType::LiveJournal | FileType::WordPress | FileType::Blogger | FileType::Facebook | FileType::Sms | FileType::Feed | FileType::Email | FileType::Pdf | FileType::Photos => parse_txt_file(file_path).await?,
         FileType::Docx => parse_docx_file(file_path).await?,
-        FileType::GDoc => parse_gdoc_file(file_path).await?,
+        FileType::GDoc => parse_gdoc_file(file_path, google_access).await?,
     };
     
     // Generate content hash for deduplication
@@ -121,7 +157,7 @@ pub async fn parse_docx_file(path: &str) -> Result<String> {
     use std::process::Command;
     
     // Try to use pandoc if available to convert DOCX to text
-    match Command::new("pandoc")
+    let pandoc_installed = match Command::new("pandoc")
         .args(["-f", "docx", "-t", "plain", path])
         .output()
     {
@@ -130,36 +166,64 @@ pub async fn parse_docx_file(path: &str) -> Result<String> {
                 let content = String::from_utf8_lossy(&output.stdout).to_string();
                 return Ok(normalize_content(&content));
             }
+            true
         }
-        Err(_) => {
-            // Pandoc not available, continue to fallback
-        }
-    }
+        // Pandoc not available, continue to fallback
+        Err(_) => false,
+    };
     
     // Fallback: Try to extract text using basic ZIP parsing
     // DOCX files are ZIP archives with XML content
     match extract_docx_text_basic(path) {
         Ok(content) => Ok(normalize_content(&content)),
+        Err(_) if !pandoc_installed => Err(crate::error::MissingTool { tool: "pandoc", hint: "install it to import this DOCX file, or convert it to TXT" }.into()),
         Err(_) => {
             // If all methods fail, return a helpful error
             Err(anyhow::anyhow!(
-                "DOCX parsing failed. Please install pandoc or convert to TXT format. File: {}", 
+                "DOCX parsing failed. Please convert to TXT format. File: {}", 
                 path
             ))
         }
     }
 }
 
+// The Drive file ID in a .gdoc link file: `doc_id` (Drive for desktop), `resource_id`
+// ("document:<id>", Backup and Sync), or the /document/d/<id> part of `url`
+pub fn gdoc_doc_id(json: &serde_json::Value) -> Option<String> {
+    let field = |key: &str| json.get(key).and_then(|v| v.as_str()).filter(|v| !v.is_empty());
+    if let Some(id) = field("doc_id") {
+        return Some(id.to_string());
+    }
+    if let Some(id) = field("resource_id").and_then(|r| r.strip_prefix("document:")) {
+        return Some(id.to_string());
+    }
+    let re = regex::Regex::new(r"https?://docs\.google\.com/document/(?:u/\d+/)?d/([a-zA-Z0-9_-]+)").ok()?;
+    re.captures(field("url")?).map(|c| c[1].to_string())
+}
+
 // Parse Google Docs link files (.gdoc). These are small JSON files pointing to the web URL.
-// We import a placeholder entry containing the doc URL so it shows up in the timeline/search.
-// For full text, export from Google Docs to .docx or .txt and import that file.
-pub async fn parse_gdoc_file(path: &str) -> Result<String> {
+// With Google connected the document text is exported through the Drive API; otherwise we try
+// the public export link, and failing that import a placeholder containing the doc URL so it
+// shows up in the timeline/search.
+pub async fn parse_gdoc_file(path: &str, google_access: Option<&str>) -> Result<String> {
     use regex::Regex;
     let text = std::fs::read_to_string(path).context("Failed to read GDOC file")?;
     let json: serde_json::Value = serde_json::from_str(&text).context("Failed to parse GDOC JSON")?;
     let url = json.get("url").and_then(|v| v.as_str()).unwrap_or("");
     let name = json.get("name").and_then(|v| v.as_str()).unwrap_or("");
 
+    if let (Some(access), Some(doc_id)) = (google_access, gdoc_doc_id(&json)) {
+        match crate::gdrive::export_doc_text(&reqwest::Client::new(), access, &doc_id).await {
+            Ok(body) => {
+                let normalized = normalize_content(&body);
+                if !normalized.is_empty() {
+                    return Ok(normalized);
+                }
+            }
+            Err(e) => tracing::warn!("Drive export of {} failed: {}", doc_id, e),
+        }
+    }
+
     // Try to extract Google Doc ID and fetch exported content
     if let Some(u) = (!url.is_empty()).then(|| url) {
         let re = Regex::new(r"https?://docs\.google\.com/document/d/([a-zA-Z0-9_-]+)").ok();
@@ -292,6 +356,19 @@ fn extract_title(content: &str, path: &Path) -> Option<String> {
         .map(|name| name.to_string())
 }
 
+// Modification time (Unix seconds) and size of an imported file, used to notice later edits
+pub(crate) fn source_stat(path: &str) -> Option<(i64, i64)> {
+    let metadata = fs::metadata(path).ok()?;
+    let mtime = metadata.modified().ok()?.duration_since(std::time::UNIX_EPOCH).ok()?.as_secs() as i64;
+    Some((mtime, metadata.len() as i64))
+}
+
+// SHA-256 of an imported file's bytes, used to tell whether it changed before writing to it
+pub(crate) fn source_hash(path: &str) -> Option<String> {
+    let bytes = fs::read(path).ok()?;
+    Some(format!("{:x}", Sha256::digest(&bytes)))
+}
+
 pub fn normalize_content(content: &str) -> String {
     // Normalize whitespace, quotes, and common formatting
     content
@@ -309,10 +386,74 @@ pub fn normalize_content(content: &str) -> String {
         .to_string()
 }
 
-pub fn detect_language(_content: &str) -> String {
-    // Simple language detection - for now just return English
-    // In a real implementation, we might use a language detection library
-    "en".to_string()
+const MONTHS: &[&str] = &["jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec"];
+
+fn month_number(name: &str) -> Option<u32> {
+    let name = name.to_lowercase();
+    MONTHS.iter().position(|m| name.starts_with(m)).map(|i| i as u32 + 1)
+}
+
+// A date written in a document or file name: "2021-03-05 Morning pages", "20210305",
+// "Journal - March 5th, 2021", "5 Mar 2021", "03/05/2021". Numeric dates with the year last are
+// read month first, unless the first number can only be a day.
+pub fn date_from_title(title: &str) -> Option<NaiveDate> {
+    static PATTERNS: std::sync::OnceLock<[regex::Regex; 5]> = std::sync::OnceLock::new();
+    let [ymd, compact, mdy_name, dmy_name, numeric] = PATTERNS.get_or_init(|| {
+        let month = r"(jan|feb|mar|apr|may|jun|jul|aug|sep|oct|nov|dec)[a-z]*\.?";
+        [
+            regex::Regex::new(r"(?:^|\D)(\d{4})[-_./ ](\d{1,2})[-_./ ](\d{1,2})(?:\D|$)").expect("valid date pattern"),
+            regex::Regex::new(r"(?:^|\D)(\d{4})(\d{2})(\d{2})(?:\D|$)").expect("valid date pattern"),
+            regex::Regex::new(&format!(r"(?i)\b{}\s+(\d{{1,2}})(?:st|nd|rd|th)?,?\s+(\d{{4}})\b", month)).expect("valid date pattern"),
+            regex::Regex::new(&format!(r"(?i)\b(\d{{1,2}})(?:st|nd|rd|th)?\s+{},?\s+(\d{{4}})\b", month)).expect("valid date pattern"),
+            regex::Regex::new(r"(?:^|\D)(\d{1,2})[-/.](\d{1,2})[-/.](\d{4})(?:\D|$)").expect("valid date pattern"),
+        ]
+    });
+    let date = |y: &str, m: u32, d: &str| {
+        let year: i32 = y.parse().ok()?;
+        (1900..=2100).contains(&year).then_some(())?;
+        NaiveDate::from_ymd_opt(year, m, d.parse().ok()?)
+    };
+    if let Some(c) = ymd.captures(title).or_else(|| compact.captures(title)) {
+        if let Some(d) = date(&c[1], c[2].parse().ok()?, &c[3]) {
+            return Some(d);
+        }
+    }
+    if let Some(c) = mdy_name.captures(title) {
+        return date(&c[3], month_number(&c[1])?, &c[2]);
+    }
+    if let Some(c) = dmy_name.captures(title) {
+        return date(&c[3], month_number(&c[2])?, &c[1]);
+    }
+    let c = numeric.captures(title)?;
+    let (first, second): (u32, u32) = (c[1].parse().ok()?, c[2].parse().ok()?);
+    if first > 12 {
+        date(&c[3], second, &c[1])
+    } else {
+        date(&c[3], first, &c[2])
+    }
+}
+
+// The date of a line made mostly of one, as written above an entry ("March 5th, 2021",
+// "Monday 5 Mar 2021"); longer lines are sentences that happen to mention a date
+pub fn date_heading(line: &str) -> Option<NaiveDate> {
+    let line = line.trim().trim_start_matches('#').trim();
+    if line.is_empty() || line.chars().count() > 40 || line.split_whitespace().count() > 6 {
+        return None;
+    }
+    date_from_title(line)
+}
+
+// ISO 639-3 code of the entry's language ("eng", "spa"); None when the text is too short or
+// mixed for a reliable guess
+pub fn detect_language(content: &str) -> Option<String> {
+    whatlang::detect(content)
+        .filter(|info| info.is_reliable())
+        .map(|info| info.lang().code().to_string())
+}
+
+// English name for a stored language code
+pub fn language_name(code: &str) -> String {
+    whatlang::Lang::from_code(code).map(|l| l.eng_name().to_string()).unwrap_or_else(|| code.to_string())
 }
 
 #[cfg(test)]
@@ -327,7 +468,41 @@ mod tests {
         assert!(matches!(FileType::from_extension("doc"), Some(FileType::Docx)));
         assert!(FileType::from_extension("pdf").is_none());
     }
+
+    #[test]
+    fn test_detect_language() {
+        let english = "Spent the morning in the garden with my sister, then we walked down to the harbour and watched the boats come in.";
+        let spanish = "Pasé la mañana en el jardín con mi hermana, y después caminamos hasta el puerto para ver llegar los barcos.";
+        assert_eq!(detect_language(english).as_deref(), Some("eng"));
+        assert_eq!(detect_language(spanish).as_deref(), Some("spa"));
+        assert_eq!(language_name("spa"), "Spanish");
+        assert_eq!(detect_language("ok"), None);
+    }
     
+    #[test]
+    fn test_gdoc_doc_id() {
+        let id = |j: &str| gdoc_doc_id(&serde_json::from_str(j).unwrap());
+        assert_eq!(id(r#"{"doc_id":"abc_1","email":"me@example.com"}"#).as_deref(), Some("abc_1"));
+        assert_eq!(id(r#"{"resource_id":"document:xyz-2"}"#).as_deref(), Some("xyz-2"));
+        assert_eq!(id(r#"{"url":"https://docs.google.com/document/d/Q9w/edit?usp=docslist_api"}"#).as_deref(), Some("Q9w"));
+        assert_eq!(id(r#"{"url":"https://docs.google.com/spreadsheets/d/Q9w/edit"}"#), None);
+    }
+
+    #[test]
+    fn test_date_from_title() {
+        let d = |y, m, day| NaiveDate::from_ymd_opt(y, m, day);
+        assert_eq!(date_from_title("2021-03-05 Morning pages"), d(2021, 3, 5));
+        assert_eq!(date_from_title("journal_2021_3_5"), d(2021, 3, 5));
+        assert_eq!(date_from_title("Entry 20210305"), d(2021, 3, 5));
+        assert_eq!(date_from_title("Journal - March 5th, 2021"), d(2021, 3, 5));
+        assert_eq!(date_from_title("5 Sept 2021 walk"), d(2021, 9, 5));
+        assert_eq!(date_from_title("03/05/2021"), d(2021, 3, 5));
+        assert_eq!(date_from_title("25.12.2020"), d(2020, 12, 25));
+        assert_eq!(date_from_title("2021-02-30 notes"), None);
+        assert_eq!(date_from_title("Trip to Lisbon"), None);
+        assert_eq!(date_from_title("Room 1234567890"), None);
+    }
+
     #[test]
     fn test_normalize_content() {
         let input = "Hello   \"world\"  with—dashes";
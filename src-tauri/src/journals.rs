@@ -0,0 +1,187 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+// Registry of journal databases, kept in `journals.json` next to the default database.
+//
+// Each journal is its own SQLite file with its own entries, settings, conversations and
+// embeddings; only the OS keychain (API keys) is shared. Every database function resolves
+// its file through `database::get_db_file_path`, which asks this module for the active
+// journal. Without a registry file there is a single journal at the original location.
+
+pub const DEFAULT_JOURNAL_ID: &str = "default";
+const REGISTRY_FILE: &str = "journals.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Journal {
+    pub id: String,
+    pub name: String,
+    pub path: String,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalList {
+    pub active: String,
+    pub journals: Vec<Journal>,
+}
+
+// Active journal's database path, so opening a connection doesn't read the registry
+static ACTIVE_PATH: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+fn default_registry(dir: &Path) -> JournalList {
+    JournalList {
+        active: DEFAULT_JOURNAL_ID.to_string(),
+        journals: vec![Journal {
+            id: DEFAULT_JOURNAL_ID.to_string(),
+            name: "Journal".to_string(),
+            path: dir.join("journal.db").to_string_lossy().to_string(),
+            created_at: String::new(),
+        }],
+    }
+}
+
+pub fn load(dir: &Path) -> Result<JournalList> {
+    let path = dir.join(REGISTRY_FILE);
+    if !path.exists() {
+        return Ok(default_registry(dir));
+    }
+    let mut list: JournalList = serde_json::from_str(&std::fs::read_to_string(&path)?)
+        .map_err(|e| anyhow::anyhow!("Invalid journal registry {}: {}", path.display(), e))?;
+    if list.journals.is_empty() {
+        list = default_registry(dir);
+    }
+    if !list.journals.iter().any(|j| j.id == list.active) {
+        list.active = list.journals[0].id.clone();
+    }
+    Ok(list)
+}
+
+fn save(dir: &Path, list: &JournalList) -> Result<()> {
+    std::fs::create_dir_all(dir)?;
+    // Write then rename so a crash never leaves a half-written registry
+    let tmp = dir.join(format!("{}.tmp", REGISTRY_FILE));
+    std::fs::write(&tmp, serde_json::to_vec_pretty(list)?)?;
+    std::fs::rename(&tmp, dir.join(REGISTRY_FILE))?;
+    Ok(())
+}
+
+fn set_active_path(path: PathBuf) {
+    if let Ok(mut guard) = ACTIVE_PATH.lock() {
+        *guard = Some(path);
+    }
+}
+
+pub fn active_path(dir: &Path) -> Result<PathBuf> {
+    if let Some(path) = ACTIVE_PATH.lock().ok().and_then(|p| p.clone()) {
+        return Ok(path);
+    }
+    let list = load(dir)?;
+    let active = list.journals.iter().find(|j| j.id == list.active).expect("load keeps active valid");
+    let path = PathBuf::from(&active.path);
+    set_active_path(path.clone());
+    Ok(path)
+}
+
+fn slug(name: &str) -> String {
+    let mut out = String::new();
+    for c in name.chars().flat_map(|c| c.to_lowercase()) {
+        if c.is_alphanumeric() {
+            out.push(c);
+        } else if !out.ends_with('-') {
+            out.push('-');
+        }
+    }
+    let out = out.trim_matches('-').to_string();
+    if out.is_empty() { "journal".to_string() } else { out }
+}
+
+// Registers a new journal; the database file is created on first switch. Without a path it
+// goes in `journals/<id>.db` under the app data dir.
+pub fn create(dir: &Path, name: &str, path: Option<&str>) -> Result<Journal> {
+    let name = name.trim();
+    if name.is_empty() {
+        anyhow::bail!("Journal name is required");
+    }
+    let mut list = load(dir)?;
+    if list.journals.iter().any(|j| j.name.eq_ignore_ascii_case(name)) {
+        anyhow::bail!("A journal named \"{}\" already exists", name);
+    }
+    let base = slug(name);
+    let mut id = base.clone();
+    let mut n = 2;
+    while list.journals.iter().any(|j| j.id == id) {
+        id = format!("{}-{}", base, n);
+        n += 1;
+    }
+    let path = match path.map(str::trim).filter(|p| !p.is_empty()) {
+        Some(p) => PathBuf::from(p),
+        None => dir.join("journals").join(format!("{}.db", id)),
+    };
+    let path = path.to_string_lossy().to_string();
+    if list.journals.iter().any(|j| j.path == path) {
+        anyhow::bail!("That database file is already registered as a journal");
+    }
+    let journal = Journal { id, name: name.to_string(), path, created_at: chrono::Utc::now().to_rfc3339() };
+    list.journals.push(journal.clone());
+    save(dir, &list)?;
+    Ok(journal)
+}
+
+// Marks a journal active; the caller resets connections and initializes the database
+pub fn set_active(dir: &Path, id: &str) -> Result<Journal> {
+    let mut list = load(dir)?;
+    let journal = list
+        .journals
+        .iter()
+        .find(|j| j.id == id)
+        .cloned()
+        .ok_or_else(|| anyhow::anyhow!("No journal with id {}", id))?;
+    if let Some(parent) = Path::new(&journal.path).parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    list.active = journal.id.clone();
+    save(dir, &list)?;
+    set_active_path(PathBuf::from(&journal.path));
+    Ok(journal)
+}
+
+// Points the active journal at its database's new location (see database::relocate_database)
+pub fn move_active(dir: &Path, new_path: &Path) -> Result<Journal> {
+    let mut list = load(dir)?;
+    let active = list.active.clone();
+    let journal = list.journals.iter_mut().find(|j| j.id == active).expect("load keeps active valid");
+    journal.path = new_path.to_string_lossy().to_string();
+    let journal = journal.clone();
+    save(dir, &list)?;
+    set_active_path(new_path.to_path_buf());
+    Ok(journal)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_create_and_switch_journals() {
+        let dir = std::env::temp_dir().join(format!("jr-journals-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let list = load(&dir).unwrap();
+        assert_eq!(list.active, DEFAULT_JOURNAL_ID);
+        assert_eq!(list.journals[0].path, dir.join("journal.db").to_string_lossy());
+
+        let work = create(&dir, " Work Notes ", None).unwrap();
+        assert_eq!(work.id, "work-notes");
+        assert!(work.path.ends_with("work-notes.db"));
+        assert!(create(&dir, "work notes", None).is_err());
+        assert_eq!(create(&dir, "Work/Notes", None).unwrap().id, "work-notes-2");
+
+        set_active(&dir, "work-notes").unwrap();
+        let list = load(&dir).unwrap();
+        assert_eq!((list.active.as_str(), list.journals.len()), ("work-notes", 3));
+        assert!(set_active(&dir, "missing").is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
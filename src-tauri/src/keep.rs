@@ -0,0 +1,188 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::AppHandle;
+
+use crate::import::{normalize_content, FileType, ParsedFile};
+
+// Google Keep import from a Takeout export (Takeout/Keep/*.json, one file per note).
+//
+// The Keep API is only available to Workspace admins, so Takeout is the route for personal
+// accounts. Each note becomes an entry dated by its creation time, titled by the note title and
+// tagged with its labels. Checklists become "- [x] item" lines. Trashed and empty notes are
+// skipped; attachments are not imported.
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TakeoutNote {
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    text_content: String,
+    #[serde(default)]
+    list_content: Vec<TakeoutListItem>,
+    #[serde(default)]
+    labels: Vec<TakeoutLabel>,
+    #[serde(default)]
+    is_trashed: bool,
+    created_timestamp_usec: Option<i64>,
+    user_edited_timestamp_usec: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TakeoutListItem {
+    #[serde(default)]
+    text: String,
+    #[serde(default)]
+    is_checked: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct TakeoutLabel {
+    name: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeepNote {
+    pub title: Option<String>,
+    pub body: String,
+    pub labels: Vec<String>,
+    pub created: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct KeepImportReport {
+    pub imported: u32,
+    pub duplicates: u32,
+    // Trashed or empty notes
+    pub skipped: u32,
+    pub failed: u32,
+    pub errors: Option<Vec<String>>,
+}
+
+// A note from its Takeout JSON; None for trashed or empty notes
+pub fn parse_takeout_note(json: &str) -> anyhow::Result<Option<KeepNote>> {
+    let note: TakeoutNote = serde_json::from_str(json)?;
+    if note.is_trashed {
+        return Ok(None);
+    }
+    let mut body = note.text_content.trim().to_string();
+    let items: Vec<String> = note
+        .list_content
+        .iter()
+        .filter(|i| !i.text.trim().is_empty())
+        .map(|i| format!("- [{}] {}", if i.is_checked { "x" } else { " " }, i.text.trim()))
+        .collect();
+    if !items.is_empty() {
+        if !body.is_empty() {
+            body.push_str("\n\n");
+        }
+        body.push_str(&items.join("\n"));
+    }
+    let title = Some(note.title.trim().to_string()).filter(|t| !t.is_empty());
+    if body.is_empty() {
+        // A title-only note is still a thought worth keeping
+        match &title {
+            Some(t) => body = t.clone(),
+            None => return Ok(None),
+        }
+    }
+    let usec = note
+        .created_timestamp_usec
+        .or(note.user_edited_timestamp_usec)
+        .ok_or_else(|| anyhow::anyhow!("note has no timestamp"))?;
+    let created = DateTime::from_timestamp_micros(usec).ok_or_else(|| anyhow::anyhow!("invalid timestamp {}", usec))?;
+    let mut labels: Vec<String> = note.labels.iter().map(|l| l.name.trim().to_lowercase()).filter(|l| !l.is_empty()).collect();
+    labels.sort();
+    labels.dedup();
+    Ok(Some(KeepNote { title, body, labels, created }))
+}
+
+// Note files under the given paths (a Takeout Keep folder, or individual .json files)
+fn note_files(paths: &[String]) -> Vec<std::path::PathBuf> {
+    let mut files: Vec<std::path::PathBuf> = paths
+        .iter()
+        .flat_map(|p| walkdir::WalkDir::new(p).into_iter().filter_map(|e| e.ok()))
+        .map(|e| e.into_path())
+        .filter(|p| p.is_file() && p.extension().and_then(|e| e.to_str()).is_some_and(|e| e.eq_ignore_ascii_case("json")))
+        .collect();
+    files.sort();
+    files.dedup();
+    files
+}
+
+pub async fn import_takeout(app_handle: &AppHandle, paths: &[String], timezone: &str) -> anyhow::Result<KeepImportReport> {
+    let mut report = KeepImportReport::default();
+    let mut errors = Vec::new();
+    for file in note_files(paths) {
+        let name = file.to_string_lossy().to_string();
+        let note = match std::fs::read_to_string(&file).map_err(anyhow::Error::from).and_then(|j| parse_takeout_note(&j)) {
+            Ok(Some(note)) => note,
+            Ok(None) => {
+                report.skipped += 1;
+                continue;
+            }
+            Err(e) => {
+                report.failed += 1;
+                errors.push(format!("{}: {}", name, e));
+                continue;
+            }
+        };
+        let content = normalize_content(&note.body);
+        let text_hash = format!("{:x}", Sha256::digest(content.as_bytes()));
+        if crate::database::check_duplicate(app_handle, &text_hash).await?.is_some() {
+            report.duplicates += 1;
+            continue;
+        }
+        let parsed = ParsedFile {
+            path: name.clone(),
+            size_bytes: content.len() as u64,
+            content,
+            title: note.title,
+            file_type: FileType::Keep,
+            text_hash,
+        };
+        match crate::database::save_entry(app_handle, parsed, note.created, timezone.to_string()).await {
+            Ok(id) => {
+                if !note.labels.is_empty() {
+                    let conn = crate::database::open_conn(app_handle)?;
+                    crate::database::add_entry_tags(&conn, &id, &note.labels)?;
+                }
+                report.imported += 1;
+            }
+            Err(e) => {
+                report.failed += 1;
+                errors.push(format!("{}: {}", name, e));
+            }
+        }
+    }
+    report.errors = (!errors.is_empty()).then_some(errors);
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_takeout_note() {
+        let note = parse_takeout_note(
+            r#"{"color":"DEFAULT","isTrashed":false,"isPinned":false,"isArchived":true,
+                "textContent":"Slept badly.\n","title":"Tuesday ",
+                "listContent":[{"text":"call mum","isChecked":true},{"text":"run","isChecked":false}],
+                "labels":[{"name":"Journal"},{"name":"health"}],
+                "userEditedTimestampUsec":1615000000000000,"createdTimestampUsec":1614950000000000}"#,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(note.title.as_deref(), Some("Tuesday"));
+        assert_eq!(note.body, "Slept badly.\n\n- [x] call mum\n- [ ] run");
+        assert_eq!(note.labels, ["health", "journal"]);
+        assert_eq!(note.created.to_rfc3339(), "2021-03-05T13:13:20+00:00");
+
+        assert_eq!(parse_takeout_note(r#"{"isTrashed":true,"textContent":"x","createdTimestampUsec":1}"#).unwrap(), None);
+        assert_eq!(parse_takeout_note(r#"{"textContent":"  ","createdTimestampUsec":1}"#).unwrap(), None);
+        assert!(parse_takeout_note(r#"{"textContent":"no date"}"#).is_err());
+    }
+}
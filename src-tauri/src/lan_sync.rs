@@ -0,0 +1,987 @@
+use std::collections::{BTreeSet, HashMap};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use rand::RngCore;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::AppHandle;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::Notify;
+
+use crate::backup::BackupEntry;
+use crate::database::EntryFilter;
+
+// Device-to-device sync over the local network.
+//
+// With `lan_sync_enabled` on, each device listens on `lan_sync_port` and advertises itself over
+// mDNS as `_journalreader._tcp`. Devices that were given the same pairing code (the
+// `lan_sync_key` secret) can sync: after plaintext hellos in which each side sends its own random
+// challenge, every frame is sealed with AES-256-GCM under a key derived from the code and carries
+// a session id hashed from both challenges, its direction and a sequence number, so a device
+// without the code can neither read nor forge messages, and frames recorded in one session can't
+// be replayed into another by either side.
+//
+// The device starting the sync does the merging. It compares per-entry digests (title, text,
+// date, tags) with the peer's, fetches the entries that differ and decides each one: content
+// changed on one side only wins; tags are unioned; when both sides changed since the last sync
+// with that peer, `lan_sync_conflicts` picks the newest edit ("newest") or keeps the local
+// version ("manual"); either way the other version is stored for review (conflicts.rs).
+// Whatever the peer is missing is then pushed back. Deletions travel as tombstones and lose to
+// a later edit.
+// Timestamps come from each device's clock, so "newest" assumes the clocks roughly agree.
+
+pub const DEVICE_ID_KEY: &str = "lan_sync_device_id";
+pub const KEY_SECRET: &str = "lan_sync_key";
+// Settings that restart the listener when changed
+pub const LISTENER_SETTINGS: &[&str] = &["lan_sync_enabled", "lan_sync_port", "lan_sync_device_name"];
+const SERVICE_TYPE: &str = "_journalreader._tcp.local.";
+const PROTOCOL_VERSION: u32 = 2;
+const DEFAULT_PORT: u16 = 47821;
+const MAX_FRAME_BYTES: usize = 64 * 1024 * 1024;
+const BATCH_SIZE: usize = 200;
+const IO_TIMEOUT: Duration = Duration::from_secs(60);
+const DISCOVERY_TIME: Duration = Duration::from_secs(3);
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ManifestItem {
+    pub id: String,
+    pub updated_at: String,
+    pub digest: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Tombstone {
+    pub entry_id: String,
+    pub deleted_at: String,
+}
+
+// The sensitive-entry key parameters (sensitive.rs), so sealed bodies open on both devices
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SensitiveKey {
+    pub info: String,
+    pub check: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Hello {
+    protocol: u32,
+    device_id: String,
+    name: String,
+    challenge: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Request {
+    Manifest { device_id: String, name: String, sensitive_key: Option<SensitiveKey> },
+    Fetch { ids: Vec<String> },
+    Push { entries: Vec<BackupEntry>, deletions: Vec<Tombstone> },
+    Finish,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Response {
+    Manifest { entries: Vec<ManifestItem>, deletions: Vec<Tombstone>, sensitive_key: Option<SensitiveKey> },
+    Entries { entries: Vec<BackupEntry> },
+    Pushed { applied: u32, deleted: u32 },
+    Finished,
+    Error { message: String },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Envelope<T> {
+    session: String,
+    from_server: bool,
+    seq: u64,
+    message: T,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncPeer {
+    pub device_id: String,
+    pub name: String,
+    pub address: Option<String>,
+    pub last_synced_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DiscoveredPeer {
+    pub device_id: String,
+    pub name: String,
+    pub address: String,
+    pub last_synced_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LanSyncStatus {
+    pub enabled: bool,
+    // Port the listener is bound to, when running
+    pub listening_port: Option<u16>,
+    pub device_id: String,
+    pub device_name: String,
+    pub paired: bool,
+    pub peers: Vec<SyncPeer>,
+    pub conflicts: u32,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LanSyncReport {
+    pub peer: String,
+    // Entries added or updated here from the peer
+    pub received: u32,
+    // Entries added or updated on the peer
+    pub sent: u32,
+    pub deleted_here: u32,
+    pub deleted_there: u32,
+    // Edited on both devices; the version not kept is stored for review (conflicts.rs)
+    pub conflicts: u32,
+    // Same text as a different entry already here (both devices imported the same file)
+    pub duplicates: u32,
+}
+
+fn restart_signal() -> &'static Notify {
+    static RESTART: OnceLock<Notify> = OnceLock::new();
+    RESTART.get_or_init(Notify::new)
+}
+
+fn listening_port() -> &'static Mutex<Option<u16>> {
+    static PORT: OnceLock<Mutex<Option<u16>>> = OnceLock::new();
+    PORT.get_or_init(|| Mutex::new(None))
+}
+
+// This device's id, created on first use. Not included in backups, so a restored copy on
+// another machine gets its own
+pub fn device_id(conn: &Connection) -> Result<String> {
+    if let Some(id) = crate::database::read_setting(conn, DEVICE_ID_KEY).filter(|v| !v.is_empty()) {
+        return Ok(id);
+    }
+    let id = uuid::Uuid::new_v4().to_string();
+    conn.execute("INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)", params![DEVICE_ID_KEY, id])?;
+    Ok(id)
+}
+
+fn device_name(conn: &Connection, device_id: &str) -> String {
+    crate::database::read_setting(conn, "lan_sync_device_name")
+        .map(|n| n.trim().to_string())
+        .filter(|n| !n.is_empty())
+        .unwrap_or_else(|| format!("Journal Reader {}", &device_id[..8.min(device_id.len())]))
+}
+
+fn port(conn: &Connection) -> u16 {
+    crate::database::read_setting(conn, "lan_sync_port").and_then(|p| p.trim().parse().ok()).unwrap_or(DEFAULT_PORT)
+}
+
+fn manual_conflicts(conn: &Connection) -> bool {
+    crate::database::read_setting(conn, "lan_sync_conflicts").as_deref() == Some("manual")
+}
+
+// A new random pairing code; the same code is entered on every device that should sync
+pub fn generate_pairing_code() -> Result<String> {
+    let mut bytes = [0u8; 24];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    let code = base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(bytes);
+    crate::secrets::set(KEY_SECRET, &code)?;
+    Ok(code)
+}
+
+fn channel_key() -> Result<[u8; 32]> {
+    let code = crate::secrets::get(KEY_SECRET)?.map(|c| c.trim().to_string()).filter(|c| !c.is_empty());
+    let code = code.context("No LAN sync pairing code set on this device")?;
+    Ok(derive_channel_key(&code))
+}
+
+fn derive_channel_key(code: &str) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(b"journal-reader-lan-sync\0");
+    hasher.update(code.as_bytes());
+    hasher.finalize().into()
+}
+
+pub fn status(conn: &Connection) -> Result<LanSyncStatus> {
+    let id = device_id(conn)?;
+    Ok(LanSyncStatus {
+        enabled: crate::database::setting_enabled(conn, "lan_sync_enabled", false),
+        listening_port: *listening_port().lock().unwrap(),
+        device_name: device_name(conn, &id),
+        device_id: id,
+        paired: crate::secrets::has(KEY_SECRET)?,
+        peers: known_peers(conn)?,
+        conflicts: crate::conflicts::count(conn)?,
+    })
+}
+
+fn known_peers(conn: &Connection) -> Result<Vec<SyncPeer>> {
+    let mut stmt = conn.prepare("SELECT device_id, name, address, last_synced_at FROM sync_peers ORDER BY name")?;
+    let peers = stmt
+        .query_map([], |r| Ok(SyncPeer { device_id: r.get(0)?, name: r.get(1)?, address: r.get(2)?, last_synced_at: r.get(3)? }))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(peers)
+}
+
+fn record_peer(conn: &Connection, device_id: &str, name: &str, address: Option<&str>, synced_at: &str) -> Result<()> {
+    conn.execute(
+        "INSERT INTO sync_peers (device_id, name, address, last_synced_at) VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT(device_id) DO UPDATE SET name = excluded.name, address = COALESCE(excluded.address, address),
+                                              last_synced_at = excluded.last_synced_at",
+        params![device_id, name, address, synced_at],
+    )?;
+    Ok(())
+}
+
+fn last_synced(conn: &Connection, device_id: &str) -> Result<Option<DateTime<Utc>>> {
+    let at: Option<String> = conn
+        .query_row("SELECT last_synced_at FROM sync_peers WHERE device_id = ?1", params![device_id], |r| r.get(0))
+        .optional()?
+        .flatten();
+    Ok(at.as_deref().map(timestamp))
+}
+
+fn timestamp(value: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(value).map(|t| t.with_timezone(&Utc)).unwrap_or(DateTime::<Utc>::MIN_UTC)
+}
+
+// What the entry looks like apart from its tags
+fn content_digest(entry: &BackupEntry) -> String {
+    let mut hasher = Sha256::new();
+    for part in [
+        entry.title.as_deref().unwrap_or_default(),
+        &entry.text_hash,
+        &entry.entry_date,
+        &entry.entry_timezone,
+        if entry.body_sealed.is_some() { "sealed" } else { "plain" },
+    ] {
+        hasher.update(part.as_bytes());
+        hasher.update([0]);
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+fn digest(entry: &BackupEntry) -> String {
+    let tags: BTreeSet<&str> = entry.tags.iter().map(String::as_str).collect();
+    let mut hasher = Sha256::new();
+    hasher.update(content_digest(entry).as_bytes());
+    for tag in tags {
+        hasher.update([0]);
+        hasher.update(tag.as_bytes());
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+fn manifest_item(entry: &BackupEntry) -> ManifestItem {
+    ManifestItem { id: entry.id.clone(), updated_at: entry.updated_at.clone(), digest: digest(entry) }
+}
+
+fn tombstones(conn: &Connection) -> Result<Vec<Tombstone>> {
+    let mut stmt = conn.prepare("SELECT entry_id, deleted_at FROM entry_tombstones ORDER BY entry_id")?;
+    let rows = stmt
+        .query_map([], |r| Ok(Tombstone { entry_id: r.get(0)?, deleted_at: r.get(1)? }))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(rows)
+}
+
+fn sensitive_key(conn: &Connection) -> Option<SensitiveKey> {
+    Some(SensitiveKey {
+        info: crate::database::read_setting(conn, "sensitive_key_info")?,
+        check: crate::database::read_setting(conn, "sensitive_key_check")?,
+    })
+}
+
+// Takes over the peer's sensitive-entry key when this device has none yet
+fn adopt_sensitive_key(conn: &Connection, key: Option<&SensitiveKey>) -> Result<()> {
+    if let (None, Some(key)) = (sensitive_key(conn), key) {
+        conn.execute("INSERT OR REPLACE INTO settings (key, value) VALUES ('sensitive_key_info', ?1)", params![key.info])?;
+        conn.execute("INSERT OR REPLACE INTO settings (key, value) VALUES ('sensitive_key_check', ?1)", params![key.check])?;
+    }
+    Ok(())
+}
+
+// How one entry that differs between the devices is settled
+#[derive(Debug)]
+enum Outcome {
+    // Write this version here and/or send it to the peer. `superseded` is the edit that lost
+    // when both sides changed it
+    Settled { entry: BackupEntry, apply_here: bool, send: bool, superseded: Option<Box<BackupEntry>> },
+    // Both sides edited it; the local version stays and the peer's is kept for review
+    Conflict(BackupEntry),
+}
+
+fn reconcile(local: Option<&BackupEntry>, remote: BackupEntry, last_sync: Option<DateTime<Utc>>, manual: bool) -> Outcome {
+    let Some(local) = local else {
+        return Outcome::Settled { entry: remote, apply_here: true, send: false, superseded: None };
+    };
+    let local_time = timestamp(&local.updated_at);
+    let remote_time = timestamp(&remote.updated_at);
+    let edited_since = |t: DateTime<Utc>| last_sync.is_none_or(|s| t > s);
+    let both_edited = content_digest(local) != content_digest(&remote) && edited_since(local_time) && edited_since(remote_time);
+    if manual && both_edited {
+        return Outcome::Conflict(remote);
+    }
+    let (mut winner, loser) = if remote_time > local_time { (remote.clone(), local) } else { (local.clone(), &remote) };
+    let tags: BTreeSet<String> = local.tags.iter().chain(remote.tags.iter()).cloned().collect();
+    winner.tags = tags.into_iter().collect();
+    let winner_digest = digest(&winner);
+    Outcome::Settled {
+        apply_here: winner_digest != digest(local),
+        send: winner_digest != digest(&remote),
+        superseded: both_edited.then(|| Box::new(loser.clone())),
+        entry: winner,
+    }
+}
+
+// Writes entries received from a peer. With `only_newer`, an entry older than the local copy is
+// ignored. Returns (applied, duplicates)
+pub(crate) fn apply_entries(conn: &Connection, entries: &[BackupEntry], only_newer: bool) -> Result<(u32, u32)> {
+    let (mut applied, mut duplicates) = (0, 0);
+    // Joins the caller's transaction when there is one (resolving a conflict)
+    let tx = if conn.is_autocommit() { Some(conn.unchecked_transaction()?) } else { None };
+    for entry in entries {
+        let current: Option<(String, String)> = conn
+            .query_row("SELECT updated_at, text_hash FROM entries WHERE id = ?1", params![entry.id], |r| Ok((r.get(0)?, r.get(1)?)))
+            .optional()?;
+        if let Some((updated_at, _)) = &current {
+            if only_newer && timestamp(&entry.updated_at) < timestamp(updated_at) {
+                continue;
+            }
+        }
+        let same_text: Option<String> = conn
+            .query_row("SELECT id FROM entries WHERE text_hash = ?1 AND id != ?2", params![entry.text_hash, entry.id], |r| r.get(0))
+            .optional()?;
+        if same_text.is_some() {
+            duplicates += 1;
+            continue;
+        }
+        let body_sealed = match &entry.body_sealed {
+            Some(b) => Some(base64::engine::general_purpose::STANDARD.decode(b).context("Invalid sealed body from peer")?),
+            None => None,
+        };
+        let sensitive = body_sealed.is_some();
+        match &current {
+            Some((_, text_hash)) => {
+                conn.execute(
+                    r#"UPDATE entries SET title = ?2, body = ?3, entry_date = ?4, entry_timezone = ?5, source_path = ?6,
+                            source_type = ?7, text_hash = ?8, created_at = ?9, updated_at = ?10, sentiment = ?11,
+                            language = ?12, sensitive = ?13, body_sealed = ?14,
+                            embedding_dirty = CASE WHEN text_hash IS NOT ?8 THEN 1 ELSE embedding_dirty END
+                        WHERE id = ?1"#,
+                    params![
+                        entry.id,
+                        entry.title,
+                        entry.body,
+                        entry.entry_date,
+                        entry.entry_timezone,
+                        entry.source_path,
+                        entry.source_type,
+                        entry.text_hash,
+                        entry.created_at,
+                        entry.updated_at,
+                        entry.sentiment,
+                        entry.language,
+                        sensitive,
+                        body_sealed,
+                    ],
+                )?;
+                if text_hash != &entry.text_hash {
+                    conn.execute("DELETE FROM entity_extractions WHERE entry_id = ?1", params![entry.id])?;
+                }
+                if sensitive {
+                    crate::sensitive::clear_derived(conn, &entry.id)?;
+                }
+            }
+            None => {
+                conn.execute(
+                    "INSERT INTO entries (id, title, body, entry_date, entry_timezone, source_path, source_type, text_hash,
+                                          created_at, updated_at, sentiment, language, sensitive, body_sealed, embedding_dirty)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, NOT ?13)",
+                    params![
+                        entry.id,
+                        entry.title,
+                        entry.body,
+                        entry.entry_date,
+                        entry.entry_timezone,
+                        entry.source_path,
+                        entry.source_type,
+                        entry.text_hash,
+                        entry.created_at,
+                        entry.updated_at,
+                        entry.sentiment,
+                        entry.language,
+                        sensitive,
+                        body_sealed,
+                    ],
+                )?;
+            }
+        }
+        // A sealed entry's body column is empty, so this indexes only its title
+        conn.execute("DELETE FROM entries_fts WHERE entry_id = ?1", params![entry.id])?;
+        conn.execute(
+            "INSERT INTO entries_fts (title, body, entry_id) VALUES (?1, ?2, ?3)",
+            params![entry.title.clone().unwrap_or_default(), entry.body, entry.id],
+        )?;
+        crate::database::add_entry_tags(conn, &entry.id, &entry.tags)?;
+        applied += 1;
+    }
+    if let Some(tx) = tx {
+        tx.commit()?;
+    }
+    Ok((applied, duplicates))
+}
+
+// Deletes entries the peer deleted, unless they were edited here after the deletion
+fn apply_deletions(conn: &Connection, deletions: &[Tombstone]) -> Result<u32> {
+    let mut deleted = 0;
+    let tx = conn.unchecked_transaction()?;
+    for tombstone in deletions {
+        let updated_at: Option<String> = tx
+            .query_row("SELECT updated_at FROM entries WHERE id = ?1", params![tombstone.entry_id], |r| r.get(0))
+            .optional()?;
+        let Some(updated_at) = updated_at else { continue };
+        if timestamp(&updated_at) > timestamp(&tombstone.deleted_at) {
+            continue;
+        }
+        tx.execute("DELETE FROM entries_fts WHERE entry_id = ?1", params![tombstone.entry_id])?;
+        tx.execute("DELETE FROM entries WHERE id = ?1", params![tombstone.entry_id])?;
+        // Keep the peer's deletion time rather than now
+        tx.execute(
+            "UPDATE entry_tombstones SET deleted_at = ?2 WHERE entry_id = ?1",
+            params![tombstone.entry_id, tombstone.deleted_at],
+        )?;
+        deleted += 1;
+    }
+    tx.commit()?;
+    Ok(deleted)
+}
+
+async fn write_frame(stream: &mut TcpStream, bytes: &[u8]) -> Result<()> {
+    let write = async {
+        stream.write_all(&(bytes.len() as u32).to_be_bytes()).await?;
+        stream.write_all(bytes).await?;
+        stream.flush().await
+    };
+    tokio::time::timeout(IO_TIMEOUT, write).await.context("Timed out sending to the peer")??;
+    Ok(())
+}
+
+async fn read_frame(stream: &mut TcpStream) -> Result<Vec<u8>> {
+    let read = async {
+        let mut len = [0u8; 4];
+        stream.read_exact(&mut len).await?;
+        let len = u32::from_be_bytes(len) as usize;
+        if len > MAX_FRAME_BYTES {
+            anyhow::bail!("Message from the peer is too large");
+        }
+        let mut buf = vec![0u8; len];
+        stream.read_exact(&mut buf).await?;
+        Ok(buf)
+    };
+    tokio::time::timeout(IO_TIMEOUT, read).await.context("Timed out waiting for the peer")?
+}
+
+fn random_challenge() -> String {
+    let mut challenge = [0u8; 16];
+    rand::thread_rng().fill_bytes(&mut challenge);
+    base64::engine::general_purpose::STANDARD.encode(challenge)
+}
+
+// Sealing and sequencing for one session, whose id depends on both sides' challenges
+struct Session {
+    key: [u8; 32],
+    id: String,
+    is_server: bool,
+    sent: u64,
+    received: u64,
+}
+
+impl Session {
+    fn new(key: [u8; 32], server_challenge: &str, client_challenge: &str, is_server: bool) -> Self {
+        let mut hasher = Sha256::new();
+        hasher.update(b"journal-reader-lan-session\0");
+        hasher.update(server_challenge.as_bytes());
+        hasher.update([0]);
+        hasher.update(client_challenge.as_bytes());
+        Self { key, id: format!("{:x}", hasher.finalize()), is_server, sent: 0, received: 0 }
+    }
+
+    fn seal<T: Serialize>(&mut self, message: T) -> Result<Vec<u8>> {
+        let envelope = Envelope { session: self.id.clone(), from_server: self.is_server, seq: self.sent, message };
+        self.sent += 1;
+        crate::encryption::seal(&self.key, &serde_json::to_vec(&envelope)?)
+    }
+
+    fn open<T: for<'de> Deserialize<'de>>(&mut self, frame: &[u8]) -> Result<T> {
+        let plain = crate::encryption::open(&self.key, frame)
+            .map_err(|_| anyhow::anyhow!("The peer uses a different pairing code"))?;
+        let envelope: Envelope<T> = serde_json::from_slice(&plain).context("Unreadable message from the peer")?;
+        if envelope.session != self.id || envelope.from_server == self.is_server || envelope.seq != self.received {
+            anyhow::bail!("Out-of-sequence message from the peer");
+        }
+        self.received += 1;
+        Ok(envelope.message)
+    }
+}
+
+// Sealed, sequenced messages over a connection
+struct Channel {
+    stream: TcpStream,
+    session: Session,
+}
+
+impl Channel {
+    async fn send<T: Serialize>(&mut self, message: T) -> Result<()> {
+        let sealed = self.session.seal(message)?;
+        write_frame(&mut self.stream, &sealed).await
+    }
+
+    async fn receive<T: for<'de> Deserialize<'de>>(&mut self) -> Result<T> {
+        let frame = read_frame(&mut self.stream).await?;
+        self.session.open(&frame)
+    }
+}
+
+// Syncs with the device listening at `address` ("host:port")
+pub async fn sync_with(app_handle: &AppHandle, address: &str) -> Result<LanSyncReport> {
+    let key = channel_key()?;
+    let (local_id, local_name, local_sensitive_key) = {
+        let conn = crate::database::open_conn(app_handle)?;
+        let id = device_id(&conn)?;
+        (id.clone(), device_name(&conn, &id), sensitive_key(&conn))
+    };
+    let started_at = Utc::now().to_rfc3339();
+    let mut stream = tokio::time::timeout(Duration::from_secs(10), TcpStream::connect(address))
+        .await
+        .with_context(|| format!("Timed out connecting to {}", address))?
+        .with_context(|| format!("Could not connect to {}", address))?;
+    let hello: Hello = serde_json::from_slice(&read_frame(&mut stream).await?).context("Not a Journal Reader sync peer")?;
+    if hello.protocol != PROTOCOL_VERSION {
+        anyhow::bail!("The peer uses sync protocol {}; this version speaks {}", hello.protocol, PROTOCOL_VERSION);
+    }
+    if hello.device_id == local_id {
+        anyhow::bail!("That address is this device");
+    }
+    // Our own challenge, so a recorded session replayed by an impostor server can't be reused
+    let challenge = random_challenge();
+    let reply = Hello { protocol: PROTOCOL_VERSION, device_id: local_id.clone(), name: local_name.clone(), challenge: challenge.clone() };
+    write_frame(&mut stream, &serde_json::to_vec(&reply)?).await?;
+    let mut channel = Channel { stream, session: Session::new(key, &hello.challenge, &challenge, false) };
+    channel.send(Request::Manifest { device_id: local_id, name: local_name, sensitive_key: local_sensitive_key }).await?;
+    let (remote_items, remote_deletions, remote_sensitive_key) = match channel.receive().await? {
+        Response::Manifest { entries, deletions, sensitive_key } => (entries, deletions, sensitive_key),
+        other => return Err(unexpected(other)),
+    };
+
+    let source = format!("lan-sync:{}", hello.name);
+    let mut report = LanSyncReport { peer: hello.name.clone(), ..Default::default() };
+    let (local, local_deletions, last_sync, manual) = {
+        let conn = crate::database::open_conn(app_handle)?;
+        adopt_sensitive_key(&conn, remote_sensitive_key.as_ref())?;
+        let local: HashMap<String, BackupEntry> =
+            crate::backup::collect_entries(&conn, &EntryFilter::default())?.into_iter().map(|e| (e.id.clone(), e)).collect();
+        (local, tombstones(&conn)?, last_synced(&conn, &hello.device_id)?, manual_conflicts(&conn))
+    };
+    let local_deleted: HashMap<&str, &str> = local_deletions.iter().map(|t| (t.entry_id.as_str(), t.deleted_at.as_str())).collect();
+    let remote_ids: BTreeSet<&str> = remote_items.iter().map(|i| i.id.as_str()).collect();
+
+    // Entries to fetch, and peer entries this device deleted afterwards
+    let mut fetch = Vec::new();
+    let mut delete_there = Vec::new();
+    for item in &remote_items {
+        match local.get(&item.id) {
+            Some(entry) if digest(entry) == item.digest => {}
+            Some(_) => fetch.push(item.id.clone()),
+            None => match local_deleted.get(item.id.as_str()) {
+                Some(deleted_at) if timestamp(deleted_at) >= timestamp(&item.updated_at) => delete_there.push(Tombstone {
+                    entry_id: item.id.clone(),
+                    deleted_at: deleted_at.to_string(),
+                }),
+                _ => fetch.push(item.id.clone()),
+            },
+        }
+    }
+    // Entries only here are sent unless the peer deleted them since
+    let remote_deleted: HashMap<&str, &str> = remote_deletions.iter().map(|t| (t.entry_id.as_str(), t.deleted_at.as_str())).collect();
+    let mut send: Vec<BackupEntry> = Vec::new();
+    let mut delete_here = Vec::new();
+    for entry in local.values().filter(|e| !remote_ids.contains(e.id.as_str())) {
+        match remote_deleted.get(entry.id.as_str()) {
+            Some(deleted_at) if timestamp(deleted_at) >= timestamp(&entry.updated_at) => delete_here.push(Tombstone {
+                entry_id: entry.id.clone(),
+                deleted_at: deleted_at.to_string(),
+            }),
+            _ => send.push(entry.clone()),
+        }
+    }
+
+    let mut received = Vec::new();
+    for ids in fetch.chunks(BATCH_SIZE) {
+        channel.send(Request::Fetch { ids: ids.to_vec() }).await?;
+        match channel.receive().await? {
+            Response::Entries { entries } => received.extend(entries),
+            other => return Err(unexpected(other)),
+        }
+    }
+    let mut apply_here = Vec::new();
+    let mut conflicts = Vec::new();
+    for remote in received {
+        match reconcile(local.get(&remote.id), remote, last_sync, manual) {
+            Outcome::Settled { entry, apply_here: here, send: there, superseded } => {
+                conflicts.extend(superseded.map(|e| *e));
+                if there {
+                    send.push(entry.clone());
+                }
+                if here {
+                    apply_here.push(entry);
+                }
+            }
+            Outcome::Conflict(remote) => conflicts.push(remote),
+        }
+    }
+    {
+        let conn = crate::database::open_conn(app_handle)?;
+        let (applied, duplicates) = apply_entries(&conn, &apply_here, false)?;
+        report.received = applied;
+        report.duplicates = duplicates;
+        report.deleted_here = apply_deletions(&conn, &delete_here)?;
+        for remote in &conflicts {
+            crate::conflicts::record(&conn, remote, &source)?;
+        }
+        report.conflicts = conflicts.len() as u32;
+    }
+    crate::vector_index::invalidate_entries(apply_here.iter().map(|e| &e.id).chain(delete_here.iter().map(|t| &t.entry_id)));
+
+    let mut batches: Vec<(Vec<BackupEntry>, Vec<Tombstone>)> = send.chunks(BATCH_SIZE).map(|c| (c.to_vec(), Vec::new())).collect();
+    if !delete_there.is_empty() {
+        batches.push((Vec::new(), delete_there));
+    }
+    for (entries, deletions) in batches {
+        channel.send(Request::Push { entries, deletions }).await?;
+        match channel.receive().await? {
+            Response::Pushed { applied, deleted } => {
+                report.sent += applied;
+                report.deleted_there += deleted;
+            }
+            other => return Err(unexpected(other)),
+        }
+    }
+    channel.send(Request::Finish).await?;
+    match channel.receive().await? {
+        Response::Finished => {}
+        other => return Err(unexpected(other)),
+    }
+    let conn = crate::database::open_conn(app_handle)?;
+    record_peer(&conn, &hello.device_id, &hello.name, Some(address), &started_at)?;
+    tracing::info!(
+        "synced with {}: {} received, {} sent, {} conflicts",
+        hello.name, report.received, report.sent, report.conflicts
+    );
+    Ok(report)
+}
+
+fn unexpected(response: Response) -> anyhow::Error {
+    match response {
+        Response::Error { message } => anyhow::anyhow!("The peer reported an error: {}", message),
+        _ => anyhow::anyhow!("Unexpected reply from the peer"),
+    }
+}
+
+async fn serve_connection(app_handle: AppHandle, mut stream: TcpStream) -> Result<()> {
+    let key = channel_key()?;
+    let (local_id, local_name) = {
+        let conn = crate::database::open_conn(&app_handle)?;
+        let id = device_id(&conn)?;
+        (id.clone(), device_name(&conn, &id))
+    };
+    let challenge = random_challenge();
+    let hello = Hello { protocol: PROTOCOL_VERSION, device_id: local_id, name: local_name, challenge: challenge.clone() };
+    write_frame(&mut stream, &serde_json::to_vec(&hello)?).await?;
+    let reply: Hello = serde_json::from_slice(&read_frame(&mut stream).await?).context("Not a Journal Reader sync peer")?;
+    if reply.protocol != PROTOCOL_VERSION {
+        anyhow::bail!("The peer uses sync protocol {}; this version speaks {}", reply.protocol, PROTOCOL_VERSION);
+    }
+    let mut channel = Channel { stream, session: Session::new(key, &challenge, &reply.challenge, true) };
+
+    let started_at = Utc::now().to_rfc3339();
+    let mut peer: Option<(String, String)> = None;
+    loop {
+        let request: Request = channel.receive().await?;
+        let response = match handle_request(&app_handle, request, &mut peer, &started_at) {
+            Ok(Some(response)) => response,
+            Ok(None) => {
+                channel.send(Response::Finished).await?;
+                return Ok(());
+            }
+            Err(e) => Response::Error { message: e.to_string() },
+        };
+        channel.send(response).await?;
+    }
+}
+
+// Answers one request from a syncing peer; None once the peer is done
+fn handle_request(
+    app_handle: &AppHandle,
+    request: Request,
+    peer: &mut Option<(String, String)>,
+    started_at: &str,
+) -> Result<Option<Response>> {
+    let conn = crate::database::open_conn(app_handle)?;
+    let response = match request {
+        Request::Manifest { device_id, name, sensitive_key: key } => {
+            adopt_sensitive_key(&conn, key.as_ref())?;
+            *peer = Some((device_id, name));
+            let entries = crate::backup::collect_entries(&conn, &EntryFilter::default())?;
+            Response::Manifest {
+                entries: entries.iter().map(manifest_item).collect(),
+                deletions: tombstones(&conn)?,
+                sensitive_key: sensitive_key(&conn),
+            }
+        }
+        Request::Fetch { ids } => {
+            let wanted: BTreeSet<String> = ids.into_iter().collect();
+            let entries = crate::backup::collect_entries(&conn, &EntryFilter::default())?;
+            Response::Entries { entries: entries.into_iter().filter(|e| wanted.contains(&e.id)).collect() }
+        }
+        Request::Push { entries, deletions } => {
+            let (applied, _) = apply_entries(&conn, &entries, true)?;
+            let deleted = apply_deletions(&conn, &deletions)?;
+            crate::vector_index::invalidate_entries(entries.iter().map(|e| &e.id).chain(deletions.iter().map(|t| &t.entry_id)));
+            // Received entries are marked dirty, so the edit worker embeds them
+            if applied > 0 && crate::database::setting_enabled(&conn, "auto_embed", true) {
+                crate::edit_embeddings::schedule(app_handle);
+            }
+            Response::Pushed { applied, deleted }
+        }
+        Request::Finish => {
+            if let Some((device_id, name)) = peer.as_ref() {
+                record_peer(&conn, device_id, name, None, started_at)?;
+                tracing::info!("{} synced with this device", name);
+            }
+            return Ok(None);
+        }
+    };
+    Ok(Some(response))
+}
+
+// Listens and advertises while `lan_sync_enabled` is on; `restart` re-reads the settings
+pub fn start(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            // The database may not be open yet at startup
+            let config = crate::database::open_conn(&app_handle).and_then(|conn| {
+                let id = device_id(&conn)?;
+                Ok((crate::database::setting_enabled(&conn, "lan_sync_enabled", false), port(&conn), device_name(&conn, &id), id))
+            });
+            match config {
+                Ok((true, port, name, id)) => {
+                    if let Err(e) = listen(&app_handle, port, &name, &id).await {
+                        tracing::warn!("listener stopped: {}", e);
+                        tokio::time::sleep(Duration::from_secs(30)).await;
+                    }
+                }
+                Ok(_) => restart_signal().notified().await,
+                Err(_) => tokio::time::sleep(Duration::from_secs(5)).await,
+            }
+        }
+    });
+}
+
+pub fn restart() {
+    restart_signal().notify_one();
+}
+
+async fn listen(app_handle: &AppHandle, port: u16, name: &str, id: &str) -> Result<()> {
+    let listener = TcpListener::bind(("0.0.0.0", port)).await.with_context(|| format!("Could not listen on port {}", port))?;
+    let mdns = advertise(port, name, id)
+        .map_err(|e| tracing::warn!("mDNS advertising unavailable: {}", e))
+        .ok();
+    *listening_port().lock().unwrap() = Some(port);
+    tracing::info!("listening on port {}", port);
+    let outcome = loop {
+        tokio::select! {
+            accepted = listener.accept() => {
+                let (stream, addr) = match accepted {
+                    Ok(a) => a,
+                    Err(e) => break Err(e.into()),
+                };
+                let handle = app_handle.clone();
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) = serve_connection(handle, stream).await {
+                        tracing::warn!("session with {} failed: {}", addr, e);
+                    }
+                });
+            }
+            _ = restart_signal().notified() => break Ok(()),
+        }
+    };
+    *listening_port().lock().unwrap() = None;
+    if let Some(daemon) = mdns {
+        let _ = daemon.shutdown();
+    }
+    outcome
+}
+
+fn advertise(port: u16, name: &str, id: &str) -> Result<mdns_sd::ServiceDaemon> {
+    let daemon = mdns_sd::ServiceDaemon::new()?;
+    let properties = HashMap::from([("id".to_string(), id.to_string()), ("name".to_string(), name.to_string())]);
+    let info = mdns_sd::ServiceInfo::new(SERVICE_TYPE, id, &format!("{}.local.", id), "", port, properties)?.enable_addr_auto();
+    daemon.register(info)?;
+    Ok(daemon)
+}
+
+// Journal Reader devices advertising on the local network, other than this one
+pub async fn discover(app_handle: &AppHandle) -> Result<Vec<DiscoveredPeer>> {
+    let (own_id, known) = {
+        let conn = crate::database::open_conn(app_handle)?;
+        (device_id(&conn)?, known_peers(&conn)?)
+    };
+    let found = tokio::task::spawn_blocking(move || -> Result<Vec<(String, String, String)>> {
+        let daemon = mdns_sd::ServiceDaemon::new()?;
+        let receiver = daemon.browse(SERVICE_TYPE)?;
+        let deadline = std::time::Instant::now() + DISCOVERY_TIME;
+        let mut found = Vec::new();
+        while let Some(left) = deadline.checked_duration_since(std::time::Instant::now()) {
+            let Ok(event) = receiver.recv_timeout(left) else { break };
+            if let mdns_sd::ServiceEvent::ServiceResolved(info) = event {
+                let Some(id) = info.get_property_val_str("id").map(str::to_string) else { continue };
+                let name = info.get_property_val_str("name").unwrap_or(&id).to_string();
+                // IPv4 first; a link-local IPv6 address would need a scope id
+                let mut addresses: Vec<_> = info.get_addresses().iter().copied().collect();
+                addresses.sort_by_key(|a| !a.is_ipv4());
+                if let Some(ip) = addresses.first() {
+                    found.push((id, name, std::net::SocketAddr::new(*ip, info.get_port()).to_string()));
+                }
+            }
+        }
+        let _ = daemon.shutdown();
+        Ok(found)
+    })
+    .await??;
+    let mut peers: Vec<DiscoveredPeer> = Vec::new();
+    for (device_id, name, address) in found {
+        if device_id == own_id || peers.iter().any(|p| p.device_id == device_id) {
+            continue;
+        }
+        let last_synced_at = known.iter().find(|p| p.device_id == device_id).and_then(|p| p.last_synced_at.clone());
+        peers.push(DiscoveredPeer { device_id, name, address, last_synced_at });
+    }
+    Ok(peers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: &str, text: &str, updated_at: &str, tags: &[&str]) -> BackupEntry {
+        BackupEntry {
+            id: id.to_string(),
+            title: None,
+            body: text.to_string(),
+            entry_date: "2024-03-01T08:00:00+00:00".to_string(),
+            entry_timezone: "UTC".to_string(),
+            source_path: "sync".to_string(),
+            source_type: "txt".to_string(),
+            text_hash: format!("{:x}", Sha256::digest(text.as_bytes())),
+            created_at: "2024-03-01T08:00:00+00:00".to_string(),
+            updated_at: updated_at.to_string(),
+            sentiment: None,
+            language: None,
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            body_sealed: None,
+        }
+    }
+
+    #[test]
+    fn test_frames_cannot_be_replayed_into_another_session() {
+        let key = derive_channel_key("pairing code");
+        let server_challenge = random_challenge();
+        let recorded = {
+            let mut server = Session::new(key, &server_challenge, &random_challenge(), true);
+            server.seal(Response::Finished).unwrap()
+        };
+
+        // An impostor reuses the recorded challenge, but the client brings a fresh one
+        let mut client = Session::new(key, &server_challenge, &random_challenge(), false);
+        assert!(client.open::<Response>(&recorded).is_err());
+
+        let client_challenge = random_challenge();
+        let mut server = Session::new(key, &server_challenge, &client_challenge, true);
+        let mut client = Session::new(key, &server_challenge, &client_challenge, false);
+        let frame = server.seal(Response::Finished).unwrap();
+        assert!(matches!(client.open::<Response>(&frame).unwrap(), Response::Finished));
+        // Nor twice within the same session
+        assert!(client.open::<Response>(&frame).is_err());
+    }
+
+    #[test]
+    fn test_reconcile_newest_wins_and_tags_union() {
+        let local = entry("a", "old text", "2024-03-01T09:00:00+00:00", &["walks"]);
+        let remote = entry("a", "new text", "2024-03-02T09:00:00+00:00", &["lake"]);
+        match reconcile(Some(&local), remote, None, false) {
+            Outcome::Settled { entry, apply_here, send, superseded } => {
+                assert_eq!(entry.body, "new text");
+                assert_eq!(entry.tags, vec!["lake", "walks"]);
+                assert!(apply_here && send);
+                // Never synced before, so the older edit is kept for review
+                assert_eq!(superseded.unwrap().body, "old text");
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+
+        // Same content, only tags differ: nothing to change on the side that has them all
+        let local = entry("a", "text", "2024-03-01T09:00:00+00:00", &["lake", "walks"]);
+        let remote = entry("a", "text", "2024-03-01T09:00:00+00:00", &["walks"]);
+        assert!(matches!(reconcile(Some(&local), remote, None, false), Outcome::Settled { apply_here: false, send: true, .. }));
+    }
+
+    #[test]
+    fn test_reconcile_manual_conflicts_only_when_both_edited() {
+        let last_sync = Some(timestamp("2024-03-01T12:00:00+00:00"));
+        let local = entry("a", "edited here", "2024-03-02T09:00:00+00:00", &[]);
+        let remote = entry("a", "edited there", "2024-03-03T09:00:00+00:00", &[]);
+        assert!(matches!(reconcile(Some(&local), remote, last_sync, true), Outcome::Conflict(_)));
+
+        // Only the peer changed it since the last sync
+        let local = entry("a", "unchanged", "2024-03-01T09:00:00+00:00", &[]);
+        let remote = entry("a", "edited there", "2024-03-03T09:00:00+00:00", &[]);
+        assert!(matches!(
+            reconcile(Some(&local), remote, last_sync, true),
+            Outcome::Settled { apply_here: true, send: false, .. }
+        ));
+    }
+
+    #[test]
+    fn test_apply_entries_and_tombstones() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::migrations::run_migrations(&mut conn).unwrap();
+        let first = entry("a", "a walk by the lake", "2024-03-01T09:00:00+00:00", &["walks"]);
+        assert_eq!(apply_entries(&conn, std::slice::from_ref(&first), true).unwrap(), (1, 0));
+
+        // An older copy is ignored when only newer ones are accepted
+        let older = entry("a", "a walk", "2024-02-01T09:00:00+00:00", &[]);
+        assert_eq!(apply_entries(&conn, &[older], true).unwrap(), (0, 0));
+        // Same text under another id is a duplicate
+        assert_eq!(apply_entries(&conn, &[entry("b", "a walk by the lake", "2024-03-05T09:00:00+00:00", &[])], false).unwrap(), (0, 1));
+
+        let stale = Tombstone { entry_id: "a".to_string(), deleted_at: "2024-02-15T00:00:00+00:00".to_string() };
+        assert_eq!(apply_deletions(&conn, &[stale]).unwrap(), 0);
+        let deletion = Tombstone { entry_id: "a".to_string(), deleted_at: "2024-03-02T00:00:00+00:00".to_string() };
+        assert_eq!(apply_deletions(&conn, std::slice::from_ref(&deletion)).unwrap(), 1);
+        assert_eq!(tombstones(&conn).unwrap(), vec![deletion]);
+
+        // Receiving the entry again clears its tombstone
+        apply_entries(&conn, &[first], false).unwrap();
+        assert!(tombstones(&conn).unwrap().is_empty());
+        let tags: i64 = conn.query_row("SELECT COUNT(*) FROM entry_tags WHERE entry_id = 'a'", [], |r| r.get(0)).unwrap();
+        assert_eq!(tags, 1);
+    }
+
+    #[test]
+    fn test_channel_key_depends_on_code() {
+        assert_eq!(derive_channel_key("abc"), derive_channel_key("abc"));
+        assert_ne!(derive_channel_key("abc"), derive_channel_key("abd"));
+    }
+}
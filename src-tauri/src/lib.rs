@@ -1,32 +1,82 @@
 // use tauri::Manager; // not needed currently
-use serde::{Deserialize, Serialize};
 
+mod app_import;
+mod archive;
+mod backup;
+mod blog;
+mod chunks;
+pub mod cli;
+mod clustering;
 mod commands;
+mod conflicts;
+mod conversations;
+mod csv_export;
 mod database;
+mod daylio;
+mod db_pool;
+mod diaro;
+mod documents;
+mod edit_embeddings;
+mod embeddings;
+mod encryption;
+mod entities;
+mod error;
+mod facebook;
+mod feed;
+mod fts_query;
+mod fuzzy;
+mod gdrive;
+mod goals;
+mod gratitude;
+mod highlight;
+mod history;
+mod hooks;
+mod imap;
 mod import;
-// mod search; // removed in simplified build
-// mod ai; // removed in simplified build
+mod journals;
+mod keep;
+mod lan_sync;
+mod livejournal;
+mod local_embeddings;
+mod locale;
+mod logging;
+mod migrations;
+mod near_duplicates;
+mod oauth_loopback;
+mod on_this_day;
+mod pdf_export;
+mod pdf_import;
+mod photos;
+mod preview;
+mod prompts;
+mod rerank;
+mod resync;
+mod s3_backup;
+mod search_cancel;
+mod sms;
+mod date_phrases;
+mod query_plan;
+mod quantize;
+mod redate;
+mod ai_http;
+mod ai_cache;
+mod ai_audit;
+mod review;
+mod stats;
+mod summaries;
+mod templates;
+mod tokens;
+mod tts;
+mod search;
+mod secrets;
+mod sensitive;
+mod sentiment;
+mod vector_index;
+mod versions;
+mod ai;
+mod ai_stream;
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct AppError {
-    pub message: String,
-    pub code: Option<String>,
-}
-
-impl std::fmt::Display for AppError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.message)
-    }
-}
-
-impl From<anyhow::Error> for AppError {
-    fn from(error: anyhow::Error) -> Self {
-        Self {
-            message: error.to_string(),
-            code: None,
-        }
-    }
-}
+pub use error::{AppError, AppErrorKind};
 
 type Result<T> = std::result::Result<T, AppError>;
 
@@ -37,6 +87,17 @@ pub fn run() {
         .plugin(tauri_plugin_sql::Builder::default().build())
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_notification::init())
+        .plugin(tauri_plugin_deep_link::init())
+        .on_window_event(|window, event| {
+            // Clicking an "on this day" notification focuses the app; open that day
+            if let tauri::WindowEvent::Focused(true) = event {
+                if let Some(link) = on_this_day::take_pending_link() {
+                    use tauri::Emitter;
+                    let _ = window.emit("app://navigate", link);
+                }
+            }
+        })
         .invoke_handler(tauri::generate_handler![
             commands::greet,
             commands::init_database,
@@ -44,27 +105,172 @@ pub fn run() {
             commands::update_setting,
             commands::scan_import_files,
             commands::import_files_with_dates,
+            commands::import_keep_takeout,
+            commands::import_diaro_export,
+            commands::import_daylio_csv,
+            commands::import_photo_folder,
+            commands::import_livejournal_export,
+            commands::import_blog_export,
+            commands::import_facebook_export,
+            commands::list_sms_contacts,
+            commands::import_sms_backup,
+            commands::import_feed,
+            commands::get_imap_status,
+            commands::fetch_imap_mail,
+            commands::preview_pdf_split,
+            commands::import_pdf,
+            commands::export_journal_json,
+            commands::import_journal_json,
+            commands::run_s3_backup,
+            commands::list_s3_backups,
+            commands::restore_s3_backup,
+            commands::get_lan_sync_status,
+            commands::generate_lan_sync_code,
+            commands::discover_lan_peers,
+            commands::sync_with_lan_peer,
+            commands::list_conflicts,
+            commands::resolve_conflict,
+            commands::export_settings,
+            commands::import_settings,
             commands::get_available_years,
+            commands::get_languages,
             commands::get_month_counts_for_year,
             commands::list_entries_for_month,
             commands::get_entry_by_id,
+            commands::get_entry_original,
+            commands::get_entry_photos,
+            commands::read_entry_photo,
+            commands::export_entry_original,
+            commands::synthesize_entry_audio,
+            commands::read_entry_audio,
+            commands::export_entry_audio,
             commands::search_entries_simple,
+            commands::cancel_search,
+            commands::export_search_results,
+            commands::search_entries_advanced,
+            commands::find_similar_entries,
+            commands::get_search_history,
+            commands::clear_search_history,
+            commands::get_search_suggestions,
             commands::get_db_diagnostics,
+            commands::get_journal_statistics,
+            commands::get_writing_streaks,
+            commands::get_writing_times,
+            commands::get_tag_trends,
+            commands::get_year_summary_stats,
+            commands::run_db_maintenance,
+            commands::rebuild_search_index,
+            commands::repair_search_index,
+            commands::get_recent_logs,
+            commands::get_date_locales,
+            commands::get_embedding_status,
+            commands::backfill_embeddings,
+            commands::reembed_all_entries,
+            commands::clear_ai_cache,
+            commands::get_ai_audit_log,
+            commands::get_database_encryption_status,
+            commands::set_database_passphrase,
+            commands::unlock_database,
+            commands::get_sensitive_status,
+            commands::set_sensitive_passphrase,
+            commands::unlock_sensitive,
+            commands::lock_sensitive,
+            commands::set_entry_sensitive,
+            commands::list_journals,
+            commands::create_journal,
+            commands::switch_journal,
+            commands::relocate_database,
+            commands::resync_from_sources,
+            commands::find_near_duplicates,
+            commands::merge_near_duplicates,
+            commands::dismiss_near_duplicate,
+            commands::propose_redates,
+            commands::apply_redates,
+            commands::test_hooks,
+            commands::get_on_this_day,
+            commands::list_templates,
+            commands::save_template,
+            commands::delete_template,
+            commands::create_entry_from_template,
+            commands::update_entry,
+            commands::list_entry_versions,
+            commands::diff_entry_versions,
             commands::test_ai_connection,
+            commands::list_ollama_models,
+            commands::set_secret,
+            commands::has_secret,
+            commands::chat_completion,
+            commands::extract_tags_ai,
+            commands::chat_completion_stream,
+            commands::rag_query_stream,
+            commands::cancel_ai_stream,
+            commands::ask_question,
+            commands::process_rag_query,
+            commands::get_conversations_list,
+            commands::get_conversation_history,
+            commands::delete_conversation,
+            commands::summarize_period,
+            commands::list_summaries,
+            commands::export_pdf,
+            commands::generate_year_in_review,
+            commands::get_sentiment_timeline,
+            commands::extract_entities,
+            commands::get_entities,
+            commands::track_goals,
+            commands::list_goals,
+            commands::extract_gratitude,
+            commands::list_gratitude,
+            commands::export_gratitude,
+            commands::cluster_entries,
+            commands::generate_writing_prompts,
             commands::get_google_oauth_status,
             commands::google_oauth_start,
             commands::google_oauth_complete,
+            commands::google_oauth_wait,
             commands::google_import_doc_by_file_id,
+            commands::google_import_folder,
             
         ])
         .setup(|app| {
+            if let Ok(dir) = database::get_db_dir(app.handle()) {
+                if let Err(e) = logging::init(&dir.join("logs")) {
+                    eprintln!("Failed to start logging: {}", e);
+                }
+                #[cfg(target_os = "android")]
+                if let Ok(data) = tauri::Manager::path(app).app_data_dir() {
+                    secrets::set_store_dir(data.join("no_backup"), &dir);
+                }
+                local_embeddings::set_model_dir(dir.join("models"));
+                ai_audit::set_db_dir(dir);
+            }
             // Initialize the database on startup
             let app_handle = app.handle().clone();
             tauri::async_runtime::spawn(async move {
                 if let Err(e) = database::init_database(&app_handle).await {
-                    eprintln!("Failed to initialize database: {}", e);
+                    tracing::error!("Failed to initialize database: {}", e);
+                    return;
+                }
+                if let Ok(conn) = database::open_conn(&app_handle) {
+                    logging::apply_setting(&conn);
+                }
+                if let Err(e) = summaries::auto_weekly_recap(&app_handle).await {
+                    tracing::warn!("weekly recap failed: {}", e);
                 }
             });
+            // Google sign-in on iOS and Android comes back as an app link
+            {
+                use tauri_plugin_deep_link::DeepLinkExt;
+                let app_handle = app.handle().clone();
+                app.deep_link().on_open_url(move |event| {
+                    for url in event.urls() {
+                        tauri::async_runtime::spawn(commands::google_oauth_app_link(app_handle.clone(), url.to_string()));
+                    }
+                });
+            }
+            on_this_day::start_scheduler(app.handle().clone());
+            s3_backup::start_scheduler(app.handle().clone());
+            lan_sync::start(app.handle().clone());
+            imap::start_scheduler(app.handle().clone());
             Ok(())
         })
         .run(tauri::generate_context!())
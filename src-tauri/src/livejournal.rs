@@ -0,0 +1,161 @@
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use regex::Regex;
+use tauri::AppHandle;
+
+use crate::app_import::{html_to_text, local_to_utc, normalize_tags, AppEntry, AppImportReport};
+use crate::import::FileType;
+
+// LiveJournal and Dreamwidth import from their XML exports.
+//
+// The export page (export.bml) produces one file per month, `<livejournal><entry>...</entry>`,
+// so the importer takes a folder or several files at once; ljdump's per-entry files
+// (`L-<itemid>`, `<event>` at the root) work too. Each entry has `itemid`, `eventtime` (the
+// poster's local time, no zone), `subject`, `event` (HTML) and, depending on the site and
+// exporter, `taglist` either directly or under `<props>`. The current mood becomes a
+// "mood:<name>" tag. Event times are read in the time zone chosen for the import.
+
+fn child_text(node: &roxmltree::Node, name: &str) -> String {
+    node.children()
+        .find(|c| c.has_tag_name(name))
+        .map(|c| c.descendants().filter(|d| d.is_text()).filter_map(|d| d.text()).collect::<String>())
+        .map(|t| t.trim().to_string())
+        .unwrap_or_default()
+}
+
+// A value stored on the entry or, in ljdump files, under <props>
+fn prop(node: &roxmltree::Node, name: &str) -> String {
+    let direct = child_text(node, name);
+    if !direct.is_empty() {
+        return direct;
+    }
+    node.children().find(|c| c.has_tag_name("props")).map(|p| child_text(&p, name)).unwrap_or_default()
+}
+
+// LJ markup: <lj user="name"> becomes the name, <lj-cut> and similar wrappers are dropped
+fn lj_to_text(event: &str) -> String {
+    static USER: OnceLock<Regex> = OnceLock::new();
+    static CUT: OnceLock<Regex> = OnceLock::new();
+    let user = USER.get_or_init(|| Regex::new(r#"(?i)<(?:lj|user)\s+(?:user|name)=["']?([\w-]+)["']?[^>]*>"#).expect("lj user pattern"));
+    let cut = CUT.get_or_init(|| Regex::new(r"(?i)</?lj-[a-z]+[^>]*>").expect("lj tag pattern"));
+    let event = user.replace_all(event, "$1");
+    html_to_text(&cut.replace_all(&event, ""))
+}
+
+fn parse_event_time(time: &str, timezone: &str) -> Option<DateTime<Utc>> {
+    let time = time.trim();
+    let local = NaiveDateTime::parse_from_str(time, "%Y-%m-%d %H:%M:%S")
+        .or_else(|_| NaiveDateTime::parse_from_str(time, "%Y-%m-%d %H:%M"))
+        .ok()?;
+    Some(local_to_utc(local, timezone))
+}
+
+// Export files (.xml) and ljdump's entry files (L-<itemid>, no extension)
+fn export_files(paths: &[String]) -> Vec<PathBuf> {
+    let mut files = crate::app_import::files_with_extension(paths, &["xml"]);
+    files.extend(
+        paths
+            .iter()
+            .flat_map(|p| walkdir::WalkDir::new(p).into_iter().filter_map(|e| e.ok()))
+            .map(|e| e.into_path())
+            .filter(|p| p.is_file() && p.file_name().and_then(|n| n.to_str()).and_then(|n| n.strip_prefix("L-")).is_some_and(|id| id.parse::<u64>().is_ok())),
+    );
+    files.sort();
+    files.dedup();
+    files
+}
+
+pub fn parse_export(xml: &str, source: &str, timezone: &str) -> Result<Vec<AppEntry>> {
+    let doc = roxmltree::Document::parse(xml).context("Not a readable LiveJournal export")?;
+    let root = doc.root_element();
+    let items: Vec<roxmltree::Node> = if root.has_tag_name("livejournal") {
+        root.children().filter(|c| c.has_tag_name("entry")).collect()
+    } else if root.has_tag_name("event") || root.has_tag_name("entry") {
+        vec![root]
+    } else {
+        anyhow::bail!("Not a LiveJournal export (expected a <livejournal> root element)");
+    };
+    let mut entries = Vec::new();
+    for item in items {
+        let itemid = child_text(&item, "itemid");
+        let created = parse_event_time(&child_text(&item, "eventtime"), timezone)
+            .with_context(|| format!("Entry {} has no valid eventtime", itemid))?;
+        let mood = prop(&item, "current_mood");
+        let mood_tag = format!("mood:{}", mood);
+        let taglist = prop(&item, "taglist");
+        let mut labels: Vec<&str> = taglist.split(',').collect();
+        if !mood.is_empty() {
+            labels.push(&mood_tag);
+        }
+        entries.push(AppEntry {
+            source: format!("{}#{}", source, itemid),
+            title: Some(html_to_text(&child_text(&item, "subject"))).filter(|t| !t.is_empty()),
+            body: lj_to_text(&child_text(&item, "event")),
+            tags: normalize_tags(labels),
+            created,
+            timezone: timezone.to_string(),
+            sentiment: None,
+        });
+    }
+    Ok(entries)
+}
+
+pub async fn import_exports(app_handle: &AppHandle, paths: &[String], timezone: &str) -> Result<AppImportReport> {
+    let mut report = AppImportReport::default();
+    for file in export_files(paths) {
+        let source = file.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        let parsed = std::fs::read_to_string(&file).map_err(anyhow::Error::from).and_then(|xml| parse_export(&xml, &source, timezone));
+        match parsed {
+            Ok(entries) => crate::app_import::save_all(app_handle, entries, FileType::LiveJournal, &mut report).await?,
+            Err(e) => report.error(format!("{}: {}", file.display(), e)),
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_export() {
+        let xml = r#"<?xml version="1.0" encoding="utf-8"?>
+<livejournal>
+<entry>
+<itemid>12</itemid>
+<eventtime>2004-07-15 21:30:00</eventtime>
+<subject>Road trip</subject>
+<event>&lt;p&gt;Drove to the coast with &lt;lj user="annie"&gt;.&lt;/p&gt;&lt;lj-cut text="more"&gt;&lt;p&gt;Slept in the car.&lt;/p&gt;&lt;/lj-cut&gt;</event>
+<security>public</security>
+<current_mood>tired</current_mood>
+<taglist>travel, Friends</taglist>
+</entry>
+<entry>
+<itemid>13</itemid>
+<eventtime>2004-07-16 08:00</eventtime>
+<subject></subject>
+<event>Home again.</event>
+</entry>
+</livejournal>"#;
+        let entries = parse_export(xml, "2004-07.xml", "America/New_York").unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].title.as_deref(), Some("Road trip"));
+        assert_eq!(entries[0].body, "Drove to the coast with annie.\n\nSlept in the car.");
+        assert_eq!(entries[0].tags, ["friends", "mood:tired", "travel"]);
+        // 21:30 EDT
+        assert_eq!(entries[0].created.to_rfc3339(), "2004-07-16T01:30:00+00:00");
+        assert_eq!(entries[0].source, "2004-07.xml#12");
+        assert_eq!(entries[1].title, None);
+        assert!(entries[1].tags.is_empty());
+
+        // ljdump keeps one entry per file with its properties under <props>
+        let dump = "<event><itemid>5</itemid><eventtime>2003-01-01 10:00:00</eventtime><event>Hi</event><props><taglist>new year</taglist></props></event>";
+        let entries = parse_export(dump, "L-5", "UTC").unwrap();
+        assert_eq!(entries[0].tags, ["new year"]);
+
+        assert!(parse_export("<rss/>", "x.xml", "UTC").is_err());
+    }
+}
@@ -0,0 +1,113 @@
+use anyhow::Result;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+// In-process embeddings (Provider::Local).
+//
+// With the `local-embeddings` feature a small ONNX model runs inside the app through fastembed,
+// so vector search needs neither an Ollama install nor sending entries to OpenAI. Model files
+// are downloaded once into the app data dir on first use and the loaded model is kept for the
+// life of the process. Without the feature every call fails, which leaves entries pending and
+// search on its keyword fallback, the same as an unreachable server. The same feature provides
+// the cross-encoder used to rerank RAG candidates.
+
+pub const DEFAULT_MODEL: &str = "bge-small-en-v1.5";
+pub const MODELS: &[&str] = &["bge-small-en-v1.5", "all-minilm-l6-v2", "multilingual-e5-small"];
+
+static MODEL_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+// Where downloaded models are cached; set once at startup
+pub fn set_model_dir(dir: PathBuf) {
+    let _ = MODEL_DIR.set(dir);
+}
+
+pub fn available() -> bool {
+    cfg!(feature = "local-embeddings")
+}
+
+pub async fn embed(text: &str, model: &str) -> Result<Vec<f32>> {
+    let text = text.to_string();
+    let model = if model.is_empty() || model == "default" { DEFAULT_MODEL } else { model }.to_string();
+    tokio::task::spawn_blocking(move || imp::embed(&text, &model)).await?
+}
+
+// Relevance of each document to `query` from a local cross-encoder; higher is more relevant
+pub async fn rerank(query: &str, documents: Vec<String>) -> Result<Vec<f32>> {
+    let query = query.to_string();
+    tokio::task::spawn_blocking(move || imp::rerank(&query, &documents)).await?
+}
+
+#[cfg(feature = "local-embeddings")]
+mod imp {
+    use anyhow::Result;
+    use fastembed::{EmbeddingModel, RerankInitOptions, RerankerModel, TextEmbedding, TextInitOptions, TextRerank};
+    use std::sync::Mutex;
+
+    // One model at a time; switching models in settings replaces it
+    static LOADED: Mutex<Option<(String, TextEmbedding)>> = Mutex::new(None);
+    static RERANKER: Mutex<Option<TextRerank>> = Mutex::new(None);
+
+    fn model_for(name: &str) -> Result<EmbeddingModel> {
+        match name {
+            "bge-small-en-v1.5" => Ok(EmbeddingModel::BGESmallENV15),
+            "all-minilm-l6-v2" => Ok(EmbeddingModel::AllMiniLML6V2),
+            "multilingual-e5-small" => Ok(EmbeddingModel::MultilingualE5Small),
+            other => Err(anyhow::anyhow!(
+                "Unknown local embedding model '{}' (available: {})",
+                other,
+                super::MODELS.join(", ")
+            )),
+        }
+    }
+
+    pub fn embed(text: &str, model: &str) -> Result<Vec<f32>> {
+        let mut loaded = LOADED.lock().unwrap_or_else(|e| e.into_inner());
+        if loaded.as_ref().map(|(name, _)| name.as_str()) != Some(model) {
+            let mut options = TextInitOptions::new(model_for(model)?);
+            if let Some(dir) = super::MODEL_DIR.get() {
+                options = options.with_cache_dir(dir.clone());
+            }
+            tracing::info!("loading local model {}", model);
+            *loaded = Some((model.to_string(), TextEmbedding::try_new(options)?));
+        }
+        let (_, embedder) = loaded.as_mut().expect("model loaded above");
+        embedder
+            .embed(vec![text], None)?
+            .pop()
+            .ok_or_else(|| anyhow::anyhow!("Local model returned no embedding"))
+    }
+
+    pub fn rerank(query: &str, documents: &[String]) -> Result<Vec<f32>> {
+        let mut loaded = RERANKER.lock().unwrap_or_else(|e| e.into_inner());
+        if loaded.is_none() {
+            let mut options = RerankInitOptions::new(RerankerModel::BGERerankerBase);
+            if let Some(dir) = super::MODEL_DIR.get() {
+                options = options.with_cache_dir(dir.clone());
+            }
+            tracing::info!("loading local reranker");
+            *loaded = Some(TextRerank::try_new(options)?);
+        }
+        let reranker = loaded.as_mut().expect("reranker loaded above");
+        let documents: Vec<&str> = documents.iter().map(String::as_str).collect();
+        let mut scores = vec![f32::NEG_INFINITY; documents.len()];
+        for result in reranker.rerank(query, documents.as_slice(), false, None)? {
+            if let Some(score) = scores.get_mut(result.index) {
+                *score = result.score;
+            }
+        }
+        Ok(scores)
+    }
+}
+
+#[cfg(not(feature = "local-embeddings"))]
+mod imp {
+    use anyhow::Result;
+
+    pub fn embed(_text: &str, _model: &str) -> Result<Vec<f32>> {
+        Err(anyhow::anyhow!("This build does not include local embeddings (enable the `local-embeddings` feature)"))
+    }
+
+    pub fn rerank(_query: &str, _documents: &[String]) -> Result<Vec<f32>> {
+        Err(anyhow::anyhow!("This build does not include the local reranker (enable the `local-embeddings` feature)"))
+    }
+}
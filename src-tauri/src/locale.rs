@@ -0,0 +1,225 @@
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+use rusqlite::Connection;
+use serde::Serialize;
+
+// Dates as people read them, for exports and anything else the backend writes out.
+//
+// `locale` is a language tag ("en-US", "de", "pt-BR"); a tag this file has no names for falls
+// back to its language and then to en-US. Only languages PDF export's WinAnsi fonts can print
+// are included. `week_start` ("monday", "sunday" or "saturday") decides where weekly sentiment
+// buckets and weekly recaps begin and the order of weekdays in writing times. The frontend
+// formats the same settings with Intl.DateTimeFormat.
+
+pub const DEFAULT_LOCALE: &str = "en-US";
+pub const DEFAULT_WEEK_START: &str = "monday";
+
+struct DateNames {
+    tag: &'static str,
+    months: [&'static str; 12],
+    // Monday first
+    weekdays: [&'static str; 7],
+    // {weekday}, {day}, {month} and {year}
+    long: &'static str,
+    medium: &'static str,
+    month_year: &'static str,
+}
+
+const LOCALES: &[DateNames] = &[
+    DateNames {
+        tag: "en-US",
+        months: ["January", "February", "March", "April", "May", "June", "July", "August", "September", "October", "November", "December"],
+        weekdays: ["Monday", "Tuesday", "Wednesday", "Thursday", "Friday", "Saturday", "Sunday"],
+        long: "{weekday}, {month} {day}, {year}",
+        medium: "{month} {day}, {year}",
+        month_year: "{month} {year}",
+    },
+    DateNames {
+        tag: "en-GB",
+        months: ["January", "February", "March", "April", "May", "June", "July", "August", "September", "October", "November", "December"],
+        weekdays: ["Monday", "Tuesday", "Wednesday", "Thursday", "Friday", "Saturday", "Sunday"],
+        long: "{weekday} {day} {month} {year}",
+        medium: "{day} {month} {year}",
+        month_year: "{month} {year}",
+    },
+    DateNames {
+        tag: "de",
+        months: ["Januar", "Februar", "März", "April", "Mai", "Juni", "Juli", "August", "September", "Oktober", "November", "Dezember"],
+        weekdays: ["Montag", "Dienstag", "Mittwoch", "Donnerstag", "Freitag", "Samstag", "Sonntag"],
+        long: "{weekday}, {day}. {month} {year}",
+        medium: "{day}. {month} {year}",
+        month_year: "{month} {year}",
+    },
+    DateNames {
+        tag: "fr",
+        months: ["janvier", "février", "mars", "avril", "mai", "juin", "juillet", "août", "septembre", "octobre", "novembre", "décembre"],
+        weekdays: ["lundi", "mardi", "mercredi", "jeudi", "vendredi", "samedi", "dimanche"],
+        long: "{weekday} {day} {month} {year}",
+        medium: "{day} {month} {year}",
+        month_year: "{month} {year}",
+    },
+    DateNames {
+        tag: "es",
+        months: ["enero", "febrero", "marzo", "abril", "mayo", "junio", "julio", "agosto", "septiembre", "octubre", "noviembre", "diciembre"],
+        weekdays: ["lunes", "martes", "miércoles", "jueves", "viernes", "sábado", "domingo"],
+        long: "{weekday}, {day} de {month} de {year}",
+        medium: "{day} de {month} de {year}",
+        month_year: "{month} de {year}",
+    },
+    DateNames {
+        tag: "it",
+        months: ["gennaio", "febbraio", "marzo", "aprile", "maggio", "giugno", "luglio", "agosto", "settembre", "ottobre", "novembre", "dicembre"],
+        weekdays: ["lunedì", "martedì", "mercoledì", "giovedì", "venerdì", "sabato", "domenica"],
+        long: "{weekday} {day} {month} {year}",
+        medium: "{day} {month} {year}",
+        month_year: "{month} {year}",
+    },
+    DateNames {
+        tag: "pt",
+        months: ["janeiro", "fevereiro", "março", "abril", "maio", "junho", "julho", "agosto", "setembro", "outubro", "novembro", "dezembro"],
+        weekdays: ["segunda-feira", "terça-feira", "quarta-feira", "quinta-feira", "sexta-feira", "sábado", "domingo"],
+        long: "{weekday}, {day} de {month} de {year}",
+        medium: "{day} de {month} de {year}",
+        month_year: "{month} de {year}",
+    },
+    DateNames {
+        tag: "nl",
+        months: ["januari", "februari", "maart", "april", "mei", "juni", "juli", "augustus", "september", "oktober", "november", "december"],
+        weekdays: ["maandag", "dinsdag", "woensdag", "donderdag", "vrijdag", "zaterdag", "zondag"],
+        long: "{weekday} {day} {month} {year}",
+        medium: "{day} {month} {year}",
+        month_year: "{month} {year}",
+    },
+    DateNames {
+        tag: "sv",
+        months: ["januari", "februari", "mars", "april", "maj", "juni", "juli", "augusti", "september", "oktober", "november", "december"],
+        weekdays: ["måndag", "tisdag", "onsdag", "torsdag", "fredag", "lördag", "söndag"],
+        long: "{weekday} {day} {month} {year}",
+        medium: "{day} {month} {year}",
+        month_year: "{month} {year}",
+    },
+];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LocaleOption {
+    pub tag: String,
+    // A long date in this locale, for the settings list
+    pub sample: String,
+}
+
+fn names_for(tag: &str) -> &'static DateNames {
+    let tag = tag.trim().replace('_', "-");
+    let language = tag.split('-').next().unwrap_or_default();
+    LOCALES
+        .iter()
+        .find(|l| l.tag.eq_ignore_ascii_case(&tag))
+        .or_else(|| LOCALES.iter().find(|l| l.tag.split('-').next().is_some_and(|lang| lang.eq_ignore_ascii_case(language))))
+        .unwrap_or(&LOCALES[0])
+}
+
+pub fn parse_week_start(value: &str) -> Weekday {
+    match value.trim().to_lowercase().as_str() {
+        "sunday" => Weekday::Sun,
+        "saturday" => Weekday::Sat,
+        _ => Weekday::Mon,
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct DateFormat {
+    names: &'static DateNames,
+    pub week_start: Weekday,
+}
+
+impl Default for DateFormat {
+    fn default() -> Self {
+        Self::new(DEFAULT_LOCALE, DEFAULT_WEEK_START)
+    }
+}
+
+impl DateFormat {
+    pub fn new(locale: &str, week_start: &str) -> Self {
+        Self { names: names_for(locale), week_start: parse_week_start(week_start) }
+    }
+
+    // From the `locale` and `week_start` settings
+    pub fn from_conn(conn: &Connection) -> Self {
+        let read = |key: &str, default: &str| crate::database::read_setting(conn, key).unwrap_or_else(|| default.to_string());
+        Self::new(&read("locale", DEFAULT_LOCALE), &read("week_start", DEFAULT_WEEK_START))
+    }
+
+    // 1-12
+    pub fn month_name(&self, month: u32) -> &'static str {
+        self.names.months.get(month.wrapping_sub(1) as usize).copied().unwrap_or_default()
+    }
+
+    pub fn weekday_name(&self, weekday: Weekday) -> &'static str {
+        self.names.weekdays[weekday.num_days_from_monday() as usize]
+    }
+
+    fn fill(&self, pattern: &str, date: NaiveDate) -> String {
+        pattern
+            .replace("{weekday}", self.weekday_name(date.weekday()))
+            .replace("{day}", &date.day().to_string())
+            .replace("{month}", self.month_name(date.month()))
+            .replace("{year}", &date.year().to_string())
+    }
+
+    // "Tuesday, March 5, 2024"
+    pub fn long_date(&self, date: NaiveDate) -> String {
+        self.fill(self.names.long, date)
+    }
+
+    // "March 5, 2024"
+    pub fn medium_date(&self, date: NaiveDate) -> String {
+        self.fill(self.names.medium, date)
+    }
+
+    // "March 2024"
+    pub fn month_year(&self, date: NaiveDate) -> String {
+        self.fill(self.names.month_year, date)
+    }
+
+    pub fn week_start_of(&self, date: NaiveDate) -> NaiveDate {
+        date - Duration::days(date.weekday().days_since(self.week_start) as i64)
+    }
+
+    // The seven weekdays from the first day of the week
+    pub fn weekdays(&self) -> impl Iterator<Item = Weekday> {
+        let start = self.week_start;
+        (0..7).map(move |i| (0..i).fold(start, |d, _| d.succ()))
+    }
+}
+
+pub fn supported_locales() -> Vec<LocaleOption> {
+    let sample = NaiveDate::from_ymd_opt(2024, 3, 5).expect("valid date");
+    LOCALES
+        .iter()
+        .map(|l| LocaleOption { tag: l.tag.to_string(), sample: DateFormat::new(l.tag, DEFAULT_WEEK_START).long_date(sample) })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_formats_and_fallbacks() {
+        let date = NaiveDate::from_ymd_opt(2024, 3, 5).unwrap();
+        assert_eq!(DateFormat::default().long_date(date), "Tuesday, March 5, 2024");
+        assert_eq!(DateFormat::new("en_GB", "monday").medium_date(date), "5 March 2024");
+        assert_eq!(DateFormat::new("de-AT", "monday").long_date(date), "Dienstag, 5. März 2024");
+        assert_eq!(DateFormat::new("pt-BR", "monday").month_year(date), "março de 2024");
+        assert_eq!(DateFormat::new("xx", "monday").month_name(12), "December");
+        assert_eq!(DateFormat::default().month_name(13), "");
+    }
+
+    #[test]
+    fn test_week_start() {
+        let tuesday = NaiveDate::from_ymd_opt(2024, 3, 5).unwrap();
+        assert_eq!(DateFormat::new("en", "monday").week_start_of(tuesday), NaiveDate::from_ymd_opt(2024, 3, 4).unwrap());
+        assert_eq!(DateFormat::new("en", "Sunday").week_start_of(tuesday), NaiveDate::from_ymd_opt(2024, 3, 3).unwrap());
+        assert_eq!(DateFormat::new("en", "saturday").week_start_of(tuesday), NaiveDate::from_ymd_opt(2024, 3, 2).unwrap());
+        let sunday_first: Vec<Weekday> = DateFormat::new("en", "sunday").weekdays().collect();
+        assert_eq!((sunday_first[0], sunday_first[6]), (Weekday::Sun, Weekday::Sat));
+    }
+}
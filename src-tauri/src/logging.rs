@@ -0,0 +1,126 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use anyhow::Result;
+use rusqlite::Connection;
+use serde::Serialize;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::filter::{LevelFilter, Targets};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, reload, Registry};
+
+// Logging to a file per day in `logs/` next to the journals, keeping the last MAX_LOG_FILES,
+// and to stderr for `npm run tauri dev`. `log_level` (error, warn, info, debug or trace;
+// default info) sets how much of the app's own logging is kept and applies without a restart;
+// libraries only log warnings and errors. `get_recent_logs` returns the end of the newest
+// files for attaching to bug reports. Entries appear in logs by id, and search queries only
+// at debug level.
+
+const LOG_PREFIX: &str = "journal-reader";
+const MAX_LOG_FILES: usize = 7;
+pub const DEFAULT_LEVEL: &str = "info";
+pub const MAX_RECENT_LINES: usize = 5000;
+
+#[derive(Debug, Serialize)]
+pub struct RecentLogs {
+    pub dir: String,
+    // Oldest first
+    pub lines: Vec<String>,
+}
+
+static STATE: OnceLock<(reload::Handle<Targets, Registry>, PathBuf)> = OnceLock::new();
+
+fn parse_level(value: &str) -> LevelFilter {
+    value.trim().to_lowercase().parse().unwrap_or(LevelFilter::INFO)
+}
+
+fn targets(level: LevelFilter) -> Targets {
+    Targets::new().with_default(LevelFilter::WARN).with_target("journal_reader_lib", level)
+}
+
+// Installs the subscriber; later calls do nothing
+pub fn init(log_dir: &Path) -> Result<()> {
+    if STATE.get().is_some() {
+        return Ok(());
+    }
+    fs::create_dir_all(log_dir)?;
+    let file = RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix(LOG_PREFIX)
+        .filename_suffix("log")
+        .max_log_files(MAX_LOG_FILES)
+        .build(log_dir)?;
+    let (filter, handle) = reload::Layer::new(targets(parse_level(DEFAULT_LEVEL)));
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt::layer().with_writer(file))
+        .with(fmt::layer().with_writer(std::io::stderr))
+        .try_init()?;
+    let _ = STATE.set((handle, log_dir.to_path_buf()));
+    Ok(())
+}
+
+pub fn set_level(value: &str) {
+    if let Some((handle, _)) = STATE.get() {
+        if let Err(e) = handle.reload(targets(parse_level(value))) {
+            tracing::warn!("could not change the log level: {}", e);
+        }
+    }
+}
+
+// Applies the stored `log_level`
+pub(crate) fn apply_setting(conn: &Connection) {
+    set_level(&crate::database::read_setting(conn, "log_level").unwrap_or_else(|| DEFAULT_LEVEL.to_string()));
+}
+
+pub(crate) fn log_dir() -> Option<PathBuf> {
+    STATE.get().map(|(_, dir)| dir.clone())
+}
+
+// The last `count` lines across the newest log files
+pub fn recent(dir: &Path, count: usize) -> Result<Vec<String>> {
+    let mut files: Vec<PathBuf> = match fs::read_dir(dir) {
+        Ok(read) => read
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| p.file_name().and_then(|n| n.to_str()).is_some_and(|n| n.starts_with(LOG_PREFIX) && n.ends_with(".log")))
+            .collect(),
+        Err(_) => return Ok(Vec::new()),
+    };
+    // Names end in the date, so they sort oldest first
+    files.sort();
+    let mut lines: Vec<String> = Vec::new();
+    for file in files.iter().rev() {
+        let text = String::from_utf8_lossy(&fs::read(file)?).into_owned();
+        let mut older: Vec<String> = text.lines().map(str::to_string).collect();
+        let keep = count.saturating_sub(lines.len());
+        older.drain(..older.len().saturating_sub(keep));
+        older.append(&mut lines);
+        lines = older;
+        if lines.len() >= count {
+            break;
+        }
+    }
+    Ok(lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recent_lines_span_files() {
+        let dir = std::env::temp_dir().join(format!("jr-logs-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("journal-reader.2026-10-16.log"), "a\nb\nc\n").unwrap();
+        fs::write(dir.join("journal-reader.2026-10-17.log"), "d\ne\n").unwrap();
+        fs::write(dir.join("notes.txt"), "x\n").unwrap();
+        assert_eq!(recent(&dir, 3).unwrap(), ["c", "d", "e"]);
+        assert_eq!(recent(&dir, 10).unwrap(), ["a", "b", "c", "d", "e"]);
+        assert!(recent(&dir.join("missing"), 10).unwrap().is_empty());
+        assert_eq!(parse_level(" Debug "), LevelFilter::DEBUG);
+        assert_eq!(parse_level("loud"), LevelFilter::INFO);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,640 @@
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection, TransactionBehavior};
+
+// Versioned schema migrations.
+//
+// Every schema change ships as a new `Migration` appended to `MIGRATIONS` with the next
+// version number. Applied versions are recorded in `schema_version`, so upgrading an
+// existing install only runs the steps it hasn't seen yet. Never edit or reorder a
+// migration that has shipped; add a new one instead.
+
+pub struct Migration {
+    pub version: i64,
+    pub description: &'static str,
+    pub up: fn(&Connection) -> rusqlite::Result<()>,
+}
+
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "initial schema (entries, entries_fts, settings)",
+        // Uses IF NOT EXISTS so databases created before migrations existed adopt version 1 as-is
+        up: |conn| conn.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS entries (
+                id TEXT PRIMARY KEY,
+                title TEXT,
+                body TEXT NOT NULL,
+                entry_date TEXT NOT NULL,
+                entry_timezone TEXT NOT NULL,
+                source_path TEXT NOT NULL,
+                source_type TEXT NOT NULL,
+                text_hash TEXT NOT NULL UNIQUE,
+                embedding BLOB,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                sentiment REAL,
+                language TEXT
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_entries_entry_date ON entries(entry_date);
+            CREATE INDEX IF NOT EXISTS idx_entries_text_hash ON entries(text_hash);
+
+            -- Full-text search virtual table
+            CREATE VIRTUAL TABLE IF NOT EXISTS entries_fts
+            USING fts5(
+                title,
+                body,
+                entry_id UNINDEXED
+            );
+
+            -- Settings table (key/value)
+            CREATE TABLE IF NOT EXISTS settings (
+                key TEXT PRIMARY KEY,
+                value TEXT NOT NULL
+            );
+            "#,
+        ),
+    },
+    Migration {
+        version: 2,
+        description: "embedding metadata on entries",
+        // Which model produced the stored vector, and the text it was computed from, so the
+        // backfill can tell fresh embeddings from missing or stale ones
+        up: |conn| conn.execute_batch(
+            r#"
+            ALTER TABLE entries ADD COLUMN embedding_model TEXT;
+            ALTER TABLE entries ADD COLUMN embedding_dim INTEGER;
+            ALTER TABLE entries ADD COLUMN embedding_text_hash TEXT;
+            "#,
+        ),
+    },
+    Migration {
+        version: 3,
+        description: "entry tags",
+        up: |conn| conn.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS entry_tags (
+                entry_id TEXT NOT NULL REFERENCES entries(id) ON DELETE CASCADE,
+                tag TEXT NOT NULL,
+                PRIMARY KEY (entry_id, tag)
+            );
+            CREATE INDEX IF NOT EXISTS idx_entry_tags_tag ON entry_tags(tag);
+            "#,
+        ),
+    },
+    Migration {
+        version: 4,
+        description: "search history",
+        up: |conn| conn.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS search_history (
+                query TEXT PRIMARY KEY,
+                use_count INTEGER NOT NULL DEFAULT 1,
+                last_used_at TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_search_history_last_used ON search_history(last_used_at);
+            "#,
+        ),
+    },
+    Migration {
+        version: 5,
+        description: "AI conversations",
+        up: |conn| conn.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS conversations (
+                id TEXT PRIMARY KEY,
+                title TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS conversation_messages (
+                id TEXT PRIMARY KEY,
+                conversation_id TEXT NOT NULL REFERENCES conversations(id) ON DELETE CASCADE,
+                role TEXT NOT NULL,
+                content TEXT NOT NULL,
+                citations TEXT,
+                created_at TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_conversation_messages_conversation
+                ON conversation_messages(conversation_id);
+            "#,
+        ),
+    },
+    Migration {
+        version: 6,
+        description: "period summaries",
+        up: |conn| conn.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS summaries (
+                id INTEGER PRIMARY KEY,
+                period_start TEXT NOT NULL,
+                period_end TEXT NOT NULL,
+                granularity TEXT NOT NULL,
+                summary TEXT NOT NULL,
+                model TEXT NOT NULL,
+                entry_count INTEGER NOT NULL,
+                source_hash TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                UNIQUE(period_start, period_end, granularity)
+            );
+            "#,
+        ),
+    },
+    Migration {
+        version: 7,
+        description: "extracted entities (people, places, events)",
+        up: |conn| conn.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS entities (
+                id INTEGER PRIMARY KEY,
+                kind TEXT NOT NULL,
+                name TEXT NOT NULL,
+                normalized TEXT NOT NULL,
+                UNIQUE(kind, normalized)
+            );
+            CREATE TABLE IF NOT EXISTS entry_entities (
+                entry_id TEXT NOT NULL REFERENCES entries(id) ON DELETE CASCADE,
+                entity_id INTEGER NOT NULL REFERENCES entities(id) ON DELETE CASCADE,
+                mentions INTEGER NOT NULL DEFAULT 1,
+                PRIMARY KEY (entry_id, entity_id)
+            );
+            CREATE INDEX IF NOT EXISTS idx_entry_entities_entity ON entry_entities(entity_id);
+            CREATE TABLE IF NOT EXISTS entity_extractions (
+                entry_id TEXT PRIMARY KEY REFERENCES entries(id) ON DELETE CASCADE,
+                text_hash TEXT NOT NULL,
+                method TEXT NOT NULL,
+                extracted_at TEXT NOT NULL
+            );
+            "#,
+        ),
+    },
+    Migration {
+        version: 8,
+        description: "entry chunks for retrieval",
+        up: |conn| conn.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS entry_chunks (
+                id INTEGER PRIMARY KEY,
+                entry_id TEXT NOT NULL REFERENCES entries(id) ON DELETE CASCADE,
+                chunk_index INTEGER NOT NULL,
+                start_offset INTEGER NOT NULL,
+                end_offset INTEGER NOT NULL,
+                text_hash TEXT NOT NULL,
+                embedding BLOB,
+                embedding_key TEXT,
+                UNIQUE(entry_id, chunk_index)
+            );
+            "#,
+        ),
+    },
+    Migration {
+        version: 9,
+        description: "entry templates",
+        up: |conn| conn.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS entry_templates (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                title TEXT,
+                body TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            );
+            "#,
+        ),
+    },
+    Migration {
+        version: 10,
+        description: "indexed entry year, month and epoch",
+        // Derived from entry_date (normalized to UTC) by triggers, so every writer keeps them
+        // current; the timeline groups and ranges on these instead of substrings of the text
+        up: |conn| conn.execute_batch(
+            r#"
+            ALTER TABLE entries ADD COLUMN entry_year INTEGER;
+            ALTER TABLE entries ADD COLUMN entry_month INTEGER;
+            ALTER TABLE entries ADD COLUMN entry_epoch INTEGER;
+            UPDATE entries SET
+                entry_year = CAST(strftime('%Y', entry_date) AS INTEGER),
+                entry_month = CAST(strftime('%m', entry_date) AS INTEGER),
+                entry_epoch = CAST(strftime('%s', entry_date) AS INTEGER);
+            CREATE INDEX IF NOT EXISTS idx_entries_year_month ON entries(entry_year, entry_month, entry_epoch);
+            CREATE INDEX IF NOT EXISTS idx_entries_epoch ON entries(entry_epoch);
+            CREATE TRIGGER IF NOT EXISTS entries_date_parts_insert AFTER INSERT ON entries
+            BEGIN
+                UPDATE entries SET
+                    entry_year = CAST(strftime('%Y', NEW.entry_date) AS INTEGER),
+                    entry_month = CAST(strftime('%m', NEW.entry_date) AS INTEGER),
+                    entry_epoch = CAST(strftime('%s', NEW.entry_date) AS INTEGER)
+                WHERE rowid = NEW.rowid;
+            END;
+            CREATE TRIGGER IF NOT EXISTS entries_date_parts_update AFTER UPDATE OF entry_date ON entries
+            BEGIN
+                UPDATE entries SET
+                    entry_year = CAST(strftime('%Y', NEW.entry_date) AS INTEGER),
+                    entry_month = CAST(strftime('%m', NEW.entry_date) AS INTEGER),
+                    entry_epoch = CAST(strftime('%s', NEW.entry_date) AS INTEGER)
+                WHERE rowid = NEW.rowid;
+            END;
+            "#,
+        ),
+    },
+    Migration {
+        version: 11,
+        description: "dismissed near-duplicate pairs",
+        up: |conn| conn.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS near_duplicate_dismissals (
+                entry_a TEXT NOT NULL REFERENCES entries(id) ON DELETE CASCADE,
+                entry_b TEXT NOT NULL REFERENCES entries(id) ON DELETE CASCADE,
+                dismissed_at TEXT NOT NULL,
+                PRIMARY KEY (entry_a, entry_b)
+            );
+            "#,
+        ),
+    },
+    Migration {
+        version: 12,
+        description: "source file modification time and size",
+        up: |conn| conn.execute_batch(
+            r#"
+            ALTER TABLE entries ADD COLUMN source_mtime INTEGER;
+            ALTER TABLE entries ADD COLUMN source_size INTEGER;
+            "#,
+        ),
+    },
+    Migration {
+        version: 13,
+        description: "source file content hash",
+        up: |conn| conn.execute_batch("ALTER TABLE entries ADD COLUMN source_hash TEXT;"),
+    },
+    Migration {
+        version: 14,
+        description: "detect the language of existing entries",
+        up: detect_entry_languages,
+    },
+    Migration {
+        version: 15,
+        description: "cache of AI results",
+        up: |conn| conn.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS ai_cache (
+                kind TEXT NOT NULL,
+                text_hash TEXT NOT NULL,
+                model TEXT NOT NULL,
+                prompt_version INTEGER NOT NULL,
+                value BLOB NOT NULL,
+                created_at TEXT NOT NULL,
+                PRIMARY KEY (kind, text_hash, model, prompt_version)
+            );
+            "#,
+        ),
+    },
+    Migration {
+        version: 16,
+        description: "audit log of AI requests",
+        up: |conn| conn.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS ai_audit_log (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                created_at TEXT NOT NULL,
+                provider TEXT NOT NULL,
+                destination TEXT NOT NULL,
+                purpose TEXT NOT NULL,
+                bytes_sent INTEGER NOT NULL,
+                status INTEGER,
+                entry_ids TEXT NOT NULL DEFAULT '[]'
+            );
+            "#,
+        ),
+    },
+    Migration {
+        version: 17,
+        description: "storage format of chunk vectors",
+        up: |conn| conn.execute_batch(
+            r#"
+            ALTER TABLE entry_chunks ADD COLUMN vector_format TEXT NOT NULL DEFAULT 'f32';
+            "#,
+        ),
+    },
+    Migration {
+        version: 18,
+        description: "dirty flag for entries edited since they were embedded",
+        up: |conn| conn.execute_batch(
+            r#"
+            ALTER TABLE entries ADD COLUMN embedding_dirty INTEGER NOT NULL DEFAULT 0;
+            CREATE INDEX IF NOT EXISTS idx_entries_embedding_dirty ON entries(embedding_dirty) WHERE embedding_dirty = 1;
+            "#,
+        ),
+    },
+    Migration {
+        version: 19,
+        description: "sensitive entries with a sealed body",
+        up: |conn| conn.execute_batch(
+            r#"
+            ALTER TABLE entries ADD COLUMN sensitive INTEGER NOT NULL DEFAULT 0;
+            ALTER TABLE entries ADD COLUMN body_sealed BLOB;
+            "#,
+        ),
+    },
+    Migration {
+        version: 20,
+        description: "LAN sync peers, deletion tombstones and sync conflicts",
+        // Tombstones are written by trigger so every way of deleting an entry reaches other
+        // devices; re-inserting the same id (a sync or a restore) clears it again
+        up: |conn| conn.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS sync_peers (
+                device_id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                address TEXT,
+                last_synced_at TEXT
+            );
+            CREATE TABLE IF NOT EXISTS entry_tombstones (
+                entry_id TEXT PRIMARY KEY,
+                deleted_at TEXT NOT NULL
+            );
+            CREATE TRIGGER IF NOT EXISTS entries_tombstone_delete AFTER DELETE ON entries
+            BEGIN
+                INSERT OR REPLACE INTO entry_tombstones (entry_id, deleted_at)
+                VALUES (OLD.id, strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now'));
+            END;
+            CREATE TRIGGER IF NOT EXISTS entries_tombstone_insert AFTER INSERT ON entries
+            BEGIN
+                DELETE FROM entry_tombstones WHERE entry_id = NEW.id;
+            END;
+            CREATE TABLE IF NOT EXISTS sync_conflicts (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                entry_id TEXT NOT NULL,
+                source TEXT NOT NULL,
+                remote TEXT NOT NULL,
+                detected_at TEXT NOT NULL,
+                UNIQUE (entry_id, source)
+            );
+            "#,
+        ),
+    },
+    Migration {
+        version: 21,
+        description: "archived copy of an entry's original file",
+        up: |conn| conn.execute_batch(
+            r#"
+            ALTER TABLE entries ADD COLUMN archive_file TEXT;
+            "#,
+        ),
+    },
+    Migration {
+        version: 22,
+        description: "previous versions of edited entries",
+        up: |conn| conn.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS entry_versions (
+                entry_id TEXT NOT NULL REFERENCES entries(id) ON DELETE CASCADE,
+                version INTEGER NOT NULL,
+                title TEXT,
+                body TEXT NOT NULL,
+                saved_at TEXT NOT NULL,
+                PRIMARY KEY (entry_id, version)
+            );
+            "#,
+        ),
+    },
+    Migration {
+        version: 23,
+        description: "goals stated in entries and their later mentions",
+        up: |conn| conn.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS goals (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                description TEXT NOT NULL,
+                normalized TEXT NOT NULL UNIQUE,
+                created_at TEXT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS goal_mentions (
+                goal_id INTEGER NOT NULL REFERENCES goals(id) ON DELETE CASCADE,
+                entry_id TEXT NOT NULL REFERENCES entries(id) ON DELETE CASCADE,
+                kind TEXT NOT NULL,
+                excerpt TEXT NOT NULL,
+                PRIMARY KEY (goal_id, entry_id)
+            );
+            CREATE INDEX IF NOT EXISTS idx_goal_mentions_entry ON goal_mentions(entry_id);
+            CREATE TABLE IF NOT EXISTS goal_extractions (
+                entry_id TEXT PRIMARY KEY REFERENCES entries(id) ON DELETE CASCADE,
+                text_hash TEXT NOT NULL,
+                method TEXT NOT NULL,
+                extracted_at TEXT NOT NULL
+            );
+            "#,
+        ),
+    },
+    Migration {
+        version: 24,
+        description: "gratitude and highlight sentences",
+        up: |conn| conn.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS gratitude_items (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                entry_id TEXT NOT NULL REFERENCES entries(id) ON DELETE CASCADE,
+                kind TEXT NOT NULL,
+                text TEXT NOT NULL,
+                position INTEGER NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_gratitude_items_entry ON gratitude_items(entry_id);
+            CREATE TABLE IF NOT EXISTS gratitude_extractions (
+                entry_id TEXT PRIMARY KEY REFERENCES entries(id) ON DELETE CASCADE,
+                text_hash TEXT NOT NULL,
+                extracted_at TEXT NOT NULL
+            );
+            "#,
+        ),
+    },
+    Migration {
+        version: 25,
+        description: "cached entry previews",
+        up: |conn| conn.execute_batch(
+            r#"
+            ALTER TABLE entries ADD COLUMN preview TEXT;
+            ALTER TABLE entries ADD COLUMN preview_hash TEXT;
+            "#,
+        ),
+    },
+    Migration {
+        version: 26,
+        description: "photos attached to entries",
+        up: |conn| conn.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS entry_photos (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                entry_id TEXT NOT NULL REFERENCES entries(id) ON DELETE CASCADE,
+                path TEXT NOT NULL,
+                taken_at TEXT NOT NULL,
+                caption TEXT,
+                position INTEGER NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_entry_photos_entry ON entry_photos(entry_id);
+            CREATE INDEX IF NOT EXISTS idx_entry_photos_path ON entry_photos(path);
+            "#,
+        ),
+    },
+];
+
+pub fn latest_version() -> i64 {
+    MIGRATIONS.last().map(|m| m.version).unwrap_or(0)
+}
+
+fn ensure_version_table(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS schema_version (
+            version INTEGER PRIMARY KEY,
+            description TEXT NOT NULL,
+            applied_at TEXT NOT NULL
+        );
+        "#,
+    )?;
+    Ok(())
+}
+
+// Until v14 every entry was stored without a language
+fn detect_entry_languages(conn: &Connection) -> rusqlite::Result<()> {
+    let pending: Vec<(String, String)> = {
+        let mut stmt = conn.prepare("SELECT id, body FROM entries WHERE language IS NULL")?;
+        let rows = stmt.query_map([], |r| Ok((r.get(0)?, r.get(1)?)))?;
+        rows.collect::<rusqlite::Result<_>>()?
+    };
+    let mut update = conn.prepare("UPDATE entries SET language = ?2 WHERE id = ?1")?;
+    for (id, body) in pending {
+        if let Some(language) = crate::import::detect_language(&body) {
+            update.execute(params![id, language])?;
+        }
+    }
+    Ok(())
+}
+
+fn max_applied_version(conn: &Connection) -> rusqlite::Result<i64> {
+    conn.query_row(
+        "SELECT IFNULL(MAX(version), 0) FROM schema_version",
+        [],
+        |row| row.get(0),
+    )
+}
+
+pub fn current_version(conn: &Connection) -> Result<i64> {
+    ensure_version_table(conn)?;
+    Ok(max_applied_version(conn)?)
+}
+
+// Apply all pending migrations in order. Each step runs in its own IMMEDIATE transaction
+// and re-checks the version inside it, so two callers racing at startup can't apply the
+// same step twice. Returns the number of migrations applied.
+pub fn run_migrations(conn: &mut Connection) -> Result<u32> {
+    let existing = current_version(conn)?;
+    if existing > latest_version() {
+        // Opened by an older build after a newer one upgraded it; leave the schema alone
+        tracing::warn!(
+            "schema version {} is newer than this build supports ({})",
+            existing,
+            latest_version()
+        );
+        return Ok(0);
+    }
+    let mut applied = 0u32;
+
+    for migration in MIGRATIONS {
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        if migration.version <= max_applied_version(&tx)? {
+            continue;
+        }
+
+        (migration.up)(&tx).with_context(|| {
+            format!("Migration {} ({}) failed", migration.version, migration.description)
+        })?;
+        tx.execute(
+            "INSERT INTO schema_version (version, description, applied_at) VALUES (?1, ?2, ?3)",
+            params![
+                migration.version,
+                migration.description,
+                chrono::Utc::now().to_rfc3339()
+            ],
+        )?;
+        tx.commit()?;
+
+        tracing::info!("applied migration {} ({})", migration.version, migration.description);
+        applied += 1;
+    }
+
+    Ok(applied)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_versions_are_strictly_increasing() {
+        let mut last = 0;
+        for m in MIGRATIONS {
+            assert!(m.version > last, "migration {} out of order", m.version);
+            last = m.version;
+        }
+    }
+
+    #[test]
+    fn test_fresh_database_reaches_latest() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        let applied = run_migrations(&mut conn).unwrap();
+        assert_eq!(applied as usize, MIGRATIONS.len());
+        assert_eq!(current_version(&conn).unwrap(), latest_version());
+
+        // Second run is a no-op
+        assert_eq!(run_migrations(&mut conn).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_entry_date_parts_follow_entry_date() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        run_migrations(&mut conn).unwrap();
+        conn.execute_batch(
+            r#"INSERT INTO entries (id, body, entry_date, entry_timezone, source_path, source_type, text_hash, created_at, updated_at)
+               VALUES ('a', 'x', '2021-01-01T01:30:00+02:00', 'UTC', '', 'txt', 'h', '', '');"#,
+        )
+        .unwrap();
+        let parts = |conn: &Connection| -> (i32, u32, i64) {
+            conn.query_row("SELECT entry_year, entry_month, entry_epoch FROM entries WHERE id = 'a'", [], |r| {
+                Ok((r.get(0)?, r.get(1)?, r.get(2)?))
+            })
+            .unwrap()
+        };
+        // Normalized to UTC
+        assert_eq!(parts(&conn), (2020, 12, 1609457400));
+
+        conn.execute("UPDATE entries SET entry_date = '2023-06-15T12:00:00+00:00' WHERE id = 'a'", []).unwrap();
+        assert_eq!(parts(&conn), (2023, 6, 1686830400));
+    }
+
+    #[test]
+    fn test_legacy_database_is_adopted() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        // Pre-migrations install: tables exist but no schema_version
+        conn.execute_batch(
+            r#"
+            CREATE TABLE entries (
+                id TEXT PRIMARY KEY, title TEXT, body TEXT NOT NULL, entry_date TEXT NOT NULL,
+                entry_timezone TEXT NOT NULL, source_path TEXT NOT NULL, source_type TEXT NOT NULL,
+                text_hash TEXT NOT NULL UNIQUE, embedding BLOB, created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL, sentiment REAL, language TEXT
+            );
+            CREATE TABLE settings (key TEXT PRIMARY KEY, value TEXT NOT NULL);
+            INSERT INTO settings (key, value) VALUES ('ai_provider', 'ollama');
+            "#,
+        )
+        .unwrap();
+
+        run_migrations(&mut conn).unwrap();
+        assert_eq!(current_version(&conn).unwrap(), latest_version());
+        let provider: String = conn
+            .query_row("SELECT value FROM settings WHERE key = 'ai_provider'", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(provider, "ollama");
+    }
+}
@@ -0,0 +1,295 @@
+use anyhow::Result;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use tauri::AppHandle;
+
+// Near-duplicate entries: the same text imported twice with different whitespace, punctuation
+// or a trailing signature, which the exact text_hash check lets through.
+//
+// Each entry gets a MinHash signature over 3-word shingles of its normalized text. Entries that
+// agree on all rows of any band become candidates (pairs above ~0.6 Jaccard almost always do),
+// and are confirmed by how much of the shorter entry's shingles appear in the longer one.
+
+const SHINGLE_WORDS: usize = 3;
+// Shorter entries ("Tired.") are too alike to compare
+const MIN_WORDS: usize = 8;
+const BANDS: usize = 16;
+const ROWS: usize = 4;
+pub const DEFAULT_THRESHOLD: f64 = 0.8;
+const MAX_PAIRS: usize = 200;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NearDuplicateEntry {
+    pub id: String,
+    pub title: Option<String>,
+    pub entry_date: String,
+    pub source_path: String,
+    pub source_type: String,
+    pub word_count: u64,
+    pub snippet: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NearDuplicatePair {
+    pub a: NearDuplicateEntry,
+    pub b: NearDuplicateEntry,
+    // Share of the shorter entry's shingles found in the other, 0-1
+    pub similarity: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MergeReport {
+    pub kept_id: String,
+    pub removed_id: String,
+    pub tags_added: u32,
+    pub text_replaced: bool,
+}
+
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_lowercase())
+        .collect()
+}
+
+// FNV-1a; stable across runs and platforms, unlike the std hasher
+fn fnv1a(text: &str) -> u64 {
+    text.bytes().fold(0xcbf29ce484222325, |hash, b| (hash ^ b as u64).wrapping_mul(0x100000001b3))
+}
+
+fn shingles(words: &[String]) -> HashSet<u64> {
+    if words.len() < SHINGLE_WORDS {
+        return std::iter::once(fnv1a(&words.join(" "))).collect();
+    }
+    words.windows(SHINGLE_WORDS).map(|w| fnv1a(&w.join(" "))).collect()
+}
+
+// splitmix64 finalizer, seeded per signature row
+fn mix(value: u64, seed: u64) -> u64 {
+    let mut z = value ^ seed.wrapping_mul(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+pub(crate) fn minhash(shingles: &HashSet<u64>) -> [u64; BANDS * ROWS] {
+    let mut signature = [u64::MAX; BANDS * ROWS];
+    for &shingle in shingles {
+        for (row, min) in signature.iter_mut().enumerate() {
+            *min = (*min).min(mix(shingle, row as u64 + 1));
+        }
+    }
+    signature
+}
+
+fn containment(a: &HashSet<u64>, b: &HashSet<u64>) -> f64 {
+    let (small, large) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+    if small.is_empty() {
+        return 0.0;
+    }
+    small.intersection(large).count() as f64 / small.len() as f64
+}
+
+// Index pairs (i < j) whose signatures match on every row of at least one band
+fn candidate_pairs(signatures: &[[u64; BANDS * ROWS]]) -> Vec<(usize, usize)> {
+    let mut pairs = HashSet::new();
+    for band in 0..BANDS {
+        let mut buckets: HashMap<&[u64], Vec<usize>> = HashMap::new();
+        for (i, signature) in signatures.iter().enumerate() {
+            buckets.entry(&signature[band * ROWS..(band + 1) * ROWS]).or_default().push(i);
+        }
+        for bucket in buckets.values() {
+            for (n, &i) in bucket.iter().enumerate() {
+                for &j in &bucket[n + 1..] {
+                    pairs.insert((i, j));
+                }
+            }
+        }
+    }
+    let mut pairs: Vec<_> = pairs.into_iter().collect();
+    pairs.sort_unstable();
+    pairs
+}
+
+fn dismissed(conn: &Connection) -> Result<HashSet<(String, String)>> {
+    let mut stmt = conn.prepare("SELECT entry_a, entry_b FROM near_duplicate_dismissals")?;
+    let rows = stmt.query_map([], |r| Ok((r.get(0)?, r.get(1)?)))?.collect::<rusqlite::Result<HashSet<_>>>()?;
+    Ok(rows)
+}
+
+// Dismissals are stored with the ids in sorted order
+fn ordered<'a>(a: &'a str, b: &'a str) -> (&'a str, &'a str) {
+    if a <= b { (a, b) } else { (b, a) }
+}
+
+pub(crate) fn find(conn: &Connection, threshold: f64) -> Result<Vec<NearDuplicatePair>> {
+    struct Candidate {
+        entry: NearDuplicateEntry,
+        shingles: HashSet<u64>,
+    }
+    let mut stmt = conn.prepare("SELECT id, title, entry_date, source_path, source_type, body FROM entries ORDER BY entry_date")?;
+    let mut rows = stmt.query([])?;
+    let mut candidates = Vec::new();
+    while let Some(row) = rows.next()? {
+        let body: String = row.get(5)?;
+        let words = words(&body);
+        if words.len() < MIN_WORDS {
+            continue;
+        }
+        candidates.push(Candidate {
+            entry: NearDuplicateEntry {
+                id: row.get(0)?,
+                title: row.get(1)?,
+                entry_date: row.get(2)?,
+                source_path: row.get(3)?,
+                source_type: row.get(4)?,
+                word_count: crate::stats::word_count(&body),
+                snippet: crate::csv_export::lead(&body),
+            },
+            shingles: shingles(&words),
+        });
+    }
+
+    let signatures: Vec<_> = candidates.iter().map(|c| minhash(&c.shingles)).collect();
+    let dismissed = dismissed(conn)?;
+    let mut pairs: Vec<NearDuplicatePair> = candidate_pairs(&signatures)
+        .into_iter()
+        .filter_map(|(i, j)| {
+            let (a, b) = (&candidates[i], &candidates[j]);
+            let (x, y) = ordered(&a.entry.id, &b.entry.id);
+            if dismissed.contains(&(x.to_string(), y.to_string())) {
+                return None;
+            }
+            let similarity = containment(&a.shingles, &b.shingles);
+            (similarity >= threshold).then(|| NearDuplicatePair { a: a.entry.clone(), b: b.entry.clone(), similarity })
+        })
+        .collect();
+    pairs.sort_by(|p, q| q.similarity.total_cmp(&p.similarity));
+    pairs.truncate(MAX_PAIRS);
+    Ok(pairs)
+}
+
+pub async fn find_near_duplicates(app_handle: &AppHandle, threshold: f64) -> Result<Vec<NearDuplicatePair>> {
+    let db_path = crate::database::get_db_file_path(app_handle)?;
+    tokio::task::spawn_blocking(move || -> Result<Vec<NearDuplicatePair>> {
+        let conn = crate::database::open_conn_at(&db_path)?;
+        find(&conn, threshold)
+    })
+    .await
+    .map_err(|e| anyhow::anyhow!(e.to_string()))?
+}
+
+// Keeps `keep_id` and deletes `remove_id`, carrying over its tags and people/places. With
+// `use_removed_text`, the kept entry takes the removed one's title and text (its date and id stay)
+pub(crate) fn merge(conn: &mut Connection, keep_id: &str, remove_id: &str, use_removed_text: bool) -> Result<MergeReport> {
+    if keep_id == remove_id {
+        anyhow::bail!("Pick two different entries");
+    }
+    let tx = conn.transaction()?;
+    let entry_text = |id: &str| {
+        tx.query_row("SELECT title, body FROM entries WHERE id = ?1", params![id], |r| {
+            Ok((r.get::<_, Option<String>>(0)?, r.get::<_, String>(1)?))
+        })
+        .optional()
+    };
+    let kept = entry_text(keep_id)?.ok_or_else(|| anyhow::anyhow!("Entry not found: {}", keep_id))?;
+    let removed = entry_text(remove_id)?.ok_or_else(|| anyhow::anyhow!("Entry not found: {}", remove_id))?;
+
+    let tags_added = tx.execute(
+        "INSERT OR IGNORE INTO entry_tags (entry_id, tag) SELECT ?1, tag FROM entry_tags WHERE entry_id = ?2",
+        params![keep_id, remove_id],
+    )? as u32;
+    tx.execute(
+        "INSERT OR IGNORE INTO entry_entities (entry_id, entity_id, mentions)
+         SELECT ?1, entity_id, mentions FROM entry_entities WHERE entry_id = ?2",
+        params![keep_id, remove_id],
+    )?;
+    tx.execute("DELETE FROM entries_fts WHERE entry_id = ?1", params![remove_id])?;
+    // Tags, entities, chunks and dismissals of the removed entry go with it
+    tx.execute("DELETE FROM entries WHERE id = ?1", params![remove_id])?;
+
+    // After the delete, since text_hash is unique
+    if use_removed_text {
+        crate::database::set_entry_text(&tx, keep_id, removed.0.or(kept.0), &removed.1)?;
+    }
+    tx.commit()?;
+    Ok(MergeReport { kept_id: keep_id.to_string(), removed_id: remove_id.to_string(), tags_added, text_replaced: use_removed_text })
+}
+
+pub async fn merge_near_duplicates(app_handle: &AppHandle, keep_id: &str, remove_id: &str, use_removed_text: bool) -> Result<MergeReport> {
+    let mut conn = crate::database::open_conn(app_handle)?;
+    let report = merge(&mut conn, keep_id, remove_id, use_removed_text)?;
+    crate::vector_index::invalidate_entries([keep_id, remove_id]);
+    Ok(report)
+}
+
+// "Not duplicates": the pair is no longer suggested
+pub async fn dismiss(app_handle: &AppHandle, a: &str, b: &str) -> Result<()> {
+    let conn = crate::database::open_conn(app_handle)?;
+    let (a, b) = ordered(a, b);
+    conn.execute(
+        "INSERT OR REPLACE INTO near_duplicate_dismissals (entry_a, entry_b, dismissed_at) VALUES (?1, ?2, ?3)",
+        params![a, b, chrono::Utc::now().to_rfc3339()],
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seeded() -> Connection {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("PRAGMA foreign_keys = ON;").unwrap();
+        crate::migrations::run_migrations(&mut conn).unwrap();
+        let text = "We drove up to the lake early this morning and watched the fog lift off the water while the kids skipped stones";
+        let entries = [
+            ("a", text.to_string()),
+            ("b", format!("  {}!!\n\n-- Sent from my phone", text.replace(" the ", "  the "))),
+            ("c", "A completely different day spent indoors reading about the history of bridges and tunnels".to_string()),
+        ];
+        for (id, body) in entries {
+            conn.execute(
+                "INSERT INTO entries (id, body, entry_date, entry_timezone, source_path, source_type, text_hash, created_at, updated_at)
+                 VALUES (?1, ?2, '2021-05-01T12:00:00+00:00', 'UTC', ?1, 'txt', ?1, '', '')",
+                params![id, body],
+            )
+            .unwrap();
+            conn.execute("INSERT INTO entries_fts (title, body, entry_id) VALUES ('', ?1, ?2)", params![body, id]).unwrap();
+        }
+        conn.execute_batch("INSERT INTO entry_tags VALUES ('a', 'family'), ('b', 'family'), ('b', 'lake');").unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_find_near_duplicates() {
+        let conn = seeded();
+        let pairs = find(&conn, DEFAULT_THRESHOLD).unwrap();
+        assert_eq!(pairs.len(), 1);
+        assert_eq!((pairs[0].a.id.as_str(), pairs[0].b.id.as_str()), ("a", "b"));
+        assert!(pairs[0].similarity > 0.99);
+
+        conn.execute("INSERT INTO near_duplicate_dismissals VALUES ('a', 'b', '')", []).unwrap();
+        assert!(find(&conn, DEFAULT_THRESHOLD).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_merge_keeps_tags_and_removes_entry() {
+        let mut conn = seeded();
+        let report = merge(&mut conn, "a", "b", true).unwrap();
+        assert_eq!((report.tags_added, report.text_replaced), (1, true));
+
+        let ids: Vec<String> = conn.prepare("SELECT id FROM entries ORDER BY id").unwrap()
+            .query_map([], |r| r.get(0)).unwrap().collect::<rusqlite::Result<_>>().unwrap();
+        assert_eq!(ids, ["a", "c"]);
+        let tags: Vec<String> = conn.prepare("SELECT tag FROM entry_tags WHERE entry_id = 'a' ORDER BY tag").unwrap()
+            .query_map([], |r| r.get(0)).unwrap().collect::<rusqlite::Result<_>>().unwrap();
+        assert_eq!(tags, ["family", "lake"]);
+        let body: String = conn.query_row("SELECT body FROM entries WHERE id = 'a'", [], |r| r.get(0)).unwrap();
+        assert!(body.ends_with("Sent from my phone"));
+        let fts: i64 = conn.query_row("SELECT COUNT(*) FROM entries_fts WHERE entry_id = 'b'", [], |r| r.get(0)).unwrap();
+        assert_eq!(fts, 0);
+        assert!(merge(&mut conn, "a", "a", false).is_err());
+    }
+}
@@ -0,0 +1,251 @@
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{Mutex, Notify};
+
+// Loopback redirect listener for the Google OAuth flow.
+//
+// `google_oauth_start` registers the pending sign-in (state + PKCE verifier) and binds
+// 127.0.0.1:8765 before the browser opens. `wait` then accepts connections until the
+// `/callback?code=...&state=...` redirect arrives, answers it with a small page, and hands the
+// code back for the token exchange. Requests with the wrong state are refused without ending the
+// flow. Starting a new sign-in cancels the previous listener.
+//
+// On iOS and Android the browser is another app and a listener here can be suspended before the
+// redirect arrives, so Google redirects to the app's own URL scheme instead
+// (`<app id>:/oauth2redirect`, registered through the deep-link plugin) and `redirect_code` reads
+// the code from the link the app is opened with. Google only allows that redirect for an iOS or
+// Android OAuth client, so the phone's `google_client_id` is a different one from the desktop's.
+
+pub const REDIRECT_URI: &str = "http://127.0.0.1:8765/callback";
+// The bundle id on iOS and the package name (which can't contain '-') on Android
+#[cfg(target_os = "android")]
+pub const APP_REDIRECT_URI: &str = "com.jasonb.journal_reader:/oauth2redirect";
+#[cfg(not(target_os = "android"))]
+pub const APP_REDIRECT_URI: &str = "com.jasonb.journal-reader:/oauth2redirect";
+const ADDR: &str = "127.0.0.1:8765";
+const WAIT_TIMEOUT: Duration = Duration::from_secs(300);
+const MAX_REQUEST_BYTES: usize = 16 * 1024;
+
+struct Pending {
+    state: String,
+    code_verifier: String,
+    listener: Option<TcpListener>,
+    cancel: Arc<Notify>,
+}
+
+fn pending() -> &'static Mutex<Option<Pending>> {
+    static PENDING: OnceLock<Mutex<Option<Pending>>> = OnceLock::new();
+    PENDING.get_or_init(|| Mutex::new(None))
+}
+
+// Registers a sign-in and binds the callback port. Returns whether the listener is up; when it
+// isn't (port taken), the code can still be pasted into `google_oauth_complete`.
+pub async fn begin(state: &str, code_verifier: &str) -> bool {
+    let mut slot = pending().lock().await;
+    if let Some(previous) = slot.take() {
+        previous.cancel.notify_waiters();
+    }
+    // A cancelled wait releases the port once its task observes the cancel
+    // Nothing listens on mobile, where the redirect comes back as an app link
+    let attempts = if cfg!(mobile) { 0 } else { 10 };
+    let mut listener = None;
+    for _ in 0..attempts {
+        match TcpListener::bind(ADDR).await {
+            Ok(l) => {
+                listener = Some(l);
+                break;
+            }
+            Err(_) => tokio::time::sleep(Duration::from_millis(100)).await,
+        }
+    }
+    if listener.is_none() && !cfg!(mobile) {
+        tracing::warn!("could not listen on {}; falling back to manual code entry", ADDR);
+    }
+    let listening = listener.is_some();
+    *slot = Some(Pending {
+        state: state.to_string(),
+        code_verifier: code_verifier.to_string(),
+        listener,
+        cancel: Arc::new(Notify::new()),
+    });
+    listening
+}
+
+// The redirect URI for sign-ins started on this platform
+pub fn redirect_uri() -> &'static str {
+    if cfg!(mobile) {
+        APP_REDIRECT_URI
+    } else {
+        REDIRECT_URI
+    }
+}
+
+// The PKCE verifier for a manually entered code, if `state` matches the pending sign-in
+pub async fn take_verifier(state: &str) -> anyhow::Result<String> {
+    let mut slot = pending().lock().await;
+    match slot.as_ref() {
+        Some(p) if p.state == state => {
+            let p = slot.take().expect("checked above");
+            p.cancel.notify_waiters();
+            Ok(p.code_verifier)
+        }
+        Some(_) => Err(anyhow::anyhow!("Sign-in state does not match; start the Google sign-in again")),
+        None => Err(anyhow::anyhow!("No Google sign-in in progress")),
+    }
+}
+
+// Waits for the browser redirect of the pending sign-in. Returns (code, code_verifier).
+pub async fn wait(state: &str) -> anyhow::Result<(String, String)> {
+    let (listener, cancel, code_verifier) = {
+        let mut slot = pending().lock().await;
+        let p = match slot.as_mut() {
+            Some(p) if p.state == state => p,
+            Some(_) => anyhow::bail!("Sign-in state does not match; start the Google sign-in again"),
+            None => anyhow::bail!("No Google sign-in in progress"),
+        };
+        let listener = p.listener.take().ok_or_else(|| anyhow::anyhow!("Callback listener is not running"))?;
+        (listener, p.cancel.clone(), p.code_verifier.clone())
+    };
+    let outcome = tokio::select! {
+        r = accept_callback(&listener, state) => r,
+        _ = cancel.notified() => Err(anyhow::anyhow!("Sign-in was cancelled")),
+        _ = tokio::time::sleep(WAIT_TIMEOUT) => Err(anyhow::anyhow!("Timed out waiting for Google sign-in")),
+    };
+    drop(listener);
+    let mut slot = pending().lock().await;
+    if slot.as_ref().is_some_and(|p| p.state == state) {
+        *slot = None;
+    }
+    outcome.map(|code| (code, code_verifier))
+}
+
+async fn accept_callback(listener: &TcpListener, state: &str) -> anyhow::Result<String> {
+    loop {
+        let (mut stream, _) = listener.accept().await?;
+        let Some(target) = read_request_target(&mut stream).await else { continue };
+        let Some(params) = callback_params(&target) else {
+            respond(&mut stream, "404 Not Found", "Not found").await;
+            continue;
+        };
+        if params.get("state").map(String::as_str) != Some(state) {
+            respond(&mut stream, "400 Bad Request", "This sign-in link is not the one Journal Reader is waiting for.").await;
+            continue;
+        }
+        if let Some(error) = params.get("error") {
+            respond(&mut stream, "200 OK", "Google sign-in was not completed. You can close this window.").await;
+            anyhow::bail!("Google sign-in failed: {}", error);
+        }
+        match params.get("code").filter(|c| !c.is_empty()) {
+            Some(code) => {
+                respond(&mut stream, "200 OK", "Google Drive is connected. You can close this window and return to Journal Reader.").await;
+                return Ok(code.clone());
+            }
+            None => respond(&mut stream, "400 Bad Request", "The sign-in response had no authorization code.").await,
+        }
+    }
+}
+
+// The request target of a GET ("/callback?code=..."), read up to the end of the headers
+async fn read_request_target(stream: &mut TcpStream) -> Option<String> {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 2048];
+    while !buf.windows(4).any(|w| w == b"\r\n\r\n") && buf.len() < MAX_REQUEST_BYTES {
+        let n = tokio::time::timeout(Duration::from_secs(5), stream.read(&mut chunk)).await.ok()?.ok()?;
+        if n == 0 {
+            break;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    }
+    let head = String::from_utf8_lossy(&buf);
+    let mut parts = head.lines().next()?.split_whitespace();
+    (parts.next()? == "GET").then_some(())?;
+    parts.next().map(str::to_string)
+}
+
+// (code, code_verifier) from an app link, or None when the link isn't a sign-in redirect
+pub async fn redirect_code(url: &str) -> anyhow::Result<Option<(String, String)>> {
+    let Some(params) = app_redirect_params(url) else { return Ok(None) };
+    let state = params.get("state").map(String::as_str).unwrap_or_default();
+    let code_verifier = take_verifier(state).await?;
+    if let Some(error) = params.get("error") {
+        anyhow::bail!("Google sign-in failed: {}", error);
+    }
+    match params.get("code").filter(|c| !c.is_empty()) {
+        Some(code) => Ok(Some((code.clone(), code_verifier))),
+        None => anyhow::bail!("The sign-in response had no authorization code"),
+    }
+}
+
+fn app_redirect_params(url: &str) -> Option<HashMap<String, String>> {
+    let (scheme, rest) = url.split_once(':')?;
+    let expected = APP_REDIRECT_URI.split_once(':').map(|(s, _)| s)?;
+    if !scheme.eq_ignore_ascii_case(expected) {
+        return None;
+    }
+    // Some browsers hand back "scheme:///oauth2redirect"
+    query_params(&format!("/{}", rest.trim_start_matches('/')), "/oauth2redirect")
+}
+
+fn callback_params(target: &str) -> Option<HashMap<String, String>> {
+    query_params(target, "/callback")
+}
+
+// Decoded query parameters when the target's path is `expected_path`
+fn query_params(target: &str, expected_path: &str) -> Option<HashMap<String, String>> {
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    if path != expected_path {
+        return None;
+    }
+    let decode = |s: &str| urlencoding::decode(&s.replace('+', " ")).map(|d| d.into_owned()).unwrap_or_default();
+    Some(
+        query
+            .split('&')
+            .filter(|p| !p.is_empty())
+            .map(|p| {
+                let (k, v) = p.split_once('=').unwrap_or((p, ""));
+                (decode(k), decode(v))
+            })
+            .collect(),
+    )
+}
+
+async fn respond(stream: &mut TcpStream, status: &str, message: &str) {
+    let body = format!(
+        "<!doctype html><html><head><meta charset=\"utf-8\"><title>Journal Reader</title></head>\
+         <body style=\"font-family: sans-serif; margin: 3em\"><p>{}</p></body></html>",
+        message
+    );
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    let _ = stream.write_all(response.as_bytes()).await;
+    let _ = stream.shutdown().await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_callback_params() {
+        let params = callback_params("/callback?state=abc&code=4%2F0Ab+x&scope=drive").unwrap();
+        assert_eq!(params["code"], "4/0Ab x");
+        assert_eq!(params["state"], "abc");
+        assert!(callback_params("/favicon.ico").is_none());
+        assert!(callback_params("/callback").unwrap().is_empty());
+
+        let scheme = APP_REDIRECT_URI.split_once(':').unwrap().0;
+        let link = app_redirect_params(&format!("{}:/oauth2redirect?state=abc&code=4%2F0Ab", scheme)).unwrap();
+        assert_eq!((link["state"].as_str(), link["code"].as_str()), ("abc", "4/0Ab"));
+        assert!(app_redirect_params(&format!("{}:///oauth2redirect?code=x", scheme)).is_some());
+        assert!(app_redirect_params(&format!("{}:/entries/12", scheme)).is_none());
+        assert!(app_redirect_params("https://example.com/oauth2redirect?code=x").is_none());
+    }
+}
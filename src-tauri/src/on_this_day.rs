@@ -0,0 +1,191 @@
+use anyhow::Result;
+use chrono::{Datelike, NaiveDate, NaiveTime};
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::AppHandle;
+
+use crate::database::{entry_from_row, ENTRY_COLUMNS};
+
+// "On this day": entries written on today's month and day in earlier years, and the optional
+// morning notification that summarizes them. Days are compared on the stored (UTC) entry date.
+//
+// Desktop notifications can't carry a click action, so the link is held here instead: when the
+// window next gains focus (which clicking the notification does), the UI is sent to the day.
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(5 * 60);
+const DEFAULT_TIME: &str = "08:00";
+const LAST_NOTIFIED_KEY: &str = "on_this_day_last_notified";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OnThisDayEntry {
+    pub id: String,
+    pub year: i32,
+    pub entry_date: String,
+    pub title: Option<String>,
+    pub snippet: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OnThisDay {
+    pub date: String,
+    // Newest first, like the entries
+    pub years: Vec<i32>,
+    pub entries: Vec<OnThisDayEntry>,
+}
+
+// Payload of `app://navigate`
+#[derive(Debug, Clone, Serialize)]
+pub struct NavigateTo {
+    pub view: String,
+    pub date: String,
+}
+
+static PENDING_LINK: Mutex<Option<NavigateTo>> = Mutex::new(None);
+
+pub(crate) fn query(conn: &Connection, date: NaiveDate) -> Result<OnThisDay> {
+    let mut days = vec![date.format("%m-%d").to_string()];
+    // Leap-day entries show up on Feb 28 in other years
+    if date.month() == 2 && date.day() == 28 && NaiveDate::from_ymd_opt(date.year(), 2, 29).is_none() {
+        days.push("02-29".to_string());
+    }
+    let placeholders = (0..days.len()).map(|i| format!("?{}", i + 2)).collect::<Vec<_>>().join(", ");
+    let sql = format!(
+        "SELECT {} FROM entries WHERE substr(entry_date, 1, 4) < ?1 AND substr(entry_date, 6, 5) IN ({}) ORDER BY entry_date DESC",
+        ENTRY_COLUMNS, placeholders
+    );
+    let mut values: Vec<String> = vec![format!("{:04}", date.year())];
+    values.extend(days);
+    let mut stmt = conn.prepare(&sql)?;
+    let entries = stmt
+        .query_map(rusqlite::params_from_iter(values.iter()), entry_from_row)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    let mut years: Vec<i32> = entries.iter().map(|e| e.entry_date.year()).collect();
+    years.dedup();
+    Ok(OnThisDay {
+        date: date.to_string(),
+        years,
+        entries: entries
+            .into_iter()
+            .map(|e| OnThisDayEntry {
+                id: e.id,
+                year: e.entry_date.year(),
+                entry_date: e.entry_date.to_rfc3339(),
+                title: e.title,
+                snippet: crate::csv_export::lead(&e.body),
+            })
+            .collect(),
+    })
+}
+
+pub async fn get(app_handle: &AppHandle, date: NaiveDate) -> Result<OnThisDay> {
+    let conn = crate::database::open_conn(app_handle)?;
+    query(&conn, date)
+}
+
+fn join_years(years: &[i32]) -> String {
+    let years: Vec<String> = years.iter().map(|y| y.to_string()).collect();
+    match years.split_last() {
+        Some((last, rest)) if !rest.is_empty() => format!("{} and {}", rest.join(", "), last),
+        _ => years.join(""),
+    }
+}
+
+// Notification body, or None when there is nothing to show
+pub(crate) fn notification_text(day: &OnThisDay) -> Option<String> {
+    let latest = day.entries.first()?;
+    let count = match day.entries.len() {
+        1 => "1 entry".to_string(),
+        n => format!("{} entries", n),
+    };
+    let mut text = format!("You wrote {} on this day in {}", count, join_years(&day.years));
+    if let Some(title) = latest.title.as_deref().map(str::trim).filter(|t| !t.is_empty()) {
+        text.push_str(&format!(". Latest: \u{201c}{}\u{201d}", title));
+    }
+    Some(text)
+}
+
+fn notify_time(conn: &Connection) -> NaiveTime {
+    let value = crate::database::read_setting(conn, "on_this_day_time").unwrap_or_else(|| DEFAULT_TIME.to_string());
+    NaiveTime::parse_from_str(value.trim(), "%H:%M")
+        .unwrap_or_else(|_| NaiveTime::parse_from_str(DEFAULT_TIME, "%H:%M").expect("valid default"))
+}
+
+// Shows today's notification once, at or after the configured local time
+async fn check_and_notify(app_handle: &AppHandle) -> Result<()> {
+    use tauri_plugin_notification::NotificationExt;
+
+    let now = chrono::Local::now();
+    let today = now.date_naive();
+    let day = {
+        let conn = crate::database::open_conn(app_handle)?;
+        if !crate::database::setting_enabled(&conn, "on_this_day_notification", false) || now.time() < notify_time(&conn) {
+            return Ok(());
+        }
+        if crate::database::read_setting(&conn, LAST_NOTIFIED_KEY).as_deref() == Some(today.to_string().as_str()) {
+            return Ok(());
+        }
+        conn.execute(
+            "INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)",
+            params![LAST_NOTIFIED_KEY, today.to_string()],
+        )?;
+        query(&conn, today)?
+    };
+    let Some(body) = notification_text(&day) else { return Ok(()) };
+    app_handle.notification().builder().title("On this day").body(body).show()?;
+    if let Ok(mut pending) = PENDING_LINK.lock() {
+        *pending = Some(NavigateTo { view: "on-this-day".to_string(), date: day.date });
+    }
+    Ok(())
+}
+
+pub fn start_scheduler(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(CHECK_INTERVAL).await;
+            if let Err(e) = check_and_notify(&app_handle).await {
+                tracing::warn!("notification check failed: {}", e);
+            }
+        }
+    });
+}
+
+// Called when the main window gains focus; opens the day from a notification shown earlier
+pub fn take_pending_link() -> Option<NavigateTo> {
+    PENDING_LINK.lock().ok().and_then(|mut p| p.take())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_on_this_day_query_and_text() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            r#"CREATE TABLE entries (id TEXT, title TEXT, body TEXT, entry_date TEXT, entry_timezone TEXT, source_path TEXT,
+                   source_type TEXT, text_hash TEXT, created_at TEXT, updated_at TEXT, sentiment REAL, language TEXT);
+               INSERT INTO entries (id, title, body, entry_date, entry_timezone, source_path, source_type, text_hash, created_at, updated_at) VALUES
+                 ('a', 'Moving day', 'Boxes everywhere.', '2021-03-05T12:00:00+00:00', 'UTC', '', 'txt', 'a', '', ''),
+                 ('b', NULL, 'Rain.', '2019-03-05T12:00:00+00:00', 'UTC', '', 'txt', 'b', '', ''),
+                 ('c', NULL, 'Today.', '2024-03-05T12:00:00+00:00', 'UTC', '', 'txt', 'c', '', ''),
+                 ('d', NULL, 'Other day.', '2020-03-06T12:00:00+00:00', 'UTC', '', 'txt', 'd', '', ''),
+                 ('e', NULL, 'Leap.', '2020-02-29T12:00:00+00:00', 'UTC', '', 'txt', 'e', '', '');"#,
+        )
+        .unwrap();
+
+        let day = query(&conn, NaiveDate::from_ymd_opt(2024, 3, 5).unwrap()).unwrap();
+        assert_eq!(day.entries.iter().map(|e| e.id.as_str()).collect::<Vec<_>>(), ["a", "b"]);
+        assert_eq!(day.years, [2021, 2019]);
+        assert_eq!(
+            notification_text(&day).unwrap(),
+            "You wrote 2 entries on this day in 2021 and 2019. Latest: \u{201c}Moving day\u{201d}"
+        );
+
+        let feb28 = query(&conn, NaiveDate::from_ymd_opt(2023, 2, 28).unwrap()).unwrap();
+        assert_eq!(feb28.entries.len(), 1);
+        assert!(notification_text(&query(&conn, NaiveDate::from_ymd_opt(2024, 7, 1).unwrap()).unwrap()).is_none());
+    }
+}
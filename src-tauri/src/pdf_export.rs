@@ -0,0 +1,514 @@
+use chrono::{Datelike, NaiveDate};
+use pdf_writer::{Content, Finish, Name, Pdf, Rect, Ref, Str, TextStr};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::database::{Entry, EntryFilter};
+use crate::locale::DateFormat;
+
+// Printable "book" of the entries matching a search filter: a title page, an optional table of contents, then one
+// chapter per month with each entry under its date. Uses the PDF standard Helvetica fonts with
+// WinAnsi encoding so nothing has to be embedded; characters outside that set print as "?".
+
+const MARGIN: f32 = 64.0;
+const FOOTER_Y: f32 = 36.0;
+const TOC_LINE: f32 = 20.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PageSize {
+    Letter,
+    A4,
+}
+
+impl PageSize {
+    fn dimensions(self) -> (f32, f32) {
+        match self {
+            PageSize::Letter => (612.0, 792.0),
+            PageSize::A4 => (595.0, 842.0),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PdfOptions {
+    // Defaults to "Journal" and the years covered
+    pub title: Option<String>,
+    pub table_of_contents: bool,
+    pub page_size: PageSize,
+    pub font_size: f32,
+}
+
+impl Default for PdfOptions {
+    fn default() -> Self {
+        Self { title: None, table_of_contents: true, page_size: PageSize::Letter, font_size: 11.0 }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PdfExportReport {
+    pub path: String,
+    pub pages: u32,
+    pub entries: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Font {
+    Regular,
+    Bold,
+    Oblique,
+}
+
+impl Font {
+    fn resource(self) -> Name<'static> {
+        match self {
+            Font::Regular => Name(b"F1"),
+            Font::Bold => Name(b"F2"),
+            Font::Oblique => Name(b"F3"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Op {
+    Text { x: f32, y: f32, font: Font, size: f32, text: String },
+    Rule { x1: f32, x2: f32, y: f32 },
+}
+
+// Helvetica and Helvetica-Bold advance widths (1/1000 em) for ASCII 32..=126, from the
+// standard AFM metrics. Helvetica-Oblique shares the regular widths.
+const HELVETICA: [u16; 95] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278, 556, 556, 556, 556, 556, 556, 556,
+    556, 556, 556, 278, 278, 584, 584, 584, 556, 1015, 667, 667, 722, 722, 667, 611, 778, 722, 278, 500, 667, 556, 833,
+    722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, 278, 278, 278, 469, 556, 333, 556, 556, 500, 556,
+    556, 278, 556, 556, 222, 222, 500, 222, 833, 556, 556, 556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334,
+    260, 334, 584,
+];
+const HELVETICA_BOLD: [u16; 95] = [
+    278, 333, 474, 556, 556, 889, 722, 238, 333, 333, 389, 584, 278, 333, 278, 278, 556, 556, 556, 556, 556, 556, 556,
+    556, 556, 556, 333, 333, 584, 584, 584, 611, 975, 722, 722, 722, 722, 667, 611, 778, 722, 278, 556, 722, 611, 833,
+    722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, 333, 278, 333, 584, 556, 333, 556, 611, 556, 611,
+    556, 333, 611, 611, 278, 278, 556, 278, 889, 611, 611, 611, 611, 389, 556, 333, 611, 556, 778, 556, 556, 500, 389,
+    280, 389, 584,
+];
+
+fn char_width(c: char, font: Font) -> u16 {
+    let table = if font == Font::Bold { &HELVETICA_BOLD } else { &HELVETICA };
+    match c as u32 {
+        code @ 32..=126 => table[(code - 32) as usize],
+        _ => table[('n' as u32 - 32) as usize],
+    }
+}
+
+fn text_width(text: &str, font: Font, size: f32) -> f32 {
+    text.chars().map(|c| char_width(c, font) as f32).sum::<f32>() * size / 1000.0
+}
+
+// WinAnsiEncoding bytes for a string; unmapped characters become '?'
+fn win_ansi(text: &str) -> Vec<u8> {
+    text.chars()
+        .map(|c| match c {
+            ' '..='~' => c as u8,
+            '\u{a0}'..='\u{ff}' => c as u32 as u8,
+            '\t' => b' ',
+            '€' => 0x80,
+            '‚' => 0x82,
+            'ƒ' => 0x83,
+            '„' => 0x84,
+            '…' => 0x85,
+            '†' => 0x86,
+            '‡' => 0x87,
+            'ˆ' => 0x88,
+            '‰' => 0x89,
+            'Š' => 0x8a,
+            '‹' => 0x8b,
+            'Œ' => 0x8c,
+            'Ž' => 0x8e,
+            '‘' => 0x91,
+            '’' => 0x92,
+            '“' => 0x93,
+            '”' => 0x94,
+            '•' => 0x95,
+            '–' => 0x96,
+            '—' => 0x97,
+            '˜' => 0x98,
+            '™' => 0x99,
+            'š' => 0x9a,
+            '›' => 0x9b,
+            'œ' => 0x9c,
+            'ž' => 0x9e,
+            'Ÿ' => 0x9f,
+            _ => b'?',
+        })
+        .collect()
+}
+
+// Greedy word wrap to `max_width`; words wider than a line are broken by character
+fn wrap(text: &str, font: Font, size: f32, max_width: f32) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        let candidate = if line.is_empty() { word.to_string() } else { format!("{} {}", line, word) };
+        if text_width(&candidate, font, size) <= max_width {
+            line = candidate;
+            continue;
+        }
+        if !line.is_empty() {
+            lines.push(std::mem::take(&mut line));
+        }
+        for c in word.chars() {
+            line.push(c);
+            if text_width(&line, font, size) > max_width && line.chars().count() > 1 {
+                line.pop();
+                lines.push(std::mem::replace(&mut line, c.to_string()));
+            }
+        }
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+struct Layout {
+    width: f32,
+    height: f32,
+    pages: Vec<Vec<Op>>,
+    y: f32,
+}
+
+impl Layout {
+    fn new(size: PageSize) -> Self {
+        let (width, height) = size.dimensions();
+        Self { width, height, pages: Vec::new(), y: 0.0 }
+    }
+
+    fn new_page(&mut self) {
+        self.pages.push(Vec::new());
+        self.y = self.height - MARGIN;
+    }
+
+    fn text_width(&self) -> f32 {
+        self.width - 2.0 * MARGIN
+    }
+
+    // Starts a new page unless `height` more points fit above the bottom margin
+    fn ensure(&mut self, height: f32) {
+        if self.pages.is_empty() || self.y - height < MARGIN {
+            self.new_page();
+        }
+    }
+
+    fn push(&mut self, op: Op) {
+        self.pages.last_mut().expect("a page is open").push(op);
+    }
+
+    fn line(&mut self, font: Font, size: f32, text: &str, leading: f32) {
+        self.ensure(leading);
+        self.y -= size;
+        let y = self.y;
+        self.push(Op::Text { x: MARGIN, y, font, size, text: text.to_string() });
+        self.y -= leading - size;
+    }
+
+    fn paragraph(&mut self, font: Font, size: f32, text: &str, leading: f32) {
+        for line in wrap(text, font, size, self.text_width()) {
+            self.line(font, size, &line, leading);
+        }
+    }
+
+    fn centered(&mut self, font: Font, size: f32, text: &str, y: f32) {
+        let x = (self.width - text_width(text, font, size)) / 2.0;
+        self.push(Op::Text { x, y, font, size, text: text.to_string() });
+    }
+}
+
+struct Chapter {
+    title: String,
+    // Index into the content pages
+    page: usize,
+}
+
+fn book_title(options: &PdfOptions, start: NaiveDate, end: NaiveDate) -> String {
+    match options.title.as_deref().map(str::trim).filter(|t| !t.is_empty()) {
+        Some(title) => title.to_string(),
+        None if start.year() == end.year() => format!("Journal {}", start.year()),
+        None => format!("Journal {}\u{2013}{}", start.year(), end.year()),
+    }
+}
+
+// Content pages: a chapter per month, entries in date order
+fn layout_entries(entries: &[Entry], options: &PdfOptions, dates: &DateFormat) -> (Vec<Vec<Op>>, Vec<Chapter>) {
+    let size = options.font_size;
+    let leading = size * 1.45;
+    let mut layout = Layout::new(options.page_size);
+    let mut chapters = Vec::new();
+    let mut month = None;
+    for entry in entries {
+        let date = entry.entry_date.date_naive();
+        if month != Some((date.year(), date.month())) {
+            month = Some((date.year(), date.month()));
+            layout.new_page();
+            let title = dates.month_year(date);
+            chapters.push(Chapter { title: title.clone(), page: layout.pages.len() - 1 });
+            layout.line(Font::Bold, size * 2.0, &title, size * 2.0 + 8.0);
+            let y = layout.y;
+            layout.push(Op::Rule { x1: MARGIN, x2: layout.width - MARGIN, y });
+            layout.y -= size * 1.5;
+        } else {
+            layout.y -= leading;
+        }
+        // Keep a date heading with at least the first lines of its entry
+        layout.ensure(leading * 4.0);
+        layout.line(Font::Bold, size * 1.1, &dates.long_date(date), leading * 1.2);
+        if let Some(title) = entry.title.as_deref().map(str::trim).filter(|t| !t.is_empty()) {
+            layout.paragraph(Font::Oblique, size, title, leading);
+        }
+        layout.y -= size * 0.4;
+        let mut first = true;
+        for paragraph in entry.body.split("\n\n").map(str::trim).filter(|p| !p.is_empty()) {
+            if !first {
+                layout.y -= size * 0.6;
+            }
+            first = false;
+            for line in paragraph.lines().map(str::trim).filter(|l| !l.is_empty()) {
+                layout.paragraph(Font::Regular, size, line, leading);
+            }
+        }
+    }
+    (layout.pages, chapters)
+}
+
+fn toc_lines_per_page(options: &PdfOptions) -> usize {
+    let (_, height) = options.page_size.dimensions();
+    // The first TOC page loses room to its heading; plan for that on every page
+    (((height - 2.0 * MARGIN - 60.0) / TOC_LINE).floor() as usize).max(1)
+}
+
+// Table of contents pages; `first_content_page` is the printed number of the first content page
+fn layout_toc(chapters: &[Chapter], options: &PdfOptions, first_content_page: usize) -> Vec<Vec<Op>> {
+    let size = options.font_size;
+    let mut layout = Layout::new(options.page_size);
+    let per_page = toc_lines_per_page(options);
+    for (i, chapter) in chapters.iter().enumerate() {
+        if i % per_page == 0 {
+            layout.new_page();
+            if i == 0 {
+                layout.line(Font::Bold, size * 2.0, "Contents", size * 2.0 + 8.0);
+                layout.y -= size * 1.5;
+            }
+        }
+        layout.y -= TOC_LINE;
+        let number = (first_content_page + chapter.page).to_string();
+        let y = layout.y;
+        layout.push(Op::Text { x: MARGIN, y, font: Font::Regular, size, text: chapter.title.clone() });
+        let x = layout.width - MARGIN - text_width(&number, Font::Regular, size);
+        layout.push(Op::Text { x, y, font: Font::Regular, size, text: number });
+    }
+    layout.pages
+}
+
+fn toc_page_count(chapters: usize, options: &PdfOptions) -> usize {
+    if !options.table_of_contents || chapters == 0 {
+        return 0;
+    }
+    chapters.div_ceil(toc_lines_per_page(options))
+}
+
+pub fn render(entries: &[Entry], start: NaiveDate, end: NaiveDate, options: &PdfOptions, dates: &DateFormat) -> (Vec<u8>, u32) {
+    let title = book_title(options, start, end);
+    let mut title_page = Layout::new(options.page_size);
+    title_page.new_page();
+    let middle = title_page.height * 0.6;
+    title_page.centered(Font::Bold, 28.0, &title, middle);
+    let range = format!("{} \u{2013} {}", dates.medium_date(start), dates.medium_date(end));
+    title_page.centered(Font::Regular, 13.0, &range, middle - 34.0);
+    let count = format!("{} {}", entries.len(), if entries.len() == 1 { "entry" } else { "entries" });
+    title_page.centered(Font::Oblique, 11.0, &count, middle - 54.0);
+
+    let (content, chapters) = layout_entries(entries, options, dates);
+    let toc_pages = toc_page_count(chapters.len(), options);
+    // Printed page numbers count the title page as 1
+    let first_content_page = 2 + toc_pages;
+    let toc = if toc_pages > 0 { layout_toc(&chapters, options, first_content_page) } else { Vec::new() };
+
+    let mut pages = title_page.pages;
+    pages.extend(toc);
+    pages.extend(content);
+    let (width, height) = options.page_size.dimensions();
+
+    let mut alloc = Ref::new(1);
+    let catalog_id = alloc.bump();
+    let tree_id = alloc.bump();
+    let info_id = alloc.bump();
+    let outline_id = alloc.bump();
+    let font_ids = [alloc.bump(), alloc.bump(), alloc.bump()];
+    let page_ids: Vec<Ref> = pages.iter().map(|_| alloc.bump()).collect();
+    let item_ids: Vec<Ref> = chapters.iter().map(|_| alloc.bump()).collect();
+
+    let mut pdf = Pdf::new();
+    let mut catalog = pdf.catalog(catalog_id);
+    catalog.pages(tree_id);
+    if !chapters.is_empty() {
+        catalog.outlines(outline_id);
+    }
+    catalog.finish();
+    pdf.pages(tree_id).kids(page_ids.iter().copied()).count(page_ids.len() as i32);
+    pdf.document_info(info_id).title(TextStr(&title)).creator(TextStr("Journal Reader"));
+    for (id, name) in font_ids.iter().zip(["Helvetica", "Helvetica-Bold", "Helvetica-Oblique"]) {
+        pdf.type1_font(*id).base_font(Name(name.as_bytes())).encoding_predefined(Name(b"WinAnsiEncoding"));
+    }
+
+    for (index, (ops, page_id)) in pages.iter().zip(&page_ids).enumerate() {
+        let content_id = alloc.bump();
+        let mut page = pdf.page(*page_id);
+        page.media_box(Rect::new(0.0, 0.0, width, height)).parent(tree_id).contents(content_id);
+        let mut resources = page.resources();
+        let mut fonts = resources.fonts();
+        for (font, id) in [Font::Regular, Font::Bold, Font::Oblique].iter().zip(font_ids) {
+            fonts.pair(font.resource(), id);
+        }
+        fonts.finish();
+        resources.finish();
+        page.finish();
+
+        let mut stream = Content::new();
+        let mut draw_text = |x: f32, y: f32, font: Font, size: f32, text: &str| {
+            stream.begin_text();
+            stream.set_font(font.resource(), size);
+            stream.next_line(x, y);
+            stream.show(Str(&win_ansi(text)));
+            stream.end_text();
+        };
+        for op in ops {
+            if let Op::Text { x, y, font, size, text } = op {
+                draw_text(*x, *y, *font, *size, text);
+            }
+        }
+        if index > 0 {
+            let number = (index + 1).to_string();
+            let x = (width - text_width(&number, Font::Regular, 9.0)) / 2.0;
+            draw_text(x, FOOTER_Y, Font::Regular, 9.0, &number);
+        }
+        for op in ops {
+            if let Op::Rule { x1, x2, y } = op {
+                stream.set_line_width(0.5);
+                stream.move_to(*x1, *y);
+                stream.line_to(*x2, *y);
+                stream.stroke();
+            }
+        }
+        pdf.stream(content_id, &stream.finish());
+    }
+
+    // Bookmarks: one per month
+    if !chapters.is_empty() {
+        pdf.outline(outline_id)
+            .first(item_ids[0])
+            .last(*item_ids.last().expect("non-empty"))
+            .count(item_ids.len() as i32);
+        let content_start = first_content_page - 1;
+        for (i, chapter) in chapters.iter().enumerate() {
+            let mut item = pdf.outline_item(item_ids[i]);
+            item.title(TextStr(&chapter.title)).parent(outline_id);
+            if i > 0 {
+                item.prev(item_ids[i - 1]);
+            }
+            if let Some(next) = item_ids.get(i + 1) {
+                item.next(*next);
+            }
+            item.dest().page(page_ids[content_start + chapter.page]).xyz(0.0, height, None);
+        }
+    }
+
+    (pdf.finish(), pages.len() as u32)
+}
+
+// The range shown on the title page: the filter's dates where given, else the entries' own
+fn covered_range(filter: &EntryFilter, entries: &[Entry]) -> Option<(NaiveDate, NaiveDate)> {
+    let day = |value: &Option<String>| {
+        let value = value.as_deref()?.trim();
+        NaiveDate::parse_from_str(value, "%Y-%m-%d")
+            .ok()
+            .or_else(|| chrono::DateTime::parse_from_rfc3339(value).ok().map(|d| d.date_naive()))
+    };
+    let start = day(&filter.date_from).or_else(|| entries.first().map(|e| e.entry_date.date_naive()))?;
+    let end = day(&filter.date_to).or_else(|| entries.last().map(|e| e.entry_date.date_naive()))?;
+    Some((start, end))
+}
+
+pub async fn export_pdf(
+    app_handle: &AppHandle,
+    filter: &EntryFilter,
+    options: &PdfOptions,
+    path: &str,
+) -> anyhow::Result<PdfExportReport> {
+    if !(6.0..=24.0).contains(&options.font_size) {
+        anyhow::bail!("Font size must be between 6 and 24");
+    }
+    let conn = crate::database::open_conn(app_handle)?;
+    let entries = crate::database::filtered_entries(&conn, filter)?;
+    let Some((start, end)) = covered_range(filter, &entries).filter(|_| !entries.is_empty()) else {
+        anyhow::bail!("No entries match the filter");
+    };
+    let (bytes, pages) = render(&entries, start, end, options, &DateFormat::from_conn(&conn));
+    std::fs::write(path, bytes)?;
+    Ok(PdfExportReport { path: path.to_string(), pages, entries: entries.len() as u32 })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(date: &str, body: &str) -> Entry {
+        let at = format!("{}T12:00:00Z", date).parse().unwrap();
+        Entry {
+            id: date.to_string(),
+            title: None,
+            body: body.to_string(),
+            entry_date: at,
+            entry_timezone: "UTC".into(),
+            source_path: String::new(),
+            source_type: "txt".into(),
+            text_hash: String::new(),
+            embedding: None,
+            created_at: at,
+            updated_at: at,
+            sentiment: None,
+            language: None,
+        }
+    }
+
+    #[test]
+    fn test_wrap_fits_width() {
+        let text = "The quick brown fox jumps over the lazy dog. ".repeat(20);
+        let lines = wrap(&text, Font::Regular, 11.0, 200.0);
+        assert!(lines.len() > 5);
+        assert!(lines.iter().all(|l| text_width(l, Font::Regular, 11.0) <= 200.0));
+        let long = wrap(&"x".repeat(200), Font::Regular, 11.0, 100.0);
+        assert!(long.len() > 1 && long.iter().all(|l| text_width(l, Font::Regular, 11.0) <= 100.0));
+        assert_eq!(win_ansi("café – “ok” ✓"), b"caf\xe9 \x96 \x93ok\x94 ?");
+    }
+
+    #[test]
+    fn test_render_chapters_per_month() {
+        let long = "A fairly ordinary day with a walk and some reading.\n\n".repeat(80);
+        let entries = vec![entry("2021-01-03", &long), entry("2021-01-20", "Short."), entry("2021-03-01", "Spring.")];
+        let (content, chapters) = layout_entries(&entries, &PdfOptions::default(), &DateFormat::default());
+        let titles: Vec<&str> = chapters.iter().map(|c| c.title.as_str()).collect();
+        assert_eq!(titles, ["January 2021", "March 2021"]);
+        let (_, french) = layout_entries(&entries, &PdfOptions::default(), &DateFormat::new("fr", "monday"));
+        assert_eq!(french[1].title, "mars 2021");
+        assert!(content.len() > 2);
+        assert_eq!(chapters[1].page, content.len() - 1);
+
+        let start = NaiveDate::from_ymd_opt(2021, 1, 1).unwrap();
+        let end = NaiveDate::from_ymd_opt(2021, 3, 31).unwrap();
+        let (bytes, pages) = render(&entries, start, end, &PdfOptions::default(), &DateFormat::default());
+        assert_eq!(pages as usize, 1 + 1 + content.len());
+        assert!(bytes.starts_with(b"%PDF-"));
+        let no_toc = PdfOptions { table_of_contents: false, ..Default::default() };
+        assert_eq!(render(&entries, start, end, &no_toc, &DateFormat::default()).1, pages - 1);
+    }
+}
@@ -0,0 +1,261 @@
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::app_import::{local_to_utc, AppEntry, AppImportReport};
+use crate::import::{date_from_title, date_heading, FileType};
+
+// PDF import with splitting, for scanned or typed journals kept as one long PDF.
+//
+// The text layer is read page by page (scans need OCR first; a PDF of images has no text) and
+// cut into entries: by page ranges ("1-10, 11-20, 25"), every N pages, or at each date heading.
+// A date heading is a short line on its own made mostly of a date ("March 5th, 2021",
+// "Monday 5 Mar 2021", "2021-03-05"), so dates mentioned inside sentences don't split. A
+// segment's date is its heading's, else the last date seen before it, else one in the file
+// name, else the file's modification time; entries are dated at noon in the import's time zone.
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum PdfSplit {
+    #[default]
+    Whole,
+    // "1-10, 11-20, 25, 30-"; pages outside every range are left out
+    Ranges { ranges: String },
+    Every { pages: usize },
+    Dates,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PdfSegment {
+    // 1-based and inclusive
+    pub first_page: usize,
+    pub last_page: usize,
+    pub date: Option<NaiveDate>,
+    pub title: Option<String>,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SegmentPreview {
+    pub first_page: usize,
+    pub last_page: usize,
+    pub date: Option<NaiveDate>,
+    pub title: Option<String>,
+    pub words: usize,
+    pub excerpt: String,
+}
+
+// Trimmed lines, with runs of blank lines (common in extracted text) kept to one
+fn tidy(text: &str) -> String {
+    let mut out: Vec<&str> = Vec::new();
+    for line in text.lines().map(str::trim_end) {
+        if line.trim().is_empty() && out.last().is_none_or(|l| l.is_empty()) {
+            continue;
+        }
+        out.push(if line.trim().is_empty() { "" } else { line });
+    }
+    while out.last().is_some_and(|l| l.is_empty()) {
+        out.pop();
+    }
+    out.join("\n")
+}
+
+pub fn parse_ranges(spec: &str, page_count: usize) -> Result<Vec<(usize, usize)>> {
+    let mut ranges = Vec::new();
+    for part in spec.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let number = |s: &str| s.trim().parse::<usize>().with_context(|| format!("Invalid page range \"{}\"", part));
+        let (first, last) = match part.split_once('-') {
+            Some((a, b)) if b.trim().is_empty() => (number(a)?, page_count),
+            Some((a, b)) => (number(a)?, number(b)?),
+            None => (number(part)?, number(part)?),
+        };
+        if first == 0 || first > last || first > page_count {
+            anyhow::bail!("Page range \"{}\" is outside the document's {} pages", part, page_count);
+        }
+        ranges.push((first, last.min(page_count)));
+    }
+    if ranges.is_empty() {
+        anyhow::bail!("No page ranges given");
+    }
+    Ok(ranges)
+}
+
+// Pages `first..=last` as one segment; a date heading among its first lines becomes the title
+fn segment(pages: &[String], first: usize, last: usize) -> PdfSegment {
+    let text = tidy(&pages[first - 1..last].join("\n"));
+    let found = text.lines().enumerate().filter(|(_, l)| !l.trim().is_empty()).take(3).find_map(|(i, l)| date_heading(l).map(|d| (i, d)));
+    match found {
+        Some((i, date)) => {
+            let lines: Vec<&str> = text.lines().collect();
+            PdfSegment {
+                first_page: first,
+                last_page: last,
+                date: Some(date),
+                title: Some(lines[i].trim().to_string()),
+                text: tidy(&[&lines[..i], &lines[i + 1..]].concat().join("\n")),
+            }
+        }
+        None => PdfSegment { first_page: first, last_page: last, date: None, title: None, text },
+    }
+}
+
+fn split_on_dates(pages: &[String]) -> Vec<PdfSegment> {
+    let mut segments = Vec::new();
+    let mut current = PdfSegment { first_page: 1, last_page: 1, date: None, title: None, text: String::new() };
+    for (i, page) in pages.iter().enumerate() {
+        let number = i + 1;
+        for line in page.lines() {
+            if let Some(date) = date_heading(line) {
+                let next = PdfSegment { first_page: number, last_page: number, date: Some(date), title: Some(line.trim().to_string()), text: String::new() };
+                let done = std::mem::replace(&mut current, next);
+                if !done.text.trim().is_empty() {
+                    segments.push(PdfSegment { text: tidy(&done.text), ..done });
+                }
+            } else {
+                current.text.push_str(line);
+                current.text.push('\n');
+                if !line.trim().is_empty() {
+                    current.last_page = number;
+                }
+            }
+        }
+    }
+    if !current.text.trim().is_empty() {
+        segments.push(PdfSegment { text: tidy(&current.text), ..current });
+    }
+    segments
+}
+
+pub fn split(pages: &[String], how: &PdfSplit) -> Result<Vec<PdfSegment>> {
+    let count = pages.len();
+    if count == 0 {
+        return Ok(Vec::new());
+    }
+    Ok(match how {
+        PdfSplit::Whole => vec![segment(pages, 1, count)],
+        PdfSplit::Ranges { ranges } => parse_ranges(ranges, count)?.into_iter().map(|(a, b)| segment(pages, a, b)).collect(),
+        PdfSplit::Every { pages: 0 } => anyhow::bail!("Pages per entry must be at least 1"),
+        PdfSplit::Every { pages: n } => (1..=count).step_by(*n).map(|a| segment(pages, a, (a + n - 1).min(count))).collect(),
+        PdfSplit::Dates => split_on_dates(pages),
+    })
+}
+
+pub fn read_pages(path: &Path) -> Result<Vec<String>> {
+    let pages = pdf_extract::extract_text_by_pages(path).map_err(|e| anyhow::anyhow!("Could not read {}: {}", path.display(), e))?;
+    if pages.iter().all(|p| p.trim().is_empty()) {
+        anyhow::bail!("{} has no text layer; run it through OCR first", path.display());
+    }
+    Ok(pages)
+}
+
+async fn segments(path: &str, how: &PdfSplit) -> Result<Vec<PdfSegment>> {
+    let owned = std::path::PathBuf::from(path);
+    let pages = tokio::task::spawn_blocking(move || read_pages(&owned)).await??;
+    split(&pages, how)
+}
+
+pub async fn preview(path: &str, how: &PdfSplit) -> Result<Vec<SegmentPreview>> {
+    Ok(segments(path, how)
+        .await?
+        .into_iter()
+        .map(|s| SegmentPreview {
+            first_page: s.first_page,
+            last_page: s.last_page,
+            date: s.date,
+            title: s.title,
+            words: s.text.split_whitespace().count(),
+            excerpt: s.text.chars().take(160).collect(),
+        })
+        .collect())
+}
+
+pub fn to_entries(segments: Vec<PdfSegment>, file_name: &str, fallback: DateTime<Utc>, timezone: &str) -> Vec<AppEntry> {
+    let stem = Path::new(file_name).file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let mut last_date = date_from_title(&stem);
+    let single = segments.len() == 1;
+    let noon = NaiveTime::from_hms_opt(12, 0, 0).expect("valid time");
+    segments
+        .into_iter()
+        .map(|s| {
+            last_date = s.date.or(last_date);
+            let pages = if s.first_page == s.last_page { format!("page {}", s.first_page) } else { format!("pages {}-{}", s.first_page, s.last_page) };
+            AppEntry {
+                source: format!("{}#pages={}-{}", file_name, s.first_page, s.last_page),
+                title: s.title.or_else(|| Some(if single { stem.clone() } else { format!("{}, {}", stem, pages) })),
+                body: s.text,
+                tags: Vec::new(),
+                created: last_date.map(|d| local_to_utc(d.and_time(noon), timezone)).unwrap_or(fallback),
+                timezone: timezone.to_string(),
+                sentiment: None,
+            }
+        })
+        .collect()
+}
+
+pub async fn import_pdf(app_handle: &AppHandle, path: &str, how: &PdfSplit, timezone: &str) -> Result<AppImportReport> {
+    let segments = segments(path, how).await?;
+    let file_name = Path::new(path).file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_else(|| path.to_string());
+    let modified = std::fs::metadata(path).and_then(|m| m.modified()).map(DateTime::<Utc>::from).unwrap_or_else(|_| Utc::now());
+    let mut report = AppImportReport::default();
+    crate::app_import::save_all(app_handle, to_entries(segments, &file_name, modified, timezone), FileType::Pdf, &mut report).await?;
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pages() -> Vec<String> {
+        [
+            "My Journal 2021\n\nKept by A.\n",
+            "March 5th, 2021\nWoke early. On 3/1/2021 I said I would\nstart running.\n\n\n\nWent for a run.\n",
+            "More about the run.\nSaturday 6 Mar 2021\nRain.\n",
+            "2021-03-08\nBack to work.\n",
+        ]
+        .iter()
+        .map(|p| p.to_string())
+        .collect()
+    }
+
+    #[test]
+    fn test_split_on_dates() {
+        let segments = split(&pages(), &PdfSplit::Dates).unwrap();
+        assert_eq!(segments.len(), 4);
+        assert_eq!((segments[0].date, segments[0].text.as_str()), (None, "My Journal 2021\n\nKept by A."));
+        assert_eq!(segments[1].date, NaiveDate::from_ymd_opt(2021, 3, 5));
+        assert_eq!(segments[1].title.as_deref(), Some("March 5th, 2021"));
+        assert_eq!(segments[1].text, "Woke early. On 3/1/2021 I said I would\nstart running.\n\nWent for a run.\nMore about the run.");
+        assert_eq!((segments[1].first_page, segments[1].last_page), (2, 3));
+        assert_eq!((segments[2].first_page, segments[2].date), (3, NaiveDate::from_ymd_opt(2021, 3, 6)));
+
+        let fallback = DateTime::parse_from_rfc3339("2022-01-01T00:00:00Z").unwrap().with_timezone(&Utc);
+        let entries = to_entries(segments, "journal.pdf", fallback, "UTC");
+        assert_eq!(entries[0].created, fallback);
+        assert_eq!(entries[0].title.as_deref(), Some("journal, page 1"));
+        assert_eq!(entries[1].created.to_rfc3339(), "2021-03-05T12:00:00+00:00");
+        assert_eq!(entries[3].source, "journal.pdf#pages=4-4");
+    }
+
+    #[test]
+    fn test_split_by_pages() {
+        assert_eq!(parse_ranges("1-2, 4, 3-", 4).unwrap(), [(1, 2), (4, 4), (3, 4)]);
+        assert!(parse_ranges("5-6", 4).is_err());
+        assert!(parse_ranges("2-1", 4).is_err());
+        assert!(parse_ranges("a-b", 4).is_err());
+
+        let segments = split(&pages(), &PdfSplit::Ranges { ranges: "2-3".to_string() }).unwrap();
+        assert_eq!(segments.len(), 1);
+        assert_eq!(segments[0].date, NaiveDate::from_ymd_opt(2021, 3, 5));
+        assert!(segments[0].text.starts_with("Woke early."));
+
+        let segments = split(&pages(), &PdfSplit::Every { pages: 3 }).unwrap();
+        assert_eq!(segments.iter().map(|s| (s.first_page, s.last_page)).collect::<Vec<_>>(), [(1, 3), (4, 4)]);
+        assert_eq!(segments[0].date, NaiveDate::from_ymd_opt(2021, 3, 5));
+        assert!(segments[0].text.starts_with("My Journal 2021\n\nKept by A.\n\nWoke early."));
+        assert_eq!(segments[1].date, NaiveDate::from_ymd_opt(2021, 3, 8));
+        assert!(split(&pages(), &PdfSplit::Every { pages: 0 }).is_err());
+    }
+}
@@ -0,0 +1,354 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use base64::Engine as _;
+use chrono::{FixedOffset, NaiveDate, NaiveDateTime};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::ai::{AiSettings, Provider};
+use crate::app_import::{local_to_utc, AppEntry, AppImportReport};
+use crate::import::FileType;
+
+// Stub entries from a photo library, for the days nothing was written.
+//
+// Every photo under the chosen folders is dated by its EXIF DateTimeOriginal (DateTime when that
+// is missing) and photos are grouped by the day they were taken: in the zone OffsetTimeOriginal
+// gives, else the one chosen for the import. Each day becomes an entry tagged "photos" that lists
+// its photos by time, dated at the first, and the photos are attached to it in `entry_photos` by
+// path (they are not copied, so moving the library breaks the link). Photos without an EXIF date
+// are skipped, and photos already attached to an entry are left out, so importing the folder again
+// only adds new days.
+//
+// With `captions`, the chat provider describes each JPEG, PNG or WebP photo in one sentence, up to
+// MAX_CAPTIONS_PER_DAY a day, with the vision model in `photo_caption_model` (empty: llava on
+// Ollama, the chat model on OpenAI). Captions go into the entry text so search finds them.
+// The first failed caption turns captions off for the rest of the import.
+
+const EXTENSIONS: &[&str] = &["jpg", "jpeg", "heic", "heif", "png", "webp", "tif", "tiff"];
+const CAPTION_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "webp"];
+const MAX_CAPTIONS_PER_DAY: usize = 8;
+// Larger files are not sent for captions
+const MAX_CAPTION_BYTES: u64 = 15 * 1024 * 1024;
+const DEFAULT_OLLAMA_CAPTION_MODEL: &str = "llava";
+const CAPTION_PROMPT: &str = "Describe this photo in one short sentence for a personal journal: who or what is in it, \
+where and what is happening. Don't mention the camera or photo quality.";
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PhotoOptions {
+    #[serde(default)]
+    pub captions: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EntryPhoto {
+    pub path: String,
+    pub taken_at: String,
+    pub caption: Option<String>,
+    pub position: u32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Photo {
+    path: PathBuf,
+    // Local time the photo was taken
+    local: NaiveDateTime,
+    // "+02:00" from EXIF, when the camera recorded it
+    offset: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+struct Day {
+    date: NaiveDate,
+    photos: Vec<Photo>,
+    captions: Vec<Option<String>>,
+}
+
+fn exif_ascii(exif: &exif::Exif, tag: exif::Tag) -> Option<String> {
+    match &exif.get_field(tag, exif::In::PRIMARY)?.value {
+        exif::Value::Ascii(values) => values.first().map(|v| String::from_utf8_lossy(v).trim().to_string()),
+        _ => None,
+    }
+}
+
+// EXIF's "2021:06:05 14:03:22", ignoring blank or zeroed-out values
+fn parse_exif_time(value: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(value.trim().trim_end_matches('\0'), "%Y:%m:%d %H:%M:%S").ok()
+}
+
+fn parse_offset(value: &str) -> Option<String> {
+    let value = value.trim();
+    value.parse::<FixedOffset>().ok().map(|_| value.to_string())
+}
+
+fn read_photo(path: &Path) -> Result<Option<Photo>> {
+    let file = File::open(path)?;
+    let exif = match exif::Reader::new().read_from_container(&mut BufReader::new(file)) {
+        Ok(exif) => exif,
+        Err(exif::Error::NotFound(_)) => return Ok(None),
+        Err(e) => return Err(e).context("Unreadable EXIF data"),
+    };
+    let original = exif_ascii(&exif, exif::Tag::DateTimeOriginal).and_then(|v| parse_exif_time(&v));
+    let (local, offset_tag) = match original {
+        Some(local) => (local, exif::Tag::OffsetTimeOriginal),
+        None => match exif_ascii(&exif, exif::Tag::DateTime).and_then(|v| parse_exif_time(&v)) {
+            Some(local) => (local, exif::Tag::OffsetTime),
+            None => return Ok(None),
+        },
+    };
+    let offset = exif_ascii(&exif, offset_tag).and_then(|v| parse_offset(&v));
+    Ok(Some(Photo { path: path.to_path_buf(), local, offset }))
+}
+
+fn group_by_day(mut photos: Vec<Photo>) -> Vec<Day> {
+    photos.sort_by(|a, b| a.local.cmp(&b.local).then_with(|| a.path.cmp(&b.path)));
+    let mut days: BTreeMap<NaiveDate, Vec<Photo>> = BTreeMap::new();
+    for photo in photos {
+        days.entry(photo.local.date()).or_default().push(photo);
+    }
+    days.into_iter()
+        .map(|(date, photos)| Day { date, captions: vec![None; photos.len()], photos })
+        .collect()
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_else(|| path.to_string_lossy().to_string())
+}
+
+fn stub_entry(day: &Day, timezone: &str) -> AppEntry {
+    let first = &day.photos[0];
+    let timezone = first.offset.clone().unwrap_or_else(|| timezone.to_string());
+    let lines: Vec<String> = day
+        .photos
+        .iter()
+        .zip(&day.captions)
+        .map(|(photo, caption)| match caption {
+            Some(caption) => format!("{} {}: {}", photo.local.format("%H:%M"), file_name(&photo.path), caption),
+            None => format!("{} {}", photo.local.format("%H:%M"), file_name(&photo.path)),
+        })
+        .collect();
+    let count = day.photos.len();
+    AppEntry {
+        source: format!("{}#{}", first.path.parent().map(|p| p.to_string_lossy().to_string()).unwrap_or_default(), day.date),
+        title: Some(if count == 1 { "1 photo".to_string() } else { format!("{} photos", count) }),
+        body: lines.join("\n"),
+        tags: vec!["photos".to_string()],
+        created: local_to_utc(first.local, &timezone),
+        timezone,
+        sentiment: None,
+    }
+}
+
+fn is_attached(conn: &Connection, path: &Path) -> Result<bool> {
+    let count: i64 = conn.query_row("SELECT COUNT(*) FROM entry_photos WHERE path = ?1", params![path.to_string_lossy()], |r| r.get(0))?;
+    Ok(count > 0)
+}
+
+fn attach(conn: &Connection, entry_id: &str, day: &Day, timezone: &str) -> Result<()> {
+    let tx = conn.unchecked_transaction()?;
+    for (position, (photo, caption)) in day.photos.iter().zip(&day.captions).enumerate() {
+        let taken_at = local_to_utc(photo.local, photo.offset.as_deref().unwrap_or(timezone));
+        tx.execute(
+            "INSERT INTO entry_photos (entry_id, path, taken_at, caption, position) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![entry_id, photo.path.to_string_lossy(), taken_at.to_rfc3339(), caption, position as i64],
+        )?;
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+pub fn entry_photos(conn: &Connection, entry_id: &str) -> Result<Vec<EntryPhoto>> {
+    let mut stmt = conn.prepare("SELECT path, taken_at, caption, position FROM entry_photos WHERE entry_id = ?1 ORDER BY position")?;
+    let rows = stmt.query_map(params![entry_id], |r| {
+        Ok(EntryPhoto { path: r.get(0)?, taken_at: r.get(1)?, caption: r.get(2)?, position: r.get(3)? })
+    })?;
+    Ok(rows.collect::<rusqlite::Result<_>>()?)
+}
+
+fn mime_type(path: &Path) -> &'static str {
+    match path.extension().and_then(|e| e.to_str()).map(|e| e.to_lowercase()).as_deref() {
+        Some("png") => "image/png",
+        Some("webp") => "image/webp",
+        _ => "image/jpeg",
+    }
+}
+
+async fn caption(settings: &AiSettings, model: &str, path: &Path) -> Result<String> {
+    let image = base64::engine::general_purpose::STANDARD.encode(std::fs::read(path)?);
+    let client = reqwest::Client::new();
+    let text = match settings.provider {
+        Provider::OpenAI => {
+            let api_key = settings.openai_api_key.as_deref().context("OpenAI API key is not set")?;
+            let request = client
+                .post("https://api.openai.com/v1/chat/completions")
+                .header("Authorization", format!("Bearer {}", api_key))
+                .json(&serde_json::json!({
+                    "model": settings.chat_model(&Provider::OpenAI, model),
+                    "messages": [{
+                        "role": "user",
+                        "content": [
+                            { "type": "text", "text": CAPTION_PROMPT },
+                            { "type": "image_url", "image_url": { "url": format!("data:{};base64,{}", mime_type(path), image), "detail": "low" } }
+                        ]
+                    }],
+                    "temperature": 0.3,
+                    "max_tokens": 80
+                }));
+            let response = crate::ai_http::send(&Provider::OpenAI, request).await?;
+            if !response.status().is_success() {
+                anyhow::bail!("OpenAI API error: {}", response.text().await.unwrap_or_default());
+            }
+            let json: serde_json::Value = response.json().await?;
+            json["choices"][0]["message"]["content"].as_str().unwrap_or_default().to_string()
+        }
+        _ => {
+            let model = if model.trim().is_empty() { DEFAULT_OLLAMA_CAPTION_MODEL } else { model.trim() };
+            let request = client.post(format!("{}/api/generate", settings.ollama_url)).json(&serde_json::json!({
+                "model": model,
+                "prompt": CAPTION_PROMPT,
+                "images": [image],
+                "stream": false,
+                "options": settings.ollama_options(0.3, Some(80))
+            }));
+            let response = crate::ai_http::send(&Provider::Ollama, request).await?;
+            if !response.status().is_success() {
+                anyhow::bail!("Ollama error ({}): {}", model, response.text().await.unwrap_or_default());
+            }
+            let json: serde_json::Value = response.json().await?;
+            json["response"].as_str().unwrap_or_default().to_string()
+        }
+    };
+    // One line, so it fits the entry's list of photos
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.is_empty() {
+        anyhow::bail!("The model returned no caption");
+    }
+    Ok(text)
+}
+
+async fn caption_day(settings: &AiSettings, model: &str, day: &mut Day) -> Result<()> {
+    let mut captioned = 0;
+    for (photo, slot) in day.photos.iter().zip(day.captions.iter_mut()) {
+        if captioned >= MAX_CAPTIONS_PER_DAY {
+            break;
+        }
+        let extension = photo.path.extension().and_then(|e| e.to_str()).unwrap_or_default().to_lowercase();
+        let size = std::fs::metadata(&photo.path).map(|m| m.len()).unwrap_or(u64::MAX);
+        if !CAPTION_EXTENSIONS.contains(&extension.as_str()) || size > MAX_CAPTION_BYTES {
+            continue;
+        }
+        *slot = Some(caption(settings, model, &photo.path).await.with_context(|| format!("Caption for {}", photo.path.display()))?);
+        captioned += 1;
+    }
+    Ok(())
+}
+
+pub async fn import_folders(app_handle: &AppHandle, paths: &[String], timezone: &str, options: &PhotoOptions) -> Result<AppImportReport> {
+    let mut report = AppImportReport::default();
+    let (settings, caption_model) = {
+        let conn = crate::database::open_conn(app_handle)?;
+        (AiSettings::load(&conn), crate::database::read_setting(&conn, "photo_caption_model").unwrap_or_default())
+    };
+    let mut photos = Vec::new();
+    {
+        let conn = crate::database::open_conn(app_handle)?;
+        for path in crate::app_import::files_with_extension(paths, EXTENSIONS) {
+            if is_attached(&conn, &path)? {
+                continue;
+            }
+            match read_photo(&path) {
+                Ok(Some(photo)) => photos.push(photo),
+                Ok(None) => report.skipped += 1,
+                Err(e) => report.error(format!("{}: {}", path.display(), e)),
+            }
+        }
+    }
+    let mut captions = options.captions;
+    for mut day in group_by_day(photos) {
+        if captions {
+            if let Err(e) = caption_day(&settings, &caption_model, &mut day).await {
+                tracing::warn!("photo captions turned off for this import: {:#}", e);
+                report.errors.get_or_insert_with(Vec::new).push(format!("Captions stopped: {:#}", e));
+                captions = false;
+            }
+        }
+        if let Some(id) = crate::app_import::save_one(app_handle, stub_entry(&day, timezone), FileType::Photos, &mut report).await? {
+            let conn = crate::database::open_conn(app_handle)?;
+            attach(&conn, &id, &day, timezone)?;
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn photo(path: &str, local: &str, offset: Option<&str>) -> Photo {
+        Photo {
+            path: PathBuf::from(path),
+            local: parse_exif_time(local).unwrap(),
+            offset: offset.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_exif_values() {
+        assert_eq!(parse_exif_time("2021:06:05 14:03:22").unwrap().to_string(), "2021-06-05 14:03:22");
+        assert_eq!(parse_exif_time("2021:06:05 14:03:22\0").unwrap().to_string(), "2021-06-05 14:03:22");
+        assert!(parse_exif_time("0000:00:00 00:00:00").is_none());
+        assert!(parse_exif_time("    :  :     :  :  ").is_none());
+        assert_eq!(parse_offset(" +02:00").as_deref(), Some("+02:00"));
+        assert!(parse_offset("   :  ").is_none());
+    }
+
+    #[test]
+    fn test_days_and_stub_entries() {
+        let days = group_by_day(vec![
+            photo("/p/IMG_0003.jpg", "2019:08:02 09:00:00", None),
+            photo("/p/IMG_0002.heic", "2019:08:01 18:30:00", Some("+02:00")),
+            photo("/p/IMG_0001.jpg", "2019:08:01 08:15:00", Some("+02:00")),
+        ]);
+        assert_eq!(days.len(), 2);
+        assert_eq!(days[0].photos.len(), 2);
+
+        let mut first = days[0].clone();
+        first.captions[0] = Some("A kayak on a calm lake.".to_string());
+        let entry = stub_entry(&first, "America/New_York");
+        assert_eq!(entry.title.as_deref(), Some("2 photos"));
+        assert_eq!(entry.body, "08:15 IMG_0001.jpg: A kayak on a calm lake.\n18:30 IMG_0002.heic");
+        assert_eq!(entry.tags, ["photos"]);
+        // The camera's own offset wins over the import's zone
+        assert_eq!(entry.timezone, "+02:00");
+        assert_eq!(entry.created.to_rfc3339(), "2019-08-01T06:15:00+00:00");
+        assert_eq!(entry.source, "/p#2019-08-01");
+
+        let second = stub_entry(&days[1], "America/New_York");
+        assert_eq!(second.title.as_deref(), Some("1 photo"));
+        assert_eq!(second.timezone, "America/New_York");
+        assert_eq!(second.created.to_rfc3339(), "2019-08-02T13:00:00+00:00");
+    }
+
+    #[test]
+    fn test_attached_photos() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::migrations::run_migrations(&mut conn).unwrap();
+        conn.execute_batch(
+            r#"INSERT INTO entries (id, body, entry_date, entry_timezone, source_path, source_type, text_hash, created_at, updated_at)
+               VALUES ('a', 'x', '2019-08-01T06:15:00+00:00', 'UTC', '', 'photos', 'h', '', '');"#,
+        )
+        .unwrap();
+        let day = group_by_day(vec![photo("/p/IMG_0001.jpg", "2019:08:01 08:15:00", None), photo("/p/IMG_0002.jpg", "2019:08:01 09:00:00", None)])
+            .remove(0);
+        attach(&conn, "a", &day, "+02:00").unwrap();
+        assert!(is_attached(&conn, Path::new("/p/IMG_0002.jpg")).unwrap());
+        assert!(!is_attached(&conn, Path::new("/p/IMG_0003.jpg")).unwrap());
+        let photos = entry_photos(&conn, "a").unwrap();
+        assert_eq!(photos.iter().map(|p| p.position).collect::<Vec<_>>(), [0, 1]);
+        assert_eq!(photos[0].taken_at, "2019-08-01T06:15:00+00:00");
+    }
+}
@@ -0,0 +1,140 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection};
+
+// Previews shown in month listings.
+//
+// A preview is the start of the body on one line, skipping opening lines that only repeat the
+// title or the date ("# Walk to the lake", "Tuesday, March 5th 2021"). Longer bodies are cut
+// after the last sentence that fits, or failing that after the last whole word with an
+// ellipsis; lengths are counted in characters, so accented and CJK text is never split inside
+// a character. Previews are cached in `entries.preview` with the text_hash they were made
+// from, filled in the first time a month is listed after an import or edit; sealing an entry
+// clears its preview with everything else derived from the plaintext.
+
+pub const PREVIEW_CHARS: usize = 200;
+// A sentence cut is only used if it keeps at least this share of the preview
+const MIN_SENTENCE_SHARE: usize = 2;
+
+#[derive(Debug, Clone)]
+pub struct PreviewRow {
+    pub id: String,
+    pub title: Option<String>,
+    pub entry_date: String,
+    pub preview: String,
+    pub sensitive: bool,
+}
+
+// Lowercase letters and digits only, so "# Walk!" matches a "Walk" title
+fn comparable(text: &str) -> String {
+    text.chars().filter(|c| c.is_alphanumeric()).flat_map(char::to_lowercase).collect()
+}
+
+fn is_sentence_end(text: &str, end: usize) -> bool {
+    let Some(c) = text[..end].chars().next_back() else { return false };
+    match c {
+        '。' | '！' | '？' => true,
+        '.' | '!' | '?' => text[end..].chars().next().is_none_or(char::is_whitespace),
+        _ => false,
+    }
+}
+
+pub fn generate(title: Option<&str>, body: &str, max_chars: usize) -> String {
+    let title = title.map(comparable).filter(|t| !t.is_empty());
+    let mut lines = body.lines().map(str::trim).filter(|l| !l.is_empty()).peekable();
+    // Only the opening lines can be a repeated heading
+    for _ in 0..3 {
+        match lines.peek() {
+            Some(line) if title.as_deref() == Some(comparable(line).as_str()) || crate::import::date_heading(line).is_some() => {
+                lines.next();
+            }
+            _ => break,
+        }
+    }
+    let text = lines.flat_map(str::split_whitespace).collect::<Vec<_>>().join(" ");
+    let Some((limit, _)) = text.char_indices().nth(max_chars) else { return text };
+    let head = &text[..limit];
+
+    let sentence = head.char_indices().map(|(i, c)| i + c.len_utf8()).rfind(|&end| is_sentence_end(&text, end));
+    if let Some(end) = sentence.filter(|&end| head[..end].chars().count() >= max_chars / MIN_SENTENCE_SHARE) {
+        return head[..end].to_string();
+    }
+    // Cut before the word the limit falls in, unless the text has no spaces to cut at
+    let cut = if text[limit..].starts_with(' ') { limit } else { head.rfind(' ').unwrap_or(limit) };
+    format!("{}…", head[..cut].trim_end_matches([' ', ',', ';', ':', '-', '—']))
+}
+
+// A month's entries in date order with their previews, making any that are missing or stale
+pub(crate) fn for_month(conn: &Connection, year: i32, month: u32) -> Result<Vec<PreviewRow>> {
+    let mut stmt = conn.prepare_cached(
+        r#"SELECT id, title, entry_date, sensitive, CASE WHEN preview_hash IS text_hash THEN preview END
+            FROM entries WHERE entry_year = ?1 AND entry_month = ?2 ORDER BY entry_epoch ASC"#,
+    )?;
+    let rows = stmt.query_map(params![year, month], |r| {
+        Ok((r.get::<_, String>(0)?, r.get::<_, Option<String>>(1)?, r.get::<_, String>(2)?, r.get::<_, bool>(3)?, r.get::<_, Option<String>>(4)?))
+    })?;
+    let mut previews = Vec::new();
+    let mut stale = Vec::new();
+    for row in rows {
+        let (id, title, entry_date, sensitive, preview) = row?;
+        if preview.is_none() {
+            stale.push(previews.len());
+        }
+        // In the same form as `Entry::entry_date`
+        let entry_date = DateTime::parse_from_rfc3339(&entry_date).map(|d| d.with_timezone(&Utc).to_rfc3339()).unwrap_or(entry_date);
+        previews.push(PreviewRow { id, title, entry_date, preview: preview.unwrap_or_default(), sensitive });
+    }
+    if !stale.is_empty() {
+        let tx = conn.unchecked_transaction()?;
+        {
+            let mut body = tx.prepare_cached("SELECT body FROM entries WHERE id = ?1")?;
+            let mut save = tx.prepare_cached("UPDATE entries SET preview = ?2, preview_hash = text_hash WHERE id = ?1")?;
+            for i in stale {
+                let row = &mut previews[i];
+                let text: String = body.query_row(params![row.id], |r| r.get(0))?;
+                row.preview = generate(row.title.as_deref(), &text, PREVIEW_CHARS);
+                save.execute(params![row.id, row.preview])?;
+            }
+        }
+        tx.commit()?;
+    }
+    Ok(previews)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate() {
+        let body = "# Walk\n\nTuesday, March 5th 2021\n\nWent to the lake. The ice was finally gone and the geese were back.";
+        assert_eq!(generate(Some("Walk"), body, 200), "Went to the lake. The ice was finally gone and the geese were back.");
+        assert_eq!(generate(Some("Walk"), body, 40), "Went to the lake. The ice was finally…");
+        assert_eq!(generate(None, "Went to the lake. The ice was finally gone.", 30), "Went to the lake.");
+        // A title that isn't repeated stays, and multi-byte text is cut on characters
+        assert_eq!(generate(Some("Other"), "Walk\nCafé crème über alles, très bien", 14), "Walk Café…");
+        assert_eq!(generate(None, "今日は晴れ。公園に行った。とても楽しかった", 13), "今日は晴れ。公園に行った。");
+        assert_eq!(generate(None, "ééééééééé", 4), "éééé…");
+        // "e.g." doesn't end a sentence mid-word, and short bodies are untouched
+        assert_eq!(generate(None, "Fruit, e.g.apples and pears from the market", 20), "Fruit, e.g.apples…");
+        assert_eq!(generate(None, "  Short\n entry. ", 200), "Short entry.");
+    }
+
+    #[test]
+    fn test_cached_previews() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::migrations::run_migrations(&mut conn).unwrap();
+        conn.execute_batch(
+            r#"INSERT INTO entries (id, title, body, entry_date, entry_timezone, source_path, source_type, text_hash, created_at, updated_at)
+               VALUES ('a', 'Walk', 'Walk' || char(10) || 'To the lake.', '2021-03-05T12:00:00+00:00', 'UTC', '', 'txt', 'h1', '', '');"#,
+        )
+        .unwrap();
+        assert_eq!(for_month(&conn, 2021, 3).unwrap()[0].preview, "To the lake.");
+        let cached: Option<String> = conn.query_row("SELECT preview FROM entries WHERE id = 'a'", [], |r| r.get(0)).unwrap();
+        assert_eq!(cached.as_deref(), Some("To the lake."));
+
+        // Stale once the text changes
+        conn.execute("UPDATE entries SET body = 'Home again.', text_hash = 'h2' WHERE id = 'a'", []).unwrap();
+        assert_eq!(for_month(&conn, 2021, 3).unwrap()[0].preview, "Home again.");
+    }
+}
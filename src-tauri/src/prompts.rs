@@ -0,0 +1,153 @@
+use anyhow::Result;
+use chrono::Duration;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::ai::{Citation, ContextEntry};
+
+// Personalized journaling prompts.
+//
+// The model sees the latest few entries plus older ones where the writer started, planned or
+// worried about something (found through the RAG retrieval path with a query about plans and
+// open questions) and suggests prompts that follow up on them. Each prompt keeps the entries
+// it refers to as citations, checked against the context like RAG answers.
+
+const RECENT_ENTRIES: u32 = 5;
+const THREAD_ENTRIES: u32 = 8;
+// Threads are looked for between these many days before the latest entry
+const THREAD_WINDOW_DAYS: (i64, i64) = (180, 7);
+const DEFAULT_PROMPTS: u32 = 5;
+const MAX_PROMPTS: u32 = 10;
+
+const THREAD_QUERY: &str = "I started learning, I want to, I'm planning to, I decided to, I hope to, I'm worried about, I need to figure out, I'm thinking about trying";
+
+const PROMPT_TEMPLATE: &str = r#"You suggest journaling prompts to someone based on their own journal. Write {count} prompts, each on its own line starting with "- ".
+
+Good prompts follow up on something specific they wrote: a plan, a new habit, a decision, a worry, a relationship or an open question ("You wrote about starting piano in March - how is it going?"). Prefer threads they have not returned to since. Address them as "you", keep each prompt to one or two sentences, and cite the entry it builds on as [Entry N]. Only cite entries listed below.
+
+Entries:
+{context}"#;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WritingPrompt {
+    pub prompt: String,
+    pub sources: Vec<Citation>,
+}
+
+fn context_from_entry(entry: crate::database::Entry) -> ContextEntry {
+    let snippet = entry.body.split_whitespace().take(80).collect::<Vec<_>>().join(" ");
+    ContextEntry {
+        entry_id: entry.id,
+        title: entry.title,
+        body: entry.body,
+        entry_date: entry.entry_date,
+        tags: vec![],
+        relevance_score: 1.0,
+        snippet,
+    }
+}
+
+// One prompt per "- " (or numbered) line, with its citations resolved and markers removed
+fn parse_prompts(response: &str, context: &[ContextEntry], limit: usize) -> Vec<WritingPrompt> {
+    static MARKER: std::sync::OnceLock<regex::Regex> = std::sync::OnceLock::new();
+    let marker = MARKER.get_or_init(|| regex::Regex::new(r"\s?(\[\d+\])+").expect("valid marker pattern"));
+
+    let mut prompts = Vec::new();
+    for line in response.lines() {
+        let line = line.trim();
+        let text = if let Some(rest) = line.strip_prefix(['-', '*', '•']) {
+            rest
+        } else {
+            let digits = line.trim_start_matches(|c: char| c.is_ascii_digit());
+            match digits.strip_prefix(['.', ')']) {
+                Some(rest) if digits.len() < line.len() => rest,
+                _ => continue,
+            }
+        };
+        let (text, sources) = crate::ai::verify_citations(text.trim(), context);
+        let text = marker.replace_all(&text, "").trim().trim_matches('"').to_string();
+        if text.is_empty() {
+            continue;
+        }
+        prompts.push(WritingPrompt { prompt: text, sources });
+        if prompts.len() == limit {
+            break;
+        }
+    }
+    prompts
+}
+
+pub async fn generate_writing_prompts(app_handle: &AppHandle, count: Option<u32>) -> Result<Vec<WritingPrompt>> {
+    let count = count.unwrap_or(DEFAULT_PROMPTS).clamp(1, MAX_PROMPTS);
+    let recent = crate::database::list_entries(app_handle, Some(RECENT_ENTRIES), None).await?;
+    let Some(latest) = recent.first().map(|e| e.entry_date) else {
+        return Err(anyhow::anyhow!("Write or import a few entries first"));
+    };
+
+    // Relative to the latest entry, so an archive that ended years ago still has "recent" threads
+    let window = Some((latest - Duration::days(THREAD_WINDOW_DAYS.0), latest - Duration::days(THREAD_WINDOW_DAYS.1)));
+    let threads = crate::ai::retrieve_relevant_context(app_handle, THREAD_QUERY, THREAD_ENTRIES, window, None)
+        .await
+        .unwrap_or_else(|e| {
+            tracing::warn!("thread retrieval failed: {}", e);
+            Vec::new()
+        });
+
+    let mut context: Vec<ContextEntry> = recent.into_iter().map(context_from_entry).collect();
+    for entry in threads {
+        if !context.iter().any(|c| c.entry_id == entry.entry_id) {
+            context.push(entry);
+        }
+    }
+    context.sort_by_key(|c| c.entry_date);
+
+    let prompt = PROMPT_TEMPLATE
+        .replace("{count}", &count.to_string())
+        .replace("{context}", &crate::ai::build_context_string(&context));
+    let settings = crate::ai::AiSettings::from_app(app_handle)?;
+    let request = crate::ai::ChatRequest {
+        messages: vec![crate::ai::ChatMessage { role: "user".to_string(), content: prompt }],
+        model: String::new(),
+        provider: None,
+    };
+    let ids = context.iter().map(|c| c.entry_id.clone()).collect();
+    let response = crate::ai_audit::with_context("writing prompts", ids, crate::ai::chat_completion(&settings, request)).await?;
+    let prompts = parse_prompts(&response, &context, count as usize);
+    if prompts.is_empty() {
+        return Err(anyhow::anyhow!("The model did not return any prompts"));
+    }
+    Ok(prompts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_prompts() {
+        let context: Vec<ContextEntry> = ["piano", "job"]
+            .iter()
+            .map(|id| ContextEntry {
+                entry_id: id.to_string(),
+                title: None,
+                body: String::new(),
+                entry_date: chrono::Utc::now(),
+                tags: vec![],
+                relevance_score: 1.0,
+                snippet: String::new(),
+            })
+            .collect();
+        let response = "Here are some prompts:\n\
+            - You wrote about starting piano in March [Entry 1] - how is it going?\n\
+            2. How do you feel about the new job now? [Entry 2] [Entry 7]\n\
+            - What surprised you this week?\n";
+        let prompts = parse_prompts(response, &context, 5);
+        assert_eq!(prompts.len(), 3);
+        assert_eq!(prompts[0].prompt, "You wrote about starting piano in March - how is it going?");
+        assert_eq!(prompts[0].sources[0].entry_id, "piano");
+        assert_eq!(prompts[1].prompt, "How do you feel about the new job now?");
+        assert_eq!(prompts[1].sources.len(), 1);
+        assert!(prompts[2].sources.is_empty());
+        assert_eq!(parse_prompts(response, &context, 1).len(), 1);
+    }
+}
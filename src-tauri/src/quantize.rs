@@ -0,0 +1,169 @@
+use anyhow::Result;
+use rusqlite::{params, Connection};
+
+use crate::embeddings::{decode_embedding, encode_embedding};
+
+// Compact storage for chunk vectors. A 768-dim f32 vector is 3 KB, so 50k chunks run to
+// hundreds of MB. int8 keeps one scale per vector and a byte per dimension (about 4x smaller,
+// near-identical ranking); binary keeps only the sign of each dimension (32x smaller, a rough
+// first pass that the vector index rescores). Each chunk row records its own format, so the
+// setting only affects vectors stored after it changes until maintenance compacts the rest.
+// Whole-entry vectors (similar entries, clustering) and the AI cache stay full precision.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Quantization {
+    None,
+    Int8,
+    Binary,
+}
+
+impl Quantization {
+    pub fn parse(value: &str) -> Self {
+        match value.trim().to_lowercase().as_str() {
+            "int8" => Quantization::Int8,
+            "binary" => Quantization::Binary,
+            _ => Quantization::None,
+        }
+    }
+
+    // Also the vector_format stored with each chunk
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Quantization::None => "f32",
+            Quantization::Int8 => "int8",
+            Quantization::Binary => "binary",
+        }
+    }
+
+    pub fn load(conn: &Connection) -> Self {
+        crate::database::read_setting(conn, "embedding_quantization").map_or(Quantization::None, |v| Self::parse(&v))
+    }
+
+    // Bits kept per dimension, lower is smaller
+    fn precision(self) -> u32 {
+        match self {
+            Quantization::None => 32,
+            Quantization::Int8 => 8,
+            Quantization::Binary => 1,
+        }
+    }
+}
+
+// Scale mapping the largest magnitude in `v` to 127
+pub(crate) fn int8_scale(v: &[f32]) -> f32 {
+    let max = v.iter().fold(0f32, |m, x| m.max(x.abs()));
+    if max > 0.0 && max.is_finite() { max / 127.0 } else { 1.0 }
+}
+
+pub(crate) fn to_int8(v: &[f32], scale: f32) -> impl Iterator<Item = i8> + '_ {
+    v.iter().map(move |x| (x / scale).round().clamp(-127.0, 127.0) as i8)
+}
+
+// Sign bits, least significant bit first
+pub(crate) fn to_bits(v: &[f32]) -> Vec<u8> {
+    let mut bits = vec![0u8; v.len().div_ceil(8)];
+    for (i, x) in v.iter().enumerate() {
+        if *x > 0.0 {
+            bits[i / 8] |= 1 << (i % 8);
+        }
+    }
+    bits
+}
+
+// int8: f32 scale then one byte per dimension; binary: u32 dimension count then the sign bits
+pub fn encode(v: &[f32], q: Quantization) -> Vec<u8> {
+    match q {
+        Quantization::None => encode_embedding(v),
+        Quantization::Int8 => {
+            let scale = int8_scale(v);
+            let mut out = scale.to_le_bytes().to_vec();
+            out.extend(to_int8(v, scale).map(|b| b as u8));
+            out
+        }
+        Quantization::Binary => {
+            let mut out = (v.len() as u32).to_le_bytes().to_vec();
+            out.extend(to_bits(v));
+            out
+        }
+    }
+}
+
+// The stored vector as f32; binary vectors come back as ±1/√dim, which is unit length
+pub fn decode(bytes: &[u8], format: &str) -> Option<Vec<f32>> {
+    match Quantization::parse(format) {
+        Quantization::None => decode_embedding(bytes),
+        Quantization::Int8 => {
+            let scale = f32::from_le_bytes(bytes.get(..4)?.try_into().ok()?);
+            Some(bytes[4..].iter().map(|b| *b as i8 as f32 * scale).collect())
+        }
+        Quantization::Binary => {
+            let dim = u32::from_le_bytes(bytes.get(..4)?.try_into().ok()?) as usize;
+            let bits = &bytes[4..];
+            if bits.len() != dim.div_ceil(8) {
+                return None;
+            }
+            let unit = 1.0 / (dim.max(1) as f32).sqrt();
+            Some((0..dim).map(|i| if bits[i / 8] >> (i % 8) & 1 == 1 { unit } else { -unit }).collect())
+        }
+    }
+}
+
+// Re-encodes stored chunk vectors kept at a higher precision than `target`. Vectors already
+// smaller are left alone: re-expanding them wouldn't bring the lost precision back.
+pub fn compact_chunks(conn: &Connection, target: Quantization) -> Result<u64> {
+    let formats: Vec<&str> = [Quantization::None, Quantization::Int8, Quantization::Binary]
+        .into_iter()
+        .filter(|q| q.precision() > target.precision())
+        .map(Quantization::as_str)
+        .collect();
+    if formats.is_empty() {
+        return Ok(0);
+    }
+    let tx = conn.unchecked_transaction()?;
+    let mut compacted = 0;
+    {
+        let mut select = tx.prepare(&format!(
+            "SELECT id, embedding, vector_format FROM entry_chunks WHERE embedding IS NOT NULL AND vector_format IN ({})",
+            formats.iter().map(|f| format!("'{}'", f)).collect::<Vec<_>>().join(", ")
+        ))?;
+        let mut update = tx.prepare("UPDATE entry_chunks SET embedding = ?1, vector_format = ?2 WHERE id = ?3")?;
+        let rows = select
+            .query_map([], |r| Ok((r.get::<_, i64>(0)?, r.get::<_, Vec<u8>>(1)?, r.get::<_, String>(2)?)))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        for (id, bytes, format) in rows {
+            if let Some(v) = decode(&bytes, &format) {
+                update.execute(params![encode(&v, target), target.as_str(), id])?;
+                compacted += 1;
+            }
+        }
+    }
+    tx.commit()?;
+    if compacted > 0 {
+        crate::vector_index::invalidate();
+    }
+    Ok(compacted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip_and_size() {
+        let v: Vec<f32> = (0..768).map(|i| ((i * 37) % 101) as f32 / 50.0 - 1.0).collect();
+        let f32_len = encode(&v, Quantization::None).len();
+
+        let int8 = encode(&v, Quantization::Int8);
+        assert_eq!(int8.len(), 4 + 768);
+        let back = decode(&int8, "int8").unwrap();
+        assert!(v.iter().zip(&back).all(|(a, b)| (a - b).abs() <= int8_scale(&v)));
+
+        let binary = encode(&v, Quantization::Binary);
+        assert_eq!(binary.len(), 4 + 96);
+        assert!(f32_len / binary.len() >= 30);
+        let back = decode(&binary, "binary").unwrap();
+        assert_eq!(back.len(), 768);
+        assert!(v.iter().zip(&back).all(|(a, b)| (*a > 0.0) == (*b > 0.0)));
+        assert!(decode(&binary[..10], "binary").is_none());
+    }
+}
@@ -0,0 +1,258 @@
+use anyhow::Result;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::ai::{AiSettings, ChatMessage, ChatRequest, Provider};
+
+// Retrieval planning for chat. Before searching, the chat model turns the question into
+// filters: "how was my first month at the new job?" becomes the 30 days after the first entry
+// about starting the job, rather than a keyword search for "job". Tags and people are checked
+// against the journal and dropped when it has none of that name. With the Local provider, or
+// when the model's reply is unusable, date phrases are parsed by rules (date_phrases.rs).
+
+// How many tags and people the model is offered to pick from
+const KNOWN_TERMS: u32 = 60;
+const DEFAULT_ANCHOR_DAYS: i64 = 30;
+
+const PLAN_PROMPT: &str = r#"Turn a question about someone's journal into search filters. Today is {today}. The journal runs from {first} to {last}.
+Known tags: {tags}
+Known people: {people}
+
+Reply with only JSON of the form {"date_from": null, "date_to": null, "anchor": null, "anchor_days": null, "tags": [], "people": [], "keywords": ""}
+- date_from, date_to: the period the question is about as YYYY-MM-DD, if it names one ("last summer", "in 2019").
+- anchor: when the period starts at an event whose date you don't know ("my first month at the new job"), a few words an entry about that event would contain ("started new job"), with anchor_days the length of the period in days. Leave the dates null then.
+- tags, people: only names from the lists above that the question is about.
+- keywords: the words worth searching for, without dates, names or filler ("" if none).
+
+Question: {question}"#;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RetrievalPlan {
+    // Inclusive
+    pub date_from: Option<NaiveDate>,
+    pub date_to: Option<NaiveDate>,
+    pub tags: Vec<String>,
+    pub people: Vec<String>,
+    // Searched for instead of the question's words; empty keeps the question's
+    pub keywords: String,
+    // The event whose first entry starts the period, when the dates came from one
+    pub anchor: Option<String>,
+    // "model" or "rules"
+    pub method: String,
+}
+
+impl RetrievalPlan {
+    pub fn has_filters(&self) -> bool {
+        self.date_from.is_some() || self.date_to.is_some() || !self.tags.is_empty() || !self.people.is_empty()
+    }
+
+    // Whole days, as SearchFilters::date_range expects
+    pub fn date_range(&self) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+        if self.date_from.is_none() && self.date_to.is_none() {
+            return None;
+        }
+        let from = self.date_from.unwrap_or(NaiveDate::MIN);
+        let to = self.date_to.unwrap_or(NaiveDate::MAX);
+        Some((from.and_hms_opt(0, 0, 0)?.and_utc(), to.and_hms_opt(23, 59, 59)?.and_utc()))
+    }
+}
+
+// What the journal has for the model to choose from
+struct Vocabulary {
+    tags: Vec<String>,
+    people: Vec<String>,
+    span: Option<(String, String)>,
+}
+
+fn vocabulary(conn: &Connection) -> Result<Vocabulary> {
+    let mut stmt = conn.prepare_cached("SELECT tag FROM entry_tags GROUP BY tag ORDER BY count(*) DESC, tag LIMIT ?1")?;
+    let tags = stmt
+        .query_map(params![KNOWN_TERMS], |r| r.get(0))?
+        .collect::<rusqlite::Result<Vec<String>>>()?;
+    let people = crate::entities::list(conn, Some(crate::entities::EntityKind::Person), KNOWN_TERMS)?
+        .into_iter()
+        .map(|e| e.name)
+        .collect();
+    let span: (Option<String>, Option<String>) =
+        conn.query_row("SELECT MIN(entry_date), MAX(entry_date) FROM entries", [], |r| Ok((r.get(0)?, r.get(1)?)))?;
+    let span = match span {
+        (Some(first), Some(last)) => Some((first.chars().take(10).collect(), last.chars().take(10).collect())),
+        _ => None,
+    };
+    Ok(Vocabulary { tags, people, span })
+}
+
+fn plan_prompt(question: &str, today: NaiveDate, vocabulary: &Vocabulary) -> String {
+    let list = |names: &[String]| if names.is_empty() { "(none)".to_string() } else { names.join(", ") };
+    let (first, last) = vocabulary.span.clone().unwrap_or_else(|| (today.to_string(), today.to_string()));
+    PLAN_PROMPT
+        .replace("{today}", &today.to_string())
+        .replace("{first}", &first)
+        .replace("{last}", &last)
+        .replace("{tags}", &list(&vocabulary.tags))
+        .replace("{people}", &list(&vocabulary.people))
+        .replace("{question}", question)
+}
+
+// Reads the model's JSON, tolerating text around it; the plan is not yet checked against the journal
+fn parse_reply(reply: &str) -> Option<(RetrievalPlan, Option<i64>)> {
+    let start = reply.find('{')?;
+    let end = reply.rfind('}')?;
+    let json: serde_json::Value = serde_json::from_str(reply.get(start..=end)?).ok()?;
+    let date = |key: &str| json[key].as_str().and_then(|s| NaiveDate::parse_from_str(s.trim(), "%Y-%m-%d").ok());
+    let names = |key: &str| -> Vec<String> {
+        json[key].as_array().into_iter().flatten().filter_map(|v| v.as_str()).map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect()
+    };
+    let text = |key: &str| json[key].as_str().map(str::trim).filter(|s| !s.is_empty()).map(str::to_string);
+    let plan = RetrievalPlan {
+        date_from: date("date_from"),
+        date_to: date("date_to"),
+        tags: names("tags"),
+        people: names("people"),
+        keywords: text("keywords").unwrap_or_default(),
+        anchor: text("anchor"),
+        method: "model".to_string(),
+    };
+    Some((plan, json["anchor_days"].as_i64()))
+}
+
+// Keeps the tags and people the journal has (under its own spelling) and dates an anchored
+// period from the first entry about the anchor; an anchor nothing matches is dropped
+fn resolve(conn: &Connection, mut plan: RetrievalPlan, anchor_days: Option<i64>, vocabulary: &Vocabulary) -> Result<RetrievalPlan> {
+    let known = |names: &[String], known: &[String]| -> Vec<String> {
+        known.iter().filter(|k| names.iter().any(|n| crate::entities::normalize(n) == crate::entities::normalize(k))).cloned().collect()
+    };
+    plan.tags = known(&plan.tags, &vocabulary.tags);
+    plan.people = known(&plan.people, &vocabulary.people);
+    if let (Some(from), Some(to)) = (plan.date_from, plan.date_to) {
+        if from > to {
+            plan.date_from = Some(to);
+            plan.date_to = Some(from);
+        }
+    }
+
+    if plan.date_from.is_none() && plan.date_to.is_none() {
+        if let Some(anchor) = &plan.anchor {
+            match crate::database::first_match_date(conn, anchor)? {
+                Some(first) => {
+                    let from = first.date_naive();
+                    let days = anchor_days.filter(|d| *d > 0).unwrap_or(DEFAULT_ANCHOR_DAYS).min(3660);
+                    plan.date_from = Some(from);
+                    plan.date_to = Some(from + Duration::days(days - 1));
+                }
+                None => plan.anchor = None,
+            }
+        }
+    } else {
+        plan.anchor = None;
+    }
+    Ok(plan)
+}
+
+// A date phrase in the question ("last summer"), found without a model
+fn rules_plan(conn: &Connection, question: &str, today: NaiveDate) -> Option<RetrievalPlan> {
+    let birthday = crate::database::read_setting(conn, "birthday").and_then(|v| crate::date_phrases::parse_birthday(&v));
+    let phrase = crate::date_phrases::extract(question, today, birthday)?;
+    Some(RetrievalPlan {
+        date_from: Some(phrase.from),
+        date_to: Some(phrase.to),
+        keywords: phrase.rest,
+        method: "rules".to_string(),
+        ..Default::default()
+    })
+}
+
+// Filters for the question, or None when planning is off or finds none
+pub async fn plan(app_handle: &AppHandle, settings: &AiSettings, provider: &Provider, model: &str, question: &str) -> Option<RetrievalPlan> {
+    let today = chrono::Local::now().date_naive();
+    let vocab = {
+        let conn = crate::database::open_conn(app_handle).ok()?;
+        if !crate::database::setting_enabled(&conn, "chat_query_planning", true) {
+            return None;
+        }
+        if *provider == Provider::Local {
+            return rules_plan(&conn, question, today);
+        }
+        vocabulary(&conn).ok()?
+    };
+
+    let request = ChatRequest {
+        messages: vec![ChatMessage { role: "user".to_string(), content: plan_prompt(question, today, &vocab) }],
+        model: model.to_string(),
+        provider: Some(provider.clone()),
+    };
+    let planned = match crate::ai_audit::with_context("chat query planning", vec![], crate::ai::chat_completion(settings, request)).await {
+        Ok(reply) => parse_reply(&reply),
+        Err(e) => {
+            tracing::warn!("planning failed, using rules: {}", e);
+            None
+        }
+    };
+    let conn = crate::database::open_conn(app_handle).ok()?;
+    let plan = match planned {
+        Some((plan, anchor_days)) => resolve(&conn, plan, anchor_days, &vocab).ok(),
+        None => None,
+    };
+    match plan {
+        Some(plan) if plan.has_filters() => Some(plan),
+        _ => rules_plan(&conn, question, today),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_reply() {
+        let (plan, days) = parse_reply(
+            "Sure:\n{\"date_from\": null, \"date_to\": \"not a date\", \"anchor\": \"started new job\", \"anchor_days\": 30, \"tags\": [\"work\", \"\"], \"people\": null, \"keywords\": \" \"}",
+        )
+        .unwrap();
+        assert_eq!(plan.date_from, None);
+        assert_eq!(plan.date_to, None);
+        assert_eq!(plan.anchor.as_deref(), Some("started new job"));
+        assert_eq!(days, Some(30));
+        assert_eq!(plan.tags, vec!["work"]);
+        assert!(plan.people.is_empty());
+        assert_eq!(plan.keywords, "");
+        assert!(parse_reply("I can't help with that").is_none());
+    }
+
+    #[test]
+    fn test_resolve_anchors_and_known_names() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::migrations::run_migrations(&mut conn).unwrap();
+        conn.execute_batch(
+            r#"
+            INSERT INTO entries (id, title, body, entry_date, entry_timezone, source_path, source_type, text_hash, created_at, updated_at)
+            VALUES ('a', NULL, 'Coffee with Sam', '2021-01-10T09:00:00+00:00', 'UTC', 'a', 'txt', 'h1', '', ''),
+                   ('c', NULL, 'Six months into the new job, I started mentoring', '2021-09-01T09:00:00+00:00', 'UTC', 'c', 'txt', 'h3', '', ''),
+                   ('b', NULL, 'First day, I started the new job today', '2021-03-01T09:00:00+00:00', 'UTC', 'b', 'txt', 'h2', '', '');
+            INSERT INTO entries_fts (title, body, entry_id) SELECT '', body, id FROM entries;
+            INSERT INTO entry_tags (entry_id, tag) VALUES ('b', 'Work');
+            "#,
+        )
+        .unwrap();
+        let vocab = vocabulary(&conn).unwrap();
+        let plan = RetrievalPlan {
+            anchor: Some("started new job".into()),
+            tags: vec!["work".into(), "travel".into()],
+            people: vec!["Sam".into()],
+            method: "model".into(),
+            ..Default::default()
+        };
+        let plan = resolve(&conn, plan, None, &vocab).unwrap();
+        assert_eq!(plan.date_from, NaiveDate::from_ymd_opt(2021, 3, 1));
+        assert_eq!(plan.date_to, NaiveDate::from_ymd_opt(2021, 3, 30));
+        assert_eq!(plan.tags, vec!["Work"]);
+        // No entities were extracted, so the journal knows no people yet
+        assert!(plan.people.is_empty());
+
+        let unmatched = RetrievalPlan { anchor: Some("moved to Lisbon".into()), ..Default::default() };
+        let unmatched = resolve(&conn, unmatched, Some(90), &vocab).unwrap();
+        assert!(unmatched.anchor.is_none() && !unmatched.has_filters());
+    }
+}
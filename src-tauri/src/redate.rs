@@ -0,0 +1,191 @@
+use std::collections::HashSet;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use crate::database::{entry_from_row, Entry, EntryFilter, ENTRY_COLUMNS};
+use crate::import::{date_from_title, date_heading};
+
+// Re-dating entries from what they say, for imports that gave every file one default date.
+//
+// Each selected entry is read for a date the way the importers look for one: in its title, in
+// its source file's name, on a date line at the top of the body, and last anywhere in the
+// body's first paragraph. Entries whose date differs from the one found are proposed for
+// review with where the date came from; approved ones are moved to noon of that day in the
+// entry's own time zone.
+// Nothing changes until `apply` is called.
+
+// The first paragraph is only searched this far for a written date
+const OPENING_CHARS: usize = 300;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct RedateSelection {
+    // These entries only; otherwise every entry matching `filter`
+    #[serde(default)]
+    pub entry_ids: Vec<String>,
+    #[serde(default)]
+    pub filter: EntryFilter,
+    // Only entries dated the day they were imported, the usual sign of a default date
+    #[serde(default)]
+    pub dated_on_import_day: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RedateProposal {
+    pub entry_id: String,
+    pub title: Option<String>,
+    pub current_date: String,
+    pub proposed_date: String,
+    // "title", "file name", "date line" or "text"
+    pub found_in: String,
+    // The text the date was read from
+    pub evidence: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RedateChange {
+    pub entry_id: String,
+    pub entry_date: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RedateReport {
+    pub updated: u32,
+}
+
+// The date the entry's text gives, where it was found and the text it was read from
+pub fn detect(title: Option<&str>, source_path: &str, body: &str) -> Option<(NaiveDate, &'static str, String)> {
+    if let Some(title) = title.map(str::trim).filter(|t| !t.is_empty()) {
+        if let Some(date) = date_from_title(title) {
+            return Some((date, "title", title.to_string()));
+        }
+    }
+    // "journal.pdf#pages=3-4" and other importers' suffixes are not part of the name
+    let file = source_path.split('#').next().unwrap_or_default();
+    if let Some(stem) = Path::new(file).file_stem().map(|s| s.to_string_lossy().to_string()) {
+        if let Some(date) = date_from_title(&stem) {
+            return Some((date, "file name", stem));
+        }
+    }
+    let paragraph = body.trim_start().split("\n\n").next().unwrap_or_default();
+    if let Some((line, date)) = paragraph.lines().take(3).find_map(|l| date_heading(l).map(|d| (l.trim(), d))) {
+        return Some((date, "date line", line.to_string()));
+    }
+    let paragraph: String = paragraph.chars().take(OPENING_CHARS).collect();
+    date_from_title(&paragraph).map(|date| (date, "text", crate::csv_export::lead(&paragraph)))
+}
+
+fn local_date(date: DateTime<Utc>, timezone: &str) -> NaiveDate {
+    match timezone.parse::<chrono_tz::Tz>() {
+        Ok(tz) => date.with_timezone(&tz).date_naive(),
+        Err(_) => match timezone.parse::<chrono::FixedOffset>() {
+            Ok(offset) => date.with_timezone(&offset).date_naive(),
+            Err(_) => date.date_naive(),
+        },
+    }
+}
+
+fn selected(conn: &Connection, selection: &RedateSelection) -> Result<Vec<Entry>> {
+    let mut entries = if selection.entry_ids.is_empty() {
+        crate::database::filtered_entries(conn, &selection.filter)?
+    } else {
+        let mut stmt = conn.prepare_cached(&format!("SELECT {} FROM entries WHERE id = ?1", ENTRY_COLUMNS))?;
+        let mut entries = Vec::new();
+        for id in selection.entry_ids.iter().collect::<HashSet<_>>() {
+            entries.extend(stmt.query_row(params![id], entry_from_row).optional()?);
+        }
+        entries.sort_by(|a, b| a.entry_date.cmp(&b.entry_date).then(a.id.cmp(&b.id)));
+        entries
+    };
+    if selection.dated_on_import_day {
+        entries.retain(|e| local_date(e.entry_date, &e.entry_timezone) == local_date(e.created_at, &e.entry_timezone));
+    }
+    Ok(entries)
+}
+
+// Proposed dates for the selected entries whose text gives a different day, oldest first.
+// Sensitive entries are judged by title and file name only.
+pub fn propose(conn: &Connection, selection: &RedateSelection) -> Result<Vec<RedateProposal>> {
+    let noon = NaiveTime::from_hms_opt(12, 0, 0).expect("valid time");
+    let mut proposals = Vec::new();
+    for entry in selected(conn, selection)? {
+        let Some((date, found_in, evidence)) = detect(entry.title.as_deref(), &entry.source_path, &entry.body) else { continue };
+        if date == local_date(entry.entry_date, &entry.entry_timezone) {
+            continue;
+        }
+        proposals.push(RedateProposal {
+            entry_id: entry.id,
+            title: entry.title,
+            current_date: entry.entry_date.to_rfc3339(),
+            proposed_date: crate::app_import::local_to_utc(date.and_time(noon), &entry.entry_timezone).to_rfc3339(),
+            found_in: found_in.to_string(),
+            evidence,
+        });
+    }
+    Ok(proposals)
+}
+
+// Moves each entry to its approved date in one transaction
+pub fn apply(conn: &Connection, changes: &[RedateChange]) -> Result<RedateReport> {
+    let now = Utc::now().to_rfc3339();
+    let tx = conn.unchecked_transaction()?;
+    let mut updated = 0;
+    {
+        let mut stmt = tx.prepare_cached("UPDATE entries SET entry_date = ?2, updated_at = ?3 WHERE id = ?1")?;
+        for change in changes {
+            let date = DateTime::parse_from_rfc3339(&change.entry_date)
+                .with_context(|| format!("Invalid date \"{}\" for entry {}", change.entry_date, change.entry_id))?
+                .with_timezone(&Utc);
+            updated += stmt.execute(params![change.entry_id, date.to_rfc3339(), now])? as u32;
+        }
+    }
+    tx.commit()?;
+    Ok(RedateReport { updated })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d);
+        let found = |t: Option<&str>, s: &str, b: &str| detect(t, s, b).map(|(d, from, _)| (d, from));
+        assert_eq!(found(Some("2021-03-05 Walk"), "/j/x.txt", ""), Some((date(2021, 3, 5).unwrap(), "title")));
+        assert_eq!(found(Some("Walk"), "/j/20210306.txt", ""), Some((date(2021, 3, 6).unwrap(), "file name")));
+        assert_eq!(found(None, "journal.pdf#pages=1-2", "\n# Sunday, March 7th 2021\nRain."), Some((date(2021, 3, 7).unwrap(), "date line")));
+        assert_eq!(found(None, "", "Dear diary, today is 8 March 2021 and it rained.\n\nOn 1/1/2020 I..."), Some((date(2021, 3, 8).unwrap(), "text")));
+        assert_eq!(found(Some("Walk"), "notes.txt", "Rain.\n\nOn 1/1/2020 we met."), None);
+    }
+
+    #[test]
+    fn test_propose_and_apply() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::migrations::run_migrations(&mut conn).unwrap();
+        conn.execute_batch(
+            r#"INSERT INTO entries (id, title, body, entry_date, entry_timezone, source_path, source_type, text_hash, created_at, updated_at)
+               VALUES ('a', '2021-03-05', 'Walk.', '2024-06-01T09:00:00+00:00', 'America/New_York', '', 'txt', 'ha', '2024-06-01T10:00:00+00:00', ''),
+                      ('b', NULL, 'March 6, 2021' || char(10) || 'Rain.', '2021-03-06T15:00:00+00:00', 'UTC', '', 'txt', 'hb', '2024-06-01T10:00:00+00:00', ''),
+                      ('c', '2021-03-07', 'Sun.', '2022-01-01T12:00:00+00:00', 'UTC', '', 'txt', 'hc', '2024-06-01T10:00:00+00:00', '');"#,
+        )
+        .unwrap();
+        // 'b' and 'c' weren't dated the day they were imported
+        let selection = RedateSelection { dated_on_import_day: true, ..Default::default() };
+        let proposals = propose(&conn, &selection).unwrap();
+        assert_eq!(proposals.len(), 1);
+        assert_eq!((proposals[0].entry_id.as_str(), proposals[0].found_in.as_str()), ("a", "title"));
+        // Noon in New York
+        assert_eq!(proposals[0].proposed_date, "2021-03-05T17:00:00+00:00");
+        assert_eq!(propose(&conn, &RedateSelection { entry_ids: vec!["c".into()], ..Default::default() }).unwrap().len(), 1);
+
+        let changes = [RedateChange { entry_id: "a".into(), entry_date: proposals[0].proposed_date.clone() }];
+        assert_eq!(apply(&conn, &changes).unwrap().updated, 1);
+        let month: (i64, i64) = conn.query_row("SELECT entry_year, entry_month FROM entries WHERE id = 'a'", [], |r| Ok((r.get(0)?, r.get(1)?))).unwrap();
+        assert_eq!(month, (2021, 3));
+        assert!(propose(&conn, &RedateSelection::default()).unwrap().iter().all(|p| p.entry_id != "a"));
+        assert!(apply(&conn, &[RedateChange { entry_id: "a".into(), entry_date: "soon".into() }]).is_err());
+    }
+}
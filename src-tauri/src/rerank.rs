@@ -0,0 +1,182 @@
+use tauri::AppHandle;
+
+use crate::ai::{AiSettings, ChatMessage, ChatRequest, ContextEntry};
+
+// Optional second pass over retrieved RAG candidates.
+//
+// Hybrid retrieval ranks by keyword rank and embedding distance, which puts entries that share
+// words with "when did things start getting better?" ahead of the ones that answer it. With the
+// `rerank` setting on, retrieval fetches `rerank_candidates` entries and a model that reads the
+// question and each passage together scores them: the chat model in one prompt ("llm"), or a local
+// cross-encoder ("local"). The best `max_entries` go on to the answer. Any failure keeps the
+// retrieval order.
+
+const DEFAULT_CANDIDATES: u32 = 20;
+const MAX_CANDIDATES: u32 = 50;
+// Passage length shown to the reranker; the LLM sees all candidates in one prompt
+const LLM_PASSAGE_TOKENS: usize = 150;
+const LOCAL_PASSAGE_TOKENS: usize = 400;
+
+const RERANK_TEMPLATE: &str = r#"Rate how useful each journal passage is for answering the question, from 0 (irrelevant) to 10 (directly answers it). Consider what the question is really asking, including time ("when did...", "how has ... changed"), not just shared words.
+
+Question: {question}
+
+{passages}
+Reply with one line per passage in the form "N: score" and nothing else."#;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RerankMode {
+    Off,
+    Llm,
+    Local,
+}
+
+impl RerankMode {
+    fn parse(value: &str) -> Self {
+        match value.trim() {
+            "llm" => RerankMode::Llm,
+            "local" => RerankMode::Local,
+            _ => RerankMode::Off,
+        }
+    }
+}
+
+// The rerank mode, and how many candidates to retrieve for it
+pub fn load_config(app_handle: &AppHandle) -> (RerankMode, u32) {
+    let Ok(conn) = crate::database::open_conn(app_handle) else { return (RerankMode::Off, 0) };
+    let mode = crate::database::read_setting(&conn, "rerank").map_or(RerankMode::Off, |v| RerankMode::parse(&v));
+    let candidates = crate::database::read_setting(&conn, "rerank_candidates")
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(DEFAULT_CANDIDATES)
+        .clamp(1, MAX_CANDIDATES);
+    (mode, candidates)
+}
+
+fn passage(entry: &ContextEntry, max_tokens: usize) -> String {
+    let text = entry.body.split_whitespace().collect::<Vec<_>>().join(" ");
+    crate::tokens::truncate_to_tokens(&text, max_tokens)
+}
+
+// "N: score" lines to one score per passage; None unless most passages got one
+fn parse_scores(response: &str, count: usize) -> Option<Vec<f32>> {
+    static LINE: std::sync::OnceLock<regex::Regex> = std::sync::OnceLock::new();
+    let line = LINE.get_or_init(|| {
+        regex::Regex::new(r"(?mi)^\W*(?:passage\s*)?(\d+)\W*?[:=\-]\s*(\d+(?:\.\d+)?)").expect("valid score pattern")
+    });
+    let mut scores = vec![None; count];
+    for caps in line.captures_iter(response) {
+        let (Ok(n), Ok(score)) = (caps[1].parse::<usize>(), caps[2].parse::<f32>()) else { continue };
+        if let Some(slot) = n.checked_sub(1).and_then(|i| scores.get_mut(i)) {
+            *slot = Some(score.clamp(0.0, 10.0) / 10.0);
+        }
+    }
+    let scored = scores.iter().filter(|s| s.is_some()).count();
+    (scored * 2 > count).then(|| scores.into_iter().map(|s| s.unwrap_or(0.0)).collect())
+}
+
+async fn score_llm(app_handle: &AppHandle, question: &str, candidates: &[ContextEntry]) -> anyhow::Result<Vec<f32>> {
+    let passages: String = candidates
+        .iter()
+        .enumerate()
+        .map(|(i, e)| format!("[{}] ({}) {}\n\n", i + 1, e.entry_date.format("%Y-%m-%d"), passage(e, LLM_PASSAGE_TOKENS)))
+        .collect();
+    let prompt = RERANK_TEMPLATE.replace("{question}", question).replace("{passages}", &passages);
+    let settings = AiSettings::from_app(app_handle)?;
+    let request = ChatRequest {
+        messages: vec![ChatMessage { role: "user".to_string(), content: prompt }],
+        model: String::new(),
+        provider: None,
+    };
+    let ids = candidates.iter().map(|e| e.entry_id.clone()).collect();
+    let response = crate::ai_audit::with_context("rerank", ids, crate::ai::chat_completion(&settings, request)).await?;
+    parse_scores(&response, candidates.len()).ok_or_else(|| anyhow::anyhow!("could not read scores from the model's reply"))
+}
+
+async fn score_local(question: &str, candidates: &[ContextEntry]) -> anyhow::Result<Vec<f32>> {
+    let documents = candidates.iter().map(|e| passage(e, LOCAL_PASSAGE_TOKENS)).collect();
+    let logits = crate::local_embeddings::rerank(question, documents).await?;
+    Ok(logits.into_iter().map(|x| 1.0 / (1.0 + (-x).exp())).collect())
+}
+
+// Reorders candidates by reranker score (ties keep retrieval order), keeps the best `keep` and
+// sets their relevance to that score so context packing follows the new order
+fn apply_scores(candidates: Vec<ContextEntry>, scores: &[f32], keep: usize) -> Vec<ContextEntry> {
+    let mut scored: Vec<(f32, ContextEntry)> = scores.iter().copied().zip(candidates).collect();
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+    scored
+        .into_iter()
+        .take(keep)
+        .map(|(score, mut entry)| {
+            entry.relevance_score = score;
+            entry
+        })
+        .collect()
+}
+
+pub(crate) async fn rerank(
+    app_handle: &AppHandle,
+    mode: RerankMode,
+    question: &str,
+    mut candidates: Vec<ContextEntry>,
+    keep: usize,
+) -> Vec<ContextEntry> {
+    if candidates.len() <= 1 || mode == RerankMode::Off {
+        candidates.truncate(keep);
+        return candidates;
+    }
+    let started = std::time::Instant::now();
+    let scores = match mode {
+        RerankMode::Llm => score_llm(app_handle, question, &candidates).await,
+        RerankMode::Local => score_local(question, &candidates).await,
+        RerankMode::Off => unreachable!("handled above"),
+    };
+    match scores {
+        Ok(scores) if scores.len() == candidates.len() => {
+            tracing::debug!("{:?} scored {} candidates in {}ms", mode, candidates.len(), started.elapsed().as_millis());
+            apply_scores(candidates, &scores, keep)
+        }
+        Ok(_) => {
+            candidates.truncate(keep);
+            candidates
+        }
+        Err(e) => {
+            tracing::warn!("{:?} failed, keeping retrieval order: {}", mode, e);
+            candidates.truncate(keep);
+            candidates
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_scores() {
+        let response = "1: 2\n2: 9.5\n[3] - 7\nPassage 4 = 12\n9: 5";
+        let scores = parse_scores(response, 4).unwrap();
+        assert_eq!(scores, vec![0.2, 0.95, 0.7, 1.0]);
+        assert_eq!(parse_scores("1: 3\n2: 4\n", 5), None);
+        assert_eq!(parse_scores("Sorry, I can't help.", 3), None);
+    }
+
+    #[test]
+    fn test_apply_scores_reorders_and_keeps_best() {
+        let candidates: Vec<ContextEntry> = ["a", "b", "c", "d"]
+            .iter()
+            .map(|id| ContextEntry {
+                entry_id: id.to_string(),
+                title: None,
+                body: String::new(),
+                entry_date: chrono::Utc::now(),
+                tags: vec![],
+                relevance_score: 0.5,
+                snippet: String::new(),
+            })
+            .collect();
+        let kept = apply_scores(candidates, &[0.1, 0.9, 0.9, 0.3], 3);
+        let ids: Vec<&str> = kept.iter().map(|e| e.entry_id.as_str()).collect();
+        assert_eq!(ids, ["b", "c", "d"]);
+        assert_eq!(kept[0].relevance_score, 0.9);
+    }
+}
@@ -0,0 +1,239 @@
+use anyhow::Result;
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use std::path::Path;
+use tauri::AppHandle;
+
+use crate::database::Entry;
+
+// Re-sync from source files: entries imported from local .txt/.docx files are compared with the
+// file on disk, and when the file was edited the entry takes its new text in place (same id,
+// date and tags; search index refreshed, embeddings rebuilt by the next backfill).
+//
+// A file is only re-read when its modification time or size differs from the one recorded at
+// import (or none was recorded, for entries imported before this existed), unless forced.
+//
+// The other direction (opt-in, `write_back_sources`): saving an entry imported from a .txt file
+// rewrites the file, but only if its content hash still matches the one recorded at the last
+// import, re-sync or write, so edits made on disk are never silently lost.
+
+// The source file was edited outside the app since it was last synced
+#[derive(Debug)]
+pub struct SourceConflict(pub String);
+
+impl std::fmt::Display for SourceConflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} was changed on disk since it was last synced", self.0)
+    }
+}
+
+impl std::error::Error for SourceConflict {}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ResyncReport {
+    pub checked: u32,
+    pub updated: u32,
+    pub unchanged: u32,
+    // Source file no longer exists
+    pub missing: u32,
+    pub failed: u32,
+    pub errors: Vec<String>,
+    pub updated_ids: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Source {
+    pub entry_id: String,
+    pub path: String,
+    pub body: String,
+}
+
+// Entries whose source file changed since it was last seen; missing files are counted in the report
+pub(crate) fn changed_sources(conn: &Connection, force: bool, report: &mut ResyncReport) -> Result<Vec<Source>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, source_path, body, source_mtime, source_size FROM entries
+         WHERE source_type IN ('txt', 'docx') ORDER BY entry_date",
+    )?;
+    let rows = stmt
+        .query_map([], |r| {
+            Ok((r.get::<_, String>(0)?, r.get::<_, String>(1)?, r.get::<_, String>(2)?, r.get::<_, Option<i64>>(3)?, r.get::<_, Option<i64>>(4)?))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    let mut changed = Vec::new();
+    for (entry_id, path, body, mtime, size) in rows {
+        report.checked += 1;
+        let Some(stat) = crate::import::source_stat(&path) else {
+            report.missing += 1;
+            continue;
+        };
+        if !force && (mtime, size) == (Some(stat.0), Some(stat.1)) {
+            report.unchanged += 1;
+            continue;
+        }
+        changed.push(Source { entry_id, path, body });
+    }
+    Ok(changed)
+}
+
+// Applies the re-parsed text of a changed file; returns whether the entry's text changed
+pub(crate) fn apply(conn: &Connection, source: &Source, parsed: crate::import::ParsedFile) -> Result<bool> {
+    let content = crate::import::normalize_content(&parsed.content);
+    let updated = content != source.body;
+    if updated {
+        crate::database::set_entry_text(conn, &source.entry_id, parsed.title, &content)?;
+    }
+    record_source(conn, &source.entry_id, &source.path)?;
+    Ok(updated)
+}
+
+fn record_source(conn: &Connection, entry_id: &str, path: &str) -> Result<()> {
+    let stat = crate::import::source_stat(path);
+    conn.execute(
+        "UPDATE entries SET source_mtime = ?2, source_size = ?3, source_hash = ?4 WHERE id = ?1",
+        params![entry_id, stat.map(|s| s.0), stat.map(|s| s.1), crate::import::source_hash(path)],
+    )?;
+    Ok(())
+}
+
+// What the source file should contain for an entry: its body, headed by the title when the
+// title isn't already the first line or the file name (where import would take it from)
+fn file_text(entry: &Entry, path: &Path) -> String {
+    let mut text = String::new();
+    if let Some(title) = &entry.title {
+        let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or_default();
+        if title != stem && entry.body.lines().next().map(str::trim) != Some(title.as_str()) {
+            text.push_str(title);
+            text.push_str("\n\n");
+        }
+    }
+    text.push_str(entry.body.trim_end());
+    text.push('\n');
+    text
+}
+
+// Rewrites a .txt entry's source file with its current text; other entries are left alone
+pub(crate) fn write_back(conn: &Connection, entry: &Entry, overwrite: bool) -> Result<()> {
+    if entry.source_type != "txt" {
+        return Ok(());
+    }
+    let path = Path::new(&entry.source_path);
+    if !path.exists() {
+        anyhow::bail!("Source file {} no longer exists", entry.source_path);
+    }
+    if !overwrite {
+        let (hash, mtime, size): (Option<String>, Option<i64>, Option<i64>) = conn.query_row(
+            "SELECT source_hash, source_mtime, source_size FROM entries WHERE id = ?1",
+            params![entry.id],
+            |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)),
+        )?;
+        // Entries imported before hashes were recorded fall back to modification time and size
+        let unchanged = match hash {
+            Some(hash) => crate::import::source_hash(&entry.source_path) == Some(hash),
+            None => mtime.zip(size).is_some() && crate::import::source_stat(&entry.source_path) == mtime.zip(size),
+        };
+        if !unchanged {
+            return Err(SourceConflict(entry.source_path.clone()).into());
+        }
+    }
+    std::fs::write(path, file_text(entry, path))?;
+    record_source(conn, &entry.id, &entry.source_path)
+}
+
+pub async fn resync_from_sources(app_handle: &AppHandle, force: bool) -> Result<ResyncReport> {
+    let mut report = ResyncReport::default();
+    let changed = {
+        let conn = crate::database::open_conn(app_handle)?;
+        changed_sources(&conn, force, &mut report)?
+    };
+    for source in changed {
+        let outcome = match crate::import::parse_file(&source.path).await {
+            Ok(parsed) => crate::database::open_conn(app_handle).and_then(|conn| apply(&conn, &source, parsed)),
+            Err(e) => Err(e),
+        };
+        match outcome {
+            Ok(true) => {
+                report.updated += 1;
+                report.updated_ids.push(source.entry_id);
+            }
+            Ok(false) => report.unchanged += 1,
+            Err(e) => {
+                report.failed += 1;
+                report.errors.push(format!("{}: {}", source.path, e));
+            }
+        }
+    }
+    if report.updated > 0 {
+        crate::vector_index::invalidate_entries(&report.updated_ids);
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_resync_updates_edited_file() {
+        let dir = std::env::temp_dir().join(format!("jr-resync-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("2021-05-01.txt");
+        std::fs::write(&file, "Walked to the market.").unwrap();
+        let path = file.to_string_lossy().to_string();
+
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::migrations::run_migrations(&mut conn).unwrap();
+        let mut parsed = crate::import::parse_file(&path).await.unwrap();
+        parsed.content = crate::import::normalize_content(&parsed.content);
+        let id = crate::database::insert_entry(&conn, parsed, chrono::Utc::now(), "UTC".into()).unwrap();
+
+        let mut report = ResyncReport::default();
+        assert!(changed_sources(&conn, false, &mut report).unwrap().is_empty());
+        assert_eq!((report.checked, report.unchanged), (1, 1));
+
+        std::fs::write(&file, "Walked to the market, then the long way home by the river.").unwrap();
+        let mut report = ResyncReport::default();
+        let changed = changed_sources(&conn, false, &mut report).unwrap();
+        assert_eq!(changed.len(), 1);
+        assert!(apply(&conn, &changed[0], crate::import::parse_file(&path).await.unwrap()).unwrap());
+
+        let body: String = conn.query_row("SELECT body FROM entries WHERE id = ?1", [&id], |r| r.get(0)).unwrap();
+        assert!(body.contains("by the river"));
+        let hits: i64 = conn.query_row("SELECT COUNT(*) FROM entries_fts WHERE entries_fts MATCH 'river'", [], |r| r.get(0)).unwrap();
+        assert_eq!(hits, 1);
+        assert!(changed_sources(&conn, false, &mut ResyncReport::default()).unwrap().is_empty());
+
+        std::fs::remove_file(&file).unwrap();
+        let mut report = ResyncReport::default();
+        changed_sources(&conn, false, &mut report).unwrap();
+        assert_eq!(report.missing, 1);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_write_back_detects_conflicts() {
+        let dir = std::env::temp_dir().join(format!("jr-writeback-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("2021-06-02.txt");
+        std::fs::write(&file, "Rain all day.").unwrap();
+        let path = file.to_string_lossy().to_string();
+
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::migrations::run_migrations(&mut conn).unwrap();
+        let parsed = crate::import::parse_file(&path).await.unwrap();
+        let id = crate::database::insert_entry(&conn, parsed, chrono::Utc::now(), "UTC".into()).unwrap();
+
+        let entry = crate::database::set_entry_text(&conn, &id, None, "Rain all day. Read by the window.").unwrap();
+        write_back(&conn, &entry, false).unwrap();
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "Rain all day. Read by the window.\n");
+
+        // Edited on disk since the write: refused unless overwriting
+        std::fs::write(&file, "Rain all day. Edited elsewhere.").unwrap();
+        let entry = crate::database::set_entry_text(&conn, &id, None, "Rain, then sun.").unwrap();
+        let err = write_back(&conn, &entry, false).unwrap_err();
+        assert!(err.downcast_ref::<SourceConflict>().is_some());
+        write_back(&conn, &entry, true).unwrap();
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "Rain, then sun.\n");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,246 @@
+use anyhow::Result;
+use chrono::{Datelike, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tauri::AppHandle;
+
+use crate::ai::{AiSettings, Citation, ContextEntry};
+use crate::database::Entry;
+use crate::summaries::Granularity;
+
+// Year-in-review retrospective.
+//
+// The numbers (activity per month, tags, people) are counted directly from the year's entries.
+// The writing is built from the monthly recaps (generated through `summaries`, so they are cached
+// and shared with the Summaries view) plus a handful of notable entries the model may cite as
+// [Entry N]; citations are checked against those entries the same way RAG answers are.
+
+// Longest entries of each month offered to the model as citable sources
+const NOTABLE_PER_MONTH: usize = 2;
+const SNIPPET_CHARS: usize = 400;
+const TOP_PEOPLE: usize = 10;
+const TOP_TAGS: usize = 10;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MonthActivity {
+    pub month: u32,
+    pub entries: u32,
+    pub words: u64,
+    // Mean of the entries' sentiment scores, when any have one
+    pub average_sentiment: Option<f32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NamedCount {
+    pub name: String,
+    pub count: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct YearInReview {
+    pub year: i32,
+    pub entry_count: u32,
+    pub word_count: u64,
+    pub months: Vec<MonthActivity>,
+    pub top_tags: Vec<NamedCount>,
+    pub people: Vec<NamedCount>,
+    pub retrospective: String,
+    pub citations: Vec<Citation>,
+    pub model: String,
+}
+
+fn month_bounds(year: i32, month: u32) -> Option<(NaiveDate, NaiveDate)> {
+    let start = NaiveDate::from_ymd_opt(year, month, 1)?;
+    let next = if month == 12 { NaiveDate::from_ymd_opt(year + 1, 1, 1)? } else { NaiveDate::from_ymd_opt(year, month + 1, 1)? };
+    Some((start, next.pred_opt()?))
+}
+
+fn month_activity(entries: &[Entry]) -> Vec<MonthActivity> {
+    let mut months: Vec<MonthActivity> = Vec::new();
+    let mut sentiment: HashMap<u32, (f32, u32)> = HashMap::new();
+    for entry in entries {
+        let month = entry.entry_date.month();
+        if months.last().map(|m| m.month) != Some(month) {
+            months.push(MonthActivity { month, entries: 0, words: 0, average_sentiment: None });
+        }
+        let current = months.last_mut().expect("pushed above");
+        current.entries += 1;
+        current.words += crate::stats::word_count(&entry.body);
+        if let Some(score) = entry.sentiment {
+            let total = sentiment.entry(month).or_insert((0.0, 0));
+            total.0 += score;
+            total.1 += 1;
+        }
+    }
+    for month in &mut months {
+        month.average_sentiment = sentiment.get(&month.month).map(|(sum, n)| sum / *n as f32);
+    }
+    months
+}
+
+// Counts how many entries mention each name
+fn top_people(entries: &[Entry], limit: usize) -> Vec<NamedCount> {
+    let mut counts: HashMap<String, u32> = HashMap::new();
+    for entry in entries {
+        for entity in crate::entities::extract_rules(&entry.body) {
+            if entity.kind == crate::entities::EntityKind::Person {
+                *counts.entry(entity.name).or_insert(0) += 1;
+            }
+        }
+    }
+    top_counts(counts, 2, limit)
+}
+
+fn top_counts(counts: HashMap<String, u32>, min: u32, limit: usize) -> Vec<NamedCount> {
+    let mut sorted: Vec<NamedCount> = counts
+        .into_iter()
+        .filter(|(_, count)| *count >= min)
+        .map(|(name, count)| NamedCount { name, count })
+        .collect();
+    sorted.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.name.cmp(&b.name)));
+    sorted.truncate(limit);
+    sorted
+}
+
+// The longest entries of each month, oldest first
+fn notable_entries(entries: &[Entry], per_month: usize) -> Vec<&Entry> {
+    let mut by_month: HashMap<u32, Vec<&Entry>> = HashMap::new();
+    for entry in entries {
+        by_month.entry(entry.entry_date.month()).or_default().push(entry);
+    }
+    let mut notable: Vec<&Entry> = Vec::new();
+    for mut month in by_month.into_values() {
+        month.sort_by_key(|e| std::cmp::Reverse(crate::stats::word_count(&e.body)));
+        notable.extend(month.into_iter().take(per_month));
+    }
+    notable.sort_by_key(|e| e.entry_date);
+    notable
+}
+
+fn context_entry(entry: &Entry, tags: &HashMap<String, Vec<String>>) -> ContextEntry {
+    let body = entry.body.split_whitespace().collect::<Vec<_>>().join(" ");
+    ContextEntry {
+        entry_id: entry.id.clone(),
+        title: entry.title.clone(),
+        snippet: body.chars().take(SNIPPET_CHARS).collect(),
+        body: entry.body.clone(),
+        entry_date: entry.entry_date,
+        tags: tags.get(&entry.id).cloned().unwrap_or_default(),
+        relevance_score: 1.0,
+    }
+}
+
+fn review_prompt(
+    year: i32,
+    months: &[MonthActivity],
+    tags: &[NamedCount],
+    people: &[NamedCount],
+    recaps: &[(String, String)],
+    sources: &str,
+) -> String {
+    let list = |items: &[NamedCount]| items.iter().map(|i| format!("{} ({})", i.name, i.count)).collect::<Vec<_>>().join(", ");
+    let activity = months
+        .iter()
+        .map(|m| format!("{}: {} entries, {} words", m.month, m.entries, m.words))
+        .collect::<Vec<_>>()
+        .join("; ");
+    let recaps = recaps.iter().map(|(label, text)| format!("### {}\n{}", label, text)).collect::<Vec<_>>().join("\n\n");
+    format!(
+        r#"Write a year-in-review of this person's {year} journal, addressed to them as "you".
+
+Use exactly these sections:
+## Top themes
+## Notable events (give the date of each)
+## Emotional arc (how the year felt, month to month)
+## People (who mattered most, and why)
+## Looking back
+
+Support specific claims with the source entries, cited as [Entry N]. Only cite entries listed below.
+
+Activity by month: {activity}
+Most used tags: {tags}
+Most mentioned names: {people}
+
+Monthly recaps:
+{recaps}
+
+Source entries:
+{sources}"#,
+        tags = if tags.is_empty() { "none".to_string() } else { list(tags) },
+        people = if people.is_empty() { "none".to_string() } else { list(people) },
+    )
+}
+
+pub async fn year_in_review(app_handle: &AppHandle, year: i32) -> Result<YearInReview> {
+    let (start, end) = match (NaiveDate::from_ymd_opt(year, 1, 1), NaiveDate::from_ymd_opt(year, 12, 31)) {
+        (Some(start), Some(end)) => (start, end),
+        _ => return Err(anyhow::anyhow!("Invalid year {}", year)),
+    };
+    let (entries, tags, settings) = {
+        let mut conn = crate::database::open_conn(app_handle)?;
+        crate::sentiment::backfill(&mut conn)?;
+        let entries = crate::summaries::load_period_entries(&conn, start, end)?;
+        let ids: Vec<String> = entries.iter().map(|e| e.id.clone()).collect();
+        let tags = crate::database::tags_for_entries(&conn, &ids)?;
+        (entries, tags, AiSettings::load(&conn))
+    };
+    if entries.is_empty() {
+        return Err(anyhow::anyhow!("No entries in {}", year));
+    }
+
+    let months = month_activity(&entries);
+    let mut tag_counts: HashMap<String, u32> = HashMap::new();
+    for tag in tags.values().flatten() {
+        *tag_counts.entry(tag.clone()).or_insert(0) += 1;
+    }
+    let top_tags = top_counts(tag_counts, 1, TOP_TAGS);
+    let people = top_people(&entries, TOP_PEOPLE);
+
+    let mut recaps = Vec::new();
+    for activity in &months {
+        let Some((month_start, month_end)) = month_bounds(year, activity.month) else { continue };
+        let recap = crate::summaries::summarize_period(
+            app_handle,
+            &month_start.to_string(),
+            &month_end.to_string(),
+            Granularity::Month,
+            false,
+        )
+        .await?;
+        recaps.push((month_start.format("%B").to_string(), recap.summary));
+    }
+
+    let sources: Vec<ContextEntry> = notable_entries(&entries, NOTABLE_PER_MONTH)
+        .into_iter()
+        .map(|e| context_entry(e, &tags))
+        .collect();
+    let prompt = review_prompt(year, &months, &top_tags, &people, &recaps, &crate::ai::build_context_string(&sources));
+    tracing::info!("{} entries={} months={} sources={}", year, entries.len(), months.len(), sources.len());
+    let ids = sources.iter().map(|s| s.entry_id.clone()).collect();
+    let answer = crate::ai_audit::with_context("year in review", ids, crate::summaries::ask(&settings, prompt)).await?;
+    let (retrospective, citations) = crate::ai::verify_citations(&answer, &sources);
+
+    Ok(YearInReview {
+        year,
+        entry_count: entries.len() as u32,
+        word_count: months.iter().map(|m| m.words).sum(),
+        months,
+        top_tags,
+        people,
+        retrospective,
+        citations,
+        model: format!("{}:{}", settings.provider.as_str(), settings.chat_model(&settings.provider, "")),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_month_bounds() {
+        let (start, end) = month_bounds(2024, 2).unwrap();
+        assert_eq!((start.to_string(), end.to_string()), ("2024-02-01".to_string(), "2024-02-29".to_string()));
+        assert_eq!(month_bounds(2023, 12).unwrap().1.to_string(), "2023-12-31");
+    }
+}
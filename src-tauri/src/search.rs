@@ -1,4 +1,4 @@
-use anyhow::{Result, Context};
+use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use chrono::{DateTime, Utc};
@@ -15,7 +15,9 @@ pub struct SearchResult {
     pub tags: Vec<String>,
     pub score: f32,
     pub snippet: String,
-    pub rank_source: String, // "fts", "vector", or "hybrid"
+    pub rank_source: String, // "fts", "fuzzy", "regex", "vector", "hybrid", or "similar"
+    // Matches in the body, for bolding in the UI
+    pub highlights: Vec<crate::highlight::SnippetFragment>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -24,6 +26,25 @@ pub struct SearchFilters {
     pub tags: Option<Vec<String>>,
     pub source_types: Option<Vec<String>>,
     pub min_score: Option<f32>,
+    // Names of extracted people/places/events, e.g. entries mentioning "Paris"
+    #[serde(default)]
+    pub entities: Option<Vec<String>>,
+    #[serde(default)]
+    pub languages: Option<Vec<String>>,
+}
+
+impl SearchFilters {
+    // The parts that can be pushed down into SQL; min_score only exists after ranking
+    pub fn to_entry_filter(&self) -> crate::database::EntryFilter {
+        crate::database::EntryFilter {
+            date_from: self.date_range.map(|(start, _)| start.to_rfc3339()),
+            date_to: self.date_range.map(|(_, end)| end.to_rfc3339()),
+            tags: self.tags.clone(),
+            source_types: self.source_types.clone(),
+            entities: self.entities.clone(),
+            languages: self.languages.clone(),
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -40,12 +61,49 @@ pub enum SearchType {
     FullText,
     Semantic,
     Hybrid,
+    Fuzzy,
+    Regex,
+}
+
+// Entry point for the search command: dispatch on search type and apply offset paging
+pub async fn search(app_handle: &AppHandle, request: &SearchRequest) -> Result<Vec<SearchResult>> {
+    let query = request.query.trim();
+    if query.is_empty() {
+        return Ok(vec![]);
+    }
+    let window = request.limit + request.offset;
+    let results = match request.search_type {
+        SearchType::FullText => full_text_search(app_handle, query, &request.filters, window).await?,
+        SearchType::Semantic => vector_search(app_handle, query, &request.filters, window).await?,
+        SearchType::Hybrid => hybrid_search(app_handle, query, &request.filters, window).await?,
+        SearchType::Fuzzy => fuzzy_search(app_handle, query, &request.filters, window).await?,
+        SearchType::Regex => regex_search(app_handle, query, &request.filters, window).await?,
+    };
+    let mut page: Vec<SearchResult> = results.into_iter().skip(request.offset as usize).collect();
+    add_highlights(&mut page, query, &request.search_type)?;
+    Ok(page)
+}
+
+// Fill in match fragments for results whose search path didn't produce them
+fn add_highlights(results: &mut [SearchResult], query: &str, search_type: &SearchType) -> Result<()> {
+    use crate::highlight::{fragments_for_regex, fragments_for_terms};
+    let re = match search_type {
+        SearchType::Regex => Some(compile_user_regex(query)?),
+        _ => None,
+    };
+    let terms = crate::fts_query::positive_terms(query);
+    for r in results.iter_mut().filter(|r| r.highlights.is_empty()) {
+        r.highlights = match &re {
+            Some(re) => fragments_for_regex(&r.body, re),
+            None => fragments_for_terms(&r.body, &terms),
+        };
+    }
+    Ok(())
 }
 
 #[derive(Debug)]
 struct RankedResult {
     result: SearchResult,
-    fts_rank: Option<usize>,
     vector_rank: Option<usize>,
     rrf_score: f32,
 }
@@ -60,13 +118,17 @@ pub async fn full_text_search(
     use crate::database::search_entries_fts;
     
     // Get entries from database using FTS search
-    let entries = search_entries_fts(app_handle, query, limit * 2).await?;
+    let entries = search_entries_fts(app_handle, query, limit * 2, &filters.to_entry_filter()).await?;
+    // Snippets and scores work on plain text, so strip the query syntax first
+    let terms = crate::fts_query::positive_terms(query);
+    let plain = terms.join(" ");
+    let anchor = terms.first().map(String::as_str).unwrap_or("");
     
     // Convert database entries to search results
     let mut results = Vec::new();
     for entry in entries {
-        let snippet = generate_snippet(&entry.body, query, 200);
-        let score = calculate_fts_score(&entry.body, &entry.title, query);
+        let snippet = generate_snippet(&entry.body, anchor, 200);
+        let score = calculate_fts_score(&entry.body, &entry.title, &plain);
         
         let search_result = SearchResult {
             id: entry.id,
@@ -75,91 +137,240 @@ pub async fn full_text_search(
             entry_date: entry.entry_date,
             source_path: entry.source_path,
             source_type: entry.source_type,
-            tags: vec![], // TODO: Load tags from database
+            tags: vec![], // Filled in by apply_filters
             score,
             snippet,
             rank_source: "fts".to_string(),
+            highlights: vec![],
         };
         results.push(search_result);
     }
     
-    Ok(apply_filters(results, filters, limit))
+    apply_filters(app_handle, results, filters, limit).await
 }
 
-// Vector Similarity Search Implementation
-pub async fn vector_search(
+// Typo-tolerant full-text search; BM25 order from FTS5 is kept
+pub async fn fuzzy_search(
     app_handle: &AppHandle,
     query: &str,
     filters: &SearchFilters,
     limit: u32,
 ) -> Result<Vec<SearchResult>> {
-    use crate::database::list_entries;
-    
-    // Implement actual vector similarity search using embeddings
-    use crate::ai::{generate_embedding, EmbeddingRequest};
-    
-    // Generate embedding for the query
-    let embedding_request = EmbeddingRequest {
-        text: query.to_string(),
-        model: "default".to_string(),
-    };
-    
-    let query_embedding = match generate_embedding(embedding_request).await {
-        Ok(embedding) => embedding,
-        Err(_) => {
-            // Fallback to semantic keyword matching if embedding fails
-            return semantic_keyword_search(app_handle, query, filters, limit).await;
-        }
-    };
-    
-    // Get all entries from database
-    let entries = list_entries(app_handle, Some(limit * 5), None).await?;
-    
-    let mut results = Vec::new();
-    for entry in entries {
-        // Generate embedding for entry content
-        let entry_text = format!("{} {}", 
-            entry.title.as_ref().unwrap_or(&String::new()), 
-            entry.body
-        );
-        
-        let entry_embedding_request = EmbeddingRequest {
-            text: entry_text,
-            model: "default".to_string(),
-        };
-        
-        let entry_embedding = match generate_embedding(entry_embedding_request).await {
-            Ok(embedding) => embedding,
-            Err(_) => continue, // Skip entries we can't generate embeddings for
-        };
-        
-        // Calculate cosine similarity
-        let similarity = cosine_similarity(&query_embedding, &entry_embedding);
-        
-        if similarity > 0.1 { // Only include entries with some similarity
-            let snippet = generate_snippet(&entry.body, query, 200);
-            
-            let search_result = SearchResult {
+    let entries = crate::database::search_entries_fuzzy(app_handle, query, limit * 2, &filters.to_entry_filter()).await?;
+    let terms = crate::fts_query::positive_terms(query);
+    let plain = terms.join(" ");
+
+    let results = entries
+        .into_iter()
+        .map(|(entry, fragments)| {
+            let score = calculate_fts_score(&entry.body, &entry.title, &plain);
+            // FTS5 marked whichever spelling actually matched
+            let snippet = crate::highlight::plain_text(&fragments);
+            SearchResult {
                 id: entry.id,
                 title: entry.title,
                 body: entry.body,
                 entry_date: entry.entry_date,
                 source_path: entry.source_path,
                 source_type: entry.source_type,
-                tags: vec![], // TODO: Load tags from database
-                score: similarity,
+                tags: vec![],
+                score,
                 snippet,
-                rank_source: "vector".to_string(),
-            };
-            results.push(search_result);
+                rank_source: "fuzzy".to_string(),
+                highlights: fragments,
+            }
+        })
+        .collect();
+
+    apply_filters(app_handle, results, filters, limit).await
+}
+
+// Regex scans are bounded: the regex crate guarantees linear-time matching, and the scan
+// itself stops at a deadline or once enough matches are found, returning what it has.
+const REGEX_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+const REGEX_MAX_RESULTS: usize = 500;
+const REGEX_SIZE_LIMIT: usize = 1 << 20;
+
+pub fn compile_user_regex(pattern: &str) -> Result<regex::Regex> {
+    regex::RegexBuilder::new(pattern)
+        .size_limit(REGEX_SIZE_LIMIT)
+        .build()
+        .map_err(|e| anyhow::anyhow!("Invalid regular expression: {}", e))
+}
+
+// Streamed scan over titles and bodies, newest entries first
+pub async fn regex_search(
+    app_handle: &AppHandle,
+    pattern: &str,
+    filters: &SearchFilters,
+    limit: u32,
+) -> Result<Vec<SearchResult>> {
+    let re = compile_user_regex(pattern)?;
+    let db_path = crate::database::get_db_file_path(app_handle)?;
+    let filter = filters.to_entry_filter();
+
+    let results = tokio::task::spawn_blocking(move || -> Result<Vec<SearchResult>> {
+        let conn = crate::database::open_conn_at(&db_path)?;
+        regex_scan(&conn, &re, &filter, REGEX_MAX_RESULTS)
+    })
+    .await
+    .map_err(|e| anyhow::anyhow!(e.to_string()))??;
+
+    apply_filters(app_handle, results, filters, limit).await
+}
+
+// Only entries passing the filter are scanned, so the cap counts matches the caller can show
+fn regex_scan(
+    conn: &rusqlite::Connection,
+    re: &regex::Regex,
+    filter: &crate::database::EntryFilter,
+    cap: usize,
+) -> Result<Vec<SearchResult>> {
+    let started = std::time::Instant::now();
+    let (conditions, values) = filter.to_sql(1)?;
+    let mut stmt = conn.prepare_cached(&format!(
+        "SELECT {} FROM entries e WHERE 1 = 1{} ORDER BY e.entry_date DESC",
+        crate::database::ENTRY_COLUMNS,
+        conditions
+    ))?;
+    let mut rows = stmt.query(rusqlite::params_from_iter(values))?;
+
+    let mut results = Vec::new();
+    let mut scanned = 0u32;
+    while let Some(row) = rows.next()? {
+        if started.elapsed() > REGEX_TIMEOUT {
+            tracing::warn!("regex scan timed out after {} entries", scanned);
+            break;
+        }
+        scanned += 1;
+        let entry = crate::database::entry_from_row(row)?;
+        let title_hit = entry.title.as_deref().map(|t| re.is_match(t)).unwrap_or(false);
+        let body_hit = re.find(&entry.body);
+        if !title_hit && body_hit.is_none() {
+            continue;
+        }
+        let snippet = match body_hit {
+            Some(m) => generate_snippet(&entry.body, m.as_str(), 200),
+            None => generate_snippet(&entry.body, "", 200),
+        };
+        let score = re.find_iter(&entry.body).count() as f32 + if title_hit { 1.0 } else { 0.0 };
+        results.push(SearchResult {
+            id: entry.id,
+            title: entry.title,
+            body: entry.body,
+            entry_date: entry.entry_date,
+            source_path: entry.source_path,
+            source_type: entry.source_type,
+            tags: vec![],
+            score,
+            snippet,
+            rank_source: "regex".to_string(),
+            highlights: vec![],
+        });
+        if results.len() >= cap {
+            tracing::warn!("regex result cap reached after {} entries", scanned);
+            break;
         }
     }
-    
-    // Sort by similarity score (descending)
-    results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
-    results.truncate(limit as usize);
-    
-    Ok(apply_filters(results, filters, limit))
+    Ok(results)
+}
+
+// Vector Similarity Search Implementation
+// Scores the query against the in-memory index of stored chunk embeddings; an entry ranks by its
+// best-matching chunk. Entries are never embedded here.
+pub async fn vector_search(
+    app_handle: &AppHandle,
+    query: &str,
+    filters: &SearchFilters,
+    limit: u32,
+) -> Result<Vec<SearchResult>> {
+    use crate::database::{get_db_file_path, get_entry_by_id, open_conn};
+    use crate::embeddings::load_config;
+
+    let (config, index) = {
+        let conn = open_conn(app_handle)?;
+        let config = load_config(&conn);
+        let db_path = get_db_file_path(app_handle)?;
+        let index = crate::vector_index::get_or_load_chunks(&conn, &db_path, &config)?;
+        (config, index)
+    };
+
+    // Nothing embedded yet (or the provider is unreachable): fall back to keyword matching
+    if index.is_empty() {
+        return semantic_keyword_search(app_handle, query, filters, limit).await;
+    }
+    let embed = config.embed(crate::embeddings::EmbedRole::Query, query);
+    let query_embedding = match crate::ai_audit::with_context("search query", vec![], embed).await {
+        Ok(embedding) => embedding,
+        Err(_) => return semantic_keyword_search(app_handle, query, filters, limit).await,
+    };
+
+    // Leave headroom for filters to drop candidates, and for several chunks of one entry
+    let mut seen = std::collections::HashSet::new();
+    let scored: Vec<(String, f32)> = index
+        .search(&query_embedding, (limit as usize).saturating_mul(10))
+        .into_iter()
+        .filter(|(_, similarity)| *similarity > 0.1) // Only include entries with some similarity
+        .filter(|(id, _)| seen.insert(id.clone()))
+        .take((limit as usize).saturating_mul(5))
+        .collect();
+
+    let mut results = Vec::new();
+    for (id, similarity) in scored {
+        let Some(entry) = get_entry_by_id(app_handle, &id).await? else { continue };
+        let snippet = generate_snippet(&entry.body, query, 200);
+        results.push(SearchResult {
+            id: entry.id,
+            title: entry.title,
+            body: entry.body,
+            entry_date: entry.entry_date,
+            source_path: entry.source_path,
+            source_type: entry.source_type,
+            tags: vec![], // Filled in by apply_filters
+            score: similarity,
+            snippet,
+            rank_source: "vector".to_string(),
+            highlights: vec![],
+        });
+    }
+
+    apply_filters(app_handle, results, filters, limit).await
+}
+
+// Entries closest to `entry_id` in embedding space, best first. Errors if the entry has no
+// embedding for the current model yet (the backfill hasn't reached it).
+pub async fn find_similar(app_handle: &AppHandle, entry_id: &str, limit: u32) -> Result<Vec<SearchResult>> {
+    use crate::database::{get_db_file_path, get_entry_by_id, open_conn};
+
+    let index = {
+        let conn = open_conn(app_handle)?;
+        let model_key = crate::embeddings::load_config(&conn).model_key();
+        let db_path = get_db_file_path(app_handle)?;
+        crate::vector_index::get_or_load(&conn, &db_path, &model_key)?
+    };
+    let neighbours = index
+        .similar_to(entry_id, limit as usize)
+        .ok_or_else(|| anyhow::anyhow!("Entry {} has no embedding yet", entry_id))?;
+
+    let mut results = Vec::new();
+    for (id, similarity) in neighbours {
+        let Some(entry) = get_entry_by_id(app_handle, &id).await? else { continue };
+        let snippet = generate_snippet(&entry.body, "", 200);
+        results.push(SearchResult {
+            id: entry.id,
+            title: entry.title,
+            body: entry.body,
+            entry_date: entry.entry_date,
+            source_path: entry.source_path,
+            source_type: entry.source_type,
+            tags: vec![],
+            score: similarity,
+            snippet,
+            rank_source: "similar".to_string(),
+            highlights: vec![],
+        });
+    }
+    Ok(results)
 }
 
 // Fallback semantic search using keyword matching
@@ -189,10 +400,11 @@ async fn semantic_keyword_search(
                 entry_date: entry.entry_date,
                 source_path: entry.source_path,
                 source_type: entry.source_type,
-                tags: vec![], // TODO: Load tags from database
+                tags: vec![], // Filled in by apply_filters
                 score: semantic_score,
                 snippet,
                 rank_source: "semantic".to_string(),
+                highlights: vec![],
             };
             results.push(search_result);
         }
@@ -202,7 +414,7 @@ async fn semantic_keyword_search(
     results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
     results.truncate(limit as usize);
     
-    Ok(apply_filters(results, filters, limit))
+    apply_filters(app_handle, results, filters, limit).await
 }
 
 // Hybrid Search with Reciprocal Rank Fusion (RRF)
@@ -211,16 +423,28 @@ pub async fn hybrid_search(
     query: &str,
     filters: &SearchFilters,
     limit: u32,
+) -> Result<Vec<SearchResult>> {
+    hybrid_search_with(app_handle, query, query, filters, limit).await
+}
+
+// Hybrid search where the keyword side gets its own FTS query, e.g. the content words of
+// a natural-language question OR'ed together, while the embedding sees the full text
+pub async fn hybrid_search_with(
+    app_handle: &AppHandle,
+    keyword_query: &str,
+    semantic_query: &str,
+    filters: &SearchFilters,
+    limit: u32,
 ) -> Result<Vec<SearchResult>> {
     // Get results from both search methods
-    let fts_results = full_text_search(app_handle, query, filters, limit * 2).await?;
-    let vector_results = vector_search(app_handle, query, filters, limit * 2).await?;
+    let fts_results = full_text_search(app_handle, keyword_query, filters, limit * 2).await?;
+    let vector_results = vector_search(app_handle, semantic_query, filters, limit * 2).await?;
     
     // Apply RRF to combine rankings
     let combined_results = reciprocal_rank_fusion(fts_results, vector_results, 60.0)?;
     
     // Apply final filtering and limit
-    Ok(apply_filters(combined_results, filters, limit))
+    apply_filters(app_handle, combined_results, filters, limit).await
 }
 
 // Reciprocal Rank Fusion Algorithm
@@ -237,7 +461,6 @@ fn reciprocal_rank_fusion(
         
         result_map.insert(result.id.clone(), RankedResult {
             result,
-            fts_rank: Some(rank),
             vector_rank: None,
             rrf_score,
         });
@@ -256,7 +479,6 @@ fn reciprocal_rank_fusion(
             // Add new entry from vector search only
             result_map.insert(result.id.clone(), RankedResult {
                 result,
-                fts_rank: None,
                 vector_rank: Some(rank),
                 rrf_score,
             });
@@ -279,11 +501,22 @@ fn reciprocal_rank_fusion(
 }
 
 // Apply filters to search results
-fn apply_filters(
+async fn apply_filters(
+    app_handle: &AppHandle,
     mut results: Vec<SearchResult>,
     filters: &SearchFilters,
     limit: u32,
-) -> Vec<SearchResult> {
+) -> Result<Vec<SearchResult>> {
+    // Attach stored tags; the tag filter below and the UI both need them
+    let ids: Vec<String> = results.iter().map(|r| r.id.clone()).collect();
+    if !ids.is_empty() {
+        let conn = crate::database::open_conn(app_handle)?;
+        let mut tags = crate::database::tags_for_entries(&conn, &ids)?;
+        for r in results.iter_mut() {
+            r.tags = tags.remove(&r.id).unwrap_or_default();
+        }
+    }
+
     // Apply date range filter
     if let Some((start, end)) = &filters.date_range {
         results.retain(|r| r.entry_date >= *start && r.entry_date <= *end);
@@ -313,63 +546,56 @@ fn apply_filters(
     // Apply limit
     results.truncate(limit as usize);
     
-    results
+    Ok(results)
 }
 
 // Generate snippet from content
 pub fn generate_snippet(content: &str, query: &str, max_length: usize) -> String {
-    let query_lower = query.to_lowercase();
-    let content_lower = content.to_lowercase();
-    
-    // Find the first occurrence of any query term
-    if let Some(pos) = content_lower.find(&query_lower) {
-        let start = pos.saturating_sub(50);
-        let end = (pos + query.len() + 50).min(content.len());
-        
-        let mut snippet = content[start..end].to_string();
-        
+    // Work in chars so multi-byte text never gets sliced mid-character
+    let chars: Vec<char> = content.chars().collect();
+    let fold = |c: char| c.to_lowercase().next().unwrap_or(c);
+    let content_lower: Vec<char> = chars.iter().map(|&c| fold(c)).collect();
+    let query_lower: Vec<char> = query.trim().chars().map(fold).collect();
+
+    // Find the first occurrence of the query
+    let pos = if query_lower.is_empty() {
+        None
+    } else {
+        content_lower
+            .windows(query_lower.len())
+            .position(|w| w == query_lower.as_slice())
+    };
+
+    if let Some(pos) = pos {
+        // Leave room for an ellipsis on each side and center the window on the match
+        let budget = max_length.saturating_sub(6).max(query_lower.len());
+        let lead = budget.saturating_sub(query_lower.len()) / 2;
+        let mut start = pos.saturating_sub(lead);
+        let end = (start + budget).min(chars.len());
+        start = end.saturating_sub(budget);
+
+        let mut snippet: String = chars[start..end].iter().collect();
+
         // Add ellipsis if we're not at the beginning/end
         if start > 0 {
             snippet = format!("...{}", snippet);
         }
-        if end < content.len() {
+        if end < chars.len() {
             snippet = format!("{}...", snippet);
         }
-        
-        // Truncate if still too long
-        if snippet.len() > max_length {
-            snippet.truncate(max_length - 3);
-            snippet.push_str("...");
-        }
-        
+
         snippet
     } else {
         // No query match, return beginning of content
-        let mut snippet = content.chars().take(max_length - 3).collect::<String>();
-        if content.len() > max_length - 3 {
+        let keep = max_length.saturating_sub(3);
+        let mut snippet = chars.iter().take(keep).collect::<String>();
+        if chars.len() > keep {
             snippet.push_str("...");
         }
         snippet
     }
 }
 
-// Compute cosine similarity between two vectors
-pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
-    if a.len() != b.len() {
-        return 0.0;
-    }
-    
-    let dot_product: f32 = a.iter().zip(b.iter()).map(|(x, y)| x * y).sum();
-    let norm_a: f32 = a.iter().map(|x| x * x).sum::<f32>().sqrt();
-    let norm_b: f32 = b.iter().map(|x| x * x).sum::<f32>().sqrt();
-    
-    if norm_a == 0.0 || norm_b == 0.0 {
-        0.0
-    } else {
-        dot_product / (norm_a * norm_b)
-    }
-}
-
 // Calculate FTS score based on query matches
 fn calculate_fts_score(body: &str, title: &Option<String>, query: &str) -> f32 {
     let query_lower = query.to_lowercase();
@@ -389,7 +615,7 @@ fn calculate_fts_score(body: &str, title: &Option<String>, query: &str) -> f32 {
     // Normalize by content length
     let content_length = body.len() + title_lower.len();
     if content_length > 0 {
-        score = score / (content_length as f32 / 100.0).max(1.0);
+        score /= (content_length as f32 / 100.0).max(1.0);
     }
     
     // Cap score at 1.0
@@ -424,7 +650,8 @@ fn calculate_semantic_similarity(body: &str, title: &Option<String>, query: &str
         }
         
         // Partial word match (for stemming-like behavior)
-        let partial_matches = full_content.matches(&query_word[..query_word.len().min(4)]).count();
+        let prefix: String = query_word.chars().take(4).collect();
+        let partial_matches = full_content.matches(prefix.as_str()).count();
         if partial_matches > 0 {
             word_score += 0.5 * (partial_matches as f32).min(3.0);
         }
@@ -463,16 +690,42 @@ mod tests {
     use super::*;
     
     #[test]
-    fn test_cosine_similarity() {
-        let a = vec![1.0, 0.0, 0.0];
-        let b = vec![1.0, 0.0, 0.0];
-        assert!((cosine_similarity(&a, &b) - 1.0).abs() < 1e-6);
-        
-        let a = vec![1.0, 0.0, 0.0];
-        let b = vec![0.0, 1.0, 0.0];
-        assert!((cosine_similarity(&a, &b) - 0.0).abs() < 1e-6);
+    fn test_compile_user_regex() {
+        let re = compile_user_regex(r"TODO:|\(\d{3}\) \d{3}-\d{4}").unwrap();
+        assert!(re.is_match("call (555) 123-4567"));
+        assert!(compile_user_regex("(unclosed").is_err());
+        assert!(compile_user_regex(r"\w{1000}{1000}").is_err());
     }
-    
+
+    #[test]
+    fn test_regex_scan_caps_filtered_matches() {
+        let mut conn = rusqlite::Connection::open_in_memory().unwrap();
+        crate::migrations::run_migrations(&mut conn).unwrap();
+        // Newer markdown entries would fill the cap before any text entry is reached
+        for i in 0..6 {
+            let source_type = if i < 3 { "txt" } else { "md" };
+            conn.execute(
+                "INSERT INTO entries (id, title, body, entry_date, entry_timezone, source_path, source_type, text_hash, created_at, updated_at)
+                    VALUES (?1, NULL, 'TODO: call back', ?2, 'UTC', ?1, ?3, ?1, ?2, ?2)",
+                rusqlite::params![format!("e{}", i), format!("2021-03-0{}T12:00:00+00:00", i + 1), source_type],
+            )
+            .unwrap();
+        }
+        let re = compile_user_regex("TODO:").unwrap();
+        let filter = crate::database::EntryFilter { source_types: Some(vec!["txt".to_string()]), ..Default::default() };
+
+        let hits = regex_scan(&conn, &re, &filter, 2).unwrap();
+        assert_eq!(hits.iter().map(|h| h.id.as_str()).collect::<Vec<_>>(), vec!["e2", "e1"]);
+        assert_eq!(regex_scan(&conn, &re, &Default::default(), 10).unwrap().len(), 6);
+    }
+
+    #[test]
+    fn test_semantic_similarity_multibyte_query() {
+        let title = Some("Café".to_string());
+        assert!(calculate_semantic_similarity("Coffee at the cafés downtown", &title, "cafés") > 0.0);
+        assert!(calculate_semantic_similarity("今日は東京へ行った", &None, "東京へ行く") > 0.0);
+    }
+
     #[test]
     fn test_generate_snippet() {
         let content = "This is a long piece of content that contains the word example somewhere in the middle of the text.";
@@ -495,6 +748,7 @@ mod tests {
                 score: 0.9,
                 snippet: "test".to_string(),
                 rank_source: "fts".to_string(),
+                highlights: vec![],
             }
         ];
         
@@ -510,6 +764,7 @@ mod tests {
                 score: 0.8,
                 snippet: "test".to_string(),
                 rank_source: "vector".to_string(),
+                highlights: vec![],
             }
         ];
         
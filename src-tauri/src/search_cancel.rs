@@ -0,0 +1,136 @@
+use rusqlite::{Connection, InterruptHandle};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+// Cancellation of in-flight searches. Each search runs under a caller-chosen key (one per
+// search box); starting a new search under the same key interrupts the previous one's SQLite
+// statement, so a query superseded by further typing stops using the database right away
+// instead of running on in its blocking thread until it finishes.
+
+// Search key -> generation of the search running under it, and its connection once attached
+type Active = HashMap<String, (u64, Option<InterruptHandle>)>;
+
+static NEXT_GENERATION: AtomicU64 = AtomicU64::new(1);
+static ACTIVE: Mutex<Option<Active>> = Mutex::new(None);
+
+fn with_active<T>(f: impl FnOnce(&mut Active) -> T) -> T {
+    let mut guard = ACTIVE.lock().unwrap_or_else(|e| e.into_inner());
+    f(guard.get_or_insert_with(HashMap::new))
+}
+
+#[derive(Debug)]
+pub struct SearchCancelled;
+
+impl std::fmt::Display for SearchCancelled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Search was cancelled")
+    }
+}
+
+impl std::error::Error for SearchCancelled {}
+
+pub(crate) struct SearchToken {
+    key: String,
+    generation: u64,
+}
+
+impl SearchToken {
+    // Supersedes (and interrupts) any search still running under `key`
+    pub fn new(key: &str) -> Self {
+        let generation = NEXT_GENERATION.fetch_add(1, Ordering::Relaxed);
+        with_active(|active| {
+            if let Some((_, Some(handle))) = active.insert(key.to_string(), (generation, None)) {
+                handle.interrupt();
+            }
+        });
+        SearchToken { key: key.to_string(), generation }
+    }
+
+    // Makes `conn` interruptible through this token; errors if it was superseded already
+    pub fn attach(&self, conn: &Connection) -> anyhow::Result<()> {
+        with_active(|active| match active.get_mut(&self.key) {
+            Some((generation, handle)) if *generation == self.generation => {
+                *handle = Some(conn.get_interrupt_handle());
+                Ok(())
+            }
+            _ => Err(SearchCancelled.into()),
+        })
+    }
+
+    // Interrupts this search, unless a newer one has taken over the key
+    pub fn cancel(&self) {
+        with_active(|active| {
+            if let Some((generation, Some(handle))) = active.get(&self.key) {
+                if *generation == self.generation {
+                    handle.interrupt();
+                }
+            }
+        });
+    }
+}
+
+impl Drop for SearchToken {
+    fn drop(&mut self) {
+        // The connection goes back to the pool; later interrupts must not reach it
+        with_active(|active| {
+            if active.get(&self.key).is_some_and(|(g, _)| *g == self.generation) {
+                active.remove(&self.key);
+            }
+        });
+    }
+}
+
+// Interrupts whatever search is running under `key`; true if there was one
+pub fn cancel(key: &str) -> bool {
+    with_active(|active| match active.remove(key) {
+        Some((_, handle)) => {
+            if let Some(handle) = handle {
+                handle.interrupt();
+            }
+            true
+        }
+        None => false,
+    })
+}
+
+// True for errors caused by a cancelled search (superseded, cancelled or timed out)
+pub(crate) fn is_cancelled(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        cause.downcast_ref::<SearchCancelled>().is_some()
+            || matches!(
+                cause.downcast_ref::<rusqlite::Error>(),
+                Some(rusqlite::Error::SqliteFailure(e, _)) if e.code == rusqlite::ErrorCode::OperationInterrupted
+            )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_search_interrupts_superseded_one() {
+        let (attached_tx, attached_rx) = std::sync::mpsc::channel();
+        let worker = std::thread::spawn(move || {
+            let conn = Connection::open_in_memory().unwrap();
+            let token = SearchToken::new("test-search");
+            token.attach(&conn).unwrap();
+            attached_tx.send(()).unwrap();
+            // Never finishes on its own
+            let result: anyhow::Result<i64> = conn
+                .query_row("WITH RECURSIVE c(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM c) SELECT count(*) FROM c", [], |r| r.get(0))
+                .map_err(Into::into);
+            is_cancelled(&result.unwrap_err())
+        });
+        attached_rx.recv().unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(50));
+
+        let newer = SearchToken::new("test-search");
+        assert!(worker.join().unwrap());
+        let conn = Connection::open_in_memory().unwrap();
+        newer.attach(&conn).unwrap();
+        drop(newer);
+        assert!(!cancel("test-search"));
+    }
+}
@@ -0,0 +1,204 @@
+use anyhow::Result;
+use rusqlite::{params, Connection};
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+// API keys and OAuth tokens, kept in the OS keychain (Keychain on macOS and iOS, Credential
+// Manager on Windows, Secret Service on Linux) instead of the settings table. Values are cached for
+// the session so the AI paths, which load settings on every request, don't hit the keychain each time.
+// Android has no keychain the keyring crate can use, so there they go to `secrets.json` in the
+// app's private storage, which other apps can't read. The file is not encrypted: anyone with root
+// or a debugger attached to the app can read it. It sits in the no-backup folder so at least it
+// never leaves the phone in an Auto Backup or device transfer.
+
+#[cfg(not(target_os = "android"))]
+const SERVICE: &str = "com.jasonb.journal-reader";
+
+// Settings that are secrets; they never live in the settings table
+pub const SECRET_KEYS: &[&str] = &[
+    "openai_api_key",
+    "google_access_token",
+    "google_refresh_token",
+    "s3_access_key_id",
+    "s3_secret_access_key",
+    "s3_backup_passphrase",
+    "lan_sync_key",
+    "imap_host",
+    "imap_username",
+    "imap_password",
+];
+
+fn cache() -> &'static Mutex<HashMap<String, Option<String>>> {
+    static CACHE: OnceLock<Mutex<HashMap<String, Option<String>>>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+pub fn is_secret(key: &str) -> bool {
+    SECRET_KEYS.contains(&key)
+}
+
+#[cfg(not(target_os = "android"))]
+fn entry(key: &str) -> Result<keyring::Entry> {
+    // Tests never touch the real keychain
+    #[cfg(test)]
+    {
+        static MOCK: std::sync::Once = std::sync::Once::new();
+        MOCK.call_once(|| keyring::set_default_credential_builder(keyring::mock::default_credential_builder()));
+    }
+    Ok(keyring::Entry::new(SERVICE, key)?)
+}
+
+#[cfg(not(target_os = "android"))]
+fn read(key: &str) -> Result<Option<String>> {
+    match entry(key)?.get_password() {
+        Ok(value) => Ok(Some(value)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(anyhow::anyhow!("Could not read {} from the keychain: {}", key, e)),
+    }
+}
+
+#[cfg(not(target_os = "android"))]
+fn write(key: &str, value: &str) -> Result<()> {
+    let entry = entry(key)?;
+    if value.is_empty() {
+        match entry.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(anyhow::anyhow!("Could not remove {} from the keychain: {}", key, e)),
+        }
+    } else {
+        entry
+            .set_password(value)
+            .map_err(|e| anyhow::anyhow!("Could not save {} to the keychain: {}", key, e))
+    }
+}
+
+#[cfg(target_os = "android")]
+static STORE_DIR: OnceLock<std::path::PathBuf> = OnceLock::new();
+
+// Where `secrets.json` lives on Android: the app's no-backup dir (Context.getNoBackupFilesDir),
+// which Auto Backup skips. Earlier versions kept it in `old_dir`, next to the database.
+#[cfg(target_os = "android")]
+pub fn set_store_dir(dir: std::path::PathBuf, old_dir: &std::path::Path) {
+    let path = dir.join("secrets.json");
+    if let Err(e) = std::fs::create_dir_all(&dir) {
+        tracing::warn!("Could not create {}: {}", dir.display(), e);
+    }
+    if !path.exists() && old_dir.join("secrets.json").exists() {
+        if let Err(e) = std::fs::rename(old_dir.join("secrets.json"), &path) {
+            tracing::warn!("Could not move secrets.json out of the backed-up folder: {}", e);
+        }
+    }
+    let _ = STORE_DIR.set(dir);
+}
+
+#[cfg(target_os = "android")]
+fn load_file() -> Result<(std::path::PathBuf, std::collections::BTreeMap<String, String>)> {
+    let path = STORE_DIR.get().map(|d| d.join("secrets.json")).ok_or_else(|| anyhow::anyhow!("Secret storage is not ready"))?;
+    let secrets = match std::fs::read(&path) {
+        Ok(bytes) => serde_json::from_slice(&bytes)?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Default::default(),
+        Err(e) => return Err(e.into()),
+    };
+    Ok((path, secrets))
+}
+
+#[cfg(target_os = "android")]
+fn read(key: &str) -> Result<Option<String>> {
+    Ok(load_file()?.1.remove(key))
+}
+
+#[cfg(target_os = "android")]
+fn write(key: &str, value: &str) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    let (path, mut secrets) = load_file()?;
+    if value.is_empty() {
+        secrets.remove(key);
+    } else {
+        secrets.insert(key.to_string(), value.to_string());
+    }
+    let partial = path.with_extension("json.partial");
+    std::fs::write(&partial, serde_json::to_vec(&secrets)?)?;
+    std::fs::set_permissions(&partial, std::fs::Permissions::from_mode(0o600))?;
+    std::fs::rename(&partial, &path)?;
+    Ok(())
+}
+
+pub fn get(key: &str) -> Result<Option<String>> {
+    if !is_secret(key) {
+        return Err(anyhow::anyhow!("Unknown secret: {}", key));
+    }
+    if let Some(value) = cache().lock().ok().and_then(|c| c.get(key).cloned()) {
+        return Ok(value);
+    }
+    let value = read(key)?;
+    if let Ok(mut c) = cache().lock() {
+        c.insert(key.to_string(), value.clone());
+    }
+    Ok(value)
+}
+
+// Stores a secret; an empty value removes it
+pub fn set(key: &str, value: &str) -> Result<()> {
+    if !is_secret(key) {
+        return Err(anyhow::anyhow!("Unknown secret: {}", key));
+    }
+    let value = value.trim();
+    write(key, value)?;
+    if let Ok(mut c) = cache().lock() {
+        c.insert(key.to_string(), Some(value.to_string()).filter(|v| !v.is_empty()));
+    }
+    Ok(())
+}
+
+pub fn has(key: &str) -> Result<bool> {
+    Ok(get(key)?.is_some())
+}
+
+// Moves secrets left in the settings table by earlier versions into the keychain. A row is only
+// deleted once its value is stored, so a missing keychain leaves it to be tried on the next start.
+pub(crate) fn migrate_settings(conn: &Connection) -> Result<usize> {
+    let mut moved = 0;
+    for key in SECRET_KEYS {
+        let Some(value) = crate::database::read_setting(conn, key) else { continue };
+        if !value.trim().is_empty() && get(key)?.is_none() {
+            set(key, &value)?;
+        }
+        conn.execute("DELETE FROM settings WHERE key = ?1", params![key])?;
+        moved += 1;
+    }
+    if moved > 0 {
+        tracing::info!("moved {} secrets from settings to the keychain", moved);
+    }
+    Ok(moved)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrate_settings_moves_secrets() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            r#"
+            CREATE TABLE settings (key TEXT PRIMARY KEY, value TEXT NOT NULL);
+            INSERT INTO settings (key, value) VALUES
+                ('google_access_token', 'ya29.token'), ('google_refresh_token', ''), ('ollama_url', 'http://nas:11434');
+            "#,
+        )
+        .unwrap();
+        assert_eq!(migrate_settings(&conn).unwrap(), 2);
+        assert_eq!(get("google_access_token").unwrap().as_deref(), Some("ya29.token"));
+        assert!(!has("google_refresh_token").unwrap());
+        let left: Vec<String> = conn
+            .prepare("SELECT key FROM settings")
+            .unwrap()
+            .query_map([], |r| r.get(0))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        assert_eq!(left, ["ollama_url"]);
+        assert_eq!(migrate_settings(&conn).unwrap(), 0);
+        assert!(set("ollama_url", "x").is_err());
+    }
+}
@@ -0,0 +1,323 @@
+use anyhow::{Context, Result};
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::sync::Mutex;
+
+// Sensitive entries: per-entry encryption on top of (or without) database encryption.
+//
+// Marking an entry sensitive seals its body with AES-256-GCM under a key derived from a
+// separate passphrase, stored as `body_sealed` (nonce followed by ciphertext). The plaintext
+// `body` becomes empty, and with it the FTS row (title only), embeddings, chunks, extracted
+// people, sentiment and language. `text_hash` keeps the hash of the real text so re-importing
+// the same file is still recognized as a duplicate.
+//
+// The passphrase's salt and iteration count live in the `sensitive_key_info` setting, next to
+// `sensitive_key_check`, a sealed known value that tells a wrong passphrase from a right one.
+// The derived key is only held in memory between `unlock` and `lock` (or quitting the app).
+
+const CHECK_VALUE: &str = "journal-reader-sensitive";
+
+static KEY: Mutex<Option<[u8; 32]>> = Mutex::new(None);
+
+// Returned while no key is unlocked, so commands can report SENSITIVE_LOCKED
+#[derive(Debug)]
+pub struct Locked;
+
+impl std::fmt::Display for Locked {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Sensitive entries are locked; unlock them with the sensitive passphrase first")
+    }
+}
+
+impl std::error::Error for Locked {}
+
+#[derive(Debug, serde::Serialize)]
+pub struct SensitiveStatus {
+    pub configured: bool,
+    pub unlocked: bool,
+    pub entries: u64,
+}
+
+pub fn seal(key: &[u8; 32], plaintext: &str) -> Result<Vec<u8>> {
+    crate::encryption::seal(key, plaintext.as_bytes())
+}
+
+pub fn open(key: &[u8; 32], sealed: &[u8]) -> Result<String> {
+    String::from_utf8(crate::encryption::open(key, sealed)?).context("Decrypted entry is not valid UTF-8")
+}
+
+fn key_info(conn: &Connection) -> Result<Option<crate::encryption::KeyInfo>> {
+    match crate::database::read_setting(conn, "sensitive_key_info") {
+        Some(json) => Ok(Some(serde_json::from_str(&json).context("Invalid sensitive_key_info setting")?)),
+        None => Ok(None),
+    }
+}
+
+fn write_setting(conn: &Connection, key: &str, value: &str) -> Result<()> {
+    conn.execute(
+        "INSERT INTO settings (key, value) VALUES (?1, ?2) ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+        params![key, value],
+    )?;
+    Ok(())
+}
+
+// Derives the key for `passphrase` and checks it against the stored check value
+fn verified_key(conn: &Connection, passphrase: &str) -> Result<[u8; 32]> {
+    use base64::Engine;
+    let info = key_info(conn)?.context("No sensitive passphrase has been set")?;
+    let key = crate::encryption::derive_raw_key(passphrase, &info)?;
+    let check = crate::database::read_setting(conn, "sensitive_key_check").context("Missing sensitive_key_check setting")?;
+    let check = base64::engine::general_purpose::STANDARD.decode(check).context("Invalid sensitive_key_check setting")?;
+    match open(&key, &check) {
+        Ok(v) if v == CHECK_VALUE => Ok(key),
+        _ => anyhow::bail!("Wrong passphrase"),
+    }
+}
+
+pub(crate) fn current_key() -> Result<[u8; 32]> {
+    KEY.lock().unwrap().ok_or_else(|| Locked.into())
+}
+
+pub fn is_unlocked() -> bool {
+    KEY.lock().unwrap().is_some()
+}
+
+pub fn status(conn: &Connection) -> Result<SensitiveStatus> {
+    let entries: i64 = conn.query_row("SELECT COUNT(*) FROM entries WHERE sensitive = 1", [], |r| r.get(0))?;
+    Ok(SensitiveStatus { configured: key_info(conn)?.is_some(), unlocked: is_unlocked(), entries: entries as u64 })
+}
+
+// Sets the passphrase, or changes it when `current` is the existing one; sealed entries are
+// re-encrypted under the new key in the same transaction. Leaves the new key unlocked.
+pub fn set_passphrase(conn: &Connection, current: Option<&str>, new: &str) -> Result<()> {
+    use base64::Engine;
+    if new.is_empty() {
+        anyhow::bail!("Passphrase must not be empty");
+    }
+    let old_key = match key_info(conn)? {
+        Some(_) => Some(verified_key(conn, current.context("Enter the current sensitive passphrase")?)?),
+        None => None,
+    };
+    let info = crate::encryption::KeyInfo::generate();
+    let key = crate::encryption::derive_raw_key(new, &info)?;
+
+    let tx = conn.unchecked_transaction()?;
+    if let Some(old_key) = old_key {
+        let sealed: Vec<(String, Vec<u8>)> = tx
+            .prepare("SELECT id, body_sealed FROM entries WHERE sensitive = 1 AND body_sealed IS NOT NULL")?
+            .query_map([], |r| Ok((r.get(0)?, r.get(1)?)))?
+            .collect::<rusqlite::Result<_>>()?;
+        for (id, bytes) in sealed {
+            let body = open(&old_key, &bytes)?;
+            tx.execute("UPDATE entries SET body_sealed = ?2 WHERE id = ?1", params![id, seal(&key, &body)?])?;
+        }
+    }
+    write_setting(&tx, "sensitive_key_info", &serde_json::to_string(&info)?)?;
+    let check = seal(&key, CHECK_VALUE)?;
+    write_setting(&tx, "sensitive_key_check", &base64::engine::general_purpose::STANDARD.encode(check))?;
+    tx.commit()?;
+    *KEY.lock().unwrap() = Some(key);
+    Ok(())
+}
+
+pub fn unlock(conn: &Connection, passphrase: &str) -> Result<()> {
+    let key = verified_key(conn, passphrase)?;
+    *KEY.lock().unwrap() = Some(key);
+    Ok(())
+}
+
+pub fn lock() {
+    *KEY.lock().unwrap() = None;
+}
+
+pub fn is_sensitive(conn: &Connection, entry_id: &str) -> Result<bool> {
+    let flag: Option<i64> = conn
+        .query_row("SELECT sensitive FROM entries WHERE id = ?1", params![entry_id], |r| r.get(0))
+        .optional()?;
+    Ok(flag == Some(1))
+}
+
+// Sensitive entries are expected to be a handful, so callers check lists against all of them
+pub fn sensitive_ids(conn: &Connection) -> Result<HashSet<String>> {
+    let mut stmt = conn.prepare_cached("SELECT id FROM entries WHERE sensitive = 1")?;
+    let ids = stmt.query_map([], |r| r.get(0))?.collect::<rusqlite::Result<_>>()?;
+    Ok(ids)
+}
+
+// The plaintext body of a sensitive entry; needs the key unlocked
+pub fn read_body(conn: &Connection, entry_id: &str) -> Result<String> {
+    let key = current_key()?;
+    let sealed: Option<Vec<u8>> = conn
+        .query_row("SELECT body_sealed FROM entries WHERE id = ?1 AND sensitive = 1", params![entry_id], |r| r.get(0))
+        .optional()?
+        .flatten();
+    open(&key, &sealed.context("Entry is not sensitive")?)
+}
+
+fn index_title_only(conn: &Connection, entry_id: &str, title: Option<&str>) -> Result<()> {
+    conn.execute("DELETE FROM entries_fts WHERE entry_id = ?1", params![entry_id])?;
+    conn.execute(
+        "INSERT INTO entries_fts (title, body, entry_id) VALUES (?1, '', ?2)",
+        params![title.unwrap_or_default(), entry_id],
+    )?;
+    Ok(())
+}
+
+// Removes everything derived from the plaintext body
+pub(crate) fn clear_derived(conn: &Connection, entry_id: &str) -> Result<()> {
+    conn.execute(
+        r#"UPDATE entries SET embedding = NULL, embedding_model = NULL, embedding_text_hash = NULL, embedding_dirty = 0,
+                sentiment = NULL, language = NULL, preview = NULL, preview_hash = NULL
+            WHERE id = ?1"#,
+        params![entry_id],
+    )?;
+    conn.execute("DELETE FROM entry_chunks WHERE entry_id = ?1", params![entry_id])?;
+    conn.execute("DELETE FROM entry_entities WHERE entry_id = ?1", params![entry_id])?;
+    conn.execute("DELETE FROM entry_versions WHERE entry_id = ?1", params![entry_id])?;
+    conn.execute("DELETE FROM goal_mentions WHERE entry_id = ?1", params![entry_id])?;
+    conn.execute("DELETE FROM gratitude_items WHERE entry_id = ?1", params![entry_id])?;
+    Ok(())
+}
+
+// Seals the entry's body; a no-op for an entry that is already sensitive
+pub fn mark(conn: &Connection, entry_id: &str) -> Result<()> {
+    let key = current_key()?;
+    let (title, body, sensitive): (Option<String>, String, i64) = conn
+        .query_row("SELECT title, body, sensitive FROM entries WHERE id = ?1", params![entry_id], |r| {
+            Ok((r.get(0)?, r.get(1)?, r.get(2)?))
+        })
+        .optional()?
+        .context("Entry not found")?;
+    if sensitive == 1 {
+        return Ok(());
+    }
+    let tx = conn.unchecked_transaction()?;
+    tx.execute(
+        "UPDATE entries SET sensitive = 1, body_sealed = ?2, body = '' WHERE id = ?1",
+        params![entry_id, seal(&key, &body)?],
+    )?;
+    clear_derived(&tx, entry_id)?;
+    index_title_only(&tx, entry_id, title.as_deref())?;
+    tx.commit()?;
+    crate::vector_index::invalidate_entries([entry_id]);
+    Ok(())
+}
+
+// Restores the plaintext body; it is re-embedded like an edit
+pub fn unmark(conn: &Connection, entry_id: &str) -> Result<()> {
+    let body = read_body(conn, entry_id)?;
+    let title: Option<String> = conn.query_row("SELECT title FROM entries WHERE id = ?1", params![entry_id], |r| r.get(0))?;
+    let tx = conn.unchecked_transaction()?;
+    tx.execute(
+        r#"UPDATE entries SET sensitive = 0, body_sealed = NULL, body = ?2, sentiment = ?3, language = ?4, embedding_dirty = 1
+            WHERE id = ?1"#,
+        params![entry_id, body, crate::sentiment::score(&body), crate::import::detect_language(&body)],
+    )?;
+    // Extract people again from the full text
+    tx.execute("DELETE FROM entity_extractions WHERE entry_id = ?1", params![entry_id])?;
+    tx.execute("DELETE FROM entries_fts WHERE entry_id = ?1", params![entry_id])?;
+    tx.execute(
+        "INSERT INTO entries_fts (title, body, entry_id) VALUES (?1, ?2, ?3)",
+        params![title.unwrap_or_default(), body, entry_id],
+    )?;
+    tx.commit()?;
+    Ok(())
+}
+
+// Saves an edit to a sensitive entry without the body ever reaching the plaintext columns
+pub(crate) fn update_sealed(conn: &Connection, entry_id: &str, title: Option<&str>, body: &str) -> Result<()> {
+    let key = current_key()?;
+    let text_hash = format!("{:x}", Sha256::digest(body.as_bytes()));
+    conn.execute(
+        "UPDATE entries SET title = ?2, body_sealed = ?3, text_hash = ?4, updated_at = ?5 WHERE id = ?1 AND sensitive = 1",
+        params![entry_id, title, seal(&key, body)?, text_hash, Utc::now().to_rfc3339()],
+    )?;
+    index_title_only(conn, entry_id, title)
+}
+
+// Drops sensitive entries from AI context unless `ai_include_sensitive` is on and the key is
+// unlocked, in which case their decrypted text is used
+pub fn prepare_context(conn: &Connection, entries: &mut Vec<crate::ai::ContextEntry>) -> Result<()> {
+    let ids = sensitive_ids(conn)?;
+    if ids.is_empty() {
+        return Ok(());
+    }
+    let include = crate::database::setting_enabled(conn, "ai_include_sensitive", false) && is_unlocked();
+    if !include {
+        entries.retain(|e| !ids.contains(&e.entry_id));
+        return Ok(());
+    }
+    for entry in entries.iter_mut().filter(|e| ids.contains(&e.entry_id)) {
+        entry.body = read_body(conn, &entry.entry_id)?;
+        entry.snippet = entry.body.split_whitespace().take(50).collect::<Vec<_>>().join(" ");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seeded() -> Connection {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::migrations::run_migrations(&mut conn).unwrap();
+        conn.execute_batch(
+            r#"
+            INSERT INTO entries (id, title, body, entry_date, entry_timezone, source_path, source_type, text_hash, created_at, updated_at)
+            VALUES ('a', 'Therapy', 'talked about the divorce', '2024-01-01T00:00:00Z', 'UTC', '', 'txt', 'h1', '2024-01-01T00:00:00Z', '2024-01-01T00:00:00Z');
+            INSERT INTO entries_fts (title, body, entry_id) VALUES ('Therapy', 'talked about the divorce', 'a');
+            "#,
+        )
+        .unwrap();
+        conn
+    }
+
+    fn fts_hits(conn: &Connection, query: &str) -> i64 {
+        conn.query_row("SELECT COUNT(*) FROM entries_fts WHERE entries_fts MATCH ?1", params![query], |r| r.get(0))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_seal_round_trip() {
+        let key = [7u8; 32];
+        let sealed = seal(&key, "secret text").unwrap();
+        assert!(!sealed.windows(6).any(|w| w == b"secret"));
+        assert_eq!(open(&key, &sealed).unwrap(), "secret text");
+        assert!(open(&[8u8; 32], &sealed).is_err());
+    }
+
+    // One test so the process-wide key isn't shared between parallel tests
+    #[test]
+    fn test_mark_unlock_and_unmark() {
+        let conn = seeded();
+        lock();
+        assert!(mark(&conn, "a").unwrap_err().downcast_ref::<Locked>().is_some());
+
+        set_passphrase(&conn, None, "hunter2").unwrap();
+        mark(&conn, "a").unwrap();
+        let body: String = conn.query_row("SELECT body FROM entries WHERE id = 'a'", [], |r| r.get(0)).unwrap();
+        assert_eq!(body, "");
+        assert_eq!(fts_hits(&conn, "divorce"), 0);
+        assert_eq!(fts_hits(&conn, "therapy"), 1);
+
+        lock();
+        assert!(read_body(&conn, "a").is_err());
+        assert!(unlock(&conn, "wrong").is_err());
+        unlock(&conn, "hunter2").unwrap();
+        assert_eq!(read_body(&conn, "a").unwrap(), "talked about the divorce");
+
+        // Changing the passphrase re-seals the entry
+        assert!(set_passphrase(&conn, Some("wrong"), "correct horse").is_err());
+        set_passphrase(&conn, Some("hunter2"), "correct horse").unwrap();
+        lock();
+        unlock(&conn, "correct horse").unwrap();
+        assert_eq!(read_body(&conn, "a").unwrap(), "talked about the divorce");
+
+        unmark(&conn, "a").unwrap();
+        assert!(!is_sensitive(&conn, "a").unwrap());
+        assert_eq!(fts_hits(&conn, "divorce"), 1);
+        lock();
+    }
+}
@@ -0,0 +1,235 @@
+use anyhow::Result;
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tauri::AppHandle;
+
+use crate::database::EntryFilter;
+use crate::locale::DateFormat;
+use crate::summaries::Granularity;
+
+// Entry sentiment and the mood timeline built from it.
+//
+// Scores come from a small word list rather than a model so every entry gets one offline at
+// import time: matched words add or subtract, a negation within the two preceding words flips
+// the word, and the sum is squashed into -1..1. Entries saved before scoring existed are
+// scored on first use of the timeline.
+
+const POSITIVE: &[&str] = &[
+    "amazing", "awesome", "beautiful", "best", "better", "blessed", "calm", "celebrate", "celebrated", "cheerful",
+    "comfortable", "confident", "content", "delighted", "delicious", "easy", "enjoy", "enjoyed", "excited",
+    "exciting", "fantastic", "fun", "glad", "good", "grateful", "great", "happy", "healthy", "hope", "hopeful",
+    "inspired", "joy", "kind", "laugh", "laughed", "love", "loved", "lovely", "lucky", "nice", "peaceful",
+    "perfect", "pleased", "proud", "relaxed", "relieved", "rested", "safe", "satisfied", "success", "successful",
+    "thankful", "thrilled", "warm", "win", "wonderful",
+];
+
+const NEGATIVE: &[&str] = &[
+    "afraid", "alone", "angry", "annoyed", "anxious", "argument", "ashamed", "awful", "bad", "bored", "broke",
+    "cried", "cry", "depressed", "difficult", "disappointed", "exhausted", "fail", "failed", "fear", "frustrated",
+    "guilty", "hard", "hate", "hated", "hurt", "ill", "irritated", "lonely", "lost", "miserable", "miss",
+    "nervous", "overwhelmed", "pain", "panic", "regret", "sad", "scared", "sick", "sorry", "stress", "stressed",
+    "terrible", "tired", "upset", "worried", "worry", "worse", "worst",
+];
+
+const NEGATIONS: &[&str] = &["not", "no", "never", "hardly", "without", "nothing"];
+
+// Notable entries returned at each end of the scale
+const NOTABLE_ENTRIES: usize = 5;
+const PREVIEW_CHARS: usize = 160;
+
+// Sentiment of a text between -1 (negative) and 1 (positive); 0 when nothing matched
+pub fn score(text: &str) -> f32 {
+    let words: Vec<String> = text
+        .split(|c: char| !c.is_alphanumeric() && c != '\'')
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_lowercase())
+        .collect();
+    let mut total = 0.0f32;
+    for (i, word) in words.iter().enumerate() {
+        let value = if POSITIVE.contains(&word.as_str()) {
+            1.0
+        } else if NEGATIVE.contains(&word.as_str()) {
+            -1.0
+        } else {
+            continue;
+        };
+        let negated = words[i.saturating_sub(2)..i]
+            .iter()
+            .any(|w| NEGATIONS.contains(&w.as_str()) || w.ends_with("n't"));
+        total += if negated { -value } else { value };
+    }
+    // Same normalization VADER uses, so a few strong words don't saturate at once
+    total / (total * total + 15.0).sqrt()
+}
+
+// Scores entries that have none yet; returns how many were updated
+pub(crate) fn backfill(conn: &mut Connection) -> Result<usize> {
+    let pending: Vec<(String, String)> = {
+        let mut stmt = conn.prepare("SELECT id, body FROM entries WHERE sentiment IS NULL")?;
+        let rows = stmt.query_map([], |r| Ok((r.get(0)?, r.get(1)?)))?;
+        rows.collect::<rusqlite::Result<_>>()?
+    };
+    if pending.is_empty() {
+        return Ok(0);
+    }
+    let tx = conn.transaction()?;
+    {
+        let mut update = tx.prepare("UPDATE entries SET sentiment = ?1 WHERE id = ?2")?;
+        for (id, body) in &pending {
+            update.execute(params![score(body), id])?;
+        }
+    }
+    tx.commit()?;
+    tracing::info!("scored {} entries", pending.len());
+    Ok(pending.len())
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SentimentPoint {
+    // First day of the period (weeks start on Monday), YYYY-MM-DD
+    pub period_start: String,
+    pub average: f32,
+    pub entry_count: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SentimentEntry {
+    pub id: String,
+    pub title: Option<String>,
+    pub entry_date: String,
+    pub sentiment: f32,
+    pub preview: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SentimentTimeline {
+    pub granularity: Granularity,
+    pub points: Vec<SentimentPoint>,
+    pub highs: Vec<SentimentEntry>,
+    pub lows: Vec<SentimentEntry>,
+}
+
+fn period_start(day: NaiveDate, granularity: Granularity, dates: &DateFormat) -> NaiveDate {
+    match granularity {
+        Granularity::Day => day,
+        Granularity::Week => dates.week_start_of(day),
+        Granularity::Month => day.with_day(1).unwrap_or(day),
+    }
+}
+
+fn preview(body: &str) -> String {
+    let text = body.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.chars().count() <= PREVIEW_CHARS {
+        return text;
+    }
+    format!("{}...", text.chars().take(PREVIEW_CHARS).collect::<String>().trim_end())
+}
+
+pub(crate) fn timeline(conn: &Connection, granularity: Granularity, filter: &EntryFilter) -> Result<SentimentTimeline> {
+    let (conditions, values) = filter.to_sql(1)?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT e.id, e.title, e.entry_date, e.sentiment, e.body FROM entries e WHERE e.sentiment IS NOT NULL{} ORDER BY e.entry_date",
+        conditions
+    ))?;
+    let rows = stmt.query_map(rusqlite::params_from_iter(values), |r| {
+        Ok(SentimentEntry {
+            id: r.get(0)?,
+            title: r.get(1)?,
+            entry_date: r.get(2)?,
+            sentiment: r.get(3)?,
+            preview: preview(&r.get::<_, String>(4)?),
+        })
+    })?;
+    let entries: Vec<SentimentEntry> = rows.collect::<rusqlite::Result<_>>()?;
+
+    let dates = DateFormat::from_conn(conn);
+    let mut buckets: BTreeMap<NaiveDate, (f32, u32)> = BTreeMap::new();
+    for entry in &entries {
+        let Ok(date) = DateTime::parse_from_rfc3339(&entry.entry_date) else { continue };
+        let bucket = buckets.entry(period_start(date.with_timezone(&Utc).date_naive(), granularity, &dates)).or_insert((0.0, 0));
+        bucket.0 += entry.sentiment;
+        bucket.1 += 1;
+    }
+    let points = buckets
+        .into_iter()
+        .map(|(start, (sum, count))| SentimentPoint { period_start: start.to_string(), average: sum / count as f32, entry_count: count })
+        .collect();
+
+    // Neutral entries (nothing matched) are neither highs nor lows
+    let mut ranked: Vec<SentimentEntry> = entries.into_iter().filter(|e| e.sentiment != 0.0).collect();
+    ranked.sort_by(|a, b| b.sentiment.total_cmp(&a.sentiment));
+    let highs_end = ranked.iter().take(NOTABLE_ENTRIES).take_while(|e| e.sentiment > 0.0).count();
+    let mut lows: Vec<SentimentEntry> = ranked.split_off(highs_end);
+    lows.reverse();
+    lows.retain(|e| e.sentiment < 0.0);
+    lows.truncate(NOTABLE_ENTRIES);
+
+    Ok(SentimentTimeline { granularity, points, highs: ranked, lows })
+}
+
+pub async fn get_sentiment_timeline(
+    app_handle: &AppHandle,
+    granularity: Granularity,
+    date_range: Option<(String, String)>,
+) -> Result<SentimentTimeline> {
+    let mut conn = crate::database::open_conn(app_handle)?;
+    backfill(&mut conn)?;
+    let (date_from, date_to) = date_range.map_or((None, None), |(from, to)| (Some(from), Some(to)));
+    timeline(&conn, granularity, &EntryFilter { date_from, date_to, ..Default::default() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_score_with_negation() {
+        assert!(score("What a wonderful, happy day with friends") > 0.3);
+        assert!(score("I was so tired and sad, everything felt awful") < -0.3);
+        assert!(score("I'm not happy about it") < 0.0);
+        assert!(score("didn't feel sad at all") > 0.0);
+        assert_eq!(score("Went to the store"), 0.0);
+        assert!(score(&"great ".repeat(50)) < 1.0);
+    }
+
+    #[test]
+    fn test_timeline_buckets_and_notable_entries() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::migrations::run_migrations(&mut conn).unwrap();
+        let entries = [
+            ("a", "2024-05-06T08:00:00+00:00", "A wonderful happy day"),
+            ("b", "2024-05-08T08:00:00+00:00", "Tired and sad"),
+            ("c", "2024-05-13T08:00:00+00:00", "Grateful and calm"),
+            ("d", "2024-06-01T08:00:00+00:00", "Went to the store"),
+        ];
+        for (id, date, body) in entries {
+            conn.execute(
+                r#"INSERT INTO entries (id, body, entry_date, entry_timezone, source_path, source_type, text_hash, created_at, updated_at)
+                    VALUES (?1, ?2, ?3, 'UTC', '', 'txt', ?1, ?3, ?3)"#,
+                params![id, body, date],
+            )
+            .unwrap();
+        }
+        assert_eq!(backfill(&mut conn).unwrap(), 4);
+        assert_eq!(backfill(&mut conn).unwrap(), 0);
+
+        let weekly = timeline(&conn, Granularity::Week, &EntryFilter::default()).unwrap();
+        let starts: Vec<&str> = weekly.points.iter().map(|p| p.period_start.as_str()).collect();
+        assert_eq!(starts, ["2024-05-06", "2024-05-13", "2024-05-27"]);
+        assert_eq!(weekly.points[0].entry_count, 2);
+        assert_eq!(weekly.highs.iter().map(|e| e.id.as_str()).collect::<Vec<_>>(), ["a", "c"]);
+        assert_eq!(weekly.lows.iter().map(|e| e.id.as_str()).collect::<Vec<_>>(), ["b"]);
+        conn.execute("INSERT INTO settings (key, value) VALUES ('week_start', 'sunday')", []).unwrap();
+        let weekly = timeline(&conn, Granularity::Week, &EntryFilter::default()).unwrap();
+        let starts: Vec<&str> = weekly.points.iter().map(|p| p.period_start.as_str()).collect();
+        assert_eq!(starts, ["2024-05-05", "2024-05-12", "2024-05-26"]);
+
+        let filter = EntryFilter { date_from: Some("2024-06-01".into()), date_to: Some("2024-06-30".into()), ..Default::default() };
+        let monthly = timeline(&conn, Granularity::Month, &filter).unwrap();
+        assert_eq!(monthly.points.len(), 1);
+        assert_eq!(monthly.points[0].period_start, "2024-06-01");
+        assert!(monthly.highs.is_empty() && monthly.lows.is_empty());
+    }
+}
@@ -0,0 +1,208 @@
+use std::collections::BTreeMap;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::app_import::{AppEntry, AppImportReport};
+use crate::import::FileType;
+
+// Import from SMS Backup & Restore's XML backups (`<smses>` with `<sms>` and `<mms>` elements).
+//
+// Messages are grouped into one entry per conversation and day (in the import's time zone), one
+// line per message: "14:05 Me: ..." or "14:07 Jane: ...". `contact` narrows the import to a
+// single thread, matched on the number's last ten digits or on the contact name. For texts sent
+// to yourself the backup holds every message twice, a sent and a received copy; `sent_only`
+// keeps just the sent side, which also drops the sender prefix. `list_contacts` lists the threads
+// in a backup so one can be picked.
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SmsOptions {
+    pub contact: Option<String>,
+    #[serde(default)]
+    pub sent_only: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SmsContact {
+    pub address: String,
+    pub name: Option<String>,
+    pub messages: u32,
+}
+
+#[derive(Debug, Clone)]
+struct Message {
+    address: String,
+    name: Option<String>,
+    sent: bool,
+    at: DateTime<Utc>,
+    body: String,
+}
+
+// Digits only, and only the last ten, so "+1 (555) 123-4567" matches "5551234567"
+fn number_key(address: &str) -> String {
+    let digits: String = address.chars().filter(char::is_ascii_digit).collect();
+    digits[digits.len().saturating_sub(10)..].to_string()
+}
+
+fn contact_name(node: &roxmltree::Node) -> Option<String> {
+    node.attribute("contact_name").map(str::trim).filter(|n| !n.is_empty() && *n != "(Unknown)").map(str::to_string)
+}
+
+fn messages(xml: &str) -> Result<Vec<Message>> {
+    let doc = roxmltree::Document::parse(xml).context("Not a readable SMS backup")?;
+    if !doc.root_element().has_tag_name("smses") {
+        anyhow::bail!("Not an SMS Backup & Restore file (expected an <smses> root element)");
+    }
+    let mut messages = Vec::new();
+    for node in doc.root_element().children().filter(|n| n.is_element()) {
+        let millis = node.attribute("date").and_then(|d| d.parse::<i64>().ok());
+        let Some(at) = millis.and_then(DateTime::from_timestamp_millis) else { continue };
+        let address = node.attribute("address").unwrap_or("").to_string();
+        let (sent, body) = if node.has_tag_name("sms") {
+            // type 2 is sent, 1 received; drafts and the like are left out
+            match node.attribute("type") {
+                Some("1") => (false, node.attribute("body").unwrap_or("").to_string()),
+                Some("2") => (true, node.attribute("body").unwrap_or("").to_string()),
+                _ => continue,
+            }
+        } else if node.has_tag_name("mms") {
+            let text: Vec<&str> = node
+                .descendants()
+                .filter(|p| p.has_tag_name("part") && p.attribute("ct") == Some("text/plain"))
+                .filter_map(|p| p.attribute("text"))
+                .collect();
+            (node.attribute("msg_box") == Some("2"), text.join("\n"))
+        } else {
+            continue;
+        };
+        if body.trim().is_empty() {
+            continue;
+        }
+        messages.push(Message { name: contact_name(&node), address, sent, at, body: body.trim().to_string() });
+    }
+    messages.sort_by_key(|m| m.at);
+    Ok(messages)
+}
+
+pub fn list_contacts(xml: &str) -> Result<Vec<SmsContact>> {
+    let mut threads: BTreeMap<String, SmsContact> = BTreeMap::new();
+    for message in messages(xml)? {
+        let contact = threads
+            .entry(number_key(&message.address))
+            .or_insert_with(|| SmsContact { address: message.address.clone(), name: None, messages: 0 });
+        contact.messages += 1;
+        if contact.name.is_none() {
+            contact.name = message.name;
+        }
+    }
+    let mut contacts: Vec<SmsContact> = threads.into_values().collect();
+    contacts.sort_by_key(|c| std::cmp::Reverse(c.messages));
+    Ok(contacts)
+}
+
+fn matches(message: &Message, contact: &str) -> bool {
+    let key = number_key(contact);
+    (!key.is_empty() && number_key(&message.address) == key)
+        || message.name.as_deref().is_some_and(|n| n.eq_ignore_ascii_case(contact.trim()))
+}
+
+pub fn parse_backup(xml: &str, source: &str, timezone: &str, options: &SmsOptions) -> Result<Vec<AppEntry>> {
+    let tz: Option<chrono_tz::Tz> = timezone.parse().ok();
+    let offset: Option<chrono::FixedOffset> = timezone.parse().ok();
+    let local = |at: DateTime<Utc>| match (tz, offset) {
+        (Some(tz), _) => at.with_timezone(&tz).naive_local(),
+        (None, Some(offset)) => at.with_timezone(&offset).naive_local(),
+        _ => at.naive_utc(),
+    };
+
+    // (thread, day) -> messages, in time order
+    let mut days: BTreeMap<(String, NaiveDate), Vec<Message>> = BTreeMap::new();
+    for message in messages(xml)? {
+        if options.sent_only && !message.sent {
+            continue;
+        }
+        if let Some(contact) = options.contact.as_deref().filter(|c| !c.trim().is_empty()) {
+            if !matches(&message, contact) {
+                continue;
+            }
+        }
+        days.entry((number_key(&message.address), local(message.at).date())).or_default().push(message);
+    }
+
+    let mut entries = Vec::new();
+    for ((thread, day), messages) in days {
+        let name = messages.iter().find_map(|m| m.name.clone()).unwrap_or_else(|| messages[0].address.clone());
+        let lines: Vec<String> = messages
+            .iter()
+            .map(|m| {
+                let time = local(m.at).format("%H:%M");
+                match (options.sent_only, m.sent) {
+                    (true, _) => format!("{} {}", time, m.body),
+                    (false, true) => format!("{} Me: {}", time, m.body),
+                    (false, false) => format!("{} {}: {}", time, name, m.body),
+                }
+            })
+            .collect();
+        entries.push(AppEntry {
+            source: format!("{}#{}/{}", source, thread, day),
+            title: Some(format!("Texts with {}", name)),
+            body: lines.join("\n"),
+            tags: vec!["sms".to_string()],
+            created: messages[0].at,
+            timezone: timezone.to_string(),
+            sentiment: None,
+        });
+    }
+    Ok(entries)
+}
+
+pub async fn import_backup(app_handle: &AppHandle, paths: &[String], timezone: &str, options: &SmsOptions) -> Result<AppImportReport> {
+    let mut report = AppImportReport::default();
+    for path in paths {
+        let source = std::path::Path::new(path).file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_else(|| path.clone());
+        let parsed = std::fs::read_to_string(path).map_err(anyhow::Error::from).and_then(|xml| parse_backup(&xml, &source, timezone, options));
+        match parsed {
+            Ok(entries) => crate::app_import::save_all(app_handle, entries, FileType::Sms, &mut report).await?,
+            Err(e) => report.error(format!("{}: {}", path, e)),
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BACKUP: &str = r#"<?xml version='1.0' encoding='UTF-8' standalone='yes' ?>
+<smses count="5">
+  <sms protocol="0" address="+1 (555) 123-4567" date="1365705900000" type="2" body="Remember: call mom" contact_name="Me" />
+  <sms protocol="0" address="5551234567" date="1365705901000" type="1" body="Remember: call mom" contact_name="Me" />
+  <sms protocol="0" address="+15559876543" date="1365706200000" type="1" body="Dinner at 7?" contact_name="Jane" />
+  <sms protocol="0" address="+15559876543" date="1365706260000" type="2" body="Yes!" contact_name="Jane" />
+  <mms date="1365792600000" address="5551234567" msg_box="2" contact_name="Me">
+    <parts><part ct="image/jpeg" data="..." /><part ct="text/plain" text="Parked on level 3" /></parts>
+  </mms>
+</smses>"#;
+
+    #[test]
+    fn test_parse_backup_groups_by_day() {
+        let all = parse_backup(BACKUP, "sms.xml", "America/New_York", &SmsOptions::default()).unwrap();
+        assert_eq!(all.len(), 3);
+        let jane = all.iter().find(|e| e.title.as_deref() == Some("Texts with Jane")).unwrap();
+        assert_eq!(jane.body, "14:50 Jane: Dinner at 7?\n14:51 Me: Yes!");
+
+        let options = SmsOptions { contact: Some("555-123-4567".to_string()), sent_only: true };
+        let myself = parse_backup(BACKUP, "sms.xml", "America/New_York", &options).unwrap();
+        assert_eq!(myself.len(), 2);
+        assert_eq!(myself[0].body, "14:45 Remember: call mom");
+        assert_eq!(myself[0].created.to_rfc3339(), "2013-04-11T18:45:00+00:00");
+        assert_eq!(myself[1].body, "14:50 Parked on level 3");
+        assert_eq!(myself[1].tags, ["sms"]);
+
+        let contacts = list_contacts(BACKUP).unwrap();
+        assert_eq!(contacts.len(), 2);
+        assert_eq!((contacts[0].name.as_deref(), contacts[0].messages), (Some("Me"), 3));
+    }
+}
@@ -0,0 +1,583 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use chrono::NaiveDate;
+use std::collections::BTreeMap;
+use tauri::AppHandle;
+
+use crate::locale::DateFormat;
+
+// Aggregate statistics over the whole journal. Word counts need the entry bodies, so these
+// scan `entries` once in Rust rather than trying to approximate in SQL.
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct JournalStatistics {
+    pub total_entries: u32,
+    pub total_words: u64,
+    pub average_words_per_entry: f64,
+    pub longest_entry: Option<LongestEntry>,
+    pub busiest_month: Option<BusiestMonth>,
+    pub years: Vec<YearTotals>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LongestEntry {
+    pub id: String,
+    pub title: Option<String>,
+    pub entry_date: String,
+    pub word_count: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BusiestMonth {
+    pub year: i32,
+    pub month: u32,
+    pub count: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct YearTotals {
+    pub year: i32,
+    pub entries: u32,
+    pub words: u64,
+}
+
+pub fn word_count(text: &str) -> u64 {
+    text.split_whitespace().count() as u64
+}
+
+// entry_date is stored as RFC3339, so the year and month are fixed-position prefixes
+pub fn year_month_of(entry_date: &str) -> Option<(i32, u32)> {
+    let year = entry_date.get(0..4)?.parse::<i32>().ok()?;
+    let month = entry_date.get(5..7)?.parse::<u32>().ok()?;
+    Some((year, month))
+}
+
+pub async fn get_journal_statistics(app_handle: &AppHandle) -> Result<JournalStatistics> {
+    let db_path = crate::database::get_db_file_path(app_handle)?;
+    tokio::task::spawn_blocking(move || -> Result<JournalStatistics> {
+        let conn = crate::database::open_conn_at(&db_path)?;
+        let mut stmt = conn.prepare("SELECT id, title, entry_date, body FROM entries")?;
+        let mut rows = stmt.query([])?;
+
+        let mut total_entries = 0u32;
+        let mut total_words = 0u64;
+        let mut longest: Option<LongestEntry> = None;
+        let mut per_month: BTreeMap<(i32, u32), u32> = BTreeMap::new();
+        let mut per_year: BTreeMap<i32, YearTotals> = BTreeMap::new();
+
+        while let Some(row) = rows.next()? {
+            let entry_date: String = row.get(2)?;
+            let body: String = row.get(3)?;
+            let words = word_count(&body);
+
+            total_entries += 1;
+            total_words += words;
+
+            if longest.as_ref().map(|l| words > l.word_count).unwrap_or(true) {
+                longest = Some(LongestEntry {
+                    id: row.get(0)?,
+                    title: row.get(1)?,
+                    entry_date: entry_date.clone(),
+                    word_count: words,
+                });
+            }
+
+            if let Some((year, month)) = year_month_of(&entry_date) {
+                *per_month.entry((year, month)).or_insert(0) += 1;
+                let totals = per_year.entry(year).or_insert(YearTotals { year, entries: 0, words: 0 });
+                totals.entries += 1;
+                totals.words += words;
+            }
+        }
+
+        // Ties go to the earliest month
+        let busiest_month = per_month
+            .iter()
+            .fold(None::<BusiestMonth>, |best, (&(year, month), &count)| match best {
+                Some(b) if b.count >= count => Some(b),
+                _ => Some(BusiestMonth { year, month, count }),
+            });
+
+        let average_words_per_entry = if total_entries > 0 {
+            total_words as f64 / total_entries as f64
+        } else {
+            0.0
+        };
+
+        Ok(JournalStatistics {
+            total_entries,
+            total_words,
+            average_words_per_entry,
+            longest_entry: longest,
+            busiest_month,
+            years: per_year.into_values().rev().collect(),
+        })
+    })
+    .await
+    .map_err(|e| anyhow::anyhow!(e.to_string()))?
+}
+
+// Streaks count calendar days with at least one entry (by the stored UTC entry date). The
+// current streak ends today, or yesterday when nothing has been written yet today.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WritingStreaks {
+    pub current_streak: u32,
+    pub longest_streak: u32,
+    pub longest_streak_start: Option<String>,
+    pub longest_streak_end: Option<String>,
+    pub last_entry_date: Option<String>,
+    pub wrote_today: bool,
+    // Oldest first, only months with entries
+    pub months: Vec<MonthFrequency>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MonthFrequency {
+    pub year: i32,
+    pub month: u32,
+    pub entries: u32,
+    pub days_written: u32,
+}
+
+// `days` maps each day with entries to its entry count
+pub(crate) fn compute_streaks(days: &BTreeMap<NaiveDate, u32>, today: NaiveDate) -> WritingStreaks {
+    let mut longest: Option<(NaiveDate, NaiveDate)> = None;
+    let mut run: Option<(NaiveDate, NaiveDate)> = None;
+    for &day in days.keys() {
+        run = match run {
+            Some((start, end)) if end.succ_opt() == Some(day) => Some((start, day)),
+            _ => Some((day, day)),
+        };
+        let (start, end) = run.expect("run was just set");
+        if longest.map(|(s, e)| (end - start) > (e - s)).unwrap_or(true) {
+            longest = Some((start, end));
+        }
+    }
+    let length = |(start, end): (NaiveDate, NaiveDate)| (end - start).num_days() as u32 + 1;
+
+    let wrote_today = days.contains_key(&today);
+    let current_streak = match run {
+        Some((start, end)) if end == today || end.succ_opt() == Some(today) => length((start, end)),
+        _ => 0,
+    };
+
+    let mut months: Vec<MonthFrequency> = Vec::new();
+    for (day, &entries) in days {
+        use chrono::Datelike;
+        match months.last_mut() {
+            Some(m) if (m.year, m.month) == (day.year(), day.month()) => {
+                m.entries += entries;
+                m.days_written += 1;
+            }
+            _ => months.push(MonthFrequency { year: day.year(), month: day.month(), entries, days_written: 1 }),
+        }
+    }
+    WritingStreaks {
+        current_streak,
+        longest_streak: longest.map(length).unwrap_or(0),
+        longest_streak_start: longest.map(|(s, _)| s.to_string()),
+        longest_streak_end: longest.map(|(_, e)| e.to_string()),
+        last_entry_date: days.keys().next_back().map(|d| d.to_string()),
+        wrote_today,
+        months,
+    }
+}
+
+pub async fn get_writing_streaks(app_handle: &AppHandle) -> Result<WritingStreaks> {
+    let conn = crate::database::open_conn(app_handle)?;
+    let mut stmt = conn.prepare("SELECT substr(entry_date, 1, 10), COUNT(*) FROM entries GROUP BY 1")?;
+    let mut days = BTreeMap::new();
+    for row in stmt.query_map([], |r| Ok((r.get::<_, String>(0)?, r.get::<_, u32>(1)?)))? {
+        let (day, count) = row?;
+        if let Ok(date) = NaiveDate::parse_from_str(&day, "%Y-%m-%d") {
+            days.insert(date, count);
+        }
+    }
+    Ok(compute_streaks(&days, chrono::Local::now().date_naive()))
+}
+
+// When entries are written: counts and average length by weekday, hour of day and part of day,
+// in the entry's own timezone. Imports that only know the day store it at noon, so entries at
+// exactly 12:00:00 local time count toward weekdays but not hours or parts of the day.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WritingTimes {
+    pub total_entries: u32,
+    // Entries with a placeholder (noon) time
+    pub untimed_entries: u32,
+    // From the first day of the week, named in the `locale` setting's language
+    pub weekdays: Vec<TimeBucket>,
+    // 0-23
+    pub hours: Vec<TimeBucket>,
+    // Morning (5-12), afternoon (12-17), evening (17-22), night (22-5)
+    pub periods: Vec<TimeBucket>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimeBucket {
+    pub label: String,
+    pub entries: u32,
+    pub words: u64,
+    pub average_words: f64,
+}
+
+impl TimeBucket {
+    fn new(label: impl Into<String>) -> Self {
+        TimeBucket { label: label.into(), entries: 0, words: 0, average_words: 0.0 }
+    }
+
+    fn add(&mut self, words: u64) {
+        self.entries += 1;
+        self.words += words;
+        self.average_words = self.words as f64 / self.entries as f64;
+    }
+}
+
+const PERIODS: [&str; 4] = ["Morning", "Afternoon", "Evening", "Night"];
+
+// entry_timezone is free text: an IANA name ("Europe/Berlin"), "UTC" or an offset ("+02:00")
+pub(crate) fn local_time(entry_date: &str, timezone: &str) -> Option<chrono::NaiveDateTime> {
+    let utc = chrono::DateTime::parse_from_rfc3339(entry_date).ok()?.with_timezone(&chrono::Utc);
+    let timezone = timezone.trim();
+    if let Ok(tz) = timezone.parse::<chrono_tz::Tz>() {
+        return Some(utc.with_timezone(&tz).naive_local());
+    }
+    if let Ok(offset) = timezone.parse::<chrono::FixedOffset>() {
+        return Some(utc.with_timezone(&offset).naive_local());
+    }
+    Some(utc.naive_utc())
+}
+
+fn period_of(hour: u32) -> usize {
+    match hour {
+        5..=11 => 0,
+        12..=16 => 1,
+        17..=21 => 2,
+        _ => 3,
+    }
+}
+
+pub(crate) fn writing_times<'a>(entries: impl IntoIterator<Item = (&'a str, &'a str, u64)>, dates: &DateFormat) -> WritingTimes {
+    use chrono::{Datelike, Timelike};
+    let mut times = WritingTimes {
+        total_entries: 0,
+        untimed_entries: 0,
+        weekdays: dates.weekdays().map(|d| TimeBucket::new(dates.weekday_name(d))).collect(),
+        hours: (0..24).map(|h| TimeBucket::new(format!("{:02}:00", h))).collect(),
+        periods: PERIODS.iter().map(|p| TimeBucket::new(*p)).collect(),
+    };
+    for (entry_date, timezone, words) in entries {
+        let Some(local) = local_time(entry_date, timezone) else { continue };
+        times.total_entries += 1;
+        times.weekdays[local.weekday().days_since(dates.week_start) as usize].add(words);
+        if local.time() == chrono::NaiveTime::from_hms_opt(12, 0, 0).expect("valid time") {
+            times.untimed_entries += 1;
+            continue;
+        }
+        times.hours[local.hour() as usize].add(words);
+        times.periods[period_of(local.hour())].add(words);
+    }
+    times
+}
+
+pub async fn get_writing_times(app_handle: &AppHandle) -> Result<WritingTimes> {
+    let db_path = crate::database::get_db_file_path(app_handle)?;
+    tokio::task::spawn_blocking(move || -> Result<WritingTimes> {
+        let conn = crate::database::open_conn_at(&db_path)?;
+        let mut stmt = conn.prepare("SELECT entry_date, entry_timezone, body FROM entries")?;
+        let rows = stmt
+            .query_map([], |r| Ok((r.get::<_, String>(0)?, r.get::<_, String>(1)?, word_count(&r.get::<_, String>(2)?))))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(writing_times(rows.iter().map(|(date, tz, words)| (date.as_str(), tz.as_str(), *words)), &DateFormat::from_conn(&conn)))
+    })
+    .await
+    .map_err(|e| anyhow::anyhow!(e.to_string()))?
+}
+
+// How often tags appear per month or year. Periods run continuously from the first to the last
+// one with any of the tags, with zeros in between, so the series can be charted directly.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TagTrends {
+    pub granularity: String,
+    // "YYYY-MM" or "YYYY", oldest first
+    pub periods: Vec<String>,
+    pub series: Vec<TagSeries>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TagSeries {
+    pub tag: String,
+    // One per period
+    pub counts: Vec<u32>,
+    pub total: u32,
+}
+
+const DEFAULT_TREND_TAGS: u32 = 5;
+
+// Every period from `first` to `last` ("YYYY-MM" or "YYYY"), inclusive
+fn period_range(first: &str, last: &str, monthly: bool) -> Vec<String> {
+    let parse = |p: &str| -> Option<(i32, u32)> {
+        let year = p.get(0..4)?.parse().ok()?;
+        let month = if monthly { p.get(5..7)?.parse().ok()? } else { 1 };
+        Some((year, month))
+    };
+    let (Some((mut year, mut month)), Some(end)) = (parse(first), parse(last)) else {
+        return Vec::new();
+    };
+    let mut periods = Vec::new();
+    while (year, month) <= end {
+        if monthly {
+            periods.push(format!("{:04}-{:02}", year, month));
+            (year, month) = if month == 12 { (year + 1, 1) } else { (year, month + 1) };
+        } else {
+            periods.push(format!("{:04}", year));
+            year += 1;
+        }
+    }
+    periods
+}
+
+// Tags are matched case-insensitively; with no tags, the most used ones are shown
+pub(crate) fn tag_trends(conn: &rusqlite::Connection, tags: &[String], granularity: &str) -> Result<TagTrends> {
+    let monthly = match granularity {
+        "month" => true,
+        "year" => false,
+        other => anyhow::bail!("Unknown granularity: {} (use month or year)", other),
+    };
+    let mut tags: Vec<String> = tags.iter().map(|t| t.trim().to_lowercase()).filter(|t| !t.is_empty()).collect();
+    let mut seen = std::collections::HashSet::new();
+    tags.retain(|t| seen.insert(t.clone()));
+    if tags.is_empty() {
+        let mut stmt = conn.prepare("SELECT lower(tag) FROM entry_tags GROUP BY lower(tag) ORDER BY COUNT(*) DESC, lower(tag) LIMIT ?1")?;
+        tags = stmt.query_map([DEFAULT_TREND_TAGS], |r| r.get(0))?.collect::<rusqlite::Result<_>>()?;
+    }
+
+    let prefix = if monthly { 7 } else { 4 };
+    let placeholders = (0..tags.len()).map(|i| format!("?{}", i + 1)).collect::<Vec<_>>().join(", ");
+    let mut stmt = conn.prepare(&format!(
+        "SELECT lower(t.tag), substr(e.entry_date, 1, {}), COUNT(*) FROM entry_tags t JOIN entries e ON e.id = t.entry_id
+         WHERE lower(t.tag) IN ({}) GROUP BY 1, 2",
+        prefix, placeholders
+    ))?;
+    let mut counts: BTreeMap<(String, String), u32> = BTreeMap::new();
+    for row in stmt.query_map(rusqlite::params_from_iter(tags.iter()), |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)))? {
+        let (tag, period, count): (String, String, u32) = row?;
+        counts.insert((tag, period), count);
+    }
+
+    let first = counts.keys().map(|(_, p)| p).min().cloned().unwrap_or_default();
+    let last = counts.keys().map(|(_, p)| p).max().cloned().unwrap_or_default();
+    let periods = period_range(&first, &last, monthly);
+    let series = tags
+        .into_iter()
+        .map(|tag| {
+            let counts: Vec<u32> = periods.iter().map(|p| counts.get(&(tag.clone(), p.clone())).copied().unwrap_or(0)).collect();
+            TagSeries { total: counts.iter().sum(), tag, counts }
+        })
+        .collect();
+    Ok(TagTrends { granularity: granularity.to_string(), periods, series })
+}
+
+pub async fn get_tag_trends(app_handle: &AppHandle, tags: &[String], granularity: &str) -> Result<TagTrends> {
+    let conn = crate::database::open_conn(app_handle)?;
+    tag_trends(&conn, tags, granularity)
+}
+
+// One year in numbers, for a year in review. People come from extracted entities, so they are
+// only as complete as entity extraction.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct YearSummaryStats {
+    pub year: i32,
+    pub entries: u32,
+    pub total_words: u64,
+    pub average_words_per_entry: f64,
+    pub days_written: u32,
+    pub longest_entry: Option<LongestEntry>,
+    pub most_active_month: Option<BusiestMonth>,
+    // Tags first used this year, in order of first use
+    pub new_tags: Vec<String>,
+    pub top_people: Vec<PersonMentions>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PersonMentions {
+    pub name: String,
+    pub entries: u32,
+    pub mentions: u32,
+}
+
+const TOP_PEOPLE: u32 = 10;
+
+pub(crate) fn year_summary(conn: &rusqlite::Connection, year: i32) -> Result<YearSummaryStats> {
+    let year_prefix = format!("{:04}", year);
+    let mut stmt = conn.prepare(
+        "SELECT id, title, entry_date, body FROM entries WHERE substr(entry_date, 1, 4) = ?1 ORDER BY entry_date",
+    )?;
+    let mut rows = stmt.query([&year_prefix])?;
+    let mut entries = 0u32;
+    let mut total_words = 0u64;
+    let mut longest: Option<LongestEntry> = None;
+    let mut per_month: BTreeMap<u32, u32> = BTreeMap::new();
+    let mut days = std::collections::BTreeSet::new();
+    while let Some(row) = rows.next()? {
+        let entry_date: String = row.get(2)?;
+        let words = word_count(&row.get::<_, String>(3)?);
+        entries += 1;
+        total_words += words;
+        if longest.as_ref().map(|l| words > l.word_count).unwrap_or(true) {
+            longest = Some(LongestEntry { id: row.get(0)?, title: row.get(1)?, entry_date: entry_date.clone(), word_count: words });
+        }
+        if let Some((_, month)) = year_month_of(&entry_date) {
+            *per_month.entry(month).or_insert(0) += 1;
+        }
+        days.insert(entry_date.get(0..10).unwrap_or_default().to_string());
+    }
+    // Ties go to the earliest month, as in the journal statistics
+    let most_active_month = per_month.iter().fold(None::<BusiestMonth>, |best, (&month, &count)| match best {
+        Some(b) if b.count >= count => Some(b),
+        _ => Some(BusiestMonth { year, month, count }),
+    });
+
+    let mut stmt = conn.prepare(
+        "SELECT t.tag FROM entry_tags t JOIN entries e ON e.id = t.entry_id
+         GROUP BY t.tag HAVING substr(MIN(e.entry_date), 1, 4) = ?1 ORDER BY MIN(e.entry_date), t.tag",
+    )?;
+    let new_tags = stmt.query_map([&year_prefix], |r| r.get(0))?.collect::<rusqlite::Result<Vec<String>>>()?;
+
+    let mut stmt = conn.prepare(
+        "SELECT en.name, COUNT(DISTINCT ee.entry_id), SUM(ee.mentions)
+         FROM entry_entities ee JOIN entities en ON en.id = ee.entity_id JOIN entries e ON e.id = ee.entry_id
+         WHERE en.kind = 'person' AND substr(e.entry_date, 1, 4) = ?1
+         GROUP BY en.id ORDER BY 3 DESC, 2 DESC, en.name LIMIT ?2",
+    )?;
+    let top_people = stmt
+        .query_map(rusqlite::params![year_prefix, TOP_PEOPLE], |r| {
+            Ok(PersonMentions { name: r.get(0)?, entries: r.get(1)?, mentions: r.get(2)? })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    Ok(YearSummaryStats {
+        year,
+        entries,
+        total_words,
+        average_words_per_entry: if entries > 0 { total_words as f64 / entries as f64 } else { 0.0 },
+        days_written: days.len() as u32,
+        longest_entry: longest,
+        most_active_month,
+        new_tags,
+        top_people,
+    })
+}
+
+pub async fn get_year_summary_stats(app_handle: &AppHandle, year: i32) -> Result<YearSummaryStats> {
+    let conn = crate::database::open_conn(app_handle)?;
+    year_summary(&conn, year)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_word_count() {
+        assert_eq!(word_count(""), 0);
+        assert_eq!(word_count("  one two\nthree\tfour  "), 4);
+    }
+
+    #[test]
+    fn test_compute_streaks() {
+        let d = |s: &str| NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap();
+        let days: BTreeMap<NaiveDate, u32> =
+            ["2024-01-30", "2024-01-31", "2024-02-01", "2024-02-10", "2024-03-08", "2024-03-09"].iter().map(|s| (d(s), 1)).collect();
+
+        let streaks = compute_streaks(&days, d("2024-03-10"));
+        assert_eq!((streaks.current_streak, streaks.wrote_today), (2, false));
+        assert_eq!(streaks.longest_streak, 3);
+        assert_eq!(streaks.longest_streak_start.as_deref(), Some("2024-01-30"));
+        assert_eq!(streaks.months.iter().map(|m| (m.month, m.days_written)).collect::<Vec<_>>(), [(1, 2), (2, 2), (3, 2)]);
+
+        assert_eq!(compute_streaks(&days, d("2024-03-11")).current_streak, 0);
+        let empty = compute_streaks(&BTreeMap::new(), d("2024-03-10"));
+        assert_eq!((empty.current_streak, empty.longest_streak, empty.last_entry_date), (0, 0, None));
+    }
+
+    #[test]
+    fn test_writing_times() {
+        let entries = [
+            // Sunday 21:30 in Berlin
+            ("2024-03-10T20:30:00+00:00", "Europe/Berlin", 300),
+            ("2024-03-17T21:10:00Z", "+01:00", 100),
+            // Saturday night in UTC, imported without a time
+            ("2024-03-09T12:00:00+00:00", "UTC", 50),
+            ("not a date", "UTC", 10),
+        ];
+        let times = writing_times(entries.iter().map(|&(d, tz, w)| (d, tz, w)), &DateFormat::default());
+        assert_eq!((times.total_entries, times.untimed_entries), (3, 1));
+        assert_eq!((times.weekdays[6].entries, times.weekdays[6].average_words), (2, 200.0));
+        assert_eq!(times.weekdays[5].entries, 1);
+        let sunday_first = writing_times(entries.iter().map(|&(d, tz, w)| (d, tz, w)), &DateFormat::new("de", "sunday"));
+        assert_eq!((sunday_first.weekdays[0].label.as_str(), sunday_first.weekdays[0].entries), ("Sonntag", 2));
+        assert_eq!((times.hours[21].entries, times.hours[22].entries), (1, 1));
+        assert_eq!((times.periods[2].label.as_str(), times.periods[2].entries), ("Evening", 1));
+        assert_eq!(times.periods[3].entries, 1);
+    }
+
+    #[test]
+    fn test_tag_trends() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            r#"CREATE TABLE entries (id TEXT, entry_date TEXT);
+               CREATE TABLE entry_tags (entry_id TEXT, tag TEXT);
+               INSERT INTO entries VALUES ('a', '2019-11-02T12:00:00+00:00'), ('b', '2020-01-15T12:00:00+00:00'),
+                                          ('c', '2020-01-20T12:00:00+00:00'), ('d', '2021-06-01T12:00:00+00:00');
+               INSERT INTO entry_tags VALUES ('a', 'Anxiety'), ('b', 'anxiety'), ('c', 'gratitude'), ('d', 'gratitude'), ('c', 'anxiety');"#,
+        )
+        .unwrap();
+
+        let trends = tag_trends(&conn, &["anxiety".into(), "Gratitude".into()], "month").unwrap();
+        assert_eq!(trends.periods.len(), 20);
+        assert_eq!((trends.periods[0].as_str(), trends.periods[19].as_str()), ("2019-11", "2021-06"));
+        assert_eq!(trends.series[0].counts[..3], [1, 0, 2]);
+        assert_eq!((trends.series[0].total, trends.series[1].total), (3, 2));
+
+        let yearly = tag_trends(&conn, &[], "year").unwrap();
+        assert_eq!(yearly.periods, ["2019", "2020", "2021"]);
+        assert_eq!(yearly.series.iter().map(|s| s.tag.as_str()).collect::<Vec<_>>(), ["anxiety", "gratitude"]);
+        assert_eq!(yearly.series[1].counts, [0, 1, 1]);
+        assert!(tag_trends(&conn, &[], "week").is_err());
+    }
+
+    #[test]
+    fn test_year_summary() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            r#"CREATE TABLE entries (id TEXT, title TEXT, entry_date TEXT, body TEXT);
+               CREATE TABLE entry_tags (entry_id TEXT, tag TEXT);
+               CREATE TABLE entities (id INTEGER PRIMARY KEY, kind TEXT, name TEXT);
+               CREATE TABLE entry_entities (entry_id TEXT, entity_id INTEGER, mentions INTEGER);
+               INSERT INTO entries VALUES ('a', NULL, '2022-12-30T12:00:00+00:00', 'old'),
+                                          ('b', 'Trip', '2023-04-02T12:00:00+00:00', 'one two three four'),
+                                          ('c', NULL, '2023-04-02T20:00:00+00:00', 'one two'),
+                                          ('d', NULL, '2023-09-09T12:00:00+00:00', 'one');
+               INSERT INTO entry_tags VALUES ('a', 'work'), ('c', 'work'), ('b', 'travel'), ('d', 'running');
+               INSERT INTO entities VALUES (1, 'person', 'Anna'), (2, 'person', 'Ben'), (3, 'place', 'Lisbon');
+               INSERT INTO entry_entities VALUES ('b', 1, 1), ('c', 1, 2), ('d', 2, 5), ('b', 3, 4), ('a', 1, 9);"#,
+        )
+        .unwrap();
+
+        let stats = year_summary(&conn, 2023).unwrap();
+        assert_eq!((stats.entries, stats.total_words, stats.days_written), (3, 7, 2));
+        assert_eq!(stats.longest_entry.unwrap().id, "b");
+        let month = stats.most_active_month.unwrap();
+        assert_eq!((month.month, month.count), (4, 2));
+        assert_eq!(stats.new_tags, ["travel", "running"]);
+        let people: Vec<_> = stats.top_people.iter().map(|p| (p.name.as_str(), p.entries, p.mentions)).collect();
+        assert_eq!(people, [("Ben", 1, 5), ("Anna", 2, 3)]);
+        assert_eq!(year_summary(&conn, 2024).unwrap().entries, 0);
+    }
+
+    #[test]
+    fn test_year_month_of() {
+        assert_eq!(year_month_of("2016-03-14T00:00:00+00:00"), Some((2016, 3)));
+        assert_eq!(year_month_of("bad"), None);
+    }
+}
@@ -0,0 +1,418 @@
+use anyhow::Result;
+use chrono::{Duration, NaiveDate, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::AppHandle;
+
+use crate::ai::{AiSettings, ChatMessage, ChatRequest};
+use crate::database::{Entry, EntryFilter};
+
+// AI recaps of a period of entries.
+//
+// Entries are packed into chunks that fit comfortably in a model's context. One chunk is
+// summarized directly; several are summarized separately and the partial notes combined in a
+// final pass. Results are stored per (start, end, granularity) together with a hash of the
+// entries they were built from, so asking again is free until an entry in the period changes.
+
+// Roughly 3k tokens of journal text per request
+const CHUNK_CHARS: usize = 12_000;
+
+const SYSTEM_PROMPT: &str = "You write concise recaps of a person's journal for them. Address them as \"you\", focus on events, people, feelings and recurring themes, and never invent details that are not in the entries.";
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Granularity {
+    Day,
+    Week,
+    Month,
+}
+
+impl Granularity {
+    pub fn parse(value: &str) -> Result<Self> {
+        match value {
+            "day" | "daily" => Ok(Granularity::Day),
+            "week" | "weekly" => Ok(Granularity::Week),
+            "month" | "monthly" => Ok(Granularity::Month),
+            other => Err(anyhow::anyhow!("Unknown granularity '{}' (use day, week or month)", other)),
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Granularity::Day => "day",
+            Granularity::Week => "week",
+            Granularity::Month => "month",
+        }
+    }
+
+    fn recap_name(&self) -> &'static str {
+        match self {
+            Granularity::Day => "daily",
+            Granularity::Week => "weekly",
+            Granularity::Month => "monthly",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PeriodSummary {
+    pub period_start: String,
+    pub period_end: String,
+    pub granularity: Granularity,
+    pub summary: String,
+    pub model: String,
+    pub entry_count: u32,
+    pub created_at: String,
+    // True when returned from the summaries table without calling the provider
+    pub cached: bool,
+}
+
+fn parse_day(value: &str) -> Result<NaiveDate> {
+    NaiveDate::parse_from_str(value.trim(), "%Y-%m-%d")
+        .map_err(|_| anyhow::anyhow!("Invalid date '{}' (expected YYYY-MM-DD)", value))
+}
+
+// Entries dated within [start, end], both inclusive, oldest first
+pub(crate) fn load_period_entries(conn: &Connection, start: NaiveDate, end: NaiveDate) -> Result<Vec<Entry>> {
+    let filter = EntryFilter {
+        date_from: Some(start.to_string()),
+        date_to: Some(end.to_string()),
+        ..Default::default()
+    };
+    crate::database::filtered_entries(conn, &filter)
+}
+
+fn source_hash(entries: &[Entry]) -> String {
+    let mut hasher = Sha256::new();
+    for entry in entries {
+        hasher.update(entry.id.as_bytes());
+        hasher.update(entry.text_hash.as_bytes());
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+fn format_entry(entry: &Entry) -> String {
+    let mut text = format!("## {}", entry.entry_date.format("%A %Y-%m-%d"));
+    if let Some(title) = entry.title.as_deref().filter(|t| !t.trim().is_empty()) {
+        text.push_str(&format!(" - {}", title.trim()));
+    }
+    text.push('\n');
+    text.push_str(entry.body.trim());
+    text.push_str("\n\n");
+    text
+}
+
+// Packs whole entries into chunks of at most `max_chars`; an entry longer than that is cut
+fn chunk_entries(entries: &[Entry], max_chars: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    let mut current_chars = 0;
+    for entry in entries {
+        let mut text = format_entry(entry);
+        let mut chars = text.chars().count();
+        if chars > max_chars {
+            text = text.chars().take(max_chars).collect();
+            chars = max_chars;
+        }
+        if current_chars + chars > max_chars && !current.is_empty() {
+            chunks.push(std::mem::take(&mut current));
+            current_chars = 0;
+        }
+        current.push_str(&text);
+        current_chars += chars;
+    }
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
+
+pub(crate) async fn ask(settings: &AiSettings, prompt: String) -> Result<String> {
+    let request = ChatRequest {
+        messages: vec![
+            ChatMessage { role: "system".to_string(), content: SYSTEM_PROMPT.to_string() },
+            ChatMessage { role: "user".to_string(), content: prompt },
+        ],
+        model: String::new(),
+        provider: None,
+    };
+    Ok(crate::ai::chat_completion(settings, request).await?.trim().to_string())
+}
+
+async fn generate(settings: &AiSettings, chunks: &[String], granularity: Granularity, label: &str) -> Result<String> {
+    let recap = granularity.recap_name();
+    if let [only] = chunks {
+        return ask(
+            settings,
+            format!(
+                "Write a {} recap of these journal entries from {}: one short paragraph, then up to 5 bullet-point highlights.\n\n{}",
+                recap, label, only
+            ),
+        )
+        .await;
+    }
+
+    let mut notes = Vec::new();
+    for (i, chunk) in chunks.iter().enumerate() {
+        let note = ask(
+            settings,
+            format!(
+                "Summarize these journal entries (part {} of {} from {}) as a few bullet points.\n\n{}",
+                i + 1,
+                chunks.len(),
+                label,
+                chunk
+            ),
+        )
+        .await?;
+        notes.push(note);
+    }
+    ask(
+        settings,
+        format!(
+            "These notes each summarize part of a journal from {}. Combine them into one {} recap: one short paragraph, then up to 5 bullet-point highlights.\n\n{}",
+            label,
+            recap,
+            notes.join("\n\n")
+        ),
+    )
+    .await
+}
+
+fn cached_summary(
+    conn: &Connection,
+    start: &str,
+    end: &str,
+    granularity: Granularity,
+    hash: &str,
+) -> Result<Option<PeriodSummary>> {
+    let row = conn
+        .query_row(
+            r#"SELECT summary, model, entry_count, created_at FROM summaries
+                WHERE period_start = ?1 AND period_end = ?2 AND granularity = ?3 AND source_hash = ?4"#,
+            params![start, end, granularity.as_str(), hash],
+            |r| Ok((r.get::<_, String>(0)?, r.get::<_, String>(1)?, r.get::<_, u32>(2)?, r.get::<_, String>(3)?)),
+        )
+        .optional()?;
+    Ok(row.map(|(summary, model, entry_count, created_at)| PeriodSummary {
+        period_start: start.to_string(),
+        period_end: end.to_string(),
+        granularity,
+        summary,
+        model,
+        entry_count,
+        created_at,
+        cached: true,
+    }))
+}
+
+fn store_summary(conn: &Connection, summary: &PeriodSummary, hash: &str) -> Result<()> {
+    conn.execute(
+        r#"INSERT INTO summaries (period_start, period_end, granularity, summary, model, entry_count, source_hash, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+            ON CONFLICT(period_start, period_end, granularity) DO UPDATE SET
+                summary = excluded.summary, model = excluded.model, entry_count = excluded.entry_count,
+                source_hash = excluded.source_hash, created_at = excluded.created_at"#,
+        params![
+            summary.period_start,
+            summary.period_end,
+            summary.granularity.as_str(),
+            summary.summary,
+            summary.model,
+            summary.entry_count,
+            hash,
+            summary.created_at
+        ],
+    )?;
+    Ok(())
+}
+
+// Recap of entries dated `start`..=`end` (YYYY-MM-DD). A stored summary is reused unless the
+// period's entries changed since, or `force` is set.
+pub async fn summarize_period(
+    app_handle: &AppHandle,
+    start: &str,
+    end: &str,
+    granularity: Granularity,
+    force: bool,
+) -> Result<PeriodSummary> {
+    let (start, end) = (parse_day(start)?, parse_day(end)?);
+    if end < start {
+        return Err(anyhow::anyhow!("Period end {} is before its start {}", end, start));
+    }
+    let (period_start, period_end) = (start.to_string(), end.to_string());
+
+    let (entries, settings, cached) = {
+        let conn = crate::database::open_conn(app_handle)?;
+        let entries = load_period_entries(&conn, start, end)?;
+        let cached = if force {
+            None
+        } else {
+            cached_summary(&conn, &period_start, &period_end, granularity, &source_hash(&entries))?
+        };
+        (entries, AiSettings::load(&conn), cached)
+    };
+    if let Some(cached) = cached {
+        return Ok(cached);
+    }
+    if entries.is_empty() {
+        return Err(anyhow::anyhow!("No entries between {} and {}", period_start, period_end));
+    }
+
+    let label = if start == end { period_start.clone() } else { format!("{} to {}", period_start, period_end) };
+    let chunks = chunk_entries(&entries, CHUNK_CHARS);
+    tracing::debug!("{} {} entries={} chunks={}", granularity.as_str(), label, entries.len(), chunks.len());
+    let ids = entries.iter().map(|e| e.id.clone()).collect();
+    let text = crate::ai_audit::with_context("summary", ids, generate(&settings, &chunks, granularity, &label)).await?;
+
+    let summary = PeriodSummary {
+        period_start,
+        period_end,
+        granularity,
+        summary: text,
+        model: format!("{}:{}", settings.provider.as_str(), settings.chat_model(&settings.provider, "")),
+        entry_count: entries.len() as u32,
+        created_at: Utc::now().to_rfc3339(),
+        cached: false,
+    };
+    let conn = crate::database::open_conn(app_handle)?;
+    store_summary(&conn, &summary, &source_hash(&entries))?;
+    Ok(summary)
+}
+
+// Stored summaries, most recent period first
+pub async fn list_summaries(app_handle: &AppHandle, granularity: Option<Granularity>, limit: u32) -> Result<Vec<PeriodSummary>> {
+    let conn = crate::database::open_conn(app_handle)?;
+    let mut stmt = conn.prepare_cached(
+        r#"SELECT period_start, period_end, granularity, summary, model, entry_count, created_at
+            FROM summaries WHERE ?1 IS NULL OR granularity = ?1
+            ORDER BY period_start DESC LIMIT ?2"#,
+    )?;
+    let rows = stmt.query_map(params![granularity.map(|g| g.as_str()), limit as i64], |r| {
+        Ok((
+            r.get::<_, String>(0)?,
+            r.get::<_, String>(1)?,
+            r.get::<_, String>(2)?,
+            r.get::<_, String>(3)?,
+            r.get::<_, String>(4)?,
+            r.get::<_, u32>(5)?,
+            r.get::<_, String>(6)?,
+        ))
+    })?;
+    let mut out = Vec::new();
+    for row in rows {
+        let (period_start, period_end, granularity, summary, model, entry_count, created_at) = row?;
+        out.push(PeriodSummary {
+            period_start,
+            period_end,
+            granularity: Granularity::parse(&granularity)?,
+            summary,
+            model,
+            entry_count,
+            created_at,
+            cached: true,
+        });
+    }
+    Ok(out)
+}
+
+// The most recent week, from the `week_start` day, that has fully ended before `today`
+fn last_complete_week(today: NaiveDate, dates: &crate::locale::DateFormat) -> (NaiveDate, NaiveDate) {
+    let this_week = dates.week_start_of(today);
+    (this_week - Duration::days(7), this_week - Duration::days(1))
+}
+
+// With `auto_weekly_summary` on, writes last week's recap if it is missing or stale.
+// Run in the background at startup; weeks without entries are skipped quietly.
+pub async fn auto_weekly_recap(app_handle: &AppHandle) -> Result<()> {
+    let dates = {
+        let conn = crate::database::open_conn(app_handle)?;
+        if !crate::database::setting_enabled(&conn, "auto_weekly_summary", false) {
+            return Ok(());
+        }
+        crate::locale::DateFormat::from_conn(&conn)
+    };
+    let (start, end) = last_complete_week(Utc::now().date_naive(), &dates);
+    match summarize_period(app_handle, &start.to_string(), &end.to_string(), Granularity::Week, false).await {
+        Ok(summary) if !summary.cached => tracing::info!("wrote weekly recap for {}", start),
+        Ok(_) => {}
+        Err(e) if e.to_string().starts_with("No entries") => {}
+        Err(e) => return Err(e),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: &str, date: &str, body: &str) -> Entry {
+        let ts = chrono::DateTime::parse_from_rfc3339(date).unwrap().with_timezone(&Utc);
+        Entry {
+            id: id.into(),
+            title: None,
+            body: body.into(),
+            entry_date: ts,
+            entry_timezone: "UTC".into(),
+            source_path: String::new(),
+            source_type: "txt".into(),
+            text_hash: format!("hash-{}", id),
+            embedding: None,
+            created_at: ts,
+            updated_at: ts,
+            sentiment: None,
+            language: None,
+        }
+    }
+
+    #[test]
+    fn test_chunk_entries_keeps_entries_whole() {
+        let entries = vec![
+            entry("1", "2024-05-06T09:00:00Z", &"a".repeat(40)),
+            entry("2", "2024-05-07T09:00:00Z", &"b".repeat(40)),
+            entry("3", "2024-05-08T09:00:00Z", &"c".repeat(500)),
+        ];
+        let chunks = chunk_entries(&entries, 150);
+        assert_eq!(chunks.len(), 2);
+        assert!(chunks[0].contains("Monday 2024-05-06") && chunks[0].contains("Tuesday 2024-05-07"));
+        assert_eq!(chunks[1].chars().count(), 150);
+    }
+
+    #[test]
+    fn test_period_entries_and_cache() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::migrations::run_migrations(&mut conn).unwrap();
+        for (id, date) in [("a", "2024-05-05T23:00:00+00:00"), ("b", "2024-05-06T08:00:00+00:00"), ("c", "2024-05-12T21:00:00+00:00"), ("d", "2024-05-13T00:00:00+00:00")] {
+            conn.execute(
+                r#"INSERT INTO entries (id, body, entry_date, entry_timezone, source_path, source_type, text_hash, created_at, updated_at)
+                    VALUES (?1, 'text', ?2, 'UTC', '', 'txt', ?1, ?2, ?2)"#,
+                params![id, date],
+            )
+            .unwrap();
+        }
+        let (start, end) = last_complete_week(NaiveDate::from_ymd_opt(2024, 5, 15).unwrap(), &crate::locale::DateFormat::default());
+        assert_eq!((start.to_string(), end.to_string()), ("2024-05-06".to_string(), "2024-05-12".to_string()));
+
+        let entries = load_period_entries(&conn, start, end).unwrap();
+        assert_eq!(entries.iter().map(|e| e.id.as_str()).collect::<Vec<_>>(), ["b", "c"]);
+
+        let hash = source_hash(&entries);
+        let summary = PeriodSummary {
+            period_start: start.to_string(),
+            period_end: end.to_string(),
+            granularity: Granularity::Week,
+            summary: "A quiet week.".into(),
+            model: "ollama:llama3.1:8b".into(),
+            entry_count: 2,
+            created_at: Utc::now().to_rfc3339(),
+            cached: false,
+        };
+        store_summary(&conn, &summary, &hash).unwrap();
+        let cached = cached_summary(&conn, "2024-05-06", "2024-05-12", Granularity::Week, &hash).unwrap().unwrap();
+        assert!(cached.cached);
+        assert_eq!(cached.summary, "A quiet week.");
+        // An edited entry changes the hash, so the stored summary no longer applies
+        assert!(cached_summary(&conn, "2024-05-06", "2024-05-12", Granularity::Week, "other").unwrap().is_none());
+    }
+}
@@ -0,0 +1,299 @@
+use anyhow::Result;
+use chrono::{NaiveDate, TimeZone, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::time::Duration;
+use tauri::AppHandle;
+
+use crate::import::{FileType, ParsedFile};
+
+// Entry templates: a saved title and body with {{variables}} that are filled in when an entry
+// is created from the template for a given day.
+//
+// {{date}}: the entry's date written out ("Friday, March 5, 2021")
+// {{weather}}: that day's weather from Open-Meteo for `weather_location` ("lat,lon"); empty
+//   when no location is set or the lookup fails
+// {{prompt}}: a writing prompt from the AI provider, or a built-in one when that isn't available
+//
+// Unknown variables are left as they are. Weather and prompt are only fetched when used.
+
+const WEATHER_TIMEOUT: Duration = Duration::from_secs(10);
+// The forecast API covers recent days; older dates come from the archive
+const FORECAST_PAST_DAYS: i64 = 60;
+
+const FALLBACK_PROMPTS: &[&str] = &[
+    "What are three things you're grateful for today?",
+    "What gave you energy today, and what drained it?",
+    "What is one thing you want to remember about today?",
+    "What would make tomorrow a good day?",
+    "Who did you think about today, and why?",
+    "What is something you're looking forward to?",
+    "What did you learn today?",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntryTemplate {
+    pub id: String,
+    pub name: String,
+    pub title: Option<String>,
+    pub body: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TemplateEntry {
+    pub entry_id: String,
+    // False when the rendered entry already existed (same template, same day, not yet edited)
+    pub created: bool,
+}
+
+fn template_from_row(row: &rusqlite::Row) -> rusqlite::Result<EntryTemplate> {
+    Ok(EntryTemplate {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        title: row.get(2)?,
+        body: row.get(3)?,
+        created_at: row.get(4)?,
+        updated_at: row.get(5)?,
+    })
+}
+
+const TEMPLATE_COLUMNS: &str = "id, name, title, body, created_at, updated_at";
+
+pub(crate) fn list(conn: &Connection) -> Result<Vec<EntryTemplate>> {
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM entry_templates ORDER BY name COLLATE NOCASE", TEMPLATE_COLUMNS))?;
+    let rows = stmt.query_map([], template_from_row)?.collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(rows)
+}
+
+pub(crate) fn get(conn: &Connection, id: &str) -> Result<Option<EntryTemplate>> {
+    let row = conn
+        .query_row(&format!("SELECT {} FROM entry_templates WHERE id = ?1", TEMPLATE_COLUMNS), params![id], template_from_row)
+        .optional()?;
+    Ok(row)
+}
+
+// Creates a template, or updates it when `id` is given
+pub(crate) fn save(conn: &Connection, id: Option<&str>, name: &str, title: Option<&str>, body: &str) -> Result<EntryTemplate> {
+    let name = name.trim();
+    if name.is_empty() {
+        anyhow::bail!("Template name is required");
+    }
+    let title = title.map(str::trim).filter(|t| !t.is_empty());
+    let now = Utc::now().to_rfc3339();
+    let id = match id {
+        Some(id) => {
+            let changed = conn.execute(
+                "UPDATE entry_templates SET name = ?2, title = ?3, body = ?4, updated_at = ?5 WHERE id = ?1",
+                params![id, name, title, body, now],
+            )?;
+            if changed == 0 {
+                anyhow::bail!("Template not found");
+            }
+            id.to_string()
+        }
+        None => {
+            let id = uuid::Uuid::new_v4().to_string();
+            conn.execute(
+                "INSERT INTO entry_templates (id, name, title, body, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?5)",
+                params![id, name, title, body, now],
+            )?;
+            id
+        }
+    };
+    get(conn, &id)?.ok_or_else(|| anyhow::anyhow!("Template not found"))
+}
+
+pub(crate) fn delete(conn: &Connection, id: &str) -> Result<bool> {
+    Ok(conn.execute("DELETE FROM entry_templates WHERE id = ?1", params![id])? > 0)
+}
+
+// Restores templates from a backup or settings file; `replace` overwrites templates with the
+// same id, otherwise they are kept. Returns how many were written
+pub(crate) fn restore(conn: &Connection, templates: &[EntryTemplate], replace: bool) -> Result<u32> {
+    let verb = if replace { "INSERT OR REPLACE" } else { "INSERT OR IGNORE" };
+    let mut stmt = conn.prepare(&format!(
+        "{} INTO entry_templates (id, name, title, body, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        verb
+    ))?;
+    let mut written = 0;
+    for t in templates {
+        written += stmt.execute(params![t.id, t.name, t.title, t.body, t.created_at, t.updated_at])? as u32;
+    }
+    Ok(written)
+}
+
+fn variable_pattern() -> &'static regex::Regex {
+    static PATTERN: std::sync::OnceLock<regex::Regex> = std::sync::OnceLock::new();
+    PATTERN.get_or_init(|| regex::Regex::new(r"\{\{\s*(\w+)\s*\}\}").expect("valid variable pattern"))
+}
+
+fn uses(text: &str, variable: &str) -> bool {
+    variable_pattern().captures_iter(text).any(|c| c[1].eq_ignore_ascii_case(variable))
+}
+
+pub(crate) fn render(text: &str, values: &HashMap<&str, String>) -> String {
+    variable_pattern()
+        .replace_all(text, |c: &regex::Captures| match values.get(c[1].to_lowercase().as_str()) {
+            Some(value) => value.clone(),
+            None => c[0].to_string(),
+        })
+        .to_string()
+}
+
+// WMO weather interpretation codes, as used by Open-Meteo
+fn weather_description(code: i64) -> &'static str {
+    match code {
+        0 => "Clear",
+        1 => "Mostly clear",
+        2 => "Partly cloudy",
+        3 => "Overcast",
+        45 | 48 => "Fog",
+        51 | 53 | 55 | 56 | 57 => "Drizzle",
+        61 | 63 | 66 => "Rain",
+        65 | 67 => "Heavy rain",
+        71 | 73 | 77 => "Snow",
+        75 => "Heavy snow",
+        80..=82 => "Rain showers",
+        85 | 86 => "Snow showers",
+        95..=99 => "Thunderstorms",
+        _ => "Unknown weather",
+    }
+}
+
+fn parse_location(value: &str) -> Option<(f64, f64)> {
+    let (lat, lon) = value.split_once(',')?;
+    let (lat, lon) = (lat.trim().parse::<f64>().ok()?, lon.trim().parse::<f64>().ok()?);
+    ((-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&lon)).then_some((lat, lon))
+}
+
+async fn weather(location: (f64, f64), date: NaiveDate) -> Result<String> {
+    let recent = (Utc::now().date_naive() - date).num_days() <= FORECAST_PAST_DAYS;
+    let base = if recent { "https://api.open-meteo.com/v1/forecast" } else { "https://archive-api.open-meteo.com/v1/archive" };
+    let client = reqwest::Client::builder().timeout(WEATHER_TIMEOUT).build()?;
+    let resp: serde_json::Value = client
+        .get(base)
+        .query(&[
+            ("latitude", location.0.to_string()),
+            ("longitude", location.1.to_string()),
+            ("daily", "weather_code,temperature_2m_max,temperature_2m_min".to_string()),
+            ("timezone", "auto".to_string()),
+            ("start_date", date.to_string()),
+            ("end_date", date.to_string()),
+        ])
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    let daily = &resp["daily"];
+    let code = daily["weather_code"][0].as_i64().ok_or_else(|| anyhow::anyhow!("no weather for {}", date))?;
+    let (low, high) = (daily["temperature_2m_min"][0].as_f64(), daily["temperature_2m_max"][0].as_f64());
+    Ok(match (low, high) {
+        (Some(low), Some(high)) => format!("{}, {:.0}–{:.0}°C", weather_description(code), low, high),
+        _ => weather_description(code).to_string(),
+    })
+}
+
+async fn writing_prompt(app_handle: &AppHandle, date: NaiveDate) -> String {
+    match crate::prompts::generate_writing_prompts(app_handle, Some(1)).await {
+        Ok(prompts) if !prompts.is_empty() => prompts[0].prompt.clone(),
+        result => {
+            if let Err(e) = result {
+                tracing::warn!("prompt generation failed, using a built-in prompt: {}", e);
+            }
+            use chrono::Datelike;
+            FALLBACK_PROMPTS[date.num_days_from_ce() as usize % FALLBACK_PROMPTS.len()].to_string()
+        }
+    }
+}
+
+async fn variables(app_handle: &AppHandle, template: &EntryTemplate, date: NaiveDate) -> HashMap<&'static str, String> {
+    let text = format!("{}\n{}", template.title.as_deref().unwrap_or_default(), template.body);
+    let dates = crate::database::open_conn(app_handle).map(|conn| crate::locale::DateFormat::from_conn(&conn)).unwrap_or_default();
+    let mut values = HashMap::from([("date", dates.long_date(date))]);
+    if uses(&text, "weather") {
+        let location = crate::database::open_conn(app_handle)
+            .ok()
+            .and_then(|conn| crate::database::read_setting(&conn, "weather_location"))
+            .and_then(|v| parse_location(&v));
+        let value = match location {
+            Some(location) => weather(location, date).await.unwrap_or_else(|e| {
+                tracing::warn!("weather lookup failed: {}", e);
+                String::new()
+            }),
+            None => String::new(),
+        };
+        values.insert("weather", value);
+    }
+    if uses(&text, "prompt") {
+        values.insert("prompt", writing_prompt(app_handle, date).await);
+    }
+    values
+}
+
+pub async fn create_entry(app_handle: &AppHandle, template_id: &str, date: NaiveDate, timezone: Option<String>) -> Result<TemplateEntry> {
+    let template = {
+        let conn = crate::database::open_conn(app_handle)?;
+        get(&conn, template_id)?.ok_or_else(|| anyhow::anyhow!("Template not found"))?
+    };
+    let values = variables(app_handle, &template, date).await;
+    let content = render(&template.body, &values);
+    let title = template.title.as_deref().map(|t| render(t, &values)).map(|t| t.trim().to_string()).filter(|t| !t.is_empty());
+    let text_hash = format!("{:x}", Sha256::digest(content.as_bytes()));
+    if let Some(existing) = crate::database::check_duplicate(app_handle, &text_hash).await? {
+        return Ok(TemplateEntry { entry_id: existing, created: false });
+    }
+
+    let parsed = ParsedFile {
+        path: format!("template:{}", template.id),
+        size_bytes: content.len() as u64,
+        content,
+        title,
+        file_type: FileType::Template,
+        text_hash,
+    };
+    // Noon UTC keeps the day the same in every timezone, like imported files
+    let entry_date = Utc.from_utc_datetime(&date.and_hms_opt(12, 0, 0).expect("valid time"));
+    let timezone = timezone.filter(|tz| !tz.trim().is_empty()).unwrap_or_else(|| "UTC".to_string());
+    let entry_id = crate::database::save_entry(app_handle, parsed, entry_date, timezone).await?;
+    Ok(TemplateEntry { entry_id, created: true })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_variables() {
+        let values = HashMap::from([("date", crate::locale::DateFormat::default().long_date(NaiveDate::from_ymd_opt(2021, 3, 5).unwrap())), ("weather", String::new())]);
+        assert_eq!(
+            render("## {{date}}\nWeather: {{ Weather }}\n{{mood}}", &values),
+            "## Friday, March 5, 2021\nWeather: \n{{mood}}"
+        );
+        assert!(uses("Prompt: {{ prompt }}", "prompt"));
+        assert!(!uses("Prompt: {prompt}", "prompt"));
+    }
+
+    #[test]
+    fn test_template_storage_and_location() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE entry_templates (id TEXT PRIMARY KEY, name TEXT NOT NULL, title TEXT, body TEXT NOT NULL, created_at TEXT NOT NULL, updated_at TEXT NOT NULL);",
+        )
+        .unwrap();
+        let t = save(&conn, None, " Nightly ", Some(" "), "Grateful for:\n- ").unwrap();
+        assert_eq!((t.name.as_str(), t.title.as_deref()), ("Nightly", None));
+        save(&conn, Some(&t.id), "Nightly", Some("{{date}}"), "Goals:").unwrap();
+        assert_eq!(list(&conn).unwrap()[0].body, "Goals:");
+        assert!(save(&conn, None, "  ", None, "").is_err());
+        assert!(delete(&conn, &t.id).unwrap() && list(&conn).unwrap().is_empty());
+
+        assert_eq!(parse_location("52.52, 13.41"), Some((52.52, 13.41)));
+        assert_eq!(parse_location("Berlin"), None);
+        assert_eq!(weather_description(2), "Partly cloudy");
+    }
+}
@@ -0,0 +1,193 @@
+use crate::ai::{AiSettings, ContextEntry, Provider};
+
+// Token estimates and fitting RAG context into a model's window.
+//
+// Counting uses the same pre-tokenization as tiktoken (words with their leading space, digit
+// runs, punctuation runs) with a per-piece estimate instead of the BPE merge tables: short
+// words are one token, longer ones about one per four characters, CJK one per character. That
+// tracks cl100k within ~10% on English prose and errs high elsewhere, and it works for every
+// provider without bundling tokenizer data.
+//
+// Packing walks the retrieved entries best first. Each gets its full text when it fits, and is
+// otherwise cut to the space left after reserving a minimal share for the entries after it;
+// whatever doesn't fit at all is dropped.
+
+// Reserved for the answer (the RAG calls ask for up to 1000 tokens)
+pub const ANSWER_TOKENS: usize = 1000;
+// Smallest useful excerpt; an entry that can't get this much is left out
+const MIN_ENTRY_TOKENS: usize = 64;
+// The "[Entry N] Date: ... | Tags: ... | Content: " line around each excerpt
+const ENTRY_OVERHEAD_TOKENS: usize = 24;
+// Ollama's default num_ctx when the `context_window` setting doesn't raise it
+const OLLAMA_DEFAULT_WINDOW: usize = 4096;
+
+fn is_cjk(c: char) -> bool {
+    matches!(c as u32, 0x3040..=0x30ff | 0x3400..=0x4dbf | 0x4e00..=0x9fff | 0xac00..=0xd7af | 0xf900..=0xfaff)
+}
+
+fn piece_tokens(piece: &str) -> usize {
+    let chars = piece.chars().count();
+    let cjk = piece.chars().filter(|c| is_cjk(*c)).count();
+    if cjk > 0 {
+        return cjk + (chars - cjk).div_ceil(4);
+    }
+    let letters = piece.trim_start().chars().count();
+    if piece.trim_start().starts_with(|c: char| c.is_ascii_digit()) {
+        return letters.div_ceil(3); // digits are split in groups of up to three
+    }
+    if letters <= 6 {
+        1
+    } else {
+        letters.div_ceil(4)
+    }
+}
+
+// (byte offset, tokens) of each tiktoken-style piece
+pub(crate) fn pieces(text: &str) -> impl Iterator<Item = (usize, usize)> + '_ {
+    static PATTERN: std::sync::OnceLock<regex::Regex> = std::sync::OnceLock::new();
+    let pattern = PATTERN.get_or_init(|| {
+        regex::Regex::new(r"'(?:s|t|re|ve|m|ll|d)| ?\p{L}+| ?\p{N}+| ?[^\s\p{L}\p{N}]+|\s+").expect("valid token pattern")
+    });
+    pattern.find_iter(text).map(|m| {
+        let tokens = if m.as_str().trim().is_empty() { usize::from(m.as_str().contains('\n')) } else { piece_tokens(m.as_str()) };
+        (m.start(), tokens)
+    })
+}
+
+pub fn estimate_tokens(text: &str) -> usize {
+    pieces(text).map(|(_, t)| t).sum()
+}
+
+// At most `max_tokens` of `text`, ending at a sentence boundary when one is reasonably close
+pub fn truncate_to_tokens(text: &str, max_tokens: usize) -> String {
+    let mut used = 0;
+    let mut cut = None;
+    for (start, tokens) in pieces(text) {
+        if used + tokens > max_tokens {
+            cut = Some(start);
+            break;
+        }
+        used += tokens;
+    }
+    let Some(cut) = cut else { return text.to_string() };
+    let head = &text[..cut];
+    let sentence_end = head.rfind(['.', '!', '?', '\n']).map(|i| i + 1).filter(|&i| i * 10 >= cut * 7);
+    format!("{} ...", head[..sentence_end.unwrap_or(cut)].trim_end())
+}
+
+// Context window of the chat model in tokens; the `context_window` setting overrides the guess
+pub fn context_window(settings: &AiSettings, provider: &Provider, model: &str) -> usize {
+    if let Some(window) = settings.context_window {
+        return window;
+    }
+    match provider {
+        Provider::OpenAI => {
+            let model = model.to_lowercase();
+            if model.starts_with("gpt-4.1") {
+                1_000_000
+            } else if model.starts_with("gpt-3.5") {
+                16_385
+            } else if model == "gpt-4" || model.starts_with("gpt-4-0") {
+                8_192
+            } else {
+                128_000
+            }
+        }
+        Provider::Ollama | Provider::Local => OLLAMA_DEFAULT_WINDOW,
+    }
+}
+
+// The excerpt of `body` to use when it must be cut: from where the search snippet matched,
+// so the relevant passage survives, else from the start
+fn excerpt_start<'a>(body: &'a str, snippet: &str) -> &'a str {
+    let probe: String = snippet.trim_matches(|c: char| c == '.' || c.is_whitespace()).chars().take(40).collect();
+    match body.find(probe.as_str()).filter(|_| probe.chars().count() >= 20) {
+        Some(at) => {
+            // Back up to the start of that sentence
+            let start = body[..at].rfind(['.', '!', '?', '\n']).map(|i| i + 1).unwrap_or(0);
+            body[start..].trim_start()
+        }
+        None => body,
+    }
+}
+
+// Keeps the most relevant entries that fit in `budget` tokens, each with as much of its text as
+// fits; the result is in relevance order and its `snippet`s hold the text to show the model
+pub fn pack_context(mut entries: Vec<ContextEntry>, budget: usize) -> Vec<ContextEntry> {
+    entries.sort_by(|a, b| b.relevance_score.total_cmp(&a.relevance_score));
+    let mut remaining = budget;
+    let mut packed = Vec::new();
+    let count = entries.len();
+    for (i, mut entry) in entries.into_iter().enumerate() {
+        let Some(room) = remaining.checked_sub(ENTRY_OVERHEAD_TOKENS).filter(|&r| r >= MIN_ENTRY_TOKENS) else {
+            break;
+        };
+        // Leave an excerpt's worth for each entry still to come, unless that squeezes this one out
+        let reserve = (count - i - 1) * (MIN_ENTRY_TOKENS + ENTRY_OVERHEAD_TOKENS);
+        let available = room.saturating_sub(reserve).max(MIN_ENTRY_TOKENS);
+        let body = entry.body.trim();
+        let text = if estimate_tokens(body) <= available {
+            body.to_string()
+        } else {
+            // One token of the budget goes to the trailing "..."
+            truncate_to_tokens(excerpt_start(body, &entry.snippet), available - 1)
+        };
+        remaining = remaining.saturating_sub(estimate_tokens(&text) + ENTRY_OVERHEAD_TOKENS);
+        entry.snippet = text;
+        packed.push(entry);
+    }
+    packed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: &str, body: &str, relevance_score: f32) -> ContextEntry {
+        ContextEntry {
+            entry_id: id.into(),
+            title: None,
+            body: body.into(),
+            entry_date: chrono::Utc::now(),
+            tags: vec![],
+            relevance_score,
+            snippet: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_estimate_tokens() {
+        assert_eq!(estimate_tokens(""), 0);
+        assert_eq!(estimate_tokens("Hello world"), 2);
+        assert_eq!(estimate_tokens("I didn't go, 2024."), 8);
+        assert_eq!(estimate_tokens("今天我们去了公园"), 8);
+        // Long words cost more than one token
+        assert!(estimate_tokens("internationalization") >= 4);
+    }
+
+    #[test]
+    fn test_truncate_prefers_sentence_end() {
+        let text = "One two three four five six seven eight nine ten. Eleven twelve thirteen.";
+        assert_eq!(truncate_to_tokens(text, 12), "One two three four five six seven eight nine ten. ...");
+        assert_eq!(truncate_to_tokens(text, 100), text);
+    }
+
+    #[test]
+    fn test_pack_prefers_relevance_and_fits_budget() {
+        let long = "word ".repeat(2000);
+        let entries = vec![
+            entry("low", "short but least relevant", 0.1),
+            entry("high", &long, 0.9),
+            entry("mid", "a medium entry about running", 0.5),
+        ];
+        let packed = pack_context(entries, 1000);
+        let ids: Vec<&str> = packed.iter().map(|e| e.entry_id.as_str()).collect();
+        assert_eq!(ids, ["high", "mid", "low"]);
+        assert!(packed[0].snippet.ends_with("..."));
+        let used: usize = packed.iter().map(|e| estimate_tokens(&e.snippet) + ENTRY_OVERHEAD_TOKENS).sum();
+        assert!(used <= 1000);
+
+        // Too small for more than one excerpt
+        assert_eq!(pack_context(vec![entry("a", &long, 0.9), entry("b", &long, 0.8)], 100).len(), 1);
+    }
+}
@@ -0,0 +1,354 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use rusqlite::Connection;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use tauri::AppHandle;
+use tokio::process::Command;
+
+use crate::ai::{AiSettings, Provider};
+use crate::database::Entry;
+use crate::error::MissingTool;
+
+// Entries read aloud into an audio file.
+//
+// The title, date (in the `locale` setting's language) and text are spoken by the engine in
+// `tts_engine`:
+// - "system" (default): the computer's own voices, offline, as WAV: `say` on macOS, `espeak-ng`
+//   or `espeak` on Linux, System.Speech through PowerShell on Windows
+// - "openai": OpenAI's speech API (`tts-1`) as MP3; the text is sent in pieces of up to
+//   OPENAI_MAX_CHARS, through the same rate limiting and audit log as other AI requests
+// `tts_voice` picks the voice: a system voice name, or one of OpenAI's (alloy, echo, fable, nova,
+// onyx, shimmer); empty uses the engine's default.
+//
+// Audio is kept in `audio/` next to the journals as "<entry id>-<hash>.<ext>", the hash covering
+// the spoken text, engine and voice, so asking again reuses the file and an edited entry or another
+// voice makes a new one, replacing the entry's old file. Sensitive entries are never read aloud,
+// since the audio would sit unencrypted on disk.
+
+pub const DEFAULT_ENGINE: &str = "system";
+const OPENAI_MODEL: &str = "tts-1";
+const OPENAI_DEFAULT_VOICE: &str = "alloy";
+const OPENAI_MAX_CHARS: usize = 4096;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Engine {
+    System,
+    OpenAI,
+}
+
+impl Engine {
+    pub fn parse(value: &str) -> Self {
+        match value.trim().to_lowercase().as_str() {
+            "openai" => Engine::OpenAI,
+            _ => Engine::System,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Engine::System => "system",
+            Engine::OpenAI => "openai",
+        }
+    }
+
+    fn extension(&self) -> &'static str {
+        match self {
+            Engine::System => "wav",
+            Engine::OpenAI => "mp3",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EntryAudio {
+    pub entry_id: String,
+    pub path: String,
+    // "wav" or "mp3"
+    pub format: String,
+    pub engine: String,
+    // Whether the file was already there
+    pub cached: bool,
+}
+
+pub(crate) fn audio_dir(app_handle: &AppHandle) -> Result<PathBuf> {
+    Ok(crate::database::get_db_dir(app_handle)?.join("audio"))
+}
+
+// `tts_engine` and `tts_voice`
+pub fn settings(conn: &Connection) -> (Engine, String) {
+    let engine = crate::database::read_setting(conn, "tts_engine").unwrap_or_else(|| DEFAULT_ENGINE.to_string());
+    (Engine::parse(&engine), crate::database::read_setting(conn, "tts_voice").unwrap_or_default().trim().to_string())
+}
+
+fn spoken_text(entry: &Entry, dates: &crate::locale::DateFormat) -> String {
+    let mut parts = Vec::new();
+    if let Some(title) = entry.title.as_deref().map(str::trim).filter(|t| !t.is_empty()) {
+        parts.push(format!("{}.", title.trim_end_matches('.')));
+    }
+    parts.push(format!("{}.", dates.long_date(entry.entry_date.date_naive())));
+    parts.push(entry.body.trim().to_string());
+    parts.join("\n\n")
+}
+
+fn file_name(entry_id: &str, engine: Engine, voice: &str, text: &str) -> String {
+    let hash = format!("{:x}", Sha256::digest(format!("{}\n{}\n{}", engine.as_str(), voice, text).as_bytes()));
+    format!("{}-{}.{}", entry_id, &hash[..16], engine.extension())
+}
+
+// Pieces of at most `max` characters, split between paragraphs, then sentences, then words
+fn pieces(text: &str, max: usize) -> Vec<String> {
+    fn push(part: &str, max: usize, out: &mut Vec<String>, current: &mut String) {
+        if !current.is_empty() && current.chars().count() + 1 + part.chars().count() > max {
+            out.push(std::mem::take(current));
+        }
+        if !current.is_empty() {
+            current.push(' ');
+        }
+        current.push_str(part);
+    }
+    let mut out: Vec<String> = Vec::new();
+    let mut current = String::new();
+    for paragraph in text.split("\n\n").map(str::trim).filter(|p| !p.is_empty()) {
+        for sentence in paragraph.split_inclusive(['.', '!', '?']).map(str::trim).filter(|s| !s.is_empty()) {
+            if sentence.chars().count() <= max {
+                push(sentence, max, &mut out, &mut current);
+                continue;
+            }
+            for word in sentence.split_whitespace() {
+                let chars: Vec<char> = word.chars().collect();
+                for chunk in chars.chunks(max) {
+                    push(&chunk.iter().collect::<String>(), max, &mut out, &mut current);
+                }
+            }
+        }
+        if !current.is_empty() {
+            out.push(std::mem::take(&mut current));
+        }
+    }
+    // Paragraphs are their own pieces above; merge small neighbours to keep requests few
+    let mut merged: Vec<String> = Vec::new();
+    for piece in out {
+        match merged.last_mut() {
+            Some(last) if last.chars().count() + 2 + piece.chars().count() <= max => {
+                last.push_str("\n\n");
+                last.push_str(&piece);
+            }
+            _ => merged.push(piece),
+        }
+    }
+    merged
+}
+
+// Writes the entry's audio unless it is already cached
+pub async fn synthesize(app_handle: &AppHandle, entry: &Entry) -> Result<EntryAudio> {
+    let (engine, voice, text, settings) = {
+        let conn = crate::database::open_conn(app_handle)?;
+        if crate::sensitive::is_sensitive(&conn, &entry.id)? {
+            anyhow::bail!("Sensitive entries are not read aloud, since the audio file would not be encrypted");
+        }
+        let (engine, voice) = settings(&conn);
+        let text = spoken_text(entry, &crate::locale::DateFormat::from_conn(&conn));
+        (engine, voice, text, AiSettings::load(&conn))
+    };
+    if entry.body.trim().is_empty() {
+        anyhow::bail!("This entry has no text to read");
+    }
+    let dir = audio_dir(app_handle)?;
+    let path = dir.join(file_name(&entry.id, engine, &voice, &text));
+    let audio = |cached| EntryAudio {
+        entry_id: entry.id.clone(),
+        path: path.to_string_lossy().to_string(),
+        format: engine.extension().to_string(),
+        engine: engine.as_str().to_string(),
+        cached,
+    };
+    if path.is_file() {
+        return Ok(audio(true));
+    }
+    fs::create_dir_all(&dir)?;
+    // Written under a temporary name so an interrupted synthesis never passes for the file
+    let partial = path.with_extension(format!("{}.partial", engine.extension()));
+    let result = match engine {
+        Engine::System => speak_system(&text, &voice, &dir, &partial).await,
+        Engine::OpenAI => speak_openai(&settings, &text, &voice, &partial).await,
+    };
+    if let Err(e) = result {
+        let _ = fs::remove_file(&partial);
+        return Err(e);
+    }
+    fs::rename(&partial, &path)?;
+    remove_older(&dir, &entry.id, &path);
+    tracing::info!("synthesized audio for entry {} with {}", entry.id, engine.as_str());
+    Ok(audio(false))
+}
+
+// The entry's audio file, if it has been synthesized
+pub fn cached(app_handle: &AppHandle, entry_id: &str) -> Result<Option<PathBuf>> {
+    let Ok(read) = fs::read_dir(audio_dir(app_handle)?) else { return Ok(None) };
+    Ok(read.filter_map(|e| e.ok().map(|e| e.path())).find(|p| is_entry_audio(p, entry_id)))
+}
+
+fn is_entry_audio(path: &Path, entry_id: &str) -> bool {
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+    // Entry ids are UUIDs, so the hash after the prefix has no '-'
+    let ours = name.strip_prefix(entry_id).and_then(|rest| rest.strip_prefix('-')).is_some_and(|rest| !rest.contains('-'));
+    ours && !name.ends_with(".partial")
+}
+
+// Earlier audio of the entry, made from older text or another voice
+fn remove_older(dir: &Path, entry_id: &str, keep: &Path) {
+    let Ok(read) = fs::read_dir(dir) else { return };
+    for path in read.filter_map(|e| e.ok().map(|e| e.path())) {
+        if is_entry_audio(&path, entry_id) && path != keep {
+            let _ = fs::remove_file(&path);
+        }
+    }
+}
+
+async fn run(command: &mut Command, tool: &'static str, hint: &'static str) -> Result<()> {
+    let output = match command.output().await {
+        Ok(output) => output,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Err(MissingTool { tool, hint }.into()),
+        Err(e) => return Err(e).with_context(|| format!("Could not run {}", tool)),
+    };
+    if !output.status.success() {
+        anyhow::bail!("{} failed: {}", tool, String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(())
+}
+
+async fn speak_system(text: &str, voice: &str, dir: &Path, out: &Path) -> Result<()> {
+    // Long entries go through a file rather than the command line
+    let input = dir.join(format!("{}.txt", uuid::Uuid::new_v4()));
+    fs::write(&input, text)?;
+    let result = speak_system_file(&input, voice, out).await;
+    let _ = fs::remove_file(&input);
+    result
+}
+
+#[cfg(target_os = "macos")]
+async fn speak_system_file(input: &Path, voice: &str, out: &Path) -> Result<()> {
+    let mut command = Command::new("say");
+    command.arg("--file-format=WAVE").arg("--data-format=LEI16@22050").arg("-o").arg(out).arg("-f").arg(input);
+    if !voice.is_empty() {
+        command.arg("-v").arg(voice);
+    }
+    run(&mut command, "say", "it comes with macOS; choose OpenAI as the read-aloud engine instead").await
+}
+
+#[cfg(target_os = "windows")]
+async fn speak_system_file(input: &Path, voice: &str, out: &Path) -> Result<()> {
+    let quote = |s: &str| format!("'{}'", s.replace('\'', "''"));
+    let select = if voice.is_empty() { String::new() } else { format!("$s.SelectVoice({}); ", quote(voice)) };
+    let script = format!(
+        "Add-Type -AssemblyName System.Speech; $s = New-Object System.Speech.Synthesis.SpeechSynthesizer; {}\
+         $s.SetOutputToWaveFile({}); $s.Speak([IO.File]::ReadAllText({})); $s.Dispose()",
+        select,
+        quote(&out.to_string_lossy()),
+        quote(&input.to_string_lossy()),
+    );
+    let mut command = Command::new("powershell");
+    command.args(["-NoProfile", "-NonInteractive", "-Command", &script]);
+    run(&mut command, "PowerShell", "choose OpenAI as the read-aloud engine instead").await
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+async fn speak_system_file(input: &Path, voice: &str, out: &Path) -> Result<()> {
+    let hint = "install espeak-ng (or espeak) to read entries aloud, or choose OpenAI as the read-aloud engine";
+    for tool in ["espeak-ng", "espeak"] {
+        let mut command = Command::new(tool);
+        command.arg("-w").arg(out).arg("-f").arg(input);
+        if !voice.is_empty() {
+            command.arg("-v").arg(voice);
+        }
+        match run(&mut command, tool, hint).await {
+            Err(e) if e.is::<MissingTool>() => continue,
+            result => return result,
+        }
+    }
+    Err(MissingTool { tool: "espeak-ng", hint }.into())
+}
+
+async fn speak_openai(settings: &AiSettings, text: &str, voice: &str, out: &Path) -> Result<()> {
+    let api_key = settings.openai_api_key.as_deref().context("OpenAI API key is not set")?;
+    let voice = if voice.is_empty() { OPENAI_DEFAULT_VOICE } else { voice };
+    let client = reqwest::Client::new();
+    let mut audio = Vec::new();
+    // MP3 frames can simply be appended, so each piece is its own request
+    for piece in pieces(text, OPENAI_MAX_CHARS) {
+        let request = client
+            .post("https://api.openai.com/v1/audio/speech")
+            .header("Authorization", format!("Bearer {}", api_key))
+            .json(&serde_json::json!({ "model": OPENAI_MODEL, "voice": voice, "input": piece, "response_format": "mp3" }));
+        let response = crate::ai_http::send(&Provider::OpenAI, request).await?;
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            anyhow::bail!("OpenAI API error: {}", error_text);
+        }
+        audio.extend_from_slice(&response.bytes().await?);
+    }
+    fs::write(out, audio)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_spoken_text_and_file_names() {
+        let entry = Entry {
+            id: "e1".into(),
+            title: Some("Beach day.".into()),
+            body: " Sand everywhere. \n".into(),
+            entry_date: chrono::Utc.with_ymd_and_hms(2021, 3, 5, 12, 0, 0).unwrap(),
+            entry_timezone: "UTC".into(),
+            source_path: String::new(),
+            source_type: "txt".into(),
+            text_hash: String::new(),
+            embedding: None,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+            sentiment: None,
+            language: None,
+        };
+        let text = spoken_text(&entry, &crate::locale::DateFormat::default());
+        assert_eq!(text, "Beach day.\n\nFriday, March 5, 2021.\n\nSand everywhere.");
+
+        let name = file_name("e1", Engine::System, "", &text);
+        assert!(name.starts_with("e1-") && name.ends_with(".wav"));
+        assert_eq!(name, file_name("e1", Engine::System, "", &text));
+        assert_ne!(name, file_name("e1", Engine::System, "Samantha", &text));
+        assert!(file_name("e1", Engine::OpenAI, "", &text).ends_with(".mp3"));
+        assert_eq!(Engine::parse(" OpenAI "), Engine::OpenAI);
+        assert_eq!(Engine::parse("piper"), Engine::System);
+    }
+
+    #[test]
+    fn test_pieces_respect_the_limit() {
+        let text = "One. Two two. Three three three.\n\nFour.";
+        assert_eq!(pieces(text, 100), ["One. Two two. Three three three.\n\nFour."]);
+        assert_eq!(pieces(text, 14), ["One. Two two.", "Three three", "three.\n\nFour."]);
+        let long = "x".repeat(25);
+        assert_eq!(pieces(&long, 10), ["xxxxxxxxxx", "xxxxxxxxxx", "xxxxx"]);
+        assert!(pieces("", 10).is_empty());
+    }
+
+    #[test]
+    fn test_remove_older_keeps_the_current_file() {
+        let dir = std::env::temp_dir().join(format!("jr-audio-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        for name in ["e1-aaaa.wav", "e1-bbbb.mp3", "e1-x-cccc.wav", "e2-aaaa.wav"] {
+            fs::write(dir.join(name), b"").unwrap();
+        }
+        remove_older(&dir, "e1", &dir.join("e1-bbbb.mp3"));
+        let mut left: Vec<String> = fs::read_dir(&dir).unwrap().map(|e| e.unwrap().file_name().to_string_lossy().to_string()).collect();
+        left.sort();
+        assert_eq!(left, ["e1-bbbb.mp3", "e1-x-cccc.wav", "e2-aaaa.wav"]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,498 @@
+use anyhow::Result;
+use rusqlite::Connection;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::quantize::Quantization;
+
+// In-memory vector index over stored entry embeddings.
+//
+// Decoding every embedding BLOB per query is what made semantic search slow, not the math:
+// a flat scan over pre-normalized vectors in one contiguous buffer is a few million
+// multiply-adds for 20k entries, i.e. single-digit milliseconds. The index is loaded once
+// per (database, model) and kept up to date in place: writers name the entries whose vectors
+// changed with `invalidate_entries()`, and the next query re-reads just those entries' rows and
+// swaps them in. Only `invalidate()`, for changes to the whole database (another file, a model
+// or vector format switch), makes the next query load everything again.
+//
+// There are two indexes: one vector per entry (similar entries, clustering) and one per chunk
+// (search), where an entry appears once for each of its chunks.
+
+struct Slot {
+    index: Option<Arc<VectorIndex>>,
+    // Entries changed since the index was loaded
+    pending: BTreeSet<String>,
+}
+
+static INDEX: Mutex<Slot> = Mutex::new(Slot { index: None, pending: BTreeSet::new() });
+static CHUNK_INDEX: Mutex<Slot> = Mutex::new(Slot { index: None, pending: BTreeSet::new() });
+static VERSION: AtomicU64 = AtomicU64::new(0);
+
+// More pending entries than this share of the index are cheaper to load from scratch
+const MAX_PENDING_SHARE: usize = 4;
+
+#[derive(Clone)]
+pub struct VectorIndex {
+    db_path: PathBuf,
+    model_key: String,
+    version: u64,
+    dim: usize,
+    ids: Vec<String>,
+    vectors: Vectors,
+}
+
+// ids.len() unit-length vectors, row-major. Quantized indexes are scanned with integer math
+// first and the best RESCORE_FACTOR * k candidates rescored against the full-precision query.
+#[derive(Clone)]
+enum Vectors {
+    F32(Vec<f32>),
+    Int8 { data: Vec<i8>, scales: Vec<f32> },
+    // `words` u64s of sign bits per row
+    Binary { bits: Vec<u64>, words: usize },
+}
+
+const RESCORE_FACTOR: usize = 4;
+
+// Call when the database file, the model or the stored vector format changes
+pub fn invalidate() {
+    VERSION.fetch_add(1, Ordering::SeqCst);
+}
+
+// Call after writing, removing or outdating the vectors of these entries
+pub fn invalidate_entries<S: AsRef<str>>(ids: impl IntoIterator<Item = S>) {
+    let ids: Vec<String> = ids.into_iter().map(|id| id.as_ref().to_string()).collect();
+    for slot in [&INDEX, &CHUNK_INDEX] {
+        slot.lock().unwrap_or_else(|e| e.into_inner()).pending.extend(ids.iter().cloned());
+    }
+}
+
+fn normalize(v: &mut [f32]) -> bool {
+    let norm = v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm == 0.0 || !norm.is_finite() {
+        return false;
+    }
+    for x in v.iter_mut() {
+        *x /= norm;
+    }
+    true
+}
+
+fn bit_words(v: &[f32]) -> impl Iterator<Item = u64> + '_ {
+    v.chunks(64).map(|c| c.iter().enumerate().fold(0u64, |w, (i, x)| if *x > 0.0 { w | 1 << i } else { w }))
+}
+
+fn by_score(a: &(usize, f32), b: &(usize, f32)) -> std::cmp::Ordering {
+    b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal)
+}
+
+// The `k` best of `scored`, best first
+fn top(mut scored: Vec<(usize, f32)>, k: usize) -> Vec<(usize, f32)> {
+    if scored.len() > k {
+        scored.select_nth_unstable_by(k - 1, by_score);
+        scored.truncate(k);
+    }
+    scored.sort_by(by_score);
+    scored
+}
+
+impl VectorIndex {
+    pub fn build(
+        db_path: &Path,
+        model_key: &str,
+        version: u64,
+        rows: Vec<(String, Vec<f32>)>,
+        quantization: Quantization,
+    ) -> Self {
+        // A model switch mid-backfill can leave mixed dimensions; keep the majority
+        let dim = {
+            let mut counts = std::collections::HashMap::new();
+            for (_, v) in &rows {
+                *counts.entry(v.len()).or_insert(0usize) += 1;
+            }
+            counts.into_iter().max_by_key(|(_, n)| *n).map(|(d, _)| d).unwrap_or(0)
+        };
+        let vectors = match quantization {
+            Quantization::None => Vectors::F32(Vec::with_capacity(rows.len() * dim)),
+            Quantization::Int8 => Vectors::Int8 { data: Vec::with_capacity(rows.len() * dim), scales: Vec::new() },
+            Quantization::Binary => Vectors::Binary { bits: Vec::new(), words: dim.div_ceil(64) },
+        };
+        let mut index = Self {
+            db_path: db_path.to_path_buf(),
+            model_key: model_key.to_string(),
+            version,
+            dim,
+            ids: Vec::with_capacity(rows.len()),
+            vectors,
+        };
+        for (id, v) in rows {
+            index.push(id, v);
+        }
+        index
+    }
+
+    fn push(&mut self, id: String, mut v: Vec<f32>) {
+        if v.len() != self.dim || !normalize(&mut v) {
+            return;
+        }
+        self.ids.push(id);
+        match &mut self.vectors {
+            Vectors::F32(data) => data.extend_from_slice(&v),
+            Vectors::Int8 { data, scales } => {
+                let scale = crate::quantize::int8_scale(&v);
+                data.extend(crate::quantize::to_int8(&v, scale));
+                scales.push(scale);
+            }
+            Vectors::Binary { bits, .. } => bits.extend(bit_words(&v)),
+        }
+    }
+
+    // Replaces every row of `changed` entries with `rows`, their current vectors (none for an
+    // entry that was deleted or no longer has a current embedding)
+    pub fn update(&mut self, changed: &BTreeSet<String>, rows: Vec<(String, Vec<f32>)>) {
+        let dim = self.dim;
+        let mut kept = 0;
+        for i in 0..self.ids.len() {
+            if changed.contains(&self.ids[i]) {
+                continue;
+            }
+            if kept != i {
+                self.ids.swap(kept, i);
+                match &mut self.vectors {
+                    Vectors::F32(data) => data.copy_within(i * dim..(i + 1) * dim, kept * dim),
+                    Vectors::Int8 { data, scales } => {
+                        data.copy_within(i * dim..(i + 1) * dim, kept * dim);
+                        scales[kept] = scales[i];
+                    }
+                    Vectors::Binary { bits, words } => bits.copy_within(i * *words..(i + 1) * *words, kept * *words),
+                }
+            }
+            kept += 1;
+        }
+        self.ids.truncate(kept);
+        match &mut self.vectors {
+            Vectors::F32(data) => data.truncate(kept * dim),
+            Vectors::Int8 { data, scales } => {
+                data.truncate(kept * dim);
+                scales.truncate(kept);
+            }
+            Vectors::Binary { bits, words } => bits.truncate(kept * *words),
+        }
+        for (id, v) in rows {
+            self.push(id, v);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    // Top `k` entries by cosine similarity, best first
+    pub fn search(&self, query: &[f32], k: usize) -> Vec<(String, f32)> {
+        if self.is_empty() || query.len() != self.dim || k == 0 {
+            return Vec::new();
+        }
+        let mut q = query.to_vec();
+        if !normalize(&mut q) {
+            return Vec::new();
+        }
+
+        let candidates = k.saturating_mul(RESCORE_FACTOR);
+        let scored = match &self.vectors {
+            Vectors::F32(data) => top(
+                data.chunks_exact(self.dim)
+                    .enumerate()
+                    .map(|(i, row)| (i, row.iter().zip(&q).map(|(a, b)| a * b).sum::<f32>()))
+                    .collect(),
+                k,
+            ),
+            Vectors::Int8 { data, scales } => {
+                // The query's own scale is the same for every row, so it can't change the order
+                let qq: Vec<i8> = crate::quantize::to_int8(&q, crate::quantize::int8_scale(&q)).collect();
+                let rows = data.chunks_exact(self.dim);
+                let first = rows
+                    .enumerate()
+                    .map(|(i, row)| (i, row.iter().zip(&qq).map(|(a, b)| *a as i32 * *b as i32).sum::<i32>() as f32 * scales[i]))
+                    .collect();
+                let rescored = top(first, candidates)
+                    .into_iter()
+                    .map(|(i, _)| {
+                        let row = &data[i * self.dim..(i + 1) * self.dim];
+                        (i, row.iter().zip(&q).map(|(a, b)| *a as f32 * b).sum::<f32>() * scales[i])
+                    })
+                    .collect();
+                top(rescored, k)
+            }
+            Vectors::Binary { bits, words } => {
+                let qbits: Vec<u64> = bit_words(&q).collect();
+                let first = bits
+                    .chunks_exact(*words)
+                    .enumerate()
+                    .map(|(i, row)| (i, -(row.iter().zip(&qbits).map(|(a, b)| (a ^ b).count_ones()).sum::<u32>() as f32)))
+                    .collect();
+                // The stored signs as a ±1/√dim vector against the full query
+                let unit = 1.0 / (self.dim as f32).sqrt();
+                let rescored = top(first, candidates)
+                    .into_iter()
+                    .map(|(i, _)| {
+                        let row = &bits[i * words..(i + 1) * words];
+                        let dot: f32 = q
+                            .iter()
+                            .enumerate()
+                            .map(|(j, x)| if row[j / 64] >> (j % 64) & 1 == 1 { *x } else { -*x })
+                            .sum();
+                        (i, dot * unit)
+                    })
+                    .collect();
+                top(rescored, k)
+            }
+        };
+        scored.into_iter().map(|(i, s)| (self.ids[i].clone(), s)).collect()
+    }
+
+    // Every indexed entry with its unit-length vector (full-precision indexes only)
+    pub fn rows(&self) -> impl Iterator<Item = (&str, &[f32])> {
+        let data: &[f32] = match &self.vectors {
+            Vectors::F32(data) => data,
+            _ => &[],
+        };
+        self.ids.iter().map(String::as_str).zip(data.chunks_exact(self.dim.max(1)))
+    }
+
+    // Nearest neighbours of an indexed entry, excluding the entry itself.
+    // None when the entry has no current embedding.
+    pub fn similar_to(&self, id: &str, k: usize) -> Option<Vec<(String, f32)>> {
+        let row = self.ids.iter().position(|x| x == id)?;
+        let vector: Vec<f32> = match &self.vectors {
+            Vectors::F32(data) => data[row * self.dim..(row + 1) * self.dim].to_vec(),
+            Vectors::Int8 { data, scales } => data[row * self.dim..(row + 1) * self.dim].iter().map(|x| *x as f32 * scales[row]).collect(),
+            Vectors::Binary { bits, words } => {
+                let row = &bits[row * words..(row + 1) * words];
+                (0..self.dim).map(|j| if row[j / 64] >> (j % 64) & 1 == 1 { 1.0 } else { -1.0 }).collect()
+            }
+        };
+        let hits = self.search(&vector, k + 1);
+        Some(hits.into_iter().filter(|(hit, _)| hit != id).take(k).collect())
+    }
+}
+
+fn cached(
+    slot: &Mutex<Slot>,
+    db_path: &Path,
+    model_key: &str,
+    quantization: Quantization,
+    load: impl FnOnce() -> Result<Vec<(String, Vec<f32>)>>,
+    load_some: impl FnOnce(&[String]) -> Result<Vec<(String, Vec<f32>)>>,
+) -> Result<Arc<VectorIndex>> {
+    let model_key = match quantization {
+        Quantization::None => model_key.to_string(),
+        q => format!("{}~{}", model_key, q.as_str()),
+    };
+    let model_key = model_key.as_str();
+    let version = VERSION.load(Ordering::SeqCst);
+    let covered = {
+        let mut guard = slot.lock().unwrap_or_else(|e| e.into_inner());
+        let Slot { index, pending } = &mut *guard;
+        if let Some(index) = index.as_mut() {
+            if index.version == version && index.model_key == model_key && index.db_path == db_path {
+                if pending.is_empty() {
+                    return Ok(index.clone());
+                }
+                if pending.len() <= index.len() / MAX_PENDING_SHARE {
+                    // Under the lock, so concurrent queries don't apply the same change twice.
+                    // On error the ids stay pending for the next query.
+                    let ids: Vec<String> = pending.iter().cloned().collect();
+                    let rows = load_some(&ids)?;
+                    let changed = std::mem::take(pending);
+                    Arc::make_mut(index).update(&changed, rows);
+                    tracing::debug!("updated index model={} entries={}", model_key, changed.len());
+                    return Ok(index.clone());
+                }
+            }
+        }
+        // Everything changed so far is covered by the full load below
+        std::mem::take(pending)
+    };
+
+    let started = std::time::Instant::now();
+    let rows = match load() {
+        Ok(rows) => rows,
+        Err(e) => {
+            slot.lock().unwrap_or_else(|e| e.into_inner()).pending.extend(covered);
+            return Err(e);
+        }
+    };
+    let index = Arc::new(VectorIndex::build(db_path, model_key, version, rows, quantization));
+    tracing::info!(
+        "loaded index model={} vectors={} dim={} in {}ms",
+        model_key,
+        index.len(),
+        index.dim,
+        started.elapsed().as_millis()
+    );
+
+    let mut guard = slot.lock().unwrap_or_else(|e| e.into_inner());
+    guard.index = Some(index.clone());
+    Ok(index)
+}
+
+// The current entry index for this database and model, loading it if stale
+pub fn get_or_load(conn: &Connection, db_path: &Path, model_key: &str) -> Result<Arc<VectorIndex>> {
+    cached(
+        &INDEX,
+        db_path,
+        model_key,
+        Quantization::None,
+        || crate::embeddings::load_embeddings(conn, model_key),
+        |ids| crate::embeddings::load_entry_embeddings(conn, model_key, ids),
+    )
+}
+
+// The current chunk index, quantized like the stored chunks; results can name an entry more than once
+pub fn get_or_load_chunks(conn: &Connection, db_path: &Path, config: &crate::embeddings::EmbeddingConfig) -> Result<Arc<VectorIndex>> {
+    let (model_key, embedding_key) = (config.model_key(), config.embedding_key());
+    let quantization = Quantization::load(conn);
+    cached(
+        &CHUNK_INDEX,
+        db_path,
+        &embedding_key,
+        quantization,
+        || crate::chunks::load_embeddings(conn, &model_key, &embedding_key),
+        |ids| crate::chunks::load_entry_embeddings(conn, &model_key, &embedding_key, ids),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_search_ranks_by_cosine() {
+        let rows = vec![
+            ("a".to_string(), vec![1.0, 0.0, 0.0]),
+            ("b".to_string(), vec![0.0, 2.0, 0.0]),
+            ("c".to_string(), vec![1.0, 1.0, 0.0]),
+            ("zero".to_string(), vec![0.0, 0.0, 0.0]),
+            ("wrong-dim".to_string(), vec![1.0, 0.0]),
+        ];
+        let index = VectorIndex::build(Path::new("/tmp/x.db"), "m", 0, rows, Quantization::None);
+        assert_eq!(index.len(), 3);
+
+        let hits = index.search(&[0.0, 3.0, 0.0], 2);
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].0, "b");
+        assert!((hits[0].1 - 1.0).abs() < 1e-6);
+        assert_eq!(hits[1].0, "c");
+
+        assert!(index.search(&[1.0, 0.0], 2).is_empty());
+
+        let similar = index.similar_to("a", 5).unwrap();
+        assert_eq!(similar.iter().map(|(id, _)| id.as_str()).collect::<Vec<_>>(), vec!["c", "b"]);
+        assert!(index.similar_to("zero", 5).is_none());
+    }
+
+    #[test]
+    fn test_update_replaces_changed_entries() {
+        for quantization in [Quantization::None, Quantization::Int8, Quantization::Binary] {
+            let rows = vec![
+                ("a".to_string(), vec![1.0, 0.0, 0.0]),
+                ("b".to_string(), vec![0.0, 1.0, 0.0]),
+                ("b".to_string(), vec![0.0, 1.0, 1.0]),
+                ("c".to_string(), vec![0.0, 0.0, 1.0]),
+            ];
+            let mut index = VectorIndex::build(Path::new("/tmp/x.db"), "m", 0, rows, quantization);
+            let changed: BTreeSet<String> = ["a", "b", "d"].iter().map(|s| s.to_string()).collect();
+            // a moves, b is gone, d is new
+            index.update(&changed, vec![("a".to_string(), vec![0.0, 0.0, 1.0]), ("d".to_string(), vec![1.0, 0.0, 0.0])]);
+
+            assert_eq!(index.ids, vec!["c", "a", "d"]);
+            let hits = index.search(&[1.0, 0.0, 0.0], 1);
+            assert_eq!(hits[0].0, "d");
+            assert!(index.search(&[0.0, 1.0, 0.0], 3).iter().all(|(id, _)| id != "b"));
+            assert_eq!(index.similar_to("c", 1).unwrap()[0].0, "a");
+        }
+    }
+
+    #[test]
+    fn test_failed_update_keeps_entries_pending() {
+        static SLOT: Mutex<Slot> = Mutex::new(Slot { index: None, pending: BTreeSet::new() });
+        let path = Path::new("/tmp/x.db");
+        let all = || Ok((0..8).filter(|i| *i != 3).map(|i| (i.to_string(), vec![1.0, i as f32])).collect());
+        let fail = || Err(anyhow::anyhow!("database is locked"));
+        SLOT.lock().unwrap().index = Some(Arc::new(VectorIndex::build(
+            path,
+            "m",
+            VERSION.load(Ordering::SeqCst),
+            (0..8).map(|i| (i.to_string(), vec![1.0, i as f32])).collect(),
+            Quantization::None,
+        )));
+        SLOT.lock().unwrap().pending.insert("3".to_string());
+
+        assert!(cached(&SLOT, path, "m", Quantization::None, fail, |_| fail()).is_err());
+        assert!(SLOT.lock().unwrap().pending.contains("3"));
+
+        let index = cached(&SLOT, path, "m", Quantization::None, all, |ids| {
+            assert_eq!(ids, ["3"]);
+            Ok(vec![])
+        })
+        .unwrap();
+        assert!(SLOT.lock().unwrap().pending.is_empty());
+        assert_eq!(index.len(), 7);
+        assert!(!index.ids.contains(&"3".to_string()));
+    }
+
+    #[test]
+    fn test_search_scales_to_large_corpus() {
+        let dim = 384;
+        let rows: Vec<(String, Vec<f32>)> = (0..20_000)
+            .map(|i| {
+                let v = (0..dim).map(|j| ((i * 31 + j * 7) % 97) as f32 - 48.0).collect();
+                (i.to_string(), v)
+            })
+            .collect();
+        let query = rows[1234].1.clone();
+        let index = VectorIndex::build(Path::new("/tmp/x.db"), "m", 0, rows, Quantization::None);
+
+        let hits = index.search(&query, 10);
+        assert_eq!(hits.len(), 10);
+        assert!((hits[0].1 - 1.0).abs() < 1e-4);
+        assert!(hits.windows(2).all(|w| w[0].1 >= w[1].1));
+    }
+
+    #[test]
+    fn test_quantized_search_rescores() {
+        let dim = 256;
+        let mut seed = 7u64;
+        let mut noise = move || {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (seed >> 40) as f32 / (1u64 << 24) as f32 - 0.5
+        };
+        let mut rows: Vec<(String, Vec<f32>)> =
+            (0..2_000).map(|i| (i.to_string(), (0..dim).map(|_| noise()).collect())).collect();
+        // Neighbours of the query at clearly different distances, so the ranking is well defined
+        for k in 1..=4 {
+            let v = rows[1234].1.iter().map(|x| x + k as f32 * 0.4 * noise()).collect();
+            rows[1234 + k].1 = v;
+        }
+        let exact = VectorIndex::build(Path::new("/tmp/x.db"), "m", 0, rows.clone(), Quantization::None);
+        let query = rows[1234].1.clone();
+        let expected = exact.search(&query, 5);
+        for q in [Quantization::Int8, Quantization::Binary] {
+            let index = VectorIndex::build(Path::new("/tmp/x.db"), "m", 0, rows.clone(), q);
+            let hits = index.search(&query, 5);
+            assert_eq!(hits[0].0, "1234", "{:?}", q);
+            assert!(hits.windows(2).all(|w| w[0].1 >= w[1].1));
+            assert_eq!(index.rows().count(), 0);
+            if q == Quantization::Int8 {
+                // Close enough to full precision that the ranking holds
+                assert_eq!(hits.iter().map(|h| &h.0).collect::<Vec<_>>(), expected.iter().map(|h| &h.0).collect::<Vec<_>>());
+                assert!((hits[1].1 - expected[1].1).abs() < 0.01);
+            }
+        }
+    }
+}
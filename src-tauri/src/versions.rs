@@ -0,0 +1,183 @@
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use similar::{ChangeTag, TextDiff};
+
+// Edit history of entries and word-level diffs between revisions.
+//
+// Every edit through `database::set_entry_text` first copies the entry's previous title and
+// body into `entry_versions`, numbered from 1 per entry and dated by the entry's `updated_at`
+// at the time. The entry's current text counts as the version after the last stored one.
+// Sensitive entries keep no versions: sealing one deletes its history with everything else
+// derived from the plaintext.
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EntryVersion {
+    pub version: i64,
+    pub title: Option<String>,
+    pub saved_at: String,
+    pub words: usize,
+    pub current: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DiffSegment {
+    // "equal", "insert" or "delete"
+    pub op: &'static str,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EntryDiff {
+    pub from: i64,
+    pub to: i64,
+    pub title_from: Option<String>,
+    pub title_to: Option<String>,
+    pub segments: Vec<DiffSegment>,
+    pub words_added: usize,
+    pub words_removed: usize,
+}
+
+// Keeps the entry's current text as a version, unless the edit leaves it unchanged
+pub(crate) fn record(conn: &Connection, entry_id: &str, title: Option<&str>, body: &str) -> Result<()> {
+    conn.execute(
+        r#"INSERT INTO entry_versions (entry_id, version, title, body, saved_at)
+            SELECT id, (SELECT IFNULL(MAX(version), 0) + 1 FROM entry_versions WHERE entry_id = ?1), title, body, updated_at
+            FROM entries WHERE id = ?1 AND sensitive = 0 AND (body IS NOT ?3 OR title IS NOT ?2)"#,
+        params![entry_id, title, body],
+    )?;
+    Ok(())
+}
+
+// (title, body, saved_at) of a version; the one after the last stored version is the entry itself
+fn version_text(conn: &Connection, entry_id: &str, version: i64) -> Result<(Option<String>, String, String)> {
+    let stored = conn
+        .query_row(
+            "SELECT title, body, saved_at FROM entry_versions WHERE entry_id = ?1 AND version = ?2",
+            params![entry_id, version],
+            |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)),
+        )
+        .optional()?;
+    if let Some(text) = stored {
+        return Ok(text);
+    }
+    if version == latest(conn, entry_id)? {
+        return conn
+            .query_row("SELECT title, body, updated_at FROM entries WHERE id = ?1", params![entry_id], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)))
+            .optional()?
+            .context("Entry not found");
+    }
+    anyhow::bail!("Entry has no version {}", version)
+}
+
+fn latest(conn: &Connection, entry_id: &str) -> Result<i64> {
+    Ok(conn.query_row("SELECT IFNULL(MAX(version), 0) + 1 FROM entry_versions WHERE entry_id = ?1", params![entry_id], |r| r.get(0))?)
+}
+
+// Oldest first, ending with the current text
+pub fn list(conn: &Connection, entry_id: &str) -> Result<Vec<EntryVersion>> {
+    let mut stmt = conn.prepare_cached("SELECT version, title, body, saved_at FROM entry_versions WHERE entry_id = ?1 ORDER BY version")?;
+    let mut versions: Vec<EntryVersion> = stmt
+        .query_map(params![entry_id], |r| {
+            Ok(EntryVersion { version: r.get(0)?, title: r.get(1)?, saved_at: r.get(3)?, words: r.get::<_, String>(2)?.split_whitespace().count(), current: false })
+        })?
+        .collect::<rusqlite::Result<_>>()?;
+    let current = latest(conn, entry_id)?;
+    let (title, body, saved_at) = version_text(conn, entry_id, current)?;
+    versions.push(EntryVersion { version: current, title, saved_at, words: body.split_whitespace().count(), current: true });
+    Ok(versions)
+}
+
+// Runs of unchanged, inserted and deleted text. A space left unchanged between two changed
+// words joins the change on both sides, so "Rain all day." → "Rain, then sun." reads as one
+// replacement rather than three.
+pub fn diff_words(old: &str, new: &str) -> Vec<DiffSegment> {
+    let diff = TextDiff::configure().timeout(std::time::Duration::from_secs(2)).diff_words(old, new);
+    let mut segments: Vec<DiffSegment> = Vec::new();
+    // Pending deleted and inserted text, and whether each has more than joined spaces
+    let (mut deleted, mut inserted) = ((String::new(), false), (String::new(), false));
+    let mut gap = String::new();
+    let flush = |segments: &mut Vec<DiffSegment>, deleted: &mut (String, bool), inserted: &mut (String, bool)| {
+        for (op, (text, changed)) in [("delete", deleted), ("insert", inserted)] {
+            if *changed {
+                segments.push(DiffSegment { op, text: std::mem::take(text) });
+            }
+            text.clear();
+            *changed = false;
+        }
+    };
+    for change in diff.iter_all_changes() {
+        let value = change.value();
+        let pending = deleted.1 || inserted.1;
+        match change.tag() {
+            ChangeTag::Equal if pending && value.trim().is_empty() => gap.push_str(value),
+            ChangeTag::Equal => {
+                flush(&mut segments, &mut deleted, &mut inserted);
+                let text = std::mem::take(&mut gap) + value;
+                match segments.last_mut() {
+                    Some(last) if last.op == "equal" => last.text.push_str(&text),
+                    _ => segments.push(DiffSegment { op: "equal", text }),
+                }
+            }
+            tag => {
+                for side in [&mut deleted, &mut inserted] {
+                    side.0.push_str(&gap);
+                }
+                gap.clear();
+                let side = if tag == ChangeTag::Delete { &mut deleted } else { &mut inserted };
+                side.0.push_str(value);
+                side.1 = true;
+            }
+        }
+    }
+    flush(&mut segments, &mut deleted, &mut inserted);
+    if !gap.is_empty() {
+        segments.push(DiffSegment { op: "equal", text: gap });
+    }
+    segments
+}
+
+pub fn diff(conn: &Connection, entry_id: &str, from: i64, to: i64) -> Result<EntryDiff> {
+    let (title_from, old, _) = version_text(conn, entry_id, from)?;
+    let (title_to, new, _) = version_text(conn, entry_id, to)?;
+    let segments = diff_words(&old, &new);
+    let count = |op: &str| segments.iter().filter(|s| s.op == op).map(|s| s.text.split_whitespace().count()).sum();
+    Ok(EntryDiff { from, to, title_from, title_to, words_added: count("insert"), words_removed: count("delete"), segments })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_versions_and_diff() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::migrations::run_migrations(&mut conn).unwrap();
+        conn.execute_batch(
+            r#"INSERT INTO entries (id, title, body, entry_date, entry_timezone, source_path, source_type, text_hash, created_at, updated_at)
+               VALUES ('a', 'Walk', 'Rain all day. Read by the window.', '2024-01-01T00:00:00Z', 'UTC', '', 'txt', 'h1', '2024-01-01T00:00:00Z', '2024-01-01T00:00:00Z');"#,
+        )
+        .unwrap();
+        crate::database::set_entry_text(&conn, "a", Some("Walk".into()), "Rain all day. Read by the window.").unwrap();
+        assert_eq!(list(&conn, "a").unwrap().len(), 1);
+
+        crate::database::set_entry_text(&conn, "a", Some("Walk".into()), "Rain, then sun. Read by the window.").unwrap();
+        crate::database::set_entry_text(&conn, "a", Some("Sunny walk".into()), "Rain, then sun. Walked to the lake.").unwrap();
+        let versions = list(&conn, "a").unwrap();
+        assert_eq!(versions.iter().map(|v| (v.version, v.current)).collect::<Vec<_>>(), [(1, false), (2, false), (3, true)]);
+
+        let d = diff(&conn, "a", 1, 2).unwrap();
+        assert_eq!(
+            d.segments,
+            [
+                DiffSegment { op: "delete", text: "Rain all day.".into() },
+                DiffSegment { op: "insert", text: "Rain, then sun.".into() },
+                DiffSegment { op: "equal", text: " Read by the window.".into() },
+            ]
+        );
+        assert_eq!((d.words_added, d.words_removed), (3, 3));
+        let d = diff(&conn, "a", 2, 3).unwrap();
+        assert_eq!((d.title_from.as_deref(), d.title_to.as_deref()), (Some("Walk"), Some("Sunny walk")));
+        assert!(diff(&conn, "a", 1, 9).is_err());
+    }
+}
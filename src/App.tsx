@@ -1,6 +1,7 @@
 import { useState, useEffect } from "react";
 import { invoke } from "@tauri-apps/api/core";
-import { BookOpen, Settings as SettingsIcon, Calendar, FileText, Search as SearchIcon } from "lucide-react";
+import { listen } from "@tauri-apps/api/event";
+import { BookOpen, Settings as SettingsIcon, Calendar, FileText, Search as SearchIcon, Plus, History, PenLine } from "lucide-react";
 import "./App.css";
 
 import { Button } from "@/components/ui/button";
@@ -9,16 +10,64 @@ import { Settings } from "@/components/Settings";
 import { Import } from "@/components/Import";
 import { Timeline } from "@/components/Timeline";
 import { Search } from "@/components/Search";
+import { OnThisDay } from "@/components/OnThisDay";
+import { Write } from "@/components/Write";
+import { errorMessage } from "@/lib/errors";
+import { loadDateLocale } from "@/lib/dates";
+
+interface Journal {
+  id: string;
+  name: string;
+  path: string;
+}
 
 function App() {
   const [isInitialized, setIsInitialized] = useState(false);
-  const [currentView, setCurrentView] = useState<'timeline' | 'search' | 'import' | 'settings'>('timeline');
+  const [currentView, setCurrentView] = useState<'timeline' | 'on-this-day' | 'write' | 'search' | 'import' | 'settings'>('timeline');
+  const [onThisDayDate, setOnThisDayDate] = useState<string | undefined>(undefined);
+  const [journals, setJournals] = useState<Journal[]>([]);
+  const [activeJournal, setActiveJournal] = useState<string>("default");
+
+  const loadJournals = async () => {
+    try {
+      const list = await invoke<{ active: string, journals: Journal[] }>("list_journals");
+      setJournals(list.journals);
+      setActiveJournal(list.active);
+    } catch (error) {
+      console.error("Failed to load journals:", error);
+    }
+  };
+
+  const switchJournal = async (id: string) => {
+    if (id === activeJournal) return;
+    try {
+      await invoke("switch_journal", { id });
+      await loadDateLocale();
+      setActiveJournal(id);
+    } catch (error) {
+      alert(`Could not switch journal: ${errorMessage(error)}`);
+    }
+  };
+
+  const createJournal = async () => {
+    const name = prompt("Name for the new journal:")?.trim();
+    if (!name) return;
+    try {
+      const journal = await invoke<Journal>("create_journal", { name });
+      await loadJournals();
+      await switchJournal(journal.id);
+    } catch (error) {
+      alert(`Could not create journal: ${errorMessage(error)}`);
+    }
+  };
 
   useEffect(() => {
     // Initialize the database on startup
     const initApp = async () => {
       try {
         await invoke("init_database");
+        await loadDateLocale();
+        await loadJournals();
         setIsInitialized(true);
       } catch (error) {
         console.error("Failed to initialize app:", error);
@@ -28,6 +77,17 @@ function App() {
     initApp();
   }, []);
 
+  useEffect(() => {
+    // Sent when the app is focused from an "on this day" notification
+    const unlisten = listen<{ view: string, date: string }>("app://navigate", (event) => {
+      if (event.payload.view === "on-this-day") {
+        setOnThisDayDate(event.payload.date);
+        setCurrentView("on-this-day");
+      }
+    });
+    return () => { unlisten.then((f) => f()); };
+  }, []);
+
   if (!isInitialized) {
     return (
       <div className="flex items-center justify-center h-screen">
@@ -48,6 +108,22 @@ function App() {
             <BookOpen className="w-8 h-8 text-primary" />
             <h1 className="text-xl font-bold">Journal Reader</h1>
           </div>
+
+          <div className="flex gap-2 mb-6">
+            <select
+              className="flex-1 h-9 rounded-md border bg-background px-2 text-sm"
+              value={activeJournal}
+              onChange={(e) => switchJournal(e.target.value)}
+              title="Active journal"
+            >
+              {journals.map((j) => (
+                <option key={j.id} value={j.id}>{j.name}</option>
+              ))}
+            </select>
+            <Button variant="outline" size="icon" onClick={createJournal} title="New journal">
+              <Plus className="w-4 h-4" />
+            </Button>
+          </div>
           
           <nav className="space-y-2">
             <Button
@@ -58,6 +134,22 @@ function App() {
               <Calendar className="w-4 h-4 mr-2" />
               Timeline
             </Button>
+            <Button
+              variant={currentView === 'on-this-day' ? 'default' : 'ghost'}
+              className="w-full justify-start"
+              onClick={() => setCurrentView('on-this-day')}
+            >
+              <History className="w-4 h-4 mr-2" />
+              On This Day
+            </Button>
+            <Button
+              variant={currentView === 'write' ? 'default' : 'ghost'}
+              className="w-full justify-start"
+              onClick={() => setCurrentView('write')}
+            >
+              <PenLine className="w-4 h-4 mr-2" />
+              Write
+            </Button>
             <Button
               variant={currentView === 'search' ? 'default' : 'ghost'}
               className="w-full justify-start"
@@ -90,12 +182,15 @@ function App() {
       <div className="flex-1 flex flex-col">
         {(
           <header className="border-b bg-card px-6 py-4">
-            <h2 className="text-2xl font-semibold capitalize">{currentView}</h2>
+            <h2 className="text-2xl font-semibold capitalize">{currentView.replace(/-/g, ' ')}</h2>
           </header>
         )}
         
-        <main className="flex-1 overflow-auto">
+        {/* Keyed by journal so every view reloads its data after a switch */}
+        <main key={activeJournal} className="flex-1 overflow-auto">
           {currentView === 'timeline' && <div className="p-6"><TimelineView /></div>}
+          {currentView === 'on-this-day' && <div className="p-6"><OnThisDay date={onThisDayDate} /></div>}
+          {currentView === 'write' && <div className="p-6"><Write /></div>}
           {currentView === 'search' && <div className="p-6"><SearchView /></div>}
           {currentView === 'import' && <div className="p-6"><ImportView /></div>}
           {currentView === 'settings' && <div className="p-6"><SettingsView /></div>}
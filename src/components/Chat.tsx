@@ -30,6 +30,7 @@ import {
   Copy,
   RefreshCw
 } from "lucide-react";
+import { formatDateTime } from "@/lib/dates";
 
 interface Citation {
   entry_id: string;
@@ -59,6 +60,18 @@ interface RagResponse {
   model_used: string;
   conversation_id: string;
   message_id: string;
+  plan?: RetrievalPlan | null;
+}
+
+// Filters the question was turned into before searching
+interface RetrievalPlan {
+  date_from?: string | null;
+  date_to?: string | null;
+  tags: string[];
+  people: string[];
+  keywords: string;
+  anchor?: string | null;
+  method: string;
 }
 
 interface ConversationMessage {
@@ -67,6 +80,7 @@ interface ConversationMessage {
   content: string;
   citations?: Citation[];
   timestamp: string;
+  plan?: RetrievalPlan | null;
 }
 
 interface ConversationSummary {
@@ -113,7 +127,7 @@ export function Chat() {
     try {
       const [conversationsList, suggestions] = await Promise.all([
         invoke<ConversationSummary[]>("get_conversations_list"),
-        invoke<string[]>("get_suggested_questions")
+        invoke<string[]>("get_suggested_questions").catch(() => [] as string[])
       ]);
       
       setConversations(conversationsList);
@@ -179,6 +193,7 @@ export function Chat() {
         content: response.answer,
         citations: response.citations,
         timestamp: new Date().toISOString(),
+        plan: response.plan,
       };
       
       setMessages(prev => [...prev, aiMessage]);
@@ -242,7 +257,7 @@ export function Chat() {
   };
 
   const formatDate = (dateString: string) => {
-    return new Date(dateString).toLocaleDateString('en-US', {
+    return formatDateTime(dateString, {
       month: 'short',
       day: 'numeric',
       hour: '2-digit',
@@ -509,6 +524,28 @@ export function Chat() {
                     )}
                   </div>
                   
+                  {message.plan && (
+                    <div className="mt-2 flex flex-wrap items-center gap-2 text-xs text-muted-foreground">
+                      <span>Searched</span>
+                      {(message.plan.date_from || message.plan.date_to) && (
+                        <Badge variant="outline" className="flex items-center gap-1">
+                          <Calendar className="w-3 h-3" />
+                          {message.plan.date_from ?? "…"} – {message.plan.date_to ?? "…"}
+                          {message.plan.anchor && ` (after "${message.plan.anchor}")`}
+                        </Badge>
+                      )}
+                      {message.plan.tags.map((tag) => (
+                        <Badge key={tag} variant="outline" className="flex items-center gap-1">
+                          <Tag className="w-3 h-3" />
+                          {tag}
+                        </Badge>
+                      ))}
+                      {message.plan.people.map((person) => (
+                        <Badge key={person} variant="outline">{person}</Badge>
+                      ))}
+                    </div>
+                  )}
+
                   {/* Citations */}
                   {message.citations && message.citations.length > 0 && (
                     <div className="mt-3 space-y-2">
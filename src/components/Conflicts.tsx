@@ -0,0 +1,138 @@
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { Card, CardContent, CardDescription, CardHeader, CardTitle } from "@/components/ui/card";
+import { Button } from "@/components/ui/button";
+import { Badge } from "@/components/ui/badge";
+import { GitMerge } from "lucide-react";
+
+interface ConflictVersion {
+  title?: string;
+  body: string;
+  updated_at: string;
+  tags: string[];
+  sealed: boolean;
+}
+
+interface EntryConflict {
+  id: number;
+  entry_id: string;
+  source: string;
+  detected_at: string;
+  current?: ConflictVersion;
+  other: ConflictVersion;
+  merged_body: string;
+}
+
+type Resolution = { keep: "current" } | { keep: "other" } | { keep: "merged"; title?: string; body: string };
+
+const sourceLabel = (source: string) => {
+  const [kind, rest] = [source.split(":")[0], source.slice(source.indexOf(":") + 1)];
+  if (kind === "lan-sync") return `Synced from ${rest}`;
+  if (kind === "backup") return `Backup from ${new Date(rest).toLocaleString()}`;
+  return source;
+};
+
+// Entries edited in two places (LAN sync or a restored backup), one at a time: keep the
+// journal's version, take the other one, or edit a merge of both
+export function Conflicts() {
+  const [conflicts, setConflicts] = useState<EntryConflict[] | null>(null);
+  const [merging, setMerging] = useState<string | null>(null);
+  const [busy, setBusy] = useState(false);
+
+  const load = async () => {
+    try {
+      setConflicts(await invoke<EntryConflict[]>("list_conflicts"));
+    } catch (error) {
+      console.error("Failed to load conflicts:", error);
+    }
+  };
+
+  useEffect(() => {
+    load();
+  }, []);
+
+  const resolve = async (conflict: EntryConflict, resolution: Resolution) => {
+    try {
+      setBusy(true);
+      await invoke("resolve_conflict", { id: conflict.id, resolution });
+      setMerging(null);
+      setConflicts((prev) => (prev || []).filter((c) => c.id !== conflict.id));
+    } catch (error: any) {
+      alert(`Could not resolve conflict: ${error?.message ?? error}`);
+    } finally {
+      setBusy(false);
+    }
+  };
+
+  if (!conflicts || conflicts.length === 0) return null;
+  const conflict = conflicts[0];
+
+  const side = (label: string, version: ConflictVersion | undefined, keep: "current" | "other") => (
+    <div className="flex-1 border rounded-lg p-4 space-y-2 min-w-0">
+      <div className="text-xs text-muted-foreground">
+        {label}{version ? ` · edited ${new Date(version.updated_at).toLocaleString()}` : ""}
+      </div>
+      {version ? (
+        <>
+          {version.title && <div className="font-medium truncate">{version.title}</div>}
+          <p className="text-sm leading-relaxed whitespace-pre-wrap max-h-64 overflow-y-auto">
+            {version.sealed && !version.body ? "Sensitive entry; unlock sensitive entries to read it." : version.body}
+          </p>
+          {version.tags.length > 0 && <div className="text-xs text-muted-foreground">#{version.tags.join(" #")}</div>}
+        </>
+      ) : (
+        <p className="text-sm text-muted-foreground">Deleted on this device.</p>
+      )}
+      <Button size="sm" disabled={busy} onClick={() => resolve(conflict, { keep })}>
+        {keep === "current" ? (version ? "Keep this version" : "Keep it deleted") : "Use this version"}
+      </Button>
+    </div>
+  );
+
+  return (
+    <Card>
+      <CardHeader>
+        <CardTitle className="flex items-center gap-2">
+          <GitMerge className="w-5 h-5" />
+          Conflicts
+        </CardTitle>
+        <CardDescription>
+          Entries that were edited in two places. Nothing was lost: choose a version or merge them, and the result is synced to your other devices.
+        </CardDescription>
+      </CardHeader>
+      <CardContent className="space-y-3">
+        <div className="flex items-center justify-between">
+          <Badge variant="secondary">{sourceLabel(conflict.source)}</Badge>
+          <span className="text-sm text-muted-foreground">{conflicts.length} to review</span>
+        </div>
+        <div className="flex gap-4">
+          {side("In this journal", conflict.current, "current")}
+          {side("Other version", conflict.other, "other")}
+        </div>
+        {merging === null ? (
+          <Button variant="outline" size="sm" disabled={busy || (conflict.other.sealed && !conflict.other.body)} onClick={() => setMerging(conflict.merged_body)}>
+            Merge...
+          </Button>
+        ) : (
+          <div className="space-y-2">
+            <textarea
+              className="w-full min-h-40 rounded-md border bg-background p-3 text-sm"
+              value={merging}
+              onChange={(e) => setMerging(e.target.value)}
+            />
+            <div className="flex gap-2">
+              <Button
+                size="sm"
+                disabled={busy || !merging.trim()}
+                onClick={() => resolve(conflict, { keep: "merged", title: conflict.current?.title ?? conflict.other.title, body: merging })}
+              >
+                Save Merge
+              </Button>
+              <Button variant="ghost" size="sm" disabled={busy} onClick={() => setMerging(null)}>Cancel</Button>
+            </div>
+          </div>
+        )}
+      </CardContent>
+    </Card>
+  );
+}
@@ -0,0 +1,132 @@
+import { useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { Card, CardContent, CardDescription, CardHeader, CardTitle } from "@/components/ui/card";
+import { Button } from "@/components/ui/button";
+import { Badge } from "@/components/ui/badge";
+import { Copy } from "lucide-react";
+import { errorMessage } from "@/lib/errors";
+import { formatDate } from "@/lib/dates";
+
+interface NearDuplicateEntry {
+  id: string;
+  title?: string;
+  entry_date: string;
+  source_path: string;
+  source_type: string;
+  word_count: number;
+  snippet: string;
+}
+
+interface NearDuplicatePair {
+  a: NearDuplicateEntry;
+  b: NearDuplicateEntry;
+  similarity: number;
+}
+
+const pairKey = (pair: NearDuplicatePair) => `${pair.a.id}:${pair.b.id}`;
+
+// Finds entries with nearly the same text and walks through them one pair at a time:
+// keep either side (optionally with the other side's text), or mark them as not duplicates
+export function NearDuplicates() {
+  const [pairs, setPairs] = useState<NearDuplicatePair[] | null>(null);
+  const [isScanning, setIsScanning] = useState(false);
+  const [busy, setBusy] = useState(false);
+
+  const scan = async () => {
+    try {
+      setIsScanning(true);
+      setPairs(await invoke<NearDuplicatePair[]>("find_near_duplicates", {}));
+    } catch (error) {
+      alert(`Could not check for near-duplicates: ${errorMessage(error)}`);
+    } finally {
+      setIsScanning(false);
+    }
+  };
+
+  // Removes every pair involving an entry that no longer exists
+  const resolved = (pair: NearDuplicatePair, removedId?: string) => {
+    setPairs((prev) =>
+      (prev || []).filter((p) => pairKey(p) !== pairKey(pair) && (!removedId || (p.a.id !== removedId && p.b.id !== removedId)))
+    );
+  };
+
+  const merge = async (pair: NearDuplicatePair, keep: NearDuplicateEntry, remove: NearDuplicateEntry, useRemovedText: boolean) => {
+    try {
+      setBusy(true);
+      await invoke("merge_near_duplicates", { keepId: keep.id, removeId: remove.id, useRemovedText });
+      resolved(pair, remove.id);
+    } catch (error) {
+      alert(`Could not merge entries: ${errorMessage(error)}`);
+    } finally {
+      setBusy(false);
+    }
+  };
+
+  const dismiss = async (pair: NearDuplicatePair) => {
+    try {
+      setBusy(true);
+      await invoke("dismiss_near_duplicate", { entryA: pair.a.id, entryB: pair.b.id });
+      resolved(pair);
+    } catch (error) {
+      alert(`Could not dismiss pair: ${errorMessage(error)}`);
+    } finally {
+      setBusy(false);
+    }
+  };
+
+  const pair = pairs && pairs.length > 0 ? pairs[0] : null;
+
+  const side = (entry: NearDuplicateEntry, other: NearDuplicateEntry) => (
+    <div className="flex-1 border rounded-lg p-4 space-y-2 min-w-0">
+      <div className="font-medium truncate">{entry.title || formatDate(entry.entry_date, "medium", { timeZone: "UTC" })}</div>
+      <div className="text-xs text-muted-foreground truncate" title={entry.source_path}>
+        {formatDate(entry.entry_date, "numeric", { timeZone: "UTC" })} · {entry.word_count} words · {entry.source_path}
+      </div>
+      <p className="text-sm leading-relaxed">{entry.snippet}</p>
+      <div className="flex flex-wrap gap-2 pt-2">
+        <Button size="sm" disabled={busy} onClick={() => merge(pair!, entry, other, false)}>Keep this one</Button>
+        <Button size="sm" variant="outline" disabled={busy} onClick={() => merge(pair!, entry, other, true)} title="Keep this entry's date, tags and id with the other entry's text">
+          Keep, with other text
+        </Button>
+      </div>
+    </div>
+  );
+
+  return (
+    <Card>
+      <CardHeader>
+        <CardTitle className="flex items-center gap-2">
+          <Copy className="w-5 h-5" />
+          Near-duplicates
+        </CardTitle>
+        <CardDescription>
+          Entries imported twice with small differences (whitespace, a signature). Merging keeps one entry and moves the other's tags to it.
+        </CardDescription>
+      </CardHeader>
+      <CardContent className="space-y-4">
+        <div className="flex items-center gap-3">
+          <Button variant="outline" size="sm" onClick={scan} disabled={isScanning || busy}>
+            {isScanning ? "Checking..." : "Find Near-Duplicates"}
+          </Button>
+          {pairs && (
+            <span className="text-sm text-muted-foreground">
+              {pairs.length === 0 ? "No near-duplicates found" : `${pairs.length} ${pairs.length === 1 ? "pair" : "pairs"} to review`}
+            </span>
+          )}
+        </div>
+        {pair && (
+          <div className="space-y-3">
+            <div className="flex items-center justify-between">
+              <Badge variant="secondary">{Math.round(pair.similarity * 100)}% similar</Badge>
+              <Button variant="ghost" size="sm" disabled={busy} onClick={() => dismiss(pair)}>Not duplicates</Button>
+            </div>
+            <div className="flex gap-4">
+              {side(pair.a, pair.b)}
+              {side(pair.b, pair.a)}
+            </div>
+          </div>
+        )}
+      </CardContent>
+    </Card>
+  );
+}
@@ -0,0 +1,138 @@
+import { useState, useEffect } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { Card, CardContent, CardDescription, CardHeader, CardTitle } from "@/components/ui/card";
+import { Button } from "@/components/ui/button";
+import { Input } from "@/components/ui/input";
+import * as Dialog from "@radix-ui/react-dialog";
+import { Badge } from "@/components/ui/badge";
+import { History } from "lucide-react";
+import { formatDate } from "@/lib/dates";
+
+interface OnThisDayEntry {
+  id: string;
+  year: number;
+  entry_date: string;
+  title?: string;
+  snippet: string;
+}
+
+interface OnThisDayResult {
+  date: string;
+  years: number[];
+  entries: OnThisDayEntry[];
+}
+
+interface EntryDetail {
+  id: string;
+  title?: string;
+  preview: string;
+  entry_date: string;
+}
+
+const todayIso = () => {
+  const now = new Date();
+  return `${now.getFullYear()}-${String(now.getMonth() + 1).padStart(2, "0")}-${String(now.getDate()).padStart(2, "0")}`;
+};
+
+// `date` comes from a notification link; otherwise today
+export function OnThisDay({ date }: { date?: string }) {
+  const [day, setDay] = useState(date || todayIso());
+  const [result, setResult] = useState<OnThisDayResult | null>(null);
+  const [isLoading, setIsLoading] = useState(true);
+  const [selectedEntry, setSelectedEntry] = useState<EntryDetail | null>(null);
+
+  useEffect(() => {
+    if (date) setDay(date);
+  }, [date]);
+
+  useEffect(() => {
+    loadDay(day);
+  }, [day]);
+
+  const loadDay = async (value: string) => {
+    try {
+      setIsLoading(true);
+      setResult(await invoke<OnThisDayResult>("get_on_this_day", { date: value }));
+    } catch (error) {
+      console.error("Failed to load on this day:", error);
+    } finally {
+      setIsLoading(false);
+    }
+  };
+
+  const openEntry = async (entryId: string) => {
+    try {
+      const data = await invoke<EntryDetail | null>("get_entry_by_id", { id: entryId });
+      if (data) setSelectedEntry(data);
+    } catch (error) {
+      console.error("Failed to load entry:", error);
+    }
+  };
+
+  const dayLabel = formatDate(`${day}T12:00:00`, "dayMonth");
+
+  return (
+    <div className="space-y-6">
+      <Card>
+        <CardHeader>
+          <div className="flex items-center justify-between gap-4">
+            <div>
+              <CardTitle className="flex items-center gap-2">
+                <History className="w-5 h-5" />
+                {dayLabel} in earlier years
+              </CardTitle>
+              <CardDescription>
+                {result && result.entries.length > 0
+                  ? `${result.entries.length} ${result.entries.length === 1 ? "entry" : "entries"} from ${result.years.join(", ")}`
+                  : "What you wrote on this date before"}
+              </CardDescription>
+            </div>
+            <Input type="date" value={day} onChange={(e) => e.target.value && setDay(e.target.value)} className="w-44" />
+          </div>
+        </CardHeader>
+        <CardContent>
+          {isLoading ? (
+            <p className="text-sm text-muted-foreground">Loading...</p>
+          ) : !result || result.entries.length === 0 ? (
+            <p className="text-sm text-muted-foreground">Nothing written on {dayLabel} in earlier years.</p>
+          ) : (
+            <div className="space-y-6">
+              {result.years.map((year) => (
+                <div key={year} className="space-y-3">
+                  <h3 className="text-lg font-semibold">{year}</h3>
+                  {result.entries.filter((e) => e.year === year).map((entry) => (
+                    <div key={entry.id} className="border rounded-lg p-4 cursor-pointer hover:bg-muted/50" onClick={() => openEntry(entry.id)}>
+                      <div className="flex items-start justify-between mb-2">
+                        <h4 className="font-medium">{entry.title || `Entry ${entry.id.slice(0, 8)}`}</h4>
+                        <Badge variant="outline" size="sm">{year}</Badge>
+                      </div>
+                      <p className="text-sm text-muted-foreground leading-relaxed">{entry.snippet}</p>
+                    </div>
+                  ))}
+                </div>
+              ))}
+            </div>
+          )}
+        </CardContent>
+      </Card>
+
+      <Dialog.Root open={selectedEntry !== null} onOpenChange={(open) => !open && setSelectedEntry(null)}>
+        <Dialog.Portal>
+          <Dialog.Overlay className="fixed inset-0 bg-black/40" />
+          <Dialog.Content className="fixed left-1/2 top-1/2 -translate-x-1/2 -translate-y-1/2 bg-card border rounded-lg shadow-lg w-[90vw] max-w-2xl max-h-[80vh] overflow-auto p-6">
+            <Dialog.Title className="text-xl font-semibold mb-2">
+              {selectedEntry?.title || (selectedEntry ? `Entry ${selectedEntry.id.slice(0, 8)}` : "Entry")}
+            </Dialog.Title>
+            <div className="text-sm text-muted-foreground mb-4">
+              {selectedEntry && formatDate(selectedEntry.entry_date)}
+            </div>
+            <div className="whitespace-pre-wrap leading-relaxed text-sm">{selectedEntry?.preview}</div>
+            <div className="mt-4 flex justify-end">
+              <Button variant="outline" onClick={() => setSelectedEntry(null)}>Close</Button>
+            </div>
+          </Dialog.Content>
+        </Dialog.Portal>
+      </Dialog.Root>
+    </div>
+  );
+}
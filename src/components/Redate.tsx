@@ -0,0 +1,141 @@
+import { useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { Card, CardContent, CardDescription, CardHeader, CardTitle } from "@/components/ui/card";
+import { Button } from "@/components/ui/button";
+import { Input } from "@/components/ui/input";
+import { Label } from "@/components/ui/label";
+import { Badge } from "@/components/ui/badge";
+import { CalendarClock } from "lucide-react";
+import { errorMessage } from "@/lib/errors";
+import { formatDate } from "@/lib/dates";
+
+interface RedateProposal {
+  entry_id: string;
+  title?: string;
+  current_date: string;
+  proposed_date: string;
+  found_in: string;
+  evidence: string;
+}
+
+// Reads dates from entries' titles, file names and opening lines, for imports that gave every
+// file the same date; proposed dates are reviewed and the approved ones applied together
+export function Redate() {
+  const [importDayOnly, setImportDayOnly] = useState(true);
+  const [dateFrom, setDateFrom] = useState("");
+  const [dateTo, setDateTo] = useState("");
+  const [proposals, setProposals] = useState<RedateProposal[] | null>(null);
+  const [approved, setApproved] = useState<Set<string>>(new Set());
+  const [isReading, setIsReading] = useState(false);
+  const [busy, setBusy] = useState(false);
+
+  const find = async () => {
+    try {
+      setIsReading(true);
+      const found = await invoke<RedateProposal[]>("propose_redates", {
+        selection: {
+          dated_on_import_day: importDayOnly,
+          filter: { date_from: dateFrom || null, date_to: dateTo || null },
+        },
+      });
+      setProposals(found);
+      setApproved(new Set(found.map((p) => p.entry_id)));
+    } catch (error) {
+      alert(`Could not read dates: ${errorMessage(error)}`);
+    } finally {
+      setIsReading(false);
+    }
+  };
+
+  const toggle = (id: string) => {
+    setApproved((prev) => {
+      const next = new Set(prev);
+      if (next.has(id)) next.delete(id);
+      else next.add(id);
+      return next;
+    });
+  };
+
+  const apply = async () => {
+    if (!proposals) return;
+    const changes = proposals.filter((p) => approved.has(p.entry_id)).map((p) => ({ entry_id: p.entry_id, entry_date: p.proposed_date }));
+    try {
+      setBusy(true);
+      const report = await invoke<{ updated: number }>("apply_redates", { changes });
+      setProposals((prev) => (prev || []).filter((p) => !approved.has(p.entry_id)));
+      setApproved(new Set());
+      alert(`Re-dated ${report.updated} ${report.updated === 1 ? "entry" : "entries"}`);
+    } catch (error) {
+      alert(`Could not re-date entries: ${errorMessage(error)}`);
+    } finally {
+      setBusy(false);
+    }
+  };
+
+  return (
+    <Card>
+      <CardHeader>
+        <CardTitle className="flex items-center gap-2">
+          <CalendarClock className="w-5 h-5" />
+          Re-date Entries
+        </CardTitle>
+        <CardDescription>
+          Find dates written in entries' titles, file names and opening lines, for imports that gave every file the same date. Review the proposed dates before applying them.
+        </CardDescription>
+      </CardHeader>
+      <CardContent className="space-y-4">
+        <div className="grid grid-cols-1 md:grid-cols-2 gap-4">
+          <div className="space-y-2">
+            <Label htmlFor="redate-from">Currently dated from</Label>
+            <Input id="redate-from" type="date" value={dateFrom} onChange={(e) => setDateFrom(e.target.value)} />
+          </div>
+          <div className="space-y-2">
+            <Label htmlFor="redate-to">To</Label>
+            <Input id="redate-to" type="date" value={dateTo} onChange={(e) => setDateTo(e.target.value)} />
+          </div>
+        </div>
+        <div className="flex flex-wrap items-center gap-3">
+          <Button variant={importDayOnly ? "default" : "outline"} size="sm" onClick={() => setImportDayOnly(!importDayOnly)}>
+            Only entries dated the day they were imported
+          </Button>
+          <Button variant="outline" size="sm" onClick={find} disabled={isReading || busy}>
+            {isReading ? "Reading..." : "Find Dates"}
+          </Button>
+          {proposals && (
+            <span className="text-sm text-muted-foreground">
+              {proposals.length === 0 ? "No other dates found" : `${proposals.length} ${proposals.length === 1 ? "entry" : "entries"} to review`}
+            </span>
+          )}
+        </div>
+        {proposals && proposals.length > 0 && (
+          <div className="space-y-3">
+            <div className="max-h-96 overflow-y-auto border rounded-lg divide-y">
+              {proposals.map((p) => (
+                <label key={p.entry_id} className="flex items-start gap-3 p-3 cursor-pointer">
+                  <input type="checkbox" className="mt-1" checked={approved.has(p.entry_id)} onChange={() => toggle(p.entry_id)} />
+                  <div className="min-w-0 flex-1 space-y-1">
+                    <div className="flex items-center gap-2 text-sm">
+                      <span className="text-muted-foreground line-through">{formatDate(p.current_date, "short")}</span>
+                      <span className="font-medium">{formatDate(p.proposed_date, "short")}</span>
+                      <Badge variant="secondary">{p.found_in}</Badge>
+                    </div>
+                    <div className="text-sm truncate">{p.title || "Untitled"}</div>
+                    <div className="text-xs text-muted-foreground truncate" title={p.evidence}>{p.evidence}</div>
+                  </div>
+                </label>
+              ))}
+            </div>
+            <div className="flex gap-2">
+              <Button size="sm" onClick={apply} disabled={busy || approved.size === 0}>
+                Apply {approved.size} {approved.size === 1 ? "Date" : "Dates"}
+              </Button>
+              <Button variant="ghost" size="sm" disabled={busy} onClick={() => setApproved(new Set())}>
+                Clear Selection
+              </Button>
+            </div>
+          </div>
+        )}
+      </CardContent>
+    </Card>
+  );
+}
@@ -1,5 +1,6 @@
 import { useState, useEffect, useCallback } from "react";
 import { invoke } from "@tauri-apps/api/core";
+import { save } from "@tauri-apps/plugin-dialog";
 import { Card, CardContent, CardDescription, CardHeader, CardTitle } from "@/components/ui/card";
 import { Button } from "@/components/ui/button";
 import { Input } from "@/components/ui/input";
@@ -19,8 +20,11 @@ import {
   Layers,
   ChevronDown,
   ChevronUp,
-  X
+  X,
+  Download
 } from "lucide-react";
+import { errorMessage } from "@/lib/errors";
+import { formatDate } from "@/lib/dates";
 
 interface SearchResultItem {
   id: string;
@@ -35,60 +39,176 @@ interface SearchResultItem {
   rank_source: string;
 }
 
+interface SearchPage {
+  results: any[];
+  total: number;
+  offset: number;
+  limit: number;
+  date_phrase?: DatePhrase | null;
+}
+
+// A date phrase found in the query ("last summer"), searched as a date range instead
+interface DatePhrase {
+  phrase: string;
+  from: string;
+  to: string;
+  rest: string;
+}
+
+const PAGE_SIZE = 50;
+// Each new search from this view interrupts the one still running
+const SEARCH_KEY = "search-view";
+
+interface SnippetFragment {
+  text: string;
+  highlights: [number, number][];
+}
+
+// Offsets are in characters, so split on code points rather than UTF-16 units
+function renderFragment(fragment: SnippetFragment, key: number) {
+  const chars = Array.from(fragment.text);
+  const parts: JSX.Element[] = [];
+  let cursor = 0;
+  fragment.highlights.forEach(([start, end], i) => {
+    if (start > cursor) parts.push(<span key={`t${i}`}>{chars.slice(cursor, start).join("")}</span>);
+    parts.push(<mark key={`m${i}`} className="bg-yellow-200 rounded-sm px-0.5">{chars.slice(start, end).join("")}</mark>);
+    cursor = end;
+  });
+  if (cursor < chars.length) parts.push(<span key="rest">{chars.slice(cursor).join("")}</span>);
+  return <span key={key} className="block">{parts}</span>;
+}
+
+interface SearchSuggestion {
+  text: string;
+  source: "history" | "term";
+}
+
+interface LanguageCount {
+  code: string;
+  name: string;
+  entries: number;
+}
+
 // Simplified for FTS demo
 
 export function Search() {
   const [query, setQuery] = useState("");
   const [results, setResults] = useState<any[]>([]);
+  const [suggestions, setSuggestions] = useState<SearchSuggestion[]>([]);
   const [isSearching, setIsSearching] = useState(false);
   const [searchTime, setSearchTime] = useState<number>(0);
   const [totalCount, setTotalCount] = useState(0);
+  const [page, setPage] = useState(0);
   const [showFilters, setShowFilters] = useState(false);
   
   // Filter states
   const [dateRange, setDateRange] = useState<[string, string]>(["", ""]);
+  const [language, setLanguage] = useState("");
+  const [languages, setLanguages] = useState<LanguageCount[]>([]);
+  const [parseDates, setParseDates] = useState(true);
+  const [datePhrase, setDatePhrase] = useState<DatePhrase | null>(null);
   
   // Available filter options (would come from backend in real app)
   const availableTags = ["personal", "work", "travel", "ideas", "goals", "reflection"];
-  const availableSourceTypes = ["txt", "docx"];
+  const availableSourceTypes = ["txt", "docx", "keep", "diaro", "daylio", "livejournal", "wordpress", "blogger", "facebook", "sms", "feed", "email", "pdf"];
+
+  const searchFilters = () => ({
+    date_from: dateRange[0] || null,
+    date_to: dateRange[1] || null,
+    languages: language ? [language] : null,
+  });
 
-  const executeSearch = useCallback(async () => {
+  const executeSearch = useCallback(async (pageIndex: number = 0, withDates: boolean = parseDates) => {
     if (!query.trim()) {
+      invoke("cancel_search", { searchKey: SEARCH_KEY }).catch(() => {});
       setResults([]);
+      setDatePhrase(null);
       return;
     }
 
     setIsSearching(true);
+    let superseded = false;
     
     try {
       const start = performance.now();
-      const response = await invoke<any[]>("search_entries_simple", { query: query.trim(), limit: 50 });
-      setResults(response);
-      setTotalCount(response.length);
+      const response = await invoke<SearchPage>("search_entries_simple", {
+        query: query.trim(),
+        limit: PAGE_SIZE,
+        offset: pageIndex * PAGE_SIZE,
+        filters: searchFilters(),
+        searchKey: SEARCH_KEY,
+        parseDates: withDates,
+      });
+      setResults(response.results);
+      setDatePhrase(response.date_phrase ?? null);
+      setTotalCount(response.total);
+      setPage(pageIndex);
       setSearchTime(Math.round(performance.now() - start));
-    } catch (error) {
+    } catch (error: any) {
+      // Superseded by a newer search, whose results will arrive instead
+      if (error?.code === "CANCELLED") {
+        superseded = true;
+        return;
+      }
       console.error("Search failed:", error);
       setResults([]);
       setTotalCount(0);
     } finally {
-      setIsSearching(false);
+      if (!superseded) setIsSearching(false);
     }
-  }, [query, dateRange]);
+  }, [query, dateRange, language, parseDates]);
+
+  // Searches the query's words literally, date phrase included
+  const ignoreDatePhrase = () => {
+    setParseDates(false);
+    executeSearch(0, false);
+  };
+
+  const exportCsv = async () => {
+    try {
+      const dest = await save({
+        defaultPath: "journal-search.csv",
+        filters: [{ name: "CSV", extensions: ["csv"] }]
+      });
+      if (!dest) return;
+      const report = await invoke<{ path: string, rows: number }>("export_search_results", { query: query.trim(), filters: searchFilters(), dest });
+      alert(`Exported ${report.rows} entries to ${report.path}`);
+    } catch (error) {
+      console.error("CSV export failed:", error);
+      alert(`CSV export failed: ${errorMessage(error)}`);
+    }
+  };
 
   // Trigger search explicitly to avoid frequent re-renders
   const onSubmit = async () => {
-    await executeSearch();
+    await executeSearch(0);
   };
 
-  const clearFilters = () => setDateRange(["", ""]);
+  const pageCount = Math.max(1, Math.ceil(totalCount / PAGE_SIZE));
+
+  const clearFilters = () => {
+    setDateRange(["", ""]);
+    setLanguage("");
+  };
+
+  useEffect(() => {
+    invoke<LanguageCount[]>("get_languages")
+      .then(setLanguages)
+      .catch((error) => console.error("Failed to load languages:", error));
+  }, []);
+
+  useEffect(() => {
+    const handle = setTimeout(() => {
+      invoke<SearchSuggestion[]>("get_search_suggestions", { prefix: query, limit: 8 })
+        .then(setSuggestions)
+        .catch(() => setSuggestions([]));
+    }, 150);
+    return () => clearTimeout(handle);
+  }, [query]);
 
   const removeTag = (_tag: string) => {};
   const removeSourceType = (_type: string) => {};
 
-  const formatDate = (dateString: string) => {
-    return new Date(dateString).toLocaleDateString();
-  };
-
   const formatFileSize = (path: string) => {
     // Mock file size - in real app would come from backend
     return "2.1 KB";
@@ -137,10 +257,19 @@ export function Search() {
                 <Input
                   placeholder="Search your journal entries..."
                   value={query}
-                  onChange={(e) => setQuery(e.target.value)}
+                  onChange={(e) => {
+                    setQuery(e.target.value);
+                    setParseDates(true);
+                  }}
                   className="pl-10"
+                  list="search-suggestions"
                   onKeyDown={(e) => { if (e.key === 'Enter') onSubmit(); }}
                 />
+                <datalist id="search-suggestions">
+                  {suggestions.map((s) => (
+                    <option key={`${s.source}:${s.text}`} value={s.text} label={s.source === "history" ? "Recent" : undefined} />
+                  ))}
+                </datalist>
               </div>
               <Button onClick={onSubmit} disabled={!query.trim() || isSearching}>Search</Button>
               <Button
@@ -155,17 +284,32 @@ export function Search() {
             </div>
 
             {/* Active Filters Display */}
-            {(dateRange[0]) && (
+            {(dateRange[0] || language || datePhrase) && (
               <div className="flex flex-wrap gap-2 items-center">
                 <span className="text-sm text-muted-foreground">Active filters:</span>
                 
                 {dateRange[0] && (
                   <Badge variant="secondary" className="flex items-center gap-1">
                     <Calendar className="w-3 h-3" />
-                    {formatDate(dateRange[0])} - {formatDate(dateRange[1])}
+                    {formatDate(dateRange[0], "numeric")} - {formatDate(dateRange[1], "numeric")}
                     <X className="w-3 h-3 cursor-pointer" onClick={() => setDateRange(["", ""])} />
                   </Badge>
                 )}
+
+                {datePhrase && (
+                  <Badge variant="secondary" className="flex items-center gap-1">
+                    <Calendar className="w-3 h-3" />
+                    Dates: {formatDate(datePhrase.from, "numeric")} - {formatDate(datePhrase.to, "numeric")} (from "{datePhrase.phrase}")
+                    <X className="w-3 h-3 cursor-pointer" onClick={ignoreDatePhrase} />
+                  </Badge>
+                )}
+
+                {language && (
+                  <Badge variant="secondary" className="flex items-center gap-1">
+                    {languages.find((l) => l.code === language)?.name || language}
+                    <X className="w-3 h-3 cursor-pointer" onClick={() => setLanguage("")} />
+                  </Badge>
+                )}
                 
                 <Button variant="ghost" size="sm" onClick={clearFilters}>
                   Clear all
@@ -205,6 +349,22 @@ export function Search() {
                   />
                 </div>
               </div>
+
+              {/* Language */}
+              <div className="space-y-2">
+                <Label>Language</Label>
+                <Select value={language || "all"} onValueChange={(v) => setLanguage(v === "all" ? "" : v)}>
+                  <SelectTrigger>
+                    <SelectValue />
+                  </SelectTrigger>
+                  <SelectContent>
+                    <SelectItem value="all">All languages</SelectItem>
+                    {languages.map((l) => (
+                      <SelectItem key={l.code} value={l.code}>{l.name} ({l.entries})</SelectItem>
+                    ))}
+                  </SelectContent>
+                </Select>
+              </div>
             </div>
           </CardContent>
         </Card>
@@ -227,8 +387,16 @@ export function Search() {
                   </div>
                 ) : (
                   <>
-                    <span>{totalCount} results</span>
+                    <span>
+                      {totalCount} results{pageCount > 1 ? `, page ${page + 1} of ${pageCount}` : ""}
+                    </span>
                     <span>{searchTime}ms</span>
+                    {totalCount > 0 && (
+                      <Button variant="outline" size="sm" onClick={exportCsv}>
+                        <Download className="w-4 h-4 mr-2" />
+                        Export CSV
+                      </Button>
+                    )}
                   </>
                 )}
               </div>
@@ -255,7 +423,7 @@ export function Search() {
                         <div className="flex items-center gap-4 text-sm text-muted-foreground mb-2">
                           <span className="flex items-center gap-1">
                             <Calendar className="w-3 h-3" />
-                            {formatDate(result.entry_date)}
+                            {formatDate(result.entry_date, "numeric")}
                           </span>
                           <span className="text-xs">ID: {result.id}</span>
                         </div>
@@ -263,7 +431,9 @@ export function Search() {
                     </div>
                     
                     <p className="text-sm mb-3 leading-relaxed">
-                      {result.preview}
+                      {result.snippets?.length
+                        ? result.snippets.map((f: SnippetFragment, i: number) => renderFragment(f, i))
+                        : result.preview}
                     </p>
                     
                     {result.tags.length > 0 && (
@@ -277,6 +447,29 @@ export function Search() {
                     )}
                   </div>
                 ))}
+                {pageCount > 1 && (
+                  <div className="flex items-center justify-between pt-2">
+                    <Button
+                      variant="outline"
+                      size="sm"
+                      disabled={page === 0 || isSearching}
+                      onClick={() => executeSearch(page - 1)}
+                    >
+                      Previous
+                    </Button>
+                    <span className="text-sm text-muted-foreground">
+                      Page {page + 1} of {pageCount}
+                    </span>
+                    <Button
+                      variant="outline"
+                      size="sm"
+                      disabled={page + 1 >= pageCount || isSearching}
+                      onClick={() => executeSearch(page + 1)}
+                    >
+                      Next
+                    </Button>
+                  </div>
+                )}
               </div>
             )}
           </CardContent>
@@ -1,11 +1,17 @@
 import { useState, useEffect } from "react";
 import { invoke } from "@tauri-apps/api/core";
+import { open, save } from "@tauri-apps/plugin-dialog";
+import { listen } from "@tauri-apps/api/event";
+import { openUrl } from "@tauri-apps/plugin-opener";
 import { Card, CardContent, CardDescription, CardHeader, CardTitle } from "@/components/ui/card";
 import { Button } from "@/components/ui/button";
 import { Input } from "@/components/ui/input";
 import { Label } from "@/components/ui/label";
 import { Select, SelectContent, SelectItem, SelectTrigger, SelectValue } from "@/components/ui/select";
 import { Separator } from "@/components/ui/separator";
+import { NearDuplicates } from "@/components/NearDuplicates";
+import { Conflicts } from "@/components/Conflicts";
+import { Redate } from "@/components/Redate";
 import { 
   Settings as SettingsIcon,
   Database,
@@ -16,14 +22,133 @@ import {
   Save,
   AlertCircle,
   CheckCircle,
-  RefreshCw
+  RefreshCw,
+  BookOpen
 } from "lucide-react";
+import { errorMessage } from "@/lib/errors";
+import { setDateLocale } from "@/lib/dates";
+
+const HOOK_EVENTS = [
+  { id: "entry-created", label: "Entry created" },
+  { id: "entry-imported", label: "Import finished" },
+  { id: "backup-completed", label: "Backup written" },
+];
 
 interface Setting {
   key: string;
   value: string;
 }
 
+interface OllamaModel {
+  name: string;
+  size: number;
+  parameter_size?: string;
+  embedding: boolean;
+}
+
+// One outbound AI request, from get_ai_audit_log
+interface AiAuditRecord {
+  id: number;
+  created_at: string;
+  provider: string;
+  destination: string;
+  purpose: string;
+  bytes_sent: number;
+  status: number | null;
+  entry_ids: string[];
+}
+
+// Write-only S3 credentials, kept in the OS keychain
+const S3_SECRETS = [
+  { key: "s3_access_key_id", label: "Access key ID" },
+  { key: "s3_secret_access_key", label: "Secret access key" },
+  { key: "s3_backup_passphrase", label: "Backup passphrase" },
+];
+
+const IMAP_SECRETS = [
+  { key: "imap_host", label: "IMAP server, e.g. imap.gmail.com" },
+  { key: "imap_username", label: "User name" },
+  { key: "imap_password", label: "Password (or app password)" },
+];
+
+interface ImapStatus {
+  configured: boolean;
+  enabled: boolean;
+  last_fetch_at?: string;
+  last_error?: string;
+}
+
+interface RestoreReport {
+  entries_imported: number;
+  entries_skipped: number;
+  conversations_imported: number;
+  conflicts: { entry_id: string; title?: string; current_updated_at: string; backup_updated_at: string }[];
+}
+
+const restoreSummary = (report: RestoreReport) => {
+  let text = `Restored ${report.entries_imported} entries (${report.entries_skipped} already present) and ${report.conversations_imported} conversations`;
+  if (report.conflicts.length > 0) {
+    const names = report.conflicts.slice(0, 3).map(c => c.title || c.entry_id.slice(0, 8)).join(", ");
+    text += `. ${report.conflicts.length} entries were edited since the backup and kept as they are: ${names}${report.conflicts.length > 3 ? ", ..." : ""}`;
+  }
+  return text;
+};
+
+interface S3Snapshot {
+  key: string;
+  size: number;
+  last_modified: string;
+}
+
+interface SensitiveStatus {
+  configured: boolean;
+  unlocked: boolean;
+  entries: number;
+}
+
+interface LanPeer {
+  device_id: string;
+  name: string;
+  address: string;
+  last_synced_at?: string;
+}
+
+interface LanSyncStatus {
+  enabled: boolean;
+  listening_port?: number;
+  device_id: string;
+  device_name: string;
+  paired: boolean;
+  conflicts: number;
+}
+
+interface DbDiagnostics {
+  db_path: string;
+  total_entries: number;
+  schema_version: number;
+  app_schema_version: number;
+  db_size: number;
+  wal_size: number;
+  fts_rows: number;
+  entries_missing_fts: number;
+  orphaned_fts_rows: number;
+  duplicate_fts_rows: number;
+  fts_tokenizer_outdated: boolean;
+  entries_missing_embeddings: number;
+  sensitive_entries: number;
+  indexes: { name: string; table: string; present: boolean }[];
+}
+
+const formatSize = (bytes: number) =>
+  bytes >= 1e9 ? `${(bytes / 1e9).toFixed(1)} GB` : `${Math.round(bytes / 1e6)} MB`;
+
+// Embedding model per provider that maps different languages into one space
+const MULTILINGUAL_EMBEDDING_MODELS: Record<string, string> = {
+  local: "multilingual-e5-small",
+  ollama: "bge-m3",
+  openai: "text-embedding-3-small",
+};
+
 export function Settings() {
   const [settings, setSettings] = useState<Setting[]>([]);
   const [loading, setLoading] = useState(true);
@@ -34,18 +159,394 @@ export function Settings() {
   const [aiProvider, setAiProvider] = useState("ollama");
   const [ollamaUrl, setOllamaUrl] = useState("http://localhost:11434");
   const [openaiApiKey, setOpenaiApiKey] = useState("");
+  const [openaiKeyStored, setOpenaiKeyStored] = useState(false);
   const [defaultModel, setDefaultModel] = useState("llama3.1:8b");
   const [embeddingModel, setEmbeddingModel] = useState("nomic-embed-text");
+  const [embeddingProvider, setEmbeddingProvider] = useState("auto");
   const [maxContextEntries, setMaxContextEntries] = useState(5);
   const [searchResultsLimit, setSearchResultsLimit] = useState(20);
+  const [searchTimeout, setSearchTimeout] = useState(10);
+  const [birthday, setBirthday] = useState("");
+  const [titleWeight, setTitleWeight] = useState(5);
+  const [bodyWeight, setBodyWeight] = useState(1);
+  const [rerank, setRerank] = useState("off");
+  const [queryPlanning, setQueryPlanning] = useState(true);
+  const [embeddingQuantization, setEmbeddingQuantization] = useState("none");
   const [autoTagging, setAutoTagging] = useState(true);
   const [googleClientId, setGoogleClientId] = useState("");
   const [googleConnected, setGoogleConnected] = useState(false);
+  const [ollamaModels, setOllamaModels] = useState<OllamaModel[]>([]);
+  const [ollamaError, setOllamaError] = useState<string | null>(null);
+  const [pdfStart, setPdfStart] = useState(`${new Date().getFullYear()}-01-01`);
+  const [pdfEnd, setPdfEnd] = useState(`${new Date().getFullYear()}-12-31`);
+  const [exportTags, setExportTags] = useState("");
+  const [pdfToc, setPdfToc] = useState(true);
+  const [pdfPageSize, setPdfPageSize] = useState("letter");
+  const [exportingPdf, setExportingPdf] = useState(false);
+  const [dbPath, setDbPath] = useState("");
+  const [diagnostics, setDiagnostics] = useState<DbDiagnostics | null>(null);
+  const [logLevel, setLogLevel] = useState("info");
+  const [locale, setLocale] = useState("en-US");
+  const [weekStart, setWeekStart] = useState("monday");
+  const [ttsEngine, setTtsEngine] = useState("system");
+  const [ttsVoice, setTtsVoice] = useState("");
+  const [photoCaptionModel, setPhotoCaptionModel] = useState("");
+  const [dateLocales, setDateLocales] = useState<{ tag: string; sample: string }[]>([]);
+  const [onThisDayNotify, setOnThisDayNotify] = useState(false);
+  const [onThisDayTime, setOnThisDayTime] = useState("08:00");
+  const [hookWebhookUrl, setHookWebhookUrl] = useState("");
+  const [hookScript, setHookScript] = useState("");
+  const [hookEvents, setHookEvents] = useState<string[]>(HOOK_EVENTS.map(e => e.id));
+  const [relocating, setRelocating] = useState(false);
+  const [resyncing, setResyncing] = useState(false);
+  const [writeBackSources, setWriteBackSources] = useState(false);
+  const [archiveOriginals, setArchiveOriginals] = useState(false);
+  const [aiActivity, setAiActivity] = useState<AiAuditRecord[] | null>(null);
+  const [sensitiveStatus, setSensitiveStatus] = useState<SensitiveStatus | null>(null);
+  const [sensitivePassphrase, setSensitivePassphrase] = useState("");
+  const [newSensitivePassphrase, setNewSensitivePassphrase] = useState("");
+  const [includeSensitive, setIncludeSensitive] = useState(false);
+  const [s3Endpoint, setS3Endpoint] = useState("");
+  const [s3Region, setS3Region] = useState("us-east-1");
+  const [s3Bucket, setS3Bucket] = useState("");
+  const [s3Prefix, setS3Prefix] = useState("journal-reader/");
+  const [s3Keep, setS3Keep] = useState(7);
+  const [s3Interval, setS3Interval] = useState(0);
+  const [s3Secrets, setS3Secrets] = useState<Record<string, string>>({});
+  const [s3Stored, setS3Stored] = useState<Record<string, boolean>>({});
+  const [s3Snapshots, setS3Snapshots] = useState<S3Snapshot[] | null>(null);
+  const [s3Busy, setS3Busy] = useState(false);
+  const [lanSyncEnabled, setLanSyncEnabled] = useState(false);
+  const [lanSyncPort, setLanSyncPort] = useState(47821);
+  const [lanSyncName, setLanSyncName] = useState("");
+  const [lanSyncConflicts, setLanSyncConflicts] = useState("newest");
+  const [lanSyncCode, setLanSyncCode] = useState("");
+  const [lanSyncStatus, setLanSyncStatus] = useState<LanSyncStatus | null>(null);
+  const [lanPeers, setLanPeers] = useState<LanPeer[] | null>(null);
+  const [lanPeerAddress, setLanPeerAddress] = useState("");
+  const [lanBusy, setLanBusy] = useState(false);
+  const [imapEnabled, setImapEnabled] = useState(false);
+  const [imapPort, setImapPort] = useState(993);
+  const [imapFolder, setImapFolder] = useState("Journal");
+  const [imapInterval, setImapInterval] = useState(15);
+  const [imapSecrets, setImapSecrets] = useState<Record<string, string>>({});
+  const [imapStatus, setImapStatus] = useState<ImapStatus | null>(null);
+  const [imapBusy, setImapBusy] = useState(false);
 
   useEffect(() => {
     loadSettings();
+    loadDbPath();
+    loadSensitiveStatus();
+    loadLanSyncStatus();
+    loadImapStatus();
+    invoke<{ tag: string; sample: string }[]>("get_date_locales").then(setDateLocales).catch(() => {});
   }, []);
 
+  const loadImapStatus = async () => {
+    try {
+      setImapStatus(await invoke<ImapStatus>("get_imap_status"));
+    } catch (error) {
+      console.error("Failed to load email import status:", error);
+    }
+  };
+
+  // Uses the saved server settings, so save first
+  const fetchImapMail = async () => {
+    try {
+      setMessage(null);
+      setImapBusy(true);
+      const report = await invoke<{ imported: number; duplicates: number; failed: number }>("fetch_imap_mail");
+      let text = report.imported > 0 ? `Imported ${report.imported} emails` : "No new emails";
+      if (report.duplicates > 0) text += `, ${report.duplicates} already in the journal`;
+      if (report.failed > 0) text += `, ${report.failed} could not be read`;
+      setMessage({ type: report.failed > 0 ? 'error' : 'success', text });
+    } catch (error: any) {
+      setMessage({ type: 'error', text: `Checking mail failed: ${error?.message ?? error}` });
+    } finally {
+      setImapBusy(false);
+      await loadImapStatus();
+    }
+  };
+
+  const loadLanSyncStatus = async () => {
+    try {
+      setLanSyncStatus(await invoke<LanSyncStatus>("get_lan_sync_status"));
+    } catch (error) {
+      console.error("Failed to load LAN sync status:", error);
+    }
+  };
+
+  // Replaces this device's pairing code; shown once so it can be typed into the other devices
+  const generateLanSyncCode = async () => {
+    try {
+      setLanSyncCode(await invoke<string>("generate_lan_sync_code"));
+      await loadLanSyncStatus();
+    } catch (error: any) {
+      setMessage({ type: 'error', text: `Could not create a pairing code: ${error?.message ?? error}` });
+    }
+  };
+
+  const discoverLanPeers = async () => {
+    try {
+      setLanBusy(true);
+      setLanPeers(await invoke<LanPeer[]>("discover_lan_peers"));
+    } catch (error: any) {
+      setMessage({ type: 'error', text: `Device search failed: ${error?.message ?? error}` });
+    } finally {
+      setLanBusy(false);
+    }
+  };
+
+  const syncWithLanPeer = async (address: string) => {
+    try {
+      setMessage(null);
+      setLanBusy(true);
+      const report = await invoke<{ peer: string; received: number; sent: number; deleted_here: number; deleted_there: number; conflicts: number }>(
+        "sync_with_lan_peer", { address }
+      );
+      let text = `Synced with ${report.peer}: ${report.received} entries received, ${report.sent} sent`;
+      if (report.deleted_here + report.deleted_there > 0) text += `, ${report.deleted_here + report.deleted_there} deleted`;
+      if (report.conflicts > 0) text += `. ${report.conflicts} entries were edited on both devices and need review`;
+      setMessage({ type: 'success', text });
+      await loadLanSyncStatus();
+    } catch (error: any) {
+      setMessage({ type: 'error', text: `Sync failed: ${error?.message ?? error}` });
+    } finally {
+      setLanBusy(false);
+    }
+  };
+
+  // Uses the saved S3 settings, so save first
+  const runS3Backup = async () => {
+    try {
+      setMessage(null);
+      setS3Busy(true);
+      const report = await invoke<{ key: string, bytes: number, entries: number, pruned: number }>("run_s3_backup");
+      setMessage({
+        type: 'success',
+        text: `Uploaded ${report.entries} entries to ${report.key} (${formatSize(report.bytes)})${report.pruned > 0 ? `, removed ${report.pruned} old snapshots` : ""}`
+      });
+      if (s3Snapshots) await listS3Backups();
+    } catch (error: any) {
+      setMessage({ type: 'error', text: `S3 backup failed: ${error?.message ?? error}` });
+    } finally {
+      setS3Busy(false);
+    }
+  };
+
+  const listS3Backups = async () => {
+    try {
+      setS3Snapshots((await invoke<S3Snapshot[]>("list_s3_backups")).reverse());
+    } catch (error: any) {
+      setMessage({ type: 'error', text: `Could not list S3 backups: ${error?.message ?? error}` });
+    }
+  };
+
+  // "full" also applies the snapshot's settings; "merge" only adds what is missing
+  const restoreS3Backup = async (key: string, mode: "full" | "merge") => {
+    try {
+      setMessage(null);
+      setS3Busy(true);
+      const report = await invoke<RestoreReport>("restore_s3_backup", { key, mode });
+      setMessage({ type: 'success', text: restoreSummary(report) });
+      await loadSettings();
+    } catch (error: any) {
+      setMessage({ type: 'error', text: `Restore failed: ${error?.message ?? error}` });
+    } finally {
+      setS3Busy(false);
+    }
+  };
+
+  const loadSensitiveStatus = async () => {
+    try {
+      setSensitiveStatus(await invoke<SensitiveStatus>("get_sensitive_status"));
+    } catch (error) {
+      console.error("Failed to load sensitive entry status:", error);
+    }
+  };
+
+  // Sets the passphrase the first time; afterwards `sensitivePassphrase` is the current one
+  const setSensitivePassphraseValue = async () => {
+    try {
+      setMessage(null);
+      await invoke("set_sensitive_passphrase", {
+        current: sensitiveStatus?.configured ? sensitivePassphrase : null,
+        passphrase: newSensitivePassphrase,
+      });
+      setSensitivePassphrase("");
+      setNewSensitivePassphrase("");
+      setMessage({ type: 'success', text: 'Sensitive passphrase saved' });
+      await loadSensitiveStatus();
+    } catch (error: any) {
+      setMessage({ type: 'error', text: errorMessage(error) });
+    }
+  };
+
+  const toggleSensitiveLock = async () => {
+    try {
+      setMessage(null);
+      if (sensitiveStatus?.unlocked) {
+        await invoke("lock_sensitive");
+      } else {
+        await invoke("unlock_sensitive", { passphrase: sensitivePassphrase });
+        setSensitivePassphrase("");
+      }
+      await loadSensitiveStatus();
+    } catch (error: any) {
+      setMessage({ type: 'error', text: errorMessage(error) });
+    }
+  };
+
+  const toggleHookEvent = (id: string) => {
+    setHookEvents(events => events.includes(id) ? events.filter(e => e !== id) : [...events, id]);
+  };
+
+  // Uses the saved hook settings, so save first
+  const testHooks = async () => {
+    try {
+      setMessage(null);
+      const results = await invoke<{ target: string, ok: boolean, error: string | null }[]>("test_hooks");
+      const failed = results.filter(r => !r.ok);
+      setMessage(failed.length === 0
+        ? { type: 'success', text: `Test event delivered to ${results.map(r => r.target).join(" and ")}` }
+        : { type: 'error', text: failed.map(r => `${r.target}: ${r.error}`).join("; ") });
+    } catch (error) {
+      setMessage({ type: 'error', text: `Hook test failed: ${errorMessage(error)}` });
+    }
+  };
+
+  const loadDbPath = async () => {
+    try {
+      const info = await invoke<{ db_path: string }>("get_db_diagnostics");
+      setDbPath(info.db_path);
+    } catch (error) {
+      console.error("Failed to load database location:", error);
+    }
+  };
+
+  const runDiagnostics = async () => {
+    try {
+      setDiagnostics(await invoke<DbDiagnostics>("get_db_diagnostics"));
+    } catch (error) {
+      setMessage({ type: 'error', text: `Diagnostics failed: ${errorMessage(error)}` });
+    }
+  };
+
+  const repairSearchIndex = async () => {
+    try {
+      const report = await invoke<{ orphans_removed: number; duplicates_removed: number; entries_added: number }>("repair_search_index");
+      setMessage({
+        type: 'success',
+        text: `Search index repaired: ${report.entries_added} entries added, ${report.orphans_removed + report.duplicates_removed} stale rows removed`,
+      });
+      await runDiagnostics();
+    } catch (error) {
+      setMessage({ type: 'error', text: `Repair failed: ${errorMessage(error)}` });
+    }
+  };
+
+  const copyRecentLogs = async () => {
+    try {
+      const logs = await invoke<{ dir: string; lines: string[] }>("get_recent_logs", { lines: 500 });
+      await navigator.clipboard.writeText(logs.lines.join("\n"));
+      setMessage({ type: 'success', text: `Copied the last ${logs.lines.length} log lines (log files are in ${logs.dir})` });
+    } catch (error) {
+      setMessage({ type: 'error', text: `Could not read logs: ${errorMessage(error)}` });
+    }
+  };
+
+  const relocateDatabase = async () => {
+    try {
+      setMessage(null);
+      const folder = await open({ directory: true, multiple: false });
+      if (!folder || typeof folder !== 'string') return;
+      setRelocating(true);
+      const report = await invoke<{ new_path: string, old_removed: boolean }>("relocate_database", { folder });
+      setDbPath(report.new_path);
+      setMessage({
+        type: 'success',
+        text: report.old_removed
+          ? `Database moved to ${report.new_path}`
+          : `Database moved to ${report.new_path}, but the old file could not be deleted`
+      });
+    } catch (error) {
+      setMessage({ type: 'error', text: `Move failed: ${errorMessage(error)}` });
+    } finally {
+      setRelocating(false);
+    }
+  };
+
+  const loadAiActivity = async () => {
+    try {
+      setAiActivity(await invoke<AiAuditRecord[]>("get_ai_audit_log", { limit: 50 }));
+    } catch (error) {
+      setMessage({ type: 'error', text: `Failed to load AI activity: ${errorMessage(error)}` });
+    }
+  };
+
+  const resyncSources = async () => {
+    try {
+      setMessage(null);
+      setResyncing(true);
+      const report = await invoke<{ checked: number, updated: number, missing: number, failed: number, errors: string[] }>("resync_from_sources", {});
+      const parts = [`${report.updated} of ${report.checked} entries updated from their files`];
+      if (report.missing > 0) parts.push(`${report.missing} source files missing`);
+      if (report.failed > 0) parts.push(`${report.failed} failed (${report.errors.slice(0, 3).join("; ")})`);
+      setMessage({ type: report.failed > 0 ? 'error' : 'success', text: parts.join(", ") });
+    } catch (error) {
+      setMessage({ type: 'error', text: `Re-sync failed: ${errorMessage(error)}` });
+    } finally {
+      setResyncing(false);
+    }
+  };
+
+  useEffect(() => {
+    if (!loading && (aiProvider === "ollama" || embeddingProvider === "ollama")) {
+      loadOllamaModels();
+    }
+  }, [loading, aiProvider, embeddingProvider]);
+
+  const loadOllamaModels = async () => {
+    try {
+      setOllamaModels(await invoke<OllamaModel[]>("list_ollama_models"));
+      setOllamaError(null);
+    } catch (error: any) {
+      setOllamaModels([]);
+      setOllamaError(errorMessage(error));
+    }
+  };
+
+  // Installed models for a picker, keeping the saved value selectable even if it isn't installed
+  const modelChoices = (current: string, embedding: boolean) => {
+    const models = ollamaModels.filter(m => m.embedding === embedding);
+    const installed = models.some(m => m.name === current || m.name === `${current}:latest`);
+    return { models, missing: current && !installed ? current : null };
+  };
+
+  const renderModelPicker = (id: string, value: string, onChange: (v: string) => void, embedding: boolean) => {
+    const { models, missing } = modelChoices(value, embedding);
+    return (
+      <div className="flex gap-2">
+        <Select value={value} onValueChange={onChange}>
+          <SelectTrigger id={id}>
+            <SelectValue placeholder="Select a model" />
+          </SelectTrigger>
+          <SelectContent>
+            {missing && <SelectItem value={missing}>{missing} (not installed)</SelectItem>}
+            {models.map(m => (
+              <SelectItem key={m.name} value={m.name}>
+                {m.name} · {[m.parameter_size, formatSize(m.size)].filter(Boolean).join(", ")}
+              </SelectItem>
+            ))}
+          </SelectContent>
+        </Select>
+        <Button onClick={loadOllamaModels} size="icon" variant="outline" title="Refresh installed models">
+          <RefreshCw className="w-4 h-4" />
+        </Button>
+      </div>
+    );
+  };
+
   const loadSettings = async () => {
     try {
       const settingsData = await invoke<Setting[]>("get_settings");
@@ -60,29 +561,144 @@ export function Settings() {
           case "ollama_url":
             setOllamaUrl(setting.value);
             break;
-          case "openai_api_key":
-            setOpenaiApiKey(setting.value);
-            break;
           case "default_model":
             setDefaultModel(setting.value);
             break;
+          case "photo_caption_model":
+            setPhotoCaptionModel(setting.value);
+            break;
           case "embedding_model":
             setEmbeddingModel(setting.value);
             break;
+          case "embedding_provider":
+            setEmbeddingProvider(setting.value);
+            break;
           case "max_context_entries":
             setMaxContextEntries(parseInt(setting.value) || 5);
             break;
+          case "chat_query_planning":
+            setQueryPlanning(setting.value !== "false");
+            break;
+          case "embedding_quantization":
+            setEmbeddingQuantization(setting.value);
+            break;
+          case "rerank":
+            setRerank(setting.value);
+            break;
           case "search_results_limit":
             setSearchResultsLimit(parseInt(setting.value) || 20);
             break;
+          case "search_timeout_secs":
+            setSearchTimeout(parseInt(setting.value) || 10);
+            break;
+          case "log_level":
+            setLogLevel(setting.value);
+            break;
+          case "birthday":
+            setBirthday(setting.value);
+            break;
+          case "locale":
+            setLocale(setting.value);
+            break;
+          case "week_start":
+            setWeekStart(setting.value);
+            break;
+          case "tts_engine":
+            setTtsEngine(setting.value);
+            break;
+          case "tts_voice":
+            setTtsVoice(setting.value);
+            break;
+          case "fts_title_weight":
+            setTitleWeight(parseFloat(setting.value) || 0);
+            break;
+          case "fts_body_weight":
+            setBodyWeight(parseFloat(setting.value) || 0);
+            break;
           case "auto_tagging":
             setAutoTagging(setting.value === "true");
             break;
           case "google_client_id":
             setGoogleClientId(setting.value);
             break;
+          case "on_this_day_notification":
+            setOnThisDayNotify(setting.value === "true");
+            break;
+          case "on_this_day_time":
+            setOnThisDayTime(setting.value);
+            break;
+          case "write_back_sources":
+            setWriteBackSources(setting.value === "true");
+            break;
+          case "archive_originals":
+            setArchiveOriginals(setting.value === "true");
+            break;
+          case "ai_include_sensitive":
+            setIncludeSensitive(setting.value === "true");
+            break;
+          case "s3_endpoint":
+            setS3Endpoint(setting.value);
+            break;
+          case "s3_region":
+            setS3Region(setting.value);
+            break;
+          case "s3_bucket":
+            setS3Bucket(setting.value);
+            break;
+          case "s3_prefix":
+            setS3Prefix(setting.value);
+            break;
+          case "s3_keep":
+            setS3Keep(parseInt(setting.value) || 7);
+            break;
+          case "s3_backup_interval_hours":
+            setS3Interval(parseInt(setting.value) || 0);
+            break;
+          case "imap_enabled":
+            setImapEnabled(setting.value === "true");
+            break;
+          case "imap_port":
+            setImapPort(parseInt(setting.value) || 993);
+            break;
+          case "imap_folder":
+            setImapFolder(setting.value);
+            break;
+          case "imap_interval_minutes":
+            setImapInterval(parseInt(setting.value) || 15);
+            break;
+          case "lan_sync_enabled":
+            setLanSyncEnabled(setting.value === "true");
+            break;
+          case "lan_sync_port":
+            setLanSyncPort(parseInt(setting.value) || 47821);
+            break;
+          case "lan_sync_device_name":
+            setLanSyncName(setting.value);
+            break;
+          case "lan_sync_conflicts":
+            setLanSyncConflicts(setting.value);
+            break;
+          case "hook_webhook_url":
+            setHookWebhookUrl(setting.value);
+            break;
+          case "hook_script":
+            setHookScript(setting.value);
+            break;
+          case "hook_events":
+            setHookEvents(setting.value.split(",").map(e => e.trim()).filter(Boolean));
+            break;
         }
       });
+      try {
+        setOpenaiKeyStored(await invoke<boolean>("has_secret", { key: "openai_api_key" }));
+      } catch {}
+      try {
+        const stored: Record<string, boolean> = {};
+        for (const { key } of S3_SECRETS) {
+          stored[key] = await invoke<boolean>("has_secret", { key });
+        }
+        setS3Stored(stored);
+      } catch {}
       try {
         const status = await invoke<{ connected: boolean }>("get_google_oauth_status");
         setGoogleConnected(status.connected);
@@ -102,13 +718,48 @@ export function Settings() {
     const settingsToUpdate = [
       { key: "ai_provider", value: aiProvider },
       { key: "ollama_url", value: ollamaUrl },
-      { key: "openai_api_key", value: openaiApiKey },
       { key: "default_model", value: defaultModel },
+      { key: "photo_caption_model", value: photoCaptionModel.trim() },
       { key: "embedding_model", value: embeddingModel },
+      { key: "embedding_provider", value: embeddingProvider },
       { key: "max_context_entries", value: maxContextEntries.toString() },
       { key: "search_results_limit", value: searchResultsLimit.toString() },
+      { key: "search_timeout_secs", value: searchTimeout.toString() },
+      { key: "log_level", value: logLevel },
+      { key: "birthday", value: birthday.trim() },
+      { key: "locale", value: locale },
+      { key: "week_start", value: weekStart },
+      { key: "tts_engine", value: ttsEngine },
+      { key: "tts_voice", value: ttsVoice.trim() },
+      { key: "fts_title_weight", value: titleWeight.toString() },
+      { key: "fts_body_weight", value: bodyWeight.toString() },
+      { key: "rerank", value: rerank },
+      { key: "chat_query_planning", value: queryPlanning.toString() },
+      { key: "embedding_quantization", value: embeddingQuantization },
       { key: "auto_tagging", value: autoTagging.toString() },
       { key: "google_client_id", value: googleClientId },
+      { key: "on_this_day_notification", value: onThisDayNotify.toString() },
+      { key: "on_this_day_time", value: onThisDayTime },
+      { key: "write_back_sources", value: writeBackSources.toString() },
+      { key: "archive_originals", value: archiveOriginals.toString() },
+      { key: "ai_include_sensitive", value: includeSensitive.toString() },
+      { key: "s3_endpoint", value: s3Endpoint.trim() },
+      { key: "s3_region", value: s3Region.trim() },
+      { key: "s3_bucket", value: s3Bucket.trim() },
+      { key: "s3_prefix", value: s3Prefix.trim() },
+      { key: "s3_keep", value: s3Keep.toString() },
+      { key: "s3_backup_interval_hours", value: s3Interval.toString() },
+      { key: "lan_sync_enabled", value: lanSyncEnabled.toString() },
+      { key: "lan_sync_port", value: lanSyncPort.toString() },
+      { key: "lan_sync_device_name", value: lanSyncName.trim() },
+      { key: "lan_sync_conflicts", value: lanSyncConflicts },
+      { key: "imap_enabled", value: imapEnabled.toString() },
+      { key: "imap_port", value: imapPort.toString() },
+      { key: "imap_folder", value: imapFolder.trim() || "Journal" },
+      { key: "imap_interval_minutes", value: imapInterval.toString() },
+      { key: "hook_webhook_url", value: hookWebhookUrl.trim() },
+      { key: "hook_script", value: hookScript.trim() },
+      { key: "hook_events", value: hookEvents.join(",") },
     ];
 
     try {
@@ -118,6 +769,26 @@ export function Settings() {
           value: setting.value,
         });
       }
+      // The key is write-only: it goes to the OS keychain and is never read back
+      if (openaiApiKey) {
+        await invoke("set_secret", { key: "openai_api_key", value: openaiApiKey });
+        setOpenaiApiKey("");
+      }
+      for (const [key, value] of Object.entries(s3Secrets)) {
+        if (value) await invoke("set_secret", { key, value });
+      }
+      setS3Secrets({});
+      if (lanSyncCode) {
+        await invoke("set_secret", { key: "lan_sync_key", value: lanSyncCode.trim() });
+        setLanSyncCode("");
+      }
+      await loadLanSyncStatus();
+      for (const [key, value] of Object.entries(imapSecrets)) {
+        if (value) await invoke("set_secret", { key, value });
+      }
+      setImapSecrets({});
+      await loadImapStatus();
+      setDateLocale(locale);
       
       setMessage({ type: 'success', text: 'Settings saved successfully!' });
       await loadSettings(); // Reload to confirm
@@ -142,15 +813,138 @@ export function Settings() {
   const connectGoogle = async () => {
     try {
       setMessage(null);
-      const init = await invoke<{ auth_url: string, state: string, code_verifier: string }>("google_oauth_start");
-      window.open(init.auth_url, "_blank");
-      const code = prompt("Authorize in your browser, then paste the 'code' parameter from the redirected URL:") || "";
-      if (!code) return;
-      const ok = await invoke<boolean>("google_oauth_complete", { req: { code, state: init.state, codeVerifier: init.code_verifier } });
+      const init = await invoke<{ auth_url: string, state: string, listening: boolean, app_redirect: boolean }>("google_oauth_start");
+      // On a phone Google sends the browser back to the app, which finishes the sign-in
+      const completed = init.app_redirect
+        ? new Promise<{ connected: boolean, error?: unknown }>((resolve) => {
+            const unlisten = listen<{ connected: boolean, error?: unknown }>("google://oauth-complete", (event) => {
+              unlisten.then((stop) => stop());
+              resolve(event.payload);
+            });
+          })
+        : null;
+      await openUrl(init.auth_url);
+      let ok: boolean;
+      if (completed) {
+        setMessage({ type: 'success', text: 'Waiting for Google sign-in in your browser...' });
+        const outcome = await completed;
+        if (outcome.error) throw outcome.error;
+        ok = outcome.connected;
+      } else if (init.listening) {
+        setMessage({ type: 'success', text: 'Waiting for Google sign-in in your browser...' });
+        ok = await invoke<boolean>("google_oauth_wait", { state: init.state });
+      } else {
+        const code = prompt("Authorize in your browser, then paste the 'code' parameter from the redirected URL:") || "";
+        if (!code) return;
+        ok = await invoke<boolean>("google_oauth_complete", { req: { code, state: init.state } });
+      }
       setGoogleConnected(ok);
       setMessage(ok ? { type: 'success', text: 'Google Drive connected!' } : { type: 'error', text: 'Failed to connect Google Drive' });
     } catch (error) {
-      setMessage({ type: 'error', text: 'Google auth failed' });
+      setMessage({ type: 'error', text: `Google auth failed: ${errorMessage(error)}` });
+    }
+  };
+
+  const exportBackup = async () => {
+    try {
+      setMessage(null);
+      const path = await save({
+        defaultPath: `journal-backup-${new Date().toISOString().slice(0, 10)}.json`,
+        filters: [{ name: "JSON", extensions: ["json"] }]
+      });
+      if (!path) return;
+      const report = await invoke<{ path: string, entries: number, conversations: number }>("export_journal_json", { path });
+      setMessage({ type: 'success', text: `Backed up ${report.entries} entries and ${report.conversations} conversations to ${report.path}` });
+    } catch (error) {
+      setMessage({ type: 'error', text: `Backup failed: ${errorMessage(error)}` });
+    }
+  };
+
+  const importBackup = async (mode: "full" | "merge") => {
+    try {
+      setMessage(null);
+      const path = await open({ multiple: false, filters: [{ name: "JSON", extensions: ["json"] }] });
+      if (!path || typeof path !== 'string') return;
+      const report = await invoke<RestoreReport>("import_journal_json", { path, mode });
+      setMessage({ type: 'success', text: restoreSummary(report) });
+      await loadSettings();
+    } catch (error) {
+      setMessage({ type: 'error', text: `Restore failed: ${errorMessage(error)}` });
+    }
+  };
+
+  const exportSettingsFile = async () => {
+    try {
+      setMessage(null);
+      const path = await save({
+        defaultPath: "journal-reader-settings.json",
+        filters: [{ name: "JSON", extensions: ["json"] }]
+      });
+      if (!path) return;
+      const count = await invoke<number>("export_settings", { path });
+      setMessage({ type: 'success', text: `Exported ${count} settings (API keys are not included)` });
+    } catch (error) {
+      setMessage({ type: 'error', text: `Settings export failed: ${errorMessage(error)}` });
+    }
+  };
+
+  const importSettingsFile = async () => {
+    try {
+      setMessage(null);
+      const path = await open({ multiple: false, filters: [{ name: "JSON", extensions: ["json"] }] });
+      if (!path || typeof path !== 'string') return;
+      const changed = await invoke<string[]>("import_settings", { path });
+      setMessage({ type: 'success', text: `Imported settings: ${changed.length} changed. Re-enter API keys on this machine.` });
+      await loadSettings();
+    } catch (error) {
+      setMessage({ type: 'error', text: `Settings import failed: ${errorMessage(error)}` });
+    }
+  };
+
+  // Same filter shape as search; empty fields are left out
+  const exportFilter = () => {
+    const tags = exportTags.split(",").map(t => t.trim()).filter(Boolean);
+    return {
+      date_from: pdfStart || null,
+      date_to: pdfEnd || null,
+      tags: tags.length > 0 ? tags : null
+    };
+  };
+
+  const exportFilteredJson = async () => {
+    try {
+      setMessage(null);
+      const path = await save({
+        defaultPath: `journal-${pdfStart}-to-${pdfEnd}.json`,
+        filters: [{ name: "JSON", extensions: ["json"] }]
+      });
+      if (!path) return;
+      const report = await invoke<{ path: string, entries: number }>("export_journal_json", { path, filter: exportFilter() });
+      setMessage({ type: 'success', text: `Exported ${report.entries} entries to ${report.path}` });
+    } catch (error) {
+      setMessage({ type: 'error', text: `Export failed: ${errorMessage(error)}` });
+    }
+  };
+
+  const exportPdf = async () => {
+    try {
+      setMessage(null);
+      const path = await save({
+        defaultPath: `journal-${pdfStart}-to-${pdfEnd}.pdf`,
+        filters: [{ name: "PDF", extensions: ["pdf"] }]
+      });
+      if (!path) return;
+      setExportingPdf(true);
+      const report = await invoke<{ path: string, pages: number, entries: number }>("export_pdf", {
+        filter: exportFilter(),
+        options: { table_of_contents: pdfToc, page_size: pdfPageSize },
+        path
+      });
+      setMessage({ type: 'success', text: `Exported ${report.entries} entries (${report.pages} pages) to ${report.path}` });
+    } catch (error) {
+      setMessage({ type: 'error', text: `PDF export failed: ${errorMessage(error)}` });
+    } finally {
+      setExportingPdf(false);
     }
   };
 
@@ -223,12 +1017,32 @@ export function Settings() {
 
             <div className="space-y-2">
               <Label htmlFor="default-model">Default Model</Label>
+              {aiProvider === "ollama" && ollamaModels.length > 0 ? (
+                renderModelPicker("default-model", defaultModel, setDefaultModel, false)
+              ) : (
+                <Input
+                  id="default-model"
+                  value={defaultModel}
+                  onChange={(e) => setDefaultModel(e.target.value)}
+                  placeholder="llama3.1:8b"
+                />
+              )}
+              {aiProvider === "ollama" && ollamaError && (
+                <p className="text-xs text-muted-foreground">Couldn't list installed models: {ollamaError}</p>
+              )}
+            </div>
+
+            <div className="space-y-2">
+              <Label htmlFor="photo-caption-model">Photo Caption Model</Label>
               <Input
-                id="default-model"
-                value={defaultModel}
-                onChange={(e) => setDefaultModel(e.target.value)}
-                placeholder="llama3.1:8b"
+                id="photo-caption-model"
+                value={photoCaptionModel}
+                onChange={(e) => setPhotoCaptionModel(e.target.value)}
+                placeholder={aiProvider === "ollama" ? "llava" : "Default model"}
               />
+              <p className="text-sm text-muted-foreground">
+                A vision model, used when photos are imported with AI captions
+              </p>
             </div>
 
             {aiProvider === "ollama" && (
@@ -251,19 +1065,80 @@ export function Settings() {
                   type="password"
                   value={openaiApiKey}
                   onChange={(e) => setOpenaiApiKey(e.target.value)}
-                  placeholder="sk-..."
+                  placeholder={openaiKeyStored ? "Stored in keychain - enter a new key to replace it" : "sk-..."}
                 />
+                {openaiKeyStored && (
+                  <Button
+                    size="sm"
+                    variant="outline"
+                    onClick={async () => {
+                      await invoke("set_secret", { key: "openai_api_key", value: "" });
+                      setOpenaiKeyStored(false);
+                    }}
+                  >
+                    Remove key
+                  </Button>
+                )}
               </div>
             )}
 
+            <div className="space-y-2">
+              <Label htmlFor="embedding-provider">Embedding Provider</Label>
+              <Select value={embeddingProvider} onValueChange={setEmbeddingProvider}>
+                <SelectTrigger>
+                  <SelectValue placeholder="Select embedding provider" />
+                </SelectTrigger>
+                <SelectContent>
+                  <SelectItem value="auto">Automatic</SelectItem>
+                  <SelectItem value="local">Built-in (no server)</SelectItem>
+                  <SelectItem value="ollama">Ollama</SelectItem>
+                  <SelectItem value="openai">OpenAI</SelectItem>
+                </SelectContent>
+              </Select>
+            </div>
+
             <div className="space-y-2">
               <Label htmlFor="embedding-model">Embedding Model</Label>
-              <Input
-                id="embedding-model"
-                value={embeddingModel}
-                onChange={(e) => setEmbeddingModel(e.target.value)}
-                placeholder="nomic-embed-text"
-              />
+              {embeddingProvider === "ollama" && ollamaModels.some(m => m.embedding) ? (
+                renderModelPicker("embedding-model", embeddingModel, setEmbeddingModel, true)
+              ) : (
+                <Input
+                  id="embedding-model"
+                  value={embeddingModel}
+                  onChange={(e) => setEmbeddingModel(e.target.value)}
+                  placeholder={embeddingProvider === "local" ? "bge-small-en-v1.5" : "nomic-embed-text"}
+                />
+              )}
+              {MULTILINGUAL_EMBEDDING_MODELS[embeddingProvider] && embeddingModel !== MULTILINGUAL_EMBEDDING_MODELS[embeddingProvider] && (
+                <p className="text-sm text-muted-foreground">
+                  Journal in more than one language?{" "}
+                  <button
+                    type="button"
+                    className="underline"
+                    onClick={() => setEmbeddingModel(MULTILINGUAL_EMBEDDING_MODELS[embeddingProvider])}
+                  >
+                    Use {MULTILINGUAL_EMBEDDING_MODELS[embeddingProvider]}
+                  </button>{" "}
+                  so a query in one language finds entries written in another. Entries are re-embedded after switching.
+                </p>
+              )}
+            </div>
+
+            <div className="space-y-2">
+              <Label htmlFor="embedding-quantization">Vector Storage</Label>
+              <Select value={embeddingQuantization} onValueChange={setEmbeddingQuantization}>
+                <SelectTrigger id="embedding-quantization">
+                  <SelectValue placeholder="Select vector storage" />
+                </SelectTrigger>
+                <SelectContent>
+                  <SelectItem value="none">Full precision</SelectItem>
+                  <SelectItem value="int8">int8 (4x smaller)</SelectItem>
+                  <SelectItem value="binary">Binary (32x smaller, less accurate)</SelectItem>
+                </SelectContent>
+              </Select>
+              <p className="text-sm text-muted-foreground">
+                Applies to passages embedded from now on; database maintenance converts the ones already stored.
+              </p>
             </div>
 
             <div className="space-y-2">
@@ -331,7 +1206,276 @@ export function Settings() {
                 Maximum number of search results to display
               </p>
             </div>
+
+            <div className="space-y-2">
+              <Label htmlFor="search-timeout">Search Timeout (seconds)</Label>
+              <Input
+                id="search-timeout"
+                type="number"
+                min="1"
+                max="300"
+                value={searchTimeout}
+                onChange={(e) => setSearchTimeout(parseInt(e.target.value) || 10)}
+              />
+              <p className="text-sm text-muted-foreground">
+                Searches taking longer than this are stopped
+              </p>
+            </div>
+
+            <div className="space-y-2">
+              <Label htmlFor="birthday">Birthday (MM-DD)</Label>
+              <Input
+                id="birthday"
+                placeholder="03-09"
+                value={birthday}
+                onChange={(e) => setBirthday(e.target.value)}
+              />
+              <p className="text-sm text-muted-foreground">
+                Lets searches like "around my birthday 2019" find entries from that week
+              </p>
+            </div>
+
+            <div className="space-y-2">
+              <Label htmlFor="date-locale">Date Language</Label>
+              <Select value={locale} onValueChange={setLocale}>
+                <SelectTrigger id="date-locale">
+                  <SelectValue />
+                </SelectTrigger>
+                <SelectContent>
+                  {dateLocales.map((l) => (
+                    <SelectItem key={l.tag} value={l.tag}>{l.sample}</SelectItem>
+                  ))}
+                </SelectContent>
+              </Select>
+              <p className="text-sm text-muted-foreground">
+                How dates and month names read in the timeline, charts and exports
+              </p>
+            </div>
+
+            <div className="space-y-2">
+              <Label htmlFor="week-start">First Day of Week</Label>
+              <Select value={weekStart} onValueChange={setWeekStart}>
+                <SelectTrigger id="week-start">
+                  <SelectValue />
+                </SelectTrigger>
+                <SelectContent>
+                  <SelectItem value="monday">Monday</SelectItem>
+                  <SelectItem value="sunday">Sunday</SelectItem>
+                  <SelectItem value="saturday">Saturday</SelectItem>
+                </SelectContent>
+              </Select>
+              <p className="text-sm text-muted-foreground">
+                Where weekly mood and weekly recaps begin, and the weekday order in writing stats
+              </p>
+            </div>
+
+            <div className="space-y-2">
+              <Label htmlFor="tts-engine">Read Aloud</Label>
+              <div className="flex gap-2">
+                <Select value={ttsEngine} onValueChange={setTtsEngine}>
+                  <SelectTrigger id="tts-engine" className="w-48">
+                    <SelectValue />
+                  </SelectTrigger>
+                  <SelectContent>
+                    <SelectItem value="system">System voices</SelectItem>
+                    <SelectItem value="openai">OpenAI</SelectItem>
+                  </SelectContent>
+                </Select>
+                <Input
+                  value={ttsVoice}
+                  onChange={(e) => setTtsVoice(e.target.value)}
+                  placeholder={ttsEngine === "openai" ? "alloy" : "Default voice"}
+                />
+              </div>
+              <p className="text-sm text-muted-foreground">
+                Voice for the Listen button on entries. System voices work offline (espeak-ng on Linux); OpenAI sends the entry text to OpenAI
+              </p>
+            </div>
+
+            <div className="space-y-2">
+              <Label>Ranking Weights</Label>
+              <div className="flex gap-3">
+                <div className="flex items-center gap-2">
+                  <Label htmlFor="title-weight" className="text-sm font-normal text-muted-foreground">Title</Label>
+                  <Input
+                    id="title-weight"
+                    type="number"
+                    min="0"
+                    max="100"
+                    step="0.5"
+                    value={titleWeight}
+                    onChange={(e) => setTitleWeight(parseFloat(e.target.value) || 0)}
+                    className="w-24"
+                  />
+                </div>
+                <div className="flex items-center gap-2">
+                  <Label htmlFor="body-weight" className="text-sm font-normal text-muted-foreground">Body</Label>
+                  <Input
+                    id="body-weight"
+                    type="number"
+                    min="0"
+                    max="100"
+                    step="0.5"
+                    value={bodyWeight}
+                    onChange={(e) => setBodyWeight(parseFloat(e.target.value) || 0)}
+                    className="w-24"
+                  />
+                </div>
+              </div>
+              <p className="text-sm text-muted-foreground">
+                How much a match in the title counts against one in the body when ranking search results
+              </p>
+            </div>
+
+            <div className="space-y-2">
+              <Label htmlFor="rerank">Rerank AI Context</Label>
+              <Select value={rerank} onValueChange={setRerank}>
+                <SelectTrigger id="rerank">
+                  <SelectValue />
+                </SelectTrigger>
+                <SelectContent>
+                  <SelectItem value="off">Off</SelectItem>
+                  <SelectItem value="llm">With the chat model</SelectItem>
+                  <SelectItem value="local">With a local reranker</SelectItem>
+                </SelectContent>
+              </Select>
+              <p className="text-sm text-muted-foreground">
+                Re-score retrieved entries against the question before answering; slower, better for vague questions
+              </p>
+            </div>
+
+            <div className="flex items-center justify-between gap-4">
+              <div className="space-y-1">
+                <Label>Plan Searches</Label>
+                <p className="text-sm text-muted-foreground">
+                  Turn questions into date, tag and people filters before searching ("my first month at the new job")
+                </p>
+              </div>
+              <Button
+                variant={queryPlanning ? "default" : "outline"}
+                size="sm"
+                onClick={() => setQueryPlanning(!queryPlanning)}
+              >
+                {queryPlanning ? "Enabled" : "Disabled"}
+              </Button>
+            </div>
+
+            <div className="space-y-2">
+              <div className="flex items-center justify-between gap-4">
+                <div className="space-y-1">
+                  <Label>AI Activity</Label>
+                  <p className="text-sm text-muted-foreground">
+                    Every request sent to an AI provider: where it went, why, its size and which entries it included
+                  </p>
+                </div>
+                <Button variant="outline" size="sm" onClick={loadAiActivity}>
+                  {aiActivity ? "Refresh" : "Show"}
+                </Button>
+              </div>
+              {aiActivity && (
+                aiActivity.length === 0 ? (
+                  <p className="text-sm text-muted-foreground">No AI requests yet</p>
+                ) : (
+                  <div className="max-h-64 overflow-y-auto rounded border text-xs">
+                    {aiActivity.map((record) => (
+                      <div key={record.id} className="flex flex-wrap gap-x-3 border-b px-2 py-1 last:border-b-0">
+                        <span className="text-muted-foreground">{new Date(record.created_at).toLocaleString()}</span>
+                        <span className="font-medium">{record.purpose}</span>
+                        <span>{record.destination}</span>
+                        <span>{record.bytes_sent.toLocaleString()} bytes</span>
+                        <span>{record.status ?? "no response"}</span>
+                        {record.entry_ids.length > 0 && <span>{record.entry_ids.length} entries</span>}
+                      </div>
+                    ))}
+                  </div>
+                )
+              )}
+            </div>
+          </div>
+        </CardContent>
+      </Card>
+
+      {/* Automation hooks */}
+      <Card>
+        <CardHeader>
+          <CardTitle className="flex items-center gap-2">
+            <Zap className="w-5 h-5" />
+            Automation
+          </CardTitle>
+          <CardDescription>
+            Daily reminders, and journal events sent to a webhook (Home Assistant, n8n) or a local script
+          </CardDescription>
+        </CardHeader>
+        <CardContent className="space-y-4">
+          <div className="flex items-center justify-between gap-4">
+            <div className="space-y-1">
+              <Label>"On this day" notification</Label>
+              <p className="text-sm text-muted-foreground">
+                Each morning, a summary of what you wrote on today's date in earlier years
+              </p>
+            </div>
+            <div className="flex items-center gap-2">
+              <Input
+                type="time"
+                value={onThisDayTime}
+                onChange={(e) => setOnThisDayTime(e.target.value)}
+                className="w-28"
+                disabled={!onThisDayNotify}
+              />
+              <Button
+                variant={onThisDayNotify ? "default" : "outline"}
+                size="sm"
+                onClick={() => setOnThisDayNotify(!onThisDayNotify)}
+              >
+                {onThisDayNotify ? "Enabled" : "Disabled"}
+              </Button>
+            </div>
+          </div>
+
+          <Separator />
+
+          <div className="space-y-2">
+            <Label htmlFor="hook-webhook">Webhook URL</Label>
+            <Input
+              id="hook-webhook"
+              value={hookWebhookUrl}
+              onChange={(e) => setHookWebhookUrl(e.target.value)}
+              placeholder="http://homeassistant.local:8123/api/webhook/journal"
+            />
+            <p className="text-sm text-muted-foreground">
+              Receives a POST with JSON: {"{ event, sent_at, data }"}
+            </p>
+          </div>
+          <div className="space-y-2">
+            <Label htmlFor="hook-script">Script</Label>
+            <Input
+              id="hook-script"
+              value={hookScript}
+              onChange={(e) => setHookScript(e.target.value)}
+              placeholder="/path/to/on-journal-event.sh"
+            />
+            <p className="text-sm text-muted-foreground">
+              Run with the event name as its argument and the same JSON on stdin
+            </p>
+          </div>
+          <div className="space-y-2">
+            <Label>Events</Label>
+            <div className="flex flex-wrap gap-2">
+              {HOOK_EVENTS.map((event) => (
+                <Button
+                  key={event.id}
+                  variant={hookEvents.includes(event.id) ? "default" : "outline"}
+                  size="sm"
+                  onClick={() => toggleHookEvent(event.id)}
+                >
+                  {event.label}
+                </Button>
+              ))}
+            </div>
           </div>
+          <Button onClick={testHooks} variant="outline" size="sm" disabled={!hookWebhookUrl.trim() && !hookScript.trim()}>
+            Send Test Event
+          </Button>
         </CardContent>
       </Card>
 
@@ -347,6 +1491,125 @@ export function Settings() {
           </CardDescription>
         </CardHeader>
         <CardContent className="space-y-4">
+          <div className="flex items-center justify-between gap-4">
+            <div className="space-y-1 min-w-0">
+              <Label>Database location</Label>
+              <p className="text-sm text-muted-foreground break-all">{dbPath || "..."}</p>
+            </div>
+            <Button onClick={relocateDatabase} variant="outline" size="sm" disabled={relocating}>
+              {relocating ? "Moving..." : "Move..."}
+            </Button>
+          </div>
+
+          <div className="flex items-center justify-between gap-4">
+            <div className="space-y-1">
+              <Label>Source files</Label>
+              <p className="text-sm text-muted-foreground">
+                Update entries whose imported .txt/.docx file was edited since import
+              </p>
+            </div>
+            <Button onClick={resyncSources} variant="outline" size="sm" disabled={resyncing}>
+              {resyncing ? "Checking..." : "Re-sync"}
+            </Button>
+          </div>
+
+          <div className="flex items-center justify-between gap-4">
+            <div className="space-y-1">
+              <Label>Write edits back</Label>
+              <p className="text-sm text-muted-foreground">
+                Saving an entry imported from a .txt file also rewrites that file, unless it was changed on disk since the last sync
+              </p>
+            </div>
+            <Button
+              variant={writeBackSources ? "default" : "outline"}
+              size="sm"
+              onClick={() => setWriteBackSources(!writeBackSources)}
+            >
+              {writeBackSources ? "Enabled" : "Disabled"}
+            </Button>
+          </div>
+
+          <div className="flex items-center justify-between gap-4">
+            <div className="space-y-1">
+              <Label>Archive originals</Label>
+              <p className="text-sm text-muted-foreground">
+                Keep a copy of each imported file with the journal, so entries can be recovered in their original form after the source folder is moved or deleted
+              </p>
+            </div>
+            <Button
+              variant={archiveOriginals ? "default" : "outline"}
+              size="sm"
+              onClick={() => setArchiveOriginals(!archiveOriginals)}
+            >
+              {archiveOriginals ? "Enabled" : "Disabled"}
+            </Button>
+          </div>
+
+          <Separator />
+
+          <div className="space-y-2">
+            <Label>Sensitive entries</Label>
+            <p className="text-sm text-muted-foreground">
+              {!sensitiveStatus?.configured
+                ? "Set a passphrase to seal individual entries. It is separate from database encryption and cannot be recovered."
+                : `${sensitiveStatus.entries} sealed ${sensitiveStatus.entries === 1 ? "entry" : "entries"}; ${sensitiveStatus.unlocked ? "unlocked until you lock them or quit" : "locked"}`}
+            </p>
+            <div className="flex gap-2">
+              {sensitiveStatus?.configured && !sensitiveStatus.unlocked && (
+                <Input
+                  type="password"
+                  placeholder="Passphrase"
+                  value={sensitivePassphrase}
+                  onChange={(e) => setSensitivePassphrase(e.target.value)}
+                />
+              )}
+              {sensitiveStatus?.configured && (
+                <Button onClick={toggleSensitiveLock} variant="outline" size="sm">
+                  {sensitiveStatus.unlocked ? "Lock" : "Unlock"}
+                </Button>
+              )}
+            </div>
+            {(!sensitiveStatus?.configured || sensitiveStatus.unlocked) && (
+              <div className="flex gap-2">
+                {sensitiveStatus?.configured && (
+                  <Input
+                    type="password"
+                    placeholder="Current passphrase"
+                    value={sensitivePassphrase}
+                    onChange={(e) => setSensitivePassphrase(e.target.value)}
+                  />
+                )}
+                <Input
+                  type="password"
+                  placeholder="New passphrase"
+                  value={newSensitivePassphrase}
+                  onChange={(e) => setNewSensitivePassphrase(e.target.value)}
+                />
+                <Button onClick={setSensitivePassphraseValue} variant="outline" size="sm" disabled={!newSensitivePassphrase}>
+                  {sensitiveStatus?.configured ? "Change" : "Set"}
+                </Button>
+              </div>
+            )}
+          </div>
+
+          <div className="flex items-center justify-between gap-4">
+            <div className="space-y-1">
+              <Label>Sensitive entries in AI chat</Label>
+              <p className="text-sm text-muted-foreground">
+                While unlocked, send the text of matching sensitive entries to the AI provider as context
+              </p>
+            </div>
+            <Button
+              variant={includeSensitive ? "default" : "outline"}
+              size="sm"
+              onClick={() => setIncludeSensitive(!includeSensitive)}
+            >
+              {includeSensitive ? "Enabled" : "Disabled"}
+            </Button>
+          </div>
+
+          <Separator />
+
           <div className="flex items-center justify-between">
             <div className="space-y-1">
               <Label>Auto-tagging</Label>
@@ -365,11 +1628,349 @@ export function Settings() {
 
           <Separator />
 
-          <div className="flex gap-2">
+          <div className="space-y-2">
+            <Label>Remote backup (S3-compatible)</Label>
+            <p className="text-sm text-muted-foreground">
+              Encrypted snapshots of the full backup, uploaded to AWS S3, MinIO, Backblaze B2, Cloudflare R2 or similar. Without the backup passphrase they cannot be restored.
+            </p>
+            <div className="grid grid-cols-2 gap-2">
+              <Input placeholder="Endpoint, e.g. https://s3.us-east-1.amazonaws.com" value={s3Endpoint} onChange={(e) => setS3Endpoint(e.target.value)} />
+              <Input placeholder="Region" value={s3Region} onChange={(e) => setS3Region(e.target.value)} />
+              <Input placeholder="Bucket" value={s3Bucket} onChange={(e) => setS3Bucket(e.target.value)} />
+              <Input placeholder="Key prefix" value={s3Prefix} onChange={(e) => setS3Prefix(e.target.value)} />
+              {S3_SECRETS.map(({ key, label }) => (
+                <Input
+                  key={key}
+                  type="password"
+                  placeholder={s3Stored[key] ? `${label} (stored in keychain)` : label}
+                  value={s3Secrets[key] ?? ""}
+                  onChange={(e) => setS3Secrets({ ...s3Secrets, [key]: e.target.value })}
+                />
+              ))}
+            </div>
+            <div className="grid grid-cols-2 gap-2">
+              <div className="space-y-1">
+                <Label htmlFor="s3-keep">Snapshots to keep</Label>
+                <Input id="s3-keep" type="number" min={1} value={s3Keep} onChange={(e) => setS3Keep(parseInt(e.target.value) || 1)} />
+              </div>
+              <div className="space-y-1">
+                <Label htmlFor="s3-interval">Back up every (hours, 0 = manual only)</Label>
+                <Input id="s3-interval" type="number" min={0} value={s3Interval} onChange={(e) => setS3Interval(parseInt(e.target.value) || 0)} />
+              </div>
+            </div>
+            <div className="flex gap-2">
+              <Button onClick={runS3Backup} variant="outline" size="sm" disabled={s3Busy}>
+                {s3Busy ? "Working..." : "Back Up Now"}
+              </Button>
+              <Button onClick={listS3Backups} variant="outline" size="sm" disabled={s3Busy}>
+                Show Backups
+              </Button>
+            </div>
+            {s3Snapshots && (
+              s3Snapshots.length === 0
+                ? <p className="text-sm text-muted-foreground">No snapshots under this prefix yet.</p>
+                : <div className="space-y-1">
+                    {s3Snapshots.map(s => (
+                      <div key={s.key} className="flex items-center justify-between gap-2 text-sm">
+                        <span className="truncate">{s.key} · {formatSize(s.size)}</span>
+                        <div className="flex gap-2">
+                          <Button onClick={() => restoreS3Backup(s.key, "merge")} variant="outline" size="sm" disabled={s3Busy}>
+                            Merge
+                          </Button>
+                          <Button onClick={() => restoreS3Backup(s.key, "full")} variant="outline" size="sm" disabled={s3Busy}>
+                            Restore
+                          </Button>
+                        </div>
+                      </div>
+                    ))}
+                  </div>
+            )}
+          </div>
+
+          <Separator />
+
+          <div className="space-y-2">
+            <div className="flex items-center justify-between gap-4">
+              <div className="space-y-1">
+                <Label>Sync over the local network</Label>
+                <p className="text-sm text-muted-foreground">
+                  Exchanges entries, tags, edits and deletions with your other devices running Journal Reader on the same network. Devices only sync when they share a pairing code.
+                  {lanSyncStatus?.listening_port ? ` Listening on port ${lanSyncStatus.listening_port}.` : ""}
+                </p>
+              </div>
+              <Button
+                variant={lanSyncEnabled ? "default" : "outline"}
+                size="sm"
+                onClick={() => setLanSyncEnabled(!lanSyncEnabled)}
+              >
+                {lanSyncEnabled ? "Enabled" : "Disabled"}
+              </Button>
+            </div>
+            <div className="grid grid-cols-2 gap-2">
+              <Input placeholder={lanSyncStatus?.device_name ?? "Device name"} value={lanSyncName} onChange={(e) => setLanSyncName(e.target.value)} />
+              <Input type="number" min={1} max={65535} value={lanSyncPort} onChange={(e) => setLanSyncPort(parseInt(e.target.value) || 47821)} />
+              <Input
+                placeholder={lanSyncStatus?.paired ? "Pairing code (stored in keychain)" : "Pairing code from another device"}
+                value={lanSyncCode}
+                onChange={(e) => setLanSyncCode(e.target.value)}
+              />
+              <Select value={lanSyncConflicts} onValueChange={setLanSyncConflicts}>
+                <SelectTrigger>
+                  <SelectValue />
+                </SelectTrigger>
+                <SelectContent>
+                  <SelectItem value="newest">Edited on both: newest edit wins</SelectItem>
+                  <SelectItem value="manual">Edited on both: keep this device's version</SelectItem>
+                </SelectContent>
+              </Select>
+            </div>
+            {lanSyncCode && lanSyncStatus?.paired && (
+              <p className="text-sm text-muted-foreground">Enter this code on your other devices, then save.</p>
+            )}
+            <div className="flex flex-wrap gap-2">
+              <Button onClick={generateLanSyncCode} variant="outline" size="sm" disabled={lanBusy}>
+                New Pairing Code
+              </Button>
+              <Button onClick={discoverLanPeers} variant="outline" size="sm" disabled={lanBusy || !lanSyncStatus?.paired}>
+                {lanBusy ? "Working..." : "Find Devices"}
+              </Button>
+              <Input
+                className="w-48"
+                placeholder="or host:port"
+                value={lanPeerAddress}
+                onChange={(e) => setLanPeerAddress(e.target.value)}
+              />
+              <Button onClick={() => syncWithLanPeer(lanPeerAddress)} variant="outline" size="sm" disabled={lanBusy || !lanPeerAddress.trim()}>
+                Sync
+              </Button>
+            </div>
+            {lanPeers && (
+              lanPeers.length === 0
+                ? <p className="text-sm text-muted-foreground">No other devices found. Check that sync is enabled on them and that they are on this network.</p>
+                : <div className="space-y-1">
+                    {lanPeers.map(p => (
+                      <div key={p.device_id} className="flex items-center justify-between gap-2 text-sm">
+                        <span className="truncate">
+                          {p.name} · {p.address}{p.last_synced_at ? ` · last synced ${new Date(p.last_synced_at).toLocaleString()}` : ""}
+                        </span>
+                        <Button onClick={() => syncWithLanPeer(p.address)} variant="outline" size="sm" disabled={lanBusy}>
+                          Sync
+                        </Button>
+                      </div>
+                    ))}
+                  </div>
+            )}
+            {lanSyncStatus && lanSyncStatus.conflicts > 0 && (
+              <p className="text-sm text-muted-foreground">{lanSyncStatus.conflicts} entries were edited in two places and are waiting for review under Conflicts.</p>
+            )}
+          </div>
+
+          <Separator />
+
+          <div className="space-y-2">
+            <div className="flex items-center justify-between gap-4">
+              <div className="space-y-1">
+                <Label>Email to journal (IMAP)</Label>
+                <p className="text-sm text-muted-foreground">
+                  Imports messages from a mail folder or label set aside for the journal; send or filter emails there to capture entries. New messages become entries tagged "email" and are marked as read.
+                </p>
+              </div>
+              <Button
+                variant={imapEnabled ? "default" : "outline"}
+                size="sm"
+                onClick={() => setImapEnabled(!imapEnabled)}
+              >
+                {imapEnabled ? "Enabled" : "Disabled"}
+              </Button>
+            </div>
+            <div className="grid grid-cols-2 gap-2">
+              {IMAP_SECRETS.map(({ key, label }) => (
+                <Input
+                  key={key}
+                  type={key === "imap_password" ? "password" : "text"}
+                  placeholder={imapStatus?.configured ? `${label} (stored in keychain)` : label}
+                  value={imapSecrets[key] ?? ""}
+                  onChange={(e) => setImapSecrets({ ...imapSecrets, [key]: e.target.value })}
+                />
+              ))}
+              <Input type="number" min={1} max={65535} value={imapPort} onChange={(e) => setImapPort(parseInt(e.target.value) || 993)} />
+            </div>
+            <div className="grid grid-cols-2 gap-2">
+              <div className="space-y-1">
+                <Label htmlFor="imap-folder">Folder or label</Label>
+                <Input id="imap-folder" value={imapFolder} onChange={(e) => setImapFolder(e.target.value)} />
+              </div>
+              <div className="space-y-1">
+                <Label htmlFor="imap-interval">Check every (minutes)</Label>
+                <Input id="imap-interval" type="number" min={1} value={imapInterval} onChange={(e) => setImapInterval(parseInt(e.target.value) || 15)} />
+              </div>
+            </div>
+            <div className="flex items-center gap-2">
+              <Button onClick={fetchImapMail} variant="outline" size="sm" disabled={imapBusy || !imapStatus?.configured}>
+                {imapBusy ? "Checking..." : "Check Now"}
+              </Button>
+              {imapStatus?.last_fetch_at && (
+                <span className="text-sm text-muted-foreground">Last checked {new Date(imapStatus.last_fetch_at).toLocaleString()}</span>
+              )}
+            </div>
+            {imapStatus?.last_error && (
+              <p className="text-sm text-red-600">{imapStatus.last_error}</p>
+            )}
+          </div>
+
+          <Separator />
+
+          <div className="flex flex-wrap gap-2">
             <Button onClick={() => invoke("init_database")} variant="outline" size="sm">
               <Database className="w-4 h-4 mr-2" />
               Reinitialize Database
             </Button>
+            <Button onClick={exportBackup} variant="outline" size="sm">
+              <Save className="w-4 h-4 mr-2" />
+              Export JSON Backup
+            </Button>
+            <Button onClick={() => importBackup("full")} variant="outline" size="sm">
+              <RefreshCw className="w-4 h-4 mr-2" />
+              Import JSON Backup
+            </Button>
+            <Button onClick={() => importBackup("merge")} variant="outline" size="sm" title="Add only missing entries and keep current settings">
+              <RefreshCw className="w-4 h-4 mr-2" />
+              Merge JSON Backup
+            </Button>
+            <Button onClick={exportSettingsFile} variant="outline" size="sm">
+              <Save className="w-4 h-4 mr-2" />
+              Export Settings
+            </Button>
+            <Button onClick={importSettingsFile} variant="outline" size="sm">
+              <RefreshCw className="w-4 h-4 mr-2" />
+              Import Settings
+            </Button>
+            <Button onClick={runDiagnostics} variant="outline" size="sm">
+              <Database className="w-4 h-4 mr-2" />
+              Diagnostics
+            </Button>
+            <Button onClick={copyRecentLogs} variant="outline" size="sm">
+              <Save className="w-4 h-4 mr-2" />
+              Copy Recent Logs
+            </Button>
+          </div>
+          <div className="space-y-2">
+            <Label htmlFor="log-level">Log Level</Label>
+            <Select value={logLevel} onValueChange={setLogLevel}>
+              <SelectTrigger id="log-level">
+                <SelectValue />
+              </SelectTrigger>
+              <SelectContent>
+                <SelectItem value="error">Errors only</SelectItem>
+                <SelectItem value="warn">Warnings</SelectItem>
+                <SelectItem value="info">Info</SelectItem>
+                <SelectItem value="debug">Debug (includes search queries)</SelectItem>
+                <SelectItem value="trace">Trace</SelectItem>
+              </SelectContent>
+            </Select>
+            <p className="text-sm text-muted-foreground">
+              How much is written to the log files; applies when saved
+            </p>
+          </div>
+          {diagnostics && (
+            <div className="text-sm space-y-1 border rounded-lg p-3">
+              <div>Schema version {diagnostics.schema_version} of {diagnostics.app_schema_version}</div>
+              <div>Database {formatSize(diagnostics.db_size)}, write-ahead log {formatSize(diagnostics.wal_size)}</div>
+              <div>
+                {diagnostics.total_entries} entries, {diagnostics.fts_rows} search index rows
+                {diagnostics.sensitive_entries > 0 && ` (${diagnostics.sensitive_entries} sensitive entries are indexed by title only)`}
+              </div>
+              {(diagnostics.entries_missing_fts > 0 || diagnostics.orphaned_fts_rows > 0 || diagnostics.duplicate_fts_rows > 0) && (
+                <div className="flex items-center justify-between gap-2 text-red-600">
+                  <span>
+                    Search index: {diagnostics.entries_missing_fts} entries missing, {diagnostics.orphaned_fts_rows} rows for deleted entries, {diagnostics.duplicate_fts_rows} duplicate rows
+                  </span>
+                  <Button onClick={repairSearchIndex} variant="outline" size="sm">Repair</Button>
+                </div>
+              )}
+              {diagnostics.fts_tokenizer_outdated && (
+                <div className="text-red-600">The search index was built with other tokenizer settings and is rebuilt on next start</div>
+              )}
+              <div>{diagnostics.entries_missing_embeddings} entries not yet embedded with the current model</div>
+              {diagnostics.indexes.some((i) => !i.present) && (
+                <div className="text-red-600">
+                  Missing indexes: {diagnostics.indexes.filter((i) => !i.present).map((i) => `${i.name} (${i.table})`).join(", ")}
+                </div>
+              )}
+            </div>
+          )}
+        </CardContent>
+      </Card>
+
+      <Conflicts />
+
+      <NearDuplicates />
+
+      <Redate />
+
+      {/* Export */}
+      <Card>
+        <CardHeader>
+          <CardTitle className="flex items-center gap-2">
+            <BookOpen className="w-5 h-5" />
+            Export
+          </CardTitle>
+          <CardDescription>
+            Export the entries in a date range, optionally only those with certain tags: as a printable PDF book (a chapter per month) or as JSON
+          </CardDescription>
+        </CardHeader>
+        <CardContent className="space-y-4">
+          <div className="grid grid-cols-1 md:grid-cols-2 gap-4">
+            <div className="space-y-2">
+              <Label htmlFor="pdf-start">From</Label>
+              <Input id="pdf-start" type="date" value={pdfStart} onChange={(e) => setPdfStart(e.target.value)} />
+            </div>
+            <div className="space-y-2">
+              <Label htmlFor="pdf-end">To</Label>
+              <Input id="pdf-end" type="date" value={pdfEnd} onChange={(e) => setPdfEnd(e.target.value)} />
+            </div>
+            <div className="space-y-2 md:col-span-2">
+              <Label htmlFor="export-tags">Tags</Label>
+              <Input
+                id="export-tags"
+                placeholder="e.g. travel, family (any of them; leave empty for all)"
+                value={exportTags}
+                onChange={(e) => setExportTags(e.target.value)}
+              />
+            </div>
+            <div className="space-y-2">
+              <Label htmlFor="pdf-page-size">Page Size</Label>
+              <Select value={pdfPageSize} onValueChange={setPdfPageSize}>
+                <SelectTrigger id="pdf-page-size">
+                  <SelectValue />
+                </SelectTrigger>
+                <SelectContent>
+                  <SelectItem value="letter">Letter</SelectItem>
+                  <SelectItem value="a4">A4</SelectItem>
+                </SelectContent>
+              </Select>
+            </div>
+            <div className="flex items-end justify-between gap-2">
+              <Button
+                variant={pdfToc ? "default" : "outline"}
+                size="sm"
+                onClick={() => setPdfToc(!pdfToc)}
+              >
+                {pdfToc ? "Table of contents" : "No table of contents"}
+              </Button>
+              <Button onClick={exportPdf} disabled={exportingPdf || !pdfStart || !pdfEnd}>
+                {exportingPdf ? (
+                  <RefreshCw className="w-4 h-4 mr-2 animate-spin" />
+                ) : (
+                  <BookOpen className="w-4 h-4 mr-2" />
+                )}
+                Export PDF
+              </Button>
+              <Button onClick={exportFilteredJson} variant="outline">
+                <Save className="w-4 h-4 mr-2" />
+                Export JSON
+              </Button>
+            </div>
           </div>
         </CardContent>
       </Card>
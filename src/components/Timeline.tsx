@@ -1,7 +1,9 @@
 import { useState, useEffect } from "react";
 import { invoke } from "@tauri-apps/api/core";
+import { save } from "@tauri-apps/plugin-dialog";
 import { Card, CardContent, CardDescription, CardHeader, CardTitle } from "@/components/ui/card";
 import { Button } from "@/components/ui/button";
+import { Input } from "@/components/ui/input";
 import { Separator } from "@/components/ui/separator";
 import * as Dialog from "@radix-ui/react-dialog";
 import { Badge } from "@/components/ui/badge";
@@ -12,8 +14,12 @@ import {
   ChevronRight,
   Clock,
   FileText,
+  Flame,
   Tag,
+  Trophy,
 } from "lucide-react";
+import { errorMessage } from "@/lib/errors";
+import { formatDate, monthName } from "@/lib/dates";
 
 interface EntryPreview {
   id: string;
@@ -21,10 +27,126 @@ interface EntryPreview {
   preview: string;
   entry_date: string;
   tags: string[];
+  sensitive?: boolean;
+}
+
+interface ArchivedOriginal {
+  source_path: string;
+  archive_path: string;
+}
+
+interface EntryPhoto {
+  path: string;
+  taken_at: string;
+  caption: string | null;
+  position: number;
+}
+
+interface EntryAudio {
+  entry_id: string;
+  path: string;
+  format: "wav" | "mp3";
+  engine: string;
+  cached: boolean;
 }
 
 interface MonthCount { month: number; count: number; }
 
+interface WritingStreaks {
+  current_streak: number;
+  longest_streak: number;
+  longest_streak_start?: string;
+  longest_streak_end?: string;
+  wrote_today: boolean;
+  months: { year: number; month: number; entries: number; days_written: number }[];
+}
+
+interface TimeBucket { label: string; entries: number; average_words: number; }
+
+interface WritingTimes {
+  total_entries: number;
+  untimed_entries: number;
+  weekdays: TimeBucket[];
+  hours: TimeBucket[];
+  periods: TimeBucket[];
+}
+
+interface YearSummaryStats {
+  entries: number;
+  total_words: number;
+  days_written: number;
+  longest_entry?: { id: string; title?: string; word_count: number };
+  most_active_month?: { month: number; count: number };
+  new_tags: string[];
+  top_people: { name: string; entries: number; mentions: number }[];
+}
+
+interface TagTrends {
+  granularity: string;
+  periods: string[];
+  series: { tag: string; counts: number[]; total: number }[];
+}
+
+const TREND_COLORS = ["#2563eb", "#dc2626", "#16a34a", "#d97706", "#7c3aed", "#0891b2"];
+
+// One line per tag over the periods, scaled to the highest count
+function TrendChart({ trends }: { trends: TagTrends }) {
+  const width = 640;
+  const height = 160;
+  const max = Math.max(1, ...trends.series.flatMap((s) => s.counts));
+  const x = (i: number) => (trends.periods.length > 1 ? (i / (trends.periods.length - 1)) * width : width / 2);
+  const y = (count: number) => height - (count / max) * (height - 8) - 4;
+  return (
+    <div className="space-y-2">
+      <svg viewBox={`0 0 ${width} ${height}`} className="w-full h-40 border rounded bg-muted/30" preserveAspectRatio="none">
+        {trends.series.map((s, i) => (
+          <polyline
+            key={s.tag}
+            fill="none"
+            stroke={TREND_COLORS[i % TREND_COLORS.length]}
+            strokeWidth={2}
+            vectorEffect="non-scaling-stroke"
+            points={s.counts.map((c, j) => `${x(j)},${y(c)}`).join(" ")}
+          />
+        ))}
+      </svg>
+      <div className="flex justify-between text-xs text-muted-foreground">
+        <span>{trends.periods[0]}</span>
+        <span>peak {max}</span>
+        <span>{trends.periods[trends.periods.length - 1]}</span>
+      </div>
+      <div className="flex flex-wrap gap-3 text-xs">
+        {trends.series.map((s, i) => (
+          <span key={s.tag} className="flex items-center gap-1">
+            <span className="inline-block w-3 h-3 rounded-sm" style={{ backgroundColor: TREND_COLORS[i % TREND_COLORS.length] }} />
+            {s.tag} ({s.total})
+          </span>
+        ))}
+      </div>
+    </div>
+  );
+}
+
+// One row per bucket: a bar scaled to the busiest bucket, the count and the average length
+function BucketBars({ buckets }: { buckets: TimeBucket[] }) {
+  const max = Math.max(1, ...buckets.map((b) => b.entries));
+  return (
+    <div className="space-y-1">
+      {buckets.map((b) => (
+        <div key={b.label} className="flex items-center gap-2 text-xs">
+          <span className="w-20 shrink-0 text-muted-foreground">{b.label}</span>
+          <div className="flex-1 h-2 rounded bg-muted">
+            <div className="h-2 rounded bg-primary" style={{ width: `${(b.entries / max) * 100}%` }} />
+          </div>
+          <span className="w-28 shrink-0 text-right">
+            {b.entries} {b.entries > 0 && `· ${Math.round(b.average_words)} words`}
+          </span>
+        </div>
+      ))}
+    </div>
+  );
+}
+
 export function Timeline() {
   const [years, setYears] = useState<number[]>([]);
   const [monthCounts, setMonthCounts] = useState<MonthCount[]>([]);
@@ -36,27 +158,73 @@ export function Timeline() {
   const [isEntryOpen, setIsEntryOpen] = useState(false);
   const [entryLoading, setEntryLoading] = useState(false);
   const [selectedEntry, setSelectedEntry] = useState<EntryPreview | null>(null);
+  const [entryOriginal, setEntryOriginal] = useState<ArchivedOriginal | null>(null);
+  const [entryError, setEntryError] = useState<string | null>(null);
+  const [entryAudioUrl, setEntryAudioUrl] = useState<string | null>(null);
+  // Object URLs for photos the webview can show (not HEIC or TIFF)
+  const [entryPhotos, setEntryPhotos] = useState<{ photo: EntryPhoto; url: string | null }[]>([]);
+  const [audioLoading, setAudioLoading] = useState(false);
+  const [streaks, setStreaks] = useState<WritingStreaks | null>(null);
+  const [writingTimes, setWritingTimes] = useState<WritingTimes | null>(null);
+  const [trendTags, setTrendTags] = useState("");
+  const [trendGranularity, setTrendGranularity] = useState<"month" | "year">("year");
+  const [tagTrends, setTagTrends] = useState<TagTrends | null>(null);
+  const [yearStats, setYearStats] = useState<YearSummaryStats | null>(null);
 
   useEffect(() => {
     loadYears();
     // quick diagnostics for user
     invoke<any>("get_db_diagnostics").then((info) => setDbInfo(info)).catch(() => {});
+    invoke<WritingStreaks>("get_writing_streaks").then(setStreaks).catch(() => {});
+    invoke<WritingTimes>("get_writing_times").then(setWritingTimes).catch(() => {});
   }, []);
 
   useEffect(() => {
     if (selectedYear) {
       loadMonthCounts(selectedYear);
+      invoke<YearSummaryStats>("get_year_summary_stats", { year: selectedYear }).then(setYearStats).catch(() => setYearStats(null));
       setSelectedMonth(null);
       setEntries([]);
     }
   }, [selectedYear]);
 
+  // Drop the previous entry's audio when another is opened or the modal closes
+  useEffect(() => {
+    return () => {
+      if (entryAudioUrl) URL.revokeObjectURL(entryAudioUrl);
+    };
+  }, [entryAudioUrl]);
+
+  useEffect(() => {
+    return () => entryPhotos.forEach((p) => p.url && URL.revokeObjectURL(p.url));
+  }, [entryPhotos]);
+
+  useEffect(() => {
+    if (!isEntryOpen) {
+      setEntryAudioUrl(null);
+      setEntryPhotos([]);
+    }
+  }, [isEntryOpen]);
+
+  useEffect(() => {
+    loadTagTrends();
+  }, [trendGranularity]);
+
   useEffect(() => {
     if (selectedMonth) {
       loadEntries(selectedYear, selectedMonth);
     }
   }, [selectedMonth]);
 
+  const loadTagTrends = async () => {
+    try {
+      const tags = trendTags.split(",").map((t) => t.trim()).filter(Boolean);
+      setTagTrends(await invoke<TagTrends>("get_tag_trends", { tags, granularity: trendGranularity }));
+    } catch (error) {
+      console.error("Failed to load tag trends:", error);
+    }
+  };
+
   const loadYears = async () => {
     try {
       setIsLoading(true);
@@ -93,8 +261,12 @@ export function Timeline() {
       setEntryLoading(true);
       const data = await invoke<EntryPreview | null>("get_entry_by_id", { id: entryId });
       if (data) {
+        setEntryError(null);
+        setEntryAudioUrl(null);
         setSelectedEntry(data);
+        setEntryOriginal(await invoke<ArchivedOriginal | null>("get_entry_original", { id: entryId }));
         setIsEntryOpen(true);
+        loadPhotos(entryId);
       }
     } catch (error) {
       console.error("Failed to load entry:", error);
@@ -103,12 +275,80 @@ export function Timeline() {
     }
   };
 
-  const formatDate = (dateString: string) => {
-    return new Date(dateString).toLocaleDateString('en-US', {
-      year: 'numeric',
-      month: 'long',
-      day: 'numeric'
-    });
+  const loadPhotos = async (entryId: string) => {
+    try {
+      const photos = await invoke<EntryPhoto[]>("get_entry_photos", { id: entryId });
+      const loaded = await Promise.all(photos.map(async (photo) => {
+        if (!/\.(jpe?g|png|webp)$/i.test(photo.path)) return { photo, url: null };
+        try {
+          const bytes = await invoke<ArrayBuffer>("read_entry_photo", { id: entryId, position: photo.position });
+          return { photo, url: URL.createObjectURL(new Blob([bytes])) };
+        } catch {
+          // Moved or deleted since the import
+          return { photo, url: null };
+        }
+      }));
+      setEntryPhotos(loaded);
+    } catch (error) {
+      console.error("Failed to load entry photos:", error);
+    }
+  };
+
+  // Sealing and unsealing both need sensitive entries unlocked in Settings
+  const toggleSensitive = async () => {
+    if (!selectedEntry) return;
+    try {
+      await invoke("set_entry_sensitive", { id: selectedEntry.id, sensitive: !selectedEntry.sensitive });
+      await openEntry(selectedEntry.id);
+    } catch (error: any) {
+      setEntryError(errorMessage(error));
+    }
+  };
+
+  // Imported files archived with the journal can be saved back out after the source is gone
+  const saveOriginal = async () => {
+    if (!selectedEntry || !entryOriginal) return;
+    try {
+      const dest = await save({ defaultPath: entryOriginal.source_path.split(/[\\/]/).pop() });
+      if (!dest) return;
+      await invoke("export_entry_original", { id: selectedEntry.id, dest });
+    } catch (error: any) {
+      setEntryError(errorMessage(error));
+    }
+  };
+
+  // The first listen synthesizes the audio, which takes a while for long entries; later ones reuse it
+  const listen = async () => {
+    if (!selectedEntry) return;
+    try {
+      setAudioLoading(true);
+      setEntryError(null);
+      const audio = await invoke<EntryAudio>("synthesize_entry_audio", { id: selectedEntry.id });
+      const bytes = await invoke<ArrayBuffer>("read_entry_audio", { id: selectedEntry.id });
+      const type = audio.format === "mp3" ? "audio/mpeg" : "audio/wav";
+      setEntryAudioUrl(URL.createObjectURL(new Blob([bytes], { type })));
+    } catch (error: any) {
+      setEntryError(errorMessage(error));
+    } finally {
+      setAudioLoading(false);
+    }
+  };
+
+  const saveAudio = async () => {
+    if (!selectedEntry) return;
+    try {
+      setAudioLoading(true);
+      setEntryError(null);
+      const audio = await invoke<EntryAudio>("synthesize_entry_audio", { id: selectedEntry.id });
+      const name = (selectedEntry.title || formatDate(selectedEntry.entry_date, "numeric", { timeZone: "UTC" })).replace(/[\\/:*?"<>|]/g, "-");
+      const dest = await save({ defaultPath: `${name}.${audio.format}`, filters: [{ name: "Audio", extensions: [audio.format] }] });
+      if (!dest) return;
+      await invoke("export_entry_audio", { id: selectedEntry.id, dest });
+    } catch (error: any) {
+      setEntryError(errorMessage(error));
+    } finally {
+      setAudioLoading(false);
+    }
   };
 
   if (isLoading) {
@@ -129,7 +369,24 @@ export function Timeline() {
           <h2 className="text-2xl font-bold">Timeline</h2>
           <p className="text-muted-foreground">Browse by year → month. Click a month to view entries.</p>
         </div>
-        <div className="flex items-center gap-2" />
+        {streaks && streaks.longest_streak > 0 && (
+          <div className="flex items-center gap-2">
+            <Badge
+              variant={streaks.current_streak > 0 ? "default" : "outline"}
+              title={streaks.current_streak > 0 && !streaks.wrote_today ? "Write today to keep it going" : undefined}
+            >
+              <Flame className="w-3 h-3 mr-1" />
+              {streaks.current_streak > 0 ? `${streaks.current_streak}-day streak` : "No current streak"}
+            </Badge>
+            <Badge
+              variant="outline"
+              title={streaks.longest_streak_start ? `${streaks.longest_streak_start} to ${streaks.longest_streak_end}` : undefined}
+            >
+              <Trophy className="w-3 h-3 mr-1" />
+              Longest: {streaks.longest_streak} {streaks.longest_streak === 1 ? "day" : "days"}
+            </Badge>
+          </div>
+        )}
       </div>
       {dbInfo && (
         <div className="text-xs text-muted-foreground">
@@ -164,12 +421,45 @@ export function Timeline() {
           </CardTitle>
         </CardHeader>
         <CardContent>
+          {yearStats && yearStats.entries > 0 && (
+            <div className="mb-4 space-y-2 text-sm">
+              <div className="flex flex-wrap gap-x-6 gap-y-1">
+                <span><strong>{yearStats.entries}</strong> {yearStats.entries === 1 ? "entry" : "entries"}</span>
+                <span><strong>{yearStats.total_words.toLocaleString()}</strong> words</span>
+                <span><strong>{yearStats.days_written}</strong> days written</span>
+                {yearStats.most_active_month && (
+                  <span>
+                    Most active: <strong>{monthName(yearStats.most_active_month.month)}</strong>
+                  </span>
+                )}
+                {yearStats.longest_entry && (
+                  <span className="cursor-pointer underline-offset-2 hover:underline" onClick={() => openEntry(yearStats.longest_entry!.id)}>
+                    Longest: <strong>{yearStats.longest_entry.title || `${yearStats.longest_entry.word_count} words`}</strong>
+                  </span>
+                )}
+              </div>
+              {yearStats.top_people.length > 0 && (
+                <div className="text-muted-foreground">
+                  People: {yearStats.top_people.slice(0, 5).map((p) => `${p.name} (${p.entries})`).join(", ")}
+                </div>
+              )}
+              {yearStats.new_tags.length > 0 && (
+                <div className="flex flex-wrap items-center gap-1">
+                  <span className="text-muted-foreground mr-1">New tags:</span>
+                  {yearStats.new_tags.map((tag) => (
+                    <Badge key={tag} variant="secondary" size="sm">{tag}</Badge>
+                  ))}
+                </div>
+              )}
+            </div>
+          )}
           {/* 12-month grid with two colors */}
           <div className="grid grid-cols-2 md:grid-cols-3 lg:grid-cols-4 gap-3">
             {Array.from({ length: 12 }, (_, i) => i + 1).map((m) => {
               const count = monthCounts.find(mc => mc.month === m)?.count || 0;
+              const daysWritten = streaks?.months.find(f => f.year === selectedYear && f.month === m)?.days_written || 0;
               const hasEntries = count > 0;
-              const label = new Date(2000, m - 1, 1).toLocaleString('en-US', { month: 'long' });
+              const label = monthName(m);
               return (
                 <Button
                   key={m}
@@ -178,7 +468,10 @@ export function Timeline() {
                   onClick={() => setSelectedMonth(m)}
                 >
                   <span className="font-medium">{label}</span>
-                  <span className="text-xs opacity-80">{count} {count === 1 ? 'entry' : 'entries'}</span>
+                  <span className="text-xs opacity-80">
+                    {count} {count === 1 ? 'entry' : 'entries'}
+                    {daysWritten > 0 && ` · ${daysWritten} ${daysWritten === 1 ? 'day' : 'days'}`}
+                  </span>
                 </Button>
               );
             })}
@@ -188,7 +481,7 @@ export function Timeline() {
           {selectedMonth && (
             <div className="mt-6 space-y-3">
               <h3 className="font-medium">
-                {new Date(selectedYear, selectedMonth - 1, 1).toLocaleString('en-US', { month: 'long', year: 'numeric' })}
+                {formatDate(new Date(selectedYear, selectedMonth - 1, 1), 'monthYear')}
               </h3>
               {entries.length === 0 ? (
                 <div className="text-center py-8 text-muted-foreground">
@@ -227,6 +520,68 @@ export function Timeline() {
         </CardContent>
       </Card>
 
+      {writingTimes && writingTimes.total_entries > 0 && (
+        <Card>
+          <CardHeader>
+            <CardTitle className="flex items-center gap-2">
+              <Clock className="w-5 h-5" />
+              When you write
+            </CardTitle>
+            <CardDescription>
+              Entries and average length, in each entry's timezone
+              {writingTimes.untimed_entries > 0 &&
+                `. ${writingTimes.untimed_entries} imported ${writingTimes.untimed_entries === 1 ? "entry has" : "entries have"} only a date and ${writingTimes.untimed_entries === 1 ? "is" : "are"} left out of the times of day`}
+            </CardDescription>
+          </CardHeader>
+          <CardContent className="grid gap-6 md:grid-cols-2">
+            <div className="space-y-2">
+              <h3 className="text-sm font-medium">By weekday</h3>
+              <BucketBars buckets={writingTimes.weekdays} />
+            </div>
+            <div className="space-y-2">
+              <h3 className="text-sm font-medium">By time of day</h3>
+              <BucketBars buckets={writingTimes.periods} />
+            </div>
+            <div className="space-y-2 md:col-span-2">
+              <h3 className="text-sm font-medium">By hour</h3>
+              <BucketBars buckets={writingTimes.hours.filter((h) => h.entries > 0)} />
+            </div>
+          </CardContent>
+        </Card>
+      )}
+
+      <Card>
+        <CardHeader>
+          <CardTitle className="flex items-center gap-2">
+            <Tag className="w-5 h-5" />
+            Tag trends
+          </CardTitle>
+          <CardDescription>How often tags appear over time; leave empty for your most used tags</CardDescription>
+        </CardHeader>
+        <CardContent className="space-y-4">
+          <div className="flex flex-wrap items-center gap-2">
+            <Input
+              value={trendTags}
+              onChange={(e) => setTrendTags(e.target.value)}
+              onKeyDown={(e) => e.key === "Enter" && loadTagTrends()}
+              placeholder="anxiety, gratitude"
+              className="w-72"
+            />
+            <Button variant="outline" size="sm" onClick={loadTagTrends}>Show</Button>
+            {(["month", "year"] as const).map((g) => (
+              <Button key={g} variant={trendGranularity === g ? "default" : "outline"} size="sm" onClick={() => setTrendGranularity(g)}>
+                By {g}
+              </Button>
+            ))}
+          </div>
+          {tagTrends && tagTrends.periods.length > 0 ? (
+            <TrendChart trends={tagTrends} />
+          ) : (
+            <p className="text-sm text-muted-foreground">No tagged entries{trendTags.trim() && " for these tags"}.</p>
+          )}
+        </CardContent>
+      </Card>
+
       {/* Entry Modal */}
       <Dialog.Root open={isEntryOpen} onOpenChange={setIsEntryOpen}>
         <Dialog.Portal>
@@ -239,9 +594,43 @@ export function Timeline() {
               {selectedEntry && formatDate(selectedEntry.entry_date)}
             </div>
             <div className="whitespace-pre-wrap leading-relaxed text-sm">
-              {entryLoading ? 'Loading…' : (selectedEntry?.preview || '')}
+              {entryLoading ? 'Loading…' : (selectedEntry?.sensitive && !selectedEntry.preview
+                ? <span className="text-muted-foreground">This entry is sensitive. Unlock sensitive entries in Settings to read it.</span>
+                : (selectedEntry?.preview || ''))}
             </div>
-            <div className="mt-4 flex justify-end">
+            {entryPhotos.length > 0 && (
+              <div className="mt-4 grid grid-cols-3 gap-2">
+                {entryPhotos.map(({ photo, url }) => (
+                  <figure key={photo.position} className="text-xs text-muted-foreground" title={photo.path}>
+                    {url
+                      ? <img src={url} alt={photo.caption ?? ''} className="w-full h-28 object-cover rounded" />
+                      : <div className="w-full h-28 rounded border flex items-center justify-center">{photo.path.split(/[\\/]/).pop()}</div>}
+                    {photo.caption && <figcaption className="mt-1 line-clamp-2">{photo.caption}</figcaption>}
+                  </figure>
+                ))}
+              </div>
+            )}
+            {entryAudioUrl && <audio controls autoPlay src={entryAudioUrl} className="mt-4 w-full" />}
+            {entryError && <p className="mt-4 text-sm text-red-600">{entryError}</p>}
+            <div className="mt-4 flex justify-end gap-2">
+              {!selectedEntry?.sensitive && (
+                <>
+                  <Button variant="outline" onClick={listen} disabled={audioLoading}>
+                    {audioLoading ? 'Reading…' : 'Listen'}
+                  </Button>
+                  <Button variant="outline" onClick={saveAudio} disabled={audioLoading}>
+                    Save audio
+                  </Button>
+                </>
+              )}
+              {entryOriginal && (
+                <Button variant="outline" onClick={saveOriginal} title={entryOriginal.source_path}>
+                  Save original
+                </Button>
+              )}
+              <Button variant="outline" onClick={toggleSensitive}>
+                {selectedEntry?.sensitive ? 'Unmark sensitive' : 'Mark sensitive'}
+              </Button>
               <Button variant="outline" onClick={() => setIsEntryOpen(false)}>Close</Button>
             </div>
           </Dialog.Content>
@@ -0,0 +1,263 @@
+import { useState, useEffect } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { Card, CardContent, CardDescription, CardHeader, CardTitle } from "@/components/ui/card";
+import { Button } from "@/components/ui/button";
+import { Input } from "@/components/ui/input";
+import { Label } from "@/components/ui/label";
+import * as Dialog from "@radix-ui/react-dialog";
+import { PenLine, Plus, Trash2 } from "lucide-react";
+import { errorMessage } from "@/lib/errors";
+import { formatDate } from "@/lib/dates";
+
+interface EntryTemplate {
+  id: string;
+  name: string;
+  title?: string;
+  body: string;
+}
+
+interface EntryDetail {
+  id: string;
+  title?: string;
+  preview: string;
+  entry_date: string;
+}
+
+interface Setting {
+  key: string;
+  value: string;
+}
+
+const todayIso = () => {
+  const now = new Date();
+  return `${now.getFullYear()}-${String(now.getMonth() + 1).padStart(2, "0")}-${String(now.getDate()).padStart(2, "0")}`;
+};
+
+const EXAMPLE_BODY = "{{date}}\nWeather: {{weather}}\n\nToday I'm grateful for:\n1. \n2. \n3. \n\nGoals for tomorrow:\n- \n\n{{prompt}}\n";
+
+// Entry templates, and new entries written from them
+export function Write() {
+  const [templates, setTemplates] = useState<EntryTemplate[]>([]);
+  const [selectedId, setSelectedId] = useState<string | null>(null);
+  const [name, setName] = useState("");
+  const [title, setTitle] = useState("");
+  const [body, setBody] = useState("");
+  const [entryDate, setEntryDate] = useState(todayIso());
+  const [weatherLocation, setWeatherLocation] = useState("");
+  const [isCreating, setIsCreating] = useState(false);
+  const [editing, setEditing] = useState<EntryDetail | null>(null);
+  const [editTitle, setEditTitle] = useState("");
+  const [editBody, setEditBody] = useState("");
+
+  useEffect(() => {
+    loadTemplates();
+    invoke<Setting[]>("get_settings")
+      .then((settings) => setWeatherLocation(settings.find((s) => s.key === "weather_location")?.value || ""))
+      .catch((error) => console.error("Failed to load settings:", error));
+  }, []);
+
+  const loadTemplates = async (select?: string) => {
+    try {
+      const list = await invoke<EntryTemplate[]>("list_templates");
+      setTemplates(list);
+      const current = list.find((t) => t.id === (select ?? selectedId)) || list[0];
+      selectTemplate(current || null);
+    } catch (error) {
+      console.error("Failed to load templates:", error);
+    }
+  };
+
+  const selectTemplate = (template: EntryTemplate | null) => {
+    setSelectedId(template?.id || null);
+    setName(template?.name || "");
+    setTitle(template?.title || "");
+    setBody(template?.body ?? EXAMPLE_BODY);
+  };
+
+  const saveTemplate = async () => {
+    try {
+      const saved = await invoke<EntryTemplate>("save_template", { id: selectedId, name, title: title || null, body });
+      await loadTemplates(saved.id);
+    } catch (error) {
+      alert(`Could not save template: ${errorMessage(error)}`);
+    }
+  };
+
+  const deleteTemplate = async () => {
+    if (!selectedId || !confirm(`Delete the template "${name}"?`)) return;
+    try {
+      await invoke("delete_template", { id: selectedId });
+      setSelectedId(null);
+      await loadTemplates("");
+    } catch (error) {
+      alert(`Could not delete template: ${errorMessage(error)}`);
+    }
+  };
+
+  const saveWeatherLocation = async () => {
+    try {
+      await invoke("update_setting", { key: "weather_location", value: weatherLocation.trim() });
+    } catch (error) {
+      console.error("Failed to save weather location:", error);
+    }
+  };
+
+  const createEntry = async () => {
+    if (!selectedId) return;
+    try {
+      setIsCreating(true);
+      const timezone = Intl.DateTimeFormat().resolvedOptions().timeZone;
+      const result = await invoke<{ entry_id: string, created: boolean }>("create_entry_from_template", {
+        templateId: selectedId,
+        date: entryDate,
+        timezone,
+      });
+      const entry = await invoke<EntryDetail | null>("get_entry_by_id", { id: result.entry_id });
+      if (entry) {
+        setEditing(entry);
+        setEditTitle(entry.title || "");
+        setEditBody(entry.preview);
+      }
+    } catch (error) {
+      alert(`Could not create entry: ${errorMessage(error)}`);
+    } finally {
+      setIsCreating(false);
+    }
+  };
+
+  const saveEntry = async (overwriteSource = false) => {
+    if (!editing) return;
+    try {
+      await invoke("update_entry", { id: editing.id, title: editTitle || null, body: editBody, overwriteSource });
+      setEditing(null);
+    } catch (error: any) {
+      // The source file was edited outside the app since it was last synced
+      if (error?.code === "SOURCE_CONFLICT" && confirm(`${error.message}\n\nOverwrite the file with this version?`)) {
+        await saveEntry(true);
+        return;
+      }
+      alert(`Could not save entry: ${error?.message ?? error}`);
+    }
+  };
+
+  return (
+    <div className="space-y-6">
+      <Card>
+        <CardHeader>
+          <CardTitle className="flex items-center gap-2">
+            <PenLine className="w-5 h-5" />
+            New entry from a template
+          </CardTitle>
+          <CardDescription>The entry is created with the template's variables filled in, then opened for writing</CardDescription>
+        </CardHeader>
+        <CardContent>
+          <div className="flex flex-wrap items-end gap-3">
+            <div className="space-y-2">
+              <Label htmlFor="entry-template">Template</Label>
+              <select
+                id="entry-template"
+                className="h-9 w-56 rounded-md border bg-background px-2 text-sm"
+                value={selectedId || ""}
+                onChange={(e) => selectTemplate(templates.find((t) => t.id === e.target.value) || null)}
+              >
+                {templates.length === 0 && <option value="">No templates yet</option>}
+                {templates.map((t) => (
+                  <option key={t.id} value={t.id}>{t.name}</option>
+                ))}
+              </select>
+            </div>
+            <div className="space-y-2">
+              <Label htmlFor="entry-date">Date</Label>
+              <Input id="entry-date" type="date" value={entryDate} onChange={(e) => setEntryDate(e.target.value)} className="w-44" />
+            </div>
+            <Button onClick={createEntry} disabled={!selectedId || !entryDate || isCreating}>
+              {isCreating ? "Creating..." : "Create Entry"}
+            </Button>
+          </div>
+        </CardContent>
+      </Card>
+
+      <Card>
+        <CardHeader>
+          <div className="flex items-center justify-between gap-4">
+            <div>
+              <CardTitle>Templates</CardTitle>
+              <CardDescription>
+                Variables: {"{{date}}"} (the entry's date), {"{{weather}}"} (that day's weather), {"{{prompt}}"} (a writing prompt)
+              </CardDescription>
+            </div>
+            <Button variant="outline" size="sm" onClick={() => selectTemplate(null)}>
+              <Plus className="w-4 h-4 mr-2" />
+              New Template
+            </Button>
+          </div>
+        </CardHeader>
+        <CardContent className="space-y-4">
+          <div className="grid grid-cols-2 gap-4">
+            <div className="space-y-2">
+              <Label htmlFor="template-name">Name</Label>
+              <Input id="template-name" value={name} onChange={(e) => setName(e.target.value)} placeholder="Nightly" />
+            </div>
+            <div className="space-y-2">
+              <Label htmlFor="template-title">Entry title</Label>
+              <Input id="template-title" value={title} onChange={(e) => setTitle(e.target.value)} placeholder="Evening, {{date}}" />
+            </div>
+          </div>
+          <div className="space-y-2">
+            <Label htmlFor="template-body">Body</Label>
+            <textarea
+              id="template-body"
+              className="w-full min-h-[220px] rounded-md border bg-background p-3 text-sm font-mono"
+              value={body}
+              onChange={(e) => setBody(e.target.value)}
+            />
+          </div>
+          <div className="space-y-2">
+            <Label htmlFor="weather-location">Weather location</Label>
+            <Input
+              id="weather-location"
+              value={weatherLocation}
+              onChange={(e) => setWeatherLocation(e.target.value)}
+              onBlur={saveWeatherLocation}
+              placeholder="latitude,longitude (e.g. 52.52,13.41)"
+              className="w-72"
+            />
+            <p className="text-sm text-muted-foreground">Used by {"{{weather}}"}; looked up from Open-Meteo. Leave empty to skip it.</p>
+          </div>
+          <div className="flex gap-2">
+            <Button onClick={saveTemplate} disabled={!name.trim()}>
+              {selectedId ? "Save Template" : "Create Template"}
+            </Button>
+            {selectedId && (
+              <Button variant="outline" onClick={deleteTemplate}>
+                <Trash2 className="w-4 h-4 mr-2" />
+                Delete
+              </Button>
+            )}
+          </div>
+        </CardContent>
+      </Card>
+
+      <Dialog.Root open={editing !== null} onOpenChange={(open) => !open && setEditing(null)}>
+        <Dialog.Portal>
+          <Dialog.Overlay className="fixed inset-0 bg-black/40" />
+          <Dialog.Content className="fixed left-1/2 top-1/2 -translate-x-1/2 -translate-y-1/2 bg-card border rounded-lg shadow-lg w-[90vw] max-w-2xl max-h-[85vh] overflow-auto p-6 space-y-4">
+            <Dialog.Title className="text-xl font-semibold">
+              {editing && formatDate(editing.entry_date, "medium", { timeZone: "UTC" })}
+            </Dialog.Title>
+            <Input value={editTitle} onChange={(e) => setEditTitle(e.target.value)} placeholder="Title" />
+            <textarea
+              className="w-full min-h-[360px] rounded-md border bg-background p-3 text-sm leading-relaxed"
+              value={editBody}
+              onChange={(e) => setEditBody(e.target.value)}
+            />
+            <div className="flex justify-end gap-2">
+              <Button variant="outline" onClick={() => setEditing(null)}>Close</Button>
+              <Button onClick={() => saveEntry()}>Save Entry</Button>
+            </div>
+          </Dialog.Content>
+        </Dialog.Portal>
+      </Dialog.Root>
+    </div>
+  );
+}
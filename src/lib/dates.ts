@@ -0,0 +1,43 @@
+import { invoke } from "@tauri-apps/api/core";
+
+// Date formatting in the `locale` setting's language, shared by the views. The backend
+// formats exports and weekday names from the same setting.
+
+let locale = "en-US";
+
+export function setDateLocale(next: string) {
+  locale = next || "en-US";
+}
+
+export async function loadDateLocale() {
+  try {
+    const settings = await invoke<{ key: string; value: string }[]>("get_settings");
+    setDateLocale(settings.find((s) => s.key === "locale")?.value ?? "");
+  } catch (error) {
+    console.error("Failed to load the date locale:", error);
+  }
+}
+
+const styles: Record<string, Intl.DateTimeFormatOptions> = {
+  long: { weekday: "long", year: "numeric", month: "long", day: "numeric" },
+  medium: { year: "numeric", month: "long", day: "numeric" },
+  short: { year: "numeric", month: "short", day: "numeric" },
+  numeric: { year: "numeric", month: "numeric", day: "numeric" },
+  dayMonth: { month: "long", day: "numeric" },
+  monthYear: { month: "long", year: "numeric" },
+};
+
+// Entry dates are stored in UTC at the entry's local time of day; pass `timeZone: "UTC"` in
+// `extra` to show the stored day unchanged
+export function formatDate(date: string | Date, style: keyof typeof styles = "medium", extra: Intl.DateTimeFormatOptions = {}) {
+  return new Date(date).toLocaleDateString(locale, { ...styles[style], ...extra });
+}
+
+export function formatDateTime(date: string | Date, options: Intl.DateTimeFormatOptions = {}) {
+  return new Date(date).toLocaleString(locale, options);
+}
+
+// 1-12
+export function monthName(month: number) {
+  return new Date(2000, month - 1, 1).toLocaleString(locale, { month: "long" });
+}
@@ -0,0 +1,43 @@
+// Errors rejected by Tauri commands; `code` is the backend's AppErrorKind
+export interface AppError {
+  message: string;
+  code: string;
+  path?: string;
+  entry_id?: string;
+  provider?: string;
+}
+
+const providerNames: Record<string, string> = { ollama: "Ollama", openai: "OpenAI", local: "the local model" };
+
+// What the user can do about each kind of error, where there's something to do
+const hints: Record<string, (error: AppError) => string | undefined> = {
+  AI: (e) =>
+    e.provider === "ollama"
+      ? "Check that Ollama is running and the model is pulled."
+      : `Check the ${providerNames[e.provider ?? ""] ?? "AI provider"} settings and API key.`,
+  NETWORK: () => "Check your internet connection and try again.",
+  AUTH: () => "Reconnect your Google account in Settings.",
+  KEYCHAIN: () => "Allow the app to use the system keychain, then try again.",
+  NOT_EMBEDDED: () => "Generate embeddings in Settings first.",
+  TIMEOUT: () => "Try a narrower search, or raise the search timeout in Settings.",
+  SENSITIVE_LOCKED: () => "Unlock sensitive entries in Settings first.",
+  ENCRYPTION: () => "Check the database passphrase.",
+  SEARCH_INDEX: () => "Run Diagnostics in Settings and repair the search index.",
+};
+
+export function isAppError(error: unknown): error is AppError {
+  return typeof error === "object" && error !== null && "message" in error && "code" in error;
+}
+
+export function errorCode(error: unknown): string | undefined {
+  return isAppError(error) ? error.code : undefined;
+}
+
+// The error's message followed by what to do about it, for alerts and inline messages
+export function errorMessage(error: unknown): string {
+  if (!isAppError(error)) {
+    return error instanceof Error ? error.message : String(error);
+  }
+  const hint = hints[error.code]?.(error);
+  return hint ? `${error.message.replace(/\.$/, "")}. ${hint}` : error.message;
+}